@@ -1,48 +1,815 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use camino::Utf8PathBuf;
-use serde::Serialize;
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::config::ResolvedConfig;
-use crate::config::{Config, DoiEntry, GenomeEntry, ProteinEntry, SrrEntry, UniprotEntry};
+use crate::audit::{self, AuditRecord, AuditTarget};
+use crate::cancel::{self, CancelScope, CancellationToken};
+use crate::config::{
+    BioProjectEntry, Config, ConfigLoader, ConfigSection, DoiEntry, GenomeEntry, ProteinEntry,
+    ProteomeEntry, SrrEntry, UniprotEntry, config_diff, write_config_atomic,
+};
+use crate::config::{CacheDecision, CachePolicy, CacheSource, IdListEntry, ResolvedConfig};
+use crate::control::{BatchControl, ControlSettings};
+use crate::doctor::{self, DoctorReport};
 use crate::domain::{
-    DatasetSpecifier, Doi, GenomeAccession, GeoSeriesAccession, ProteinFormat, ProteinId, Registry,
-    SrrFormat, SrrId, UniprotId,
+    AssemblyName, BioProjectAccession, DatasetAlias, DatasetSpecifier, Doi, EnsemblGenome,
+    GenomeAccession, GeoSampleAccession, GeoSeriesAccession, GoaSpecies, KeggOrganism,
+    ProteinFormat, ProteinId, ProteomeId, Registry, SpecifierPattern, SpecifierReading,
+    SraExperimentAccession, SrrFormat, SrrId, StructureQuery, TaxonQuery, UniprotId,
+};
+use crate::error::{ErrorContext, KiraError};
+use crate::fs_util::{
+    Digests, ZipLimits, extract_tar, path_size, sha256_bytes, sha256_file, to_canonical_json,
+    write_hashed,
+};
+use crate::geo::{
+    BundleDimensions, BundleFiles, BundlePreference, FileCategory, GeoClient, SeriesRelation,
+    classify_supplementary, detect_10x_files, detect_10x_h5, extract_series_samples,
+    extract_supplementary_urls, filter_urls_by_samples, is_10x_archive, measure_10x_bundle,
+    normalize_10x_bundles, normalize_10x_h5, resolve_10x_layouts,
+};
+use crate::history::{self, HistoryRecord};
+use crate::http::{Conditional, HttpSettings, StoredValidators, redact_credentials};
+use crate::index::{self, IndexEntry};
+use crate::knowledge::{
+    GoFlavor, KnowledgeClient, REACTOME_DOWNLOAD, filter_reactome_species, goa_file_name, goa_url,
+    kegg_links_defect, kegg_list_ids, normalize_kegg_ko_links, parse_go_header,
+    parse_kegg_info_entries,
+};
+use crate::lock::{DEFAULT_LOCK_TIMEOUT, DatasetLock};
+use crate::manifest;
+use crate::ncbi::{AssemblyLevel, NcbiClient, TaxonFilter, genome_alias, select_assembly};
+use crate::ping::EndpointProbe;
+use crate::project::Project;
+use crate::providers::doi::{
+    DoiAgency, DoiResolution, DoiResolver, HydratedBioProject, ResolvedTarget,
+};
+use crate::providers::ensembl::{EnsemblClient, EnsemblFile, check_gzip};
+use crate::rcsb::{
+    FastaSequence, RcsbClient, RcsbEntity, RcsbMetadata, parse_fasta, polymer_entities,
+    structure_file_defect,
+};
+use crate::readme;
+use crate::runs::{self, RunInvocation, RunManifest, RunSummary, redact_arguments};
+use crate::search::{SearchMatch, collect_documents, search};
+use crate::soft::{SoftRecord, SoftSample};
+use crate::srr::{SrrClient, SrrDownloadOptions, SrrRunInfo, ToolInfo};
+use crate::stats::{self, CacheSavings};
+use crate::store::{
+    Acquisition, DATASET_TYPES, DownloadRecord, Materialization, Metadata, Provenance, StagingDir,
+    Store, StoreWarning, atomic_rename_dir, walk_dir,
+};
+use crate::uniprot::{
+    PdbXref, UniprotClient, UniprotHttpClient, UniprotRecord, rank_pdb_xrefs,
+    rank_pdb_xrefs_for_range,
 };
-use crate::error::KiraError;
-use crate::geo::{GeoClient, extract_organism, extract_supplementary_urls};
-use crate::knowledge::{KnowledgeClient, parse_go_header};
-use crate::ncbi::NcbiClient;
-use crate::providers::doi::{DoiResolution, DoiResolver};
-use crate::rcsb::{RcsbClient, RcsbMetadata};
-use crate::srr::{SrrClient, ToolInfo};
-use crate::store::{Metadata, Store, atomic_rename_dir};
-use crate::uniprot::UniprotClient;
 
-#[derive(Debug, Clone)]
+/// The bulk KEGG link file is a few MB; responses at or above this size are
+/// treated as capped and re-fetched in batches.
+const KEGG_LINK_SINGLE_SHOT_MAX: usize = 32 * 1024 * 1024;
+const KEGG_LINK_BATCH: usize = 10;
+/// Share of the requested residue range a structure must cover to be picked
+/// for a `structure-for:` specifier.
+const STRUCTURE_MIN_COVERAGE: f64 = 0.8;
+/// Partial matches named in the error when nothing covers the range.
+const STRUCTURE_PARTIAL_REPORT: usize = 3;
+/// Assemblies a `genome:taxon:` fetch takes without `--limit`.
+const DEFAULT_TAXON_LIMIT: usize = 10;
+
+/// Files written by `--metadata-only` fetches, relative to the dataset
+/// directory.
+/// The action of a fetch whose cache entry failed its digest check and
+/// was downloaded again.
+pub const ACTION_REFETCHED_CORRUPT: &str = "cache-refetched-corrupt";
+const GENOME_REPORT: &str = "dataset_report.json";
+const SRR_RUN_INFO: &str = "run_info.json";
+/// How far a run's FASTQ read count may stray from ENA's before the
+/// Verify phase warns: 1%.
+const READ_COUNT_TOLERANCE: f64 = 0.01;
+const GEO_SOFT: &str = "metadata/geo_soft.txt";
+const GEO_SAMPLES: &str = "metadata/samples.json";
+const UNIPROT_METADATA: [&str; 2] = ["metadata.json", "raw.json"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
 pub struct FetchOptions {
     pub force: bool,
     pub no_cache: bool,
     pub dry_run: bool,
+    /// Fetch descriptive metadata only, leaving the data files for a later
+    /// fetch.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub metadata_only: bool,
+    /// Stop a config-driven fetch at the first dataset that fails instead
+    /// of recording it and going on.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub fail_fast: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pause_timeout_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lock_timeout_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline_secs: Option<u64>,
+    /// How a cache entry is checked against its recorded digests before
+    /// it is reused.
+    #[serde(skip_serializing_if = "VerifyCache::is_fast")]
+    pub verify_cache: VerifyCache,
+    /// Leave a `README.md` describing the dataset in each project dataset
+    /// directory fetched.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub write_readme: bool,
+    /// Stops the fetch, including downloads and external tools in flight.
+    #[serde(skip)]
+    pub cancel: Option<CancellationToken>,
+    /// Recorded on the project copies of datasets a `doi:` fetch resolves to.
+    #[serde(skip)]
+    pub provenance: Option<Provenance>,
+    /// Whether the dataset being fetched goes to the cache, recorded in its
+    /// project metadata. Set by the fetch from the cache policy.
+    #[serde(skip)]
+    pub cache: Option<CacheDecision>,
+    /// SHA-256 of the config file of a config-driven fetch, recorded in the
+    /// project metadata it writes.
+    #[serde(skip)]
+    pub config_sha256: Option<String>,
+}
+
+impl FetchOptions {
+    pub fn builder() -> FetchOptionsBuilder {
+        FetchOptionsBuilder::default()
+    }
+
+    pub fn summary(&self) -> String {
+        format!("options: {self}")
+    }
+
+    /// The options as command-line flags.
+    pub fn flags(&self) -> Vec<String> {
+        [
+            (self.force, "--force"),
+            (self.no_cache, "--no-cache"),
+            (self.dry_run, "--dry-run"),
+            (self.metadata_only, "--metadata-only"),
+            (self.fail_fast, "--fail-fast"),
+            (self.write_readme, "--write-readme"),
+        ]
+        .into_iter()
+        .filter_map(|(set, flag)| set.then_some(flag.to_string()))
+        .chain(
+            self.pause_timeout_secs
+                .map(|secs| format!("--pause-timeout {secs}")),
+        )
+        .chain(
+            self.lock_timeout_secs
+                .map(|secs| format!("--lock-timeout {secs}")),
+        )
+        .chain(self.deadline_secs.map(|secs| format!("--deadline {secs}")))
+        .chain(
+            (!self.verify_cache.is_fast()).then(|| format!("--verify-cache {}", self.verify_cache)),
+        )
+        .collect()
+    }
+}
+
+impl std::fmt::Display for FetchOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let flags = [
+            (self.force, "force"),
+            (self.no_cache, "no-cache"),
+            (self.dry_run, "dry-run"),
+            (self.metadata_only, "metadata-only"),
+            (self.fail_fast, "fail-fast"),
+            (self.write_readme, "write-readme"),
+        ]
+        .into_iter()
+        .filter_map(|(set, name)| set.then_some(name.to_string()))
+        .chain(
+            self.pause_timeout_secs
+                .map(|secs| format!("pause-timeout={secs}s")),
+        )
+        .chain(
+            self.lock_timeout_secs
+                .map(|secs| format!("lock-timeout={secs}s")),
+        )
+        .chain(self.deadline_secs.map(|secs| format!("deadline={secs}s")))
+        .chain(
+            (!self.verify_cache.is_fast()).then(|| format!("verify-cache={}", self.verify_cache)),
+        )
+        .collect::<Vec<_>>();
+        if flags.is_empty() {
+            write!(f, "defaults")
+        } else {
+            write!(f, "{}", flags.join(", "))
+        }
+    }
+}
+
+/// How a fetch checks a cache entry against the digests recorded with it
+/// before copying it into the project. A mismatch evicts the entry and the
+/// dataset is downloaded again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum VerifyCache {
+    /// Hash every file.
+    Full,
+    /// Hash files up to 64 MiB; larger ones must add up to the recorded size.
+    #[default]
+    Fast,
+    /// Reuse the entry as is.
+    Off,
+}
+
+impl VerifyCache {
+    fn is_fast(&self) -> bool {
+        *self == VerifyCache::Fast
+    }
+}
+
+impl std::fmt::Display for VerifyCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            VerifyCache::Full => "full",
+            VerifyCache::Fast => "fast",
+            VerifyCache::Off => "off",
+        })
+    }
 }
 
 #[derive(Debug, Clone, Default)]
+pub struct FetchOptionsBuilder {
+    options: FetchOptions,
+}
+
+impl FetchOptionsBuilder {
+    pub fn force(mut self, force: bool) -> Self {
+        self.options.force = force;
+        self
+    }
+
+    pub fn no_cache(mut self, no_cache: bool) -> Self {
+        self.options.no_cache = no_cache;
+        self
+    }
+
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.options.dry_run = dry_run;
+        self
+    }
+
+    pub fn metadata_only(mut self, metadata_only: bool) -> Self {
+        self.options.metadata_only = metadata_only;
+        self
+    }
+
+    pub fn fail_fast(mut self, fail_fast: bool) -> Self {
+        self.options.fail_fast = fail_fast;
+        self
+    }
+
+    pub fn pause_timeout_secs(mut self, secs: Option<u64>) -> Self {
+        self.options.pause_timeout_secs = secs;
+        self
+    }
+
+    pub fn lock_timeout_secs(mut self, secs: Option<u64>) -> Self {
+        self.options.lock_timeout_secs = secs;
+        self
+    }
+
+    pub fn deadline_secs(mut self, secs: Option<u64>) -> Self {
+        self.options.deadline_secs = secs;
+        self
+    }
+
+    pub fn verify_cache(mut self, mode: VerifyCache) -> Self {
+        self.options.verify_cache = mode;
+        self
+    }
+
+    pub fn write_readme(mut self, write_readme: bool) -> Self {
+        self.options.write_readme = write_readme;
+        self
+    }
+
+    pub fn cancel(mut self, token: CancellationToken) -> Self {
+        self.options.cancel = Some(token);
+        self
+    }
+
+    pub fn build(self) -> FetchOptions {
+        self.options
+    }
+}
+
+/// Command-line flags that map onto [`FetchOptions`].
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct FetchFlags {
+    #[arg(long)]
+    pub force: bool,
+
+    #[arg(long)]
+    pub no_cache: bool,
+
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Fetch only descriptive metadata (entry JSON, SOFT text, assembly or
+    /// run reports); a later fetch without the flag downloads the data.
+    #[arg(long)]
+    pub metadata_only: bool,
+
+    /// Stop a fetch from kira-bm.json at the first dataset that fails; by
+    /// default the rest are fetched and the failures reported.
+    #[arg(long)]
+    pub fail_fast: bool,
+
+    /// Give up after a batch has been paused this many seconds.
+    #[arg(long, value_name = "SECS")]
+    pub pause_timeout: Option<u64>,
+
+    /// Give up after waiting this many seconds for another process fetching
+    /// the same dataset.
+    #[arg(long, value_name = "SECS")]
+    pub lock_timeout: Option<u64>,
+
+    /// Cancel the fetch if it is still running after this many seconds.
+    #[arg(long, value_name = "SECS")]
+    pub deadline: Option<u64>,
+
+    /// How a cache entry is checked before it is reused: `full` hashes
+    /// every file, `fast` skips files over 64 MiB, `off` trusts the cache.
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = VerifyCache::Fast)]
+    pub verify_cache: VerifyCache,
+
+    /// Write a README.md describing the dataset into each project dataset
+    /// directory fetched; `write_readme` in kira-bm.json turns it on for
+    /// every fetch.
+    #[arg(long)]
+    pub write_readme: bool,
+}
+
+impl From<&FetchFlags> for FetchOptions {
+    fn from(flags: &FetchFlags) -> Self {
+        FetchOptions::builder()
+            .force(flags.force)
+            .no_cache(flags.no_cache)
+            .dry_run(flags.dry_run)
+            .metadata_only(flags.metadata_only)
+            .fail_fast(flags.fail_fast)
+            .pause_timeout_secs(flags.pause_timeout)
+            .lock_timeout_secs(flags.lock_timeout)
+            .deadline_secs(flags.deadline)
+            .verify_cache(flags.verify_cache)
+            .write_readme(flags.write_readme)
+            .build()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
 pub struct FetchOverrides {
     pub protein_format: Option<ProteinFormat>,
+    /// Skip the FASTA sequences stored beside a protein's structure.
+    pub no_fasta: bool,
     pub srr_format: Option<SrrFormat>,
     pub srr_paired: Option<bool>,
+    /// fasterq-dump threads and scratch directory for SRR fetches.
+    pub srr_threads: Option<u32>,
+    pub srr_temp_dir: Option<PathBuf>,
+    pub follow_superseries: bool,
+    pub include_categories: Vec<FileCategory>,
+    /// Files a genome fetch downloads when no config entry lists them.
+    pub genome_include: Option<Vec<String>>,
+    /// Files a `genome:ensembl:` fetch downloads; empty for dna and gtf.
+    pub ensembl_include: Vec<EnsemblFile>,
+    pub allow_missing_artifacts: bool,
+    /// How many structures a `structure-for:` fetch downloads.
+    pub structure_top: Option<usize>,
+    /// Resolve `genome:<NAME>` only by exact assembly name.
+    pub exact: bool,
+    /// Filters and count for `genome:taxon:` fetches.
+    pub assembly_level: Option<AssemblyLevel>,
+    pub reference_only: bool,
+    pub limit: Option<usize>,
+    /// Ontology edition and annotation species for `go` fetches.
+    pub go_flavor: Option<GoFlavor>,
+    pub go_annotations: Vec<GoaSpecies>,
+    /// Species a `reactome` fetch keeps, and whether the filtered files
+    /// replace the full downloads.
+    pub reactome_species: Option<String>,
+    pub replace_unfiltered: bool,
+    /// Fetch the PDB structures a UniProt entry cross-references, at most
+    /// `max_structures` of them.
+    pub with_structures: bool,
+    pub max_structures: Option<usize>,
+    /// Layout an `expression10x` fetch keeps for samples published as both
+    /// MTX and HDF5 matrices.
+    pub prefer_bundle: Option<BundlePreference>,
+    /// Which resolved targets a `doi:` fetch downloads.
+    pub doi_selection: DoiSelection,
+    /// Search the DOIs a `doi:` record supplements or references as well.
+    pub follow_related: bool,
+}
+
+/// Dataset types a DOI resolves to.
+pub const DOI_TARGET_TYPES: [&str; 4] = ["protein", "uniprot", "genome", "srr"];
+
+/// Which targets of a `doi:` fetch are downloaded. Unless `download_all`,
+/// a filter or a hand-picked list is given, the DOI is only resolved and
+/// its targets reported.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DoiSelection {
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub download_all: bool,
+    /// Dataset types to keep, e.g. `srr`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub only: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_per_type: Option<usize>,
+    /// Ids, or `type:id` specifiers, to leave out.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// The `type:id` specifiers ticked in the `--select` checklist.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub picked: Option<Vec<String>>,
+}
+
+impl DoiSelection {
+    /// Whether anything is to be downloaded at all.
+    pub fn downloads(&self) -> bool {
+        self.download_all
+            || !self.only.is_empty()
+            || self.limit_per_type.is_some()
+            || !self.exclude.is_empty()
+            || self.picked.is_some()
+    }
+
+    /// Why `targets[i]` is not downloaded, for each target in resolution
+    /// order; `None` marks the selected ones.
+    pub fn skip_reasons(&self, targets: &[ResolvedTarget]) -> Vec<Option<&'static str>> {
+        let mut per_type = BTreeMap::<&str, usize>::new();
+        targets
+            .iter()
+            .map(|target| {
+                let specifier = format!("{}:{}", target.dataset_type, target.id);
+                if !self.downloads() {
+                    return Some("report-only");
+                }
+                if !self.only.is_empty()
+                    && !self
+                        .only
+                        .iter()
+                        .any(|kind| kind.eq_ignore_ascii_case(&target.dataset_type))
+                {
+                    return Some("type");
+                }
+                if self.exclude.iter().any(|excluded| {
+                    excluded.eq_ignore_ascii_case(&target.id)
+                        || excluded.eq_ignore_ascii_case(&specifier)
+                }) {
+                    return Some("excluded");
+                }
+                if let Some(picked) = &self.picked
+                    && !picked
+                        .iter()
+                        .any(|picked| picked.eq_ignore_ascii_case(&specifier))
+                {
+                    return Some("not-picked");
+                }
+                let count = per_type.entry(target.dataset_type.as_str()).or_default();
+                if self.limit_per_type.is_some_and(|limit| *count >= limit) {
+                    return Some("limit-per-type");
+                }
+                *count += 1;
+                None
+            })
+            .collect()
+    }
+}
+
+impl FetchOverrides {
+    pub fn builder() -> FetchOverridesBuilder {
+        FetchOverridesBuilder::default()
+    }
+
+    fn srr_download_options(&self) -> SrrDownloadOptions {
+        SrrDownloadOptions {
+            threads: self.srr_threads,
+            temp_dir: self.srr_temp_dir.clone(),
+        }
+    }
+
+    fn genome_include(&self) -> Vec<String> {
+        self.genome_include
+            .clone()
+            .unwrap_or_else(crate::config::default_genome_include)
+    }
+
+    fn ensembl_include(&self) -> Vec<EnsemblFile> {
+        if self.ensembl_include.is_empty() {
+            return EnsemblFile::DEFAULT.to_vec();
+        }
+        let mut files = self.ensembl_include.clone();
+        files.sort();
+        files.dedup();
+        files
+    }
+}
+
+impl std::fmt::Display for FetchOverrides {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(format) = self.protein_format {
+            parts.push(format!("protein-format={format}"));
+        }
+        if self.no_fasta {
+            parts.push("no-fasta".to_string());
+        }
+        if let Some(format) = self.srr_format {
+            parts.push(format!("srr-format={format}"));
+        }
+        if let Some(paired) = self.srr_paired {
+            parts.push(format!("paired={paired}"));
+        }
+        if let Some(threads) = self.srr_threads {
+            parts.push(format!("srr-threads={threads}"));
+        }
+        if let Some(temp_dir) = &self.srr_temp_dir {
+            parts.push(format!("srr-temp-dir={}", temp_dir.display()));
+        }
+        if self.follow_superseries {
+            parts.push("follow-superseries".to_string());
+        }
+        if !self.include_categories.is_empty() {
+            let categories = self
+                .include_categories
+                .iter()
+                .map(|category| category.to_string())
+                .collect::<Vec<_>>();
+            parts.push(format!("include-categories={}", categories.join(",")));
+        }
+        if !self.ensembl_include.is_empty() {
+            let files = self
+                .ensembl_include
+                .iter()
+                .map(|file| file.to_string())
+                .collect::<Vec<_>>();
+            parts.push(format!("ensembl-include={}", files.join(",")));
+        }
+        if self.allow_missing_artifacts {
+            parts.push("allow-missing-artifacts".to_string());
+        }
+        if let Some(top) = self.structure_top {
+            parts.push(format!("top={top}"));
+        }
+        if self.exact {
+            parts.push("exact".to_string());
+        }
+        if let Some(level) = self.assembly_level {
+            parts.push(format!("assembly-level={level}"));
+        }
+        if self.reference_only {
+            parts.push("reference-only".to_string());
+        }
+        if let Some(limit) = self.limit {
+            parts.push(format!("limit={limit}"));
+        }
+        if let Some(flavor) = self.go_flavor {
+            parts.push(format!("go-flavor={flavor}"));
+        }
+        if !self.go_annotations.is_empty() {
+            let species = self
+                .go_annotations
+                .iter()
+                .map(|species| species.to_string())
+                .collect::<Vec<_>>();
+            parts.push(format!("go-annotations={}", species.join(",")));
+        }
+        if let Some(species) = &self.reactome_species {
+            parts.push(format!("species={species}"));
+        }
+        if self.replace_unfiltered {
+            parts.push("replace-unfiltered".to_string());
+        }
+        if self.with_structures {
+            parts.push("with-structures".to_string());
+        }
+        if let Some(max) = self.max_structures {
+            parts.push(format!("max-structures={max}"));
+        }
+        if let Some(prefer) = self.prefer_bundle {
+            parts.push(format!("prefer-bundle={prefer}"));
+        }
+        let selection = &self.doi_selection;
+        if selection.download_all {
+            parts.push("download-all".to_string());
+        }
+        if !selection.only.is_empty() {
+            parts.push(format!("only={}", selection.only.join(",")));
+        }
+        if let Some(limit) = selection.limit_per_type {
+            parts.push(format!("limit-per-type={limit}"));
+        }
+        if !selection.exclude.is_empty() {
+            parts.push(format!("exclude={}", selection.exclude.join(",")));
+        }
+        if let Some(picked) = &selection.picked {
+            parts.push(format!("picked={}", picked.len()));
+        }
+        if parts.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FetchOverridesBuilder {
+    overrides: FetchOverrides,
+}
+
+impl FetchOverridesBuilder {
+    pub fn protein_format(mut self, format: ProteinFormat) -> Self {
+        self.overrides.protein_format = Some(format);
+        self
+    }
+
+    pub fn no_fasta(mut self, no_fasta: bool) -> Self {
+        self.overrides.no_fasta = no_fasta;
+        self
+    }
+
+    pub fn srr_format(mut self, format: SrrFormat) -> Self {
+        self.overrides.srr_format = Some(format);
+        self
+    }
+
+    pub fn srr_paired(mut self, paired: bool) -> Self {
+        self.overrides.srr_paired = Some(paired);
+        self
+    }
+
+    pub fn srr_threads(mut self, threads: u32) -> Self {
+        self.overrides.srr_threads = Some(threads);
+        self
+    }
+
+    pub fn srr_temp_dir(mut self, temp_dir: impl Into<PathBuf>) -> Self {
+        self.overrides.srr_temp_dir = Some(temp_dir.into());
+        self
+    }
+
+    pub fn follow_superseries(mut self, follow: bool) -> Self {
+        self.overrides.follow_superseries = follow;
+        self
+    }
+
+    pub fn genome_include(mut self, include: Vec<String>) -> Self {
+        self.overrides.genome_include = Some(include);
+        self
+    }
+
+    pub fn follow_related(mut self, follow: bool) -> Self {
+        self.overrides.follow_related = follow;
+        self
+    }
+
+    pub fn ensembl_include<I>(mut self, files: I) -> Self
+    where
+        I: IntoIterator<Item = EnsemblFile>,
+    {
+        self.overrides.ensembl_include = files.into_iter().collect();
+        self
+    }
+
+    pub fn include_categories<I>(mut self, categories: I) -> Self
+    where
+        I: IntoIterator<Item = FileCategory>,
+    {
+        self.overrides.include_categories = categories.into_iter().collect();
+        self
+    }
+
+    pub fn allow_missing_artifacts(mut self, allow: bool) -> Self {
+        self.overrides.allow_missing_artifacts = allow;
+        self
+    }
+
+    pub fn structure_top(mut self, top: usize) -> Self {
+        self.overrides.structure_top = Some(top);
+        self
+    }
+
+    pub fn exact(mut self, exact: bool) -> Self {
+        self.overrides.exact = exact;
+        self
+    }
+
+    pub fn assembly_level(mut self, level: AssemblyLevel) -> Self {
+        self.overrides.assembly_level = Some(level);
+        self
+    }
+
+    pub fn reference_only(mut self, reference_only: bool) -> Self {
+        self.overrides.reference_only = reference_only;
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.overrides.limit = Some(limit);
+        self
+    }
+
+    pub fn go_flavor(mut self, flavor: GoFlavor) -> Self {
+        self.overrides.go_flavor = Some(flavor);
+        self
+    }
+
+    pub fn go_annotations<I>(mut self, species: I) -> Self
+    where
+        I: IntoIterator<Item = GoaSpecies>,
+    {
+        self.overrides.go_annotations = species.into_iter().collect();
+        self
+    }
+
+    pub fn reactome_species(mut self, species: impl Into<String>) -> Self {
+        self.overrides.reactome_species = Some(species.into());
+        self
+    }
+
+    pub fn replace_unfiltered(mut self, replace: bool) -> Self {
+        self.overrides.replace_unfiltered = replace;
+        self
+    }
+
+    pub fn with_structures(mut self, with_structures: bool) -> Self {
+        self.overrides.with_structures = with_structures;
+        self
+    }
+
+    pub fn max_structures(mut self, max: usize) -> Self {
+        self.overrides.max_structures = Some(max);
+        self
+    }
+
+    pub fn prefer_bundle(mut self, prefer: BundlePreference) -> Self {
+        self.overrides.prefer_bundle = Some(prefer);
+        self
+    }
+
+    pub fn doi_selection(mut self, selection: DoiSelection) -> Self {
+        self.overrides.doi_selection = selection;
+        self
+    }
+
+    pub fn build(self) -> FetchOverrides {
+        self.overrides
+    }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct FetchResult {
     pub items: Vec<FetchItemResult>,
+    /// Config entries that failed while the rest were fetched.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FetchItemError>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub summary: Option<FetchSummary>,
+    /// Wall-clock time of the whole fetch.
+    pub total_duration_ms: u64,
+    /// The items' phase durations summed.
+    pub timings: PhaseTimings,
+}
+
+impl FetchResult {
+    /// Stamps the fetch's duration and the phase totals of its items.
+    fn timed(mut self, started: Instant) -> Self {
+        self.total_duration_ms = started.elapsed().as_millis() as u64;
+        self.timings = self
+            .items
+            .iter()
+            .fold(PhaseTimings::default(), |total, item| total + item.timings);
+        self
+    }
+
+    /// The `count` items that took longest, slowest first.
+    pub fn slowest(&self, count: usize) -> Vec<&FetchItemResult> {
+        let mut items = self.items.iter().collect::<Vec<_>>();
+        items.sort_by_key(|item| std::cmp::Reverse(item.timings.total_ms()));
+        items.truncate(count);
+        items
+    }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct FetchItemResult {
     pub dataset_type: String,
     pub id: String,
@@ -51,68 +818,581 @@ pub struct FetchItemResult {
     pub action: String,
     pub project_path: Option<String>,
     pub cache_path: Option<String>,
+    /// Where the data came from and what was written, absent for dry runs
+    /// and metadata-only fetches.
+    #[serde(flatten)]
+    pub download: Option<DownloadRecord>,
+    /// What a fetch served from the cache saved over downloading again.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_savings: Option<CacheSavings>,
+    /// Whether the item was copied into the cache, and why.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheDecision>,
+    /// Where the item's time went, by the phase it reported. Time spent
+    /// in nested items (a DOI's targets) is theirs.
+    pub timings: PhaseTimings,
+}
+
+/// Milliseconds spent in each fetch phase. `Prepare` counts as resolving
+/// and `Fetch` as downloading.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct PhaseTimings {
+    pub resolve_ms: u64,
+    pub download_ms: u64,
+    pub verify_ms: u64,
+    pub store_ms: u64,
+}
+
+impl PhaseTimings {
+    pub fn total_ms(&self) -> u64 {
+        self.resolve_ms + self.download_ms + self.verify_ms + self.store_ms
+    }
+
+    fn record(&mut self, phase: &str, elapsed: Duration) {
+        let ms = elapsed.as_millis() as u64;
+        match phase {
+            "Fetch" => self.download_ms += ms,
+            "Verify" => self.verify_ms += ms,
+            "Store" => self.store_ms += ms,
+            _ => self.resolve_ms += ms,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize)]
+impl std::ops::Add for PhaseTimings {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            resolve_ms: self.resolve_ms + other.resolve_ms,
+            download_ms: self.download_ms + other.download_ms,
+            verify_ms: self.verify_ms + other.verify_ms,
+            store_ms: self.store_ms + other.store_ms,
+        }
+    }
+}
+
+/// A dataset a config-driven fetch could not fetch.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct FetchItemError {
+    pub specifier: String,
+    pub message: String,
+    /// Whether fetching it again later may succeed.
+    pub retryable: bool,
+}
+
+impl FetchItemError {
+    /// The failure of the config entry `label`, or of the dataset within it
+    /// the error names.
+    fn new(label: &str, err: &KiraError) -> Self {
+        Self {
+            specifier: err
+                .context()
+                .map_or(label, |(specifier, _)| specifier)
+                .to_string(),
+            message: err.root().to_string(),
+            retryable: err.is_retryable(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct FetchSummary {
     pub kind: String,
     pub doi: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bioproject: Option<String>,
+    /// The SRA experiment of an `srx:` fetch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub experiment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub taxon: Option<String>,
     pub id_counts: Vec<IdCount>,
     pub resolved_targets: usize,
     pub unresolved: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selection: Option<StructureSelection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uniprot: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structures: Option<StructureCounts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doi_selection: Option<DoiTargets>,
+}
+
+/// The resolved targets of a `doi:` fetch, split by whether they were
+/// downloaded.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct DoiTargets {
+    pub selected: Vec<DoiTarget>,
+    pub skipped: Vec<DoiTarget>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct DoiTarget {
+    pub specifier: String,
+    pub dataset_type: String,
+    /// Size of a copy already in the project store or the cache.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    /// `report-only`, `type`, `excluded`, `not-picked` or `limit-per-type`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// What a `--with-structures` UniProt fetch did with the entry's PDB
+/// cross-references.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct StructureCounts {
+    pub listed: usize,
+    pub downloaded: usize,
+    /// Already in the project store or the cache.
+    pub cached: usize,
+    /// Left out by `--max-structures`.
+    pub skipped: usize,
+}
+
+/// Why a `structure-for:` fetch picked its structures.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct StructureSelection {
+    pub accession: String,
+    pub start: u64,
+    pub end: u64,
+    pub min_coverage: f64,
+    /// Every listed structure, best first.
+    pub candidates: Vec<StructureCandidate>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct StructureCandidate {
+    pub id: String,
+    pub method: Option<String>,
+    pub resolution: Option<f64>,
+    pub covered_residues: u64,
+    /// Share of the requested range, rounded to three decimals.
+    pub coverage: f64,
+    pub selected: bool,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct IdCount {
     pub id_type: String,
     pub count: usize,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct ListResult {
     pub datasets: Vec<ListEntry>,
+    /// Store entries that could not be read and were left out.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<StoreWarning>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct ListEntry {
     pub dataset_type: String,
     pub id: String,
     pub format: Option<String>,
+    /// Every format a protein is stored in, across both stores.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub formats: Vec<String>,
     pub source: Option<String>,
     pub project_path: Option<String>,
     pub cache_path: Option<String>,
+    /// `expired` when retention has deleted the project copy's data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// From the project copy when there is one, otherwise the cache copy.
+    pub downloaded_at: Option<String>,
+    pub bytes: Option<u64>,
+    /// The DOI the project copy was fetched for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+    /// `metadata-only` when the project copy has no data files yet.
+    #[serde(skip_serializing_if = "Materialization::is_full")]
+    pub materialization: Materialization,
+    /// The project copy's alias.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// Whether either copy is pinned.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
 }
 
-#[derive(Debug, Clone, Serialize)]
-pub struct InfoResult {
-    pub dataset_type: String,
-    pub id: String,
-    pub format: Option<String>,
-    pub source: Option<String>,
-    pub project_path: Option<String>,
-    pub cache_path: Option<String>,
-    pub details: Option<Value>,
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ListLocation {
+    Project,
+    Cache,
+    #[default]
+    Both,
 }
 
-#[derive(Debug, Clone, Serialize)]
-pub struct ClearResult {
-    pub cleared: bool,
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ListSort {
+    /// By id, then type.
+    #[default]
+    Name,
+    /// By type, then id.
+    Type,
+    /// Newest first.
+    Date,
+    /// Largest first.
+    Size,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListOptions {
+    pub dataset_type: Option<String>,
+    pub location: ListLocation,
+    pub sort: ListSort,
+}
+
+impl ListOptions {
+    pub fn builder() -> ListOptionsBuilder {
+        ListOptionsBuilder::default()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ListOptionsBuilder {
+    options: ListOptions,
+}
+
+impl ListOptionsBuilder {
+    pub fn dataset_type(mut self, dataset_type: impl Into<String>) -> Self {
+        self.options.dataset_type = Some(dataset_type.into());
+        self
+    }
+
+    pub fn location(mut self, location: ListLocation) -> Self {
+        self.options.location = location;
+        self
+    }
+
+    pub fn sort(mut self, sort: ListSort) -> Self {
+        self.options.sort = sort;
+        self
+    }
+
+    pub fn build(self) -> ListOptions {
+        self.options
+    }
+}
+
+/// Command-line flags that map onto [`ListOptions`].
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct ListFlags {
+    /// Only datasets of this type, e.g. protein or srr.
+    #[arg(long = "type", value_name = "TYPE")]
+    pub dataset_type: Option<String>,
+
+    #[arg(long, conflicts_with = "cache_only")]
+    pub project_only: bool,
+
+    #[arg(long)]
+    pub cache_only: bool,
+
+    /// Every dataset in the cache with the projects holding it, read from
+    /// the cache index.
+    #[arg(long, conflicts_with_all = ["project_only", "cache_only"])]
+    pub global: bool,
+
+    #[arg(long, value_enum, default_value_t)]
+    pub sort: ListSort,
+}
+
+impl From<&ListFlags> for ListOptions {
+    fn from(flags: &ListFlags) -> Self {
+        let location = if flags.project_only {
+            ListLocation::Project
+        } else if flags.cache_only {
+            ListLocation::Cache
+        } else {
+            ListLocation::Both
+        };
+        let mut builder = ListOptions::builder().location(location).sort(flags.sort);
+        if let Some(dataset_type) = &flags.dataset_type {
+            builder = builder.dataset_type(dataset_type.clone());
+        }
+        builder.build()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct FindResult {
+    pub pattern: String,
+    pub matches: Vec<SearchMatch>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ConvertResult {
+    pub dataset_type: String,
+    pub id: String,
+    pub from: String,
+    pub to: String,
+    /// `project` or `cache`: the store the converted file was written to.
+    pub location: String,
+    pub path: String,
+    /// `convert`, or `present` when the store already had the format.
+    pub action: String,
+    /// What the target format cannot represent.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// How `import` takes local files in.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImportOptions {
+    /// Hard-link the files instead of copying them, where the filesystem
+    /// allows it.
+    pub link: bool,
+    /// Skip the check that the files hold what the specifier names.
+    pub no_verify: bool,
+    /// Replace a dataset the project store already has.
+    pub force: bool,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ImportResult {
+    pub dataset_type: String,
+    pub id: String,
+    pub format: Option<String>,
+    pub imported_from: String,
+    pub project_path: String,
+    /// Paths of the imported files, relative to the dataset directory.
+    pub files: Vec<String>,
+    pub bytes: u64,
+    pub linked: bool,
+    pub verified: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct InfoResult {
+    pub dataset_type: String,
+    pub id: String,
+    pub format: Option<String>,
+    /// Each stored format of a protein with its file, the project's copy
+    /// where it has one.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub formats: BTreeMap<String, String>,
+    pub source: Option<String>,
+    pub project_path: Option<String>,
+    pub cache_path: Option<String>,
+    pub details: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+    /// The local path `import` took the project copy from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imported_from: Option<String>,
+    /// Of the project copy; cached copies are always full.
+    #[serde(skip_serializing_if = "Materialization::is_full")]
+    pub materialization: Materialization,
+    /// How the project copy, or failing that the cached one, was fetched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acquisition: Option<Acquisition>,
+}
+
+/// Local datasets fetched for one DOI.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct DoiDatasets {
+    pub doi: String,
+    pub datasets: Vec<Metadata>,
+}
+
+/// What `clear` removes from the project store.
+#[derive(Debug, Clone, Default)]
+pub struct ClearOptions {
+    /// Clear pinned datasets too.
+    pub include_pinned: bool,
+    /// Dataset types to clear, e.g. `protein`; every type when empty.
+    pub types: Vec<String>,
+    /// Remove the data but keep the datasets' metadata records.
+    pub keep_metadata: bool,
+    /// Only report what would be removed.
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ClearResult {
+    /// False for a dry run.
+    pub cleared: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+    /// Datasets removed, or that a dry run would remove.
+    #[serde(default)]
+    pub datasets: usize,
+    #[serde(default)]
+    pub bytes: u64,
+    /// The same per dataset type.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<ClearedType>,
+    /// Pinned datasets that were left in place.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_pinned: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ClearedType {
+    pub dataset_type: String,
+    pub datasets: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PinResult {
+    pub specifier: String,
+    pub pinned: bool,
+    /// Metadata records that changed; empty when the pin was already so.
+    pub records: Vec<String>,
+}
+
+/// `info` of a [`SpecifierPattern`]: each matching project dataset.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct InfoMatches {
+    pub pattern: String,
+    pub matched: usize,
+    pub datasets: Vec<InfoResult>,
+}
+
+/// `pin` or `unpin` of a [`SpecifierPattern`].
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PinMatches {
+    pub pattern: String,
+    pub pinned: bool,
+    pub matched: usize,
+    pub datasets: Vec<PinResult>,
+}
+
+/// Staging directories idle this long are taken to belong to dead runs.
+pub const DEFAULT_GC_MIN_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// What `gc` may delete.
+#[derive(Debug, Clone, Copy)]
+pub struct GcOptions {
+    pub min_age: Duration,
+    /// Delete orphaned dataset directories as well as reporting them.
+    pub prune_orphans: bool,
+    /// Delete dangling records even when they are pinned.
+    pub include_pinned: bool,
+    /// Delete instead of only reporting.
+    pub apply: bool,
+}
+
+impl Default for GcOptions {
+    fn default() -> Self {
+        Self {
+            min_age: DEFAULT_GC_MIN_AGE,
+            prune_orphans: false,
+            include_pinned: false,
+            apply: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct GcResult {
+    pub dry_run: bool,
+    /// Staging directories of runs that did not finish.
+    pub temp_dirs: Vec<GcEntry>,
+    /// Metadata records whose data is gone.
+    pub dangling_metadata: Vec<GcEntry>,
+    /// Dataset directories without a metadata record.
+    pub orphans: Vec<GcEntry>,
+    /// Blobs in the cache's blob store that no dataset links to any more.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unreferenced_blobs: Vec<GcEntry>,
+    /// Pinned datasets whose dangling records were kept.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_pinned: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct GcEntry {
+    pub path: String,
+    /// The dataset a dangling record describes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub specifier: Option<String>,
+    /// Where a dangling record expected its data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub missing_path: Option<String>,
+    pub bytes: u64,
+    pub removed: bool,
+}
+
+impl GcResult {
+    pub fn is_empty(&self) -> bool {
+        self.temp_dirs.is_empty()
+            && self.dangling_metadata.is_empty()
+            && self.orphans.is_empty()
+            && self.unreferenced_blobs.is_empty()
+    }
+}
+
+/// How `init` treats an existing `kira-bm.json`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InitOptions {
+    /// Replace the file with the store's datasets instead of merging.
+    pub force: bool,
+    /// Work out the result and its diff without writing anything.
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct InitResult {
     pub path: String,
     pub written: bool,
+    pub dry_run: bool,
+    /// Entries per section in the resulting file.
     pub counts: InitCounts,
+    /// Entries added for stored datasets the file did not list.
+    pub added: InitCounts,
+    /// Entries kept from the existing file.
+    pub preserved: InitCounts,
+    /// What a dry run would change, as a line diff of the file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, JsonSchema)]
 pub struct InitCounts {
     pub proteins: usize,
     pub genomes: usize,
     pub srr: usize,
     pub uniprot: usize,
     pub doi: usize,
+    pub bioprojects: usize,
+    pub proteomes: usize,
+}
+
+impl InitCounts {
+    fn of(config: &Config) -> Self {
+        Self::from_fn(|section| config.entry_ids(section).len())
+    }
+
+    fn of_specifiers(specifiers: &[String]) -> Self {
+        Self::from_fn(|section| {
+            specifiers
+                .iter()
+                .filter(|spec| spec.split_once(':').map(|(kind, _)| kind) == Some(section.kind()))
+                .count()
+        })
+    }
+
+    fn from_fn(count: impl Fn(ConfigSection) -> usize) -> Self {
+        Self {
+            proteins: count(ConfigSection::Proteins),
+            genomes: count(ConfigSection::Genomes),
+            srr: count(ConfigSection::Srr),
+            uniprot: count(ConfigSection::Uniprot),
+            doi: count(ConfigSection::Doi),
+            bioprojects: count(ConfigSection::BioProjects),
+            proteomes: count(ConfigSection::Proteomes),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -127,10 +1407,310 @@ pub enum ProgressSinkKind {
 pub struct ProgressEvent {
     pub message: String,
     pub elapsed: Option<Duration>,
+    pub level: ProgressLevel,
+}
+
+/// How much detail an event is: headless runs print events up to the level
+/// their [`Verbosity`] asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProgressLevel {
+    /// Phases, items and warnings.
+    Phase,
+    /// One per registry request or download.
+    Request,
+    /// Latencies, retries and lock bookkeeping.
+    Detail,
+}
+
+/// Progress shown by `--quiet`, the default, `-v` and `-vv`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Verbosity {
+    Quiet,
+    #[default]
+    Normal,
+    Verbose,
+    Detailed,
+}
+
+impl Verbosity {
+    pub fn from_flags(quiet: bool, verbose: u8) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Self::Quiet,
+            (false, 0) => Self::Normal,
+            (false, 1) => Self::Verbose,
+            (false, _) => Self::Detailed,
+        }
+    }
+
+    pub fn shows(self, level: ProgressLevel) -> bool {
+        match self {
+            Self::Quiet => false,
+            Self::Normal => level == ProgressLevel::Phase,
+            Self::Verbose => level <= ProgressLevel::Request,
+            Self::Detailed => true,
+        }
+    }
 }
 
 pub trait ProgressSink {
     fn event(&self, event: ProgressEvent);
+
+    /// Whether the caller asked to stop; checked between phases and items.
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+
+    /// Called once per dataset as soon as it is fetched, in the order they
+    /// finish and before the fetch returns its result. Ignored unless the
+    /// sink overrides it.
+    fn item_completed(&self, _item: &FetchItemResult) {}
+}
+
+/// Hands completed items to a callback before passing them on.
+struct ItemCallback<'a, F> {
+    inner: &'a dyn ProgressSink,
+    on_item: RefCell<F>,
+}
+
+impl<F: FnMut(&FetchItemResult)> ProgressSink for ItemCallback<'_, F> {
+    fn event(&self, event: ProgressEvent) {
+        self.inner.event(event);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.inner.is_cancelled()
+    }
+
+    fn item_completed(&self, item: &FetchItemResult) {
+        (self.on_item.borrow_mut())(item);
+        self.inner.item_completed(item);
+    }
+}
+
+/// Forwards the events a [`Verbosity`] shows and drops the rest.
+pub struct ProgressFilter<'a> {
+    inner: &'a dyn ProgressSink,
+    verbosity: Verbosity,
+}
+
+impl<'a> ProgressFilter<'a> {
+    pub fn new(inner: &'a dyn ProgressSink, verbosity: Verbosity) -> Self {
+        Self { inner, verbosity }
+    }
+}
+
+impl ProgressSink for ProgressFilter<'_> {
+    fn event(&self, event: ProgressEvent) {
+        if self.verbosity.shows(event.level) {
+            self.inner.event(event);
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.inner.is_cancelled()
+    }
+
+    fn item_completed(&self, item: &FetchItemResult) {
+        if self.verbosity.shows(ProgressLevel::Phase) {
+            self.inner.item_completed(item);
+        }
+    }
+}
+
+/// Forwards events while remembering the last `phase=` reported, so an
+/// error escaping a fetch can name the phase it happened in, and timing
+/// each phase. Phases of nested items (between `item.begin` and
+/// `item.end`) are theirs, not ours.
+struct PhaseTracker<'a> {
+    inner: &'a dyn ProgressSink,
+    phase: RefCell<String>,
+    outer: RefCell<Vec<String>>,
+    /// When the current phase started, or `None` inside a nested item.
+    since: Cell<Option<Instant>>,
+    timings: RefCell<PhaseTimings>,
+}
+
+impl<'a> PhaseTracker<'a> {
+    fn new(inner: &'a dyn ProgressSink) -> Self {
+        Self {
+            inner,
+            phase: RefCell::new("Resolve".to_string()),
+            outer: RefCell::new(Vec::new()),
+            since: Cell::new(Some(Instant::now())),
+            timings: RefCell::new(PhaseTimings::default()),
+        }
+    }
+
+    /// Books the time since the current phase started to it.
+    fn lap(&self) {
+        if let Some(since) = self.since.take() {
+            let phase = self.phase.borrow();
+            self.timings.borrow_mut().record(&phase, since.elapsed());
+        }
+    }
+
+    fn finish(self) -> (String, PhaseTimings) {
+        self.lap();
+        (self.phase.into_inner(), self.timings.into_inner())
+    }
+}
+
+impl ProgressSink for PhaseTracker<'_> {
+    fn event(&self, event: ProgressEvent) {
+        let message = event.message.as_str();
+        let nested = !self.outer.borrow().is_empty();
+        if let Some(phase) = message
+            .strip_prefix("phase=")
+            .and_then(|rest| rest.split(';').next())
+        {
+            if !nested {
+                self.lap();
+                self.since.set(Some(Instant::now()));
+            }
+            *self.phase.borrow_mut() = phase.trim().to_string();
+        } else if message.starts_with("item.begin ") {
+            if !nested {
+                self.lap();
+            }
+            let phase = self.phase.borrow().clone();
+            self.outer.borrow_mut().push(phase);
+        } else if message.starts_with("item.end ") || message.starts_with("item.failed ") {
+            let mut outer = self.outer.borrow_mut();
+            if let Some(phase) = outer.pop() {
+                *self.phase.borrow_mut() = phase;
+                if outer.is_empty() {
+                    self.since.set(Some(Instant::now()));
+                }
+            }
+        }
+        self.inner.event(event);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.inner.is_cancelled()
+    }
+
+    fn item_completed(&self, item: &FetchItemResult) {
+        self.inner.item_completed(item);
+    }
+}
+
+/// Runs one dataset's fetch, attaching `specifier` and the phase it reached
+/// to any error that escapes.
+fn in_context<T>(
+    specifier: &str,
+    sink: &dyn ProgressSink,
+    fetch: impl FnOnce(&dyn ProgressSink) -> Result<T, KiraError>,
+) -> Result<T, KiraError> {
+    timed_in_context(specifier, sink, fetch).0
+}
+
+/// [`in_context`], also returning how long each phase took.
+fn timed_in_context<T>(
+    specifier: &str,
+    sink: &dyn ProgressSink,
+    fetch: impl FnOnce(&dyn ProgressSink) -> Result<T, KiraError>,
+) -> (Result<T, KiraError>, PhaseTimings) {
+    let tracker = PhaseTracker::new(sink);
+    let result = fetch(&tracker);
+    let (phase, timings) = tracker.finish();
+    (result.with_context(specifier, &phase), timings)
+}
+
+/// [`in_context`] for a config entry made of several datasets. Reports the
+/// entry itself as a failed item when it fails before reaching any of them.
+fn in_entry_context(
+    label: &str,
+    sink: &dyn ProgressSink,
+    fetch: impl FnOnce(&dyn ProgressSink) -> Result<FetchResult, KiraError>,
+) -> Result<FetchResult, KiraError> {
+    let result = in_context(label, sink, fetch);
+    if let Err(err) = &result
+        && let Some((specifier, phase)) = err.context()
+        && specifier == label
+    {
+        sink.event(ProgressEvent {
+            message: format!("item.failed {label} [{phase}] {}", err.root()),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+    }
+    result
+}
+
+/// Records a failed config entry and lets the fetch go on, unless
+/// `--fail-fast` is set or the whole batch was stopped.
+fn settle<T>(
+    result: Result<T, KiraError>,
+    label: &str,
+    options: &FetchOptions,
+    errors: &mut Vec<FetchItemError>,
+) -> Result<Option<T>, KiraError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(err)
+            if options.fail_fast
+                || matches!(
+                    err.root(),
+                    KiraError::Cancelled | KiraError::BatchPauseTimeout { .. }
+                ) =>
+        {
+            Err(err)
+        }
+        Err(err) => {
+            errors.push(FetchItemError::new(label, &err));
+            Ok(None)
+        }
+    }
+}
+
+pub fn check_cancelled(sink: &dyn ProgressSink) -> Result<(), KiraError> {
+    if sink.is_cancelled() {
+        return Err(KiraError::Cancelled);
+    }
+    cancel::check_current()
+}
+
+/// The command line an `App` was started from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AppContext {
+    /// Arguments after the program name, credentials redacted.
+    pub arguments: Vec<String>,
+    pub working_dir: Option<String>,
+}
+
+impl AppContext {
+    /// Redacts `arguments` as run manifests do.
+    pub fn new(arguments: Vec<String>, working_dir: Option<&Path>) -> Self {
+        Self {
+            arguments: redact_arguments(arguments),
+            working_dir: working_dir.map(|dir| dir.display().to_string()),
+        }
+    }
+
+    /// This process's arguments and working directory.
+    pub fn current() -> Self {
+        Self::new(
+            std::env::args().skip(1).collect(),
+            std::env::current_dir().ok().as_deref(),
+        )
+    }
+
+    fn acquisition(&self, http: &HttpSettings) -> Acquisition {
+        Acquisition {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            arguments: self.arguments.clone(),
+            working_dir: self.working_dir.clone(),
+            config_sha256: None,
+            registries: http
+                .effective_registry_bases()
+                .into_iter()
+                .map(|(registry, base)| (registry.to_string(), redact_credentials(&base)))
+                .collect(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -149,6 +1729,18 @@ pub struct App<
     uniprot: U,
     geo: G,
     knowledge: K,
+    http: HttpSettings,
+    control: ControlSettings,
+    /// Where DOI resolution and BioProject hydration send their requests
+    /// instead of the registries.
+    doi_base_url: Option<String>,
+    /// Where `genome:ensembl:` fetches send their requests instead of
+    /// Ensembl.
+    ensembl_base_url: Option<String>,
+    cache_policy: CachePolicy,
+    zip_limits: ZipLimits,
+    /// Recorded as the `acquisition` of every metadata record written.
+    context: Option<AppContext>,
 }
 
 impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient, K: KnowledgeClient>
@@ -163,6 +1755,65 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
             uniprot,
             geo,
             knowledge,
+            http: HttpSettings::from_env(),
+            control: ControlSettings::default(),
+            doi_base_url: None,
+            ensembl_base_url: None,
+            cache_policy: CachePolicy::default(),
+            zip_limits: ZipLimits::default(),
+            context: None,
+        }
+    }
+
+    pub fn with_http_settings(mut self, http: HttpSettings) -> Self {
+        self.http = http;
+        self
+    }
+
+    pub fn with_control_settings(mut self, control: ControlSettings) -> Self {
+        self.control = control;
+        self
+    }
+
+    /// Which dataset types fetches copy into the shared cache.
+    pub fn with_cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.cache_policy = policy;
+        self
+    }
+
+    /// The invocation this `App` serves, recorded in the metadata it writes.
+    pub fn with_context(mut self, context: AppContext) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// What a downloaded genome package may unpack to.
+    pub fn with_zip_limits(mut self, limits: ZipLimits) -> Self {
+        self.zip_limits = limits;
+        self
+    }
+
+    pub fn with_doi_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.doi_base_url = Some(base_url.into());
+        self
+    }
+
+    fn doi_resolver(&self) -> Result<DoiResolver, KiraError> {
+        match &self.doi_base_url {
+            Some(base_url) => DoiResolver::with_base_url(self.http.clone(), base_url),
+            None => DoiResolver::with_settings(self.http.clone()),
+        }
+    }
+
+    pub fn with_ensembl_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.ensembl_base_url = Some(base_url.into());
+        self
+    }
+
+    fn ensembl_client(&self) -> Result<EnsemblClient, KiraError> {
+        match &self.ensembl_base_url {
+            Some(base_url) => EnsemblClient::with_base_url(self.http.clone(), base_url),
+            None => EnsemblClient::with_settings(self.http.clone()),
         }
     }
 
@@ -174,439 +1825,2529 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
         options: FetchOptions,
         sink: &dyn ProgressSink,
     ) -> Result<FetchResult, KiraError> {
+        let options = self.with_store_cache(options, sink);
+        let _scope = self.begin_fetch(&overrides, &options, sink);
+        let started = Instant::now();
+        let specifier = specifier.map(|spec| self.resolve_alias(spec)).transpose()?;
         let mut items = Vec::new();
 
+        if let Some(spec) = specifier.clone().filter(|spec| match spec {
+            DatasetSpecifier::Doi(_)
+            | DatasetSpecifier::BioProject(_)
+            | DatasetSpecifier::SrrExperiment(_)
+            | DatasetSpecifier::StructureFor(_)
+            | DatasetSpecifier::GenomeTaxon(_) => false,
+            DatasetSpecifier::Uniprot(_) => !overrides.with_structures,
+            _ => true,
+        }) {
+            let label = spec.to_string();
+            items.push(self.batch_item(None, &options, sink, &label, |sink| {
+                self.fetch_single(spec, overrides, options.clone(), sink)
+            })?);
+            return Ok(FetchResult {
+                items,
+                errors: Vec::new(),
+                summary: None,
+                total_duration_ms: 0,
+                timings: PhaseTimings::default(),
+            }
+            .timed(started));
+        }
+
+        if specifier.is_none() && config.is_none() {
+            return Err(KiraError::MissingConfig);
+        }
+        let control = self.start_batch(&options)?;
+        let control = control.as_ref();
+
+        let label = specifier
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
         if let Some(DatasetSpecifier::Doi(doi)) = specifier.clone() {
-            return self.fetch_doi(doi, overrides, options, sink);
+            return in_context(&label, sink, |sink| {
+                self.fetch_doi(doi, overrides, options, control, sink)
+            })
+            .map(|result| result.timed(started));
+        }
+        if let Some(DatasetSpecifier::StructureFor(query)) = specifier.clone() {
+            return in_context(&label, sink, |sink| {
+                self.fetch_structure_for(query, overrides, options, control, sink)
+            })
+            .map(|result| result.timed(started));
+        }
+        if let Some(DatasetSpecifier::GenomeTaxon(taxon)) = specifier.clone() {
+            return in_context(&label, sink, |sink| {
+                self.fetch_genome_taxon(taxon, overrides, options, control, sink)
+            })
+            .map(|result| result.timed(started));
+        }
+        if let Some(DatasetSpecifier::Uniprot(id)) = specifier.clone() {
+            return in_context(&label, sink, |sink| {
+                self.fetch_uniprot_with_structures(id, &overrides, options, control, sink)
+            })
+            .map(|result| result.timed(started));
+        }
+        if let Some(DatasetSpecifier::SrrExperiment(acc)) = specifier.clone() {
+            return in_context(&label, sink, |sink| {
+                self.fetch_srr_experiment(acc, overrides, options, control, sink)
+            })
+            .map(|result| result.timed(started));
+        }
+        if let Some(DatasetSpecifier::BioProject(acc)) = specifier {
+            return in_context(&label, sink, |sink| {
+                self.fetch_bioproject(acc, overrides, options, control, sink)
+            })
+            .map(|result| result.timed(started));
         }
 
-        if let Some(spec) = specifier {
-            items.push(self.fetch_single(spec, overrides, options.clone(), sink)?);
-        } else if let Some(config) = config {
+        let mut errors = Vec::new();
+        let options = FetchOptions {
+            config_sha256: config.and_then(|config| config.sha256.clone()),
+            ..options
+        };
+        if let Some(config) = config {
             for protein in &config.proteins {
                 let spec = DatasetSpecifier::Protein(protein.id.clone());
                 let format = overrides.protein_format.unwrap_or(protein.format);
-                items.push(self.fetch_single(
-                    spec,
-                    FetchOverrides {
-                        protein_format: Some(format),
-                        ..FetchOverrides::default()
-                    },
-                    options.clone(),
-                    sink,
-                )?);
+                let label = spec.to_string();
+                let result = self.batch_item(control, &options, sink, &label, |sink| {
+                    self.fetch_single(
+                        spec,
+                        FetchOverrides {
+                            protein_format: Some(format),
+                            no_fasta: overrides.no_fasta,
+                            ..FetchOverrides::default()
+                        },
+                        options.clone(),
+                        sink,
+                    )
+                });
+                if let Some(item) = settle(result, &label, &options, &mut errors)? {
+                    items.push(item);
+                    let alias = protein.alias.as_ref();
+                    self.apply_alias("protein", protein.id.as_str(), alias, &options, sink)?;
+                }
             }
             for genome in &config.genomes {
-                items.push(self.fetch_genome_with_include(
-                    genome.accession.clone(),
-                    genome.include.clone(),
-                    options.clone(),
-                    sink,
-                )?);
+                let label = format!("genome:{}", genome.accession.as_str());
+                let result = self.batch_item(control, &options, sink, &label, |sink| {
+                    self.fetch_genome_with_include(
+                        genome.accession.clone(),
+                        genome.include.clone(),
+                        overrides.allow_missing_artifacts,
+                        options.clone(),
+                        sink,
+                    )
+                });
+                if let Some(item) = settle(result, &label, &options, &mut errors)? {
+                    items.push(item);
+                    let (accession, alias) = (genome.accession.as_str(), genome.alias.as_ref());
+                    self.apply_alias("genome", accession, alias, &options, sink)?;
+                }
             }
             for srr in &config.srr {
                 let format = overrides.srr_format.unwrap_or(srr.format);
                 let paired = overrides.srr_paired.unwrap_or(srr.paired);
-                items.push(self.fetch_srr(
-                    srr.id.clone(),
-                    format,
-                    paired,
-                    options.clone(),
-                    sink,
-                )?);
+                let download = SrrDownloadOptions {
+                    threads: overrides.srr_threads.or(srr.threads),
+                    temp_dir: overrides
+                        .srr_temp_dir
+                        .clone()
+                        .or_else(|| srr.temp_dir.clone()),
+                };
+                let label = format!("srr:{}", srr.id.as_str());
+                let result = self.batch_item(control, &options, sink, &label, |sink| {
+                    self.fetch_srr(
+                        srr.id.clone(),
+                        format,
+                        paired,
+                        &download,
+                        options.clone(),
+                        sink,
+                    )
+                });
+                if let Some(item) = settle(result, &label, &options, &mut errors)? {
+                    items.push(item);
+                    self.apply_alias("srr", srr.id.as_str(), srr.alias.as_ref(), &options, sink)?;
+                }
             }
             for uni in &config.uniprot {
-                items.push(self.fetch_uniprot(uni.id.clone(), options.clone(), sink)?);
+                let label = format!("uniprot:{}", uni.id.as_str());
+                let result = if uni.with_structures || overrides.with_structures {
+                    in_entry_context(&label, sink, |sink| {
+                        self.fetch_uniprot_with_structures(
+                            uni.id.clone(),
+                            &overrides,
+                            options.clone(),
+                            control,
+                            sink,
+                        )
+                    })
+                    .map(|result| result.items)
+                } else {
+                    self.batch_item(control, &options, sink, &label, |sink| {
+                        self.fetch_uniprot(uni.id.clone(), options.clone(), sink)
+                    })
+                    .map(|item| vec![item])
+                };
+                if let Some(fetched) = settle(result, &label, &options, &mut errors)? {
+                    items.extend(fetched);
+                    self.apply_alias(
+                        "uniprot",
+                        uni.id.as_str(),
+                        uni.alias.as_ref(),
+                        &options,
+                        sink,
+                    )?;
+                }
             }
             for doi in &config.doi {
-                let result =
-                    self.fetch_doi(doi.id.clone(), overrides.clone(), options.clone(), sink)?;
-                items.extend(result.items);
+                let label = format!("doi:{}", doi.id.as_str());
+                let result = in_entry_context(&label, sink, |sink| {
+                    self.fetch_doi(
+                        doi.id.clone(),
+                        overrides.clone(),
+                        options.clone(),
+                        control,
+                        sink,
+                    )
+                });
+                if let Some(result) = settle(result, &label, &options, &mut errors)? {
+                    items.extend(result.items);
+                    self.apply_alias("doi", doi.id.as_str(), doi.alias.as_ref(), &options, sink)?;
+                }
+            }
+            for project in &config.bioprojects {
+                let label = format!("bioproject:{}", project.accession.as_str());
+                let result = in_entry_context(&label, sink, |sink| {
+                    self.fetch_bioproject(
+                        project.accession.clone(),
+                        overrides.clone(),
+                        options.clone(),
+                        control,
+                        sink,
+                    )
+                });
+                if let Some(result) = settle(result, &label, &options, &mut errors)? {
+                    items.extend(result.items);
+                    let (accession, alias) = (project.accession.as_str(), project.alias.as_ref());
+                    self.apply_alias("bioproject", accession, alias, &options, sink)?;
+                }
+            }
+            for proteome in &config.proteomes {
+                let label = format!("uniprot-proteome:{}", proteome.id.as_str());
+                let result = self.batch_item(control, &options, sink, &label, |sink| {
+                    self.fetch_proteome(proteome.id.clone(), options.clone(), sink)
+                });
+                if let Some(item) = settle(result, &label, &options, &mut errors)? {
+                    items.push(item);
+                    let (id, alias) = (proteome.id.as_str(), proteome.alias.as_ref());
+                    self.apply_alias("uniprot-proteome", id, alias, &options, sink)?;
+                }
             }
-        } else {
-            return Err(KiraError::MissingConfig);
         }
 
         Ok(FetchResult {
             items,
+            errors,
             summary: None,
-        })
+            total_duration_ms: 0,
+            timings: PhaseTimings::default(),
+        }
+        .timed(started))
+    }
+
+    /// [`App::fetch`], handing each dataset to `on_item` as soon as it is
+    /// fetched: in the order they finish, all of them before the result
+    /// with its summary is returned. Failed datasets are not reported.
+    pub fn fetch_streaming(
+        &self,
+        specifier: Option<DatasetSpecifier>,
+        config: Option<&ResolvedConfig>,
+        overrides: FetchOverrides,
+        options: FetchOptions,
+        sink: &dyn ProgressSink,
+        on_item: impl FnMut(&FetchItemResult),
+    ) -> Result<FetchResult, KiraError> {
+        let sink = ItemCallback {
+            inner: sink,
+            on_item: RefCell::new(on_item),
+        };
+        self.fetch(specifier, config, overrides, options, &sink)
+    }
+
+    /// Fetches the datasets of an ID list one after another as a single
+    /// batch. Multi-item specifiers contribute all of their items.
+    pub fn fetch_list(
+        &self,
+        entries: &[IdListEntry],
+        overrides: FetchOverrides,
+        options: FetchOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchResult, KiraError> {
+        let options = self.with_store_cache(options, sink);
+        let _scope = self.begin_fetch(&overrides, &options, sink);
+        let started = Instant::now();
+        let control = self.start_batch(&options)?;
+        let control = control.as_ref();
+        let mut items = Vec::new();
+        for entry in entries {
+            let spec = self.resolve_alias(entry.specifier.clone())?;
+            let label = spec.to_string();
+            let multi = |fetch: &dyn Fn(&dyn ProgressSink) -> Result<FetchResult, KiraError>| {
+                in_context(&label, sink, fetch).map(|result| result.items)
+            };
+            match spec {
+                DatasetSpecifier::Doi(doi) => items.extend(multi(&|sink| {
+                    self.fetch_doi(
+                        doi.clone(),
+                        overrides.clone(),
+                        options.clone(),
+                        control,
+                        sink,
+                    )
+                })?),
+                DatasetSpecifier::StructureFor(query) => items.extend(multi(&|sink| {
+                    self.fetch_structure_for(
+                        query.clone(),
+                        overrides.clone(),
+                        options.clone(),
+                        control,
+                        sink,
+                    )
+                })?),
+                DatasetSpecifier::GenomeTaxon(taxon) => items.extend(multi(&|sink| {
+                    self.fetch_genome_taxon(
+                        taxon.clone(),
+                        overrides.clone(),
+                        options.clone(),
+                        control,
+                        sink,
+                    )
+                })?),
+                DatasetSpecifier::SrrExperiment(acc) => items.extend(multi(&|sink| {
+                    self.fetch_srr_experiment(
+                        acc.clone(),
+                        overrides.clone(),
+                        options.clone(),
+                        control,
+                        sink,
+                    )
+                })?),
+                DatasetSpecifier::BioProject(acc) => items.extend(multi(&|sink| {
+                    self.fetch_bioproject(
+                        acc.clone(),
+                        overrides.clone(),
+                        options.clone(),
+                        control,
+                        sink,
+                    )
+                })?),
+                DatasetSpecifier::Uniprot(id) if overrides.with_structures => {
+                    items.extend(multi(&|sink| {
+                        self.fetch_uniprot_with_structures(
+                            id.clone(),
+                            &overrides,
+                            options.clone(),
+                            control,
+                            sink,
+                        )
+                    })?)
+                }
+                spec => items.push(self.batch_item(control, &options, sink, &label, |sink| {
+                    self.fetch_single(spec, overrides.clone(), options.clone(), sink)
+                })?),
+            }
+        }
+        Ok(FetchResult {
+            items,
+            errors: Vec::new(),
+            summary: None,
+            total_duration_ms: 0,
+            timings: PhaseTimings::default(),
+        }
+        .timed(started))
+    }
+
+    /// Arms cancellation and the deadline for one fetch and reports its
+    /// options.
+    /// Applies the store's cache to a fetch's options: a cache turned off
+    /// by `KIRA_BM_CACHE_DIR` means `--no-cache`, and a user cache that
+    /// cannot be written is swapped for the project-local one, with a
+    /// `cache.fallback` warning.
+    fn with_store_cache(&self, mut options: FetchOptions, sink: &dyn ProgressSink) -> FetchOptions {
+        if self.store.cache_disabled() {
+            options.no_cache = true;
+        } else if !options.no_cache
+            && let Some(reason) = self.store.prepare_cache()
+        {
+            sink.event(ProgressEvent {
+                message: format!("cache.fallback {}; {reason}", self.store.cache_root()),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+        }
+        options
+    }
+
+    fn begin_fetch(
+        &self,
+        overrides: &FetchOverrides,
+        options: &FetchOptions,
+        sink: &dyn ProgressSink,
+    ) -> CancelScope {
+        let token = options
+            .cancel
+            .clone()
+            .or_else(cancel::current)
+            .unwrap_or_default();
+        if let Some(secs) = options.deadline_secs {
+            token.set_deadline(Instant::now() + Duration::from_secs(secs));
+        }
+        let scope = token.enter();
+
+        let overrides_summary = overrides.to_string();
+        sink.event(ProgressEvent {
+            message: if overrides_summary == "none" {
+                options.summary()
+            } else {
+                format!("{}; overrides: {overrides_summary}", options.summary())
+            },
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        scope
+    }
+
+    /// Registers a multi-item fetch so `kira-bm batch pause` can reach it.
+    fn start_batch(&self, options: &FetchOptions) -> Result<Option<BatchControl>, KiraError> {
+        if options.dry_run {
+            return Ok(None);
+        }
+        BatchControl::start(
+            self.store.project_root(),
+            self.control,
+            options.pause_timeout_secs.map(Duration::from_secs),
+        )
+        .map(Some)
     }
 
-    pub fn list(&self, sink: &dyn ProgressSink) -> Result<ListResult, KiraError> {
+    pub fn list(
+        &self,
+        options: &ListOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<ListResult, KiraError> {
         sink.event(ProgressEvent {
             message: "phase=Resolve; scanning stores".to_string(),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
 
-        let project_metadata = Store::list_metadata(self.store.project_root())?;
-        let cache_metadata = Store::list_metadata(self.store.cache_root())?;
+        let mut warnings = Vec::new();
+        let mut scan = |root: &Utf8Path| {
+            let (entries, skipped) = Store::scan_metadata(root);
+            for warning in &skipped {
+                tracing::warn!("skipped {warning}");
+            }
+            warnings.extend(skipped);
+            entries
+                .into_iter()
+                .map(|(_, metadata)| metadata)
+                .collect::<Vec<_>>()
+        };
+        let project_metadata = match options.location {
+            ListLocation::Cache => Vec::new(),
+            _ => scan(self.store.project_root()),
+        };
+        let cache_metadata = match options.location {
+            ListLocation::Project => Vec::new(),
+            _ => scan(self.store.cache_root()),
+        };
 
-        let mut map = std::collections::HashMap::<(String, String), ListEntry>::new();
-        for entry in project_metadata {
-            let key = (entry.dataset_type.clone(), entry.id.clone());
-            let value = map.entry(key).or_insert_with(|| ListEntry {
-                dataset_type: entry.dataset_type.clone(),
-                id: entry.id.clone(),
-                format: entry.format.clone(),
-                source: Some(entry.source.clone()),
-                project_path: None,
-                cache_path: None,
+        let mut map = BTreeMap::<(String, String), ListEntry>::new();
+        let stores = [(true, project_metadata), (false, cache_metadata)];
+        for (is_project, metadata) in stores {
+            for entry in metadata {
+                if options
+                    .dataset_type
+                    .as_ref()
+                    .is_some_and(|wanted| !wanted.eq_ignore_ascii_case(&entry.dataset_type))
+                {
+                    continue;
+                }
+                let key = (entry.dataset_type.clone(), entry.id.clone());
+                let value = map.entry(key).or_insert_with(|| ListEntry {
+                    dataset_type: entry.dataset_type.clone(),
+                    id: entry.id.clone(),
+                    format: entry.format.clone(),
+                    formats: Vec::new(),
+                    source: Some(entry.source.clone()),
+                    project_path: None,
+                    cache_path: None,
+                    status: None,
+                    downloaded_at: Some(entry.downloaded_at.clone()),
+                    bytes: stored_bytes(&entry),
+                    provenance: None,
+                    materialization: Materialization::Full,
+                    alias: None,
+                    pinned: false,
+                });
+                value.pinned |= entry.pinned;
+                for format in entry.formats.keys() {
+                    if !value.formats.contains(format) {
+                        value.formats.push(format.clone());
+                    }
+                }
+                value.formats.sort();
+                if is_project {
+                    value.project_path = Some(entry.resolved_path.clone());
+                    value.status = entry.status.clone();
+                    value.provenance = entry.provenance.clone();
+                    value.materialization = entry.materialization;
+                    value.alias = entry.alias.clone();
+                } else {
+                    value.cache_path = Some(entry.resolved_path.clone());
+                }
+            }
+        }
+
+        let mut datasets = map.into_values().collect::<Vec<_>>();
+        match options.sort {
+            ListSort::Name => datasets.sort_by(|a, b| {
+                a.id.to_ascii_lowercase()
+                    .cmp(&b.id.to_ascii_lowercase())
+                    .then_with(|| a.dataset_type.cmp(&b.dataset_type))
+            }),
+            // Entries come out of the map ordered by (type, id).
+            ListSort::Type => {}
+            ListSort::Date => datasets.sort_by_cached_key(|entry| {
+                std::cmp::Reverse(
+                    entry
+                        .downloaded_at
+                        .as_deref()
+                        .and_then(|value| chrono::DateTime::parse_from_rfc3339(value).ok()),
+                )
+            }),
+            ListSort::Size => datasets.sort_by_key(|entry| std::cmp::Reverse(entry.bytes)),
+        }
+        Ok(ListResult { datasets, warnings })
+    }
+
+    pub fn find(&self, pattern: &str, sink: &dyn ProgressSink) -> Result<FindResult, KiraError> {
+        sink.event(ProgressEvent {
+            message: format!("phase=Resolve; searching stores for {pattern}"),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let project = Store::list_metadata(self.store.project_root())?;
+        let cache = Store::list_metadata(self.store.cache_root())?;
+        let documents = collect_documents(project, cache);
+        Ok(FindResult {
+            pattern: pattern.to_string(),
+            matches: search(&documents, pattern),
+        })
+    }
+
+    /// The specifier of the project dataset an `alias:` specifier names;
+    /// other specifiers are returned as they are.
+    pub fn resolve_alias(
+        &self,
+        specifier: DatasetSpecifier,
+    ) -> Result<DatasetSpecifier, KiraError> {
+        let DatasetSpecifier::Alias(alias) = &specifier else {
+            return Ok(specifier);
+        };
+        let meta = self.aliased(alias)?;
+        if meta.id == meta.dataset_type {
+            // go, kegg and reactome are named by their type alone.
+            return meta.dataset_type.parse();
+        }
+        format!("{}:{}", meta.dataset_type, meta.id).parse()
+    }
+
+    /// Reads a specifier typed on the command line. A bare id is matched
+    /// against the id patterns and the aliases of the project's datasets.
+    pub fn read_specifier(&self, value: &str) -> Result<SpecifierReading, KiraError> {
+        if let Ok(specifier) = value.parse::<DatasetSpecifier>() {
+            return Ok(SpecifierReading::Explicit(specifier));
+        }
+        let mut candidates = DatasetSpecifier::candidates(value);
+        if !value.contains(':')
+            && let Ok(alias) = value.parse::<DatasetAlias>()
+            && self.aliased(&alias).is_ok()
+        {
+            candidates.push(DatasetSpecifier::Alias(alias));
+        }
+        match candidates.len() {
+            0 => DatasetSpecifier::infer(value).map(SpecifierReading::Explicit),
+            1 => Ok(SpecifierReading::Inferred(candidates.remove(0))),
+            _ => Ok(SpecifierReading::Ambiguous(candidates)),
+        }
+    }
+
+    fn aliased(&self, alias: &DatasetAlias) -> Result<Metadata, KiraError> {
+        Store::list_metadata(self.store.project_root())?
+            .into_iter()
+            .find(|meta| meta.alias.as_deref() == Some(alias.as_str()))
+            .ok_or_else(|| KiraError::DatasetNotFound(format!("alias:{alias}")))
+    }
+
+    /// The `(dataset_type, id)` under which a specifier's dataset is stored.
+    fn stored_key(&self, specifier: &DatasetSpecifier) -> Result<(String, String), KiraError> {
+        Ok(match specifier {
+            DatasetSpecifier::Protein(id) => ("protein".to_string(), id.as_str().to_string()),
+            DatasetSpecifier::Genome(acc) => ("genome".to_string(), acc.as_str().to_string()),
+            DatasetSpecifier::GenomeName(name) => {
+                ("genome".to_string(), self.local_genome_accession(name))
+            }
+            DatasetSpecifier::GenomeTaxon(_) => {
+                return Err(KiraError::InvalidSpecifier(format!(
+                    "{specifier} names several genomes; use genome:<ACCESSION>"
+                )));
+            }
+            DatasetSpecifier::GenomeEnsembl(genome) => {
+                ("genome".to_string(), self.local_ensembl_id(genome))
+            }
+            DatasetSpecifier::Srr(id) => ("srr".to_string(), id.as_str().to_string()),
+            DatasetSpecifier::SrrExperiment(acc) => ("srx".to_string(), acc.as_str().to_string()),
+            DatasetSpecifier::Uniprot(id) => ("uniprot".to_string(), id.as_str().to_string()),
+            DatasetSpecifier::Proteome(id) => {
+                ("uniprot-proteome".to_string(), id.as_str().to_string())
+            }
+            DatasetSpecifier::Doi(id) => ("doi".to_string(), id.as_str().to_string()),
+            DatasetSpecifier::Expression(id) => ("expression".to_string(), id.as_str().to_string()),
+            DatasetSpecifier::Expression10x(id) => {
+                ("expression10x".to_string(), id.as_str().to_string())
+            }
+            DatasetSpecifier::ExpressionSample(id) => {
+                ("expressionsample".to_string(), id.as_str().to_string())
+            }
+            DatasetSpecifier::BioProject(acc) => {
+                ("bioproject".to_string(), acc.as_str().to_string())
+            }
+            DatasetSpecifier::StructureFor(query) => {
+                ("uniprot".to_string(), query.accession.as_str().to_string())
+            }
+            DatasetSpecifier::Go => ("go".to_string(), "go".to_string()),
+            DatasetSpecifier::Kegg(organism) => (
+                "kegg".to_string(),
+                organism
+                    .as_ref()
+                    .map_or("kegg", KeggOrganism::as_str)
+                    .to_string(),
+            ),
+            DatasetSpecifier::Reactome => ("reactome".to_string(), "reactome".to_string()),
+            DatasetSpecifier::Alias(alias) => {
+                let meta = self.aliased(alias)?;
+                (meta.dataset_type, meta.id)
+            }
+        })
+    }
+
+    pub fn info(
+        &self,
+        specifier: DatasetSpecifier,
+        sink: &dyn ProgressSink,
+    ) -> Result<InfoResult, KiraError> {
+        let key = self.stored_key(&specifier)?;
+        self.info_key(key, sink)
+    }
+
+    /// [`App::info`] for every project dataset `pattern` matches.
+    pub fn info_matching(
+        &self,
+        pattern: &SpecifierPattern,
+        sink: &dyn ProgressSink,
+    ) -> Result<InfoMatches, KiraError> {
+        let datasets = self
+            .matching(pattern)?
+            .into_iter()
+            .map(|key| self.info_key(key, sink))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(InfoMatches {
+            pattern: pattern.to_string(),
+            matched: datasets.len(),
+            datasets,
+        })
+    }
+
+    /// The `(type, id)` of each project dataset `pattern` matches, sorted;
+    /// an error when there is none.
+    fn matching(&self, pattern: &SpecifierPattern) -> Result<Vec<(String, String)>, KiraError> {
+        let keys = Store::list_metadata(self.store.project_root())?
+            .into_iter()
+            .filter(|meta| pattern.matches(&meta.dataset_type, &meta.id))
+            .map(|meta| (meta.dataset_type, meta.id))
+            .collect::<BTreeSet<_>>();
+        if keys.is_empty() {
+            return Err(KiraError::NoMatchingDatasets(pattern.to_string()));
+        }
+        Ok(keys.into_iter().collect())
+    }
+
+    fn info_key(
+        &self,
+        key: (String, String),
+        sink: &dyn ProgressSink,
+    ) -> Result<InfoResult, KiraError> {
+        sink.event(ProgressEvent {
+            message: format!("phase=Resolve; looking up {}", key.1),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+
+        let project = Store::list_metadata(self.store.project_root())?;
+        let cache = Store::list_metadata(self.store.cache_root())?;
+        let project_meta = project
+            .into_iter()
+            .find(|meta| meta.dataset_type == key.0 && meta.id == key.1);
+        let cache_meta = cache
+            .into_iter()
+            .find(|meta| meta.dataset_type == key.0 && meta.id == key.1);
+
+        if project_meta.is_none() && cache_meta.is_none() {
+            return Err(KiraError::DatasetNotFound(format!("{}:{}", key.0, key.1)));
+        }
+
+        let details = dataset_details(&key.0, project_meta.as_ref(), cache_meta.as_ref());
+        let mut formats = cache_meta
+            .as_ref()
+            .map(|meta| meta.formats.clone())
+            .unwrap_or_default();
+        if let Some(meta) = &project_meta {
+            formats.extend(meta.formats.clone());
+        }
+
+        Ok(InfoResult {
+            dataset_type: key.0,
+            id: key.1,
+            format: project_meta
+                .as_ref()
+                .and_then(|meta| meta.format.clone())
+                .or_else(|| cache_meta.as_ref().and_then(|meta| meta.format.clone())),
+            formats,
+            source: project_meta
+                .as_ref()
+                .map(|meta| meta.source.clone())
+                .or_else(|| cache_meta.as_ref().map(|meta| meta.source.clone())),
+            provenance: project_meta
+                .as_ref()
+                .and_then(|meta| meta.provenance.clone()),
+            imported_from: project_meta
+                .as_ref()
+                .and_then(|meta| meta.imported_from.clone()),
+            materialization: project_meta
+                .as_ref()
+                .map(|meta| meta.materialization)
+                .unwrap_or_default(),
+            acquisition: project_meta
+                .as_ref()
+                .and_then(|meta| meta.acquisition.clone())
+                .or_else(|| {
+                    cache_meta
+                        .as_ref()
+                        .and_then(|meta| meta.acquisition.clone())
+                }),
+            project_path: project_meta.map(|meta| meta.resolved_path),
+            cache_path: cache_meta.map(|meta| meta.resolved_path),
+            details,
+        })
+    }
+
+    /// Writes a stored protein in another format next to the stored file,
+    /// without network access. The project copy is converted when it holds
+    /// the structure, otherwise the cached one; the record of that store
+    /// lists the new format under `converted_formats`.
+    pub fn convert(
+        &self,
+        specifier: DatasetSpecifier,
+        to: ProteinFormat,
+        sink: &dyn ProgressSink,
+    ) -> Result<ConvertResult, KiraError> {
+        let DatasetSpecifier::Protein(id) = specifier else {
+            return Err(KiraError::InvalidSpecifier(format!(
+                "{specifier}: only protein datasets can be converted"
+            )));
+        };
+        sink.event(ProgressEvent {
+            message: format!("phase=Resolve; protein {id} to {to}"),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let _lock = self.lock_dataset("protein", id.as_str(), &FetchOptions::default(), sink)?;
+
+        let stores = [
+            (
+                "project",
+                self.store.project_metadata_path("protein", id.as_str()),
+                self.store.project_protein_path(&id, to),
+            ),
+            (
+                "cache",
+                self.store.cache_metadata_path("protein", id.as_str()),
+                self.store.cache_protein_path(&id, to),
+            ),
+        ];
+        for (location, record_path, target) in stores {
+            let Some(mut record) = fs::read(record_path.as_std_path())
+                .ok()
+                .and_then(|bytes| serde_json::from_slice::<Metadata>(&bytes).ok())
+            else {
+                continue;
+            };
+            let stored = record
+                .format
+                .iter()
+                .chain(&record.converted_formats)
+                .chain(record.formats.keys())
+                .filter_map(|format| parse_protein_format(format))
+                .filter(|format| target.with_extension(format.to_string()).exists())
+                .collect::<Vec<_>>();
+            let mut result = ConvertResult {
+                dataset_type: "protein".to_string(),
+                id: id.as_str().to_string(),
+                from: String::new(),
+                to: to.to_string(),
+                location: location.to_string(),
+                path: target.to_string(),
+                action: "present".to_string(),
+                notes: Vec::new(),
+            };
+            if stored.contains(&to) {
+                result.from = to.to_string();
+                return Ok(result);
+            }
+            // Text mmCIF converts to PDB without decoding BinaryCIF first.
+            let Some(from) = [ProteinFormat::Cif, ProteinFormat::Bcif]
+                .into_iter()
+                .find(|from| stored.contains(from) && crate::convert::can_convert(*from, to))
+            else {
+                if let Some(from) = stored.first() {
+                    return Err(KiraError::Conversion {
+                        from: from.to_string(),
+                        to: to.to_string(),
+                        reason: "this conversion is not supported".to_string(),
+                    });
+                }
+                continue;
+            };
+
+            sink.event(ProgressEvent {
+                message: format!("phase=Store; converting {location} {from} to {to}"),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            let source = target.with_extension(from.to_string());
+            let bytes = fs::read(source.as_std_path())
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            let converted = crate::convert::convert_structure(&bytes, from, to)?;
+            let tmp_path = target.with_extension(format!("{to}.tmp"));
+            fs::write(tmp_path.as_std_path(), converted)
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            fs::rename(tmp_path.as_std_path(), target.as_std_path())
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+
+            record.converted_formats.push(to.to_string());
+            record.converted_formats.sort();
+            record.converted_formats.dedup();
+            if let Some(dir) = target.parent() {
+                record.formats = Store::protein_files(dir, &id);
+            }
+            Store::write_metadata(&record_path, &record)?;
+
+            result.from = from.to_string();
+            result.action = "convert".to_string();
+            result.notes = crate::convert::lossy_notes(to);
+            return Ok(result);
+        }
+        Err(KiraError::DatasetNotFound(format!("protein:{id}")))
+    }
+
+    /// Registers local files as the project copy of `specifier`, placed
+    /// where a fetch would have stored them and recorded with `source`
+    /// `import`, the path they came from and their checksums. Unless
+    /// `options.no_verify`, the files must hold what the specifier names.
+    pub fn import(
+        &self,
+        specifier: DatasetSpecifier,
+        from: &Path,
+        options: ImportOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<ImportResult, KiraError> {
+        let invalid = |reason: String| KiraError::InvalidImport {
+            dataset: specifier.to_string(),
+            reason,
+        };
+        let from =
+            fs::canonicalize(from).map_err(|err| invalid(format!("{}: {err}", from.display())))?;
+        let (dataset_type, id) = match &specifier {
+            DatasetSpecifier::Protein(_)
+            | DatasetSpecifier::Genome(_)
+            | DatasetSpecifier::Srr(_)
+            | DatasetSpecifier::Uniprot(_)
+            | DatasetSpecifier::Expression(_)
+            | DatasetSpecifier::Expression10x(_) => self.stored_key(&specifier)?,
+            _ => {
+                return Err(KiraError::InvalidSpecifier(format!(
+                    "{specifier}: only protein, genome:<ACCESSION>, srr, uniprot, expression \
+                     and expression10x datasets can be imported"
+                )));
+            }
+        };
+        sink.event(ProgressEvent {
+            message: format!("phase=Resolve; {dataset_type} {id} from {}", from.display()),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let _lock = self.lock_dataset(&dataset_type, &id, &FetchOptions::default(), sink)?;
+
+        let record_path = self.store.project_metadata_path(&dataset_type, &id);
+        if record_path.as_std_path().exists() && !options.force {
+            return Err(invalid(
+                "already in the project store; pass --force to replace it".to_string(),
+            ));
+        }
+
+        let (source, format, dest) = match &specifier {
+            DatasetSpecifier::Protein(protein) => {
+                let (file, format) =
+                    crate::import::protein_file(&from, protein).map_err(invalid)?;
+                let dest = self.store.project_protein_path(protein, format);
+                (file, Some(format.to_string()), dest)
+            }
+            DatasetSpecifier::Genome(acc) => {
+                (from.clone(), None, self.store.project_genome_dir(acc))
+            }
+            DatasetSpecifier::Srr(srr) => (from.clone(), None, self.store.project_srr_dir(srr)),
+            DatasetSpecifier::Uniprot(uniprot) => {
+                (from.clone(), None, self.store.project_uniprot_dir(uniprot))
+            }
+            DatasetSpecifier::Expression(acc) => {
+                (from.clone(), None, self.store.project_expression_dir(acc))
+            }
+            DatasetSpecifier::Expression10x(acc) => (
+                from.clone(),
+                None,
+                self.store.project_expression10x_dir(acc),
+            ),
+            _ => unreachable!("import rejects other specifiers above"),
+        };
+
+        if !options.no_verify {
+            sink.event(ProgressEvent {
+                message: format!("phase=Verify; {}", source.display()),
+                elapsed: None,
+                level: ProgressLevel::Phase,
             });
-            value.project_path = Some(entry.resolved_path.clone());
+            let defect = match &specifier {
+                DatasetSpecifier::Protein(protein) => {
+                    let format = format
+                        .as_deref()
+                        .and_then(parse_protein_format)
+                        .unwrap_or(ProteinFormat::Cif);
+                    crate::import::protein_defect(&source, format, protein)?
+                }
+                DatasetSpecifier::Genome(_) => crate::import::genome_defect(&source)?,
+                DatasetSpecifier::Srr(_) => crate::import::reads_defect(&source)?,
+                DatasetSpecifier::Uniprot(_) => crate::import::uniprot_defect(&source, &id)?,
+                _ => crate::import::empty_defect(&source)?,
+            };
+            if let Some(defect) = defect {
+                return Err(invalid(format!(
+                    "{defect}; pass --no-verify to import it anyway"
+                )));
+            }
+        }
+
+        if options.force {
+            let label = format!("{dataset_type}:{id}");
+            let targets = Store::list_metadata(self.store.project_root())?
+                .iter()
+                .filter(|entry| audit::specifier_for(entry) == label)
+                .map(|entry| AuditTarget::from_metadata(entry, None))
+                .collect::<Vec<_>>();
+            if !targets.is_empty() {
+                audit::append(
+                    self.store.project_root(),
+                    &AuditRecord::new("force-replace", targets, vec!["--force".to_string()]),
+                )?;
+            }
+        }
+
+        sink.event(ProgressEvent {
+            message: format!(
+                "phase=Store; {} {}",
+                if options.link { "linking" } else { "copying" },
+                dest
+            ),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let (files, linked, root) = if let Some(format) = format.as_deref() {
+            let tmp_path = dest.with_extension(format!("{format}.tmp"));
+            let (_, linked) = crate::import::place(&source, tmp_path.as_std_path(), options.link)?;
+            fs::rename(tmp_path.as_std_path(), dest.as_std_path())
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            let name = dest.file_name().unwrap_or_default().to_string();
+            let root = dest.parent().map(Utf8Path::to_path_buf).unwrap_or_default();
+            (vec![name], linked, root)
+        } else {
+            let parent = dest.parent().map(Utf8Path::to_path_buf).unwrap_or_default();
+            fs::create_dir_all(parent.as_std_path())
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            let staging = StagingDir::create_in(parent.as_std_path(), "import", &id)
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            let target = match source.is_file() {
+                true => staging.path().join(source.file_name().unwrap_or_default()),
+                false => staging.path().to_path_buf(),
+            };
+            let (files, linked) = crate::import::place(&source, &target, options.link)?;
+            staging
+                .persist(dest.as_std_path())
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            (files, linked, dest.clone())
+        };
+
+        let download =
+            DownloadRecord::of_files(root.as_std_path(), &files, Vec::new(), &Digests::new())?;
+        let mut meta = self.build_metadata(
+            "import",
+            &dataset_type,
+            &id,
+            format.clone(),
+            dest.as_str(),
+            None,
+        );
+        meta.imported_from = Some(from.display().to_string());
+        meta.download = Some(download.clone());
+        if let DatasetSpecifier::Protein(protein) = &specifier {
+            record_protein_formats(&mut meta, &record_path, &root, protein);
+        }
+        Store::write_metadata(&record_path, &meta)?;
+
+        let mut notes = Vec::new();
+        if options.link && !linked {
+            notes.push("some files could not be hard-linked and were copied".to_string());
+        }
+        Ok(ImportResult {
+            dataset_type,
+            id,
+            format,
+            imported_from: from.display().to_string(),
+            project_path: dest.to_string(),
+            files,
+            bytes: download.bytes_written,
+            linked,
+            verified: !options.no_verify,
+            notes,
+        })
+    }
+
+    /// Every project dataset whose record traces it to `doi`.
+    pub fn provenance(&self, doi: &Doi, sink: &dyn ProgressSink) -> Result<DoiDatasets, KiraError> {
+        sink.event(ProgressEvent {
+            message: format!("phase=Resolve; datasets for doi {}", doi.as_str()),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let project = Store::list_metadata(self.store.project_root())?;
+        let resolved = project
+            .iter()
+            .any(|meta| meta.dataset_type == "doi" && meta.id == doi.as_str());
+        let mut datasets = project
+            .into_iter()
+            .filter(|meta| {
+                meta.provenance
+                    .as_ref()
+                    .is_some_and(|provenance| provenance.doi == doi.as_str())
+            })
+            .collect::<Vec<_>>();
+        if datasets.is_empty() && !resolved {
+            return Err(KiraError::DatasetNotFound(format!("doi:{}", doi.as_str())));
+        }
+        datasets.sort_by(|a, b| (&a.dataset_type, &a.id).cmp(&(&b.dataset_type, &b.id)));
+        Ok(DoiDatasets {
+            doi: doi.as_str().to_string(),
+            datasets,
+        })
+    }
+
+    /// Writes the manifest of a finished fetch into the project store and
+    /// prunes all but the newest `keep`.
+    pub fn record_run(
+        &self,
+        invocation: RunInvocation,
+        outcome: &Result<FetchResult, KiraError>,
+        keep: usize,
+    ) -> Result<RunManifest, KiraError> {
+        let manifest = RunManifest::finish(invocation, outcome)?;
+        runs::write(self.store.project_root(), &manifest, keep)?;
+        Ok(manifest)
+    }
+
+    /// The project's run manifests, oldest first.
+    pub fn list_runs(&self) -> Result<Vec<RunSummary>, KiraError> {
+        Ok(runs::list(self.store.project_root())?
+            .iter()
+            .map(RunManifest::summary)
+            .collect())
+    }
+
+    pub fn show_run(&self, id: &str) -> Result<RunManifest, KiraError> {
+        runs::show(self.store.project_root(), id)
+    }
+
+    /// Checks the tools, registries and store roots fetches depend on.
+    pub fn doctor(&self, probe: &dyn EndpointProbe) -> DoctorReport {
+        doctor::run(
+            &self.store,
+            &self.srr,
+            &self.http,
+            probe,
+            chrono::Utc::now(),
+        )
+    }
+
+    /// Clears the project store, or the datasets of `options.types`,
+    /// keeping pinned datasets unless `options.include_pinned`.
+    pub fn clear(
+        &self,
+        options: &ClearOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<ClearResult, KiraError> {
+        if let Some(kind) = options
+            .types
+            .iter()
+            .find(|kind| !DATASET_TYPES.contains(&kind.to_ascii_lowercase().as_str()))
+        {
+            return Err(KiraError::InvalidFormat(format!(
+                "unknown dataset type {kind}; expected one of {}",
+                DATASET_TYPES.join(", ")
+            )));
+        }
+        if !options.dry_run {
+            sink.event(ProgressEvent {
+                message: "phase=Store; clearing project store".to_string(),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+        }
+        let clear = self.store.clear_project(options)?;
+        let mut types = BTreeMap::<&str, (usize, u64)>::new();
+        for (entry, bytes) in &clear.cleared {
+            let totals = types.entry(entry.dataset_type.as_str()).or_default();
+            totals.0 += 1;
+            totals.1 += bytes;
+        }
+        Ok(ClearResult {
+            cleared: !options.dry_run,
+            dry_run: options.dry_run,
+            datasets: clear.cleared.len(),
+            bytes: clear.cleared.iter().map(|(_, bytes)| bytes).sum(),
+            types: types
+                .into_iter()
+                .map(|(dataset_type, (datasets, bytes))| ClearedType {
+                    dataset_type: dataset_type.to_string(),
+                    datasets,
+                    bytes,
+                })
+                .collect(),
+            skipped_pinned: clear.kept.iter().map(audit::specifier_for).collect(),
+        })
+    }
+
+    /// Sets or clears the pin on every stored copy of a dataset.
+    pub fn pin(
+        &self,
+        specifier: DatasetSpecifier,
+        pinned: bool,
+        sink: &dyn ProgressSink,
+    ) -> Result<PinResult, KiraError> {
+        let key = self.stored_key(&specifier)?;
+        self.pin_key(key, pinned, sink)
+    }
+
+    /// [`App::pin`] for every project dataset `pattern` matches.
+    pub fn pin_matching(
+        &self,
+        pattern: &SpecifierPattern,
+        pinned: bool,
+        sink: &dyn ProgressSink,
+    ) -> Result<PinMatches, KiraError> {
+        let datasets = self
+            .matching(pattern)?
+            .into_iter()
+            .map(|key| self.pin_key(key, pinned, sink))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(PinMatches {
+            pattern: pattern.to_string(),
+            pinned,
+            matched: datasets.len(),
+            datasets,
+        })
+    }
+
+    fn pin_key(
+        &self,
+        (dataset_type, id): (String, String),
+        pinned: bool,
+        sink: &dyn ProgressSink,
+    ) -> Result<PinResult, KiraError> {
+        let records =
+            self.store
+                .set_pinned(&dataset_type, &id, pinned, DEFAULT_LOCK_TIMEOUT, sink)?;
+        Ok(PinResult {
+            specifier: if dataset_type == id {
+                dataset_type
+            } else {
+                format!("{dataset_type}:{id}")
+            },
+            pinned,
+            records: records.iter().map(ToString::to_string).collect(),
+        })
+    }
+
+    /// Finds what crashed runs and hand edits left in both stores: stale
+    /// staging directories, metadata records whose data is gone and dataset
+    /// directories no record points into. Only `options.apply` deletes, and
+    /// orphans only with `options.prune_orphans`; project-side deletions of
+    /// datasets are audited.
+    pub fn gc(&self, options: GcOptions, sink: &dyn ProgressSink) -> Result<GcResult, KiraError> {
+        sink.event(ProgressEvent {
+            message: "phase=Resolve; scanning project store and cache".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let now = std::time::SystemTime::now();
+        let mut result = GcResult {
+            dry_run: !options.apply,
+            temp_dirs: Vec::new(),
+            dangling_metadata: Vec::new(),
+            orphans: Vec::new(),
+            unreferenced_blobs: Vec::new(),
+            skipped_pinned: Vec::new(),
+        };
+        let mut targets = Vec::new();
+        let project_root = self.store.project_root();
+        for root in [project_root, self.store.cache_root()] {
+            let records = Store::list_metadata_files(root)?;
+            for dir in Store::stale_staging_dirs(root, options.min_age, now) {
+                result
+                    .temp_dirs
+                    .push(gc_remove(&dir, None, None, options.apply)?);
+            }
+            for (path, metadata) in &records {
+                if metadata.is_expired() || Path::new(&metadata.resolved_path).exists() {
+                    continue;
+                }
+                let keep = metadata.pinned && !options.include_pinned;
+                if keep {
+                    result.skipped_pinned.push(audit::specifier_for(metadata));
+                }
+                let entry = gc_remove(
+                    path,
+                    Some(audit::specifier_for(metadata)),
+                    Some(metadata.resolved_path.clone()),
+                    options.apply && !keep,
+                )?;
+                if entry.removed && root == project_root {
+                    targets.push(AuditTarget::from_metadata(
+                        metadata,
+                        Some(audit::refetch_hint(metadata)),
+                    ));
+                }
+                result.dangling_metadata.push(entry);
+            }
+            for dir in Store::orphan_dataset_dirs(root, &records) {
+                let downloaded_at = gc_mtime(&dir);
+                let entry = gc_remove(&dir, None, None, options.apply && options.prune_orphans)?;
+                if entry.removed && root == project_root {
+                    targets.push(AuditTarget {
+                        specifier: dir
+                            .strip_prefix(root)
+                            .map(Utf8Path::to_string)
+                            .unwrap_or_else(|_| dir.to_string()),
+                        bytes: entry.bytes,
+                        path: entry.path.clone(),
+                        downloaded_at,
+                        undo: None,
+                    });
+                }
+                result.orphans.push(entry);
+            }
+        }
+        // Swept last, so blobs only the removed datasets linked to go too.
+        for blob in self.store.unreferenced_blobs() {
+            result
+                .unreferenced_blobs
+                .push(gc_remove(&blob, None, None, options.apply)?);
+        }
+        if !targets.is_empty() {
+            let flags = if options.prune_orphans {
+                vec!["--prune-orphans".to_string()]
+            } else {
+                Vec::new()
+            };
+            audit::append(project_root, &AuditRecord::new("gc", targets, flags))?;
+        }
+        Ok(result)
+    }
+
+    /// Writes `kira-bm.json` for the datasets in the project store. An
+    /// existing file is merged into: its entries, options and settings are
+    /// kept and only stored datasets it does not list are appended, unless
+    /// `options.force` asks for the file to be regenerated.
+    pub fn init_config(
+        &self,
+        options: InitOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<InitResult, KiraError> {
+        let path = Project::current()?.config_path();
+        self.init_config_at(&path, options, sink)
+    }
+
+    /// [`App::init_config`] for the config file at `path`.
+    pub fn init_config_at(
+        &self,
+        path: &Path,
+        options: InitOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<InitResult, KiraError> {
+        sink.event(ProgressEvent {
+            message: "phase=Resolve; scanning project store".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+
+        let metadata = Store::list_metadata(self.store.project_root())?;
+        // A file that cannot be read is only replaced when asked to.
+        let existing = match ConfigLoader::load(path) {
+            Ok(config) => Some(config),
+            Err(_) if options.force || !path.exists() => None,
+            Err(err) => return Err(err),
+        };
+        // Aliases come from the store, or else from the existing config for
+        // datasets fetched before they were given one.
+        let mut configured = existing
+            .as_ref()
+            .map(|config| config.aliases().into_iter().collect::<BTreeMap<_, _>>())
+            .unwrap_or_default();
+        configured.retain(|_, alias| {
+            !metadata
+                .iter()
+                .any(|entry| entry.alias.as_ref() == Some(alias))
+        });
+        let mut proteins = Vec::new();
+        let mut genomes = Vec::new();
+        let mut srr = Vec::new();
+        let mut uniprot = Vec::new();
+        let mut doi = Vec::new();
+        let mut bioprojects = Vec::new();
+        let mut proteomes = Vec::new();
+
+        for entry in metadata {
+            let id = entry.id.clone();
+            let alias = entry
+                .alias
+                .clone()
+                .or_else(|| configured.remove(&format!("{}:{}", entry.dataset_type, entry.id)));
+            match entry.dataset_type.as_str() {
+                "protein" => {
+                    let format = entry
+                        .format
+                        .as_deref()
+                        .and_then(parse_protein_format)
+                        .filter(|format| *format != ProteinFormat::Cif);
+                    proteins.push(if format.is_none() && alias.is_none() {
+                        ProteinEntry::Shorthand(id)
+                    } else {
+                        ProteinEntry::Detailed(crate::config::ProteinEntryObject {
+                            id,
+                            format,
+                            alias,
+                        })
+                    });
+                }
+                // kira-bm.json lists genomes by accession only.
+                "genome" if entry.source == "ensembl" => {}
+                "genome" => genomes.push(match alias {
+                    None => GenomeEntry::Shorthand(id),
+                    alias => GenomeEntry::Detailed(crate::config::GenomeEntryObject {
+                        accession: id,
+                        include: None,
+                        alias,
+                    }),
+                }),
+                "srr" => {
+                    let (format, paired) = load_srr_settings(&entry.resolved_path);
+                    if matches!(format, Some(SrrFormat::Fastq) | None)
+                        && !paired.unwrap_or(false)
+                        && alias.is_none()
+                    {
+                        srr.push(SrrEntry::Shorthand(id));
+                    } else {
+                        srr.push(SrrEntry::Detailed(crate::config::SrrEntryObject {
+                            id,
+                            format,
+                            paired,
+                            threads: None,
+                            temp_dir: None,
+                            alias,
+                        }));
+                    }
+                }
+                "uniprot" => uniprot.push(match alias {
+                    None => UniprotEntry::Shorthand(id),
+                    alias => UniprotEntry::Detailed(crate::config::UniprotEntryObject {
+                        id,
+                        with_structures: None,
+                        alias,
+                    }),
+                }),
+                "doi" => doi.push(match alias {
+                    None => DoiEntry::Shorthand(id),
+                    alias => DoiEntry::Detailed(crate::config::DoiEntryObject { id, alias }),
+                }),
+                "bioproject" => bioprojects.push(match alias {
+                    None => BioProjectEntry::Shorthand(id),
+                    alias => BioProjectEntry::Detailed(crate::config::BioProjectEntryObject {
+                        accession: id,
+                        alias,
+                    }),
+                }),
+                "uniprot-proteome" => proteomes.push(match alias {
+                    None => ProteomeEntry::Shorthand(id),
+                    alias => {
+                        ProteomeEntry::Detailed(crate::config::ProteomeEntryObject { id, alias })
+                    }
+                }),
+                _ => {}
+            }
+        }
+
+        let scanned = Config {
+            schema_version: Some(1),
+            proteins,
+            genomes,
+            srr,
+            uniprot,
+            doi,
+            bioprojects,
+            proteomes,
+            ..Config::default()
+        };
+
+        let (config, added, preserved) = match existing.clone().filter(|_| !options.force) {
+            Some(mut config) => {
+                let preserved = InitCounts::of(&config);
+                let added = config.merge_entries(scanned);
+                let added = InitCounts::of_specifiers(&added);
+                (config, added, preserved)
+            }
+            None => {
+                let added = InitCounts::of(&scanned);
+                (scanned, added, InitCounts::default())
+            }
+        };
+        let unchanged = existing.is_some() && !options.force && added == InitCounts::default();
+        let diff = if options.dry_run {
+            Some(config_diff(path, &config)?)
+        } else {
+            None
+        };
+
+        let written = !options.dry_run && !unchanged;
+        if written {
+            sink.event(ProgressEvent {
+                message: "phase=Store; writing kira-bm.json".to_string(),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            write_config_atomic(path, &config)?;
+        }
+
+        Ok(InitResult {
+            path: path.to_string_lossy().to_string(),
+            written,
+            dry_run: options.dry_run,
+            counts: InitCounts::of(&config),
+            added,
+            preserved,
+            diff,
+        })
+    }
+
+    fn fetch_single(
+        &self,
+        specifier: DatasetSpecifier,
+        overrides: FetchOverrides,
+        options: FetchOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchItemResult, KiraError> {
+        if options.metadata_only && !specifier.supports_metadata_only() {
+            return Err(KiraError::MetadataOnlyUnsupported(
+                specifier.dataset_type().to_string(),
+            ));
+        }
+        if !options.dry_run {
+            self.store.ensure_project_root()?;
+            self.store.ensure_cache_root()?;
+        }
+
+        let registry = specifier.resolve_registry(overrides.protein_format);
+        match (specifier, registry) {
+            (DatasetSpecifier::Protein(id), Registry::Rcsb) => {
+                self.fetch_protein(id, &overrides, options, sink)
+            }
+            (DatasetSpecifier::Protein(id), Registry::Ncbi) => {
+                self.fetch_protein(id, &overrides, options, sink)
+            }
+            (DatasetSpecifier::Genome(acc), Registry::Ncbi) => self.fetch_genome_with_include(
+                acc,
+                overrides.genome_include(),
+                overrides.allow_missing_artifacts,
+                options,
+                sink,
+            ),
+            (DatasetSpecifier::Genome(acc), Registry::Rcsb) => self.fetch_genome_with_include(
+                acc,
+                overrides.genome_include(),
+                overrides.allow_missing_artifacts,
+                options,
+                sink,
+            ),
+            (DatasetSpecifier::GenomeName(name), _) => {
+                self.fetch_genome_name(name, &overrides, options, sink)
+            }
+            (DatasetSpecifier::GenomeEnsembl(genome), Registry::Ensembl) => {
+                self.fetch_ensembl_genome(genome, &overrides, options, sink)
+            }
+            (DatasetSpecifier::Srr(id), Registry::Ncbi) => self.fetch_srr(
+                id,
+                overrides.srr_format.unwrap_or(SrrFormat::Fastq),
+                overrides.srr_paired.unwrap_or(false),
+                &overrides.srr_download_options(),
+                options,
+                sink,
+            ),
+            (DatasetSpecifier::Uniprot(id), Registry::Uniprot) => {
+                self.fetch_uniprot(id, options, sink)
+            }
+            (DatasetSpecifier::Proteome(id), Registry::Uniprot) => {
+                self.fetch_proteome(id, options, sink)
+            }
+            (DatasetSpecifier::Doi(_), Registry::Doi) => Err(KiraError::DoiResolution(
+                "doi resolution must be invoked from the top-level fetch".to_string(),
+            )),
+            (DatasetSpecifier::BioProject(acc), _) => Err(KiraError::InvalidSpecifier(format!(
+                "bioproject:{acc} must be fetched from the top-level fetch"
+            ))),
+            (DatasetSpecifier::SrrExperiment(acc), _) => Err(KiraError::InvalidSpecifier(format!(
+                "srx:{acc} must be fetched from the top-level fetch"
+            ))),
+            (DatasetSpecifier::StructureFor(query), _) => Err(KiraError::InvalidSpecifier(
+                format!("structure-for:{query} must be fetched from the top-level fetch"),
+            )),
+            (DatasetSpecifier::GenomeTaxon(taxon), _) => Err(KiraError::InvalidSpecifier(format!(
+                "genome:taxon:{taxon} must be fetched from the top-level fetch"
+            ))),
+            (DatasetSpecifier::Expression(acc), Registry::Geo) => {
+                self.fetch_expression(acc, &overrides, options, sink)
+            }
+            (DatasetSpecifier::Expression10x(acc), Registry::Geo) => {
+                self.fetch_expression10x(acc, &overrides, options, sink)
+            }
+            (DatasetSpecifier::ExpressionSample(acc), Registry::Geo) => {
+                self.fetch_expression_sample(acc, &overrides, options, sink)
+            }
+            (DatasetSpecifier::Go, Registry::Go) => self.fetch_go(&overrides, options, sink),
+            (DatasetSpecifier::Kegg(None), Registry::Kegg) => self.fetch_kegg(options, sink),
+            (DatasetSpecifier::Kegg(Some(organism)), Registry::Kegg) => {
+                self.fetch_kegg_organism(organism, options, sink)
+            }
+            (DatasetSpecifier::Reactome, Registry::Reactome) => {
+                self.fetch_reactome(&overrides, options, sink)
+            }
+            _ => Err(KiraError::InvalidFormat(
+                "unsupported registry for dataset type".to_string(),
+            )),
+        }
+    }
+
+    /// Resolves `doi` to the datasets it cites and stores the resolution,
+    /// downloading nothing. A stored resolution is reused unless
+    /// `options.force`, or unless `follow_related` asks for related records
+    /// it did not search.
+    pub fn resolve_doi(
+        &self,
+        doi: &Doi,
+        options: &FetchOptions,
+        follow_related: bool,
+        sink: &dyn ProgressSink,
+    ) -> Result<DoiResolution, KiraError> {
+        sink.event(ProgressEvent {
+            message: format!("phase=Resolve; doi {}", doi.as_str()),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+
+        if !options.dry_run {
+            self.store.ensure_project_root()?;
+        }
+
+        let resolver = self.doi_resolver()?.follow_related(follow_related);
+        let resolution_path = self.store.project_doi_resolution_path(doi);
+        let stored = if !options.force && resolution_path.as_std_path().exists() {
+            Some(read_doi_resolution(&resolution_path)?)
+        } else {
+            None
+        };
+        let stored = stored.filter(|stored| {
+            !follow_related || stored.related.iter().all(|related| related.followed)
+        });
+        let mut resolution = if let Some(stored) = stored {
+            stored
+        } else {
+            sink.event(ProgressEvent {
+                message: "phase=Prepare; preparing DOI resolution".to_string(),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            sink.event(ProgressEvent {
+                message: "phase=Fetch; resolving DOI metadata".to_string(),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            sink.event(ProgressEvent {
+                message: "crossref.request".to_string(),
+                elapsed: None,
+                level: ProgressLevel::Request,
+            });
+            let result = resolver.resolve_with_progress(doi, |msg| {
+                sink.event(ProgressEvent {
+                    message: msg.to_string(),
+                    elapsed: None,
+                    level: ProgressLevel::Request,
+                });
+            })?;
+            sink.event(ProgressEvent {
+                message: "phase=Verify; validating identifiers".to_string(),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            result
+        };
+
+        if resolution.resolved_at.is_none() {
+            resolution.resolved_at = Some(iso_timestamp());
+        }
+
+        if !options.dry_run {
+            let dir = self.store.project_doi_dir(doi);
+            std::fs::create_dir_all(dir.as_std_path())
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            sink.event(ProgressEvent {
+                message: "phase=Store; writing provenance".to_string(),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            write_doi_resolution(&resolution_path, &resolution)?;
+            let agency = resolution.agency.unwrap_or(DoiAgency::Crossref);
+            let meta = self.build_metadata(
+                agency.as_str(),
+                "doi",
+                doi.as_str(),
+                None,
+                dir.as_str(),
+                None,
+            );
+            Store::write_metadata(&self.store.project_doi_metadata_path(doi), &meta)?;
+        }
+        Ok(resolution)
+    }
+
+    /// Splits a resolution's targets as `selection` would, with the size of
+    /// each one already stored.
+    pub fn doi_targets(&self, resolution: &DoiResolution, selection: &DoiSelection) -> DoiTargets {
+        let mut targets = DoiTargets::default();
+        let reasons = selection.skip_reasons(&resolution.resolved_targets);
+        for (target, reason) in resolution.resolved_targets.iter().zip(reasons) {
+            let bytes = [
+                self.store
+                    .project_metadata_path(&target.dataset_type, &target.id),
+                self.store
+                    .cache_metadata_path(&target.dataset_type, &target.id),
+            ]
+            .iter()
+            .filter_map(|path| fs::read(path.as_std_path()).ok())
+            .filter_map(|bytes| serde_json::from_slice::<Metadata>(&bytes).ok())
+            .find_map(|meta| {
+                meta.download
+                    .as_ref()
+                    .map(|download| download.bytes_written)
+                    .or_else(|| stored_bytes(&meta))
+            });
+            let entry = DoiTarget {
+                specifier: format!("{}:{}", target.dataset_type, target.id),
+                dataset_type: target.dataset_type.clone(),
+                bytes,
+                reason: reason.map(str::to_string),
+            };
+            if entry.reason.is_some() {
+                targets.skipped.push(entry);
+            } else {
+                targets.selected.push(entry);
+            }
+        }
+        targets
+    }
+
+    fn fetch_doi(
+        &self,
+        doi: Doi,
+        overrides: FetchOverrides,
+        options: FetchOptions,
+        control: Option<&BatchControl>,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchResult, KiraError> {
+        let selection = overrides.doi_selection.clone();
+        // A checklist picks from the resolution `--select` just stored.
+        let resolve_options = FetchOptions {
+            force: options.force && selection.picked.is_none(),
+            ..options.clone()
+        };
+        let resolution =
+            self.resolve_doi(&doi, &resolve_options, overrides.follow_related, sink)?;
+
+        let counts = resolution
+            .extracted
+            .counts()
+            .into_iter()
+            .map(|(id_type, count)| IdCount { id_type, count })
+            .collect::<Vec<_>>();
+        let resolved_specifiers = resolution.resolved_specifiers()?;
+        let targets = self.doi_targets(&resolution, &selection);
+        let reasons = selection.skip_reasons(&resolution.resolved_targets);
+        let mut items = Vec::new();
+
+        sink.event(ProgressEvent {
+            message: format!(
+                "doi.resolved ids={} targets={} selected={}",
+                counts.iter().map(|c| c.count).sum::<usize>(),
+                resolved_specifiers.len(),
+                targets.selected.len()
+            ),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+
+        for ((target, spec), reason) in resolution
+            .resolved_targets
+            .iter()
+            .zip(resolved_specifiers)
+            .zip(reasons)
+        {
+            if reason.is_some() {
+                continue;
+            }
+            let label = spec.to_string();
+            let options = FetchOptions {
+                provenance: Some(resolution.provenance(target)),
+                ..options.clone()
+            };
+            items.push(self.batch_item(control, &options, sink, &label, |sink| {
+                self.fetch_single(spec, overrides.clone(), options.clone(), sink)
+            })?);
+        }
+
+        Ok(FetchResult {
+            items,
+            errors: Vec::new(),
+            summary: Some(FetchSummary {
+                kind: "doi".to_string(),
+                doi: Some(doi.as_str().to_string()),
+                bioproject: None,
+                experiment: None,
+                taxon: None,
+                id_counts: counts,
+                resolved_targets: resolution.resolved_targets.len(),
+                unresolved: resolution.unresolved.len(),
+                selection: None,
+                uniprot: None,
+                structures: None,
+                doi_selection: Some(targets),
+            }),
+            total_duration_ms: 0,
+            timings: PhaseTimings::default(),
+        })
+    }
+
+    fn fetch_structure_for(
+        &self,
+        query: StructureQuery,
+        overrides: FetchOverrides,
+        options: FetchOptions,
+        control: Option<&BatchControl>,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchResult, KiraError> {
+        sink.event(ProgressEvent {
+            message: format!("phase=Resolve; structure-for {query}"),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let accession = query.accession.clone();
+        let label = format!("uniprot:{accession}");
+        let mut items = vec![self.batch_item(control, &options, sink, &label, |sink| {
+            self.fetch_uniprot(accession.clone(), options.clone(), sink)
+        })?];
+
+        let (xrefs, sequence_length) = self.uniprot_pdb_xrefs(&accession)?;
+        sink.event(ProgressEvent {
+            message: format!("phase=Verify; ranking {} PDB structures", xrefs.len()),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let selection = select_structures(
+            &query,
+            &xrefs,
+            sequence_length,
+            overrides.structure_top.unwrap_or(1),
+        )?;
+
+        let chosen = selection
+            .candidates
+            .iter()
+            .filter(|candidate| candidate.selected)
+            .map(|candidate| candidate.id.parse::<ProteinId>())
+            .collect::<Result<Vec<_>, _>>()?;
+        for id in &chosen {
+            let label = format!("protein:{id}");
+            items.push(self.batch_item(control, &options, sink, &label, |sink| {
+                self.fetch_protein(id.clone(), &overrides, options.clone(), sink)
+            })?);
+            if !options.dry_run {
+                record_selection(&self.store.project_protein_dir(id), &selection)?;
+            }
+        }
+
+        Ok(FetchResult {
+            items,
+            errors: Vec::new(),
+            summary: Some(FetchSummary {
+                kind: "structure-for".to_string(),
+                doi: None,
+                bioproject: None,
+                experiment: None,
+                taxon: None,
+                id_counts: vec![IdCount {
+                    id_type: "pdb".to_string(),
+                    count: selection.candidates.len(),
+                }],
+                resolved_targets: chosen.len(),
+                unresolved: 0,
+                selection: Some(selection),
+                uniprot: None,
+                structures: None,
+                doi_selection: None,
+            }),
+            total_duration_ms: 0,
+            timings: PhaseTimings::default(),
+        })
+    }
+
+    /// A UniProt entry and the PDB structures it cross-references, best
+    /// first, at most `max_structures` of them.
+    fn fetch_uniprot_with_structures(
+        &self,
+        id: UniprotId,
+        overrides: &FetchOverrides,
+        options: FetchOptions,
+        control: Option<&BatchControl>,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchResult, KiraError> {
+        let label = format!("uniprot:{id}");
+        let mut items = vec![self.batch_item(control, &options, sink, &label, |sink| {
+            self.fetch_uniprot(id.clone(), options.clone(), sink)
+        })?];
+
+        let (xrefs, sequence_length) = self.uniprot_pdb_xrefs(&id)?;
+        let ranked = rank_pdb_xrefs(&xrefs, sequence_length);
+        let limit = overrides.max_structures.unwrap_or(ranked.len());
+        sink.event(ProgressEvent {
+            message: format!(
+                "phase=Resolve; uniprot {id} lists {} PDB structures, fetching {}",
+                ranked.len(),
+                limit.min(ranked.len())
+            ),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let chosen = ranked
+            .iter()
+            .take(limit)
+            .map(|xref| xref.id.parse::<ProteinId>())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut counts = StructureCounts {
+            listed: ranked.len(),
+            downloaded: 0,
+            cached: 0,
+            skipped: ranked.len() - chosen.len(),
+        };
+        for pdb in &chosen {
+            let label = format!("protein:{pdb}");
+            let item = self.batch_item(control, &options, sink, &label, |sink| {
+                self.fetch_protein(pdb.clone(), overrides, options.clone(), sink)
+            })?;
+            if matches!(item.action.as_str(), "download" | ACTION_REFETCHED_CORRUPT) {
+                counts.downloaded += 1;
+            } else {
+                counts.cached += 1;
+            }
+            items.push(item);
         }
-
-        for entry in cache_metadata {
-            let key = (entry.dataset_type.clone(), entry.id.clone());
-            let value = map.entry(key).or_insert_with(|| ListEntry {
-                dataset_type: entry.dataset_type.clone(),
-                id: entry.id.clone(),
-                format: entry.format.clone(),
-                source: Some(entry.source.clone()),
-                project_path: None,
-                cache_path: None,
-            });
-            value.cache_path = Some(entry.resolved_path.clone());
+        if !options.dry_run && !options.metadata_only {
+            let linked = chosen
+                .iter()
+                .map(|pdb| Value::String(pdb.to_string()))
+                .collect();
+            annotate_metadata(
+                &self.store.project_uniprot_dir(&id),
+                "linked_structures",
+                Value::Array(linked),
+            )?;
         }
 
-        Ok(ListResult {
-            datasets: map.into_values().collect(),
+        Ok(FetchResult {
+            items,
+            errors: Vec::new(),
+            summary: Some(FetchSummary {
+                kind: "uniprot-structures".to_string(),
+                doi: None,
+                bioproject: None,
+                experiment: None,
+                taxon: None,
+                id_counts: vec![IdCount {
+                    id_type: "pdb".to_string(),
+                    count: counts.listed,
+                }],
+                resolved_targets: chosen.len(),
+                unresolved: 0,
+                selection: None,
+                uniprot: Some(id.to_string()),
+                structures: Some(counts),
+                doi_selection: None,
+            }),
+            total_duration_ms: 0,
+            timings: PhaseTimings::default(),
         })
     }
 
-    pub fn info(
+    fn fetch_genome_taxon(
         &self,
-        specifier: DatasetSpecifier,
+        taxon: TaxonQuery,
+        overrides: FetchOverrides,
+        options: FetchOptions,
+        control: Option<&BatchControl>,
         sink: &dyn ProgressSink,
-    ) -> Result<InfoResult, KiraError> {
-        let key = match &specifier {
-            DatasetSpecifier::Protein(id) => ("protein".to_string(), id.as_str().to_string()),
-            DatasetSpecifier::Genome(acc) => ("genome".to_string(), acc.as_str().to_string()),
-            DatasetSpecifier::Srr(id) => ("srr".to_string(), id.as_str().to_string()),
-            DatasetSpecifier::Uniprot(id) => ("uniprot".to_string(), id.as_str().to_string()),
-            DatasetSpecifier::Doi(id) => ("doi".to_string(), id.as_str().to_string()),
-            DatasetSpecifier::Expression(id) => ("expression".to_string(), id.as_str().to_string()),
-            DatasetSpecifier::Expression10x(id) => {
-                ("expression10x".to_string(), id.as_str().to_string())
-            }
-            DatasetSpecifier::Go => ("go".to_string(), "go".to_string()),
-            DatasetSpecifier::Kegg => ("kegg".to_string(), "kegg".to_string()),
-            DatasetSpecifier::Reactome => ("reactome".to_string(), "reactome".to_string()),
+    ) -> Result<FetchResult, KiraError> {
+        sink.event(ProgressEvent {
+            message: format!("phase=Resolve; genome taxon {taxon}"),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let filter = TaxonFilter {
+            level: overrides.assembly_level,
+            reference_only: overrides.reference_only,
+            limit: overrides.limit.unwrap_or(DEFAULT_TAXON_LIMIT),
         };
-
+        let found = self.ncbi.find_taxon_assemblies(&taxon, &filter)?;
+        let accessions = found
+            .assemblies
+            .iter()
+            .take(filter.limit)
+            .map(|assembly| assembly.accession.parse::<GenomeAccession>())
+            .collect::<Result<Vec<_>, _>>()?;
+        if accessions.is_empty() {
+            return Err(KiraError::NoMatchingAssemblies(format!(
+                "genome:taxon:{taxon}"
+            )));
+        }
         sink.event(ProgressEvent {
-            message: format!("phase=Resolve; looking up {}", key.1),
+            message: format!(
+                "phase=Resolve; {} assemblies match taxon {taxon}, fetching {}",
+                found.total_count,
+                accessions.len()
+            ),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
 
-        let project = Store::list_metadata(self.store.project_root())?;
-        let cache = Store::list_metadata(self.store.cache_root())?;
-        let project_meta = project
-            .into_iter()
-            .find(|meta| meta.dataset_type == key.0 && meta.id == key.1);
-        let cache_meta = cache
-            .into_iter()
-            .find(|meta| meta.dataset_type == key.0 && meta.id == key.1);
-
-        if project_meta.is_none() && cache_meta.is_none() {
-            return Err(KiraError::DatasetNotFound(format!("{}:{}", key.0, key.1)));
+        let mut items = Vec::new();
+        for accession in &accessions {
+            let label = format!("genome:{accession}");
+            items.push(self.batch_item(control, &options, sink, &label, |sink| {
+                self.fetch_genome_with_include(
+                    accession.clone(),
+                    overrides.genome_include(),
+                    overrides.allow_missing_artifacts,
+                    options.clone(),
+                    sink,
+                )
+            })?);
         }
 
-        let details = match key.0.as_str() {
-            "uniprot" => load_uniprot_details(project_meta.as_ref(), cache_meta.as_ref()),
-            "doi" => load_doi_details(project_meta.as_ref(), cache_meta.as_ref()),
-            "expression" | "expression10x" => {
-                load_expression_details(project_meta.as_ref(), cache_meta.as_ref())
-            }
-            "go" | "kegg" | "reactome" => {
-                load_kb_details(project_meta.as_ref(), cache_meta.as_ref())
-            }
-            _ => None,
-        };
-
-        Ok(InfoResult {
-            dataset_type: key.0,
-            id: key.1,
-            format: project_meta
-                .as_ref()
-                .and_then(|meta| meta.format.clone())
-                .or_else(|| cache_meta.as_ref().and_then(|meta| meta.format.clone())),
-            source: project_meta
-                .as_ref()
-                .map(|meta| meta.source.clone())
-                .or_else(|| cache_meta.as_ref().map(|meta| meta.source.clone())),
-            project_path: project_meta.map(|meta| meta.resolved_path),
-            cache_path: cache_meta.map(|meta| meta.resolved_path),
-            details,
+        Ok(FetchResult {
+            items,
+            errors: Vec::new(),
+            summary: Some(FetchSummary {
+                kind: "genome-taxon".to_string(),
+                doi: None,
+                bioproject: None,
+                experiment: None,
+                taxon: Some(taxon.to_string()),
+                id_counts: vec![IdCount {
+                    id_type: "assembly".to_string(),
+                    count: found.total_count,
+                }],
+                resolved_targets: accessions.len(),
+                unresolved: 0,
+                selection: None,
+                uniprot: None,
+                structures: None,
+                doi_selection: None,
+            }),
+            total_duration_ms: 0,
+            timings: PhaseTimings::default(),
         })
     }
 
-    pub fn clear(&self, sink: &dyn ProgressSink) -> Result<ClearResult, KiraError> {
-        sink.event(ProgressEvent {
-            message: "phase=Store; clearing project store".to_string(),
-            elapsed: None,
-        });
-        self.store.clear_project()?;
-        Ok(ClearResult { cleared: true })
+    /// Typed PDB cross-references and sequence length of a UniProt entry,
+    /// from the stored copy when it has them, otherwise from UniProt.
+    fn uniprot_pdb_xrefs(&self, id: &UniprotId) -> Result<(Vec<PdbXref>, Option<u64>), KiraError> {
+        for dir in [
+            self.store.project_uniprot_dir(id),
+            self.store.cache_uniprot_dir(id),
+        ] {
+            let Ok(content) = fs::read(dir.join("metadata.json").as_std_path()) else {
+                continue;
+            };
+            let Ok(metadata) = serde_json::from_slice::<Value>(&content) else {
+                continue;
+            };
+            if let Some(xrefs) = pdb_xrefs(&metadata) {
+                let sequence_length = metadata.get("sequence_length").and_then(Value::as_u64);
+                return Ok((xrefs, sequence_length));
+            }
+        }
+        let record = self.uniprot.fetch(id)?;
+        Ok((
+            record.metadata.cross_references.pdb,
+            record.metadata.sequence_length,
+        ))
     }
 
-    pub fn init_config(&self, sink: &dyn ProgressSink) -> Result<InitResult, KiraError> {
+    fn batch_item(
+        &self,
+        control: Option<&BatchControl>,
+        options: &FetchOptions,
+        sink: &dyn ProgressSink,
+        label: &str,
+        fetch: impl FnOnce(&dyn ProgressSink) -> Result<FetchItemResult, KiraError>,
+    ) -> Result<FetchItemResult, KiraError> {
+        if let Some(control) = control {
+            control.checkpoint(sink)?;
+        }
+        check_cancelled(sink)?;
+        self.audit_replacement(label, options)?;
+        // Registry calls made for the item nest under this span.
+        let _span = tracing::info_span!("fetch.item", dataset = label).entered();
         sink.event(ProgressEvent {
-            message: "phase=Resolve; scanning project store".to_string(),
+            message: format!("item.begin {label}"),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
-
-        let metadata = Store::list_metadata(self.store.project_root())?;
-        let mut proteins = Vec::new();
-        let mut genomes = Vec::new();
-        let mut srr = Vec::new();
-        let mut uniprot = Vec::new();
-        let mut doi = Vec::new();
-
-        for entry in metadata {
-            match entry.dataset_type.as_str() {
-                "protein" => {
-                    let format = entry.format.as_deref().and_then(parse_protein_format);
-                    if matches!(format, Some(ProteinFormat::Cif) | None) {
-                        proteins.push(ProteinEntry::Shorthand(entry.id.clone()));
-                    } else if let Some(format) = format {
-                        proteins.push(ProteinEntry::Detailed(crate::config::ProteinEntryObject {
-                            id: entry.id.clone(),
-                            format: Some(format),
-                        }));
-                    } else {
-                        proteins.push(ProteinEntry::Shorthand(entry.id.clone()));
-                    }
-                }
-                "genome" => {
-                    genomes.push(GenomeEntry::Shorthand(entry.id.clone()));
-                }
-                "srr" => {
-                    let (format, paired) = load_srr_settings(&entry.resolved_path);
-                    if matches!(format, Some(SrrFormat::Fastq) | None) && !paired.unwrap_or(false) {
-                        srr.push(SrrEntry::Shorthand(entry.id.clone()));
-                    } else {
-                        srr.push(SrrEntry::Detailed(crate::config::SrrEntryObject {
-                            id: entry.id.clone(),
-                            format,
-                            paired,
-                        }));
-                    }
-                }
-                "uniprot" => {
-                    uniprot.push(UniprotEntry::Shorthand(entry.id.clone()));
-                }
-                "doi" => {
-                    doi.push(DoiEntry::Shorthand(entry.id.clone()));
+        let started = std::time::Instant::now();
+        let (item, timings) = timed_in_context(label, sink, fetch);
+        let mut item = match item {
+            Ok(item) => FetchItemResult {
+                timings,
+                cache: Some(
+                    self.cache_policy
+                        .decision(&item.dataset_type, options.no_cache),
+                ),
+                ..item
+            },
+            Err(err) => {
+                let phase = err.context().map_or("Resolve", |(_, phase)| phase);
+                sink.event(ProgressEvent {
+                    message: format!("item.failed {label} [{phase}] {}", err.root()),
+                    elapsed: Some(started.elapsed()),
+                    level: ProgressLevel::Phase,
+                });
+                // A nested item that failed has recorded itself already.
+                if err
+                    .context()
+                    .is_some_and(|(specifier, _)| specifier == label)
+                {
+                    self.record_history(
+                        options,
+                        HistoryRecord::failed(label, phase, &err.root().to_string()),
+                    );
                 }
-                _ => {}
+                return Err(err);
             }
-        }
-
-        let config = Config {
-            schema_version: Some(1),
-            proteins,
-            genomes,
-            srr,
-            uniprot,
-            doi,
         };
-
         sink.event(ProgressEvent {
-            message: "phase=Store; writing kira-bm.json".to_string(),
-            elapsed: None,
+            message: format!("item.end {label}"),
+            elapsed: Some(started.elapsed()),
+            level: ProgressLevel::Phase,
         });
+        if item.action == "cache" {
+            tracing::info!(
+                dataset_type = %item.dataset_type,
+                id = %item.id,
+                cache_path = item.cache_path.as_deref(),
+                "cache hit"
+            );
+        }
+        let bytes = item
+            .project_path
+            .as_ref()
+            .or(item.cache_path.as_ref())
+            .and_then(|path| path_size(Path::new(path)))
+            .unwrap_or(0);
+        self.record_history(options, HistoryRecord::fetched(label, &item.action, bytes));
+        if !options.dry_run {
+            self.record_savings(&mut item, started.elapsed(), sink);
+            self.record_in_index(&item, sink);
+            if options.write_readme {
+                self.write_readme(&item, sink);
+            }
+        }
+        sink.item_completed(&item);
+        if let Some(control) = control {
+            control.item_done()?;
+        }
+        Ok(item)
+    }
 
-        let path = std::env::current_dir()
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?
-            .join("kira-bm.json");
-        write_config_atomic(&path, &config)?;
+    /// Stamps a download's duration on its records, or adds what a cache hit
+    /// saved to the cache's totals. Best effort, like history.
+    fn record_savings(
+        &self,
+        item: &mut FetchItemResult,
+        elapsed: Duration,
+        sink: &dyn ProgressSink,
+    ) {
+        match (item.action.as_str(), item.download.as_mut()) {
+            ("download" | ACTION_REFETCHED_CORRUPT, Some(download)) => {
+                let duration_ms = elapsed.as_millis() as u64;
+                download.duration_ms = Some(duration_ms);
+                let _ = self.store.record_download_duration(
+                    &item.dataset_type,
+                    &item.id,
+                    duration_ms,
+                    DEFAULT_LOCK_TIMEOUT,
+                    sink,
+                );
+            }
+            ("cache", Some(download)) => {
+                let savings = CacheSavings::of(download, elapsed);
+                item.cache_savings = Some(savings);
+                let _ = self
+                    .store
+                    .lock_dataset("stats", "stats", DEFAULT_LOCK_TIMEOUT, sink)
+                    .and_then(|_lock| stats::record(self.store.cache_root(), &savings));
+            }
+            _ => {}
+        }
+    }
 
-        Ok(InitResult {
-            path: path.to_string_lossy().to_string(),
-            written: true,
-            counts: InitCounts {
-                proteins: config.proteins.len(),
-                genomes: config.genomes.len(),
-                srr: config.srr.len(),
-                uniprot: config.uniprot.len(),
-                doi: config.doi.len(),
-            },
-        })
+    /// Notes a dataset written to or copied from the cache in the cache
+    /// index, with this project among its holders when it has a copy.
+    /// Best effort: `cache reindex` repairs what a failed update missed.
+    fn record_in_index(&self, item: &FetchItemResult, sink: &dyn ProgressSink) {
+        let Some(cache_path) = item
+            .cache_path
+            .as_deref()
+            .map(Path::new)
+            .filter(|path| path.exists())
+        else {
+            return;
+        };
+        let mut entry = IndexEntry::new(
+            &item.dataset_type,
+            &item.id,
+            cache_path,
+            item.download.as_ref(),
+        );
+        if item.project_path.is_some() {
+            entry.projects.insert(self.store.project_root().to_string());
+        }
+        if let Err(err) = index::update(&self.store, DEFAULT_LOCK_TIMEOUT, sink, |index| {
+            index.upsert(entry)
+        }) {
+            tracing::warn!("cache index not updated: {err}");
+        }
     }
 
-    fn fetch_single(
+    /// Writes the README of a fetched project dataset. A failure to write it
+    /// is reported without failing the fetch.
+    fn write_readme(&self, item: &FetchItemResult, sink: &dyn ProgressSink) {
+        let Some(path) = item.project_path.as_deref().map(Path::new) else {
+            return;
+        };
+        let dir = if path.is_file() {
+            path.parent().unwrap_or(path)
+        } else {
+            path
+        };
+        let record = self.store.read_project_record(&item.dataset_type, &item.id);
+        let (Some(record), true) = (record, dir.is_dir()) else {
+            return;
+        };
+        // An imported dataset linked in place points at the user's files.
+        if fs::symlink_metadata(dir).is_ok_and(|meta| meta.file_type().is_symlink()) {
+            return;
+        }
+        match readme::write(dir, &record, &self.store) {
+            Ok(true) => {}
+            Ok(false) => sink.event(ProgressEvent {
+                message: format!("readme.kept {}", dir.display()),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            }),
+            Err(err) => tracing::warn!("README not written: {err}"),
+        }
+    }
+
+    /// Records a config entry's alias, or its lack of one, on the project
+    /// copy of the dataset it fetched.
+    fn apply_alias(
         &self,
-        specifier: DatasetSpecifier,
-        overrides: FetchOverrides,
-        options: FetchOptions,
+        dataset_type: &str,
+        id: &str,
+        alias: Option<&DatasetAlias>,
+        options: &FetchOptions,
         sink: &dyn ProgressSink,
-    ) -> Result<FetchItemResult, KiraError> {
+    ) -> Result<(), KiraError> {
+        if options.dry_run {
+            return Ok(());
+        }
+        self.store.set_alias(
+            dataset_type,
+            id,
+            alias.map(DatasetAlias::as_str),
+            options
+                .lock_timeout_secs
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_LOCK_TIMEOUT),
+            sink,
+        )
+    }
+
+    /// History is best effort: a fetch that worked is not failed for it.
+    fn record_history(&self, options: &FetchOptions, record: HistoryRecord) {
         if !options.dry_run {
-            self.store.ensure_project_root()?;
-            self.store.ensure_cache_root()?;
+            let _ = history::append(self.store.project_root(), &record);
         }
+    }
 
-        let registry = specifier.resolve_registry(overrides.protein_format);
-        match (specifier, registry) {
-            (DatasetSpecifier::Protein(id), Registry::Rcsb) => {
-                self.fetch_protein(id, overrides.protein_format, options, sink)
-            }
-            (DatasetSpecifier::Protein(id), Registry::Ncbi) => {
-                self.fetch_protein(id, overrides.protein_format, options, sink)
-            }
-            (DatasetSpecifier::Genome(acc), Registry::Ncbi) => self.fetch_genome_with_include(
-                acc,
-                crate::config::default_genome_include(),
-                options,
-                sink,
-            ),
-            (DatasetSpecifier::Genome(acc), Registry::Rcsb) => self.fetch_genome_with_include(
-                acc,
-                crate::config::default_genome_include(),
-                options,
-                sink,
-            ),
-            (DatasetSpecifier::Srr(id), Registry::Ncbi) => self.fetch_srr(
-                id,
-                overrides.srr_format.unwrap_or(SrrFormat::Fastq),
-                overrides.srr_paired.unwrap_or(false),
-                options,
-                sink,
-            ),
-            (DatasetSpecifier::Uniprot(id), Registry::Uniprot) => {
-                self.fetch_uniprot(id, options, sink)
-            }
-            (DatasetSpecifier::Doi(_), Registry::Doi) => Err(KiraError::DoiResolution(
-                "doi resolution must be invoked from the top-level fetch".to_string(),
-            )),
-            (DatasetSpecifier::Expression(acc), Registry::Geo) => {
-                self.fetch_expression(acc, options, sink)
-            }
-            (DatasetSpecifier::Expression10x(acc), Registry::Geo) => {
-                self.fetch_expression10x(acc, options, sink)
+    /// Logs the stored copies a forced fetch of `label` is about to replace.
+    fn audit_replacement(&self, label: &str, options: &FetchOptions) -> Result<(), KiraError> {
+        if !options.force || options.dry_run {
+            return Ok(());
+        }
+        for root in [self.store.project_root(), self.store.cache_root()] {
+            let targets = Store::list_metadata(root)?
+                .iter()
+                .filter(|entry| audit::specifier_for(entry) == label)
+                .map(|entry| AuditTarget::from_metadata(entry, None))
+                .collect::<Vec<_>>();
+            if !targets.is_empty() {
+                audit::append(
+                    root,
+                    &AuditRecord::new("force-replace", targets, options.flags()),
+                )?;
             }
-            (DatasetSpecifier::Go, Registry::Go) => self.fetch_go(options, sink),
-            (DatasetSpecifier::Kegg, Registry::Kegg) => self.fetch_kegg(options, sink),
-            (DatasetSpecifier::Reactome, Registry::Reactome) => self.fetch_reactome(options, sink),
-            _ => Err(KiraError::InvalidFormat(
-                "unsupported registry for dataset type".to_string(),
-            )),
         }
+        Ok(())
     }
 
-    fn fetch_doi(
+    fn fetch_bioproject(
         &self,
-        doi: Doi,
+        accession: BioProjectAccession,
         overrides: FetchOverrides,
         options: FetchOptions,
+        control: Option<&BatchControl>,
         sink: &dyn ProgressSink,
     ) -> Result<FetchResult, KiraError> {
         sink.event(ProgressEvent {
-            message: format!("phase=Resolve; doi {}", doi.as_str()),
+            message: format!("phase=Resolve; bioproject {}", accession.as_str()),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
-
         if !options.dry_run {
             self.store.ensure_project_root()?;
         }
 
-        let resolver = DoiResolver::new()?;
-        let resolution_path = self.store.project_doi_resolution_path(&doi);
-        let resolution = if !options.force && resolution_path.as_std_path().exists() {
-            read_doi_resolution(&resolution_path)?
+        let source = if accession.is_ena() { "ena" } else { "ncbi" };
+        let manifest_path = self.store.project_bioproject_manifest_path(&accession);
+        let hydrated = if !options.force && manifest_path.as_std_path().exists() {
+            let manifest = read_bioproject_manifest(&manifest_path)?;
+            HydratedBioProject {
+                bioproject: manifest.bioproject,
+                srr: manifest.srr,
+                assemblies: manifest.assemblies,
+            }
         } else {
             sink.event(ProgressEvent {
-                message: "phase=Prepare; preparing DOI resolution".to_string(),
-                elapsed: None,
-            });
-            sink.event(ProgressEvent {
-                message: "phase=Fetch; resolving Crossref metadata".to_string(),
-                elapsed: None,
-            });
-            sink.event(ProgressEvent {
-                message: "crossref.request".to_string(),
+                message: "phase=Fetch; hydrating bioproject members".to_string(),
                 elapsed: None,
+                level: ProgressLevel::Phase,
             });
-            let result = resolver.resolve_with_progress(&doi, |msg| {
+            let resolver = self.doi_resolver()?;
+            resolver.hydrate_project(&accession, |msg| {
                 sink.event(ProgressEvent {
                     message: msg.to_string(),
                     elapsed: None,
+                    level: ProgressLevel::Request,
                 });
-            })?;
+            })?
+        };
+
+        sink.event(ProgressEvent {
+            message: format!(
+                "bioproject.members srr={} assemblies={}",
+                hydrated.srr.len(),
+                hydrated.assemblies.len()
+            ),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+
+        let mut items = Vec::new();
+        for run in &hydrated.srr {
+            let Ok(id) = run.parse::<SrrId>() else {
+                sink.event(ProgressEvent {
+                    message: format!("bioproject.skip {run}: unsupported run accession"),
+                    elapsed: None,
+                    level: ProgressLevel::Phase,
+                });
+                continue;
+            };
+            let label = format!("srr:{}", id.as_str());
+            items.push(self.batch_item(control, &options, sink, &label, |sink| {
+                self.fetch_srr(
+                    id,
+                    overrides.srr_format.unwrap_or(SrrFormat::Fastq),
+                    overrides.srr_paired.unwrap_or(false),
+                    &overrides.srr_download_options(),
+                    options.clone(),
+                    sink,
+                )
+            })?);
+        }
+        for acc in &hydrated.assemblies {
+            let acc: GenomeAccession = acc.parse()?;
+            let label = format!("genome:{}", acc.as_str());
+            items.push(self.batch_item(control, &options, sink, &label, |sink| {
+                self.fetch_genome_with_include(
+                    acc,
+                    overrides.genome_include(),
+                    overrides.allow_missing_artifacts,
+                    options.clone(),
+                    sink,
+                )
+            })?);
+        }
+
+        if !options.dry_run {
+            let dir = self.store.project_bioproject_dir(&accession);
+            fs::create_dir_all(dir.as_std_path())
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
             sink.event(ProgressEvent {
-                message: "phase=Verify; validating identifiers".to_string(),
+                message: "phase=Store; writing bioproject manifest".to_string(),
                 elapsed: None,
+                level: ProgressLevel::Phase,
             });
-            result
-        };
+            let manifest = BioProjectManifest {
+                bioproject: accession.as_str().to_string(),
+                source: source.to_string(),
+                srr: hydrated.srr.clone(),
+                assemblies: hydrated.assemblies.clone(),
+                members: items
+                    .iter()
+                    .map(|item| BioProjectMember {
+                        dataset_type: item.dataset_type.clone(),
+                        id: item.id.clone(),
+                        project_path: item.project_path.clone(),
+                        cache_path: item.cache_path.clone(),
+                    })
+                    .collect(),
+            };
+            write_bioproject_manifest(&manifest_path, &manifest)?;
+            let meta = self.build_metadata(
+                source,
+                "bioproject",
+                accession.as_str(),
+                None,
+                dir.as_str(),
+                None,
+            );
+            Store::write_metadata(
+                &self
+                    .store
+                    .project_metadata_path("bioproject", accession.as_str()),
+                &meta,
+            )?;
+        }
 
-        if !options.dry_run {
-            let dir = self.store.project_doi_dir(&doi);
-            std::fs::create_dir_all(dir.as_std_path())
-                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let resolved_targets = items.len();
+        Ok(FetchResult {
+            items,
+            errors: Vec::new(),
+            summary: Some(FetchSummary {
+                kind: "bioproject".to_string(),
+                doi: None,
+                bioproject: Some(accession.as_str().to_string()),
+                experiment: None,
+                taxon: None,
+                id_counts: vec![
+                    IdCount {
+                        id_type: "srr".to_string(),
+                        count: hydrated.srr.len(),
+                    },
+                    IdCount {
+                        id_type: "assembly".to_string(),
+                        count: hydrated.assemblies.len(),
+                    },
+                ],
+                resolved_targets,
+                unresolved: hydrated.srr.len() + hydrated.assemblies.len() - resolved_targets,
+                selection: None,
+                uniprot: None,
+                structures: None,
+                doi_selection: None,
+            }),
+            total_duration_ms: 0,
+            timings: PhaseTimings::default(),
+        })
+    }
+
+    /// Resolves an SRA experiment to its runs and fetches each as an `srr`
+    /// dataset, recording the experiment in the runs' project metadata.
+    fn fetch_srr_experiment(
+        &self,
+        accession: SraExperimentAccession,
+        overrides: FetchOverrides,
+        options: FetchOptions,
+        control: Option<&BatchControl>,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchResult, KiraError> {
+        sink.event(ProgressEvent {
+            message: format!("phase=Resolve; srx {}", accession.as_str()),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let runs = self.doi_resolver()?.experiment_runs(&accession, |msg| {
             sink.event(ProgressEvent {
-                message: "phase=Store; writing provenance".to_string(),
+                message: msg.to_string(),
                 elapsed: None,
+                level: ProgressLevel::Request,
             });
-            write_doi_resolution(&resolution_path, &resolution)?;
-            let meta = self.build_metadata("crossref", "doi", doi.as_str(), None, dir.as_str());
-            Store::write_metadata(&self.store.project_doi_metadata_path(&doi), &meta)?;
-        }
-
-        let counts = resolution
-            .extracted
-            .counts()
-            .into_iter()
-            .map(|(id_type, count)| IdCount { id_type, count })
-            .collect::<Vec<_>>();
-        let resolved_specifiers = resolution.resolved_specifiers()?;
-        let mut items = Vec::new();
-
+        })?;
         sink.event(ProgressEvent {
-            message: format!(
-                "doi.resolved ids={} targets={}",
-                counts.iter().map(|c| c.count).sum::<usize>(),
-                resolved_specifiers.len()
-            ),
+            message: format!("srx.runs {} {}", accession.as_str(), runs.join(",")),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
 
-        for spec in resolved_specifiers {
-            items.push(self.fetch_single(spec, overrides.clone(), options.clone(), sink)?);
+        let mut items = Vec::new();
+        for run in &runs {
+            let Ok(id) = run.parse::<SrrId>() else {
+                continue;
+            };
+            let label = format!("srr:{}", id.as_str());
+            let item = self.batch_item(control, &options, sink, &label, |sink| {
+                self.fetch_srr(
+                    id.clone(),
+                    overrides.srr_format.unwrap_or(SrrFormat::Fastq),
+                    overrides.srr_paired.unwrap_or(false),
+                    &overrides.srr_download_options(),
+                    options.clone(),
+                    sink,
+                )
+            })?;
+            if !options.dry_run {
+                self.store.record_experiment(&id, &accession)?;
+            }
+            items.push(item);
         }
 
+        let resolved_targets = items.len();
         Ok(FetchResult {
             items,
+            errors: Vec::new(),
             summary: Some(FetchSummary {
-                kind: "doi".to_string(),
-                doi: Some(doi.as_str().to_string()),
-                id_counts: counts,
-                resolved_targets: resolution.resolved_targets.len(),
-                unresolved: resolution.unresolved.len(),
+                kind: "srx".to_string(),
+                doi: None,
+                bioproject: None,
+                experiment: Some(accession.as_str().to_string()),
+                taxon: None,
+                id_counts: vec![IdCount {
+                    id_type: "srr".to_string(),
+                    count: runs.len(),
+                }],
+                resolved_targets,
+                unresolved: runs.len() - resolved_targets,
+                selection: None,
+                uniprot: None,
+                structures: None,
+                doi_selection: None,
             }),
+            total_duration_ms: 0,
+            timings: PhaseTimings::default(),
         })
     }
 
     fn fetch_expression(
         &self,
         accession: GeoSeriesAccession,
+        overrides: &FetchOverrides,
         options: FetchOptions,
         sink: &dyn ProgressSink,
     ) -> Result<FetchItemResult, KiraError> {
+        let options = self.cache_options("expression", options);
         sink.event(ProgressEvent {
             message: format!("phase=Resolve; expression {}", accession.as_str()),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
         if !options.dry_run {
             self.store.ensure_project_root()?;
             self.store.ensure_cache_root()?;
         }
+        let _lock = self.lock_dataset("expression", accession.as_str(), &options, sink)?;
 
         let project_dir = self.store.project_expression_dir(&accession);
         let cache_dir = self.store.cache_expression_dir(&accession);
 
-        if !options.force && self.store.project_exists(&project_dir) {
+        if options.metadata_only {
+            return self.fetch_expression_metadata(&accession, &project_dir, &options, sink);
+        }
+
+        // Sample fetches may nest under expression/<GSE>/, so only a series
+        // metadata directory marks the series itself as present.
+        let has_metadata = self
+            .store
+            .is_metadata_only("expression", accession.as_str());
+        if !options.force
+            && self.store.project_exists(&project_dir.join("metadata"))
+            && !has_metadata
+        {
             return Ok(FetchItemResult {
                 dataset_type: "expression".to_string(),
                 id: accession.as_str().to_string(),
@@ -618,19 +4359,37 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                     .as_std_path()
                     .exists()
                     .then(|| cache_dir.to_string()),
+                download: Store::recorded_download(
+                    &self
+                        .store
+                        .project_metadata_path("expression", accession.as_str()),
+                ),
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
-        if !options.force && self.store.cache_exists(&cache_dir) {
+        let corrupt = self.evict_corrupt_cache("expression", accession.as_str(), &options, sink)?;
+        if !options.force && self.store.cache_exists(&cache_dir.join("metadata")) {
+            let download = Store::recorded_download(
+                &self
+                    .store
+                    .cache_metadata_path("expression", accession.as_str()),
+            );
             if !options.dry_run {
-                Store::copy_dir_atomic(&cache_dir, &project_dir)?;
-                let meta = self.build_metadata(
+                let mut meta = self.project_metadata(
                     "geo",
                     "expression",
                     accession.as_str(),
                     None,
                     project_dir.as_str(),
+                    &options,
                 );
+                meta.download = download.clone();
+                let carried = read_carried(&project_dir, &[GEO_SAMPLES]);
+                Store::materialize_dir(&cache_dir, &project_dir, &meta.downloaded_at)?;
+                write_carried(&project_dir, &carried)?;
                 Store::write_metadata(
                     &self
                         .store
@@ -646,17 +4405,40 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 action: "cache".to_string(),
                 project_path: Some(project_dir.to_string()),
                 cache_path: Some(cache_dir.to_string()),
+                download,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
-        let soft_text = self.geo.fetch_soft_text(&accession)?;
-        let urls = extract_supplementary_urls(&soft_text);
+        // A metadata-only copy already holds the series SOFT text.
+        let stored_soft = has_metadata
+            .then(|| fs::read_to_string(project_dir.join(GEO_SOFT).as_std_path()).ok())
+            .flatten();
+        let soft_text = match stored_soft {
+            Some(text) => text,
+            None => self.geo.fetch_soft_text(&accession)?,
+        };
+        let soft = SoftRecord::parse(&soft_text);
+        let relations = soft.relations.clone();
+        let mut urls = soft.supplementary_urls.clone();
+        let mut source = None;
         if urls.is_empty() {
-            return Err(KiraError::GeoResolution(
-                "GEO series contains no supplementary files".to_string(),
-            ));
+            let (related, related_soft, related_urls) = self.related_series_files(
+                &accession,
+                &soft_text,
+                &relations,
+                overrides.follow_superseries,
+                sink,
+            )?;
+            urls = related_urls;
+            source = Some((related, related_soft));
         }
 
+        let (urls, file_categories, skipped) =
+            self.select_supplementary(urls, overrides, &options, sink);
+
         if options.dry_run {
             return Ok(FetchItemResult {
                 dataset_type: "expression".to_string(),
@@ -666,6 +4448,10 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 action: "dry-run".to_string(),
                 project_path: Some(project_dir.to_string()),
                 cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+                download: None,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
@@ -677,10 +4463,14 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
         let parent = target_dir
             .parent()
             .ok_or_else(|| KiraError::Filesystem("invalid target dir".to_string()))?;
-        let temp_dir = tempfile::Builder::new()
-            .prefix("kira-bm-geo")
-            .tempdir_in(parent.as_std_path())
+        fs::create_dir_all(parent.as_std_path())
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let temp_dir = StagingDir::create_in(
+            parent.as_std_path(),
+            "kira-bm-geo",
+            target_dir.file_name().unwrap_or_default(),
+        )
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
         let temp_path = Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf())
             .map_err(|_| KiraError::Filesystem("invalid temp dir".to_string()))?;
 
@@ -692,8 +4482,23 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
             soft_text.as_bytes(),
         )
         .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        if let Some((related, related_soft)) = &source {
+            fs::write(
+                metadata_dir
+                    .join(format!("geo_soft_{}.txt", related.as_str()))
+                    .as_std_path(),
+                related_soft.as_bytes(),
+            )
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        }
 
+        sink.event(ProgressEvent {
+            message: "phase=Fetch; downloading supplementary files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
         let mut files = Vec::new();
+        let mut digests = Digests::new();
         for url in &urls {
             let rel = geo_relative_path(url);
             let dest = temp_path.join(&rel);
@@ -701,7 +4506,8 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 fs::create_dir_all(parent.as_std_path())
                     .map_err(|err| KiraError::Filesystem(err.to_string()))?;
             }
-            self.geo.download_url(url, dest.as_std_path())?;
+            let digest = self.geo.download_url(url, dest.as_std_path(), sink)?;
+            digests.insert(rel, digest);
             if let Some(name) = dest.file_name() {
                 files.push(name.to_string());
             }
@@ -711,32 +4517,63 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
             registry: "geo".to_string(),
             dataset_type: "expression".to_string(),
             accession: accession.as_str().to_string(),
-            organism: extract_organism(&soft_text),
+            n_samples: Some(soft.sample_count()),
+            title: soft.series_title,
+            summary: soft.summary,
+            platforms: soft.platforms,
+            samples: soft.samples,
+            organism: soft.organism,
             bundle_format: None,
             n_bundles: None,
             files: files.clone(),
+            relations,
+            source_series: source.map(|(related, _)| related.as_str().to_string()),
+            series: None,
+            file_categories,
+            skipped,
+            bundles: Vec::new(),
+            dimensions: Vec::new(),
+            skipped_bundles: Vec::new(),
+            unpacked_archives: Vec::new(),
+            notes: Vec::new(),
+            warnings: Vec::new(),
             downloaded_at: iso_timestamp(),
         };
         let meta_path = metadata_dir.join("metadata.json");
-        let meta_bytes = serde_json::to_vec_pretty(&meta)
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let meta_bytes = to_canonical_json(&meta)?;
         fs::write(meta_path.as_std_path(), meta_bytes)
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
 
-        atomic_rename_dir(temp_path.as_std_path(), target_dir.as_std_path())
+        sink.event(ProgressEvent {
+            message: "phase=Store; writing files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let carried = read_carried(&project_dir, &[GEO_SAMPLES]);
+        temp_dir
+            .persist(target_dir.as_std_path())
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
 
-        if !options.no_cache {
-            Store::copy_dir_atomic(&cache_dir, &project_dir)?;
-        }
-
-        let project_meta = self.build_metadata(
+        let download = geo_download(target_dir, &urls, &digests)?;
+        manifest::write(
+            "expression",
+            accession.as_str(),
+            target_dir.as_std_path(),
+            &download,
+        )?;
+        let mut project_meta = self.project_metadata(
             "geo",
             "expression",
             accession.as_str(),
             None,
             project_dir.as_str(),
+            &options,
         );
+        project_meta.download = Some(download.clone());
+        if !options.no_cache {
+            Store::materialize_dir(&cache_dir, &project_dir, &project_meta.downloaded_at)?;
+        }
+        write_carried(&project_dir, &carried)?;
         Store::write_metadata(
             &self
                 .store
@@ -745,14 +4582,16 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
         )?;
 
         if !options.no_cache {
-            let cache_meta = self.build_metadata(
+            let mut cache_meta = self.build_metadata(
                 "geo",
                 "expression",
                 accession.as_str(),
                 None,
                 cache_dir.as_str(),
+                None,
             );
-            Store::write_metadata(
+            cache_meta.download = Some(download.clone());
+            self.store.write_cache_metadata(
                 &self
                     .store
                     .cache_metadata_path("expression", accession.as_str()),
@@ -765,26 +4604,460 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
             id: accession.as_str().to_string(),
             format: None,
             source: "geo".to_string(),
-            action: "download".to_string(),
+            action: if corrupt {
+                ACTION_REFETCHED_CORRUPT
+            } else {
+                "download"
+            }
+            .to_string(),
+            project_path: Some(project_dir.to_string()),
+            cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+            download: Some(download),
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
+        })
+    }
+
+    /// The series SOFT text and its sample list, without supplementary files.
+    fn fetch_expression_metadata(
+        &self,
+        accession: &GeoSeriesAccession,
+        project_dir: &Utf8Path,
+        options: &FetchOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchItemResult, KiraError> {
+        let result = |action: &str| FetchItemResult {
+            dataset_type: "expression".to_string(),
+            id: accession.as_str().to_string(),
+            format: None,
+            source: "geo".to_string(),
+            action: action.to_string(),
+            project_path: Some(project_dir.to_string()),
+            cache_path: None,
+            download: None,
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
+        };
+        if !options.force && self.has_project_record("expression", accession.as_str()) {
+            return Ok(result("project"));
+        }
+        if options.dry_run {
+            return Ok(result("dry-run"));
+        }
+        sink.event(ProgressEvent {
+            message: "phase=Fetch; downloading series SOFT".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let soft_text = self.geo.fetch_soft_text(accession)?;
+        check_cancelled(sink)?;
+        sink.event(ProgressEvent {
+            message: "phase=Store; writing files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        // Written file by file: sample fetches may already nest under the
+        // series directory.
+        Store::write_bytes_atomic(&project_dir.join(GEO_SOFT), soft_text.as_bytes())?;
+        Store::write_bytes_atomic(
+            &project_dir.join(GEO_SAMPLES),
+            &to_canonical_json(&extract_series_samples(&soft_text))?,
+        )?;
+        self.write_metadata_only_record(
+            "geo",
+            "expression",
+            accession.as_str(),
+            project_dir,
+            options,
+        )?;
+        Ok(result("metadata"))
+    }
+
+    fn fetch_expression_sample(
+        &self,
+        accession: GeoSampleAccession,
+        overrides: &FetchOverrides,
+        options: FetchOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchItemResult, KiraError> {
+        let options = self.cache_options("expressionsample", options);
+        sink.event(ProgressEvent {
+            message: format!("phase=Resolve; expressionsample {}", accession.as_str()),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        if !options.dry_run {
+            self.store.ensure_project_root()?;
+            self.store.ensure_cache_root()?;
+        }
+        let _lock = self.lock_dataset("expressionsample", accession.as_str(), &options, sink)?;
+
+        let id = accession.as_str();
+        let stored_cache =
+            stored_dataset_dir(&self.store.cache_metadata_path("expressionsample", id));
+        if !options.force
+            && let Some(project_dir) =
+                stored_dataset_dir(&self.store.project_metadata_path("expressionsample", id))
+        {
+            return Ok(FetchItemResult {
+                dataset_type: "expressionsample".to_string(),
+                id: id.to_string(),
+                format: None,
+                source: "geo".to_string(),
+                action: "project".to_string(),
+                project_path: Some(project_dir.to_string()),
+                cache_path: stored_cache.map(|dir| dir.to_string()),
+                download: Store::recorded_download(
+                    &self.store.project_metadata_path("expressionsample", id),
+                ),
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
+            });
+        }
+
+        let corrupt = self.evict_corrupt_cache("expressionsample", id, &options, sink)?;
+        if !options.force
+            && let Some(cache_dir) = stored_cache
+            && let Ok(relative) = cache_dir.strip_prefix(self.store.cache_root())
+        {
+            let project_dir = self.store.project_root().join(relative);
+            let download =
+                Store::recorded_download(&self.store.cache_metadata_path("expressionsample", id));
+            if !options.dry_run {
+                let mut meta = self.project_metadata(
+                    "geo",
+                    "expressionsample",
+                    id,
+                    None,
+                    project_dir.as_str(),
+                    &options,
+                );
+                meta.download = download.clone();
+                Store::materialize_dir(&cache_dir, &project_dir, &meta.downloaded_at)?;
+                Store::write_metadata(
+                    &self.store.project_metadata_path("expressionsample", id),
+                    &meta,
+                )?;
+            }
+            return Ok(FetchItemResult {
+                dataset_type: "expressionsample".to_string(),
+                id: id.to_string(),
+                format: None,
+                source: "geo".to_string(),
+                action: "cache".to_string(),
+                project_path: Some(project_dir.to_string()),
+                cache_path: Some(cache_dir.to_string()),
+                download,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
+            });
+        }
+
+        let soft_text = self.geo.fetch_sample_soft_text(&accession)?;
+        let sample_soft = SoftRecord::parse(&soft_text);
+        let series = sample_soft
+            .samples
+            .iter()
+            .find_map(|sample| sample.series.first().cloned());
+        let project_dir = self
+            .store
+            .project_expression_sample_dir(&accession, series.as_ref());
+        let cache_dir = self
+            .store
+            .cache_expression_sample_dir(&accession, series.as_ref());
+
+        let urls = sample_soft.supplementary_urls.clone();
+        if urls.is_empty() {
+            return Err(KiraError::GeoResolution(format!(
+                "GEO sample {id} contains no supplementary files"
+            )));
+        }
+        let (urls, file_categories, skipped) =
+            self.select_supplementary(urls, overrides, &options, sink);
+        let names = urls
+            .iter()
+            .map(|url| geo_relative_path(url))
+            .collect::<Vec<_>>();
+        let (mtx, h5) = (detect_10x_files(&names), detect_10x_h5(&names));
+
+        if options.dry_run {
+            return Ok(FetchItemResult {
+                dataset_type: "expressionsample".to_string(),
+                id: id.to_string(),
+                format: None,
+                source: "geo".to_string(),
+                action: "dry-run".to_string(),
+                project_path: Some(project_dir.to_string()),
+                cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+                download: None,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
+            });
+        }
+
+        let target_dir = if options.no_cache {
+            &project_dir
+        } else {
+            &cache_dir
+        };
+        let parent = target_dir
+            .parent()
+            .ok_or_else(|| KiraError::Filesystem("invalid target dir".to_string()))?;
+        fs::create_dir_all(parent.as_std_path())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let temp_dir = StagingDir::create_in(
+            parent.as_std_path(),
+            "kira-bm-geo",
+            target_dir.file_name().unwrap_or_default(),
+        )
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let temp_path = Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf())
+            .map_err(|_| KiraError::Filesystem("invalid temp dir".to_string()))?;
+
+        let metadata_dir = temp_path.join("metadata");
+        fs::create_dir_all(metadata_dir.as_std_path())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        fs::write(
+            metadata_dir.join("geo_soft.txt").as_std_path(),
+            soft_text.as_bytes(),
+        )
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+
+        sink.event(ProgressEvent {
+            message: "phase=Fetch; downloading supplementary files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let mut files = Vec::new();
+        let mut digests = Digests::new();
+        for url in &urls {
+            let rel = geo_relative_path(url);
+            let dest = temp_path.join(&rel);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent.as_std_path())
+                    .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            }
+            let digest = self.geo.download_url(url, dest.as_std_path(), sink)?;
+            digests.insert(rel, digest);
+            if let Some(name) = dest.file_name() {
+                files.push(name.to_string());
+            }
+        }
+
+        let meta = ExpressionMetadataFile {
+            registry: "geo".to_string(),
+            dataset_type: "expressionsample".to_string(),
+            accession: id.to_string(),
+            title: None,
+            summary: None,
+            platforms: sample_soft.platforms,
+            n_samples: None,
+            samples: sample_soft.samples,
+            organism: sample_soft.organism,
+            bundle_format: tenx_bundle_format(mtx.len(), h5.len()).map(str::to_string),
+            n_bundles: (mtx.len() + h5.len() > 0).then_some(mtx.len() + h5.len()),
+            files: unique_sorted(files),
+            relations: Vec::new(),
+            source_series: None,
+            series: series.as_ref().map(|series| series.as_str().to_string()),
+            file_categories,
+            skipped,
+            bundles: Vec::new(),
+            dimensions: Vec::new(),
+            skipped_bundles: Vec::new(),
+            unpacked_archives: Vec::new(),
+            notes: Vec::new(),
+            warnings: Vec::new(),
+            downloaded_at: iso_timestamp(),
+        };
+        let meta_path = metadata_dir.join("metadata.json");
+        let meta_bytes = to_canonical_json(&meta)?;
+        fs::write(meta_path.as_std_path(), meta_bytes)
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+
+        sink.event(ProgressEvent {
+            message: "phase=Store; writing files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        temp_dir
+            .persist(target_dir.as_std_path())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+
+        let download = geo_download(target_dir, &urls, &digests)?;
+        manifest::write("expressionsample", id, target_dir.as_std_path(), &download)?;
+        let mut project_meta = self.project_metadata(
+            "geo",
+            "expressionsample",
+            id,
+            None,
+            project_dir.as_str(),
+            &options,
+        );
+        project_meta.download = Some(download.clone());
+        if !options.no_cache {
+            Store::materialize_dir(&cache_dir, &project_dir, &project_meta.downloaded_at)?;
+        }
+        Store::write_metadata(
+            &self.store.project_metadata_path("expressionsample", id),
+            &project_meta,
+        )?;
+
+        if !options.no_cache {
+            let mut cache_meta = self.build_metadata(
+                "geo",
+                "expressionsample",
+                id,
+                None,
+                cache_dir.as_str(),
+                None,
+            );
+            cache_meta.download = Some(download.clone());
+            self.store.write_cache_metadata(
+                &self.store.cache_metadata_path("expressionsample", id),
+                &cache_meta,
+            )?;
+        }
+
+        Ok(FetchItemResult {
+            dataset_type: "expressionsample".to_string(),
+            id: id.to_string(),
+            format: None,
+            source: "geo".to_string(),
+            action: if corrupt {
+                ACTION_REFETCHED_CORRUPT
+            } else {
+                "download"
+            }
+            .to_string(),
             project_path: Some(project_dir.to_string()),
             cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+            download: Some(download),
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
         })
     }
 
+    /// Splits supplementary URLs into the files to download and the ones
+    /// skipped by category, reporting each skip.
+    fn select_supplementary(
+        &self,
+        urls: Vec<String>,
+        overrides: &FetchOverrides,
+        options: &FetchOptions,
+        sink: &dyn ProgressSink,
+    ) -> (
+        Vec<String>,
+        BTreeMap<String, FileCategory>,
+        Vec<SkippedFile>,
+    ) {
+        let mut file_categories = BTreeMap::new();
+        let mut skipped = Vec::new();
+        let mut selected = Vec::new();
+        for url in urls {
+            let category = classify_supplementary(&url);
+            let name = url.rsplit('/').next().unwrap_or(&url).to_string();
+            if category == FileCategory::Data || overrides.include_categories.contains(&category) {
+                file_categories.insert(name, category);
+                selected.push(url);
+            } else {
+                sink.event(ProgressEvent {
+                    message: format!("expression.skip {name} category={category}"),
+                    elapsed: None,
+                    level: ProgressLevel::Phase,
+                });
+                skipped.push(SkippedFile {
+                    size: (!options.dry_run)
+                        .then(|| self.geo.content_length(&url))
+                        .flatten(),
+                    name,
+                    url,
+                    category,
+                });
+            }
+        }
+        // Downloaded and recorded in path order, whatever order the SOFT
+        // text lists them in.
+        selected.sort_by_key(|url| geo_relative_path(url));
+        skipped.sort_by(|a, b| a.name.cmp(&b.name));
+        (selected, file_categories, skipped)
+    }
+
+    fn related_series_files(
+        &self,
+        accession: &GeoSeriesAccession,
+        soft_text: &str,
+        relations: &[SeriesRelation],
+        follow_superseries: bool,
+        sink: &dyn ProgressSink,
+    ) -> Result<(GeoSeriesAccession, String, Vec<String>), KiraError> {
+        if relations.is_empty() {
+            return Err(KiraError::GeoResolution(
+                "GEO series contains no supplementary files".to_string(),
+            ));
+        }
+        if !follow_superseries {
+            let related = relations
+                .iter()
+                .map(|item| format!("{} ({})", item.accession, item.relation))
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(KiraError::GeoResolution(format!(
+                "GEO series {} contains no supplementary files; related series: {}. Re-run with --follow-superseries to fetch its samples' files from the related series",
+                accession.as_str(),
+                related
+            )));
+        }
+
+        let samples = extract_series_samples(soft_text);
+        for relation in relations {
+            let related: GeoSeriesAccession = relation.accession.parse()?;
+            sink.event(ProgressEvent {
+                message: format!(
+                    "phase=Resolve; following {} {} for {}",
+                    relation.relation,
+                    related.as_str(),
+                    accession.as_str()
+                ),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            let related_soft = self.geo.fetch_soft_text(&related)?;
+            let urls = filter_urls_by_samples(&extract_supplementary_urls(&related_soft), &samples);
+            if !urls.is_empty() {
+                return Ok((related, related_soft, urls));
+            }
+        }
+        Err(KiraError::GeoResolution(format!(
+            "GEO series {} contains no supplementary files, and no related series has files for its samples",
+            accession.as_str()
+        )))
+    }
+
     fn fetch_expression10x(
         &self,
         accession: GeoSeriesAccession,
+        overrides: &FetchOverrides,
         options: FetchOptions,
         sink: &dyn ProgressSink,
     ) -> Result<FetchItemResult, KiraError> {
+        let options = self.cache_options("expression10x", options);
         sink.event(ProgressEvent {
             message: format!("phase=Resolve; expression10x {}", accession.as_str()),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
         if !options.dry_run {
             self.store.ensure_project_root()?;
             self.store.ensure_cache_root()?;
         }
+        let _lock = self.lock_dataset("expression10x", accession.as_str(), &options, sink)?;
 
         let project_dir = self.store.project_expression10x_dir(&accession);
         let cache_dir = self.store.cache_expression10x_dir(&accession);
@@ -801,19 +5074,36 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                     .as_std_path()
                     .exists()
                     .then(|| cache_dir.to_string()),
+                download: Store::recorded_download(
+                    &self
+                        .store
+                        .project_metadata_path("expression10x", accession.as_str()),
+                ),
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
+        let corrupt =
+            self.evict_corrupt_cache("expression10x", accession.as_str(), &options, sink)?;
         if !options.force && self.store.cache_exists(&cache_dir) {
+            let download = Store::recorded_download(
+                &self
+                    .store
+                    .cache_metadata_path("expression10x", accession.as_str()),
+            );
             if !options.dry_run {
-                Store::copy_dir_atomic(&cache_dir, &project_dir)?;
-                let meta = self.build_metadata(
+                let mut meta = self.project_metadata(
                     "geo",
                     "expression10x",
                     accession.as_str(),
                     None,
                     project_dir.as_str(),
+                    &options,
                 );
+                meta.download = download.clone();
+                Store::materialize_dir(&cache_dir, &project_dir, &meta.downloaded_at)?;
                 Store::write_metadata(
                     &self
                         .store
@@ -829,13 +5119,45 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 action: "cache".to_string(),
                 project_path: Some(project_dir.to_string()),
                 cache_path: Some(cache_dir.to_string()),
+                download,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
         let soft_text = self.geo.fetch_soft_text(&accession)?;
-        let urls = extract_supplementary_urls(&soft_text);
-        let bundles = detect_10x_bundles(&urls);
-        if bundles.is_empty() {
+        let soft = SoftRecord::parse(&soft_text);
+        let urls = soft.supplementary_urls.clone();
+        let prefer = overrides.prefer_bundle.unwrap_or_default();
+        let archives = urls
+            .iter()
+            .filter(|url| is_10x_archive(url))
+            .cloned()
+            .collect::<Vec<_>>();
+        // Loose files are matched by name before downloading, so a dropped
+        // layout is never fetched; bundles inside archives are matched again
+        // once unpacked.
+        let loose = urls
+            .iter()
+            .map(|url| geo_relative_path(url))
+            .collect::<Vec<_>>();
+        let mut mtx = detect_10x_files(&loose);
+        let mut h5 = detect_10x_h5(&loose);
+        let mut skipped_bundles = resolve_10x_layouts(&mut mtx, &mut h5, prefer);
+        let wanted = mtx
+            .iter()
+            .map(BundleFiles::from)
+            .chain(h5.iter().map(BundleFiles::from))
+            .flat_map(|bundle| bundle.files)
+            .collect::<BTreeSet<_>>();
+        let urls = urls
+            .iter()
+            .filter(|url| wanted.contains(&geo_relative_path(url)))
+            .chain(archives.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        if urls.is_empty() {
             return Err(KiraError::GeoResolution(
                 "no 10x bundle found in GEO supplementary files".to_string(),
             ));
@@ -850,6 +5172,10 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 action: "dry-run".to_string(),
                 project_path: Some(project_dir.to_string()),
                 cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+                download: None,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
@@ -861,10 +5187,14 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
         let parent = target_dir
             .parent()
             .ok_or_else(|| KiraError::Filesystem("invalid target dir".to_string()))?;
-        let temp_dir = tempfile::Builder::new()
-            .prefix("kira-bm-geo")
-            .tempdir_in(parent.as_std_path())
+        fs::create_dir_all(parent.as_std_path())
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let temp_dir = StagingDir::create_in(
+            parent.as_std_path(),
+            "kira-bm-geo",
+            target_dir.file_name().unwrap_or_default(),
+        )
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
         let temp_path = Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf())
             .map_err(|_| KiraError::Filesystem("invalid temp dir".to_string()))?;
 
@@ -877,52 +5207,169 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
         )
         .map_err(|err| KiraError::Filesystem(err.to_string()))?;
 
-        let mut file_names = Vec::new();
-        for bundle in &bundles {
-            for url in &bundle.urls {
-                let rel = geo_relative_path(url);
-                let dest = temp_path.join(&rel);
-                if let Some(parent) = dest.parent() {
-                    fs::create_dir_all(parent.as_std_path())
-                        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-                }
-                self.geo.download_url(url, dest.as_std_path())?;
-                if let Some(name) = dest.file_name() {
-                    file_names.push(name.to_string());
-                }
+        sink.event(ProgressEvent {
+            message: "phase=Fetch; downloading 10x bundles".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        for url in &urls {
+            let dest = temp_path.join(geo_relative_path(url));
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent.as_std_path())
+                    .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            }
+            self.geo.download_url(url, dest.as_std_path(), sink)?;
+        }
+
+        // Archives are unpacked aside and dropped once their bundles are
+        // moved out; everything ends up under bundles/<sample>/.
+        let unpacked = temp_path.join("unpacked");
+        let mut unpacked_archives = Vec::new();
+        for url in &archives {
+            let rel = geo_relative_path(url);
+            sink.event(ProgressEvent {
+                message: format!("phase=Verify; unpacking {rel}"),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            let archive = temp_path.join(&rel);
+            extract_tar(archive.as_std_path(), unpacked.join(&rel).as_std_path())?;
+            fs::remove_file(archive.as_std_path())
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            unpacked_archives.push(rel);
+        }
+        let staged = staged_files(&temp_path)?;
+        let mut mtx = detect_10x_files(&staged);
+        let mut h5 = detect_10x_h5(&staged);
+        for bundle in resolve_10x_layouts(&mut mtx, &mut h5, prefer) {
+            for file in &bundle.files {
+                fs::remove_file(temp_path.join(file).as_std_path())
+                    .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            }
+            skipped_bundles.push(bundle);
+        }
+        if mtx.is_empty() && h5.is_empty() {
+            return Err(KiraError::GeoResolution(
+                "no 10x bundle found in GEO supplementary files or their archives".to_string(),
+            ));
+        }
+        let notes = normalize_10x_bundles(temp_path.as_std_path(), &mtx)?;
+        normalize_10x_h5(temp_path.as_std_path(), &h5)?;
+        sink.event(ProgressEvent {
+            message: "phase=Verify; measuring 10x matrices".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let mut dimensions = Vec::new();
+        let mut warnings = Vec::new();
+        for bundle in &mtx {
+            let (dims, problems) = measure_10x_bundle(temp_path.as_std_path(), &bundle.sample);
+            dimensions.extend(dims);
+            for problem in &problems {
+                sink.event(ProgressEvent {
+                    message: format!("expression10x.verify_warning {problem}"),
+                    elapsed: None,
+                    level: ProgressLevel::Phase,
+                });
             }
+            warnings.extend(problems);
+        }
+        dimensions.sort_by(|a, b| a.sample.cmp(&b.sample));
+        let stored = |sample: &str, format, names: &[&str]| BundleFiles {
+            sample: sample.to_string(),
+            format,
+            files: names
+                .iter()
+                .map(|name| format!("bundles/{sample}/{name}"))
+                .collect(),
+        };
+        let mut bundles = mtx
+            .iter()
+            .map(|bundle| {
+                stored(
+                    &bundle.sample,
+                    BundlePreference::Mtx,
+                    &["matrix.mtx.gz", "barcodes.tsv.gz", "features.tsv.gz"],
+                )
+            })
+            .chain(
+                h5.iter()
+                    .map(|bundle| stored(&bundle.sample, BundlePreference::H5, &["matrix.h5"])),
+            )
+            .collect::<Vec<_>>();
+        bundles.sort_by(|a, b| a.sample.cmp(&b.sample));
+        if unpacked.as_std_path().exists() {
+            fs::remove_dir_all(unpacked.as_std_path())
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
         }
 
         let meta = ExpressionMetadataFile {
             registry: "geo".to_string(),
             dataset_type: "expression10x".to_string(),
             accession: accession.as_str().to_string(),
-            organism: extract_organism(&soft_text),
-            bundle_format: Some("10x".to_string()),
+            n_samples: Some(soft.sample_count()),
+            title: soft.series_title,
+            summary: soft.summary,
+            platforms: soft.platforms,
+            samples: soft.samples,
+            organism: soft.organism,
+            bundle_format: tenx_bundle_format(mtx.len(), h5.len()).map(str::to_string),
             n_bundles: Some(bundles.len()),
-            files: unique_sorted(file_names),
+            files: staged_files(&temp_path)?,
+            relations: soft.relations,
+            source_series: None,
+            series: None,
+            file_categories: BTreeMap::new(),
+            skipped: Vec::new(),
+            bundles,
+            dimensions,
+            skipped_bundles,
+            unpacked_archives,
+            notes,
+            warnings,
             downloaded_at: iso_timestamp(),
         };
         let meta_path = metadata_dir.join("metadata.json");
-        let meta_bytes = serde_json::to_vec_pretty(&meta)
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let meta_bytes = to_canonical_json(&meta)?;
         fs::write(meta_path.as_std_path(), meta_bytes)
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
 
-        atomic_rename_dir(temp_path.as_std_path(), target_dir.as_std_path())
+        sink.event(ProgressEvent {
+            message: "phase=Store; writing files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        temp_dir
+            .persist(target_dir.as_std_path())
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
 
-        if !options.no_cache {
-            Store::copy_dir_atomic(&cache_dir, &project_dir)?;
-        }
-
-        let project_meta = self.build_metadata(
+        // Bundles are moved and renamed after they are downloaded or
+        // unpacked, so they are hashed where they end up.
+        let download = DownloadRecord::of_dir(
+            target_dir.as_std_path(),
+            urls.iter()
+                .map(|url| crate::geo::GeoHttpClient::normalize_url(url))
+                .collect(),
+            &Digests::new(),
+        )?;
+        manifest::write(
+            "expression10x",
+            accession.as_str(),
+            target_dir.as_std_path(),
+            &download,
+        )?;
+        let mut project_meta = self.project_metadata(
             "geo",
             "expression10x",
             accession.as_str(),
             None,
             project_dir.as_str(),
+            &options,
         );
+        project_meta.download = Some(download.clone());
+        if !options.no_cache {
+            Store::materialize_dir(&cache_dir, &project_dir, &project_meta.downloaded_at)?;
+        }
         Store::write_metadata(
             &self
                 .store
@@ -931,14 +5378,16 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
         )?;
 
         if !options.no_cache {
-            let cache_meta = self.build_metadata(
+            let mut cache_meta = self.build_metadata(
                 "geo",
                 "expression10x",
                 accession.as_str(),
                 None,
                 cache_dir.as_str(),
+                None,
             );
-            Store::write_metadata(
+            cache_meta.download = Some(download.clone());
+            self.store.write_cache_metadata(
                 &self
                     .store
                     .cache_metadata_path("expression10x", accession.as_str()),
@@ -951,20 +5400,32 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
             id: accession.as_str().to_string(),
             format: None,
             source: "geo".to_string(),
-            action: "download".to_string(),
+            action: if corrupt {
+                ACTION_REFETCHED_CORRUPT
+            } else {
+                "download"
+            }
+            .to_string(),
             project_path: Some(project_dir.to_string()),
             cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+            download: Some(download),
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
         })
     }
 
     fn fetch_go(
         &self,
+        overrides: &FetchOverrides,
         options: FetchOptions,
         sink: &dyn ProgressSink,
     ) -> Result<FetchItemResult, KiraError> {
+        let options = self.cache_options("go", options);
         sink.event(ProgressEvent {
             message: "phase=Resolve; go".to_string(),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
         if !options.dry_run {
             if options.no_cache {
@@ -973,9 +5434,25 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 self.store.ensure_cache_root()?;
             }
         }
+        let _lock = self.lock_dataset("go", "go", &options, sink)?;
         let cache_dir = self.store.cache_kb_dir("go");
         let project_dir = self.store.project_kb_dir("go");
-        if !options.force && self.store.cache_exists(&cache_dir) && !options.no_cache {
+        let flavor = overrides.go_flavor.unwrap_or_default();
+        let target_dir = if options.no_cache {
+            &project_dir
+        } else {
+            &cache_dir
+        };
+        let stored = if options.force {
+            None
+        } else {
+            StoredGo::read(target_dir)
+        };
+        let covered = stored
+            .as_ref()
+            .is_some_and(|stored| stored.covers(flavor, &overrides.go_annotations));
+        let corrupt = !options.no_cache && self.evict_corrupt_cache("go", "go", &options, sink)?;
+        if covered && self.store.cache_exists(&cache_dir) && !options.no_cache {
             return Ok(FetchItemResult {
                 dataset_type: "go".to_string(),
                 id: "go".to_string(),
@@ -984,6 +5461,10 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 action: "cache".to_string(),
                 project_path: None,
                 cache_path: Some(cache_dir.to_string()),
+                download: Store::recorded_download(&self.store.cache_metadata_path("go", "go")),
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
         if options.dry_run {
@@ -995,6 +5476,364 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 action: "dry-run".to_string(),
                 project_path: options.no_cache.then(|| project_dir.to_string()),
                 cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+                download: None,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
+            });
+        }
+
+        let parent = target_dir
+            .parent()
+            .ok_or_else(|| KiraError::Filesystem("invalid cache dir".to_string()))?;
+        fs::create_dir_all(parent.as_std_path())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let temp_dir = StagingDir::create_in(
+            parent.as_std_path(),
+            "kira-bm-go",
+            target_dir.file_name().unwrap_or_default(),
+        )
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let temp_path = Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf())
+            .map_err(|_| KiraError::Filesystem("invalid temp dir".to_string()))?;
+
+        // Files already stored are kept rather than downloaded again, so
+        // adding a species only fetches that species' annotations.
+        let keep = |file: &str| -> Result<bool, KiraError> {
+            let existing = target_dir.join(file);
+            if !existing.as_std_path().is_file() {
+                return Ok(false);
+            }
+            fs::copy(existing.as_std_path(), temp_path.join(file).as_std_path())
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            Ok(true)
+        };
+
+        let record_path = if options.no_cache {
+            self.store.project_metadata_path("go", "go")
+        } else {
+            self.store.cache_metadata_path("go", "go")
+        };
+        // The ontology's validators, kept for as long as the stored copy is.
+        let mut validators: StoredValidators = stored_validators(&record_path)
+            .into_iter()
+            .filter(|(url, _)| url == flavor.url())
+            .collect();
+        // A forced refetch asks whether the stored ontology changed.
+        let previous = if options.force {
+            StoredGo::read(target_dir).filter(|previous| previous.flavor == flavor)
+        } else {
+            None
+        };
+        let mut not_modified = false;
+        let mut digests = Digests::new();
+        let (version, release_date) = match stored.as_ref() {
+            Some(stored) if stored.flavor == flavor && keep(flavor.file_name())? => {
+                (stored.version.clone(), stored.release_date.clone())
+            }
+            _ => {
+                sink.event(ProgressEvent {
+                    message: format!("phase=Fetch; downloading {}", flavor.file_name()),
+                    elapsed: None,
+                    level: ProgressLevel::Phase,
+                });
+                let obo_path = temp_path.join(flavor.file_name());
+                let current = if previous.is_some()
+                    && target_dir.join(flavor.file_name()).as_std_path().is_file()
+                {
+                    validators.clone()
+                } else {
+                    StoredValidators::new()
+                };
+                match self.knowledge.download_go_ontology_if_modified(
+                    flavor,
+                    obo_path.as_std_path(),
+                    sink,
+                    &current,
+                )? {
+                    Conditional::Modified(obo_bytes, fresh) => {
+                        validators = fresh;
+                        digests.insert(flavor.file_name().to_string(), sha256_bytes(&obo_bytes));
+                        parse_go_header(&obo_bytes)
+                    }
+                    Conditional::NotModified => {
+                        sink.event(ProgressEvent {
+                            message: "go.not_modified".to_string(),
+                            elapsed: None,
+                            level: ProgressLevel::Request,
+                        });
+                        let Some(previous) = previous.as_ref() else {
+                            return Err(KiraError::KnowledgeHttp(
+                                "the GO ontology was reported unchanged without a stored copy"
+                                    .to_string(),
+                            ));
+                        };
+                        keep(flavor.file_name())?;
+                        not_modified = true;
+                        (previous.version.clone(), previous.release_date.clone())
+                    }
+                }
+            }
+        };
+
+        let mut species = overrides.go_annotations.clone();
+        if let Some(stored) = stored.as_ref() {
+            species.extend(
+                stored
+                    .annotations
+                    .iter()
+                    .map(|annotation| annotation.species.clone()),
+            );
+        }
+        species.sort();
+        species.dedup();
+        let mut annotations = Vec::with_capacity(species.len());
+        for species in species {
+            let file = goa_file_name(&species);
+            if let Some(annotation) = stored
+                .as_ref()
+                .and_then(|stored| stored.annotation(&species))
+                && keep(&file)?
+            {
+                annotations.push(annotation.clone());
+                continue;
+            }
+            sink.event(ProgressEvent {
+                message: format!("phase=Fetch; downloading {file}"),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            let bytes = self.knowledge.download_go_annotations(
+                &species,
+                temp_path.join(&file).as_std_path(),
+                sink,
+            )?;
+            let (_, release_date) = parse_go_header(&bytes);
+            digests.insert(file.clone(), sha256_bytes(&bytes));
+            annotations.push(GoAnnotation {
+                source_url: goa_url(&species),
+                species,
+                file,
+                release_date,
+            });
+        }
+
+        let mut source_urls = vec![flavor.url().to_string()];
+        source_urls.extend(
+            annotations
+                .iter()
+                .map(|annotation| annotation.source_url.clone()),
+        );
+        let meta = KnowledgeMetadataFile {
+            registry: "go".to_string(),
+            dataset_type: "go".to_string(),
+            organism: None,
+            flavor: Some(flavor),
+            annotations,
+            species_filter: None,
+            version,
+            release_date,
+            source_urls,
+            downloaded_at: iso_timestamp(),
+            validation: None,
+        };
+        let meta_path = temp_path.join("metadata.json");
+        let meta_bytes = to_canonical_json(&meta)?;
+        fs::write(meta_path.as_std_path(), meta_bytes)
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+
+        sink.event(ProgressEvent {
+            message: "phase=Store; writing files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        temp_dir
+            .persist(target_dir.as_std_path())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let mut download =
+            DownloadRecord::of_dir(target_dir.as_std_path(), meta.source_urls, &digests)?;
+        manifest::write("go", "go", target_dir.as_std_path(), &download)?;
+        download.validators = validators;
+
+        if options.no_cache {
+            let mut project_meta =
+                self.project_metadata("go", "go", "go", None, project_dir.as_str(), &options);
+            project_meta.download = Some(download.clone());
+            Store::write_metadata(&record_path, &project_meta)?;
+        } else {
+            let mut cache_meta =
+                self.build_metadata("go", "go", "go", None, cache_dir.as_str(), None);
+            cache_meta.download = Some(download.clone());
+            self.store.write_cache_metadata(&record_path, &cache_meta)?;
+        }
+
+        // Annotations fetched alongside an unchanged ontology still count
+        // as a download.
+        let not_modified = not_modified && meta.annotations.is_empty();
+        Ok(FetchItemResult {
+            dataset_type: "go".to_string(),
+            id: "go".to_string(),
+            format: None,
+            source: "go".to_string(),
+            action: if not_modified {
+                "not-modified"
+            } else if corrupt {
+                ACTION_REFETCHED_CORRUPT
+            } else {
+                "download"
+            }
+            .to_string(),
+            project_path: options.no_cache.then(|| project_dir.to_string()),
+            cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+            download: Some(download),
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
+        })
+    }
+
+    fn kegg_declared_pathways(&self, sink: &dyn ProgressSink) -> Option<usize> {
+        let declared = self
+            .knowledge
+            .fetch_kegg_info("pathway")
+            .ok()
+            .and_then(|text| parse_kegg_info_entries(&text));
+        if declared.is_none() {
+            sink.event(ProgressEvent {
+                message: "kegg info/pathway unavailable; pathway count is unverified".to_string(),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+        }
+        declared
+    }
+
+    /// The pathway ids listed and the list's SHA-256.
+    fn download_kegg_list(
+        &self,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<(Vec<String>, String), KiraError> {
+        let digest = self.knowledge.download_kegg_pathways(destination, sink)?;
+        let text = fs::read_to_string(destination)
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        Ok((kegg_list_ids(&text), digest))
+    }
+
+    /// Fetches the KO-pathway links in one request, falling back to batches
+    /// of pathways when the bulk response fails or looks truncated. Returns
+    /// the rows, how they were retrieved and the file's SHA-256.
+    fn download_kegg_links(
+        &self,
+        pathway_ids: &[String],
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<(usize, &'static str, String), KiraError> {
+        let suspect = match self
+            .knowledge
+            .download_kegg_pathway_links(destination, sink)
+        {
+            Ok(digest) => {
+                let text = fs::read_to_string(destination)
+                    .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+                match kegg_links_defect(&text) {
+                    None if text.len() < KEGG_LINK_SINGLE_SHOT_MAX => {
+                        return Ok((text.lines().count(), "single", digest));
+                    }
+                    None => format!("response reached {} bytes", text.len()),
+                    Some(defect) => defect,
+                }
+            }
+            Err(err) => err.to_string(),
+        };
+        sink.event(ProgressEvent {
+            message: format!("kegg link/pathway/ko unusable ({suspect}); fetching in batches"),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let batches = pathway_ids.chunks(KEGG_LINK_BATCH).collect::<Vec<_>>();
+        let mut rows = Vec::new();
+        for (index, batch) in batches.iter().enumerate() {
+            check_cancelled(sink)?;
+            sink.event(ProgressEvent {
+                message: format!("kegg links batch {}/{}", index + 1, batches.len()),
+                elapsed: None,
+                level: ProgressLevel::Request,
+            });
+            let text = self.knowledge.fetch_kegg_links("ko", batch)?;
+            if let Some(defect) = kegg_links_defect(&text) {
+                return Err(KiraError::KnowledgeIncomplete(format!(
+                    "KEGG links for {}..: {defect}",
+                    batch[0]
+                )));
+            }
+            rows.extend(normalize_kegg_ko_links(&text));
+        }
+        let mut body = rows.join("\n");
+        if !body.is_empty() {
+            body.push('\n');
+        }
+        let digest = write_hashed(destination, body.as_bytes())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        Ok((rows.len(), "chunked", digest))
+    }
+
+    fn fetch_kegg(
+        &self,
+        options: FetchOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchItemResult, KiraError> {
+        let options = self.cache_options("kegg", options);
+        sink.event(ProgressEvent {
+            message: "phase=Resolve; kegg".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        if !options.dry_run {
+            if options.no_cache {
+                self.store.ensure_project_root()?;
+            } else {
+                self.store.ensure_cache_root()?;
+            }
+        }
+        let _lock = self.lock_dataset("kegg", "kegg", &options, sink)?;
+        let cache_dir = self.store.cache_kb_dir("kegg");
+        let project_dir = self.store.project_kb_dir("kegg");
+        // Organism pathways live in subdirectories, so only the generic
+        // files' metadata marks the generic dataset as present.
+        let corrupt =
+            !options.no_cache && self.evict_corrupt_cache("kegg", "kegg", &options, sink)?;
+        if !options.force
+            && self.store.cache_exists(&cache_dir.join("metadata.json"))
+            && !options.no_cache
+        {
+            return Ok(FetchItemResult {
+                dataset_type: "kegg".to_string(),
+                id: "kegg".to_string(),
+                format: None,
+                source: "kegg".to_string(),
+                action: "cache".to_string(),
+                project_path: None,
+                cache_path: Some(cache_dir.to_string()),
+                download: Store::recorded_download(&self.store.cache_metadata_path("kegg", "kegg")),
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
+            });
+        }
+        if options.dry_run {
+            return Ok(FetchItemResult {
+                dataset_type: "kegg".to_string(),
+                id: "kegg".to_string(),
+                format: None,
+                source: "kegg".to_string(),
+                action: "dry-run".to_string(),
+                project_path: options.no_cache.then(|| project_dir.to_string()),
+                cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+                download: None,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
@@ -1006,60 +5845,154 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
         let parent = target_dir
             .parent()
             .ok_or_else(|| KiraError::Filesystem("invalid cache dir".to_string()))?;
-        let temp_dir = tempfile::Builder::new()
-            .prefix("kira-bm-go")
-            .tempdir_in(parent.as_std_path())
+        fs::create_dir_all(parent.as_std_path())
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let temp_dir = StagingDir::create_in(
+            parent.as_std_path(),
+            "kira-bm-kegg",
+            target_dir.file_name().unwrap_or_default(),
+        )
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
         let temp_path = Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf())
             .map_err(|_| KiraError::Filesystem("invalid temp dir".to_string()))?;
 
-        let obo_path = temp_path.join("go-basic.obo");
-        let obo_bytes = self.knowledge.download_go(obo_path.as_std_path())?;
-        let (version, release_date) = parse_go_header(&obo_bytes);
+        sink.event(ProgressEvent {
+            message: "phase=Fetch; downloading KEGG pathways".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+
+        let list_path = temp_path.join("pathway_list.txt");
+        let link_path = temp_path.join("pathway_ko.txt");
+        let declared = self.kegg_declared_pathways(sink);
+        let mut digests = Digests::new();
+        let (mut pathway_ids, mut list_digest) =
+            self.download_kegg_list(list_path.as_std_path(), sink)?;
+        if let Some(declared) = declared
+            && pathway_ids.len() < declared
+        {
+            tracing::warn!(
+                registry = "kegg",
+                received = pathway_ids.len(),
+                declared,
+                "retrying truncated kegg pathway list"
+            );
+            sink.event(ProgressEvent {
+                message: format!(
+                    "kegg pathway list has {} of {declared} entries; retrying",
+                    pathway_ids.len()
+                ),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            (pathway_ids, list_digest) = self.download_kegg_list(list_path.as_std_path(), sink)?;
+            if pathway_ids.len() < declared {
+                return Err(KiraError::KnowledgeIncomplete(format!(
+                    "KEGG pathway list has {} of {declared} declared entries",
+                    pathway_ids.len()
+                )));
+            }
+        }
+        digests.insert("pathway_list.txt".to_string(), list_digest);
+        let (link_rows, link_retrieval, link_digest) =
+            self.download_kegg_links(&pathway_ids, link_path.as_std_path(), sink)?;
+        digests.insert("pathway_ko.txt".to_string(), link_digest);
+        let mut source_urls = vec![
+            "https://rest.kegg.jp/list/pathway".to_string(),
+            "https://rest.kegg.jp/link/pathway/ko".to_string(),
+        ];
+        if link_retrieval == "chunked" {
+            source_urls[1] = "https://rest.kegg.jp/link/ko/<pathways>".to_string();
+        }
         let meta = KnowledgeMetadataFile {
-            registry: "go".to_string(),
-            dataset_type: "go".to_string(),
-            version,
-            release_date,
-            source_urls: vec!["http://purl.obolibrary.org/obo/go/go-basic.obo".to_string()],
+            registry: "kegg".to_string(),
+            dataset_type: "kegg".to_string(),
+            organism: None,
+            flavor: None,
+            annotations: Vec::new(),
+            species_filter: None,
+            version: None,
+            release_date: None,
+            source_urls,
             downloaded_at: iso_timestamp(),
+            validation: Some(KnowledgeValidation {
+                pathway_rows: pathway_ids.len(),
+                declared_pathways: declared,
+                link_rows,
+                link_retrieval: link_retrieval.to_string(),
+                complete: declared.is_some(),
+            }),
         };
         let meta_path = temp_path.join("metadata.json");
-        let meta_bytes = serde_json::to_vec_pretty(&meta)
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let meta_bytes = to_canonical_json(&meta)?;
         fs::write(meta_path.as_std_path(), meta_bytes)
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
 
-        atomic_rename_dir(temp_path.as_std_path(), target_dir.as_std_path())
+        sink.event(ProgressEvent {
+            message: "phase=Store; writing files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        carry_kegg_organisms(target_dir, &temp_path)?;
+        temp_dir
+            .persist(target_dir.as_std_path())
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let download =
+            DownloadRecord::of_dir(target_dir.as_std_path(), meta.source_urls, &digests)?;
+        manifest::write("kegg", "kegg", target_dir.as_std_path(), &download)?;
 
         if options.no_cache {
-            let project_meta = self.build_metadata("go", "go", "go", None, project_dir.as_str());
-            Store::write_metadata(&self.store.project_metadata_path("go", "go"), &project_meta)?;
+            let mut project_meta =
+                self.project_metadata("kegg", "kegg", "kegg", None, project_dir.as_str(), &options);
+            project_meta.download = Some(download.clone());
+            Store::write_metadata(
+                &self.store.project_metadata_path("kegg", "kegg"),
+                &project_meta,
+            )?;
         } else {
-            let cache_meta = self.build_metadata("go", "go", "go", None, cache_dir.as_str());
-            Store::write_metadata(&self.store.cache_metadata_path("go", "go"), &cache_meta)?;
+            let mut cache_meta =
+                self.build_metadata("kegg", "kegg", "kegg", None, cache_dir.as_str(), None);
+            cache_meta.download = Some(download.clone());
+            self.store.write_cache_metadata(
+                &self.store.cache_metadata_path("kegg", "kegg"),
+                &cache_meta,
+            )?;
         }
 
         Ok(FetchItemResult {
-            dataset_type: "go".to_string(),
-            id: "go".to_string(),
+            dataset_type: "kegg".to_string(),
+            id: "kegg".to_string(),
             format: None,
-            source: "go".to_string(),
-            action: "download".to_string(),
+            source: "kegg".to_string(),
+            action: if corrupt {
+                ACTION_REFETCHED_CORRUPT
+            } else {
+                "download"
+            }
+            .to_string(),
             project_path: options.no_cache.then(|| project_dir.to_string()),
             cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+            download: Some(download),
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
         })
     }
 
-    fn fetch_kegg(
+    /// One organism's pathways and gene-pathway links, stored under the
+    /// generic KEGG directory as `<org>/`.
+    fn fetch_kegg_organism(
         &self,
+        organism: KeggOrganism,
         options: FetchOptions,
         sink: &dyn ProgressSink,
     ) -> Result<FetchItemResult, KiraError> {
+        let options = self.cache_options("kegg", options);
+        let id = organism.as_str();
         sink.event(ProgressEvent {
-            message: "phase=Resolve; kegg".to_string(),
+            message: format!("phase=Resolve; kegg {id}"),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
         if !options.dry_run {
             if options.no_cache {
@@ -1068,28 +6001,38 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 self.store.ensure_cache_root()?;
             }
         }
-        let cache_dir = self.store.cache_kb_dir("kegg");
-        let project_dir = self.store.project_kb_dir("kegg");
+        let _lock = self.lock_dataset("kegg", id, &options, sink)?;
+        let cache_dir = self.store.cache_kb_dir("kegg").join(id);
+        let project_dir = self.store.project_kb_dir("kegg").join(id);
+        let corrupt = !options.no_cache && self.evict_corrupt_cache("kegg", id, &options, sink)?;
         if !options.force && self.store.cache_exists(&cache_dir) && !options.no_cache {
             return Ok(FetchItemResult {
                 dataset_type: "kegg".to_string(),
-                id: "kegg".to_string(),
+                id: id.to_string(),
                 format: None,
                 source: "kegg".to_string(),
                 action: "cache".to_string(),
                 project_path: None,
                 cache_path: Some(cache_dir.to_string()),
+                download: Store::recorded_download(&self.store.cache_metadata_path("kegg", id)),
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
         if options.dry_run {
             return Ok(FetchItemResult {
                 dataset_type: "kegg".to_string(),
-                id: "kegg".to_string(),
+                id: id.to_string(),
                 format: None,
                 source: "kegg".to_string(),
                 action: "dry-run".to_string(),
                 project_path: options.no_cache.then(|| project_dir.to_string()),
                 cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+                download: None,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
@@ -1101,70 +6044,123 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
         let parent = target_dir
             .parent()
             .ok_or_else(|| KiraError::Filesystem("invalid cache dir".to_string()))?;
-        let temp_dir = tempfile::Builder::new()
-            .prefix("kira-bm-kegg")
-            .tempdir_in(parent.as_std_path())
+        fs::create_dir_all(parent.as_std_path())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let temp_dir = StagingDir::create_in(parent.as_std_path(), "kira-bm-kegg", id)
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
         let temp_path = Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf())
             .map_err(|_| KiraError::Filesystem("invalid temp dir".to_string()))?;
 
+        sink.event(ProgressEvent {
+            message: format!("phase=Fetch; downloading KEGG pathways for {id}"),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
         let list_path = temp_path.join("pathway_list.txt");
-        let link_path = temp_path.join("pathway_ko.txt");
-        self.knowledge
-            .download_kegg_pathways(list_path.as_std_path())?;
-        self.knowledge
-            .download_kegg_pathway_links(link_path.as_std_path())?;
+        let link_path = temp_path.join("pathway_gene.txt");
+        let mut digests = Digests::new();
+        let digest = self.knowledge.download_kegg_organism_pathways(
+            &organism,
+            list_path.as_std_path(),
+            sink,
+        )?;
+        digests.insert("pathway_list.txt".to_string(), digest);
+        let list = fs::read_to_string(list_path.as_std_path())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        if kegg_list_ids(&list).is_empty() {
+            return Err(KiraError::KnowledgeIncomplete(format!(
+                "KEGG lists no pathways for organism {id}"
+            )));
+        }
+        check_cancelled(sink)?;
+        let digest =
+            self.knowledge
+                .download_kegg_gene_links(&organism, link_path.as_std_path(), sink)?;
+        digests.insert("pathway_gene.txt".to_string(), digest);
+        let links = fs::read_to_string(link_path.as_std_path())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        if let Some(defect) = kegg_links_defect(&links) {
+            return Err(KiraError::KnowledgeIncomplete(format!(
+                "KEGG gene links for {id}: {defect}"
+            )));
+        }
         let meta = KnowledgeMetadataFile {
             registry: "kegg".to_string(),
             dataset_type: "kegg".to_string(),
+            organism: Some(id.to_string()),
+            flavor: None,
+            annotations: Vec::new(),
+            species_filter: None,
             version: None,
             release_date: None,
             source_urls: vec![
-                "https://rest.kegg.jp/list/pathway".to_string(),
-                "https://rest.kegg.jp/link/pathway/ko".to_string(),
+                format!("https://rest.kegg.jp/list/pathway/{id}"),
+                format!("https://rest.kegg.jp/link/{id}/pathway"),
             ],
             downloaded_at: iso_timestamp(),
+            validation: None,
         };
         let meta_path = temp_path.join("metadata.json");
-        let meta_bytes = serde_json::to_vec_pretty(&meta)
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let meta_bytes = to_canonical_json(&meta)?;
         fs::write(meta_path.as_std_path(), meta_bytes)
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
 
-        atomic_rename_dir(temp_path.as_std_path(), target_dir.as_std_path())
+        sink.event(ProgressEvent {
+            message: "phase=Store; writing files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        temp_dir
+            .persist(target_dir.as_std_path())
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let download =
+            DownloadRecord::of_dir(target_dir.as_std_path(), meta.source_urls, &digests)?;
+        manifest::write("kegg", id, target_dir.as_std_path(), &download)?;
 
         if options.no_cache {
-            let project_meta =
-                self.build_metadata("kegg", "kegg", "kegg", None, project_dir.as_str());
-            Store::write_metadata(
-                &self.store.project_metadata_path("kegg", "kegg"),
-                &project_meta,
-            )?;
+            let mut project_meta =
+                self.project_metadata("kegg", "kegg", id, None, project_dir.as_str(), &options);
+            project_meta.download = Some(download.clone());
+            Store::write_metadata(&self.store.project_metadata_path("kegg", id), &project_meta)?;
         } else {
-            let cache_meta = self.build_metadata("kegg", "kegg", "kegg", None, cache_dir.as_str());
-            Store::write_metadata(&self.store.cache_metadata_path("kegg", "kegg"), &cache_meta)?;
+            let mut cache_meta =
+                self.build_metadata("kegg", "kegg", id, None, cache_dir.as_str(), None);
+            cache_meta.download = Some(download.clone());
+            self.store
+                .write_cache_metadata(&self.store.cache_metadata_path("kegg", id), &cache_meta)?;
         }
 
         Ok(FetchItemResult {
             dataset_type: "kegg".to_string(),
-            id: "kegg".to_string(),
+            id: id.to_string(),
             format: None,
             source: "kegg".to_string(),
-            action: "download".to_string(),
+            action: if corrupt {
+                ACTION_REFETCHED_CORRUPT
+            } else {
+                "download"
+            }
+            .to_string(),
             project_path: options.no_cache.then(|| project_dir.to_string()),
             cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+            download: Some(download),
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
         })
     }
 
     fn fetch_reactome(
         &self,
+        overrides: &FetchOverrides,
         options: FetchOptions,
         sink: &dyn ProgressSink,
     ) -> Result<FetchItemResult, KiraError> {
+        let options = self.cache_options("reactome", options);
         sink.event(ProgressEvent {
             message: "phase=Resolve; reactome".to_string(),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
         if !options.dry_run {
             if options.no_cache {
@@ -1173,9 +6169,21 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 self.store.ensure_cache_root()?;
             }
         }
+        let _lock = self.lock_dataset("reactome", "reactome", &options, sink)?;
         let cache_dir = self.store.cache_kb_dir("reactome");
         let project_dir = self.store.project_kb_dir("reactome");
-        if !options.force && self.store.cache_exists(&cache_dir) && !options.no_cache {
+        let species = overrides
+            .reactome_species
+            .as_deref()
+            .map(str::trim)
+            .filter(|species| !species.is_empty());
+        let corrupt = !options.no_cache
+            && self.evict_corrupt_cache("reactome", "reactome", &options, sink)?;
+        if !options.force
+            && self.store.cache_exists(&cache_dir)
+            && !options.no_cache
+            && reactome_satisfies(&cache_dir, species, overrides.replace_unfiltered)
+        {
             return Ok(FetchItemResult {
                 dataset_type: "reactome".to_string(),
                 id: "reactome".to_string(),
@@ -1184,6 +6192,12 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 action: "cache".to_string(),
                 project_path: None,
                 cache_path: Some(cache_dir.to_string()),
+                download: Store::recorded_download(
+                    &self.store.cache_metadata_path("reactome", "reactome"),
+                ),
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
         if options.dry_run {
@@ -1195,6 +6209,10 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 action: "dry-run".to_string(),
                 project_path: options.no_cache.then(|| project_dir.to_string()),
                 cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+                download: None,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
@@ -1206,55 +6224,144 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
         let parent = target_dir
             .parent()
             .ok_or_else(|| KiraError::Filesystem("invalid cache dir".to_string()))?;
-        let temp_dir = tempfile::Builder::new()
-            .prefix("kira-bm-reactome")
-            .tempdir_in(parent.as_std_path())
+        fs::create_dir_all(parent.as_std_path())
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let temp_dir = StagingDir::create_in(
+            parent.as_std_path(),
+            "kira-bm-reactome",
+            target_dir.file_name().unwrap_or_default(),
+        )
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
         let temp_path = Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf())
             .map_err(|_| KiraError::Filesystem("invalid temp dir".to_string()))?;
 
+        sink.event(ProgressEvent {
+            message: "phase=Fetch; downloading Reactome pathways".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+
         let pathways_path = temp_path.join("ReactomePathways.txt");
         let mapping_path = temp_path.join("UniProt2Reactome.txt");
-        self.knowledge
-            .download_reactome_pathways(pathways_path.as_std_path())?;
-        self.knowledge
-            .download_reactome_mappings(mapping_path.as_std_path())?;
+        let relation_path = temp_path.join("ReactomePathwaysRelation.txt");
+        let mut digests = Digests::new();
+        digests.insert(
+            "ReactomePathways.txt".to_string(),
+            self.knowledge
+                .download_reactome_pathways(pathways_path.as_std_path(), sink)?,
+        );
+        digests.insert(
+            "UniProt2Reactome.txt".to_string(),
+            self.knowledge
+                .download_reactome_mappings(mapping_path.as_std_path(), sink)?,
+        );
+        digests.insert(
+            "ReactomePathwaysRelation.txt".to_string(),
+            self.knowledge
+                .download_reactome_relations(relation_path.as_std_path(), sink)?,
+        );
+
+        let species_filter = match species {
+            Some(species) => {
+                sink.event(ProgressEvent {
+                    message: format!("phase=Fetch; filtering Reactome files to {species}"),
+                    elapsed: None,
+                    level: ProgressLevel::Phase,
+                });
+                let mut files = Vec::new();
+                for source in ["ReactomePathways.txt", "UniProt2Reactome.txt"] {
+                    let file = if overrides.replace_unfiltered {
+                        source.to_string()
+                    } else {
+                        reactome_filtered_name(source, species)
+                    };
+                    let filtered = temp_path.join(format!("{file}.filtering"));
+                    let (lines_before, lines_after, digest) = filter_reactome_species(
+                        temp_path.join(source).as_std_path(),
+                        filtered.as_std_path(),
+                        species,
+                    )?;
+                    if source == "ReactomePathways.txt" && lines_after == 0 {
+                        return Err(KiraError::UnknownReactomeSpecies(species.to_string()));
+                    }
+                    fs::rename(filtered.as_std_path(), temp_path.join(&file).as_std_path())
+                        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+                    digests.insert(file.clone(), digest);
+                    files.push(FilteredFile {
+                        source: source.to_string(),
+                        file,
+                        lines_before,
+                        lines_after,
+                    });
+                }
+                Some(SpeciesFilter {
+                    species: species.to_string(),
+                    replaced_unfiltered: overrides.replace_unfiltered,
+                    files,
+                })
+            }
+            None => None,
+        };
+
         let meta = KnowledgeMetadataFile {
             registry: "reactome".to_string(),
             dataset_type: "reactome".to_string(),
+            organism: None,
+            flavor: None,
+            annotations: Vec::new(),
+            species_filter,
             version: None,
             release_date: None,
             source_urls: vec![
-                "https://reactome.org/download/current/ReactomePathways.txt".to_string(),
-                "https://reactome.org/download/current/UniProt2Reactome.txt".to_string(),
+                format!("{REACTOME_DOWNLOAD}/ReactomePathways.txt"),
+                format!("{REACTOME_DOWNLOAD}/UniProt2Reactome.txt"),
+                format!("{REACTOME_DOWNLOAD}/ReactomePathwaysRelation.txt"),
             ],
             downloaded_at: iso_timestamp(),
+            validation: None,
         };
         let meta_path = temp_path.join("metadata.json");
-        let meta_bytes = serde_json::to_vec_pretty(&meta)
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let meta_bytes = to_canonical_json(&meta)?;
         fs::write(meta_path.as_std_path(), meta_bytes)
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
 
-        atomic_rename_dir(temp_path.as_std_path(), target_dir.as_std_path())
+        sink.event(ProgressEvent {
+            message: "phase=Store; writing files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        temp_dir
+            .persist(target_dir.as_std_path())
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let download =
+            DownloadRecord::of_dir(target_dir.as_std_path(), meta.source_urls, &digests)?;
+        manifest::write("reactome", "reactome", target_dir.as_std_path(), &download)?;
 
         if options.no_cache {
-            let project_meta = self.build_metadata(
+            let mut project_meta = self.project_metadata(
                 "reactome",
                 "reactome",
                 "reactome",
                 None,
                 project_dir.as_str(),
+                &options,
             );
+            project_meta.download = Some(download.clone());
             Store::write_metadata(
                 &self.store.project_metadata_path("reactome", "reactome"),
                 &project_meta,
             )?;
         } else {
-            let cache_meta =
-                self.build_metadata("reactome", "reactome", "reactome", None, cache_dir.as_str());
-            Store::write_metadata(
+            let mut cache_meta = self.build_metadata(
+                "reactome",
+                "reactome",
+                "reactome",
+                None,
+                cache_dir.as_str(),
+                None,
+            );
+            cache_meta.download = Some(download.clone());
+            self.store.write_cache_metadata(
                 &self.store.cache_metadata_path("reactome", "reactome"),
                 &cache_meta,
             )?;
@@ -1265,38 +6372,55 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
             id: "reactome".to_string(),
             format: None,
             source: "reactome".to_string(),
-            action: "download".to_string(),
+            action: if corrupt {
+                ACTION_REFETCHED_CORRUPT
+            } else {
+                "download"
+            }
+            .to_string(),
             project_path: options.no_cache.then(|| project_dir.to_string()),
             cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+            download: Some(download),
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
         })
     }
 
     fn fetch_protein(
         &self,
         id: ProteinId,
-        format_override: Option<ProteinFormat>,
+        overrides: &FetchOverrides,
         options: FetchOptions,
         sink: &dyn ProgressSink,
     ) -> Result<FetchItemResult, KiraError> {
+        let options = self.cache_options("protein", options);
         sink.event(ProgressEvent {
             message: format!("phase=Resolve; protein {}", id.as_str()),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
-        let format = format_override.unwrap_or(ProteinFormat::Cif);
+        let format = overrides.protein_format.unwrap_or(ProteinFormat::Cif);
         if !options.dry_run {
             self.store.ensure_project_root()?;
             self.store.ensure_cache_root()?;
         }
+        let _lock = self.lock_dataset("protein", id.as_str(), &options, sink)?;
 
         let project_path = self.store.project_protein_path(&id, format);
         let cache_path = self.store.cache_protein_path(&id, format);
         let project_dir = self.store.project_protein_dir(&id);
         let cache_dir = self.store.cache_protein_dir(&id);
 
+        if options.metadata_only {
+            return self.fetch_protein_metadata(&id, &project_dir, &options, sink);
+        }
+
         if !options.force && self.store.project_exists(&project_path) {
             sink.event(ProgressEvent {
                 message: "phase=Store; already in project store".to_string(),
                 elapsed: None,
+                level: ProgressLevel::Phase,
             });
             return Ok(FetchItemResult {
                 dataset_type: "protein".to_string(),
@@ -1309,35 +6433,54 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                     .as_std_path()
                     .exists()
                     .then(|| cache_path.to_string()),
+                download: Store::recorded_download(
+                    &self.store.project_metadata_path("protein", id.as_str()),
+                ),
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
+        let corrupt = self.evict_corrupt_cache("protein", id.as_str(), &options, sink)?;
         if !options.force && self.store.cache_exists(&cache_path) {
             sink.event(ProgressEvent {
                 message: "phase=Store; using cached dataset".to_string(),
                 elapsed: None,
+                level: ProgressLevel::Phase,
             });
+            let download =
+                Store::recorded_download(&self.store.cache_metadata_path("protein", id.as_str()));
             if !options.dry_run {
-                Store::copy_file_atomic(&cache_path, &project_path)?;
-                let (cache_meta, cache_raw) = rcsb_metadata_paths(&cache_dir);
-                let (project_meta, project_raw) = rcsb_metadata_paths(&project_dir);
-                if cache_meta.as_std_path().exists() {
-                    Store::copy_file_atomic(&cache_meta, &project_meta)?;
-                }
-                if cache_raw.as_std_path().exists() {
-                    Store::copy_file_atomic(&cache_raw, &project_raw)?;
-                }
-                let meta = self.build_metadata(
+                let mut meta = self.project_metadata(
                     "rcsb",
                     "protein",
                     id.as_str(),
                     Some(format.to_string()),
                     project_path.as_str(),
+                    &options,
                 );
-                Store::write_metadata(
-                    &self.store.project_metadata_path("protein", id.as_str()),
-                    &meta,
-                )?;
+                meta.download = download.clone();
+                Store::materialize_file(&cache_path, &project_path, &meta.downloaded_at)?;
+                let (cache_meta, cache_raw) = rcsb_metadata_paths(&cache_dir);
+                let (project_meta, project_raw) = rcsb_metadata_paths(&project_dir);
+                if cache_meta.as_std_path().exists() {
+                    Store::materialize_file(&cache_meta, &project_meta, &meta.downloaded_at)?;
+                }
+                if cache_raw.as_std_path().exists() {
+                    Store::materialize_file(&cache_raw, &project_raw, &meta.downloaded_at)?;
+                }
+                let cache_fasta = protein_fasta_path(&cache_dir, &id);
+                if cache_fasta.as_std_path().exists() {
+                    Store::materialize_file(
+                        &cache_fasta,
+                        &protein_fasta_path(&project_dir, &id),
+                        &meta.downloaded_at,
+                    )?;
+                }
+                let record_path = self.store.project_metadata_path("protein", id.as_str());
+                record_protein_formats(&mut meta, &record_path, &project_dir, &id);
+                Store::write_metadata(&record_path, &meta)?;
             }
             return Ok(FetchItemResult {
                 dataset_type: "protein".to_string(),
@@ -1347,6 +6490,10 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 action: "cache".to_string(),
                 project_path: Some(project_path.to_string()),
                 cache_path: Some(cache_path.to_string()),
+                download,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
@@ -1359,127 +6506,451 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 action: "download".to_string(),
                 project_path: Some(project_path.to_string()),
                 cache_path: (!options.no_cache).then(|| cache_path.to_string()),
+                download: None,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
-        let temp_dir = tempfile::Builder::new()
-            .prefix("kira-bm-protein")
-            .tempdir_in(self.store.project_root().as_std_path())
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let temp_dir = StagingDir::create_in(
+            self.store.project_root().as_std_path(),
+            "kira-bm-protein",
+            id.as_str(),
+        )
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
         let temp_path = temp_dir.path().join(format!("{}.tmp", id.as_str()));
         let temp_meta = temp_dir.path().join("metadata.json");
         let temp_raw = temp_dir.path().join("metadata.raw.json");
+        let temp_fasta = temp_dir.path().join(format!("{}.fasta", id.as_str()));
+        let (project_meta, project_raw) = rcsb_metadata_paths(&project_dir);
+        // A metadata-only copy already holds the entry JSON.
+        let has_metadata = self.store.is_metadata_only("protein", id.as_str())
+            && project_meta.as_std_path().exists()
+            && project_raw.as_std_path().exists();
+
+        check_cancelled(sink)?;
+
+        sink.event(ProgressEvent {
+            message: "phase=Prepare; preparing download".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        sink.event(ProgressEvent {
+            message: "phase=Fetch; downloading".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        sink.event(ProgressEvent {
+            message: "rcsb.request".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Request,
+        });
+        // A forced refetch asks RCSB whether the stored copy changed.
+        let stored = if options.force && self.store.project_exists(&project_path) {
+            stored_validators(&self.store.project_metadata_path("protein", id.as_str()))
+        } else {
+            StoredValidators::new()
+        };
+        let structure_url = crate::rcsb::RcsbHttpClient::structure_url(&id, format);
+        let metadata_url = crate::rcsb::RcsbHttpClient::metadata_url(&id);
+        let mut validators = stored.clone();
+        let start = std::time::Instant::now();
+        let structure_modified = match self
+            .rcsb
+            .download_structure_if_modified(&id, format, &temp_path, sink, &stored)?
+        {
+            Conditional::Modified((), fresh) => {
+                validators.remove(&structure_url);
+                validators.extend(fresh);
+                true
+            }
+            Conditional::NotModified => {
+                fs::copy(&project_path, &temp_path)
+                    .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+                false
+            }
+        };
+        let rcsb_meta = if has_metadata {
+            None
+        } else {
+            let stored =
+                if project_meta.as_std_path().exists() && project_raw.as_std_path().exists() {
+                    &stored
+                } else {
+                    &StoredValidators::new()
+                };
+            match self.rcsb.fetch_metadata_if_modified(&id, stored)? {
+                Conditional::Modified(mut rcsb_meta, fresh) => {
+                    validators.remove(&metadata_url);
+                    validators.extend(fresh);
+                    rcsb_meta.source_structure_url = structure_url.clone();
+                    Some(rcsb_meta)
+                }
+                Conditional::NotModified => None,
+            }
+        };
+        let fasta = if overrides.no_fasta {
+            None
+        } else {
+            self.rcsb.fetch_fasta(&id)?
+        };
+        let not_modified = !structure_modified && rcsb_meta.is_none();
+        if not_modified {
+            sink.event(ProgressEvent {
+                message: "rcsb.not_modified".to_string(),
+                elapsed: None,
+                level: ProgressLevel::Request,
+            });
+        }
+        let latency = start.elapsed().as_millis();
+        sink.event(ProgressEvent {
+            message: format!("rcsb.response latency_ms={latency}"),
+            elapsed: None,
+            level: ProgressLevel::Detail,
+        });
+
+        check_cancelled(sink)?;
+
+        sink.event(ProgressEvent {
+            message: "phase=Verify; validating package".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        if let Some(reason) = structure_file_defect(&temp_path, format)? {
+            // Nothing has reached the project or the cache yet.
+            let _ = fs::remove_file(&temp_path);
+            return Err(KiraError::InvalidDownload {
+                dataset: format!("protein:{}", id.as_str()),
+                reason,
+            });
+        }
+        let sequences = fasta.as_deref().map(parse_fasta).unwrap_or_default();
+        if let Some(fasta) = &fasta {
+            std::fs::write(&temp_fasta, fasta)
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        }
+        if let Some(rcsb_meta) = &rcsb_meta {
+            let meta_payload = RcsbMetadataFile::new(rcsb_meta, &sequences);
+            let meta_bytes = to_canonical_json(&meta_payload)?;
+            let raw_bytes = to_canonical_json(&rcsb_meta.raw_json)?;
+            std::fs::write(&temp_meta, &meta_bytes)
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            std::fs::write(&temp_raw, &raw_bytes)
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        }
+
+        check_cancelled(sink)?;
+
+        sink.event(ProgressEvent {
+            message: "phase=Store; writing files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let data_file = Utf8PathBuf::from_path_buf(temp_path)
+            .map_err(|_| KiraError::Filesystem("non-utf8 file path in dataset".to_string()))?;
+        Store::copy_file_atomic(&data_file, &project_path)?;
+        if rcsb_meta.is_some() {
+            let temp_meta = Utf8PathBuf::from_path_buf(temp_meta)
+                .map_err(|_| KiraError::Filesystem("non-utf8 file path in dataset".to_string()))?;
+            let temp_raw = Utf8PathBuf::from_path_buf(temp_raw)
+                .map_err(|_| KiraError::Filesystem("non-utf8 file path in dataset".to_string()))?;
+            Store::copy_file_atomic(&temp_meta, &project_meta)?;
+            Store::copy_file_atomic(&temp_raw, &project_raw)?;
+        }
+        let project_fasta = protein_fasta_path(&project_dir, &id);
+        let mut files = vec![project_path.file_name().unwrap_or_default().to_string()];
+        let mut urls = vec![structure_url];
+        if fasta.is_some() {
+            let temp_fasta = Utf8PathBuf::from_path_buf(temp_fasta)
+                .map_err(|_| KiraError::Filesystem("non-utf8 file path in dataset".to_string()))?;
+            Store::copy_file_atomic(&temp_fasta, &project_fasta)?;
+            files.push(project_fasta.file_name().unwrap_or_default().to_string());
+            urls.push(crate::rcsb::RcsbHttpClient::fasta_url(&id));
+        }
+        let mut download =
+            DownloadRecord::of_files(project_dir.as_std_path(), &files, urls, &Digests::new())?;
+        download.validators = validators;
+        let mut meta = self.project_metadata(
+            "rcsb",
+            "protein",
+            id.as_str(),
+            Some(format.to_string()),
+            project_path.as_str(),
+            &options,
+        );
+        meta.download = Some(download.clone());
+        let record_path = self.store.project_metadata_path("protein", id.as_str());
+        record_protein_formats(&mut meta, &record_path, &project_dir, &id);
+        Store::write_metadata(&record_path, &meta)?;
+
+        if !options.no_cache {
+            Store::copy_file_atomic(&project_path, &cache_path)?;
+            let (cache_meta, cache_raw) = rcsb_metadata_paths(&cache_dir);
+            Store::copy_file_atomic(&project_meta, &cache_meta)?;
+            Store::copy_file_atomic(&project_raw, &cache_raw)?;
+            if fasta.is_some() {
+                Store::copy_file_atomic(&project_fasta, &protein_fasta_path(&cache_dir, &id))?;
+            }
+            let mut meta = self.build_metadata(
+                "rcsb",
+                "protein",
+                id.as_str(),
+                Some(format.to_string()),
+                cache_path.as_str(),
+                None,
+            );
+            meta.download = Some(download.clone());
+            let record_path = self.store.cache_metadata_path("protein", id.as_str());
+            record_protein_formats(&mut meta, &record_path, &cache_dir, &id);
+            self.store.write_cache_metadata(&record_path, &meta)?;
+        }
+
+        Ok(FetchItemResult {
+            dataset_type: "protein".to_string(),
+            id: id.as_str().to_string(),
+            format: Some(format.to_string()),
+            source: "rcsb".to_string(),
+            action: if not_modified {
+                "not-modified"
+            } else if corrupt {
+                ACTION_REFETCHED_CORRUPT
+            } else {
+                "download"
+            }
+            .to_string(),
+            project_path: Some(project_path.to_string()),
+            cache_path: (!options.no_cache).then(|| cache_path.to_string()),
+            download: Some(download),
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
+        })
+    }
+
+    /// The RCSB entry JSON alone, without the structure file.
+    fn fetch_protein_metadata(
+        &self,
+        id: &ProteinId,
+        project_dir: &Utf8Path,
+        options: &FetchOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchItemResult, KiraError> {
+        let result = |action: &str| FetchItemResult {
+            dataset_type: "protein".to_string(),
+            id: id.as_str().to_string(),
+            format: None,
+            source: "rcsb".to_string(),
+            action: action.to_string(),
+            project_path: Some(project_dir.to_string()),
+            cache_path: None,
+            download: None,
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
+        };
+        if !options.force && self.has_project_record("protein", id.as_str()) {
+            return Ok(result("project"));
+        }
+        if options.dry_run {
+            return Ok(result("dry-run"));
+        }
+        sink.event(ProgressEvent {
+            message: "phase=Fetch; downloading entry metadata".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let rcsb_meta = self.rcsb.fetch_metadata(id)?;
+        check_cancelled(sink)?;
+        sink.event(ProgressEvent {
+            message: "phase=Store; writing files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let (meta_path, raw_path) = rcsb_metadata_paths(project_dir);
+        Store::write_bytes_atomic(
+            &meta_path,
+            &to_canonical_json(&RcsbMetadataFile::new(&rcsb_meta, &[]))?,
+        )?;
+        Store::write_bytes_atomic(&raw_path, &to_canonical_json(&rcsb_meta.raw_json)?)?;
+        self.write_metadata_only_record("rcsb", "protein", id.as_str(), project_dir, options)?;
+        Ok(result("metadata"))
+    }
+
+    fn fetch_genome_name(
+        &self,
+        name: AssemblyName,
+        overrides: &FetchOverrides,
+        options: FetchOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchItemResult, KiraError> {
+        let accession = self.resolve_genome_name(&name, overrides.exact, sink)?;
+        let dry_run = options.dry_run;
+        let result = self.fetch_genome_with_include(
+            accession.clone(),
+            overrides.genome_include(),
+            overrides.allow_missing_artifacts,
+            options,
+            sink,
+        )?;
+        if !dry_run {
+            self.record_assembly_name(&accession, &name)?;
+        }
+        Ok(result)
+    }
 
+    /// The accession `name` refers to: a built-in alias when there is one,
+    /// otherwise NCBI's current assembly by that name.
+    fn resolve_genome_name(
+        &self,
+        name: &AssemblyName,
+        exact: bool,
+        sink: &dyn ProgressSink,
+    ) -> Result<GenomeAccession, KiraError> {
         sink.event(ProgressEvent {
-            message: "phase=Prepare; preparing download".to_string(),
+            message: format!("phase=Resolve; genome name {name}"),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
+        if let Some(accession) = genome_alias(name.as_str(), exact) {
+            sink.event(ProgressEvent {
+                message: format!("phase=Resolve; {name} is {accession} (built-in alias)"),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            return Ok(accession);
+        }
+        let candidates = self.ncbi.find_assemblies(name, exact)?;
+        let chosen = select_assembly(name, exact, candidates)?;
         sink.event(ProgressEvent {
-            message: "rcsb.request".to_string(),
+            message: format!("phase=Resolve; {name} is {chosen}"),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
-        let start = std::time::Instant::now();
-        self.rcsb.download_structure(&id, format, &temp_path)?;
-        let mut rcsb_meta = self.rcsb.fetch_metadata(&id)?;
-        rcsb_meta.source_structure_url = crate::rcsb::RcsbHttpClient::structure_url(&id, format);
-        let latency = start.elapsed().as_millis();
+        chosen.accession.parse()
+    }
+
+    fn record_assembly_name(
+        &self,
+        accession: &GenomeAccession,
+        name: &AssemblyName,
+    ) -> Result<(), KiraError> {
+        let path = self
+            .store
+            .project_metadata_path("genome", accession.as_str());
+        let Some(mut meta) = fs::read(path.as_std_path())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Metadata>(&bytes).ok())
+        else {
+            return Ok(());
+        };
+        meta.assembly_name = Some(name.as_str().to_string());
+        Store::write_metadata(&path, &meta)
+    }
+
+    /// The accession of a locally stored genome known as `name`, for
+    /// lookups that should not reach the registry; falls back to the name.
+    fn local_genome_accession(&self, name: &AssemblyName) -> String {
+        if let Some(accession) = genome_alias(name.as_str(), false) {
+            return accession.as_str().to_string();
+        }
+        Store::list_metadata(self.store.project_root())
+            .unwrap_or_default()
+            .into_iter()
+            .find(|meta| {
+                meta.dataset_type == "genome"
+                    && meta
+                        .assembly_name
+                        .as_deref()
+                        .is_some_and(|stored| stored.eq_ignore_ascii_case(name.as_str()))
+            })
+            .map(|meta| meta.id)
+            .unwrap_or_else(|| name.as_str().to_string())
+    }
+
+    /// Downloads (or resumes) a genome package and records its checksum
+    /// beside it, returning the checksum.
+    fn download_genome_package(
+        &self,
+        accession: &GenomeAccession,
+        include: &[String],
+        zip_path: &Path,
+        digest_path: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
         sink.event(ProgressEvent {
-            message: format!("rcsb.response latency_ms={latency}"),
+            message: "phase=Fetch; downloading".to_string(),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
-
         sink.event(ProgressEvent {
-            message: "phase=Verify; validating package".to_string(),
+            message: "ncbi.request".to_string(),
             elapsed: None,
+            level: ProgressLevel::Request,
         });
-        let meta_payload = RcsbMetadataFile::from(&rcsb_meta);
-        let meta_bytes = serde_json::to_vec_pretty(&meta_payload)
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        let raw_bytes = serde_json::to_vec_pretty(&rcsb_meta.raw_json)
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        std::fs::write(&temp_meta, &meta_bytes)
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        std::fs::write(&temp_raw, &raw_bytes)
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-
+        let start = std::time::Instant::now();
+        let download = self
+            .ncbi
+            .download_genome(accession, include, zip_path, sink)?;
+        let latency = start.elapsed().as_millis();
         sink.event(ProgressEvent {
-            message: "phase=Store; writing files".to_string(),
+            message: format!("ncbi.response latency_ms={latency}"),
             elapsed: None,
+            level: ProgressLevel::Detail,
         });
-        let data_file = Utf8PathBuf::from_path_buf(temp_path)
-            .map_err(|_| KiraError::Filesystem("non-utf8 file path in dataset".to_string()))?;
-        Store::copy_file_atomic(&data_file, &project_path)?;
-        let (project_meta, project_raw) = rcsb_metadata_paths(&project_dir);
-        let temp_meta = Utf8PathBuf::from_path_buf(temp_meta)
-            .map_err(|_| KiraError::Filesystem("non-utf8 file path in dataset".to_string()))?;
-        let temp_raw = Utf8PathBuf::from_path_buf(temp_raw)
-            .map_err(|_| KiraError::Filesystem("non-utf8 file path in dataset".to_string()))?;
-        Store::copy_file_atomic(&temp_meta, &project_meta)?;
-        Store::copy_file_atomic(&temp_raw, &project_raw)?;
-        let meta = self.build_metadata(
-            "rcsb",
-            "protein",
-            id.as_str(),
-            Some(format.to_string()),
-            project_path.as_str(),
-        );
-        Store::write_metadata(
-            &self.store.project_metadata_path("protein", id.as_str()),
-            &meta,
-        )?;
-
-        if !options.no_cache {
-            Store::copy_file_atomic(&project_path, &cache_path)?;
-            let (cache_meta, cache_raw) = rcsb_metadata_paths(&cache_dir);
-            Store::copy_file_atomic(&project_meta, &cache_meta)?;
-            Store::copy_file_atomic(&project_raw, &cache_raw)?;
-            let meta = self.build_metadata(
-                "rcsb",
-                "protein",
-                id.as_str(),
-                Some(format.to_string()),
-                cache_path.as_str(),
-            );
-            Store::write_metadata(
-                &self.store.cache_metadata_path("protein", id.as_str()),
-                &meta,
-            )?;
+        if !zip_path.exists() {
+            return Err(KiraError::Filesystem(format!(
+                "genome download missing file: {}",
+                zip_path.display()
+            )));
         }
-
-        Ok(FetchItemResult {
-            dataset_type: "protein".to_string(),
-            id: id.as_str().to_string(),
-            format: Some(format.to_string()),
-            source: "rcsb".to_string(),
-            action: "download".to_string(),
-            project_path: Some(project_path.to_string()),
-            cache_path: (!options.no_cache).then(|| cache_path.to_string()),
-        })
+        if !download.is_zip {
+            remove_package(zip_path, digest_path)?;
+            return Err(KiraError::Filesystem(
+                "expected genome download to be a zip archive".to_string(),
+            ));
+        }
+        let digest = sha256_file(zip_path)?;
+        fs::write(digest_path, format!("{digest}\n"))
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        Ok(digest)
     }
 
     fn fetch_genome_with_include(
         &self,
         accession: GenomeAccession,
         include: Vec<String>,
+        allow_missing_artifacts: bool,
         options: FetchOptions,
         sink: &dyn ProgressSink,
     ) -> Result<FetchItemResult, KiraError> {
+        let options = self.cache_options("genome", options);
         sink.event(ProgressEvent {
             message: format!("phase=Resolve; genome {}", accession.as_str()),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
         if !options.dry_run {
             self.store.ensure_project_root()?;
             self.store.ensure_cache_root()?;
         }
+        let _lock = self.lock_dataset("genome", accession.as_str(), &options, sink)?;
 
         let project_dir = self.store.project_genome_dir(&accession);
         let cache_dir = self.store.cache_genome_dir(&accession);
 
-        if !options.force && self.store.project_exists(&project_dir) {
+        if options.metadata_only {
+            return self.fetch_genome_metadata(&accession, &project_dir, &options, sink);
+        }
+
+        if !options.force
+            && self.store.project_exists(&project_dir)
+            && !self.store.is_metadata_only("genome", accession.as_str())
+        {
             sink.event(ProgressEvent {
                 message: "phase=Store; already in project store".to_string(),
                 elapsed: None,
+                level: ProgressLevel::Phase,
             });
             return Ok(FetchItemResult {
                 dataset_type: "genome".to_string(),
@@ -1492,23 +6963,40 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                     .as_std_path()
                     .exists()
                     .then(|| cache_dir.to_string()),
+                download: Store::recorded_download(
+                    &self
+                        .store
+                        .project_metadata_path("genome", accession.as_str()),
+                ),
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
+        let corrupt = self.evict_corrupt_cache("genome", accession.as_str(), &options, sink)?;
         if !options.force && self.store.cache_exists(&cache_dir) {
             sink.event(ProgressEvent {
                 message: "phase=Store; using cached dataset".to_string(),
                 elapsed: None,
+                level: ProgressLevel::Phase,
             });
+            let download = Store::recorded_download(
+                &self.store.cache_metadata_path("genome", accession.as_str()),
+            );
             if !options.dry_run {
-                Store::copy_dir_atomic(&cache_dir, &project_dir)?;
-                let meta = self.build_metadata(
+                let mut meta = self.project_metadata(
                     "ncbi",
                     "genome",
                     accession.as_str(),
                     None,
                     project_dir.as_str(),
+                    &options,
                 );
+                meta.download = download.clone();
+                let carried = read_carried(&project_dir, &[GENOME_REPORT]);
+                Store::materialize_dir(&cache_dir, &project_dir, &meta.downloaded_at)?;
+                write_carried(&project_dir, &carried)?;
                 Store::write_metadata(
                     &self
                         .store
@@ -1524,6 +7012,10 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 action: "cache".to_string(),
                 project_path: Some(project_dir.to_string()),
                 cache_path: Some(cache_dir.to_string()),
+                download,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
@@ -1536,72 +7028,142 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 action: "download".to_string(),
                 project_path: Some(project_dir.to_string()),
                 cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+                download: None,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
-        let temp_dir = tempfile::Builder::new()
-            .prefix("kira-bm-genome")
-            .tempdir_in(self.store.project_root().as_std_path())
+        let temp_dir = StagingDir::create_in(
+            self.store.project_root().as_std_path(),
+            "kira-bm-genome",
+            accession.as_str(),
+        )
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let package_dir = if options.no_cache {
+            Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf())
+                .map_err(|_| KiraError::Filesystem("invalid temp dir".to_string()))?
+        } else {
+            self.store.cache_genome_package_dir(&accession, &include)
+        };
+        fs::create_dir_all(package_dir.as_std_path())
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        let zip_path = temp_dir.path().join("dataset.zip");
+        let zip_path = package_dir.join("dataset.zip").into_std_path_buf();
+        let digest_path = package_dir.join("dataset.zip.sha256").into_std_path_buf();
+
+        check_cancelled(sink)?;
 
         sink.event(ProgressEvent {
             message: "phase=Prepare; preparing download".to_string(),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
-        sink.event(ProgressEvent {
-            message: "ncbi.request".to_string(),
-            elapsed: None,
-        });
-        let start = std::time::Instant::now();
-        let download = self.ncbi.download_genome(&accession, &include, &zip_path)?;
-        let latency = start.elapsed().as_millis();
-        sink.event(ProgressEvent {
-            message: format!("ncbi.response latency_ms={latency}"),
-            elapsed: None,
-        });
-        if !zip_path.exists() {
-            return Err(KiraError::Filesystem(format!(
-                "genome download missing file: {}",
-                zip_path.display()
-            )));
-        }
-
-        if !download.is_zip {
-            return Err(KiraError::Filesystem(
-                "expected genome download to be a zip archive".to_string(),
-            ));
+        // A package an earlier run downloaded in full is reused while it
+        // still matches its checksum; one without a checksum was cut off and
+        // is resumed.
+        let kept = fs::read_to_string(&digest_path)
+            .ok()
+            .is_some_and(|recorded| {
+                sha256_file(&zip_path).is_ok_and(|digest| digest == recorded.trim())
+            });
+        if !kept && digest_path.exists() {
+            sink.event(ProgressEvent {
+                message:
+                    "phase=Prepare; kept package no longer matches its checksum, downloading again"
+                        .to_string(),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            remove_package(&zip_path, &digest_path)?;
         }
+        let mut package_sha256 = if kept {
+            sink.event(ProgressEvent {
+                message: "phase=Fetch; reusing the package an earlier run downloaded".to_string(),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            fs::read_to_string(&digest_path)
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?
+                .trim()
+                .to_string()
+        } else {
+            self.download_genome_package(&accession, &include, &zip_path, &digest_path, sink)?
+        };
+        check_cancelled(sink)?;
         sink.event(ProgressEvent {
             message: "phase=Verify; validating package".to_string(),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
-        crate::fs_util::validate_zip(&zip_path)?;
+        if let Err(err) = crate::fs_util::validate_zip(&zip_path) {
+            // A resumed download can splice together two different packages;
+            // start over once rather than fail on every run.
+            sink.event(ProgressEvent {
+                message: format!("phase=Verify; package is corrupt ({err}), downloading again"),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            remove_package(&zip_path, &digest_path)?;
+            package_sha256 =
+                self.download_genome_package(&accession, &include, &zip_path, &digest_path, sink)?;
+            crate::fs_util::validate_zip(&zip_path)?;
+        }
         let extract_dir = temp_dir.path().join("extract");
         fs::create_dir_all(&extract_dir).map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        crate::fs_util::extract_zip(&zip_path, &extract_dir)?;
+        let digests = crate::fs_util::extract_zip(&zip_path, &extract_dir, &self.zip_limits)?;
+        let mut genome_meta = check_genome_catalog(
+            &accession,
+            &include,
+            &extract_dir,
+            allow_missing_artifacts,
+            sink,
+        )?;
+        genome_meta.package_sha256 = Some(package_sha256);
+        let genome_meta_bytes = to_canonical_json(&genome_meta)?;
+        fs::write(extract_dir.join("metadata.json"), genome_meta_bytes)
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let carried = read_carried(&project_dir, &[GENOME_REPORT]);
+        write_carried(
+            Utf8Path::from_path(&extract_dir)
+                .ok_or_else(|| KiraError::Filesystem("invalid temp dir".to_string()))?,
+            &carried,
+        )?;
 
-        if project_dir.as_std_path().exists() {
-            fs::remove_dir_all(project_dir.as_std_path())
-                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        }
-        if let Some(parent) = project_dir.parent() {
-            fs::create_dir_all(parent.as_std_path())
-                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        }
+        check_cancelled(sink)?;
         sink.event(ProgressEvent {
             message: "phase=Store; writing files".to_string(),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
         atomic_rename_dir(&extract_dir, project_dir.as_std_path())
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        let meta = self.build_metadata(
+        if !options.no_cache {
+            fs::remove_dir_all(package_dir.as_std_path())
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        }
+        let download = DownloadRecord::of_dir(
+            project_dir.as_std_path(),
+            vec![crate::ncbi::NcbiHttpClient::genome_url(
+                &accession, &include,
+            )?],
+            &digests,
+        )?;
+        manifest::write(
+            "genome",
+            accession.as_str(),
+            project_dir.as_std_path(),
+            &download,
+        )?;
+        let mut meta = self.project_metadata(
             "ncbi",
             "genome",
             accession.as_str(),
             None,
             project_dir.as_str(),
+            &options,
         );
+        meta.download = Some(download.clone());
         Store::write_metadata(
             &self
                 .store
@@ -1611,28 +7173,303 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
 
         if !options.no_cache {
             Store::copy_dir_atomic(&project_dir, &cache_dir)?;
-            let meta = self.build_metadata(
+            let mut meta = self.build_metadata(
                 "ncbi",
                 "genome",
                 accession.as_str(),
                 None,
                 cache_dir.as_str(),
+                None,
             );
-            Store::write_metadata(
+            meta.download = Some(download.clone());
+            self.store.write_cache_metadata(
                 &self.store.cache_metadata_path("genome", accession.as_str()),
                 &meta,
             )?;
         }
 
-        Ok(FetchItemResult {
-            dataset_type: "genome".to_string(),
-            id: accession.as_str().to_string(),
-            format: None,
-            source: "ncbi".to_string(),
-            action: "download".to_string(),
-            project_path: Some(project_dir.to_string()),
-            cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
-        })
+        Ok(FetchItemResult {
+            dataset_type: "genome".to_string(),
+            id: accession.as_str().to_string(),
+            format: None,
+            source: "ncbi".to_string(),
+            action: if corrupt {
+                ACTION_REFETCHED_CORRUPT
+            } else {
+                "download"
+            }
+            .to_string(),
+            project_path: Some(project_dir.to_string()),
+            cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+            download: Some(download),
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
+        })
+    }
+
+    /// The assembly's dataset report alone, without the sequence package.
+    fn fetch_genome_metadata(
+        &self,
+        accession: &GenomeAccession,
+        project_dir: &Utf8Path,
+        options: &FetchOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchItemResult, KiraError> {
+        let result = |action: &str| FetchItemResult {
+            dataset_type: "genome".to_string(),
+            id: accession.as_str().to_string(),
+            format: None,
+            source: "ncbi".to_string(),
+            action: action.to_string(),
+            project_path: Some(project_dir.to_string()),
+            cache_path: None,
+            download: None,
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
+        };
+        if !options.force && self.has_project_record("genome", accession.as_str()) {
+            return Ok(result("project"));
+        }
+        if options.dry_run {
+            return Ok(result("dry-run"));
+        }
+        let temp_dir = StagingDir::create_in(
+            self.store.project_root().as_std_path(),
+            "kira-bm-genome",
+            accession.as_str(),
+        )
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        sink.event(ProgressEvent {
+            message: "phase=Fetch; downloading dataset report".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        self.ncbi
+            .download_genome_report(accession, &temp_dir.path().join(GENOME_REPORT))?;
+        check_cancelled(sink)?;
+        sink.event(ProgressEvent {
+            message: "phase=Store; writing files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        temp_dir
+            .persist(project_dir.as_std_path())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        self.write_metadata_only_record(
+            "ncbi",
+            "genome",
+            accession.as_str(),
+            project_dir,
+            options,
+        )?;
+        Ok(result("metadata"))
+    }
+
+    /// A genome from Ensembl's FTP site. A release-less specifier is pinned
+    /// to the current release before anything else, so the stored id and
+    /// directory always name a concrete release.
+    fn fetch_ensembl_genome(
+        &self,
+        genome: EnsemblGenome,
+        overrides: &FetchOverrides,
+        options: FetchOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchItemResult, KiraError> {
+        let options = self.cache_options("genome", options);
+        sink.event(ProgressEvent {
+            message: format!("phase=Resolve; genome ensembl:{genome}"),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let client = self.ensembl_client()?;
+        let release = match genome.release() {
+            Some(release) => release,
+            None => {
+                let release = client.current_release()?;
+                sink.event(ProgressEvent {
+                    message: format!("phase=Resolve; current Ensembl release is {release}"),
+                    elapsed: None,
+                    level: ProgressLevel::Phase,
+                });
+                release
+            }
+        };
+        let species = genome.species();
+        let id = format!("ensembl:{}", genome.pinned(release));
+        let include = overrides.ensembl_include();
+        let _lock = self.lock_dataset("genome", &id, &options, sink)?;
+
+        let project_dir = self.store.project_ensembl_dir(species, release);
+        let cache_dir = self.store.cache_ensembl_dir(species, release);
+        let result =
+            |action: &str, download: Option<DownloadRecord>, cached: bool| FetchItemResult {
+                dataset_type: "genome".to_string(),
+                id: id.clone(),
+                format: None,
+                source: "ensembl".to_string(),
+                action: action.to_string(),
+                project_path: Some(project_dir.to_string()),
+                cache_path: cached.then(|| cache_dir.to_string()),
+                download,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
+            };
+
+        if !options.force && self.store.project_exists(&project_dir) {
+            sink.event(ProgressEvent {
+                message: "phase=Store; already in project store".to_string(),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            return Ok(result(
+                "project",
+                Store::recorded_download(&self.store.project_metadata_path("genome", &id)),
+                cache_dir.as_std_path().exists(),
+            ));
+        }
+
+        let corrupt = self.evict_corrupt_cache("genome", &id, &options, sink)?;
+        if !options.force && self.store.cache_exists(&cache_dir) {
+            sink.event(ProgressEvent {
+                message: "phase=Store; using cached dataset".to_string(),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            let download = Store::recorded_download(&self.store.cache_metadata_path("genome", &id));
+            if !options.dry_run {
+                let mut meta = self.project_metadata(
+                    "ensembl",
+                    "genome",
+                    &id,
+                    None,
+                    project_dir.as_str(),
+                    &options,
+                );
+                meta.download = download.clone();
+                meta.assembly_name =
+                    fs::read(self.store.cache_metadata_path("genome", &id).as_std_path())
+                        .ok()
+                        .and_then(|bytes| serde_json::from_slice::<Metadata>(&bytes).ok())
+                        .and_then(|cached| cached.assembly_name);
+                Store::materialize_dir(&cache_dir, &project_dir, &meta.downloaded_at)?;
+                Store::write_metadata(&self.store.project_metadata_path("genome", &id), &meta)?;
+            }
+            return Ok(result("cache", download, true));
+        }
+
+        if options.dry_run {
+            return Ok(result("download", None, !options.no_cache));
+        }
+
+        let temp_dir = StagingDir::create_in(
+            self.store.project_root().as_std_path(),
+            "kira-bm-genome",
+            &format!("ensembl-{species}-{release}"),
+        )
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let stage_dir = temp_dir.path().join("genome");
+        fs::create_dir_all(&stage_dir).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let mut files = BTreeMap::new();
+        let mut urls = Vec::new();
+        let mut digests = Digests::new();
+        let mut assembly = None;
+        for file in &include {
+            check_cancelled(sink)?;
+            let listing = client.locate(species, release, *file)?;
+            sink.event(ProgressEvent {
+                message: format!("phase=Fetch; downloading {}", listing.name),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            let destination = stage_dir.join(&listing.name);
+            let (_, digest) = client.download(&listing.url, &destination, sink)?;
+            sink.event(ProgressEvent {
+                message: format!("phase=Verify; checking {}", listing.name),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            check_gzip(&destination)?;
+            assembly.get_or_insert(listing.assembly);
+            digests.insert(listing.name.clone(), digest);
+            files.insert(file.to_string(), listing.name);
+            urls.push(listing.url);
+        }
+        let meta_file = EnsemblMetadataFile {
+            registry: "ensembl".to_string(),
+            dataset_type: "genome".to_string(),
+            species: species.to_string(),
+            release,
+            assembly: assembly.clone(),
+            include: include.clone(),
+            files,
+            downloaded_at: iso_timestamp(),
+        };
+        fs::write(
+            stage_dir.join("metadata.json"),
+            to_canonical_json(&meta_file)?,
+        )
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+
+        check_cancelled(sink)?;
+        sink.event(ProgressEvent {
+            message: "phase=Store; writing files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        atomic_rename_dir(&stage_dir, project_dir.as_std_path())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let download = DownloadRecord::of_dir(project_dir.as_std_path(), urls, &digests)?;
+        manifest::write("genome", &id, project_dir.as_std_path(), &download)?;
+        let mut meta = self.project_metadata(
+            "ensembl",
+            "genome",
+            &id,
+            None,
+            project_dir.as_str(),
+            &options,
+        );
+        meta.download = Some(download.clone());
+        meta.assembly_name = assembly.clone();
+        Store::write_metadata(&self.store.project_metadata_path("genome", &id), &meta)?;
+
+        if !options.no_cache {
+            Store::copy_dir_atomic(&project_dir, &cache_dir)?;
+            let mut meta =
+                self.build_metadata("ensembl", "genome", &id, None, cache_dir.as_str(), None);
+            meta.download = Some(download.clone());
+            meta.assembly_name = assembly;
+            self.store
+                .write_cache_metadata(&self.store.cache_metadata_path("genome", &id), &meta)?;
+        }
+
+        let action = if corrupt {
+            ACTION_REFETCHED_CORRUPT
+        } else {
+            "download"
+        };
+        Ok(result(action, Some(download), !options.no_cache))
+    }
+
+    /// The id of the newest locally stored release of an Ensembl species
+    /// when `genome` names no release, so lookups stay off the network.
+    fn local_ensembl_id(&self, genome: &EnsemblGenome) -> String {
+        if genome.release().is_some() {
+            return format!("ensembl:{genome}");
+        }
+        let prefix = format!("ensembl:{}@", genome.species());
+        Store::list_metadata(self.store.project_root())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|meta| meta.dataset_type == "genome")
+            .filter_map(|meta| meta.id.strip_prefix(&prefix)?.parse::<u32>().ok())
+            .max()
+            .map_or_else(
+                || format!("ensembl:{genome}"),
+                |release| format!("ensembl:{}", genome.pinned(release)),
+            )
     }
 
     fn fetch_srr(
@@ -1640,25 +7477,37 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
         id: SrrId,
         format: SrrFormat,
         paired: bool,
+        download: &SrrDownloadOptions,
         options: FetchOptions,
         sink: &dyn ProgressSink,
     ) -> Result<FetchItemResult, KiraError> {
+        let options = self.cache_options("srr", options);
         sink.event(ProgressEvent {
             message: format!("phase=Resolve; srr {}", id.as_str()),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
         if !options.dry_run {
             self.store.ensure_project_root()?;
             self.store.ensure_cache_root()?;
         }
+        let _lock = self.lock_dataset("srr", id.as_str(), &options, sink)?;
 
         let project_dir = self.store.project_srr_dir(&id);
         let cache_dir = self.store.cache_srr_dir(&id);
 
-        if !options.force && self.store.project_exists(&project_dir) {
+        if options.metadata_only {
+            return self.fetch_srr_metadata(&id, &project_dir, &options, sink);
+        }
+
+        if !options.force
+            && self.store.project_exists(&project_dir)
+            && !self.store.is_metadata_only("srr", id.as_str())
+        {
             sink.event(ProgressEvent {
                 message: "phase=Store; already in project store".to_string(),
                 elapsed: None,
+                level: ProgressLevel::Phase,
             });
             return Ok(FetchItemResult {
                 dataset_type: "srr".to_string(),
@@ -1671,23 +7520,37 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                     .as_std_path()
                     .exists()
                     .then(|| cache_dir.to_string()),
+                download: Store::recorded_download(
+                    &self.store.project_metadata_path("srr", id.as_str()),
+                ),
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
+        let corrupt = self.evict_corrupt_cache("srr", id.as_str(), &options, sink)?;
         if !options.force && self.store.cache_exists(&cache_dir) {
             sink.event(ProgressEvent {
                 message: "phase=Store; using cached dataset".to_string(),
                 elapsed: None,
+                level: ProgressLevel::Phase,
             });
+            let download =
+                Store::recorded_download(&self.store.cache_metadata_path("srr", id.as_str()));
             if !options.dry_run {
-                Store::copy_dir_atomic(&cache_dir, &project_dir)?;
-                let meta = self.build_metadata(
+                let mut meta = self.project_metadata(
                     "ncbi",
                     "srr",
                     id.as_str(),
                     Some(format.to_string()),
                     project_dir.as_str(),
+                    &options,
                 );
+                meta.download = download.clone();
+                let carried = read_carried(&project_dir, &[SRR_RUN_INFO]);
+                Store::materialize_dir(&cache_dir, &project_dir, &meta.downloaded_at)?;
+                write_carried(&project_dir, &carried)?;
                 Store::write_metadata(
                     &self.store.project_metadata_path("srr", id.as_str()),
                     &meta,
@@ -1701,6 +7564,10 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 action: "cache".to_string(),
                 project_path: Some(project_dir.to_string()),
                 cache_path: Some(cache_dir.to_string()),
+                download,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
@@ -1713,43 +7580,102 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 action: "download".to_string(),
                 project_path: Some(project_dir.to_string()),
                 cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+                download: None,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
-        let temp_dir = tempfile::Builder::new()
-            .prefix("kira-bm-srr")
-            .tempdir_in(self.store.project_root().as_std_path())
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let temp_dir = StagingDir::create_in(
+            self.store.project_root().as_std_path(),
+            "kira-bm-srr",
+            id.as_str(),
+        )
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
         let staging_dir = temp_dir.path().join("staging");
         fs::create_dir_all(&staging_dir).map_err(|err| KiraError::Filesystem(err.to_string()))?;
 
+        check_cancelled(sink)?;
+
         sink.event(ProgressEvent {
             message: "phase=Prepare; preparing download".to_string(),
             elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        sink.event(ProgressEvent {
+            message: "phase=Fetch; downloading".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
         });
         sink.event(ProgressEvent {
             message: "ncbi.request".to_string(),
             elapsed: None,
+            level: ProgressLevel::Request,
         });
         let start = std::time::Instant::now();
-        let fastq_files = self.srr.download_fastq(&id, paired, &staging_dir)?;
+        // fasterq-dump's scratch files stay in the project's temp area
+        // unless the run asked for another disk.
+        let tool_options = SrrDownloadOptions {
+            threads: download.threads,
+            temp_dir: Some(
+                download
+                    .temp_dir
+                    .clone()
+                    .unwrap_or_else(|| temp_dir.path().join("scratch")),
+            ),
+        };
+        let fastq_files = self
+            .srr
+            .download_fastq(&id, paired, &staging_dir, &tool_options)?;
         let detected_paired = !paired && detect_paired_fastq(&fastq_files);
         let paired = paired || detected_paired;
         let latency = start.elapsed().as_millis();
         sink.event(ProgressEvent {
             message: format!("ncbi.response latency_ms={latency}"),
             elapsed: None,
+            level: ProgressLevel::Detail,
         });
+        // The reads are what was asked for; a run without ENA's record is
+        // stored without it.
+        let run_info = match self.srr.fetch_run_info(&id) {
+            Ok(record) => Some(SrrRunInfo::from_ena(&record)),
+            Err(err) => {
+                sink.event(ProgressEvent {
+                    message: format!("srr.run_info_unavailable {} {err}", id.as_str()),
+                    elapsed: None,
+                    level: ProgressLevel::Phase,
+                });
+                None
+            }
+        };
+
+        check_cancelled(sink)?;
 
         sink.event(ProgressEvent {
             message: "phase=Verify; validating package".to_string(),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
 
         let normalized_dir = temp_dir.path().join("normalized");
         fs::create_dir_all(&normalized_dir)
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
         let normalized_fastq = normalize_fastq_files(&fastq_files, paired, &normalized_dir)?;
+        // Mate files hold one read per spot each, so the first one counts.
+        let fastq_read_count = count_fastq_records(&normalized_fastq[0])?;
+        if let Some(expected) = run_info.as_ref().and_then(|info| info.read_count)
+            && read_count_differs(fastq_read_count, expected)
+        {
+            sink.event(ProgressEvent {
+                message: format!(
+                    "srr.read_count_mismatch {} {fastq_read_count} {expected}",
+                    id.as_str()
+                ),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+        }
 
         let output_files = match format {
             SrrFormat::Fastq => normalized_fastq.clone(),
@@ -1764,9 +7690,12 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
             }
         };
 
+        check_cancelled(sink)?;
+
         sink.event(ProgressEvent {
             message: "phase=Store; writing files".to_string(),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
 
         for file in &output_files {
@@ -1781,7 +7710,14 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
             Store::copy_file_atomic(&utf8, &target)?;
         }
 
-        let tools = self.srr.tool_info();
+        let tools = SrrToolsRecord {
+            tools: self.srr.tool_info(),
+            threads: download.threads,
+            temp_dir: download
+                .temp_dir
+                .as_ref()
+                .map(|dir| dir.to_string_lossy().into_owned()),
+        };
         let metadata = SrrMetadataFile {
             registry: "ncbi".to_string(),
             dataset_type: "srr".to_string(),
@@ -1790,6 +7726,8 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
             paired,
             downloaded_at: iso_timestamp(),
             tools,
+            run_info,
+            fastq_read_count,
             source_fastq: if format == SrrFormat::Fasta {
                 Some(
                     normalized_fastq
@@ -1810,26 +7748,42 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
         };
         write_srr_metadata(&project_dir, &metadata)?;
 
-        let meta = self.build_metadata(
+        let download = DownloadRecord::of_files(
+            project_dir.as_std_path(),
+            &output_files
+                .iter()
+                .filter_map(|file| file.file_name())
+                .map(|name| name.to_string_lossy().to_string())
+                .collect::<Vec<_>>(),
+            Vec::new(),
+            &Digests::new(),
+        )?;
+        manifest::write("srr", id.as_str(), project_dir.as_std_path(), &download)?;
+        let mut meta = self.project_metadata(
             "ncbi",
             "srr",
             id.as_str(),
             Some(format.to_string()),
             project_dir.as_str(),
+            &options,
         );
+        meta.download = Some(download.clone());
         Store::write_metadata(&self.store.project_metadata_path("srr", id.as_str()), &meta)?;
 
         if !options.no_cache {
             Store::copy_dir_atomic(&project_dir, &cache_dir)?;
             write_srr_metadata(&cache_dir, &metadata)?;
-            let meta = self.build_metadata(
+            let mut meta = self.build_metadata(
                 "ncbi",
                 "srr",
                 id.as_str(),
                 Some(format.to_string()),
                 cache_dir.as_str(),
+                None,
             );
-            Store::write_metadata(&self.store.cache_metadata_path("srr", id.as_str()), &meta)?;
+            meta.download = Some(download.clone());
+            self.store
+                .write_cache_metadata(&self.store.cache_metadata_path("srr", id.as_str()), &meta)?;
         }
 
         Ok(FetchItemResult {
@@ -1837,34 +7791,143 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
             id: id.as_str().to_string(),
             format: Some(format.to_string()),
             source: "ncbi".to_string(),
-            action: "download".to_string(),
+            action: if corrupt {
+                ACTION_REFETCHED_CORRUPT
+            } else {
+                "download"
+            }
+            .to_string(),
             project_path: Some(project_dir.to_string()),
             cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+            download: Some(download),
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
         })
     }
 
+    /// The UniProt entry JSON alone, without the FASTA sequence.
+    fn fetch_uniprot_metadata(
+        &self,
+        id: &UniprotId,
+        project_dir: &Utf8Path,
+        options: &FetchOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchItemResult, KiraError> {
+        let result = |action: &str| FetchItemResult {
+            dataset_type: "uniprot".to_string(),
+            id: id.as_str().to_string(),
+            format: None,
+            source: "uniprot".to_string(),
+            action: action.to_string(),
+            project_path: Some(project_dir.to_string()),
+            cache_path: None,
+            download: None,
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
+        };
+        if !options.force && self.has_project_record("uniprot", id.as_str()) {
+            return Ok(result("project"));
+        }
+        if options.dry_run {
+            return Ok(result("dry-run"));
+        }
+        sink.event(ProgressEvent {
+            message: "phase=Fetch; downloading entry metadata".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let record = self.uniprot.fetch(id)?;
+        check_cancelled(sink)?;
+        sink.event(ProgressEvent {
+            message: "phase=Store; writing files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        write_uniprot_metadata(project_dir, &record)?;
+        self.write_metadata_only_record("uniprot", "uniprot", id.as_str(), project_dir, options)?;
+        Ok(result("metadata"))
+    }
+
+    /// The ENA run report alone, without the reads.
+    fn fetch_srr_metadata(
+        &self,
+        id: &SrrId,
+        project_dir: &Utf8Path,
+        options: &FetchOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchItemResult, KiraError> {
+        let result = |action: &str| FetchItemResult {
+            dataset_type: "srr".to_string(),
+            id: id.as_str().to_string(),
+            format: None,
+            source: "ncbi".to_string(),
+            action: action.to_string(),
+            project_path: Some(project_dir.to_string()),
+            cache_path: None,
+            download: None,
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
+        };
+        if !options.force && self.has_project_record("srr", id.as_str()) {
+            return Ok(result("project"));
+        }
+        if options.dry_run {
+            return Ok(result("dry-run"));
+        }
+        sink.event(ProgressEvent {
+            message: "phase=Fetch; downloading run info".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let run_info = self.srr.fetch_run_info(id)?;
+        check_cancelled(sink)?;
+        sink.event(ProgressEvent {
+            message: "phase=Store; writing files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        Store::write_bytes_atomic(
+            &project_dir.join(SRR_RUN_INFO),
+            &to_canonical_json(&run_info)?,
+        )?;
+        self.write_metadata_only_record("ncbi", "srr", id.as_str(), project_dir, options)?;
+        Ok(result("metadata"))
+    }
+
     fn fetch_uniprot(
         &self,
         id: UniprotId,
         options: FetchOptions,
         sink: &dyn ProgressSink,
     ) -> Result<FetchItemResult, KiraError> {
+        let options = self.cache_options("uniprot", options);
         sink.event(ProgressEvent {
             message: format!("phase=Resolve; uniprot {}", id.as_str()),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
         if !options.dry_run {
             self.store.ensure_project_root()?;
             self.store.ensure_cache_root()?;
         }
+        let _lock = self.lock_dataset("uniprot", id.as_str(), &options, sink)?;
 
         let project_dir = self.store.project_uniprot_dir(&id);
         let cache_dir = self.store.cache_uniprot_dir(&id);
 
-        if !options.force && self.store.project_exists(&project_dir) {
+        if options.metadata_only {
+            return self.fetch_uniprot_metadata(&id, &project_dir, &options, sink);
+        }
+
+        let has_metadata = self.store.is_metadata_only("uniprot", id.as_str());
+        if !options.force && self.store.project_exists(&project_dir) && !has_metadata {
             sink.event(ProgressEvent {
                 message: "phase=Store; already in project store".to_string(),
                 elapsed: None,
+                level: ProgressLevel::Phase,
             });
             return Ok(FetchItemResult {
                 dataset_type: "uniprot".to_string(),
@@ -1877,23 +7940,35 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                     .as_std_path()
                     .exists()
                     .then(|| cache_dir.to_string()),
+                download: Store::recorded_download(
+                    &self.store.project_metadata_path("uniprot", id.as_str()),
+                ),
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
+        let corrupt = self.evict_corrupt_cache("uniprot", id.as_str(), &options, sink)?;
         if !options.force && self.store.cache_exists(&cache_dir) {
             sink.event(ProgressEvent {
                 message: "phase=Store; using cached dataset".to_string(),
                 elapsed: None,
+                level: ProgressLevel::Phase,
             });
+            let download =
+                Store::recorded_download(&self.store.cache_metadata_path("uniprot", id.as_str()));
             if !options.dry_run {
-                Store::copy_dir_atomic(&cache_dir, &project_dir)?;
-                let meta = self.build_metadata(
+                let mut meta = self.project_metadata(
                     "uniprot",
                     "uniprot",
                     id.as_str(),
                     None,
                     project_dir.as_str(),
+                    &options,
                 );
+                meta.download = download.clone();
+                Store::materialize_dir(&cache_dir, &project_dir, &meta.downloaded_at)?;
                 Store::write_metadata(
                     &self.store.project_metadata_path("uniprot", id.as_str()),
                     &meta,
@@ -1907,6 +7982,10 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 action: "cache".to_string(),
                 project_path: Some(project_dir.to_string()),
                 cache_path: Some(cache_dir.to_string()),
+                download,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
@@ -1919,61 +7998,126 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
                 action: "download".to_string(),
                 project_path: Some(project_dir.to_string()),
                 cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+                download: None,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
             });
         }
 
-        let temp_dir = tempfile::Builder::new()
-            .prefix("kira-bm-uniprot")
-            .tempdir_in(self.store.project_root().as_std_path())
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let temp_dir = StagingDir::create_in(
+            self.store.project_root().as_std_path(),
+            "kira-bm-uniprot",
+            id.as_str(),
+        )
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
         let staging_dir = temp_dir.path().join("dataset");
         fs::create_dir_all(&staging_dir).map_err(|err| KiraError::Filesystem(err.to_string()))?;
 
+        check_cancelled(sink)?;
+
         sink.event(ProgressEvent {
             message: "phase=Prepare; preparing download".to_string(),
             elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        sink.event(ProgressEvent {
+            message: "phase=Fetch; downloading".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
         });
         sink.event(ProgressEvent {
             message: "uniprot.request".to_string(),
             elapsed: None,
+            level: ProgressLevel::Request,
         });
         let start = std::time::Instant::now();
-        let record = self.uniprot.fetch(&id)?;
+        // A metadata-only copy already holds the entry JSON.
+        let stored = has_metadata
+            .then(|| read_carried(&project_dir, &UNIPROT_METADATA))
+            .filter(|carried| carried.len() == UNIPROT_METADATA.len());
+        let staged = Utf8Path::from_path(&staging_dir)
+            .ok_or_else(|| KiraError::Filesystem("invalid temp dir".to_string()))?;
+        let mut validators = StoredValidators::new();
+        let mut not_modified = false;
+        let fasta = match &stored {
+            Some(carried) => {
+                write_carried(staged, carried)?;
+                self.uniprot.fetch_fasta(&id)?
+            }
+            None => {
+                // A forced refetch of a complete copy asks UniProt whether
+                // either part changed.
+                let fasta_path = project_dir.join(format!("{}.fasta", id.as_str()));
+                let previous = read_carried(&project_dir, &UNIPROT_METADATA);
+                let current = if options.force
+                    && previous.len() == UNIPROT_METADATA.len()
+                    && fasta_path.as_std_path().is_file()
+                {
+                    stored_validators(&self.store.project_metadata_path("uniprot", id.as_str()))
+                } else {
+                    StoredValidators::new()
+                };
+                match self.uniprot.fetch_if_modified(&id, &current)? {
+                    Conditional::Modified(record, fresh) => {
+                        validators = fresh;
+                        write_uniprot_metadata(staged, &record)?;
+                        record.fasta
+                    }
+                    Conditional::NotModified => {
+                        sink.event(ProgressEvent {
+                            message: "uniprot.not_modified".to_string(),
+                            elapsed: None,
+                            level: ProgressLevel::Request,
+                        });
+                        not_modified = true;
+                        validators = current;
+                        write_carried(staged, &previous)?;
+                        fs::read_to_string(fasta_path.as_std_path())
+                            .map_err(|err| KiraError::Filesystem(err.to_string()))?
+                    }
+                }
+            }
+        };
         let latency = start.elapsed().as_millis();
         sink.event(ProgressEvent {
             message: format!("uniprot.response latency_ms={latency}"),
             elapsed: None,
+            level: ProgressLevel::Detail,
         });
 
+        check_cancelled(sink)?;
+
         sink.event(ProgressEvent {
             message: "phase=Store; writing files".to_string(),
             elapsed: None,
+            level: ProgressLevel::Phase,
         });
 
-        let fasta_path = staging_dir.join(format!("{}.fasta", id.as_str()));
-        fs::write(&fasta_path, record.fasta.as_bytes())
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-
-        let meta_path = staging_dir.join("metadata.json");
-        let meta_bytes = serde_json::to_vec_pretty(&record.metadata)
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        fs::write(&meta_path, &meta_bytes).map_err(|err| KiraError::Filesystem(err.to_string()))?;
-
-        let raw_path = staging_dir.join("raw.json");
-        let raw_bytes = serde_json::to_vec_pretty(&record.raw_json)
+        let mut source_urls = vec![UniprotHttpClient::fasta_url(&id)];
+        if stored.is_none() {
+            source_urls.insert(0, UniprotHttpClient::metadata_url(&id));
+        }
+        let fasta_name = format!("{}.fasta", id.as_str());
+        let fasta_digest = write_hashed(&staging_dir.join(&fasta_name), fasta.as_bytes())
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        fs::write(&raw_path, &raw_bytes).map_err(|err| KiraError::Filesystem(err.to_string()))?;
 
         atomic_rename_dir(&staging_dir, project_dir.as_std_path())
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
 
-        let meta = self.build_metadata(
+        let digests = Digests::from([(fasta_name, fasta_digest)]);
+        let mut download =
+            DownloadRecord::of_dir(project_dir.as_std_path(), source_urls, &digests)?;
+        download.validators = validators;
+        let mut meta = self.project_metadata(
             "uniprot",
             "uniprot",
             id.as_str(),
             None,
             project_dir.as_str(),
+            &options,
         );
+        meta.download = Some(download.clone());
         Store::write_metadata(
             &self.store.project_metadata_path("uniprot", id.as_str()),
             &meta,
@@ -1981,23 +8125,334 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
 
         if !options.no_cache {
             Store::copy_dir_atomic(&project_dir, &cache_dir)?;
-            let meta =
-                self.build_metadata("uniprot", "uniprot", id.as_str(), None, cache_dir.as_str());
-            Store::write_metadata(
+            let mut meta = self.build_metadata(
+                "uniprot",
+                "uniprot",
+                id.as_str(),
+                None,
+                cache_dir.as_str(),
+                None,
+            );
+            meta.download = Some(download.clone());
+            self.store.write_cache_metadata(
                 &self.store.cache_metadata_path("uniprot", id.as_str()),
                 &meta,
             )?;
         }
 
-        Ok(FetchItemResult {
-            dataset_type: "uniprot".to_string(),
-            id: id.as_str().to_string(),
-            format: None,
-            source: "uniprot".to_string(),
-            action: "download".to_string(),
-            project_path: Some(project_dir.to_string()),
-            cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
-        })
+        Ok(FetchItemResult {
+            dataset_type: "uniprot".to_string(),
+            id: id.as_str().to_string(),
+            format: None,
+            source: "uniprot".to_string(),
+            action: if not_modified {
+                "not-modified"
+            } else if corrupt {
+                ACTION_REFETCHED_CORRUPT
+            } else {
+                "download"
+            }
+            .to_string(),
+            project_path: Some(project_dir.to_string()),
+            cache_path: (!options.no_cache).then(|| cache_dir.to_string()),
+            download: Some(download),
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
+        })
+    }
+
+    /// A whole proteome: its sequences streamed to `<id>.fasta.gz`, beside
+    /// the proteome entry summarised in `metadata.json`.
+    fn fetch_proteome(
+        &self,
+        id: ProteomeId,
+        options: FetchOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchItemResult, KiraError> {
+        let options = self.cache_options("uniprot-proteome", options);
+        sink.event(ProgressEvent {
+            message: format!("phase=Resolve; uniprot-proteome {}", id.as_str()),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let _lock = self.lock_dataset("uniprot-proteome", id.as_str(), &options, sink)?;
+
+        let project_dir = self.store.project_proteome_dir(&id);
+        let cache_dir = self.store.cache_proteome_dir(&id);
+        let result = |action: &str, cache_path: Option<String>, download| FetchItemResult {
+            dataset_type: "uniprot-proteome".to_string(),
+            id: id.as_str().to_string(),
+            format: None,
+            source: "uniprot".to_string(),
+            action: action.to_string(),
+            project_path: Some(project_dir.to_string()),
+            cache_path,
+            download,
+            cache_savings: None,
+            cache: None,
+            timings: PhaseTimings::default(),
+        };
+
+        if !options.force && self.store.project_exists(&project_dir) {
+            sink.event(ProgressEvent {
+                message: "phase=Store; already in project store".to_string(),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            let cache_path = cache_dir
+                .as_std_path()
+                .exists()
+                .then(|| cache_dir.to_string());
+            let download = Store::recorded_download(
+                &self
+                    .store
+                    .project_metadata_path("uniprot-proteome", id.as_str()),
+            );
+            return Ok(result("project", cache_path, download));
+        }
+
+        let corrupt = self.evict_corrupt_cache("uniprot-proteome", id.as_str(), &options, sink)?;
+        if !options.force && self.store.cache_exists(&cache_dir) {
+            sink.event(ProgressEvent {
+                message: "phase=Store; using cached dataset".to_string(),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+            let download = Store::recorded_download(
+                &self
+                    .store
+                    .cache_metadata_path("uniprot-proteome", id.as_str()),
+            );
+            if !options.dry_run {
+                let mut meta = self.project_metadata(
+                    "uniprot",
+                    "uniprot-proteome",
+                    id.as_str(),
+                    None,
+                    project_dir.as_str(),
+                    &options,
+                );
+                meta.download = download.clone();
+                Store::materialize_dir(&cache_dir, &project_dir, &meta.downloaded_at)?;
+                Store::write_metadata(
+                    &self
+                        .store
+                        .project_metadata_path("uniprot-proteome", id.as_str()),
+                    &meta,
+                )?;
+            }
+            return Ok(result("cache", Some(cache_dir.to_string()), download));
+        }
+
+        if options.dry_run {
+            let cache_path = (!options.no_cache).then(|| cache_dir.to_string());
+            return Ok(result("download", cache_path, None));
+        }
+
+        let temp_dir = StagingDir::create_in(
+            self.store.project_root().as_std_path(),
+            "kira-bm-proteome",
+            id.as_str(),
+        )
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let staging_dir = temp_dir.path().join("dataset");
+        fs::create_dir_all(&staging_dir).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let staged = Utf8Path::from_path(&staging_dir)
+            .ok_or_else(|| KiraError::Filesystem("invalid temp dir".to_string()))?;
+
+        check_cancelled(sink)?;
+
+        sink.event(ProgressEvent {
+            message: "phase=Fetch; downloading".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        let record = self.uniprot.fetch_proteome(&id)?;
+        Store::write_bytes_atomic(
+            &staged.join(UNIPROT_METADATA[0]),
+            &to_canonical_json(&record.metadata)?,
+        )?;
+        Store::write_bytes_atomic(
+            &staged.join(UNIPROT_METADATA[1]),
+            &to_canonical_json(&record.raw_json)?,
+        )?;
+        let fasta_name = format!("{}.fasta.gz", id.as_str());
+        let fasta_digest =
+            self.uniprot
+                .download_proteome(&id, staged.join(&fasta_name).as_std_path(), sink)?;
+
+        check_cancelled(sink)?;
+
+        sink.event(ProgressEvent {
+            message: "phase=Store; writing files".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        atomic_rename_dir(&staging_dir, project_dir.as_std_path())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+
+        let source_urls = vec![
+            UniprotHttpClient::proteome_url(&id),
+            UniprotHttpClient::proteome_fasta_url(&id),
+        ];
+        let digests = Digests::from([(fasta_name, fasta_digest)]);
+        let download = DownloadRecord::of_dir(project_dir.as_std_path(), source_urls, &digests)?;
+        let mut meta = self.project_metadata(
+            "uniprot",
+            "uniprot-proteome",
+            id.as_str(),
+            None,
+            project_dir.as_str(),
+            &options,
+        );
+        meta.download = Some(download.clone());
+        Store::write_metadata(
+            &self
+                .store
+                .project_metadata_path("uniprot-proteome", id.as_str()),
+            &meta,
+        )?;
+
+        if !options.no_cache {
+            Store::copy_dir_atomic(&project_dir, &cache_dir)?;
+            let mut meta = self.build_metadata(
+                "uniprot",
+                "uniprot-proteome",
+                id.as_str(),
+                None,
+                cache_dir.as_str(),
+                None,
+            );
+            meta.download = Some(download.clone());
+            self.store.write_cache_metadata(
+                &self
+                    .store
+                    .cache_metadata_path("uniprot-proteome", id.as_str()),
+                &meta,
+            )?;
+        }
+
+        let cache_path = (!options.no_cache).then(|| cache_dir.to_string());
+        Ok(result(
+            if corrupt {
+                ACTION_REFETCHED_CORRUPT
+            } else {
+                "download"
+            },
+            cache_path,
+            Some(download),
+        ))
+    }
+
+    /// Whether the project has a record for the dataset, full or not.
+    fn has_project_record(&self, dataset_type: &str, id: &str) -> bool {
+        self.store
+            .project_metadata_path(dataset_type, id)
+            .as_std_path()
+            .exists()
+    }
+
+    fn write_metadata_only_record(
+        &self,
+        source: &str,
+        dataset_type: &str,
+        id: &str,
+        path: &Utf8Path,
+        options: &FetchOptions,
+    ) -> Result<(), KiraError> {
+        let mut meta =
+            self.project_metadata(source, dataset_type, id, None, path.as_str(), options);
+        meta.materialization = Materialization::MetadataOnly;
+        Store::write_metadata(&self.store.project_metadata_path(dataset_type, id), &meta)
+    }
+
+    /// Holds the dataset's lock for the rest of a fetch; dry runs take none.
+    fn lock_dataset(
+        &self,
+        dataset_type: &str,
+        id: &str,
+        options: &FetchOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<Option<DatasetLock>, KiraError> {
+        if options.dry_run {
+            return Ok(None);
+        }
+        let timeout = options
+            .lock_timeout_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_LOCK_TIMEOUT);
+        self.store
+            .lock_dataset(dataset_type, id, timeout, sink)
+            .map(Some)
+    }
+
+    /// Checks the cache entry a fetch may reuse against its recorded
+    /// digests. One that fails is evicted, with a `cache.corrupt` event, and
+    /// `true` is returned so the fetch downloads the dataset again.
+    fn evict_corrupt_cache(
+        &self,
+        dataset_type: &str,
+        id: &str,
+        options: &FetchOptions,
+        sink: &dyn ProgressSink,
+    ) -> Result<bool, KiraError> {
+        if options.force || options.dry_run {
+            return Ok(false);
+        }
+        let bad = self
+            .store
+            .verify_cache_dataset(dataset_type, id, options.verify_cache)?;
+        if bad.is_empty() {
+            return Ok(false);
+        }
+        tracing::warn!(dataset_type, id, files = ?bad, "evicting corrupt cache entry");
+        sink.event(ProgressEvent {
+            message: format!("cache.corrupt {dataset_type}:{id} {}", bad.join(",")),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        self.store.evict_cache_dataset(dataset_type, id)?;
+        Ok(true)
+    }
+
+    /// `options` for fetching a `dataset_type`: one the cache policy keeps
+    /// out of the cache is fetched as with `--no-cache`.
+    fn cache_options(&self, dataset_type: &str, mut options: FetchOptions) -> FetchOptions {
+        // A nested fetch starts over from the flag, not the outer type's policy.
+        let no_cache = options
+            .cache
+            .map_or(options.no_cache, |outer| outer.source == CacheSource::Flag);
+        let decision = self.cache_policy.decision(dataset_type, no_cache);
+        options.no_cache = !decision.enabled;
+        options.cache = Some(decision);
+        options
+    }
+
+    /// The metadata of a project copy, with the provenance and cache
+    /// decision of `options`.
+    fn project_metadata(
+        &self,
+        source: &str,
+        dataset_type: &str,
+        id: &str,
+        format: Option<String>,
+        path: &str,
+        options: &FetchOptions,
+    ) -> Metadata {
+        let mut meta = self.build_metadata(
+            source,
+            dataset_type,
+            id,
+            format,
+            path,
+            options.provenance.clone(),
+        );
+        meta.cache = options.cache;
+        if let Some(acquisition) = &mut meta.acquisition {
+            acquisition.config_sha256 = options.config_sha256.clone();
+        }
+        meta
     }
 
     fn build_metadata(
@@ -2007,6 +8462,7 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
         id: &str,
         format: Option<String>,
         path: &str,
+        provenance: Option<Provenance>,
     ) -> Metadata {
         Metadata {
             source: source.to_string(),
@@ -2016,6 +8472,27 @@ impl<N: NcbiClient, R: RcsbClient, S: SrrClient, U: UniprotClient, G: GeoClient,
             downloaded_at: iso_timestamp(),
             tool: format!("kira-bm/{}", env!("CARGO_PKG_VERSION")),
             resolved_path: path.to_string(),
+            status: None,
+            expiry: None,
+            provenance,
+            materialization: Materialization::Full,
+            assembly_name: None,
+            download: None,
+            converted_formats: Vec::new(),
+            formats: BTreeMap::new(),
+            // Aliases belong to the project copy and outlive re-fetches.
+            alias: Utf8Path::new(path)
+                .starts_with(self.store.project_root())
+                .then(|| self.store.recorded_alias(dataset_type, id))
+                .flatten(),
+            pinned: self.store.recorded_pin(dataset_type, id),
+            imported_from: None,
+            experiment: None,
+            cache: None,
+            acquisition: self
+                .context
+                .as_ref()
+                .map(|context| context.acquisition(&self.http)),
         }
     }
 }
@@ -2033,6 +8510,10 @@ struct RcsbMetadataFile {
     resolution: Option<f64>,
     deposition_date: Option<String>,
     release_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    polymer_entity_count: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    entities: Vec<RcsbEntity>,
     source_urls: RcsbSourceUrls,
 }
 
@@ -2049,35 +8530,215 @@ struct ExpressionMetadataFile {
     dataset_type: String,
     accession: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    platforms: Vec<String>,
+    /// The series' sample count, including samples not fetched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n_samples: Option<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    samples: Vec<SoftSample>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     organism: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     bundle_format: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     n_bundles: Option<usize>,
     files: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    relations: Vec<SeriesRelation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_series: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    series: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    file_categories: BTreeMap<String, FileCategory>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped: Vec<SkippedFile>,
+    /// Per-sample layout and files of a 10x fetch.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    bundles: Vec<BundleFiles>,
+    /// Cells, genes and entries of each MTX bundle, from its matrix header.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    dimensions: Vec<BundleDimensions>,
+    /// Layouts dropped for samples published as both MTX and HDF5.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped_bundles: Vec<BundleFiles>,
+    /// Supplementary tar archives whose 10x bundles were unpacked.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    unpacked_archives: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    notes: Vec<String>,
+    /// Bundles whose barcode or feature count disagrees with the matrix.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+    downloaded_at: String,
+}
+
+#[derive(Debug, Serialize)]
+struct GenomeMetadataFile {
+    registry: String,
+    #[serde(rename = "type")]
+    dataset_type: String,
+    accession: String,
+    include: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    catalog_version: Option<String>,
+    files: BTreeMap<String, Vec<String>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    missing_artifacts: Vec<String>,
+    /// Checksum of the downloaded package zip.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    package_sha256: Option<String>,
+    downloaded_at: String,
+}
+
+/// `metadata.json` of an Ensembl genome: the release it was pinned to and
+/// the file each requested kind resolved to.
+#[derive(Debug, Serialize)]
+struct EnsemblMetadataFile {
+    registry: String,
+    #[serde(rename = "type")]
+    dataset_type: String,
+    species: String,
+    release: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    assembly: Option<String>,
+    include: Vec<EnsemblFile>,
+    files: BTreeMap<String, String>,
     downloaded_at: String,
 }
 
+#[derive(Debug, Serialize)]
+struct SkippedFile {
+    name: String,
+    url: String,
+    category: FileCategory,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BioProjectManifest {
+    bioproject: String,
+    source: String,
+    srr: Vec<String>,
+    assemblies: Vec<String>,
+    members: Vec<BioProjectMember>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BioProjectMember {
+    dataset_type: String,
+    id: String,
+    project_path: Option<String>,
+    cache_path: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 struct KnowledgeMetadataFile {
     registry: String,
     #[serde(rename = "type")]
     dataset_type: String,
+    /// The KEGG organism code of organism-specific pathways.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    organism: Option<String>,
+    /// The GO ontology edition and the species annotation files beside it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flavor: Option<GoFlavor>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<GoAnnotation>,
+    /// The species Reactome files were filtered to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    species_filter: Option<SpeciesFilter>,
     #[serde(skip_serializing_if = "Option::is_none")]
     version: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     release_date: Option<String>,
     source_urls: Vec<String>,
     downloaded_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    validation: Option<KnowledgeValidation>,
+}
+
+/// A species' GOA annotation file stored with the ontology.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GoAnnotation {
+    species: GoaSpecies,
+    file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    release_date: Option<String>,
+    source_url: String,
+}
+
+/// What a stored `go` dataset holds; older datasets record no flavor and
+/// hold only `go-basic.obo`.
+#[derive(Debug, Default, Deserialize)]
+struct StoredGo {
+    #[serde(default)]
+    flavor: GoFlavor,
+    version: Option<String>,
+    release_date: Option<String>,
+    #[serde(default)]
+    annotations: Vec<GoAnnotation>,
+}
+
+impl StoredGo {
+    fn read(dir: &Utf8Path) -> Option<Self> {
+        let content = fs::read(dir.join("metadata.json").as_std_path()).ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    fn annotation(&self, species: &GoaSpecies) -> Option<&GoAnnotation> {
+        self.annotations
+            .iter()
+            .find(|annotation| &annotation.species == species)
+    }
+
+    fn covers(&self, flavor: GoFlavor, species: &[GoaSpecies]) -> bool {
+        self.flavor == flavor
+            && species
+                .iter()
+                .all(|species| self.annotation(species).is_some())
+    }
+}
+
+/// How Reactome files were cut down to one species.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SpeciesFilter {
+    species: String,
+    /// The filtered files took the place of the full downloads.
+    replaced_unfiltered: bool,
+    files: Vec<FilteredFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FilteredFile {
+    source: String,
+    file: String,
+    lines_before: usize,
+    lines_after: usize,
 }
 
-#[derive(Debug)]
-struct Bundle {
-    urls: Vec<String>,
+/// Row counts checked against what the source declares. `complete` is false
+/// when the source did not say how many entries to expect.
+#[derive(Debug, Serialize)]
+struct KnowledgeValidation {
+    pathway_rows: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    declared_pathways: Option<usize>,
+    link_rows: usize,
+    link_retrieval: String,
+    complete: bool,
 }
 
-impl From<&RcsbMetadata> for RcsbMetadataFile {
-    fn from(value: &RcsbMetadata) -> Self {
+impl RcsbMetadataFile {
+    /// The entry's summary; `sequences` from its FASTA fill in the chains
+    /// and organism of each polymer entity.
+    fn new(value: &RcsbMetadata, sequences: &[FastaSequence]) -> Self {
+        let (polymer_entity_count, entities) = polymer_entities(&value.raw_json, sequences);
         Self {
             registry: value.registry.clone(),
             pdb_id: value.pdb_id.clone(),
@@ -2086,6 +8747,8 @@ impl From<&RcsbMetadata> for RcsbMetadataFile {
             resolution: value.resolution,
             deposition_date: value.deposition_date.clone(),
             release_date: value.release_date.clone(),
+            polymer_entity_count,
+            entities,
             source_urls: RcsbSourceUrls {
                 structure: value.source_structure_url.clone(),
                 metadata: value.source_metadata_url.clone(),
@@ -2094,10 +8757,85 @@ impl From<&RcsbMetadata> for RcsbMetadataFile {
     }
 }
 
-fn rcsb_metadata_paths(dir: &Utf8PathBuf) -> (Utf8PathBuf, Utf8PathBuf) {
+/// Files a metadata-only copy holds that the full dataset lacks, read
+/// before the copy is replaced by the full one.
+fn read_carried(dir: &Utf8Path, names: &[&str]) -> Vec<(String, Vec<u8>)> {
+    names
+        .iter()
+        .filter_map(|name| {
+            fs::read(dir.join(name).as_std_path())
+                .ok()
+                .map(|bytes| (name.to_string(), bytes))
+        })
+        .collect()
+}
+
+fn write_carried(dir: &Utf8Path, carried: &[(String, Vec<u8>)]) -> Result<(), KiraError> {
+    for (name, bytes) in carried {
+        Store::write_bytes_atomic(&dir.join(name), bytes)?;
+    }
+    Ok(())
+}
+
+fn write_uniprot_metadata(dir: &Utf8Path, record: &UniprotRecord) -> Result<(), KiraError> {
+    Store::write_bytes_atomic(
+        &dir.join(UNIPROT_METADATA[0]),
+        &to_canonical_json(&record.metadata)?,
+    )?;
+    Store::write_bytes_atomic(
+        &dir.join(UNIPROT_METADATA[1]),
+        &to_canonical_json(&record.raw_json)?,
+    )
+}
+
+/// Size of a record's data: every stored format of a protein, otherwise
+/// whatever its path holds.
+fn stored_bytes(metadata: &Metadata) -> Option<u64> {
+    if metadata.formats.is_empty() {
+        return path_size(Path::new(&metadata.resolved_path));
+    }
+    metadata
+        .formats
+        .values()
+        .map(|path| path_size(Path::new(path)))
+        .sum()
+}
+
+/// Lists every structure file beside a protein record's, and keeps the
+/// converted formats of the record it replaces that are still stored.
+fn record_protein_formats(
+    meta: &mut Metadata,
+    record_path: &Utf8Path,
+    dir: &Utf8Path,
+    id: &ProteinId,
+) {
+    meta.formats = Store::protein_files(dir, id);
+    let previous = fs::read(record_path.as_std_path())
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<Metadata>(&bytes).ok());
+    meta.converted_formats = previous
+        .map(|previous| previous.converted_formats)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|format| meta.formats.contains_key(format) && meta.format.as_ref() != Some(format))
+        .collect();
+}
+
+/// The validators a dataset's last download was served with.
+fn stored_validators(metadata_path: &Utf8Path) -> StoredValidators {
+    Store::recorded_download(metadata_path)
+        .map(|download| download.validators)
+        .unwrap_or_default()
+}
+
+fn rcsb_metadata_paths(dir: &Utf8Path) -> (Utf8PathBuf, Utf8PathBuf) {
     (dir.join("metadata.json"), dir.join("metadata.raw.json"))
 }
 
+fn protein_fasta_path(dir: &Utf8Path, id: &ProteinId) -> Utf8PathBuf {
+    dir.join(format!("{}.fasta", id.as_str()))
+}
+
 #[derive(Debug, Serialize)]
 struct SrrMetadataFile {
     registry: String,
@@ -2106,15 +8844,29 @@ struct SrrMetadataFile {
     format: String,
     paired: bool,
     downloaded_at: String,
-    tools: ToolInfo,
+    tools: SrrToolsRecord,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run_info: Option<SrrRunInfo>,
+    /// Records in the (first) FASTQ file, checked against `run_info`.
+    fastq_read_count: u64,
     source_fastq: Option<Vec<String>>,
     conversion: Option<String>,
 }
 
+/// Tool versions, plus the fasterq-dump settings the run chose.
+#[derive(Debug, Serialize)]
+struct SrrToolsRecord {
+    #[serde(flatten)]
+    tools: ToolInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    threads: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temp_dir: Option<String>,
+}
+
 fn write_srr_metadata(dir: &Utf8PathBuf, metadata: &SrrMetadataFile) -> Result<(), KiraError> {
     let path = dir.join("metadata.json");
-    let content = serde_json::to_vec_pretty(metadata)
-        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    let content = to_canonical_json(metadata)?;
     Store::write_bytes_atomic(&path, &content)
 }
 
@@ -2134,7 +8886,7 @@ fn normalize_fastq_files(
     let mut outputs = Vec::new();
     if paired {
         let first = sorted
-            .get(0)
+            .first()
             .ok_or_else(|| KiraError::Filesystem("missing paired FASTQ file 1".to_string()))?;
         let second = sorted
             .get(1)
@@ -2147,7 +8899,7 @@ fn normalize_fastq_files(
         outputs.push(out2);
     } else {
         let first = sorted
-            .get(0)
+            .first()
             .ok_or_else(|| KiraError::Filesystem("missing FASTQ file".to_string()))?;
         let out = out_dir.join("reads.fastq");
         fs::copy(first, &out).map_err(|err| KiraError::Filesystem(err.to_string()))?;
@@ -2156,6 +8908,79 @@ fn normalize_fastq_files(
     Ok(outputs)
 }
 
+/// Sizes `path` and, when `remove` is set, deletes it.
+fn gc_remove(
+    path: &Utf8Path,
+    specifier: Option<String>,
+    missing_path: Option<String>,
+    remove: bool,
+) -> Result<GcEntry, KiraError> {
+    let path = path.as_std_path();
+    let bytes = path_size(path).unwrap_or(0);
+    if remove {
+        if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        }
+        .map_err(|err| KiraError::Filesystem(format!("{}: {err}", path.display())))?;
+    }
+    Ok(GcEntry {
+        path: path.display().to_string(),
+        specifier,
+        missing_path,
+        bytes,
+        removed: remove,
+    })
+}
+
+fn remove_package(zip_path: &Path, digest_path: &Path) -> Result<(), KiraError> {
+    for path in [zip_path, digest_path] {
+        match fs::remove_file(path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                return Err(KiraError::Filesystem(format!("{}: {err}", path.display())));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// When an orphan was last written, standing in for its download time.
+fn gc_mtime(path: &Utf8Path) -> String {
+    fs::metadata(path.as_std_path())
+        .and_then(|meta| meta.modified())
+        .map(|mtime| chrono::DateTime::<chrono::Utc>::from(mtime).to_rfc3339())
+        .unwrap_or_default()
+}
+
+fn count_fastq_records(path: &std::path::Path) -> Result<u64, KiraError> {
+    let file = fs::File::open(path).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    let mut lines = 0u64;
+    let mut last = b'\n';
+    let mut reader = std::io::BufReader::new(file);
+    loop {
+        let buffer = std::io::BufRead::fill_buf(&mut reader)
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let Some(&end) = buffer.last() else {
+            break;
+        };
+        lines += buffer.iter().filter(|byte| **byte == b'\n').count() as u64;
+        last = end;
+        let consumed = buffer.len();
+        std::io::BufRead::consume(&mut reader, consumed);
+    }
+    if last != b'\n' {
+        lines += 1;
+    }
+    Ok(lines / 4)
+}
+
+fn read_count_differs(counted: u64, expected: u64) -> bool {
+    let difference = counted.abs_diff(expected) as f64;
+    difference > expected as f64 * READ_COUNT_TOLERANCE
+}
+
 fn detect_paired_fastq(files: &[std::path::PathBuf]) -> bool {
     let mut has_1 = false;
     let mut has_2 = false;
@@ -2180,70 +9005,455 @@ fn replace_ext(path: &std::path::Path, ext: &str) -> Result<std::path::PathBuf,
 
 fn load_uniprot_details(project: Option<&Metadata>, cache: Option<&Metadata>) -> Option<Value> {
     let paths = [
-        project.and_then(|meta| Some(meta.resolved_path.clone())),
-        cache.and_then(|meta| Some(meta.resolved_path.clone())),
+        project.map(|meta| meta.resolved_path.clone()),
+        cache.map(|meta| meta.resolved_path.clone()),
     ];
     for path in paths.into_iter().flatten() {
         let meta_path = std::path::Path::new(&path).join("metadata.json");
-        if let Ok(content) = std::fs::read_to_string(&meta_path) {
-            if let Ok(value) = serde_json::from_str::<Value>(&content) {
-                return Some(value);
+        if let Ok(content) = std::fs::read_to_string(&meta_path)
+            && let Ok(mut value) = serde_json::from_str::<Value>(&content)
+        {
+            let structures = uniprot_structures(&value);
+            if !structures.is_empty()
+                && let Some(object) = value.as_object_mut()
+            {
+                object.insert("structures".to_string(), Value::Array(structures));
             }
+            return Some(value);
         }
     }
     None
 }
 
+/// Ranked PDB cross-references as compact rows for `info`.
+pub fn uniprot_structures(metadata: &Value) -> Vec<Value> {
+    let Some(xrefs) = pdb_xrefs(metadata) else {
+        return Vec::new();
+    };
+    let sequence_length = metadata.get("sequence_length").and_then(Value::as_u64);
+    rank_pdb_xrefs(&xrefs, sequence_length)
+        .into_iter()
+        .map(|xref| {
+            let chains = xref
+                .chains
+                .iter()
+                .map(|chain| match (chain.start, chain.end) {
+                    (Some(start), Some(end)) => format!("{}={start}-{end}", chain.chain),
+                    _ => chain.chain.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            serde_json::json!({
+                "id": xref.id,
+                "method": xref.method,
+                "resolution": xref.resolution,
+                "coverage": xref.coverage(sequence_length),
+                "chains": chains,
+            })
+        })
+        .collect()
+}
+
+fn pdb_xrefs(metadata: &Value) -> Option<Vec<PdbXref>> {
+    // Metadata written before typed xrefs only has plain ids.
+    metadata
+        .get("cross_references")
+        .and_then(|refs| refs.get("pdb"))
+        .and_then(|pdb| serde_json::from_value::<Vec<PdbXref>>(pdb.clone()).ok())
+}
+
+/// Ranks `xrefs` against the requested range and marks the best `top` that
+/// cover at least [`STRUCTURE_MIN_COVERAGE`] of it.
+fn select_structures(
+    query: &StructureQuery,
+    xrefs: &[PdbXref],
+    sequence_length: Option<u64>,
+    top: usize,
+) -> Result<StructureSelection, KiraError> {
+    let accession = &query.accession;
+    let (start, end) = match (query.range, sequence_length) {
+        (Some((_, end)), Some(length)) if end > length => {
+            return Err(KiraError::InvalidSpecifier(format!(
+                "structure-for:{query}: {accession} has only {length} residues"
+            )));
+        }
+        (Some(range), _) => range,
+        (None, Some(length)) if length > 0 => (1, length),
+        (None, _) => {
+            return Err(KiraError::NoCoveringStructure(format!(
+                "{accession} has no sequence length; give a range such as structure-for:{accession}[1-100]"
+            )));
+        }
+    };
+    if xrefs.is_empty() {
+        return Err(KiraError::NoCoveringStructure(format!(
+            "{accession} lists no PDB structures"
+        )));
+    }
+
+    let span = (end - start + 1) as f64;
+    let mut candidates = rank_pdb_xrefs_for_range(xrefs, start, end)
+        .into_iter()
+        .map(|xref| {
+            let covered = xref.coverage_of(start, end);
+            StructureCandidate {
+                id: xref.id,
+                method: xref.method,
+                resolution: xref.resolution,
+                covered_residues: covered,
+                coverage: (covered as f64 / span * 1000.0).round() / 1000.0,
+                selected: false,
+            }
+        })
+        .collect::<Vec<_>>();
+    let eligible = candidates
+        .iter()
+        .take_while(|candidate| candidate.covered_residues as f64 >= STRUCTURE_MIN_COVERAGE * span)
+        .count();
+    if eligible == 0 {
+        let partial = candidates
+            .iter()
+            .filter(|candidate| candidate.covered_residues > 0)
+            .take(STRUCTURE_PARTIAL_REPORT)
+            .map(describe_candidate)
+            .collect::<Vec<_>>();
+        let best = if partial.is_empty() {
+            "none of its structures overlap the range".to_string()
+        } else {
+            format!("best partial matches: {}", partial.join(", "))
+        };
+        return Err(KiraError::NoCoveringStructure(format!(
+            "no PDB structure covers at least {:.0}% of {accession}[{start}-{end}]; {best}",
+            STRUCTURE_MIN_COVERAGE * 100.0
+        )));
+    }
+    for candidate in candidates.iter_mut().take(eligible.min(top)) {
+        candidate.selected = true;
+    }
+    Ok(StructureSelection {
+        accession: accession.to_string(),
+        start,
+        end,
+        min_coverage: STRUCTURE_MIN_COVERAGE,
+        candidates,
+    })
+}
+
+fn describe_candidate(candidate: &StructureCandidate) -> String {
+    let mut details = Vec::new();
+    if let Some(method) = &candidate.method {
+        details.push(method.clone());
+    }
+    if let Some(resolution) = candidate.resolution {
+        details.push(format!("{resolution:.2} Å"));
+    }
+    let coverage = format!("{} {:.0}%", candidate.id, candidate.coverage * 100.0);
+    if details.is_empty() {
+        coverage
+    } else {
+        format!("{coverage} ({})", details.join(", "))
+    }
+}
+
+/// Adds the selection rationale to a protein's `metadata.json`.
+fn record_selection(dir: &Utf8PathBuf, selection: &StructureSelection) -> Result<(), KiraError> {
+    let selection =
+        serde_json::to_value(selection).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    annotate_metadata(dir, "selection", selection)
+}
+
+/// Sets `key` in the `metadata.json` of a stored dataset directory.
+fn annotate_metadata(dir: &Utf8Path, key: &str, value: Value) -> Result<(), KiraError> {
+    let path = dir.join("metadata.json");
+    let mut metadata = fs::read(path.as_std_path())
+        .ok()
+        .and_then(|content| serde_json::from_slice::<Value>(&content).ok())
+        .filter(Value::is_object)
+        .unwrap_or_else(|| Value::Object(Default::default()));
+    if let Some(object) = metadata.as_object_mut() {
+        object.insert(key.to_string(), value);
+    }
+    let bytes = to_canonical_json(&metadata)?;
+    fs::write(path.as_std_path(), bytes).map_err(|err| KiraError::Filesystem(err.to_string()))
+}
+
+/// How a structure was selected, its polymer entities and, when its FASTA
+/// was stored, each sequence's chains and length.
+fn load_protein_details(project: Option<&Metadata>) -> Option<Value> {
+    let project = project?;
+    let dir = Utf8Path::new(&project.resolved_path).parent()?;
+    let metadata = fs::read(dir.join("metadata.json").as_std_path())
+        .ok()
+        .and_then(|content| serde_json::from_slice::<Value>(&content).ok());
+    let mut details = serde_json::Map::new();
+    for key in ["selection", "polymer_entity_count", "entities"] {
+        if let Some(value) = metadata.as_ref().and_then(|metadata| metadata.get(key)) {
+            details.insert(key.to_string(), value.clone());
+        }
+    }
+    if let Ok(fasta) = fs::read_to_string(dir.join(format!("{}.fasta", project.id)).as_std_path()) {
+        let sequences = parse_fasta(&fasta)
+            .into_iter()
+            .map(|sequence| {
+                serde_json::json!({
+                    "entity_id": sequence.entity_id,
+                    "chain_ids": sequence.chain_ids,
+                    "length": sequence.length,
+                })
+            })
+            .collect();
+        details.insert("sequences".to_string(), Value::Array(sequences));
+    }
+    (!details.is_empty()).then_some(Value::Object(details))
+}
+
 fn load_doi_details(project: Option<&Metadata>, cache: Option<&Metadata>) -> Option<Value> {
     let paths = [
-        project.and_then(|meta| Some(meta.resolved_path.clone())),
-        cache.and_then(|meta| Some(meta.resolved_path.clone())),
+        project.map(|meta| meta.resolved_path.clone()),
+        cache.map(|meta| meta.resolved_path.clone()),
     ];
     for path in paths.into_iter().flatten() {
         let meta_path = std::path::Path::new(&path).join("doi_resolution.json");
-        if let Ok(content) = std::fs::read_to_string(&meta_path) {
-            if let Ok(value) = serde_json::from_str::<Value>(&content) {
-                return Some(value);
-            }
+        if let Ok(content) = std::fs::read_to_string(&meta_path)
+            && let Ok(value) = serde_json::from_str::<Value>(&content)
+        {
+            return Some(value);
         }
     }
     None
 }
 
+fn load_bioproject_details(project: Option<&Metadata>) -> Option<Value> {
+    let path = std::path::Path::new(&project?.resolved_path).join("manifest.json");
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
 fn load_expression_details(project: Option<&Metadata>, cache: Option<&Metadata>) -> Option<Value> {
     let paths = [
-        project.and_then(|meta| Some(meta.resolved_path.clone())),
-        cache.and_then(|meta| Some(meta.resolved_path.clone())),
+        project.map(|meta| meta.resolved_path.clone()),
+        cache.map(|meta| meta.resolved_path.clone()),
     ];
     for path in paths.into_iter().flatten() {
         let meta_path = std::path::Path::new(&path)
             .join("metadata")
             .join("metadata.json");
-        if let Ok(content) = std::fs::read_to_string(&meta_path) {
-            if let Ok(value) = serde_json::from_str::<Value>(&content) {
-                return Some(value);
-            }
+        if let Ok(content) = std::fs::read_to_string(&meta_path)
+            && let Ok(value) = serde_json::from_str::<Value>(&content)
+        {
+            return Some(value);
         }
     }
     None
 }
 
+/// Runs one fetch item between `item.begin`/`item.end` events, honouring
+/// batch pause requests before it starts.
+/// Cross-checks the package's dataset catalog against the requested includes.
+fn check_genome_catalog(
+    accession: &GenomeAccession,
+    include: &[String],
+    extract_dir: &std::path::Path,
+    allow_missing_artifacts: bool,
+    sink: &dyn ProgressSink,
+) -> Result<GenomeMetadataFile, KiraError> {
+    let mut meta = GenomeMetadataFile {
+        registry: "ncbi".to_string(),
+        dataset_type: "genome".to_string(),
+        accession: accession.as_str().to_string(),
+        include: include.to_vec(),
+        catalog_version: None,
+        files: BTreeMap::new(),
+        missing_artifacts: Vec::new(),
+        package_sha256: None,
+        downloaded_at: iso_timestamp(),
+    };
+    let catalog_path = extract_dir.join(crate::ncbi::DATASET_CATALOG_PATH);
+    let Ok(text) = fs::read_to_string(&catalog_path) else {
+        sink.event(ProgressEvent {
+            message: "genome.catalog missing; skipping artifact check".to_string(),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+        return Ok(meta);
+    };
+    let catalog = crate::ncbi::parse_dataset_catalog(&text)?;
+    let missing = catalog.missing_artifacts(include);
+    if let Some(artifact) = missing.first()
+        && !allow_missing_artifacts
+    {
+        return Err(KiraError::MissingArtifact {
+            accession: accession.as_str().to_string(),
+            artifact: artifact.clone(),
+        });
+    }
+    for artifact in &missing {
+        sink.event(ProgressEvent {
+            message: format!("genome.missing_artifact {} {artifact}", accession.as_str()),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+    }
+    meta.catalog_version = catalog.api_version.clone();
+    meta.files = catalog.inventory();
+    meta.missing_artifacts = missing;
+    Ok(meta)
+}
+
+/// Type-specific details shown by `info`, read from the stored dataset.
+pub fn dataset_details(
+    dataset_type: &str,
+    project: Option<&Metadata>,
+    cache: Option<&Metadata>,
+) -> Option<Value> {
+    match dataset_type {
+        "protein" => load_protein_details(project),
+        "uniprot" => load_uniprot_details(project, cache),
+        "uniprot-proteome" => load_kb_details(project, cache),
+        "doi" => load_doi_details(project, cache),
+        "bioproject" => load_bioproject_details(project),
+        "expression" | "expression10x" | "expressionsample" => {
+            load_expression_details(project, cache)
+        }
+        "genome" | "go" | "kegg" | "reactome" => load_kb_details(project, cache),
+        "srr" => load_srr_details(project, cache),
+        _ => None,
+    }
+}
+
+/// The SRR metadata file; for a metadata-only dataset, its ENA run record
+/// as `run_info`.
+fn load_srr_details(project: Option<&Metadata>, cache: Option<&Metadata>) -> Option<Value> {
+    if let Some(details) = load_kb_details(project, cache) {
+        return Some(details);
+    }
+    let dir = Utf8PathBuf::from(&project?.resolved_path);
+    let run_info = stored_run_info(dir.as_std_path())?;
+    Some(serde_json::json!({ "run_info": run_info }))
+}
+
+/// The run info stored with an SRR dataset in `dir`, by a full fetch or a
+/// `--metadata-only` one.
+pub fn stored_run_info(dir: &Path) -> Option<SrrRunInfo> {
+    if let Ok(content) = fs::read(dir.join("metadata.json")) {
+        let value = serde_json::from_slice::<Value>(&content).ok()?;
+        return serde_json::from_value(value.get("run_info")?.clone()).ok();
+    }
+    let content = fs::read(dir.join(SRR_RUN_INFO)).ok()?;
+    let record = serde_json::from_slice::<Value>(&content).ok()?;
+    Some(SrrRunInfo::from_ena(&record))
+}
+
+fn stored_dataset_dir(metadata_path: &Utf8Path) -> Option<Utf8PathBuf> {
+    let content = fs::read_to_string(metadata_path.as_std_path()).ok()?;
+    let metadata = serde_json::from_str::<Metadata>(&content).ok()?;
+    let dir = Utf8PathBuf::from(metadata.resolved_path);
+    dir.as_std_path().exists().then_some(dir)
+}
+
 fn load_kb_details(project: Option<&Metadata>, cache: Option<&Metadata>) -> Option<Value> {
     let paths = [
-        project.and_then(|meta| Some(meta.resolved_path.clone())),
-        cache.and_then(|meta| Some(meta.resolved_path.clone())),
+        project.map(|meta| meta.resolved_path.clone()),
+        cache.map(|meta| meta.resolved_path.clone()),
     ];
     for path in paths.into_iter().flatten() {
         let meta_path = std::path::Path::new(&path).join("metadata.json");
-        if let Ok(content) = std::fs::read_to_string(&meta_path) {
-            if let Ok(value) = serde_json::from_str::<Value>(&content) {
-                return Some(value);
-            }
+        if let Ok(content) = std::fs::read_to_string(&meta_path)
+            && let Ok(value) = serde_json::from_str::<Value>(&content)
+        {
+            return Some(value);
         }
     }
     None
 }
 
+/// Whether the stored Reactome files answer a fetch for `species` (all
+/// species when `None`). Datasets stored before the hierarchy file was
+/// fetched do not.
+fn reactome_satisfies(dir: &Utf8Path, species: Option<&str>, replace_unfiltered: bool) -> bool {
+    #[derive(Deserialize)]
+    struct Stored {
+        species_filter: Option<SpeciesFilter>,
+    }
+
+    if !dir
+        .join("ReactomePathwaysRelation.txt")
+        .as_std_path()
+        .exists()
+    {
+        return false;
+    }
+    let Some(stored) = fs::read(dir.join("metadata.json").as_std_path())
+        .ok()
+        .and_then(|content| serde_json::from_slice::<Stored>(&content).ok())
+    else {
+        return false;
+    };
+    match (species, stored.species_filter) {
+        (None, None) => true,
+        (None, Some(filter)) => !filter.replaced_unfiltered,
+        (Some(_), None) => false,
+        (Some(species), Some(filter)) => {
+            filter.species.eq_ignore_ascii_case(species)
+                && filter.replaced_unfiltered == replace_unfiltered
+        }
+    }
+}
+
+/// `ReactomePathways.txt` filtered to Homo sapiens is stored as
+/// `ReactomePathways.homo_sapiens.txt`.
+fn reactome_filtered_name(source: &str, species: &str) -> String {
+    let slug = species
+        .trim()
+        .to_ascii_lowercase()
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+        .collect::<String>();
+    let stem = source.strip_suffix(".txt").unwrap_or(source);
+    format!("{stem}.{slug}.txt")
+}
+
+/// Moves the organism subdirectories and records kept in the generic KEGG
+/// directory into its replacement, so re-fetching `kegg` keeps `kegg:<org>`.
+fn carry_kegg_organisms(from: &Utf8Path, to: &Utf8Path) -> Result<(), KiraError> {
+    let Ok(entries) = fs::read_dir(from.as_std_path()) else {
+        return Ok(());
+    };
+    for entry in entries {
+        let path = entry
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?
+            .path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let is_record = name.ends_with(".json") && name != "metadata.json";
+        if path.is_dir() || is_record {
+            fs::rename(&path, to.join(name).as_std_path())
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+/// The supplementary files fetched from `urls`, as stored under `dir`.
+fn geo_download(
+    dir: &Utf8Path,
+    urls: &[String],
+    digests: &Digests,
+) -> Result<DownloadRecord, KiraError> {
+    DownloadRecord::of_files(
+        dir.as_std_path(),
+        &urls
+            .iter()
+            .map(|url| geo_relative_path(url))
+            .collect::<Vec<_>>(),
+        urls.iter()
+            .map(|url| crate::geo::GeoHttpClient::normalize_url(url))
+            .collect(),
+        digests,
+    )
+}
+
 fn geo_relative_path(url: &str) -> String {
     let without_query = url.split('?').next().unwrap_or(url);
     if let Some(idx) = without_query.find("/suppl/") {
@@ -2256,44 +9466,34 @@ fn geo_relative_path(url: &str) -> String {
         .to_string()
 }
 
-fn detect_10x_bundles(urls: &[String]) -> Vec<Bundle> {
-    let mut map: std::collections::HashMap<String, Vec<String>> = std::collections::HashMap::new();
-    for url in urls {
-        let rel = geo_relative_path(url);
-        let file_name = rel.rsplit('/').next().unwrap_or(&rel);
-        if !matches!(
-            file_name,
-            "matrix.mtx.gz" | "barcodes.tsv.gz" | "features.tsv.gz" | "genes.tsv.gz"
-        ) {
-            continue;
-        }
-        let key = rel
-            .rsplit_once('/')
-            .map(|(dir, _)| dir.to_string())
-            .unwrap_or_else(|| "root".to_string());
-        map.entry(key).or_default().push(url.clone());
+/// `10x` for MTX bundles, `10x-h5` for HDF5 matrices, `10x-mixed` for both.
+fn tenx_bundle_format(mtx: usize, h5: usize) -> Option<&'static str> {
+    match (mtx > 0, h5 > 0) {
+        (true, false) => Some("10x"),
+        (false, true) => Some("10x-h5"),
+        (true, true) => Some("10x-mixed"),
+        (false, false) => None,
     }
+}
 
-    let mut bundles = Vec::new();
-    for (_key, bundle_urls) in map {
-        let mut has_matrix = false;
-        let mut has_barcodes = false;
-        let mut has_features = false;
-        for url in &bundle_urls {
-            let rel = geo_relative_path(url);
-            let file_name = rel.rsplit('/').next().unwrap_or(&rel);
-            match file_name {
-                "matrix.mtx.gz" => has_matrix = true,
-                "barcodes.tsv.gz" => has_barcodes = true,
-                "features.tsv.gz" | "genes.tsv.gz" => has_features = true,
-                _ => {}
-            }
-        }
-        if has_matrix && has_barcodes && has_features {
-            bundles.push(Bundle { urls: bundle_urls });
-        }
-    }
-    bundles
+/// Data files under a staging directory, relative to it and sorted,
+/// without kira-bm's own `metadata/`.
+fn staged_files(dir: &Utf8Path) -> Result<Vec<String>, KiraError> {
+    let mut files = walk_dir(dir.as_std_path())?
+        .into_iter()
+        .filter(|path| path.is_file())
+        .filter_map(|path| {
+            let relative = path.strip_prefix(dir.as_std_path()).ok()?;
+            let relative = relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            (!relative.starts_with("metadata/")).then_some(relative)
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    Ok(files)
 }
 
 fn unique_sorted(mut items: Vec<String>) -> Vec<String> {
@@ -2309,8 +9509,21 @@ fn read_doi_resolution(path: &Utf8PathBuf) -> Result<DoiResolution, KiraError> {
 }
 
 fn write_doi_resolution(path: &Utf8PathBuf, value: &DoiResolution) -> Result<(), KiraError> {
-    let bytes =
-        serde_json::to_vec_pretty(value).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    let bytes = to_canonical_json(value)?;
+    Store::write_bytes_atomic(path, &bytes)
+}
+
+fn read_bioproject_manifest(path: &Utf8PathBuf) -> Result<BioProjectManifest, KiraError> {
+    let content = std::fs::read_to_string(path.as_std_path())
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    serde_json::from_str(&content).map_err(|err| KiraError::Filesystem(err.to_string()))
+}
+
+fn write_bioproject_manifest(
+    path: &Utf8PathBuf,
+    value: &BioProjectManifest,
+) -> Result<(), KiraError> {
+    let bytes = to_canonical_json(value)?;
     Store::write_bytes_atomic(path, &bytes)
 }
 
@@ -2341,25 +9554,12 @@ fn load_srr_settings(path: &str) -> (Option<SrrFormat>, Option<bool>) {
     (parsed.format, parsed.paired)
 }
 
-fn write_config_atomic(path: &std::path::Path, config: &Config) -> Result<(), KiraError> {
-    let payload =
-        serde_json::to_vec_pretty(config).map_err(|err| KiraError::Filesystem(err.to_string()))?;
-    let tmp = path.with_extension("json.tmp");
-    std::fs::write(&tmp, &payload).map_err(|err| KiraError::Filesystem(err.to_string()))?;
-    std::fs::rename(&tmp, path).map_err(|err| KiraError::Filesystem(err.to_string()))?;
-    Ok(())
-}
-
 fn fastq_to_fasta(input: &std::path::Path, output: &std::path::Path) -> Result<(), KiraError> {
     let content =
         std::fs::read_to_string(input).map_err(|err| KiraError::Filesystem(err.to_string()))?;
     let mut out = String::new();
     let mut lines = content.lines();
-    loop {
-        let header = match lines.next() {
-            Some(value) => value,
-            None => break,
-        };
+    while let Some(header) = lines.next() {
         let seq = lines.next().ok_or_else(|| {
             KiraError::SrrConversion("invalid FASTQ: missing sequence".to_string())
         })?;
@@ -0,0 +1,100 @@
+//! Append-only log of destructive operations.
+//!
+//! Each store root keeps its own `audit.jsonl`: project-side operations
+//! (`clear`, `remove`, forced re-fetches) are logged under `.kira-bm/`, and
+//! forced replacement of cached data under the cache root. The file is never
+//! removed by `clear`.
+
+use std::fs;
+
+use camino::Utf8Path;
+use serde::{Deserialize, Serialize};
+
+use crate::error::KiraError;
+use crate::fs_util::{append_json_line, path_size, read_json_lines};
+use crate::store::Metadata;
+
+pub const AUDIT_FILE: &str = "audit.jsonl";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: String,
+    pub operation: String,
+    pub user: String,
+    pub targets: Vec<AuditTarget>,
+    #[serde(default)]
+    pub flags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditTarget {
+    pub specifier: String,
+    pub bytes: u64,
+    pub path: String,
+    /// `downloaded_at` of the removed copy, which identifies that instance.
+    pub downloaded_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub undo: Option<String>,
+}
+
+impl AuditRecord {
+    pub fn new(operation: &str, targets: Vec<AuditTarget>, flags: Vec<String>) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            operation: operation.to_string(),
+            user: current_user(),
+            targets,
+            flags,
+        }
+    }
+}
+
+impl AuditTarget {
+    /// Describes a stored dataset before it is destroyed.
+    pub fn from_metadata(metadata: &Metadata, undo: Option<String>) -> Self {
+        Self {
+            specifier: specifier_for(metadata),
+            bytes: path_size(Utf8Path::new(&metadata.resolved_path).as_std_path()).unwrap_or(0),
+            path: metadata.resolved_path.clone(),
+            downloaded_at: metadata.downloaded_at.clone(),
+            undo,
+        }
+    }
+}
+
+/// `type:id` as accepted by `kira-bm fetch`.
+pub fn specifier_for(metadata: &Metadata) -> String {
+    match metadata.dataset_type.as_str() {
+        "go" | "kegg" | "reactome" if metadata.id == metadata.dataset_type => {
+            metadata.dataset_type.clone()
+        }
+        dataset_type => format!("{dataset_type}:{}", metadata.id),
+    }
+}
+
+pub fn refetch_hint(metadata: &Metadata) -> String {
+    format!(
+        "re-fetchable via `kira-bm fetch {}`",
+        specifier_for(metadata)
+    )
+}
+
+/// Appends one record as a single line.
+pub fn append(root: &Utf8Path, record: &AuditRecord) -> Result<(), KiraError> {
+    fs::create_dir_all(root).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    append_json_line(&root.join(AUDIT_FILE), record)
+}
+
+/// Reads the newest `limit` records, oldest first. Torn or foreign lines are
+/// skipped.
+pub fn read(root: &Utf8Path, limit: Option<usize>) -> Result<Vec<AuditRecord>, KiraError> {
+    read_json_lines(&root.join(AUDIT_FILE), limit)
+}
+
+fn current_user() -> String {
+    ["USER", "USERNAME", "LOGNAME"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
@@ -1,20 +1,61 @@
+use std::path::PathBuf;
 use std::process::ExitCode;
+use std::sync::{Arc, OnceLock};
 
-use clap::{Args, Parser, Subcommand};
+use clap::builder::PossibleValuesParser;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
 use miette::IntoDiagnostic;
+#[cfg(feature = "telemetry")]
+use tracing::Level;
 use tracing_subscriber::EnvFilter;
+#[cfg(feature = "telemetry")]
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::prelude::*;
 
-use kira_biodata_manager::app::{App, FetchOptions, FetchOverrides, ProgressSinkKind};
-use kira_biodata_manager::config::ConfigLoader;
-use kira_biodata_manager::domain::{DatasetSpecifier, FetchFormat, ProteinFormat, SrrFormat};
-use kira_biodata_manager::error::KiraError;
-use kira_biodata_manager::geo::{GeoClient, GeoHttpClient};
-use kira_biodata_manager::knowledge::{KnowledgeClient, KnowledgeHttpClient};
-use kira_biodata_manager::ncbi::{NcbiClient, NcbiHttpClient};
-use kira_biodata_manager::output::{JsonOutput, OutputMode};
+use kira_biodata_manager::app::{
+    App, AppContext, ClearOptions, DOI_TARGET_TYPES, DoiSelection, FetchFlags, FetchOptions,
+    FetchOverrides, FetchResult, GcOptions, ImportOptions, InitOptions, ListFlags, ListOptions,
+    ProgressEvent, ProgressFilter, ProgressLevel, ProgressSink, ProgressSinkKind, Verbosity,
+};
+use kira_biodata_manager::audit::{self, AUDIT_FILE};
+use kira_biodata_manager::cancel::CancellationToken;
+use kira_biodata_manager::completion;
+use kira_biodata_manager::config::{
+    CachePolicy, Config, ConfigLoader, ConfigSection, FetchDefaults, GlobalConfig, read_id_list,
+};
+use kira_biodata_manager::control::{self, BatchState, ControlSettings};
+use kira_biodata_manager::doctor::CheckStatus;
+use kira_biodata_manager::domain::{
+    DatasetSpecifier, FetchFormat, GoaSpecies, ProteinFormat, SpecifierPattern, SpecifierReading,
+    SrrFormat,
+};
+use kira_biodata_manager::error::{ErrorReport, KiraError};
+use kira_biodata_manager::fs_util::ZipLimits;
+use kira_biodata_manager::geo::{BundlePreference, FileCategory, GeoClient, GeoHttpClient};
+use kira_biodata_manager::http::{
+    HEALTH_ENDPOINTS, HttpSettings, TimeoutOverrides, health_endpoint, registry_bases_from_env,
+};
+use kira_biodata_manager::index;
+use kira_biodata_manager::knowledge::{GoFlavor, KnowledgeClient, KnowledgeHttpClient};
+use kira_biodata_manager::lock::DEFAULT_LOCK_TIMEOUT;
+use kira_biodata_manager::ncbi::{AssemblyLevel, NcbiClient, NcbiHttpClient};
+use kira_biodata_manager::output::{
+    JsonOutput, OutputMode, PlainOutput, ProgressLines, StyledOutput,
+};
+use kira_biodata_manager::ping::{self, HttpProbe};
+use kira_biodata_manager::project::Project;
+use kira_biodata_manager::providers::ensembl::EnsemblFile;
 use kira_biodata_manager::rcsb::{RcsbClient, RcsbHttpClient};
-use kira_biodata_manager::srr::{SrrClient, SrrToolStatus, SystemSrrClient};
+use kira_biodata_manager::retention;
+use kira_biodata_manager::runs::{DEFAULT_RUNS_KEPT, RunInvocation};
+use kira_biodata_manager::schema::SchemaCommand;
+use kira_biodata_manager::srr::{SrrClient, SrrToolStatus, SystemSrrClient, ToolCheckStatus};
+use kira_biodata_manager::stats;
 use kira_biodata_manager::store::Store;
+#[cfg(feature = "telemetry")]
+use kira_biodata_manager::telemetry;
 use kira_biodata_manager::tui::Tui;
 use kira_biodata_manager::uniprot::{UniprotClient, UniprotHttpClient};
 
@@ -26,6 +67,37 @@ struct Cli {
     #[arg(long, global = true)]
     non_interactive: bool,
 
+    /// Print plain ASCII text: no TUI, color or emoji
+    #[arg(long, global = true)]
+    plain: bool,
+
+    /// Send every registry request through this proxy (overrides HTTPS_PROXY/HTTP_PROXY)
+    #[arg(long, global = true, value_name = "URL")]
+    proxy: Option<String>,
+
+    /// PEM file of CA certificates to trust, e.g. a corporate proxy's
+    #[arg(long, global = true, value_name = "PATH")]
+    ca_bundle: Option<PathBuf>,
+
+    /// Seconds an API request may take in total (downloads only time out
+    /// when they stall)
+    #[arg(long, global = true, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    timeout: Option<u64>,
+
+    /// Seconds to wait for a registry connection
+    #[arg(long, global = true, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    connect_timeout: Option<u64>,
+
+    /// Use the current directory as the project instead of searching parent
+    /// directories for kira-bm.json
+    #[arg(long, global = true)]
+    no_discover: bool,
+
+    /// Export registry spans to this OTLP/HTTP collector when the command ends
+    #[cfg(feature = "telemetry")]
+    #[arg(long, global = true, value_name = "URL")]
+    otlp_endpoint: Option<reqwest::Url>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -37,15 +109,202 @@ enum Commands {
     #[command(about = "Fetch datasets (alias of fetch)")]
     Add(FetchArgs),
     #[command(about = "List locally available datasets")]
-    List,
+    List(ListFlags),
+    #[command(about = "Search local datasets by id, title, organism or file name")]
+    Find(FindArgs),
     #[command(about = "Show dataset info")]
     Info(InfoArgs),
+    #[command(about = "Print where a dataset is stored")]
+    Path(PathArgs),
+    #[command(about = "Convert a stored protein to another format, offline")]
+    Convert(ConvertArgs),
+    #[command(about = "Register local files as a dataset in the project store")]
+    Import(ImportArgs),
     #[command(about = "Clear project-local store")]
-    Clear,
-    #[command(about = "Generate kira-bm.json from local store")]
-    Init,
+    Clear(ClearArgs),
+    #[command(about = "Generate kira-bm.json from local store, or add to an existing one")]
+    Init(InitArgs),
+    #[command(about = "Protect a dataset from clear, retention, gc and removal")]
+    Pin(PinArgs),
+    #[command(about = "Remove the protection added by pin")]
+    Unpin(PinArgs),
     #[command(about = "Manage external tools")]
     Tools(ToolsArgs),
+    #[command(about = "Pause, resume or inspect a running batch fetch")]
+    Batch(BatchArgs),
+    #[command(about = "Inspect the global cache")]
+    Cache(CacheArgs),
+    #[command(about = "Show the log of destructive operations")]
+    Audit(AuditArgs),
+    #[command(about = "Expire project datasets older than their retention policy")]
+    Retention(RetentionArgs),
+    #[command(about = "Remove stale temp directories, dangling metadata and orphaned datasets")]
+    Gc(GcArgs),
+    #[command(about = "Show the manifests written by past fetches")]
+    Runs(RunsArgs),
+    #[command(about = "Check kira-bm.json")]
+    Config(ConfigArgs),
+    #[command(about = "Check SRA tools, registry access, clock and store directories")]
+    Doctor(DoctorArgs),
+    #[command(about = "Check that registries are reachable, with status and latency")]
+    Ping(PingArgs),
+    #[command(about = "Print a shell completion script (bash, zsh, fish, elvish, powershell)")]
+    Completions { shell: Shell },
+    #[command(about = "Print the JSON Schema of a command's --non-interactive output")]
+    Schema { command: SchemaCommand },
+    #[command(name = "__complete-specifiers", hide = true)]
+    CompleteSpecifiers { partial: Option<String> },
+}
+
+#[derive(Args)]
+struct RetentionArgs {
+    #[command(subcommand)]
+    command: RetentionCommand,
+}
+
+#[derive(Subcommand)]
+enum RetentionCommand {
+    #[command(about = "Delete the data of expired datasets, keeping their metadata")]
+    Apply {
+        /// Report what would expire without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Expire datasets pinned with `kira-bm pin` too
+        #[arg(long)]
+        include_pinned: bool,
+
+        #[arg(long)]
+        config: Option<String>,
+    },
+}
+
+#[derive(Args)]
+struct GcArgs {
+    /// Delete what is found; without it gc only reports
+    #[arg(long)]
+    yes: bool,
+
+    /// Also delete dataset directories that no metadata record points into
+    #[arg(long)]
+    prune_orphans: bool,
+
+    /// Delete dangling records of pinned datasets too
+    #[arg(long)]
+    include_pinned: bool,
+
+    /// Leave temp directories touched more recently than this, like "24h" or "7d"
+    #[arg(long, value_name = "AGE", default_value = "24h", value_parser = parse_gc_age)]
+    older_than: std::time::Duration,
+}
+
+fn parse_gc_age(value: &str) -> Result<std::time::Duration, String> {
+    retention::parse_max_age(value)
+        .and_then(|age| age.to_std().ok())
+        .ok_or_else(|| format!("expected an age like \"36h\", \"7d\" or \"2w\", got {value:?}"))
+}
+
+#[derive(Args)]
+struct RunsArgs {
+    #[command(subcommand)]
+    command: RunsCommand,
+}
+
+#[derive(Subcommand)]
+enum RunsCommand {
+    #[command(about = "List recorded fetch runs, oldest first")]
+    List,
+    #[command(about = "Print the manifest of one fetch run")]
+    Show { id: String },
+}
+
+#[derive(Args)]
+struct ConfigArgs {
+    #[command(subcommand)]
+    command: ConfigCommand,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    #[command(about = "Check entries, ids and duplicates offline; exits 1 on any problem")]
+    Validate {
+        #[arg(long)]
+        config: Option<String>,
+    },
+    #[command(
+        about = "Show the fetch defaults and cache policy in effect and which config set each"
+    )]
+    Show {
+        #[arg(long)]
+        config: Option<String>,
+    },
+}
+
+#[derive(Args)]
+struct DoctorArgs {
+    /// Apply automated fixes (reserved; no fixes are available yet)
+    #[arg(long)]
+    fix: bool,
+}
+
+#[derive(Args)]
+struct PingArgs {
+    /// Registries to probe; all of them when none are given
+    #[arg(value_parser = PossibleValuesParser::new(HEALTH_ENDPOINTS.map(|endpoint| endpoint.registry)))]
+    registries: Vec<String>,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args)]
+struct AuditArgs {
+    /// Show only the newest N records
+    #[arg(long)]
+    limit: Option<usize>,
+
+    /// Show the global cache's log instead of the project's
+    #[arg(long)]
+    cache: bool,
+}
+
+#[derive(Args)]
+struct BatchArgs {
+    #[command(subcommand)]
+    command: BatchCommand,
+}
+
+#[derive(Subcommand)]
+enum BatchCommand {
+    #[command(about = "Pause the running batch after its current item")]
+    Pause,
+    #[command(about = "Resume a paused batch")]
+    Resume,
+    #[command(about = "Show the running batch and any pending request")]
+    Status,
+}
+
+#[derive(Args)]
+struct CacheArgs {
+    #[command(subcommand)]
+    command: CacheCommand,
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    #[command(about = "Show what cache hits have saved: hits, bytes and download time")]
+    Stats {
+        /// Show the datasets and bytes each project holds from the cache instead
+        #[arg(long)]
+        by_project: bool,
+    },
+    #[command(
+        about = "Move cached files into a shared blob store, linking duplicates, and report bytes reclaimed"
+    )]
+    Dedup,
+    #[command(about = "Rebuild the cache index behind list --global and cache stats --by-project")]
+    Reindex,
 }
 
 #[derive(Args)]
@@ -58,6 +317,8 @@ struct ToolsArgs {
 enum ToolsCommand {
     #[command(about = "Print instructions to install SRA Toolkit (prefetch/fasterq-dump)")]
     InstallSra,
+    #[command(about = "Check the versions of prefetch, fasterq-dump and datasets")]
+    Check,
 }
 
 #[derive(Subcommand)]
@@ -67,13 +328,15 @@ enum DataCommand {
     #[command(about = "Fetch datasets (alias of fetch)")]
     Add(FetchArgs),
     #[command(about = "List locally available datasets")]
-    List,
+    List(ListFlags),
+    #[command(about = "Search local datasets by id, title, organism or file name")]
+    Find(FindArgs),
     #[command(about = "Show dataset info")]
     Info(InfoArgs),
     #[command(about = "Clear project-local store")]
-    Clear,
-    #[command(about = "Generate kira-bm.json from local store")]
-    Init,
+    Clear(ClearArgs),
+    #[command(about = "Generate kira-bm.json from local store, or add to an existing one")]
+    Init(InitArgs),
 }
 
 #[derive(Args, Clone)]
@@ -83,32 +346,265 @@ struct FetchArgs {
     #[arg(long)]
     config: Option<String>,
 
+    /// Fetch every dataset listed in a file, one specifier or ID per line
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["specifier", "config"])]
+    from_file: Option<PathBuf>,
+
     #[arg(long)]
     format: Option<FetchFormat>,
 
-    #[arg(long)]
+    #[arg(long, overrides_with = "no_paired")]
     paired: bool,
 
+    /// Fetch SRR runs single-ended where the config defaults to paired
+    #[arg(long, overrides_with = "paired")]
+    no_paired: bool,
+
+    /// Threads fasterq-dump converts SRR runs with
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    srr_threads: Option<u32>,
+
+    /// Directory for fasterq-dump's scratch files (default: the project's temp area)
+    #[arg(long, value_name = "PATH")]
+    srr_temp_dir: Option<PathBuf>,
+
+    #[command(flatten)]
+    flags: FetchFlags,
+
+    /// Take an expression series' empty file listing from its SuperSeries or SubSeries
     #[arg(long)]
-    force: bool,
+    follow_superseries: bool,
+
+    /// Expression file categories to download besides data: document, image, other
+    #[arg(long, value_delimiter = ',')]
+    include_categories: Vec<FileCategory>,
+
+    /// Keep a genome package that lacks a requested file, with a warning
+    #[arg(long)]
+    allow_missing_artifacts: bool,
+
+    /// Files a genome:ensembl: fetch downloads: dna, cdna, gtf, gff3 (default dna,gtf)
+    #[arg(long, value_delimiter = ',', value_name = "FILES")]
+    ensembl_include: Vec<EnsemblFile>,
+
+    /// Skip the FASTA sequences fetched beside a protein structure
+    #[arg(long)]
+    no_fasta: bool,
 
+    /// Number of structures to fetch for a structure-for specifier
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    top: Option<u16>,
+
+    /// Resolve a genome name only by its exact assembly name
+    #[arg(long)]
+    exact: bool,
+
+    /// Assembly level a genome:taxon: fetch keeps
     #[arg(long)]
-    no_cache: bool,
+    assembly_level: Option<AssemblyLevel>,
 
+    /// Keep only reference genomes in a genome:taxon: fetch
+    #[arg(long)]
+    reference_only: bool,
+
+    /// Number of assemblies to fetch for a genome:taxon: specifier
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    limit: Option<u16>,
+
+    /// GO ontology edition: basic (go-basic.obo) or full (go.obo)
+    #[arg(long)]
+    go_flavor: Option<GoFlavor>,
+
+    /// Species whose GOA annotation files a go fetch adds, e.g. human,mouse
+    #[arg(long, value_delimiter = ',')]
+    go_annotations: Vec<GoaSpecies>,
+
+    /// Species a reactome fetch keeps, e.g. "Homo sapiens"
+    #[arg(long)]
+    species: Option<String>,
+
+    /// Store only the species-filtered Reactome files
+    #[arg(long, requires = "species")]
+    replace_unfiltered: bool,
+
+    /// Also fetch the PDB structures a uniprot entry cross-references
+    #[arg(long)]
+    with_structures: bool,
+
+    /// Number of structures a --with-structures fetch downloads at most
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    max_structures: Option<u16>,
+
+    /// Layout an expression10x fetch keeps for samples published as both
+    /// MTX and HDF5 matrices
+    #[arg(long)]
+    prefer_bundle: Option<BundlePreference>,
+
+    /// Pick the targets of a doi: fetch to download from a checklist
+    #[arg(long)]
+    select: bool,
+
+    /// Download only these types of a doi: fetch's targets, e.g. srr,protein
+    #[arg(long, value_delimiter = ',', value_name = "TYPES")]
+    only: Vec<String>,
+
+    /// Download at most N targets of each type in a doi: fetch
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    limit_per_type: Option<u32>,
+
+    /// Leave these ids out of a doi: fetch, e.g. SRR1,SRR2
+    #[arg(long, value_delimiter = ',', value_name = "IDS")]
+    exclude: Vec<String>,
+
+    /// Download every target a doi: fetch resolves to
+    #[arg(long)]
+    download_all: bool,
+
+    /// Also search the DOIs a doi: record supplements or references
+    #[arg(long)]
+    follow_related: bool,
+
+    /// Print only the result, without progress lines or warnings
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Also print each registry request (-v) and its latency and retries (-vv)
+    #[arg(short, long, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+}
+
+#[derive(Args, Clone, Copy)]
+struct InitArgs {
+    /// Regenerate kira-bm.json from the store, dropping entries and settings it does not list
+    #[arg(long)]
+    force: bool,
+
+    /// Print the changes to kira-bm.json without writing it
     #[arg(long)]
     dry_run: bool,
 }
 
+impl From<InitArgs> for InitOptions {
+    fn from(args: InitArgs) -> Self {
+        Self {
+            force: args.force,
+            dry_run: args.dry_run,
+        }
+    }
+}
+
 #[derive(Args)]
 struct InfoArgs {
     specifier: String,
+
+    /// For a doi: specifier, list the local datasets fetched for it
+    #[arg(long)]
+    datasets: bool,
+}
+
+#[derive(Args)]
+struct PathArgs {
+    specifier: String,
+
+    /// The structure file of a protein in this format instead of the one fetched last
+    #[arg(long)]
+    format: Option<ProteinFormat>,
+
+    /// The cache copy instead of the project's
+    #[arg(long)]
+    cache: bool,
+}
+
+#[derive(Args)]
+struct ConvertArgs {
+    specifier: String,
+
+    /// Format to write next to the stored file
+    #[arg(long)]
+    to: ProteinFormat,
+}
+
+#[derive(Args)]
+struct ImportArgs {
+    specifier: String,
+
+    /// File or directory holding the dataset
+    #[arg(long, value_name = "PATH")]
+    from: PathBuf,
+
+    /// Hard-link the files instead of copying them, falling back to a copy
+    #[arg(long)]
+    link: bool,
+
+    /// Skip checking that the files hold the dataset the specifier names
+    #[arg(long)]
+    no_verify: bool,
+
+    /// Replace the dataset if the project store already has it
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(Args, Clone)]
+struct ClearArgs {
+    /// Clear pinned datasets too
+    #[arg(long)]
+    include_pinned: bool,
+
+    /// Clear only these dataset types, e.g. protein,srr
+    #[arg(long = "type", value_delimiter = ',', value_name = "TYPES")]
+    types: Vec<String>,
+
+    /// Remove the data files but keep the datasets' metadata records
+    #[arg(long)]
+    keep_metadata: bool,
+
+    /// List what would be removed, with sizes, without removing it
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl ClearArgs {
+    fn options(&self) -> ClearOptions {
+        ClearOptions {
+            include_pinned: self.include_pinned,
+            types: self.types.clone(),
+            keep_metadata: self.keep_metadata,
+            dry_run: self.dry_run,
+        }
+    }
+}
+
+#[derive(Args)]
+struct PinArgs {
+    specifier: String,
+}
+
+#[derive(Args)]
+struct FindArgs {
+    pattern: String,
 }
 
 fn main() -> ExitCode {
-    if let Err(report) = run() {
-        eprintln!("{report:?}");
-        if let Some(kira) = report.downcast_ref::<KiraError>() {
-            return ExitCode::from(map_exit_code(kira));
+    let cli = Cli::parse();
+    let output_mode = OutputMode::detect(cli.non_interactive, cli.plain);
+    if let Err(report) = run(cli, output_mode) {
+        let kira = report.downcast_ref::<KiraError>();
+        // The fetch result already lists what failed.
+        let incomplete = matches!(kira, Some(KiraError::FetchIncomplete { .. }));
+        if output_mode == OutputMode::NonInteractive && !incomplete {
+            let error = kira.map(ErrorReport::from).unwrap_or_else(|| ErrorReport {
+                error: report.to_string(),
+                specifier: None,
+                phase: None,
+                help: None,
+            });
+            let _ = JsonOutput::print_error(&error);
+        } else if output_mode != OutputMode::NonInteractive {
+            eprintln!("{report:?}");
+        }
+        if let Some(kira) = kira {
+            return ExitCode::from(map_exit_code(kira.root()));
         }
         return ExitCode::from(1);
     }
@@ -117,7 +613,7 @@ fn main() -> ExitCode {
 
 fn map_exit_code(error: &KiraError) -> u8 {
     match error {
-        KiraError::DatasetNotFound(_) => 2,
+        KiraError::DatasetNotFound(_) | KiraError::NoMatchingDatasets(_) => 2,
         KiraError::MissingConfig => 2,
         KiraError::NcbiHttp(_)
         | KiraError::NcbiStatus { .. }
@@ -127,158 +623,446 @@ fn map_exit_code(error: &KiraError) -> u8 {
         | KiraError::UniprotStatus { .. }
         | KiraError::CrossrefHttp(_)
         | KiraError::CrossrefStatus { .. }
+        | KiraError::DataciteHttp(_)
+        | KiraError::DataciteStatus { .. }
+        | KiraError::EnsemblHttp(_)
+        | KiraError::EnsemblStatus { .. }
+        | KiraError::GeoHttp(_)
+        | KiraError::GeoStatus { .. }
+        | KiraError::KnowledgeHttp(_)
+        | KiraError::KnowledgeStatus { .. }
+        | KiraError::RegistriesUnreachable(_)
         | KiraError::MissingTool(_)
-        | KiraError::SrrConversion(_) => 3,
-        KiraError::DoiResolution(_) => 2,
+        | KiraError::OutdatedTools(_)
+        | KiraError::SrrConversion(_)
+        | KiraError::FetchIncomplete { .. } => 3,
+        KiraError::DoiResolution(_)
+        | KiraError::NoCoveringStructure(_)
+        | KiraError::NcbiGenomeRejected { .. }
+        | KiraError::EnsemblFileMissing { .. } => 2,
+        KiraError::Cancelled => 130,
         _ => 1,
     }
 }
 
-fn run() -> miette::Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
+/// Token cancelled by the first Ctrl-C; a second one exits immediately.
+fn interrupt_token() -> CancellationToken {
+    static TOKEN: OnceLock<CancellationToken> = OnceLock::new();
+    TOKEN
+        .get_or_init(|| {
+            let token = CancellationToken::new();
+            let handler = token.clone();
+            let installed = ctrlc::set_handler(move || {
+                if handler.is_cancelled() {
+                    std::process::exit(130);
+                }
+                eprintln!("cancelling; press Ctrl-C again to exit immediately");
+                handler.cancel();
+            });
+            if let Err(err) = installed {
+                eprintln!("warning: unable to handle Ctrl-C: {err}");
+            }
+            token
+        })
+        .clone()
+}
+
+fn is_cancelled(report: &miette::Report) -> bool {
+    matches!(
+        report.downcast_ref::<KiraError>().map(KiraError::root),
+        Some(KiraError::Cancelled)
+    )
+}
+
+/// Fails with exit code 3 when some datasets of a config-driven fetch
+/// could not be fetched.
+fn ensure_complete(result: &FetchResult) -> miette::Result<()> {
+    if result.errors.is_empty() {
+        return Ok(());
+    }
+    Err(KiraError::FetchIncomplete {
+        failed: result.errors.len(),
+        total: result.errors.len() + result.items.len(),
+    }
+    .into())
+}
+
+fn run(cli: Cli, output_mode: OutputMode) -> miette::Result<()> {
+    // RUST_LOG filters the log output only, so an OTLP export gets every
+    // registry span whatever the log level.
+    let log = tracing_subscriber::fmt::layer()
         .with_target(false)
         .with_writer(std::io::stderr)
-        .init();
-
-    let cli = Cli::parse();
-    let output_mode = if cli.non_interactive {
-        OutputMode::NonInteractive
-    } else {
-        OutputMode::Interactive
+        .with_span_events(FmtSpan::CLOSE)
+        .with_filter(EnvFilter::from_default_env());
+    let subscriber = tracing_subscriber::registry().with(log);
+    #[cfg(feature = "telemetry")]
+    let _otlp = {
+        let (layer, guard) = match &cli.otlp_endpoint {
+            Some(endpoint) => {
+                let (layer, guard) = telemetry::otlp(endpoint);
+                let layer = layer
+                    .with_filter(Targets::new().with_target("kira_biodata_manager", Level::INFO));
+                (Some(layer), Some(guard))
+            }
+            None => (None, None),
+        };
+        subscriber.with(layer).init();
+        guard
     };
+    #[cfg(not(feature = "telemetry"))]
+    subscriber.init();
 
-    let store = Store::new().into_diagnostic()?;
+    let cwd = std::env::current_dir().into_diagnostic()?;
+    let explicit_config = match &cli.command {
+        Some(Commands::Fetch(args) | Commands::Add(args)) => args.config.is_some(),
+        Some(Commands::Retention(args)) => match &args.command {
+            RetentionCommand::Apply { config, .. } => config.is_some(),
+        },
+        Some(Commands::Config(args)) => match &args.command {
+            ConfigCommand::Validate { config } | ConfigCommand::Show { config } => config.is_some(),
+        },
+        _ => false,
+    };
+    if cli.no_discover || explicit_config {
+        Project::at(&cwd).select();
+    } else {
+        Project::discover(&cwd).select();
+    }
+    let store = Store::new()?;
+    let _ = NETWORK_FLAGS.set(NetworkFlags {
+        proxy: cli.proxy.clone(),
+        ca_bundle: cli.ca_bundle.clone(),
+        timeouts: TimeoutOverrides {
+            connect_secs: cli.connect_timeout,
+            request_secs: cli.timeout,
+            stall_secs: None,
+        },
+    });
 
     match cli.command {
         Some(Commands::Fetch(args)) => {
             run_data_command(DataCommand::Fetch(args), store, output_mode)
         }
         Some(Commands::Add(args)) => run_data_command(DataCommand::Add(args), store, output_mode),
-        Some(Commands::List) => run_data_command(DataCommand::List, store, output_mode),
+        Some(Commands::List(flags)) if flags.global => run_global_list(&flags, &store, output_mode),
+        Some(Commands::List(flags)) => {
+            run_data_command(DataCommand::List(flags), store, output_mode)
+        }
+        Some(Commands::Find(args)) => run_data_command(DataCommand::Find(args), store, output_mode),
         Some(Commands::Info(args)) => run_data_command(DataCommand::Info(args), store, output_mode),
-        Some(Commands::Clear) => run_data_command(DataCommand::Clear, store, output_mode),
-        Some(Commands::Init) => run_data_command(DataCommand::Init, store, output_mode),
-        Some(Commands::Tools(args)) => run_tools(args),
+        Some(Commands::Path(args)) => run_path(args, &store),
+        Some(Commands::Convert(args)) => run_convert(args, store, output_mode),
+        Some(Commands::Import(args)) => run_import(args, store, output_mode),
+        Some(Commands::Clear(args)) => {
+            run_data_command(DataCommand::Clear(args), store, output_mode)
+        }
+        Some(Commands::Init(args)) => run_data_command(DataCommand::Init(args), store, output_mode),
+        Some(Commands::Pin(args)) => run_pin(args, true, store, output_mode),
+        Some(Commands::Unpin(args)) => run_pin(args, false, store, output_mode),
+        Some(Commands::Tools(args)) => run_tools(args, output_mode),
+        Some(Commands::Batch(args)) => run_batch(args, &store, output_mode),
+        Some(Commands::Cache(args)) => run_cache(args, &store, output_mode),
+        Some(Commands::Audit(args)) => run_audit(args, &store, output_mode),
+        Some(Commands::Retention(args)) => run_retention(args, &store, output_mode),
+        Some(Commands::Gc(args)) => run_gc(args, store, output_mode),
+        Some(Commands::Runs(args)) => run_runs(args, store, output_mode),
+        Some(Commands::Config(args)) => run_config(args, output_mode),
+        Some(Commands::Doctor(args)) => run_doctor(args, store, output_mode),
+        Some(Commands::Ping(args)) => run_ping(args, output_mode),
+        Some(Commands::Completions { shell }) => {
+            print!("{}", completion::script(shell, &mut Cli::command()));
+            Ok(())
+        }
+        Some(Commands::Schema { command }) => {
+            let schema = serde_json::to_string_pretty(&command.schema()).into_diagnostic()?;
+            println!("{schema}");
+            Ok(())
+        }
+        Some(Commands::CompleteSpecifiers { partial }) => {
+            for candidate in completion::specifiers(&store, partial.as_deref().unwrap_or_default())
+            {
+                println!("{candidate}");
+            }
+            Ok(())
+        }
         None => {
-            if matches!(output_mode, OutputMode::Interactive) {
-                if let Ok(resolved) = ConfigLoader::resolve(None) {
-                    let ncbi = NcbiHttpClient::new().into_diagnostic()?;
-                    let rcsb = RcsbHttpClient::new().into_diagnostic()?;
-                    let srr = SystemSrrClient::new();
-                    let uniprot = UniprotHttpClient::new().into_diagnostic()?;
-                    let geo = GeoHttpClient::new().into_diagnostic()?;
-                    let knowledge = KnowledgeHttpClient::new().into_diagnostic()?;
-                    let app = App::new(store.clone(), ncbi, rcsb, srr, uniprot, geo, knowledge);
-                    let mut tui = Tui::new(ProgressSinkKind::Fetch);
-                    if let SrrToolStatus::Missing { .. } = SystemSrrClient::new().tool_status() {
-                        tui.note_warning(
-                            "warning: SRR identifiers require the external NCBI SRA Toolkit (sratools https://github.com/ncbi/sra-tools ).",
-                        );
-                        tui.note_warning(
-                            "warning: The toolkit is not bundled. Please install it separately if needed.",
-                        );
-                    }
-                    let fetch_options = FetchOptions {
-                        force: false,
-                        no_cache: false,
-                        dry_run: false,
-                    };
-                    let result = tui.run(move |sink| {
-                        app.fetch(
-                            None,
-                            Some(&resolved),
-                            FetchOverrides::default(),
-                            fetch_options,
-                            sink,
-                        )
-                    });
-                    match result {
-                        Ok(result) => {
-                            tui.finish_fetch(&result)?;
-                            print_fetch_summary(&result);
-                            Ok(())
-                        }
-                        Err(err) => {
-                            let mut tui = Tui::new(ProgressSinkKind::Fetch);
-                            tui.note_error(&format!("error: {err}"));
-                            loop {
-                                let command = tui.idle_command()?;
-                                let Some(command) = command else {
-                                    break Ok(());
-                                };
-                                if command.trim_start().starts_with("tools ") {
-                                    if let Err(err) = run_tools_from_line(&command) {
-                                        tui.note_error(&format!("error: {err}"));
-                                    }
-                                    continue;
-                                }
-                                let data_command = match parse_tui_command(&command) {
-                                    Ok(cmd) => cmd,
-                                    Err(err) => {
-                                        tui.note_error(&format!("error: {err}"));
-                                        continue;
-                                    }
-                                };
-                                let keep_open = matches!(
-                                    data_command,
-                                    DataCommand::Fetch(_) | DataCommand::Add(_)
-                                );
-                                if let Err(err) =
-                                    run_data_command(data_command, store.clone(), output_mode)
-                                {
-                                    tui.note_error(&format!("error: {err}"));
-                                }
-                                if !keep_open {
-                                    break Ok(());
-                                }
-                            }
-                        }
-                    }
-                } else {
-                    let mut tui = Tui::new(ProgressSinkKind::Fetch);
-                    if let SrrToolStatus::Missing { .. } = SystemSrrClient::new().tool_status() {
-                        tui.note_warning(
-                            "warning: SRR identifiers require the external NCBI SRA Toolkit (sratools https://github.com/ncbi/sra-tools ).",
-                        );
-                        tui.note_warning(
-                            "warning: The toolkit is not bundled. Please install it separately if needed.",
-                        );
-                    }
-                    loop {
-                        let command = tui.idle_command()?;
-                        let Some(command) = command else {
-                            break Ok(());
-                        };
-                        if command.trim_start().starts_with("tools ") {
-                            run_tools_from_line(&command)?;
-                            continue;
-                        }
-                        let data_command = match parse_tui_command(&command) {
-                            Ok(cmd) => cmd,
-                            Err(err) => {
-                                tui.note_error(&format!("error: {err}"));
-                                continue;
-                            }
-                        };
-                        let keep_open =
-                            matches!(data_command, DataCommand::Fetch(_) | DataCommand::Add(_));
-                        if let Err(err) = run_data_command(data_command, store.clone(), output_mode)
-                        {
-                            tui.note_error(&format!("error: {err}"));
-                        }
-                        if !keep_open {
-                            break Ok(());
-                        }
+            if !matches!(output_mode, OutputMode::Interactive) {
+                return Err(miette::Report::msg(
+                    "command required (try `kira-bm --help`)",
+                ));
+            }
+            let app = Arc::new(http_app(store)?);
+            let mut tui = Tui::new(ProgressSinkKind::Fetch);
+            if let SrrToolStatus::Missing { .. } = SystemSrrClient::new().tool_status() {
+                tui.note_warning(
+                    "warning: SRR identifiers require the external NCBI SRA Toolkit (sratools https://github.com/ncbi/sra-tools ).",
+                );
+                tui.note_warning(
+                    "warning: The toolkit is not bundled. Please install it separately if needed.",
+                );
+            }
+            if let Ok(resolved) = ConfigLoader::resolve(None) {
+                let config_app = app.clone();
+                let result = tui.run(move |sink| {
+                    config_app.fetch(
+                        None,
+                        Some(&resolved),
+                        FetchOverrides::default(),
+                        FetchOptions::default(),
+                        sink,
+                    )
+                });
+                match result {
+                    Ok(result) => {
+                        tui.finish_fetch(&result)?;
+                        StyledOutput::print_fetch(&result).into_diagnostic()?;
+                        return ensure_complete(&result);
                     }
+                    Err(err) if is_cancelled(&err) => return Err(err),
+                    Err(err) => tui.note_error(&format!("error: {err}")),
                 }
-            } else {
-                Err(miette::Report::msg(
-                    "command required (try `kira-bm --help`)",
-                ))
             }
+            run_session(tui, app)
+        }
+    }
+}
+
+/// The TUI prompt. Every command typed into it runs on the same screen
+/// with the same `App`, so the log, history, warnings and store summary
+/// carry over; a command that prints to the shell closes the screen and
+/// ends the session.
+fn run_session(mut tui: Tui, app: Arc<HttpApp>) -> miette::Result<()> {
+    tui.open()?;
+    while let Some(command) = tui.idle_command()? {
+        if command.trim_start().starts_with("tools ") {
+            // The installer talks to the shell.
+            tui.close()?;
+            let result = run_tools_from_line(&command);
+            tui.open()?;
+            if let Err(err) = result {
+                tui.note_error(&format!("error: {err}"));
+            }
+            continue;
+        }
+        let data_command = match parse_tui_command(&command) {
+            Ok(cmd) => cmd,
+            Err(err) => {
+                tui.note_error(&format!("error: {err}"));
+                continue;
+            }
+        };
+        // Datasets record the command typed, not the `kira-bm` that opened
+        // the session.
+        let context = AppContext::new(
+            command.split_whitespace().map(str::to_string).collect(),
+            std::env::current_dir().ok().as_deref(),
+        );
+        let command_app = Arc::new(app.as_ref().clone().with_context(context));
+        if let Err(err) = dispatch_data_command(
+            data_command,
+            command_app,
+            OutputMode::Interactive,
+            Some(&mut tui),
+        ) {
+            tui.note_error(&format!("error: {err}"));
+        }
+        if !tui.is_open() {
+            break;
+        }
+    }
+    tui.close()
+}
+
+/// The open session's TUI, switched to `kind`, or a new one parked in
+/// `own` for a single command.
+fn session_tui<'a>(
+    session: Option<&'a mut Tui>,
+    own: &'a mut Option<Tui>,
+    kind: ProgressSinkKind,
+) -> &'a mut Tui {
+    match session {
+        Some(tui) => {
+            tui.set_kind(kind);
+            tui
+        }
+        None => own.insert(Tui::new(kind)),
+    }
+}
+
+/// Output for the shell cannot share the screen, so it ends a session.
+fn leave_session(session: Option<&mut Tui>) -> miette::Result<()> {
+    match session {
+        Some(tui) => tui.close(),
+        None => Ok(()),
+    }
+}
+
+/// The dataset a command-line value names. What was inferred for a bare id
+/// is noted; an id several types fit is offered as a choice in the
+/// interactive terminal and fails elsewhere.
+fn read_specifier<
+    N: NcbiClient,
+    R: RcsbClient,
+    S: SrrClient,
+    U: UniprotClient,
+    G: GeoClient,
+    K: KnowledgeClient,
+>(
+    app: &App<N, R, S, U, G, K>,
+    value: &str,
+    output_mode: OutputMode,
+    session: Option<&mut Tui>,
+) -> miette::Result<DatasetSpecifier> {
+    let candidates = match app.read_specifier(value)? {
+        SpecifierReading::Explicit(specifier) => return Ok(specifier),
+        SpecifierReading::Inferred(specifier) => {
+            let note = format!("{} read as {specifier}", value.trim());
+            match session {
+                Some(tui) => tui.note(&note),
+                None => eprintln!("note: {note}"),
+            }
+            return Ok(specifier);
         }
+        SpecifierReading::Ambiguous(candidates) => candidates,
+    };
+    let ambiguous = || {
+        miette::Report::new(KiraError::AmbiguousSpecifier {
+            value: value.trim().to_string(),
+            candidates: candidates.iter().map(ToString::to_string).collect(),
+        })
+    };
+    if !matches!(output_mode, OutputMode::Interactive) {
+        return Err(ambiguous());
+    }
+    let mut own = None;
+    let tui = match session {
+        Some(tui) => tui,
+        None => own.insert(Tui::new(ProgressSinkKind::Info)),
+    };
+    match tui.pick_specifier(value.trim(), &candidates)? {
+        Some(specifier) => Ok(specifier),
+        None => Err(miette::Report::new(KiraError::Cancelled)),
+    }
+}
+
+/// `--proxy`, `--ca-bundle`, `--timeout` and `--connect-timeout`, which
+/// apply to every command that talks to a registry.
+#[derive(Clone, Default)]
+struct NetworkFlags {
+    proxy: Option<String>,
+    ca_bundle: Option<PathBuf>,
+    timeouts: TimeoutOverrides,
+}
+
+static NETWORK_FLAGS: OnceLock<NetworkFlags> = OnceLock::new();
+
+/// Reports the project root when it was found above the current directory.
+fn note_project(sink: &dyn ProgressSink) {
+    let Ok(project) = Project::current() else {
+        return;
+    };
+    if std::env::current_dir().is_ok_and(|cwd| cwd != project.root) {
+        sink.event(ProgressEvent {
+            message: format!("project.root {}", project.root.display()),
+            elapsed: None,
+            level: ProgressLevel::Phase,
+        });
+    }
+}
+
+/// Registry access settings: flags first, then `kira-bm.json`, then the
+/// environment. Registry base URLs come from the environment, then the
+/// global config.
+fn http_settings() -> miette::Result<HttpSettings> {
+    let config = ConfigLoader::resolve(None).ok();
+    let global = GlobalConfig::load()?;
+    let flags = NETWORK_FLAGS.get().cloned().unwrap_or_default();
+    let proxy = flags
+        .proxy
+        .or_else(|| config.as_ref().and_then(|config| config.proxy.clone()));
+    let ca_bundle = flags
+        .ca_bundle
+        .or_else(|| config.as_ref().and_then(|config| config.ca_bundle.clone()));
+    let mut http = HttpSettings::from_env()
+        .with_ncbi_api_key(
+            config
+                .as_ref()
+                .and_then(|config| config.ncbi_api_key.clone()),
+        )
+        .with_proxy(proxy.as_deref())?
+        .with_ca_bundle(ca_bundle)
+        .with_registry_bases(global.registries)
+        .with_registry_bases(registry_bases_from_env());
+    for (registry, base) in http.effective_registry_bases() {
+        tracing::debug!(registry, %base, "registry base URL");
+    }
+    if let Some(config) = config {
+        http = http.with_timeouts(config.timeouts);
+        for (registry, timeouts) in config.registry_timeouts {
+            http = http.with_registry_timeouts(&registry, timeouts);
+        }
+    }
+    Ok(http.with_timeouts(flags.timeouts))
+}
+
+/// Settings for commands that fetch: a CA bundle that does not load fails
+/// here rather than as a TLS error on the first request.
+fn checked_http_settings() -> miette::Result<HttpSettings> {
+    let http = http_settings()?;
+    if let Some(bundle) = http.ca_bundle() {
+        bundle?;
     }
+    Ok(http)
+}
+
+type HttpApp = App<
+    NcbiHttpClient,
+    RcsbHttpClient,
+    SystemSrrClient,
+    UniprotHttpClient,
+    GeoHttpClient,
+    KnowledgeHttpClient,
+>;
+
+/// An `App` that talks to the registries.
+fn http_app(store: Store) -> miette::Result<HttpApp> {
+    let http = checked_http_settings()?;
+    let ncbi = NcbiHttpClient::with_settings(http.clone())?;
+    let rcsb = RcsbHttpClient::with_settings(http.clone())?;
+    let zip_limits = zip_limits()?;
+    let srr = SystemSrrClient::new()
+        .with_http_settings(http.clone())
+        .with_zip_limits(zip_limits);
+    let uniprot = UniprotHttpClient::with_settings(http.clone())?;
+    let geo = GeoHttpClient::with_settings(http.clone())?;
+    let knowledge = KnowledgeHttpClient::with_settings(http.clone())?;
+    Ok(App::new(store, ncbi, rcsb, srr, uniprot, geo, knowledge)
+        .with_http_settings(http)
+        .with_cache_policy(cache_policy()?)
+        .with_zip_limits(zip_limits)
+        .with_context(AppContext::current()))
+}
+
+/// The user config's archive limits, or the defaults.
+fn zip_limits() -> miette::Result<ZipLimits> {
+    let global = GlobalConfig::load()?;
+    Ok(global.archives.unwrap_or_default().limits())
+}
+
+/// The user config's cache policy, with the project's keys over it.
+fn cache_policy() -> miette::Result<CachePolicy> {
+    let global = GlobalConfig::load()?;
+    let policy = global
+        .cache
+        .as_ref()
+        .map(CachePolicy::from_config)
+        .transpose()?
+        .unwrap_or_default();
+    Ok(match ConfigLoader::resolve(None) {
+        Ok(config) => policy.overlay(&config.cache),
+        Err(_) => policy,
+    })
 }
 
 fn run_data_command(
@@ -286,104 +1070,45 @@ fn run_data_command(
     store: Store,
     output_mode: OutputMode,
 ) -> miette::Result<()> {
-    match command {
-        DataCommand::Fetch(args) | DataCommand::Add(args) => {
-            let ncbi = NcbiHttpClient::new().into_diagnostic()?;
-            let rcsb = RcsbHttpClient::new().into_diagnostic()?;
-            let srr = SystemSrrClient::new();
-            let uniprot = UniprotHttpClient::new().into_diagnostic()?;
-            let geo = GeoHttpClient::new().into_diagnostic()?;
-            let knowledge = KnowledgeHttpClient::new().into_diagnostic()?;
-            let app = App::new(store, ncbi, rcsb, srr, uniprot, geo, knowledge);
-            run_fetch(args, app, output_mode)
-        }
-        DataCommand::List => {
-            let app = App::new(
-                store,
-                NopNcbi,
-                NopRcsb,
-                NopSrr,
-                NopUniprot,
-                NopGeo,
-                NopKnowledge,
-            );
-            run_list(app, output_mode)
-        }
-        DataCommand::Info(args) => {
-            let app = App::new(
-                store,
-                NopNcbi,
-                NopRcsb,
-                NopSrr,
-                NopUniprot,
-                NopGeo,
-                NopKnowledge,
-            );
-            run_info(args, app, output_mode)
-        }
-        DataCommand::Clear => {
-            let app = App::new(
-                store,
-                NopNcbi,
-                NopRcsb,
-                NopSrr,
-                NopUniprot,
-                NopGeo,
-                NopKnowledge,
-            );
-            run_clear(app, output_mode)
-        }
-        DataCommand::Init => {
-            let app = App::new(
-                store,
-                NopNcbi,
-                NopRcsb,
-                NopSrr,
-                NopUniprot,
-                NopGeo,
-                NopKnowledge,
-            );
-            run_init(app, output_mode)
-        }
+    if matches!(command, DataCommand::Fetch(_) | DataCommand::Add(_)) {
+        let app = http_app(store)?;
+        return dispatch_data_command(command, Arc::new(app), output_mode, None);
     }
+    let app = App::new(
+        store,
+        NopNcbi,
+        NopRcsb,
+        NopSrr,
+        NopUniprot,
+        NopGeo,
+        NopKnowledge,
+    );
+    dispatch_data_command(command, Arc::new(app), output_mode, None)
 }
 
-fn print_fetch_summary(result: &kira_biodata_manager::app::FetchResult) {
-    let green = "\x1b[32m";
-    let yellow = "\x1b[33m";
-    let cyan = "\x1b[36m";
-    let red = "\x1b[31m";
-    let reset = "\x1b[0m";
-
-    println!("{cyan}📦 KIRA-BM summary{reset}");
-    println!(
-        "{green}✅ Downloaded datasets: {}{reset}",
-        result.items.len()
-    );
-    println!("{yellow}⚠️ Errors: 0{reset}");
-
-    for item in &result.items {
-        let action = item.action.as_str();
-        let (icon, color) = if action.contains("cache") {
-            ("♻️", green)
-        } else if action.contains("download") || action.contains("fetched") {
-            ("⬇️", cyan)
-        } else {
-            ("•", yellow)
-        };
-        println!(
-            "{color}{icon} {} {} ({}){reset}",
-            item.dataset_type, item.id, action
-        );
-        if let Some(path) = &item.project_path {
-            println!("{color}   📁 project: {path}{reset}");
-        }
-        if let Some(path) = &item.cache_path {
-            println!("{color}   🗃️  cache: {path}{reset}");
+fn dispatch_data_command<
+    N: NcbiClient + 'static,
+    R: RcsbClient + 'static,
+    S: SrrClient + 'static,
+    U: UniprotClient + 'static,
+    G: GeoClient + 'static,
+    K: KnowledgeClient + 'static,
+>(
+    command: DataCommand,
+    app: Arc<App<N, R, S, U, G, K>>,
+    output_mode: OutputMode,
+    session: Option<&mut Tui>,
+) -> miette::Result<()> {
+    match command {
+        DataCommand::Fetch(args) | DataCommand::Add(args) => {
+            run_fetch(args, app, output_mode, session)
         }
+        DataCommand::List(flags) => run_list(ListOptions::from(&flags), app, output_mode, session),
+        DataCommand::Find(args) => run_find(args, app, output_mode, session),
+        DataCommand::Info(args) => run_info(args, app, output_mode, session),
+        DataCommand::Clear(args) => run_clear(app, args, output_mode, session),
+        DataCommand::Init(args) => run_init(app, args, output_mode, session),
     }
-
-    let _ = red;
 }
 
 fn parse_tui_command(input: &str) -> miette::Result<DataCommand> {
@@ -401,11 +1126,38 @@ fn parse_tui_command(input: &str) -> miette::Result<DataCommand> {
         return Ok(DataCommand::Fetch(FetchArgs {
             specifier: None,
             config: None,
+            from_file: None,
             format: None,
             paired: false,
-            force: false,
-            no_cache: false,
-            dry_run: false,
+            no_paired: false,
+            srr_threads: None,
+            srr_temp_dir: None,
+            flags: FetchFlags::default(),
+            follow_superseries: false,
+            include_categories: Vec::new(),
+            allow_missing_artifacts: false,
+            ensembl_include: Vec::new(),
+            no_fasta: false,
+            top: None,
+            exact: false,
+            assembly_level: None,
+            reference_only: false,
+            limit: None,
+            go_flavor: None,
+            go_annotations: Vec::new(),
+            species: None,
+            replace_unfiltered: false,
+            with_structures: false,
+            max_structures: None,
+            prefer_bundle: None,
+            select: false,
+            only: Vec::new(),
+            limit_per_type: None,
+            exclude: Vec::new(),
+            download_all: false,
+            follow_related: false,
+            quiet: false,
+            verbose: 0,
         }));
     }
 
@@ -414,45 +1166,114 @@ fn parse_tui_command(input: &str) -> miette::Result<DataCommand> {
 
     match command {
         "fetch" | "add" => Ok(DataCommand::Fetch(FetchArgs {
-            specifier: rest.get(0).map(|s| s.to_string()),
+            specifier: rest.first().map(|s| s.to_string()),
             config: None,
+            from_file: None,
             format: None,
             paired: false,
-            force: false,
-            no_cache: false,
-            dry_run: false,
+            no_paired: false,
+            srr_threads: None,
+            srr_temp_dir: None,
+            flags: FetchFlags::default(),
+            follow_superseries: false,
+            include_categories: Vec::new(),
+            allow_missing_artifacts: false,
+            ensembl_include: Vec::new(),
+            no_fasta: false,
+            top: None,
+            exact: false,
+            assembly_level: None,
+            reference_only: false,
+            limit: None,
+            go_flavor: None,
+            go_annotations: Vec::new(),
+            species: None,
+            replace_unfiltered: false,
+            with_structures: false,
+            max_structures: None,
+            prefer_bundle: None,
+            select: false,
+            only: Vec::new(),
+            limit_per_type: None,
+            exclude: Vec::new(),
+            download_all: false,
+            follow_related: false,
+            quiet: false,
+            verbose: 0,
         })),
-        "list" => Ok(DataCommand::List),
+        "list" => Ok(DataCommand::List(ListFlags::default())),
+        "find" => {
+            let pattern = rest
+                .first()
+                .ok_or_else(|| miette::Report::msg("find requires a pattern"))?;
+            Ok(DataCommand::Find(FindArgs {
+                pattern: pattern.to_string(),
+            }))
+        }
         "info" => {
             let spec = rest
-                .get(0)
+                .first()
                 .ok_or_else(|| miette::Report::msg("info requires a specifier"))?;
             Ok(DataCommand::Info(InfoArgs {
                 specifier: spec.to_string(),
+                datasets: false,
             }))
         }
-        "clear" => Ok(DataCommand::Clear),
-        "init" => Ok(DataCommand::Init),
+        "clear" => Ok(DataCommand::Clear(ClearArgs {
+            include_pinned: rest.contains(&"--include-pinned"),
+            types: rest
+                .iter()
+                .position(|arg| *arg == "--type")
+                .and_then(|index| rest.get(index + 1))
+                .map(|types| types.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+            keep_metadata: rest.contains(&"--keep-metadata"),
+            dry_run: rest.contains(&"--dry-run"),
+        })),
+        "init" => Ok(DataCommand::Init(InitArgs {
+            force: rest.contains(&"--force"),
+            dry_run: rest.contains(&"--dry-run"),
+        })),
         _ => {
-            if command.contains(':') {
-                Ok(DataCommand::Fetch(FetchArgs {
-                    specifier: Some(command.to_string()),
-                    config: None,
-                    format: None,
-                    paired: false,
-                    force: false,
-                    no_cache: false,
-                    dry_run: false,
-                }))
-            } else if matches!(command, "go" | "kegg" | "reactome") {
+            if command.contains(':')
+                || matches!(command, "go" | "kegg" | "reactome")
+                || !DatasetSpecifier::candidates(command).is_empty()
+            {
                 Ok(DataCommand::Fetch(FetchArgs {
                     specifier: Some(command.to_string()),
                     config: None,
+                    from_file: None,
                     format: None,
                     paired: false,
-                    force: false,
-                    no_cache: false,
-                    dry_run: false,
+                    no_paired: false,
+                    srr_threads: None,
+                    srr_temp_dir: None,
+                    flags: FetchFlags::default(),
+                    follow_superseries: false,
+                    include_categories: Vec::new(),
+                    allow_missing_artifacts: false,
+                    ensembl_include: Vec::new(),
+                    no_fasta: false,
+                    top: None,
+                    exact: false,
+                    assembly_level: None,
+                    reference_only: false,
+                    limit: None,
+                    go_flavor: None,
+                    go_annotations: Vec::new(),
+                    species: None,
+                    replace_unfiltered: false,
+                    with_structures: false,
+                    max_structures: None,
+                    prefer_bundle: None,
+                    select: false,
+                    only: Vec::new(),
+                    limit_per_type: None,
+                    exclude: Vec::new(),
+                    download_all: false,
+                    follow_related: false,
+                    quiet: false,
+                    verbose: 0,
                 }))
             } else {
                 Err(miette::Report::msg("unknown command"))
@@ -486,6 +1307,7 @@ impl NcbiClient for NopNcbi {
         _accession: &kira_biodata_manager::domain::GenomeAccession,
         _include: &[String],
         _destination: &std::path::Path,
+        _sink: &dyn ProgressSink,
     ) -> Result<kira_biodata_manager::ncbi::DownloadInfo, KiraError> {
         Err(KiraError::NcbiHttp(
             "NCBI client not configured".to_string(),
@@ -499,6 +1321,7 @@ impl RcsbClient for NopRcsb {
         _id: &kira_biodata_manager::domain::ProteinId,
         _format: kira_biodata_manager::domain::ProteinFormat,
         _destination: &std::path::Path,
+        _sink: &dyn ProgressSink,
     ) -> Result<(), KiraError> {
         Err(KiraError::RcsbHttp(
             "RCSB client not configured".to_string(),
@@ -521,6 +1344,7 @@ impl SrrClient for NopSrr {
         _id: &kira_biodata_manager::domain::SrrId,
         _paired: bool,
         _destination_dir: &std::path::Path,
+        _options: &kira_biodata_manager::srr::SrrDownloadOptions,
     ) -> Result<Vec<std::path::PathBuf>, KiraError> {
         Err(KiraError::MissingTool(
             "SRA tools not configured".to_string(),
@@ -528,10 +1352,7 @@ impl SrrClient for NopSrr {
     }
 
     fn tool_info(&self) -> kira_biodata_manager::srr::ToolInfo {
-        kira_biodata_manager::srr::ToolInfo {
-            datasets: None,
-            sra_toolkit: None,
-        }
+        kira_biodata_manager::srr::ToolInfo::from_output(None, None)
     }
 }
 
@@ -554,116 +1375,480 @@ impl GeoClient for NopGeo {
         Err(KiraError::GeoHttp("GEO client not configured".to_string()))
     }
 
-    fn download_url(&self, _url: &str, _destination: &std::path::Path) -> Result<(), KiraError> {
+    fn fetch_sample_soft_text(
+        &self,
+        _accession: &kira_biodata_manager::domain::GeoSampleAccession,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::GeoHttp("GEO client not configured".to_string()))
+    }
+
+    fn download_url(
+        &self,
+        _url: &str,
+        _destination: &std::path::Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
         Err(KiraError::GeoHttp("GEO client not configured".to_string()))
     }
 }
 
 impl KnowledgeClient for NopKnowledge {
-    fn download_go(&self, _destination: &std::path::Path) -> Result<Vec<u8>, KiraError> {
+    fn download_go_ontology(
+        &self,
+        _flavor: kira_biodata_manager::knowledge::GoFlavor,
+        _destination: &std::path::Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<Vec<u8>, KiraError> {
+        Err(KiraError::KnowledgeHttp(
+            "knowledge client not configured".to_string(),
+        ))
+    }
+
+    fn download_kegg_pathways(
+        &self,
+        _destination: &std::path::Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp(
+            "knowledge client not configured".to_string(),
+        ))
+    }
+
+    fn download_kegg_pathway_links(
+        &self,
+        _destination: &std::path::Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp(
+            "knowledge client not configured".to_string(),
+        ))
+    }
+
+    fn download_reactome_pathways(
+        &self,
+        _destination: &std::path::Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp(
+            "knowledge client not configured".to_string(),
+        ))
+    }
+
+    fn download_reactome_mappings(
+        &self,
+        _destination: &std::path::Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
         Err(KiraError::KnowledgeHttp(
             "knowledge client not configured".to_string(),
         ))
     }
 
-    fn download_kegg_pathways(&self, _destination: &std::path::Path) -> Result<(), KiraError> {
+    fn fetch_kegg_info(&self, _database: &str) -> Result<String, KiraError> {
         Err(KiraError::KnowledgeHttp(
             "knowledge client not configured".to_string(),
         ))
     }
 
-    fn download_kegg_pathway_links(&self, _destination: &std::path::Path) -> Result<(), KiraError> {
+    fn fetch_kegg_links(&self, _target: &str, _ids: &[String]) -> Result<String, KiraError> {
         Err(KiraError::KnowledgeHttp(
             "knowledge client not configured".to_string(),
         ))
     }
-
-    fn download_reactome_pathways(&self, _destination: &std::path::Path) -> Result<(), KiraError> {
-        Err(KiraError::KnowledgeHttp(
-            "knowledge client not configured".to_string(),
-        ))
+}
+
+fn run_fetch<
+    N: NcbiClient + 'static,
+    R: RcsbClient + 'static,
+    S: SrrClient + 'static,
+    U: UniprotClient + 'static,
+    G: GeoClient + 'static,
+    K: KnowledgeClient + 'static,
+>(
+    args: FetchArgs,
+    app: Arc<App<N, R, S, U, G, K>>,
+    output_mode: OutputMode,
+    mut session: Option<&mut Tui>,
+) -> miette::Result<()> {
+    let FetchArgs {
+        specifier,
+        config,
+        from_file,
+        format,
+        paired,
+        no_paired,
+        srr_threads,
+        srr_temp_dir,
+        flags,
+        follow_superseries,
+        include_categories,
+        allow_missing_artifacts,
+        ensembl_include,
+        no_fasta,
+        top,
+        exact,
+        assembly_level,
+        reference_only,
+        limit,
+        go_flavor,
+        go_annotations,
+        species,
+        replace_unfiltered,
+        with_structures,
+        max_structures,
+        prefer_bundle,
+        select,
+        only,
+        limit_per_type,
+        exclude,
+        download_all,
+        follow_related,
+        quiet,
+        verbose,
+    } = args;
+    let verbosity = Verbosity::from_flags(quiet, verbose);
+
+    let arguments = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Some(value) = specifier
+        .as_deref()
+        .filter(|value| SpecifierPattern::is_pattern(value))
+    {
+        return Err(miette::Report::new(KiraError::InvalidSpecifier(format!(
+            "{value}: fetch needs exact ids; patterns work with info, pin and unpin"
+        ))));
+    }
+    let specifier = specifier
+        .map(|value| read_specifier(&app, &value, output_mode, session.as_deref_mut()))
+        .transpose()?
+        .map(|spec| app.resolve_alias(spec))
+        .transpose()?;
+
+    let id_list = from_file.as_deref().map(read_id_list).transpose()?;
+    let listed_srr = id_list.as_ref().is_some_and(|entries| {
+        entries
+            .iter()
+            .any(|entry| matches!(entry.specifier, DatasetSpecifier::Srr(_)))
+    });
+
+    let resolved_config = if specifier.is_none() && id_list.is_none() {
+        Some(ConfigLoader::resolve(config.as_deref())?)
+    } else {
+        None
+    };
+
+    if listed_srr || requires_srr_tools(specifier.as_ref(), resolved_config.as_ref()) {
+        let status = SystemSrrClient::new().tool_status();
+        if let SrrToolStatus::Missing { message } = status {
+            return Err(miette::Report::msg(format!(
+                "SRA tools not available: {message}\n\nInstall:\n  kira-bm tools install-sra\n\nAfter installing, add the SRA Toolkit directory to PATH and restart your terminal."
+            )));
+        }
+    } else if session.is_none()
+        && let SrrToolStatus::Missing { .. } = SystemSrrClient::new().tool_status()
+    {
+        let lines = match output_mode {
+            OutputMode::NonInteractive => ProgressLines::json(),
+            _ => ProgressLines::plain(),
+        };
+        for warning in [
+            "warning: SRR identifiers require the external NCBI SRA Toolkit (sratools https://github.com/ncbi/sra-tools ).",
+            "warning: The toolkit is not bundled. Please install it separately if needed.",
+        ] {
+            lines.event(ProgressEvent {
+                message: warning.to_string(),
+                elapsed: None,
+                level: ProgressLevel::Phase,
+            });
+        }
+    }
+
+    let mut fetch_options = FetchOptions::from(&flags);
+    fetch_options.write_readme |= match &resolved_config {
+        Some(config) => config.write_readme,
+        None => ConfigLoader::resolve(None).is_ok_and(|config| config.write_readme),
+    };
+    if !matches!(output_mode, OutputMode::Interactive) {
+        // The TUI cancels on its own keys; elsewhere Ctrl-C stops the fetch
+        // cleanly, including any running SRA tool.
+        fetch_options.cancel = Some(interrupt_token());
+    }
+    let paired = match (paired, no_paired) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    };
+    let mut overrides = build_overrides(specifier.as_ref(), format, paired)?;
+    let defaults = match &resolved_config {
+        // The config's entries carry its defaults already.
+        Some(config) => config.defaults.clone(),
+        None => {
+            let defaults = fetch_defaults()?;
+            apply_defaults(&mut overrides, &defaults);
+            defaults
+        }
+    };
+    if srr_threads.is_some() || srr_temp_dir.is_some() {
+        if !matches!(
+            specifier,
+            Some(
+                DatasetSpecifier::Srr(_)
+                    | DatasetSpecifier::SrrExperiment(_)
+                    | DatasetSpecifier::BioProject(_)
+            ) | None
+        ) {
+            return Err(KiraError::InvalidFormat(
+                "--srr-threads and --srr-temp-dir are only valid for srr, srx and bioproject datasets"
+                    .to_string(),
+            )
+            .into());
+        }
+        overrides.srr_threads = srr_threads;
+        overrides.srr_temp_dir = srr_temp_dir;
+    }
+    overrides.follow_superseries = follow_superseries;
+    overrides.allow_missing_artifacts = allow_missing_artifacts;
+    overrides.no_fasta = no_fasta;
+    if let Some(top) = top {
+        if !matches!(specifier, Some(DatasetSpecifier::StructureFor(_))) {
+            return Err(KiraError::InvalidFormat(
+                "--top is only valid for structure-for datasets".to_string(),
+            )
+            .into());
+        }
+        overrides.structure_top = Some(usize::from(top));
+    }
+    if !ensembl_include.is_empty() {
+        if !matches!(specifier, Some(DatasetSpecifier::GenomeEnsembl(_))) {
+            return Err(KiraError::InvalidFormat(
+                "--ensembl-include is only valid for genome:ensembl: datasets".to_string(),
+            )
+            .into());
+        }
+        overrides.ensembl_include = ensembl_include;
+    }
+    if exact {
+        if !matches!(specifier, Some(DatasetSpecifier::GenomeName(_))) {
+            return Err(KiraError::InvalidFormat(
+                "--exact is only valid for genome names".to_string(),
+            )
+            .into());
+        }
+        overrides.exact = true;
+    }
+    if assembly_level.is_some() || reference_only || limit.is_some() {
+        if !matches!(specifier, Some(DatasetSpecifier::GenomeTaxon(_))) {
+            return Err(KiraError::InvalidFormat(
+                "--assembly-level, --reference-only and --limit are only valid for genome:taxon: datasets"
+                    .to_string(),
+            )
+            .into());
+        }
+        overrides.assembly_level = assembly_level;
+        overrides.reference_only = reference_only;
+        overrides.limit = limit.map(usize::from);
+    }
+    if go_flavor.is_some() || !go_annotations.is_empty() {
+        if !matches!(specifier, Some(DatasetSpecifier::Go)) {
+            return Err(KiraError::InvalidFormat(
+                "--go-flavor and --go-annotations are only valid for go datasets".to_string(),
+            )
+            .into());
+        }
+        overrides.go_flavor = go_flavor;
+        overrides.go_annotations = go_annotations;
+    } else if matches!(specifier, Some(DatasetSpecifier::Go))
+        && let Ok(config) = ConfigLoader::resolve(None)
+    {
+        overrides.go_flavor = config.go_flavor;
+        overrides.go_annotations = config.go_annotations;
+    }
+    if species.is_some() {
+        if !matches!(specifier, Some(DatasetSpecifier::Reactome)) {
+            return Err(KiraError::InvalidFormat(
+                "--species and --replace-unfiltered are only valid for reactome datasets"
+                    .to_string(),
+            )
+            .into());
+        }
+        overrides.reactome_species = species;
+        overrides.replace_unfiltered = replace_unfiltered;
+    }
+    if with_structures || max_structures.is_some() {
+        if !matches!(specifier, None | Some(DatasetSpecifier::Uniprot(_))) {
+            return Err(KiraError::InvalidFormat(
+                "--with-structures and --max-structures are only valid for uniprot datasets"
+                    .to_string(),
+            )
+            .into());
+        }
+        overrides.with_structures = with_structures;
+        overrides.max_structures = max_structures.map(usize::from);
+    }
+    if prefer_bundle.is_some() {
+        if !matches!(specifier, None | Some(DatasetSpecifier::Expression10x(_))) {
+            return Err(KiraError::InvalidFormat(
+                "--prefer-bundle is only valid for expression10x datasets".to_string(),
+            )
+            .into());
+        }
+        overrides.prefer_bundle = prefer_bundle;
+    }
+    if select || !only.is_empty() || limit_per_type.is_some() || !exclude.is_empty() || download_all
+    {
+        if !matches!(specifier, None | Some(DatasetSpecifier::Doi(_))) {
+            return Err(KiraError::InvalidFormat(
+                "--select, --only, --limit-per-type, --exclude and --download-all are only valid for doi datasets"
+                    .to_string(),
+            )
+            .into());
+        }
+        if let Some(kind) = only
+            .iter()
+            .find(|kind| !DOI_TARGET_TYPES.contains(&kind.to_ascii_lowercase().as_str()))
+        {
+            return Err(KiraError::InvalidFormat(format!(
+                "--only {kind}: a DOI resolves to {} targets",
+                DOI_TARGET_TYPES.join(", ")
+            ))
+            .into());
+        }
+        if select && !matches!(specifier, Some(DatasetSpecifier::Doi(_))) {
+            return Err(
+                KiraError::InvalidFormat("--select needs a doi: specifier".to_string()).into(),
+            );
+        }
+        if select && !matches!(output_mode, OutputMode::Interactive) {
+            return Err(KiraError::InvalidFormat(
+                "--select needs the interactive terminal; use --only, --limit-per-type or --exclude instead"
+                    .to_string(),
+            )
+            .into());
+        }
+        overrides.doi_selection = DoiSelection {
+            download_all,
+            only,
+            limit_per_type: limit_per_type.map(|limit| limit as usize),
+            exclude,
+            picked: None,
+        };
     }
-
-    fn download_reactome_mappings(&self, _destination: &std::path::Path) -> Result<(), KiraError> {
-        Err(KiraError::KnowledgeHttp(
-            "knowledge client not configured".to_string(),
-        ))
+    if follow_related {
+        if !matches!(specifier, None | Some(DatasetSpecifier::Doi(_))) {
+            return Err(KiraError::InvalidFormat(
+                "--follow-related is only valid for doi datasets".to_string(),
+            )
+            .into());
+        }
+        overrides.follow_related = true;
     }
-}
-
-fn run_fetch<
-    N: NcbiClient + 'static,
-    R: RcsbClient + 'static,
-    S: SrrClient + 'static,
-    U: UniprotClient + 'static,
-    G: GeoClient + 'static,
-    K: KnowledgeClient + 'static,
->(
-    args: FetchArgs,
-    app: App<N, R, S, U, G, K>,
-    output_mode: OutputMode,
-) -> miette::Result<()> {
-    let FetchArgs {
-        specifier,
-        config,
-        format,
-        paired,
-        force,
-        no_cache,
-        dry_run,
-    } = args;
-
-    let specifier = specifier
-        .map(|value| value.parse::<DatasetSpecifier>())
-        .transpose()
-        .into_diagnostic()?;
-
-    let resolved_config = if specifier.is_none() {
-        ConfigLoader::resolve(config.as_deref())
-            .into_diagnostic()
-            .map(Some)?
+    overrides.include_categories = if include_categories.is_empty() {
+        defaults.include_categories.value
     } else {
-        None
+        include_categories
     };
 
-    if requires_srr_tools(specifier.as_ref(), resolved_config.as_ref()) {
-        let status = SystemSrrClient::new().tool_status();
-        if let SrrToolStatus::Missing { message } = status {
-            return Err(miette::Report::msg(format!(
-                "SRA tools not available: {message}\n\nInstall:\n  kira-bm tools install-sra\n\nAfter installing, add the SRA Toolkit directory to PATH and restart your terminal."
-            )));
+    let invocation = match (&specifier, &id_list) {
+        (Some(spec), _) => RunInvocation::new(arguments, vec![spec.to_string()], None),
+        (None, Some(entries)) => RunInvocation::new(
+            arguments,
+            entries
+                .iter()
+                .map(|entry| entry.specifier.to_string())
+                .collect(),
+            None,
+        ),
+        (None, None) => {
+            let path = match config.as_deref() {
+                Some(path) => PathBuf::from(path),
+                None => Project::current()?.config_path(),
+            };
+            let raw = ConfigLoader::load(&path)?;
+            RunInvocation::new(arguments, config_specifiers(&raw), Some(&raw))
         }
-    } else if let SrrToolStatus::Missing { .. } = SystemSrrClient::new().tool_status() {
-        eprintln!(
-            "warning: SRR identifiers require the external NCBI SRA Toolkit (sratools https://github.com/ncbi/sra-tools )."
-        );
-        eprintln!("warning: The toolkit is not bundled. Please install it separately if needed.");
-    }
+    };
+    let runs_kept = resolved_config
+        .as_ref()
+        .map(|config| config.runs_kept)
+        .or_else(|| {
+            ConfigLoader::resolve(None)
+                .ok()
+                .map(|config| config.runs_kept)
+        })
+        .unwrap_or(DEFAULT_RUNS_KEPT);
 
-    let fetch_options = FetchOptions {
-        force,
-        no_cache,
-        dry_run,
+    let app = match (&specifier, select) {
+        (Some(DatasetSpecifier::Doi(doi)), true) => {
+            let doi = doi.clone();
+            let resolve_options = fetch_options.clone();
+            let mut own = None;
+            let tui = session_tui(session.as_deref_mut(), &mut own, ProgressSinkKind::Fetch);
+            let (app, resolution) = tui.run(move |sink| {
+                let resolution = app.resolve_doi(&doi, &resolve_options, follow_related, sink)?;
+                Ok((app, resolution))
+            })?;
+            let preview = app.doi_targets(&resolution, &overrides.doi_selection);
+            // Filters given with --select only decide what starts ticked.
+            let mut targets = preview
+                .selected
+                .into_iter()
+                .map(|target| (target, true))
+                .chain(preview.skipped.into_iter().map(|target| (target, false)))
+                .collect::<Vec<_>>();
+            targets.sort_by(|(a, _), (b, _)| a.specifier.cmp(&b.specifier));
+            let (targets, checked): (Vec<_>, Vec<_>) = targets.into_iter().unzip();
+            let Some(picked) = tui.select_targets(&resolution.doi, &targets, checked)? else {
+                if tui.is_open() {
+                    tui.note("selection cancelled; nothing fetched");
+                } else {
+                    eprintln!("selection cancelled; nothing fetched");
+                }
+                return Ok(());
+            };
+            overrides.doi_selection = DoiSelection {
+                picked: Some(picked),
+                ..DoiSelection::default()
+            };
+            app
+        }
+        _ => app,
     };
-    let overrides = build_overrides(specifier.as_ref(), format, paired)?;
 
+    let fetch = move |sink: &dyn ProgressSink| {
+        note_project(sink);
+        let outcome = match id_list {
+            Some(entries) => app.fetch_list(&entries, overrides, fetch_options, sink),
+            None => app.fetch(
+                specifier,
+                resolved_config.as_ref(),
+                overrides,
+                fetch_options,
+                sink,
+            ),
+        };
+        if let Err(err) = app.record_run(invocation, &outcome, runs_kept) {
+            tracing::warn!("run manifest not written: {err}");
+        }
+        outcome
+    };
     match output_mode {
         OutputMode::NonInteractive => {
-            let result = app
-                .fetch(
-                    specifier,
-                    resolved_config.as_ref(),
-                    overrides.clone(),
-                    fetch_options,
-                    &JsonOutput,
-                )
-                .into_diagnostic()?;
+            let result = fetch(&ProgressFilter::new(&ProgressLines::json(), verbosity))?;
             JsonOutput::print_fetch(&result).into_diagnostic()?;
-            Ok(())
+            ensure_complete(&result)
+        }
+        OutputMode::Plain => {
+            let result = fetch(&ProgressFilter::new(&ProgressLines::plain(), verbosity))?;
+            PlainOutput::print_fetch(&result).into_diagnostic()?;
+            ensure_complete(&result)
         }
         OutputMode::Interactive => {
-            let mut tui = Tui::new(ProgressSinkKind::Fetch);
-            if let SrrToolStatus::Missing { .. } = SystemSrrClient::new().tool_status() {
+            // A session said this when it started.
+            let in_session = session.is_some();
+            let mut own = None;
+            let tui = session_tui(session, &mut own, ProgressSinkKind::Fetch);
+            if !in_session
+                && let SrrToolStatus::Missing { .. } = SystemSrrClient::new().tool_status()
+            {
                 tui.note_warning(
                     "warning: SRR identifiers require the external NCBI SRA Toolkit (sratools https://github.com/ncbi/sra-tools ).",
                 );
@@ -671,20 +1856,13 @@ fn run_fetch<
                     "warning: The toolkit is not bundled. Please install it separately if needed.",
                 );
             }
-            let result = tui.run(move |sink| {
-                app.fetch(
-                    specifier,
-                    resolved_config.as_ref(),
-                    overrides,
-                    fetch_options,
-                    sink,
-                )
-            });
+            let result = tui.run(fetch);
             match result {
                 Ok(result) => {
                     tui.finish_fetch(&result)?;
-                    Ok(())
+                    ensure_complete(&result)
                 }
+                Err(err) if is_cancelled(&err) || in_session => Err(err),
                 Err(err) => {
                     let mut tui = Tui::new(ProgressSinkKind::Fetch);
                     tui.note_error(&format!("error: {err}"));
@@ -696,6 +1874,20 @@ fn run_fetch<
     }
 }
 
+/// `type:id` of every entry a config-driven fetch will visit.
+fn config_specifiers(config: &Config) -> Vec<String> {
+    ConfigSection::ALL
+        .into_iter()
+        .flat_map(|section| {
+            config
+                .entry_ids(section)
+                .into_iter()
+                .filter(move |id| !config.is_excluded(section, id))
+                .map(move |id| format!("{}:{id}", section.kind()))
+        })
+        .collect()
+}
+
 fn requires_srr_tools(
     specifier: Option<&DatasetSpecifier>,
     config: Option<&kira_biodata_manager::config::ResolvedConfig>,
@@ -709,8 +1901,32 @@ fn requires_srr_tools(
     false
 }
 
-fn run_tools(args: ToolsArgs) -> miette::Result<()> {
+fn run_tools(args: ToolsArgs, output_mode: OutputMode) -> miette::Result<()> {
     match args.command {
+        ToolsCommand::Check => {
+            let report = SystemSrrClient::new().check_tools();
+            match output_mode {
+                OutputMode::NonInteractive => JsonOutput::print_tools(&report),
+                OutputMode::Plain | OutputMode::Interactive => PlainOutput::print_tools(&report),
+            }
+            .into_diagnostic()?;
+            let outdated = report
+                .tools
+                .iter()
+                .filter(|check| check.status == ToolCheckStatus::Outdated)
+                .map(|check| {
+                    format!(
+                        "{} {}",
+                        check.tool,
+                        check.version.as_deref().unwrap_or_default()
+                    )
+                })
+                .collect::<Vec<_>>();
+            if !outdated.is_empty() {
+                return Err(miette::Report::new(KiraError::OutdatedTools(outdated)));
+            }
+            Ok(())
+        }
         ToolsCommand::InstallSra => {
             println!(
                 "Optional external dependency required for the optional `srr:<SRR_ID>` dataset feature.\n\n\
@@ -725,28 +1941,361 @@ If the user uses `srr:` datasets, the following external utilities are required:
     }
 }
 
+fn run_batch(args: BatchArgs, store: &Store, output_mode: OutputMode) -> miette::Result<()> {
+    let root = store.project_root();
+    let settings = ControlSettings::default();
+    let report = match args.command {
+        BatchCommand::Pause => control::request(root, BatchState::Paused, settings),
+        BatchCommand::Resume => control::request(root, BatchState::Running, settings),
+        BatchCommand::Status => control::status(root, settings),
+    }?;
+    if matches!(output_mode, OutputMode::NonInteractive) {
+        return JsonOutput::print_control(&report).into_diagnostic();
+    }
+    match &report.batch {
+        None => println!("no batch running"),
+        Some(batch) if report.stale => println!(
+            "batch {} (pid {}) stopped responding; its control state is ignored",
+            batch.id, batch.pid
+        ),
+        Some(batch) => {
+            println!(
+                "batch {} (pid {}): {}, {} items done",
+                batch.id, batch.pid, batch.state, batch.completed
+            );
+            if let Some(request) = report.request.as_ref().filter(|r| r.state != batch.state) {
+                println!("requested: {}", request.state);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_cache(args: CacheArgs, store: &Store, output_mode: OutputMode) -> miette::Result<()> {
+    match args.command {
+        CacheCommand::Stats { by_project: true } => {
+            let usage = index::load(store, DEFAULT_LOCK_TIMEOUT, &PlainOutput)?.usage();
+            if matches!(output_mode, OutputMode::NonInteractive) {
+                JsonOutput::print_cache_usage(&usage).into_diagnostic()
+            } else {
+                PlainOutput::print_cache_usage(&usage).into_diagnostic()
+            }
+        }
+        CacheCommand::Stats { by_project: false } => {
+            let stats = stats::read(store.cache_root())?;
+            if matches!(output_mode, OutputMode::NonInteractive) {
+                JsonOutput::print_cache_stats(&stats).into_diagnostic()
+            } else {
+                PlainOutput::print_cache_stats(&stats).into_diagnostic()
+            }
+        }
+        CacheCommand::Dedup => {
+            if matches!(output_mode, OutputMode::NonInteractive) {
+                let report = store.dedup_cache(DEFAULT_LOCK_TIMEOUT, &JsonOutput)?;
+                JsonOutput::print_cache_dedup(&report).into_diagnostic()
+            } else {
+                let report = store.dedup_cache(DEFAULT_LOCK_TIMEOUT, &PlainOutput)?;
+                PlainOutput::print_cache_dedup(&report).into_diagnostic()
+            }
+        }
+        CacheCommand::Reindex => {
+            let (_, report) = index::rebuild(store, DEFAULT_LOCK_TIMEOUT, &PlainOutput)?;
+            if matches!(output_mode, OutputMode::NonInteractive) {
+                JsonOutput::print_reindex(&report).into_diagnostic()
+            } else {
+                PlainOutput::print_reindex(&report).into_diagnostic()
+            }
+        }
+    }
+}
+
+fn run_global_list(
+    flags: &ListFlags,
+    store: &Store,
+    output_mode: OutputMode,
+) -> miette::Result<()> {
+    let mut index = index::load(store, DEFAULT_LOCK_TIMEOUT, &PlainOutput)?;
+    if let Some(wanted) = &flags.dataset_type {
+        index
+            .datasets
+            .retain(|entry| entry.dataset_type.eq_ignore_ascii_case(wanted));
+    }
+    if matches!(output_mode, OutputMode::NonInteractive) {
+        JsonOutput::print_cache_index(&index).into_diagnostic()
+    } else {
+        PlainOutput::print_cache_index(&index).into_diagnostic()
+    }
+}
+
+fn run_audit(args: AuditArgs, store: &Store, output_mode: OutputMode) -> miette::Result<()> {
+    let root = if args.cache {
+        store.cache_root()
+    } else {
+        store.project_root()
+    };
+    let records = audit::read(root, args.limit)?;
+    if matches!(output_mode, OutputMode::NonInteractive) {
+        return JsonOutput::print_audit(&records).into_diagnostic();
+    }
+    if records.is_empty() {
+        println!(
+            "no destructive operations recorded in {}",
+            root.join(AUDIT_FILE)
+        );
+        return Ok(());
+    }
+    println!(
+        "{:<25} {:<14} {:<12} {:<32} {:>10}",
+        "TIME", "OPERATION", "USER", "TARGET", "BYTES"
+    );
+    for record in &records {
+        let time = record.timestamp.get(..19).unwrap_or(&record.timestamp);
+        if record.targets.is_empty() {
+            println!(
+                "{time:<25} {:<14} {:<12} {:<32} {:>10}",
+                record.operation, record.user, "-", 0
+            );
+        }
+        for target in &record.targets {
+            println!(
+                "{time:<25} {:<14} {:<12} {:<32} {:>10}",
+                record.operation, record.user, target.specifier, target.bytes
+            );
+            if let Some(undo) = &target.undo {
+                println!("{:<25} {undo}", "");
+            }
+        }
+        if !record.flags.is_empty() {
+            println!("{:<25} flags: {}", "", record.flags.join(" "));
+        }
+    }
+    Ok(())
+}
+
+fn run_retention(
+    args: RetentionArgs,
+    store: &Store,
+    output_mode: OutputMode,
+) -> miette::Result<()> {
+    let RetentionCommand::Apply {
+        dry_run,
+        include_pinned,
+        config,
+    } = args.command;
+    let resolved = ConfigLoader::resolve(config.as_deref())?;
+    if resolved.retention.is_empty() {
+        return Err(miette::Report::msg(
+            "no retention policy configured (add a `retention` section to kira-bm.json)",
+        ));
+    }
+    let report = retention::apply(
+        store,
+        &resolved.retention,
+        chrono::Utc::now(),
+        dry_run,
+        include_pinned,
+    )?;
+    if matches!(output_mode, OutputMode::NonInteractive) {
+        return JsonOutput::print_retention(&report).into_diagnostic();
+    }
+    let verb = if dry_run { "would expire" } else { "expired" };
+    if report.expired.is_empty() {
+        println!("nothing to expire");
+    } else {
+        println!(
+            "{:<32} {:>6} {:<16} {:>12}",
+            "DATASET", "AGE", "POLICY", "BYTES"
+        );
+        for entry in &report.expired {
+            println!(
+                "{:<32} {:>5}d {:<16} {:>12}",
+                entry.specifier, entry.age_days, entry.policy, entry.bytes
+            );
+        }
+        let bytes = report.expired.iter().map(|entry| entry.bytes).sum::<u64>();
+        println!("{verb} {} datasets, {bytes} bytes", report.expired.len());
+    }
+    for specifier in &report.pinned {
+        println!("kept {specifier}: pinned");
+    }
+    Ok(())
+}
+
+fn run_pin(
+    args: PinArgs,
+    pinned: bool,
+    store: Store,
+    output_mode: OutputMode,
+) -> miette::Result<()> {
+    let app = App::new(
+        store,
+        NopNcbi,
+        NopRcsb,
+        NopSrr,
+        NopUniprot,
+        NopGeo,
+        NopKnowledge,
+    );
+    if SpecifierPattern::is_pattern(&args.specifier) {
+        let pattern = args.specifier.parse::<SpecifierPattern>()?;
+        let result = app.pin_matching(&pattern, pinned, &JsonOutput)?;
+        return if matches!(output_mode, OutputMode::NonInteractive) {
+            JsonOutput::print_pin_matches(&result)
+        } else {
+            PlainOutput::print_pin_matches(&result)
+        }
+        .into_diagnostic();
+    }
+    let specifier = args.specifier.parse::<DatasetSpecifier>()?;
+    if matches!(output_mode, OutputMode::NonInteractive) {
+        let result = app.pin(specifier, pinned, &JsonOutput)?;
+        return JsonOutput::print_pin(&result).into_diagnostic();
+    }
+    let result = app.pin(specifier, pinned, &PlainOutput)?;
+    PlainOutput::print_pin(&result).into_diagnostic()
+}
+
+fn run_gc(args: GcArgs, store: Store, output_mode: OutputMode) -> miette::Result<()> {
+    let app = App::new(
+        store,
+        NopNcbi,
+        NopRcsb,
+        NopSrr,
+        NopUniprot,
+        NopGeo,
+        NopKnowledge,
+    );
+    let options = GcOptions {
+        min_age: args.older_than,
+        prune_orphans: args.prune_orphans,
+        include_pinned: args.include_pinned,
+        apply: args.yes,
+    };
+    if matches!(output_mode, OutputMode::NonInteractive) {
+        let result = app.gc(options, &JsonOutput)?;
+        return JsonOutput::print_gc(&result).into_diagnostic();
+    }
+    let result = app.gc(options, &PlainOutput)?;
+    PlainOutput::print_gc(&result).into_diagnostic()
+}
+
+fn run_doctor(args: DoctorArgs, store: Store, output_mode: OutputMode) -> miette::Result<()> {
+    if args.fix {
+        eprintln!("note: no automated fixes are available yet; follow the hints below");
+    }
+    let http = http_settings()?;
+    let probe = HttpProbe::with_settings(http.clone()).into_diagnostic()?;
+    let app = App::new(
+        store,
+        NopNcbi,
+        NopRcsb,
+        SystemSrrClient::new(),
+        NopUniprot,
+        NopGeo,
+        NopKnowledge,
+    )
+    .with_http_settings(http);
+    let report = app.doctor(&probe);
+    match output_mode {
+        OutputMode::NonInteractive => JsonOutput::print_doctor(&report),
+        OutputMode::Plain => PlainOutput::print_doctor(&report),
+        OutputMode::Interactive => StyledOutput::print_doctor(&report),
+    }
+    .into_diagnostic()?;
+    if !report.is_healthy() {
+        return Err(miette::Report::new(KiraError::ChecksFailed(
+            report.count(CheckStatus::Fail),
+        )));
+    }
+    Ok(())
+}
+
+fn run_ping(args: PingArgs, output_mode: OutputMode) -> miette::Result<()> {
+    let endpoints = if args.registries.is_empty() {
+        HEALTH_ENDPOINTS.iter().collect::<Vec<_>>()
+    } else {
+        args.registries
+            .iter()
+            .filter_map(|registry| health_endpoint(registry))
+            .collect()
+    };
+    let probe = HttpProbe::with_settings(checked_http_settings()?).into_diagnostic()?;
+    let report = ping::ping(&probe, &endpoints);
+    if args.json || output_mode == OutputMode::NonInteractive {
+        JsonOutput::print_ping(&report)
+    } else if output_mode == OutputMode::Plain {
+        PlainOutput::print_ping(&report)
+    } else {
+        StyledOutput::print_ping(&report)
+    }
+    .into_diagnostic()?;
+    let unreachable = report.unreachable();
+    if !unreachable.is_empty() {
+        return Err(miette::Report::new(KiraError::RegistriesUnreachable(
+            unreachable.into_iter().map(str::to_string).collect(),
+        )));
+    }
+    Ok(())
+}
+
 fn run_tools_from_line(line: &str) -> miette::Result<()> {
     let parts: Vec<&str> = line.split_whitespace().collect();
-    if parts.len() >= 2 && parts[0] == "tools" && parts[1] == "install-sra" {
-        return run_tools(ToolsArgs {
-            command: ToolsCommand::InstallSra,
-        });
+    let command = match parts.as_slice() {
+        ["tools", "install-sra", ..] => ToolsCommand::InstallSra,
+        ["tools", "check", ..] => ToolsCommand::Check,
+        _ => return Err(miette::Report::msg("unknown tools command")),
+    };
+    run_tools(ToolsArgs { command }, OutputMode::Plain)
+}
+
+/// The user config's fetch defaults, with the current project's over
+/// them.
+fn fetch_defaults() -> miette::Result<FetchDefaults> {
+    match ConfigLoader::resolve(None) {
+        Ok(config) => Ok(config.defaults),
+        Err(_) => {
+            let global = GlobalConfig::load()?;
+            Ok(FetchDefaults::layered(global.defaults.as_ref(), None))
+        }
+    }
+}
+
+/// Fills in what the command line left open from the configs' `defaults`.
+fn apply_defaults(overrides: &mut FetchOverrides, defaults: &FetchDefaults) {
+    overrides.protein_format = overrides
+        .protein_format
+        .or(defaults.protein_format.configured().copied());
+    overrides.srr_format = overrides
+        .srr_format
+        .or(defaults.srr_format.configured().copied());
+    overrides.srr_paired = overrides
+        .srr_paired
+        .or(defaults.srr_paired.configured().copied());
+    if overrides.genome_include.is_none() {
+        overrides.genome_include = defaults.genome_include.configured().cloned();
     }
-    Err(miette::Report::msg("unknown tools command"))
 }
 
 fn build_overrides(
     specifier: Option<&DatasetSpecifier>,
     format: Option<FetchFormat>,
-    paired: bool,
+    paired: Option<bool>,
 ) -> Result<FetchOverrides, KiraError> {
     let mut overrides = FetchOverrides::default();
-    if paired {
-        if matches!(specifier, Some(DatasetSpecifier::Srr(_)) | None) {
-            overrides.srr_paired = Some(true);
+    if let Some(paired) = paired {
+        if matches!(
+            specifier,
+            Some(
+                DatasetSpecifier::Srr(_)
+                    | DatasetSpecifier::SrrExperiment(_)
+                    | DatasetSpecifier::BioProject(_)
+            ) | None
+        ) {
+            overrides.srr_paired = Some(paired);
         } else {
             return Err(KiraError::InvalidFormat(
-                "--paired is only valid for srr datasets".to_string(),
+                "--paired and --no-paired are only valid for srr, srx and bioproject datasets"
+                    .to_string(),
             ));
         }
     }
@@ -754,7 +2303,7 @@ fn build_overrides(
         return Ok(overrides);
     };
     match specifier {
-        Some(DatasetSpecifier::Protein(_)) => {
+        Some(DatasetSpecifier::Protein(_) | DatasetSpecifier::StructureFor(_)) => {
             overrides.protein_format = Some(match format {
                 FetchFormat::Cif => ProteinFormat::Cif,
                 FetchFormat::Pdb => ProteinFormat::Pdb,
@@ -766,7 +2315,7 @@ fn build_overrides(
                 }
             });
         }
-        Some(DatasetSpecifier::Srr(_)) => {
+        Some(DatasetSpecifier::Srr(_) | DatasetSpecifier::SrrExperiment(_)) => {
             overrides.srr_format = Some(match format {
                 FetchFormat::Fastq => SrrFormat::Fastq,
                 FetchFormat::Fasta => SrrFormat::Fasta,
@@ -777,11 +2326,27 @@ fn build_overrides(
                 }
             });
         }
+        Some(DatasetSpecifier::BioProject(_)) => {
+            overrides.srr_format = Some(match format {
+                FetchFormat::Fastq => SrrFormat::Fastq,
+                FetchFormat::Fasta => SrrFormat::Fasta,
+                _ => {
+                    return Err(KiraError::InvalidFormat(
+                        "format must be fastq|fasta for bioproject runs".to_string(),
+                    ));
+                }
+            });
+        }
         Some(DatasetSpecifier::Uniprot(_)) => {
             return Err(KiraError::InvalidFormat(
                 "format override is not supported for uniprot datasets".to_string(),
             ));
         }
+        Some(DatasetSpecifier::Proteome(_)) => {
+            return Err(KiraError::InvalidFormat(
+                "format override is not supported for uniprot-proteome datasets".to_string(),
+            ));
+        }
         Some(DatasetSpecifier::Doi(_)) => {
             return Err(KiraError::InvalidFormat(
                 "format override is not supported for doi datasets".to_string(),
@@ -797,12 +2362,17 @@ fn build_overrides(
                 "format override is not supported for expression10x datasets".to_string(),
             ));
         }
+        Some(DatasetSpecifier::ExpressionSample(_)) => {
+            return Err(KiraError::InvalidFormat(
+                "format override is not supported for expressionsample datasets".to_string(),
+            ));
+        }
         Some(DatasetSpecifier::Go) => {
             return Err(KiraError::InvalidFormat(
                 "format override is not supported for go datasets".to_string(),
             ));
         }
-        Some(DatasetSpecifier::Kegg) => {
+        Some(DatasetSpecifier::Kegg(_)) => {
             return Err(KiraError::InvalidFormat(
                 "format override is not supported for kegg datasets".to_string(),
             ));
@@ -812,7 +2382,18 @@ fn build_overrides(
                 "format override is not supported for reactome datasets".to_string(),
             ));
         }
-        Some(DatasetSpecifier::Genome(_)) => {
+        // run_fetch resolves aliases before building overrides.
+        Some(DatasetSpecifier::Alias(alias)) => {
+            return Err(KiraError::InvalidFormat(format!(
+                "alias:{alias} must be resolved before a format override"
+            )));
+        }
+        Some(
+            DatasetSpecifier::Genome(_)
+            | DatasetSpecifier::GenomeName(_)
+            | DatasetSpecifier::GenomeTaxon(_)
+            | DatasetSpecifier::GenomeEnsembl(_),
+        ) => {
             return Err(KiraError::InvalidFormat(
                 "format override is not supported for genome datasets".to_string(),
             ));
@@ -837,24 +2418,54 @@ fn run_list<
     G: GeoClient + 'static,
     K: KnowledgeClient + 'static,
 >(
-    app: App<N, R, S, U, G, K>,
+    options: ListOptions,
+    app: Arc<App<N, R, S, U, G, K>>,
     output_mode: OutputMode,
+    session: Option<&mut Tui>,
 ) -> miette::Result<()> {
     match output_mode {
         OutputMode::NonInteractive => {
-            let result = app.list(&JsonOutput).into_diagnostic()?;
+            let result = app.list(&options, &JsonOutput)?;
             JsonOutput::print_list(&result).into_diagnostic()?;
             Ok(())
         }
+        OutputMode::Plain => {
+            let result = app.list(&options, &PlainOutput)?;
+            PlainOutput::print_list(&result).into_diagnostic()
+        }
         OutputMode::Interactive => {
-            let mut tui = Tui::new(ProgressSinkKind::List);
-            let result = tui.run(move |sink| app.list(sink))?;
+            let mut own = None;
+            let tui = session_tui(session, &mut own, ProgressSinkKind::List);
+            let result = tui.run(move |sink| app.list(&options, sink))?;
             tui.finish_list(&result)?;
-            Ok(())
+            tui.close()?;
+            StyledOutput::print_list(&result).into_diagnostic()
         }
     }
 }
 
+fn run_find<
+    N: NcbiClient + 'static,
+    R: RcsbClient + 'static,
+    S: SrrClient + 'static,
+    U: UniprotClient + 'static,
+    G: GeoClient + 'static,
+    K: KnowledgeClient + 'static,
+>(
+    args: FindArgs,
+    app: Arc<App<N, R, S, U, G, K>>,
+    output_mode: OutputMode,
+    session: Option<&mut Tui>,
+) -> miette::Result<()> {
+    let result = app.find(&args.pattern, &JsonOutput)?;
+    leave_session(session)?;
+    match output_mode {
+        OutputMode::NonInteractive => JsonOutput::print_find(&result).into_diagnostic(),
+        OutputMode::Plain => PlainOutput::print_find(&result).into_diagnostic(),
+        OutputMode::Interactive => StyledOutput::print_find(&result).into_diagnostic(),
+    }
+}
+
 fn run_info<
     N: NcbiClient + 'static,
     R: RcsbClient + 'static,
@@ -864,22 +2475,47 @@ fn run_info<
     K: KnowledgeClient + 'static,
 >(
     args: InfoArgs,
-    app: App<N, R, S, U, G, K>,
+    app: Arc<App<N, R, S, U, G, K>>,
     output_mode: OutputMode,
+    mut session: Option<&mut Tui>,
 ) -> miette::Result<()> {
-    let specifier = args
-        .specifier
-        .parse::<DatasetSpecifier>()
-        .into_diagnostic()?;
+    // Several datasets print as JSON in every mode, as `info` does in plain
+    // mode.
+    if SpecifierPattern::is_pattern(&args.specifier) && !args.datasets {
+        let pattern = args.specifier.parse::<SpecifierPattern>()?;
+        let result = app.info_matching(&pattern, &JsonOutput)?;
+        leave_session(session)?;
+        return JsonOutput::print_info_matches(&result).into_diagnostic();
+    }
+    let specifier = read_specifier(&app, &args.specifier, output_mode, session.as_deref_mut())?;
+    let specifier = app.resolve_alias(specifier)?;
+
+    if args.datasets {
+        let DatasetSpecifier::Doi(doi) = specifier else {
+            return Err(miette::Report::new(KiraError::InvalidSpecifier(format!(
+                "--datasets needs a doi: specifier, got {}",
+                args.specifier
+            ))));
+        };
+        let result = app.provenance(&doi, &JsonOutput)?;
+        leave_session(session)?;
+        return match output_mode {
+            OutputMode::NonInteractive => JsonOutput::print_doi_datasets(&result),
+            OutputMode::Plain => PlainOutput::print_doi_datasets(&result),
+            OutputMode::Interactive => StyledOutput::print_doi_datasets(&result),
+        }
+        .into_diagnostic();
+    }
 
     match output_mode {
-        OutputMode::NonInteractive => {
-            let result = app.info(specifier, &JsonOutput).into_diagnostic()?;
+        OutputMode::NonInteractive | OutputMode::Plain => {
+            let result = app.info(specifier, &JsonOutput)?;
             JsonOutput::print_info(&result).into_diagnostic()?;
             Ok(())
         }
         OutputMode::Interactive => {
-            let mut tui = Tui::new(ProgressSinkKind::Info);
+            let mut own = None;
+            let tui = session_tui(session, &mut own, ProgressSinkKind::Info);
             let result = tui.run(move |sink| app.info(specifier, sink))?;
             tui.finish_info(&result)?;
             Ok(())
@@ -887,6 +2523,131 @@ fn run_info<
     }
 }
 
+fn run_runs(args: RunsArgs, store: Store, output_mode: OutputMode) -> miette::Result<()> {
+    let app = App::new(
+        store,
+        NopNcbi,
+        NopRcsb,
+        NopSrr,
+        NopUniprot,
+        NopGeo,
+        NopKnowledge,
+    );
+    let json = matches!(output_mode, OutputMode::NonInteractive);
+    match args.command {
+        RunsCommand::List => {
+            let runs = app.list_runs()?;
+            if json {
+                JsonOutput::print_runs(&runs).into_diagnostic()
+            } else {
+                PlainOutput::print_runs(&runs).into_diagnostic()
+            }
+        }
+        // A manifest is a JSON document in every output mode.
+        RunsCommand::Show { id } => {
+            let manifest = app.show_run(&id)?;
+            JsonOutput::print_run(&manifest).into_diagnostic()
+        }
+    }
+}
+
+fn run_config(args: ConfigArgs, output_mode: OutputMode) -> miette::Result<()> {
+    match args.command {
+        ConfigCommand::Validate { config } => {
+            let path = match config {
+                Some(path) => PathBuf::from(path),
+                None => Project::current()?.config_path(),
+            };
+            if !path.exists() {
+                return Err(miette::Report::new(KiraError::ConfigRead(path)));
+            }
+            ConfigLoader::validate(&path)?;
+            if matches!(output_mode, OutputMode::NonInteractive) {
+                JsonOutput::print_config_valid(&path).into_diagnostic()
+            } else {
+                PlainOutput::print_config_valid(&path).into_diagnostic()
+            }
+        }
+        ConfigCommand::Show { config } => {
+            let path = match config {
+                Some(path) => Some(PathBuf::from(path)),
+                None => Some(Project::current()?.config_path()).filter(|path| path.exists()),
+            };
+            let project = path.map(|path| ConfigLoader::load(&path)).transpose()?;
+            let user = GlobalConfig::load()?;
+            let settings = ConfigLoader::effective_settings(&user, project.as_ref());
+            if matches!(output_mode, OutputMode::NonInteractive) {
+                JsonOutput::print_config_settings(&settings).into_diagnostic()
+            } else {
+                PlainOutput::print_config_settings(&settings).into_diagnostic()
+            }
+        }
+    }
+}
+
+/// Prints the bare path in every mode, for scripts to substitute.
+fn run_path(args: PathArgs, store: &Store) -> miette::Result<()> {
+    let specifier = DatasetSpecifier::infer(&args.specifier)?;
+    let path = if args.cache {
+        store.resolve_cache_dataset_path(&specifier, args.format)
+    } else {
+        store.resolve_dataset_path(&specifier, args.format)
+    };
+    match path {
+        Some(path) => {
+            println!("{path}");
+            Ok(())
+        }
+        None => Err(miette::Report::new(KiraError::DatasetNotFound(
+            specifier.to_string(),
+        ))),
+    }
+}
+
+fn run_convert(args: ConvertArgs, store: Store, output_mode: OutputMode) -> miette::Result<()> {
+    let specifier = args.specifier.parse::<DatasetSpecifier>()?;
+    let app = App::new(
+        store,
+        NopNcbi,
+        NopRcsb,
+        NopSrr,
+        NopUniprot,
+        NopGeo,
+        NopKnowledge,
+    );
+    if matches!(output_mode, OutputMode::NonInteractive) {
+        let result = app.convert(specifier, args.to, &JsonOutput)?;
+        return JsonOutput::print_convert(&result).into_diagnostic();
+    }
+    let result = app.convert(specifier, args.to, &PlainOutput)?;
+    PlainOutput::print_convert(&result).into_diagnostic()
+}
+
+fn run_import(args: ImportArgs, store: Store, output_mode: OutputMode) -> miette::Result<()> {
+    let specifier = args.specifier.parse::<DatasetSpecifier>()?;
+    let options = ImportOptions {
+        link: args.link,
+        no_verify: args.no_verify,
+        force: args.force,
+    };
+    let app = App::new(
+        store,
+        NopNcbi,
+        NopRcsb,
+        NopSrr,
+        NopUniprot,
+        NopGeo,
+        NopKnowledge,
+    )
+    .with_context(AppContext::current());
+    if matches!(output_mode, OutputMode::NonInteractive) {
+        let result = app.import(specifier, &args.from, options, &JsonOutput)?;
+        return JsonOutput::print_import(&result).into_diagnostic();
+    }
+    let result = app.import(specifier, &args.from, options, &PlainOutput)?;
+    PlainOutput::print_import(&result).into_diagnostic()
+}
+
 fn run_clear<
     N: NcbiClient + 'static,
     R: RcsbClient + 'static,
@@ -895,23 +2656,47 @@ fn run_clear<
     G: GeoClient + 'static,
     K: KnowledgeClient + 'static,
 >(
-    app: App<N, R, S, U, G, K>,
+    app: Arc<App<N, R, S, U, G, K>>,
+    args: ClearArgs,
     output_mode: OutputMode,
+    session: Option<&mut Tui>,
 ) -> miette::Result<()> {
+    let options = args.options();
     match output_mode {
         OutputMode::NonInteractive => {
-            let result = app.clear(&JsonOutput).into_diagnostic()?;
+            let result = app.clear(&options, &JsonOutput)?;
             JsonOutput::print_clear(&result).into_diagnostic()?;
             Ok(())
         }
+        OutputMode::Plain => {
+            let result = app.clear(&options, &PlainOutput)?;
+            PlainOutput::print_clear(&result).into_diagnostic()
+        }
         OutputMode::Interactive => {
-            let mut tui = Tui::new(ProgressSinkKind::Clear);
-            let confirmed = tui.confirm_clear()?;
+            let mut own = None;
+            let tui = session_tui(session, &mut own, ProgressSinkKind::Clear);
+            let preview_options = ClearOptions {
+                dry_run: true,
+                ..options.clone()
+            };
+            let preview_app = Arc::clone(&app);
+            let preview = tui.run(move |sink| preview_app.clear(&preview_options, sink))?;
+            if options.dry_run {
+                tui.close()?;
+                return PlainOutput::print_clear(&preview).into_diagnostic();
+            }
+            let confirmed = tui.confirm_clear(&preview)?;
             if !confirmed {
                 return Ok(());
             }
-            let _result = tui.run(move |sink| app.clear(sink))?;
+            let result = tui.run(move |sink| app.clear(&options, sink))?;
             tui.finish_clear()?;
+            if !result.skipped_pinned.is_empty() {
+                tui.note_warning(&format!(
+                    "kept pinned: {} (pass --include-pinned to clear them)",
+                    result.skipped_pinned.join(", ")
+                ));
+            }
             Ok(())
         }
     }
@@ -925,18 +2710,36 @@ fn run_init<
     G: GeoClient + 'static,
     K: KnowledgeClient + 'static,
 >(
-    app: App<N, R, S, U, G, K>,
+    app: Arc<App<N, R, S, U, G, K>>,
+    args: InitArgs,
     output_mode: OutputMode,
+    session: Option<&mut Tui>,
 ) -> miette::Result<()> {
+    let options = InitOptions::from(args);
     match output_mode {
         OutputMode::NonInteractive => {
-            let result = app.init_config(&JsonOutput).into_diagnostic()?;
+            note_project(&JsonOutput);
+            let result = app.init_config(options, &JsonOutput)?;
             JsonOutput::print_init(&result).into_diagnostic()?;
             Ok(())
         }
+        OutputMode::Plain => {
+            note_project(&PlainOutput);
+            let result = app.init_config(options, &PlainOutput)?;
+            PlainOutput::print_init(&result).into_diagnostic()
+        }
         OutputMode::Interactive => {
-            let mut tui = Tui::new(ProgressSinkKind::Fetch);
-            let _result = tui.run(move |sink| app.init_config(sink))?;
+            let mut own = None;
+            let tui = session_tui(session, &mut own, ProgressSinkKind::Fetch);
+            let result = tui.run(move |sink| {
+                note_project(sink);
+                app.init_config(options, sink)
+            })?;
+            // The diff outlives the TUI screen.
+            if result.dry_run {
+                tui.close()?;
+                PlainOutput::print_init(&result).into_diagnostic()?;
+            }
             Ok(())
         }
     }
@@ -0,0 +1,208 @@
+//! Cooperative cancellation with optional deadlines.
+//!
+//! A [`CancellationToken`] fires when a front end cancels it (Ctrl-C, the
+//! TUI) or when its deadline passes. `App::fetch` makes the token of its
+//! [`FetchOptions`](crate::app::FetchOptions) current on the fetching thread;
+//! clients, whose traits take no token, pick it up with [`current`] to stop
+//! streaming downloads and external tools.
+
+use std::cell::RefCell;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Read, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Instant;
+
+use crate::error::KiraError;
+use crate::fs_util::HashingWriter;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+thread_local! {
+    static CURRENT: RefCell<Option<CancellationToken>> = const { RefCell::new(None) };
+}
+
+/// Shared flag a front end sets to stop a running operation. Clones share
+/// state, so cloning is cheap.
+#[derive(Clone, Default)]
+pub struct CancellationToken(Arc<Inner>);
+
+#[derive(Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    deadline: Mutex<Option<Instant>>,
+    waiters: Mutex<Vec<Sender<()>>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        let waiters = std::mem::take(&mut *lock(&self.0.waiters));
+        for waiter in waiters {
+            let _ = waiter.send(());
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        if self.0.cancelled.load(Ordering::SeqCst) {
+            return true;
+        }
+        if self
+            .deadline()
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.cancel();
+            return true;
+        }
+        false
+    }
+
+    /// Cancels the token at `deadline`; an earlier deadline already set wins.
+    pub fn set_deadline(&self, deadline: Instant) {
+        {
+            let mut current = lock(&self.0.deadline);
+            if current.is_some_and(|current| current <= deadline) {
+                return;
+            }
+            *current = Some(deadline);
+        }
+        // Wakes blocking waiters when the deadline passes; holds no strong
+        // reference, so a dropped token does not outlive its operation.
+        let inner = Arc::downgrade(&self.0);
+        std::thread::spawn(move || {
+            std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+            if let Some(token) = Weak::upgrade(&inner).map(CancellationToken) {
+                let _ = token.is_cancelled();
+            }
+        });
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        *lock(&self.0.deadline)
+    }
+
+    /// A channel that receives once the token fires, for blocking waits.
+    pub fn cancelled_channel(&self) -> Receiver<()> {
+        let (sender, receiver) = mpsc::channel();
+        if self.is_cancelled() {
+            let _ = sender.send(());
+        } else {
+            lock(&self.0.waiters).push(sender);
+        }
+        receiver
+    }
+
+    pub fn check(&self) -> Result<(), KiraError> {
+        if self.is_cancelled() {
+            return Err(KiraError::Cancelled);
+        }
+        Ok(())
+    }
+
+    /// Makes this the [`current`] token on this thread until the guard drops.
+    pub fn enter(&self) -> CancelScope {
+        let previous = CURRENT.with(|current| current.replace(Some(self.clone())));
+        CancelScope {
+            previous,
+            _thread_bound: PhantomData,
+        }
+    }
+}
+
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancellationToken {}
+
+impl std::fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.0.cancelled.load(Ordering::SeqCst))
+            .field("deadline", &self.deadline())
+            .finish()
+    }
+}
+
+/// Restores the previously current token when dropped.
+pub struct CancelScope {
+    previous: Option<CancellationToken>,
+    _thread_bound: PhantomData<*const ()>,
+}
+
+impl Drop for CancelScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// The token entered on this thread, if any.
+pub fn current() -> Option<CancellationToken> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Fails with [`KiraError::Cancelled`] once the current token has fired.
+pub fn check_current() -> Result<(), KiraError> {
+    current().map_or(Ok(()), |token| token.check())
+}
+
+/// Copies `reader` to `writer` in chunks, stopping at the first chunk
+/// boundary after the current token fires. I/O errors go through `io_error`.
+pub fn copy<R: Read + ?Sized, W: Write + ?Sized>(
+    reader: &mut R,
+    writer: &mut W,
+    io_error: fn(String) -> KiraError,
+) -> Result<u64, KiraError> {
+    let token = current();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut copied = 0u64;
+    loop {
+        if let Some(token) = &token {
+            token.check()?;
+        }
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(copied),
+            Ok(read) => read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(io_error(err.to_string())),
+        };
+        writer
+            .write_all(&buffer[..read])
+            .map_err(|err| io_error(err.to_string()))?;
+        copied += read as u64;
+    }
+}
+
+/// Streams `reader` into a new file at `destination`, removing the partial
+/// file if the copy fails or is cancelled. Returns the bytes copied and
+/// their SHA-256, taken on the way.
+pub fn copy_to_file<R: Read + ?Sized>(
+    reader: &mut R,
+    destination: &Path,
+) -> Result<(u64, String), KiraError> {
+    let file = File::create(destination).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    let mut file = HashingWriter::new(file);
+    let result = copy(reader, &mut file, KiraError::Filesystem);
+    let (file, digest) = file.finish();
+    drop(file);
+    if result.is_err() {
+        let _ = fs::remove_file(destination);
+    }
+    Ok((result?, digest))
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
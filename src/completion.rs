@@ -0,0 +1,153 @@
+//! Shell completion scripts.
+//!
+//! The static part comes from `clap_complete`; each script is then patched so
+//! the `SPECIFIER` argument of `fetch`, `add`, `info`, `path` and `convert` asks
+//! `kira-bm __complete-specifiers <partial>` for candidates.
+
+use std::collections::BTreeSet;
+
+use clap_complete::Shell;
+
+use crate::audit::specifier_for;
+use crate::store::Store;
+
+const HELPER: &str = "__complete-specifiers";
+const SPECIFIER_COMMANDS: [&str; 8] = [
+    "fetch", "add", "info", "path", "convert", "import", "pin", "unpin",
+];
+
+/// Specifier prefixes in the order they are offered; entries without `:` are
+/// complete specifiers on their own.
+pub const SPECIFIER_PREFIXES: [&str; 15] = [
+    "protein:",
+    "genome:",
+    "srr:",
+    "srx:",
+    "uniprot:",
+    "doi:",
+    "bioproject:",
+    "expression:",
+    "expression10x:",
+    "expressionsample:",
+    "structure-for:",
+    "go",
+    "kegg",
+    "reactome",
+    "alias:",
+];
+
+/// Candidates for a partially typed specifier: datasets already in the
+/// project store whose specifier starts with `partial`, plus the type
+/// prefixes that match and have no stored dataset to complete to.
+pub fn specifiers(store: &Store, partial: &str) -> Vec<String> {
+    let partial = partial.trim().to_ascii_lowercase();
+    let metadata = Store::list_metadata(store.project_root()).unwrap_or_default();
+    let stored = metadata
+        .iter()
+        .map(specifier_for)
+        .chain(
+            metadata
+                .iter()
+                .filter_map(|meta| Some(format!("alias:{}", meta.alias.as_ref()?))),
+        )
+        .filter(|specifier| specifier.to_ascii_lowercase().starts_with(&partial))
+        .collect::<BTreeSet<_>>();
+    let mut candidates = SPECIFIER_PREFIXES
+        .iter()
+        .filter(|prefix| prefix.starts_with(&partial))
+        .filter(|prefix| {
+            !stored
+                .iter()
+                .any(|specifier| specifier.starts_with(**prefix))
+        })
+        .map(|prefix| prefix.to_string())
+        .collect::<Vec<_>>();
+    candidates.extend(stored);
+    candidates
+}
+
+pub fn script(shell: Shell, command: &mut clap::Command) -> String {
+    let mut out = Vec::new();
+    clap_complete::generate(shell, command, "kira-bm", &mut out);
+    let script = String::from_utf8_lossy(&out).into_owned();
+    match shell {
+        Shell::Bash => patch_bash(script),
+        Shell::Zsh => patch_zsh(script),
+        Shell::Fish => patch_fish(script),
+        _ => script,
+    }
+}
+
+fn patch_bash(script: String) -> String {
+    // Bash splits words at `:`, so the current word is re-read from the line
+    // and the part before the last `:` is trimmed from each candidate.
+    let hook = format!(
+        r#"
+_kira_bm_specifiers() {{
+    local line="${{COMP_LINE:0:COMP_POINT}}"
+    local cur="${{line##*[[:space:]]}}"
+    local prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    case "${{COMP_WORDS[1]}}" in
+        {commands})
+            case "$prev" in
+                --config|--format|--srr-threads|--srr-temp-dir|--include-categories|--pause-timeout|--lock-timeout|--deadline|--proxy|--ca-bundle|--timeout|--connect-timeout) ;;
+                *)
+                    if [[ "$cur" != -* ]]; then
+                        local IFS=$'\n'
+                        COMPREPLY=( $(kira-bm {HELPER} "$cur" 2>/dev/null) )
+                        local colon_prefix="${{cur%"${{cur##*:}}"}}"
+                        COMPREPLY=( "${{COMPREPLY[@]#"$colon_prefix"}}" )
+                        compopt -o nospace 2>/dev/null
+                        return 0
+                    fi
+                    ;;
+            esac
+            ;;
+    esac
+    _kira__bm "$@"
+}}
+"#,
+        commands = SPECIFIER_COMMANDS.join("|"),
+    );
+    let script = script.replace("complete -F _kira__bm ", "complete -F _kira_bm_specifiers ");
+    insert_before_registration(script, "\nif [[ \"${BASH_VERSINFO[0]}\"", &hook)
+}
+
+fn patch_zsh(script: String) -> String {
+    let hook = format!(
+        r#"
+(( $+functions[_kira_bm_specifiers] )) ||
+_kira_bm_specifiers() {{
+    local -a candidates
+    candidates=(${{(f)"$(kira-bm {HELPER} "$PREFIX" 2>/dev/null)"}})
+    compadd -S '' -- $candidates
+}}
+"#
+    );
+    let script = script
+        .replace(
+            "'::specifier:_default'",
+            "'::specifier:_kira_bm_specifiers'",
+        )
+        .replace("':specifier:_default'", "':specifier:_kira_bm_specifiers'");
+    insert_before_registration(script, "\nif [ \"$funcstack[1]\" = \"_kira-bm\" ]", &hook)
+}
+
+fn patch_fish(mut script: String) -> String {
+    let condition = SPECIFIER_COMMANDS
+        .iter()
+        .map(|command| format!("__fish_kira_bm_using_subcommand {command}"))
+        .collect::<Vec<_>>()
+        .join("; or ");
+    script.push_str(&format!(
+        "complete -c kira-bm -n \"{condition}\" -f -a \"(kira-bm {HELPER} (commandline -ct))\"\n"
+    ));
+    script
+}
+
+fn insert_before_registration(script: String, marker: &str, hook: &str) -> String {
+    match script.rfind(marker) {
+        Some(index) => format!("{}{hook}{}", &script[..index], &script[index..]),
+        None => script + hook,
+    }
+}
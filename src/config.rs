@@ -1,104 +1,365 @@
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::domain::{Doi, GenomeAccession, ProteinFormat, ProteinId, SrrFormat, SrrId, UniprotId};
+use crate::domain::{
+    BioProjectAccession, DatasetAlias, DatasetSpecifier, Doi, GenomeAccession, GoaSpecies,
+    ProteinFormat, ProteinId, ProteomeId, SrrFormat, SrrId, UniprotId,
+};
 use crate::error::KiraError;
+use crate::fs_util::{ZipLimits, sha256_file};
+use crate::geo::FileCategory;
+use crate::http::{TIMEOUT_REGISTRIES, TimeoutOverrides};
+use crate::io_util::{self, InputError};
+use crate::knowledge::GoFlavor;
+use crate::project::Project;
+use crate::retention::{KB_KEY, KB_TYPES, RetentionPolicy};
+use crate::runs::DEFAULT_RUNS_KEPT;
+use crate::store::DATASET_TYPES;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct Config {
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub schema_version: Option<u32>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub proteins: Vec<ProteinEntry>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub genomes: Vec<GenomeEntry>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub srr: Vec<SrrEntry>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub uniprot: Vec<UniprotEntry>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub doi: Vec<DoiEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bioprojects: Vec<BioProjectEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proteomes: Vec<ProteomeEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_categories: Vec<FileCategory>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ncbi_api_key: Option<String>,
+    /// Proxy for every registry request; `--proxy` overrides it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// PEM file of extra CA certificates; `--ca-bundle` overrides it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<String>,
+    /// Entries (as `type:id` specifiers) skipped by config-driven fetches.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<RetentionConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub go: Option<GoConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runs: Option<RunsConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeouts: Option<TimeoutsConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub defaults: Option<DefaultsConfig>,
+    /// Whether every fetch writes dataset READMEs, as `--write-readme`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write_readme: Option<bool>,
+    /// Keys this version does not model, kept so rewrites do not drop them.
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Maximum age per dataset type, e.g. `{"srr": "180d", "kb": "90d"}`; `kb`
+/// covers go, kegg and reactome unless they have their own key. Datasets
+/// that must not expire are pinned with `kira-bm pin`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RetentionConfig {
+    #[serde(flatten)]
+    pub max_age: BTreeMap<String, String>,
+}
+
+/// What `go` fetches download besides the defaults, e.g.
+/// `{"flavor": "full", "annotations": ["human", "mouse"]}`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GoConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flavor: Option<GoFlavor>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<String>,
+}
+
+/// How many fetch run manifests the project store keeps, e.g.
+/// `{"keep": 20}`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RunsConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep: Option<usize>,
+}
+
+/// HTTP timeouts in seconds for every registry, with per-registry
+/// overrides, e.g. `{"request_secs": 120, "ncbi": {"stall_secs": 300}}`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TimeoutsConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stall_secs: Option<u64>,
+    #[serde(flatten)]
+    pub registries: BTreeMap<String, TimeoutOverrides>,
+}
+
+/// Whether fetches copy each dataset type into the shared cache, e.g.
+/// `{"srr": false, "default": true}`. `kb` covers go, kegg and reactome
+/// unless they have their own key, and `default` every type without one.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CacheConfig {
+    #[serde(flatten)]
+    pub types: BTreeMap<String, bool>,
+}
+
+/// Fetch options used when the command line does not give them, e.g.
+/// `{"protein_format": "pdb", "srr_paired": true}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DefaultsConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protein_format: Option<ProteinFormat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srr_format: Option<SrrFormat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srr_paired: Option<bool>,
+    /// What genome entries without an `include` of their own download.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub genome_include: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_categories: Option<Vec<FileCategory>>,
+}
+
+impl TimeoutsConfig {
+    pub fn global(&self) -> TimeoutOverrides {
+        TimeoutOverrides {
+            connect_secs: self.connect_secs,
+            request_secs: self.request_secs,
+            stall_secs: self.stall_secs,
+        }
+    }
+}
+
+/// Settings shared by every project of the user, read from
+/// `~/.config/kira-biodata-manager/config.json`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GlobalConfig {
+    /// Mirror base URLs by registry key, e.g.
+    /// `{"rcsb": "https://pdb.example.org"}`; the `KIRA_BM_*_BASE`
+    /// variables override them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub registries: BTreeMap<String, String>,
+    /// The cache policy of every project; a project's `kira-bm.json`
+    /// overrides it key by key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheConfig>,
+    /// Fetch defaults of every project; a project's `defaults` override
+    /// them option by option.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub defaults: Option<DefaultsConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archives: Option<ArchivesConfig>,
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Limits on what a downloaded zip archive may unpack to, e.g.
+/// `{"max_unpacked_bytes": 137438953472, "max_entries": 500000}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArchivesConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_unpacked_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_entries: Option<usize>,
+}
+
+impl ArchivesConfig {
+    pub fn limits(&self) -> ZipLimits {
+        let defaults = ZipLimits::default();
+        ZipLimits {
+            max_unpacked_bytes: self
+                .max_unpacked_bytes
+                .unwrap_or(defaults.max_unpacked_bytes),
+            max_entries: self.max_entries.unwrap_or(defaults.max_entries),
+        }
+    }
+}
+
+impl GlobalConfig {
+    pub fn path() -> Option<PathBuf> {
+        directories::BaseDirs::new().map(|dirs| {
+            dirs.home_dir()
+                .join(".config")
+                .join("kira-biodata-manager")
+                .join("config.json")
+        })
+    }
+
+    /// The global config, or the defaults when there is none.
+    pub fn load() -> Result<Self, KiraError> {
+        match Self::path() {
+            Some(path) if path.exists() => Self::load_from(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, KiraError> {
+        let value = ConfigLoader::read_value(path)?;
+        serde_json::from_value(value)
+            .map_err(|err| KiraError::ConfigParse(format!("{err} (in {})", path.display())))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum ProteinEntry {
     Shorthand(String),
     Detailed(ProteinEntryObject),
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct ProteinEntryObject {
     pub id: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub format: Option<ProteinFormat>,
+    /// Logical name for `alias:` specifiers, unique across the config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum GenomeEntry {
     Shorthand(String),
     Detailed(GenomeEntryObject),
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct GenomeEntryObject {
     pub accession: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub include: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum UniprotEntry {
     Shorthand(String),
     Detailed(UniprotEntryObject),
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct UniprotEntryObject {
     pub id: String,
+    /// Also fetch the PDB structures the entry cross-references.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub with_structures: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum DoiEntry {
     Shorthand(String),
     Detailed(DoiEntryObject),
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct DoiEntryObject {
     pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
 }
-#[derive(Debug, Deserialize, Serialize)]
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum BioProjectEntry {
+    Shorthand(String),
+    Detailed(BioProjectEntryObject),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BioProjectEntryObject {
+    pub accession: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ProteomeEntry {
+    Shorthand(String),
+    Detailed(ProteomeEntryObject),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProteomeEntryObject {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum SrrEntry {
     Shorthand(String),
     Detailed(SrrEntryObject),
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct SrrEntryObject {
     pub id: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub format: Option<SrrFormat>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub paired: Option<bool>,
+    /// fasterq-dump threads and scratch directory for this run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threads: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temp_dir: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct ProteinRequest {
     pub id: ProteinId,
     pub format: ProteinFormat,
+    pub alias: Option<DatasetAlias>,
 }
 
 #[derive(Debug, Clone)]
 pub struct GenomeRequest {
     pub accession: GenomeAccession,
     pub include: Vec<String>,
+    pub alias: Option<DatasetAlias>,
 }
 
 #[derive(Debug, Clone)]
@@ -109,6 +370,24 @@ pub struct ResolvedConfig {
     pub srr: Vec<SrrRequest>,
     pub uniprot: Vec<UniprotRequest>,
     pub doi: Vec<DoiRequest>,
+    pub bioprojects: Vec<BioProjectRequest>,
+    pub proteomes: Vec<ProteomeRequest>,
+    pub include_categories: Vec<FileCategory>,
+    pub ncbi_api_key: Option<String>,
+    pub proxy: Option<String>,
+    pub ca_bundle: Option<PathBuf>,
+    pub retention: RetentionPolicy,
+    pub go_flavor: Option<GoFlavor>,
+    pub go_annotations: Vec<GoaSpecies>,
+    pub runs_kept: usize,
+    pub timeouts: TimeoutOverrides,
+    pub registry_timeouts: BTreeMap<String, TimeoutOverrides>,
+    pub cache: CachePolicy,
+    /// Fetch defaults of the user config with the project's over them.
+    pub defaults: FetchDefaults,
+    /// SHA-256 of the file the config was read from, as stored.
+    pub sha256: Option<String>,
+    pub write_readme: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -116,41 +395,146 @@ pub struct SrrRequest {
     pub id: SrrId,
     pub format: SrrFormat,
     pub paired: bool,
+    pub threads: Option<u32>,
+    pub temp_dir: Option<PathBuf>,
+    pub alias: Option<DatasetAlias>,
 }
 
 #[derive(Debug, Clone)]
 pub struct UniprotRequest {
     pub id: UniprotId,
+    pub with_structures: bool,
+    pub alias: Option<DatasetAlias>,
 }
 
 #[derive(Debug, Clone)]
 pub struct DoiRequest {
     pub id: Doi,
+    pub alias: Option<DatasetAlias>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BioProjectRequest {
+    pub accession: BioProjectAccession,
+    pub alias: Option<DatasetAlias>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProteomeRequest {
+    pub id: ProteomeId,
+    pub alias: Option<DatasetAlias>,
 }
 
 pub struct ConfigLoader;
 
 impl ConfigLoader {
+    /// Resolves the config at `path`, or else the current project's
+    /// `kira-bm.json`.
     pub fn resolve(path: Option<&str>) -> Result<ResolvedConfig, KiraError> {
         let config_path = match path {
             Some(path) => PathBuf::from(path),
-            None => PathBuf::from("kira-bm.json"),
+            None => Project::current()?.config_path(),
         };
 
         if path.is_none() && !config_path.exists() {
             return Err(KiraError::MissingConfig);
         }
 
-        let content = fs::read_to_string(&config_path)
-            .map_err(|_| KiraError::ConfigRead(config_path.clone()))?;
-        let config: Config = serde_json::from_str(&content)
-            .map_err(|err| KiraError::ConfigParse(err.to_string()))?;
+        Self::validate(&config_path)
+    }
+
+    /// Loads and resolves the file, reporting every malformed, invalid or
+    /// duplicated entry at once rather than stopping at the first. The
+    /// user config's `defaults` fill in what the file leaves open.
+    pub fn validate(path: &Path) -> Result<ResolvedConfig, KiraError> {
+        let config = Self::load(path)?;
+        let errors = config.issues();
+        if !errors.is_empty() {
+            return Err(KiraError::InvalidConfig {
+                path: path.to_path_buf(),
+                errors,
+            });
+        }
+        let user = GlobalConfig::load()?;
+        let mut resolved = Self::resolve_layered(config, user.defaults.as_ref())?;
+        resolved.sha256 = Some(sha256_file(path)?);
+        Ok(resolved)
+    }
+
+    /// Reads the file as written, without resolving defaults. The file may
+    /// be gzip-compressed. Entries of the wrong shape are reported by
+    /// section and index.
+    pub fn load(path: &Path) -> Result<Config, KiraError> {
+        let value = Self::read_value(path)?;
+        let errors = entry_shape_issues(&value);
+        if !errors.is_empty() {
+            return Err(KiraError::InvalidConfig {
+                path: path.to_path_buf(),
+                errors,
+            });
+        }
+        serde_json::from_value(value).map_err(|err| KiraError::ConfigParse(err.to_string()))
+    }
 
-        Self::resolve_config(config)
+    fn read_value(path: &Path) -> Result<Value, KiraError> {
+        io_util::read_json(path).map_err(|err| match err {
+            InputError::Read(_) => KiraError::ConfigRead(path.to_path_buf()),
+            InputError::CorruptGzip(message) => KiraError::CorruptGzip {
+                path: path.to_path_buf(),
+                message,
+            },
+            InputError::Malformed { message, gzip } if gzip => KiraError::ConfigParse(format!(
+                "{message} (in {} after gzip decompression)",
+                path.display()
+            )),
+            InputError::Malformed { message, .. } => KiraError::ConfigParse(message),
+        })
     }
 
     pub fn resolve_config(config: Config) -> Result<ResolvedConfig, KiraError> {
+        Self::resolve_layered(config, None)
+    }
+
+    /// Resolves `config` over the built-in defaults and the user's
+    /// `defaults`, in that order.
+    pub fn resolve_layered(
+        config: Config,
+        user: Option<&DefaultsConfig>,
+    ) -> Result<ResolvedConfig, KiraError> {
+        config.check_aliases()?;
+        let defaults = FetchDefaults::layered(user, Some(&config.project_defaults()));
         let schema_version = config.schema_version.unwrap_or(1);
+        let retention = config
+            .retention
+            .as_ref()
+            .map(RetentionPolicy::from_config)
+            .transpose()?
+            .unwrap_or_default();
+        let go = config.go.clone().unwrap_or_default();
+        let runs_kept = config
+            .runs
+            .as_ref()
+            .and_then(|runs| runs.keep)
+            .unwrap_or(DEFAULT_RUNS_KEPT);
+        let (timeouts, registry_timeouts) = resolve_timeouts(config.timeouts.as_ref())?;
+        let cache = config
+            .cache
+            .as_ref()
+            .map(CachePolicy::from_config)
+            .transpose()?
+            .unwrap_or_default();
+        let go_annotations = go
+            .annotations
+            .iter()
+            .map(|species| species.parse())
+            .collect::<Result<Vec<GoaSpecies>, KiraError>>()?;
+        let excluded = |kind: &str, id: &str| {
+            let spec = format!("{kind}:{id}");
+            config
+                .exclude
+                .iter()
+                .any(|entry| entry.eq_ignore_ascii_case(&spec))
+        };
 
         let proteins = config
             .proteins
@@ -158,14 +542,19 @@ impl ConfigLoader {
             .map(|entry| match entry {
                 ProteinEntry::Shorthand(value) => Ok(ProteinRequest {
                     id: value.parse()?,
-                    format: ProteinFormat::Cif,
+                    format: defaults.protein_format.value,
+                    alias: None,
                 }),
                 ProteinEntry::Detailed(obj) => Ok(ProteinRequest {
                     id: obj.id.parse()?,
-                    format: obj.format.unwrap_or(ProteinFormat::Cif),
+                    format: obj.format.unwrap_or(defaults.protein_format.value),
+                    alias: parse_alias(obj.alias)?,
                 }),
             })
-            .collect::<Result<Vec<_>, KiraError>>()?;
+            .collect::<Result<Vec<_>, KiraError>>()?
+            .into_iter()
+            .filter(|entry| !excluded("protein", entry.id.as_str()))
+            .collect::<Vec<_>>();
 
         let genomes = config
             .genomes
@@ -173,14 +562,21 @@ impl ConfigLoader {
             .map(|entry| match entry {
                 GenomeEntry::Shorthand(value) => Ok(GenomeRequest {
                     accession: value.parse()?,
-                    include: default_genome_include(),
+                    include: defaults.genome_include.value.clone(),
+                    alias: None,
                 }),
                 GenomeEntry::Detailed(obj) => Ok(GenomeRequest {
                     accession: obj.accession.parse()?,
-                    include: obj.include.unwrap_or_else(default_genome_include),
+                    include: obj
+                        .include
+                        .unwrap_or_else(|| defaults.genome_include.value.clone()),
+                    alias: parse_alias(obj.alias)?,
                 }),
             })
-            .collect::<Result<Vec<_>, KiraError>>()?;
+            .collect::<Result<Vec<_>, KiraError>>()?
+            .into_iter()
+            .filter(|entry| !excluded("genome", entry.accession.as_str()))
+            .collect::<Vec<_>>();
 
         let srr = config
             .srr
@@ -188,38 +584,107 @@ impl ConfigLoader {
             .map(|entry| match entry {
                 SrrEntry::Shorthand(value) => Ok(SrrRequest {
                     id: value.parse()?,
-                    format: SrrFormat::Fastq,
-                    paired: false,
-                }),
-                SrrEntry::Detailed(obj) => Ok(SrrRequest {
-                    id: obj.id.parse()?,
-                    format: obj.format.unwrap_or(SrrFormat::Fastq),
-                    paired: obj.paired.unwrap_or(false),
+                    format: defaults.srr_format.value,
+                    paired: defaults.srr_paired.value,
+                    threads: None,
+                    temp_dir: None,
+                    alias: None,
                 }),
+                SrrEntry::Detailed(obj) => {
+                    if obj.threads == Some(0) {
+                        return Err(KiraError::ConfigParse(format!(
+                            "srr {}: threads must be at least 1",
+                            obj.id
+                        )));
+                    }
+                    Ok(SrrRequest {
+                        id: obj.id.parse()?,
+                        format: obj.format.unwrap_or(defaults.srr_format.value),
+                        paired: obj.paired.unwrap_or(defaults.srr_paired.value),
+                        threads: obj.threads,
+                        temp_dir: obj.temp_dir,
+                        alias: parse_alias(obj.alias)?,
+                    })
+                }
             })
-            .collect::<Result<Vec<_>, KiraError>>()?;
+            .collect::<Result<Vec<_>, KiraError>>()?
+            .into_iter()
+            .filter(|entry| !excluded("srr", entry.id.as_str()))
+            .collect::<Vec<_>>();
 
         let uniprot = config
             .uniprot
             .into_iter()
             .map(|entry| match entry {
-                UniprotEntry::Shorthand(value) => Ok(UniprotRequest { id: value.parse()? }),
+                UniprotEntry::Shorthand(value) => Ok(UniprotRequest {
+                    id: value.parse()?,
+                    with_structures: false,
+                    alias: None,
+                }),
                 UniprotEntry::Detailed(obj) => Ok(UniprotRequest {
                     id: obj.id.parse()?,
+                    with_structures: obj.with_structures.unwrap_or(false),
+                    alias: parse_alias(obj.alias)?,
                 }),
             })
-            .collect::<Result<Vec<_>, KiraError>>()?;
+            .collect::<Result<Vec<_>, KiraError>>()?
+            .into_iter()
+            .filter(|entry| !excluded("uniprot", entry.id.as_str()))
+            .collect::<Vec<_>>();
 
         let doi = config
             .doi
             .into_iter()
             .map(|entry| match entry {
-                DoiEntry::Shorthand(value) => Ok(DoiRequest { id: value.parse()? }),
+                DoiEntry::Shorthand(value) => Ok(DoiRequest {
+                    id: value.parse()?,
+                    alias: None,
+                }),
                 DoiEntry::Detailed(obj) => Ok(DoiRequest {
                     id: obj.id.parse()?,
+                    alias: parse_alias(obj.alias)?,
+                }),
+            })
+            .collect::<Result<Vec<_>, KiraError>>()?
+            .into_iter()
+            .filter(|entry| !excluded("doi", entry.id.as_str()))
+            .collect::<Vec<_>>();
+
+        let bioprojects = config
+            .bioprojects
+            .into_iter()
+            .map(|entry| match entry {
+                BioProjectEntry::Shorthand(value) => Ok(BioProjectRequest {
+                    accession: value.parse()?,
+                    alias: None,
+                }),
+                BioProjectEntry::Detailed(obj) => Ok(BioProjectRequest {
+                    accession: obj.accession.parse()?,
+                    alias: parse_alias(obj.alias)?,
+                }),
+            })
+            .collect::<Result<Vec<_>, KiraError>>()?
+            .into_iter()
+            .filter(|entry| !excluded("bioproject", entry.accession.as_str()))
+            .collect::<Vec<_>>();
+
+        let proteomes = config
+            .proteomes
+            .into_iter()
+            .map(|entry| match entry {
+                ProteomeEntry::Shorthand(value) => Ok(ProteomeRequest {
+                    id: value.parse()?,
+                    alias: None,
+                }),
+                ProteomeEntry::Detailed(obj) => Ok(ProteomeRequest {
+                    id: obj.id.parse()?,
+                    alias: parse_alias(obj.alias)?,
                 }),
             })
-            .collect::<Result<Vec<_>, KiraError>>()?;
+            .collect::<Result<Vec<_>, KiraError>>()?
+            .into_iter()
+            .filter(|entry| !excluded("uniprot-proteome", entry.id.as_str()))
+            .collect::<Vec<_>>();
 
         Ok(ResolvedConfig {
             schema_version,
@@ -228,8 +693,373 @@ impl ConfigLoader {
             srr,
             uniprot,
             doi,
+            bioprojects,
+            proteomes,
+            include_categories: defaults.include_categories.value.clone(),
+            ncbi_api_key: config.ncbi_api_key,
+            proxy: config.proxy,
+            ca_bundle: config.ca_bundle.map(PathBuf::from),
+            retention,
+            go_flavor: go.flavor,
+            go_annotations,
+            runs_kept,
+            timeouts,
+            registry_timeouts,
+            cache,
+            defaults,
+            sha256: None,
+            write_readme: config.write_readme.unwrap_or(false),
         })
     }
+
+    /// Every fetch default and cache policy key in effect for a project,
+    /// with the layer that set it.
+    pub fn effective_settings(user: &GlobalConfig, project: Option<&Config>) -> ConfigSettings {
+        let project_defaults = project.map(Config::project_defaults);
+        let defaults = FetchDefaults::layered(user.defaults.as_ref(), project_defaults.as_ref());
+        let mut settings = defaults.settings();
+        let mut cache = BTreeMap::new();
+        for (config, origin) in [
+            (user.cache.as_ref(), SettingOrigin::User),
+            (
+                project.and_then(|project| project.cache.as_ref()),
+                SettingOrigin::Project,
+            ),
+        ] {
+            for (key, cached) in config.into_iter().flat_map(|config| &config.types) {
+                cache.insert(key.to_ascii_lowercase(), (*cached, origin));
+            }
+        }
+        settings.extend(
+            cache
+                .into_iter()
+                .map(|(key, (cached, origin))| EffectiveSetting {
+                    key: format!("cache.{key}"),
+                    value: Value::Bool(cached),
+                    origin,
+                }),
+        );
+        ConfigSettings { settings }
+    }
+}
+
+/// `section[index]: problem` for every entry that is neither an id string
+/// nor an object of its section's fields. Checked on the raw JSON because
+/// the untagged entry enums would only say that no variant matched.
+fn entry_shape_issues(value: &Value) -> Vec<String> {
+    let Some(root) = value.as_object() else {
+        return vec!["expected a JSON object at the top level".to_string()];
+    };
+    let mut issues = Vec::new();
+    for section in ConfigSection::ALL {
+        let key = section.key();
+        let Some(entries) = root.get(key) else {
+            continue;
+        };
+        let Some(entries) = entries.as_array() else {
+            issues.push(format!("{key}: expected an array of entries"));
+            continue;
+        };
+        for (index, entry) in entries.iter().enumerate() {
+            let checked = match entry {
+                Value::String(_) => Ok(()),
+                Value::Object(_) => match section {
+                    ConfigSection::Proteins => check_object::<ProteinEntryObject>(entry),
+                    ConfigSection::Genomes => check_object::<GenomeEntryObject>(entry),
+                    ConfigSection::Srr => check_object::<SrrEntryObject>(entry),
+                    ConfigSection::Uniprot => check_object::<UniprotEntryObject>(entry),
+                    ConfigSection::Doi => check_object::<DoiEntryObject>(entry),
+                    ConfigSection::BioProjects => check_object::<BioProjectEntryObject>(entry),
+                    ConfigSection::Proteomes => check_object::<ProteomeEntryObject>(entry),
+                },
+                _ => Err("expected an id string or an object".to_string()),
+            };
+            if let Err(message) = checked {
+                issues.push(format!("{key}[{index}]: {message}"));
+            }
+        }
+    }
+    if root
+        .get("retention")
+        .and_then(|retention| retention.get("pin"))
+        .is_some()
+    {
+        issues.push(
+            "retention.pin: no longer read; pin those datasets with `kira-bm pin <SPECIFIER>`"
+                .to_string(),
+        );
+    }
+    issues
+}
+
+fn check_object<T: DeserializeOwned>(entry: &Value) -> Result<(), String> {
+    T::deserialize(entry)
+        .map(drop)
+        .map_err(|err| err.to_string())
+}
+
+fn resolve_timeouts(
+    config: Option<&TimeoutsConfig>,
+) -> Result<(TimeoutOverrides, BTreeMap<String, TimeoutOverrides>), KiraError> {
+    let Some(config) = config else {
+        return Ok(Default::default());
+    };
+    let check = |key: &str, overrides: &TimeoutOverrides| {
+        let fields = [
+            ("connect_secs", overrides.connect_secs),
+            ("request_secs", overrides.request_secs),
+            ("stall_secs", overrides.stall_secs),
+        ];
+        match fields.iter().find(|(_, secs)| *secs == Some(0)) {
+            Some((field, _)) => Err(KiraError::ConfigParse(format!(
+                "timeouts.{key}{field}: must be at least 1 second"
+            ))),
+            None => Ok(()),
+        }
+    };
+    let global = config.global();
+    check("", &global)?;
+    let mut registries = BTreeMap::new();
+    for (key, overrides) in &config.registries {
+        let key = key.to_ascii_lowercase();
+        if !TIMEOUT_REGISTRIES.contains(&key.as_str()) {
+            return Err(KiraError::ConfigParse(format!(
+                "timeouts.{key}: unknown registry (expected one of {})",
+                TIMEOUT_REGISTRIES.join(", ")
+            )));
+        }
+        check(&format!("{key}."), overrides)?;
+        registries.insert(key, *overrides);
+    }
+    Ok((global, registries))
+}
+
+/// Policy key for dataset types without a key of their own.
+const CACHE_DEFAULT_KEY: &str = "default";
+
+/// Which dataset types fetches copy into the shared cache. Types the
+/// policy does not mention are cached.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CachePolicy {
+    types: BTreeMap<String, bool>,
+}
+
+impl CachePolicy {
+    pub fn from_config(config: &CacheConfig) -> Result<Self, KiraError> {
+        let mut types = BTreeMap::new();
+        for (key, cached) in &config.types {
+            let key = key.to_ascii_lowercase();
+            if key != CACHE_DEFAULT_KEY && key != KB_KEY && !DATASET_TYPES.contains(&key.as_str()) {
+                return Err(KiraError::ConfigParse(format!(
+                    "cache.{key}: unknown dataset type"
+                )));
+            }
+            types.insert(key, *cached);
+        }
+        Ok(Self { types })
+    }
+
+    /// This policy with `other`'s keys taking precedence.
+    pub fn overlay(mut self, other: &CachePolicy) -> Self {
+        self.types.extend(
+            other
+                .types
+                .iter()
+                .map(|(key, cached)| (key.clone(), *cached)),
+        );
+        self
+    }
+
+    /// The setting that decides `dataset_type`, if any key does.
+    fn rule(&self, dataset_type: &str) -> Option<bool> {
+        self.types
+            .get(dataset_type)
+            .or_else(|| {
+                KB_TYPES
+                    .contains(&dataset_type)
+                    .then(|| self.types.get(KB_KEY))
+                    .flatten()
+            })
+            .or_else(|| self.types.get(CACHE_DEFAULT_KEY))
+            .copied()
+    }
+
+    pub fn caches(&self, dataset_type: &str) -> bool {
+        self.rule(dataset_type).unwrap_or(true)
+    }
+
+    /// Whether a fetch of `dataset_type` copies into the cache; `no_cache`
+    /// (`--no-cache`) overrides the policy.
+    pub fn decision(&self, dataset_type: &str, no_cache: bool) -> CacheDecision {
+        match self.rule(dataset_type) {
+            _ if no_cache => CacheDecision {
+                enabled: false,
+                source: CacheSource::Flag,
+            },
+            Some(enabled) => CacheDecision {
+                enabled,
+                source: CacheSource::Config,
+            },
+            None => CacheDecision {
+                enabled: true,
+                source: CacheSource::Default,
+            },
+        }
+    }
+}
+
+/// Whether a fetch copied a dataset into the shared cache, and what
+/// decided it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CacheDecision {
+    pub enabled: bool,
+    pub source: CacheSource,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum CacheSource {
+    /// No key of the `cache` policy covers the type.
+    Default,
+    /// The `cache` section of `kira-bm.json` or the user config.
+    Config,
+    /// `--no-cache`.
+    Flag,
+}
+
+/// The layer an effective setting came from, lowest first; each layer
+/// overrides the ones before it.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum SettingOrigin {
+    /// Built into kira-bm.
+    Default,
+    /// The user config, `~/.config/kira-biodata-manager/config.json`.
+    User,
+    /// The project's `kira-bm.json`.
+    Project,
+    /// The command line.
+    Flag,
+}
+
+/// A setting's value and the layer that set it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting<T> {
+    pub value: T,
+    pub origin: SettingOrigin,
+}
+
+impl<T> Setting<T> {
+    fn builtin(value: T) -> Self {
+        Self {
+            value,
+            origin: SettingOrigin::Default,
+        }
+    }
+
+    /// `value`, set by `origin`, when there is one; this setting otherwise.
+    pub fn over(self, value: Option<T>, origin: SettingOrigin) -> Self {
+        match value {
+            Some(value) => Self { value, origin },
+            None => self,
+        }
+    }
+
+    /// The value, unless it is only the built-in default.
+    pub fn configured(&self) -> Option<&T> {
+        (self.origin != SettingOrigin::Default).then_some(&self.value)
+    }
+}
+
+/// The fetch options `defaults` sections can set, resolved through the
+/// layers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchDefaults {
+    pub protein_format: Setting<ProteinFormat>,
+    pub srr_format: Setting<SrrFormat>,
+    pub srr_paired: Setting<bool>,
+    pub genome_include: Setting<Vec<String>>,
+    pub include_categories: Setting<Vec<FileCategory>>,
+}
+
+impl Default for FetchDefaults {
+    fn default() -> Self {
+        Self {
+            protein_format: Setting::builtin(ProteinFormat::Cif),
+            srr_format: Setting::builtin(SrrFormat::Fastq),
+            srr_paired: Setting::builtin(false),
+            genome_include: Setting::builtin(default_genome_include()),
+            include_categories: Setting::builtin(Vec::new()),
+        }
+    }
+}
+
+impl FetchDefaults {
+    /// The built-in defaults, then the user's, then the project's.
+    pub fn layered(user: Option<&DefaultsConfig>, project: Option<&DefaultsConfig>) -> Self {
+        [
+            (user, SettingOrigin::User),
+            (project, SettingOrigin::Project),
+        ]
+        .into_iter()
+        .filter_map(|(config, origin)| config.map(|config| (config, origin)))
+        .fold(Self::default(), |defaults, (config, origin)| {
+            defaults.overlay(config, origin)
+        })
+    }
+
+    /// These defaults with what `config` sets taking precedence.
+    pub fn overlay(self, config: &DefaultsConfig, origin: SettingOrigin) -> Self {
+        Self {
+            protein_format: self.protein_format.over(config.protein_format, origin),
+            srr_format: self.srr_format.over(config.srr_format, origin),
+            srr_paired: self.srr_paired.over(config.srr_paired, origin),
+            genome_include: self
+                .genome_include
+                .over(config.genome_include.clone(), origin),
+            include_categories: self
+                .include_categories
+                .over(config.include_categories.clone(), origin),
+        }
+    }
+
+    /// Each default as `config show` lists it, under its `defaults` key.
+    pub fn settings(&self) -> Vec<EffectiveSetting> {
+        fn entry<T: Serialize>(key: &str, setting: &Setting<T>) -> EffectiveSetting {
+            EffectiveSetting {
+                key: format!("defaults.{key}"),
+                value: serde_json::to_value(&setting.value).unwrap_or(Value::Null),
+                origin: setting.origin,
+            }
+        }
+        vec![
+            entry("protein_format", &self.protein_format),
+            entry("srr_format", &self.srr_format),
+            entry("srr_paired", &self.srr_paired),
+            entry("genome_include", &self.genome_include),
+            entry("include_categories", &self.include_categories),
+        ]
+    }
+}
+
+/// One setting in effect, as printed by `config show`.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct EffectiveSetting {
+    pub key: String,
+    pub value: Value,
+    pub origin: SettingOrigin,
+}
+
+/// What `config show` prints.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ConfigSettings {
+    pub settings: Vec<EffectiveSetting>,
+}
+
+fn parse_alias(alias: Option<String>) -> Result<Option<DatasetAlias>, KiraError> {
+    alias.map(|alias| alias.parse()).transpose()
 }
 
 pub fn default_genome_include() -> Vec<String> {
@@ -240,3 +1070,622 @@ pub fn default_genome_include() -> Vec<String> {
         "seq-report".to_string(),
     ]
 }
+
+/// One dataset named in a `--from-file` ID list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdListEntry {
+    /// 1-based line number in the file.
+    pub line: usize,
+    pub specifier: DatasetSpecifier,
+}
+
+/// Reads a newline-delimited ID list. Blank lines and `#` comments are
+/// skipped; every other line is a specifier or a bare identifier whose type
+/// is inferred. All unparseable lines are reported together, by line number.
+pub fn read_id_list(path: &Path) -> Result<Vec<IdListEntry>, KiraError> {
+    let text = fs::read_to_string(path).map_err(|_| KiraError::IdListRead(path.to_path_buf()))?;
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let value = line.trim();
+        if value.is_empty() || value.starts_with('#') {
+            continue;
+        }
+        match DatasetSpecifier::infer(value) {
+            Ok(specifier) => entries.push(IdListEntry {
+                line: index + 1,
+                specifier,
+            }),
+            Err(err) => errors.push(format!("line {}: {err}", index + 1)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(KiraError::InvalidIdList {
+            path: path.to_path_buf(),
+            errors,
+        });
+    }
+    Ok(entries)
+}
+
+/// Writes `config` over the file at `path`, merged into what the file
+/// already holds: its keys keep their order, new ones go after them, and a
+/// gzip-compressed file stays gzip.
+pub fn write_config_atomic(path: &Path, config: &Config) -> Result<(), KiraError> {
+    let existing = existing_config_value(path);
+    let mut payload = config_json(existing.clone(), config)?;
+    if existing.is_some() && is_gzip_file(path) {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(&payload)
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        payload = encoder
+            .finish()
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, &payload).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    fs::rename(&tmp, path).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    Ok(())
+}
+
+/// `config` as JSON merged into `existing`, the file it is written over.
+/// Empty sections and unset values are left out, except those the file
+/// already spells out, so a rewrite keeps the file's shape.
+fn config_json(existing: Option<Value>, config: &Config) -> Result<Vec<u8>, KiraError> {
+    let current =
+        serde_json::to_value(config).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    let value = match existing {
+        Some(mut previous) => {
+            merge_value(&mut previous, current);
+            previous
+        }
+        None => current,
+    };
+    let mut bytes =
+        serde_json::to_vec_pretty(&value).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    bytes.push(b'\n');
+    Ok(bytes)
+}
+
+/// Replaces `previous` with `current` in place: an object keeps the keys it
+/// shares with `current` where they are, drops those `current` no longer
+/// has unless they are empty, and gains the new ones at the end.
+fn merge_value(previous: &mut Value, current: Value) {
+    let (old, mut new) = match (previous, current) {
+        (Value::Object(old), Value::Object(new)) => (old, new),
+        (previous, current) => {
+            *previous = current;
+            return;
+        }
+    };
+    old.retain(|key, value| new.contains_key(key) || is_empty_value(value));
+    for (key, value) in old.iter_mut() {
+        if let Some(updated) = new.remove(key) {
+            merge_value(value, updated);
+        }
+    }
+    old.extend(new);
+}
+
+fn existing_config_value(path: &Path) -> Option<Value> {
+    path.exists()
+        .then(|| ConfigLoader::read_value(path).ok())
+        .flatten()
+}
+
+fn is_gzip_file(path: &Path) -> bool {
+    io_util::open_maybe_gzip(path).is_ok_and(|reader| reader.is_gzip())
+}
+
+fn is_empty_value(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Array(items) => items.is_empty(),
+        Value::Object(fields) => fields.is_empty(),
+        _ => false,
+    }
+}
+
+/// Line diff from the file at `path` (nothing when there is none) to
+/// `after` as [`write_config_atomic`] would write it: removed lines start
+/// with `- `, added ones with `+ `, and up to two unchanged lines around
+/// each change with two spaces. Empty when nothing changes.
+pub fn config_diff(path: &Path, after: &Config) -> Result<String, KiraError> {
+    let existing = existing_config_value(path);
+    let before = match &existing {
+        Some(value) => serde_json::to_string_pretty(value)
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?,
+        None => String::new(),
+    };
+    let after = String::from_utf8_lossy(&config_json(existing, after)?).into_owned();
+    Ok(line_diff(&before, &after))
+}
+
+const DIFF_CONTEXT: usize = 2;
+
+fn line_diff(before: &str, after: &str) -> String {
+    let old = before.lines().collect::<Vec<_>>();
+    let new = after.lines().collect::<Vec<_>>();
+    // common[i][j]: length of the longest common subsequence of old[i..]
+    // and new[j..].
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(' ');
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || common[i][j + 1] >= common[i + 1][j]) {
+            lines.push('+');
+            j += 1;
+        } else {
+            lines.push('-');
+            i += 1;
+        }
+    }
+    // Replay the edit script, printing lines near a change.
+    let near_change = |index: usize| {
+        let from = index.saturating_sub(DIFF_CONTEXT);
+        let to = (index + DIFF_CONTEXT + 1).min(lines.len());
+        lines[from..to].iter().any(|op| *op != ' ')
+    };
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    let mut skipped = false;
+    for (index, op) in lines.iter().enumerate() {
+        let text = match op {
+            '+' => new[j],
+            _ => old[i],
+        };
+        if near_change(index) {
+            if skipped && !out.is_empty() {
+                out.push_str("  ...\n");
+            }
+            skipped = false;
+            out.push_str(&format!("{op} {text}\n"));
+        } else {
+            skipped = true;
+        }
+        match op {
+            '+' => j += 1,
+            '-' => i += 1,
+            _ => {
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSection {
+    Proteins,
+    Genomes,
+    Srr,
+    Uniprot,
+    Doi,
+    BioProjects,
+    Proteomes,
+}
+
+impl ConfigSection {
+    pub const ALL: [ConfigSection; 7] = [
+        ConfigSection::Proteins,
+        ConfigSection::Genomes,
+        ConfigSection::Srr,
+        ConfigSection::Uniprot,
+        ConfigSection::Doi,
+        ConfigSection::BioProjects,
+        ConfigSection::Proteomes,
+    ];
+
+    /// JSON key of the section in `kira-bm.json`.
+    pub fn key(self) -> &'static str {
+        match self {
+            ConfigSection::Proteins => "proteins",
+            ConfigSection::Genomes => "genomes",
+            ConfigSection::Srr => "srr",
+            ConfigSection::Uniprot => "uniprot",
+            ConfigSection::Doi => "doi",
+            ConfigSection::BioProjects => "bioprojects",
+            ConfigSection::Proteomes => "proteomes",
+        }
+    }
+
+    /// What an id of the section is called in messages.
+    pub fn id_label(self) -> &'static str {
+        match self {
+            ConfigSection::Proteins => "PDB id",
+            ConfigSection::Genomes => "genome accession",
+            ConfigSection::Srr => "SRR accession",
+            ConfigSection::Uniprot => "UniProt accession",
+            ConfigSection::Doi => "DOI",
+            ConfigSection::BioProjects => "BioProject accession",
+            ConfigSection::Proteomes => "UniProt proteome id",
+        }
+    }
+
+    /// The id in canonical form, or the error its `FromStr` gives.
+    fn canonical_id(self, id: &str) -> Result<String, KiraError> {
+        Ok(match self {
+            ConfigSection::Proteins => id.parse::<ProteinId>()?.as_str().to_string(),
+            ConfigSection::Genomes => id.parse::<GenomeAccession>()?.as_str().to_string(),
+            ConfigSection::Srr => id.parse::<SrrId>()?.as_str().to_string(),
+            ConfigSection::Uniprot => id.parse::<UniprotId>()?.as_str().to_string(),
+            ConfigSection::Doi => id.parse::<Doi>()?.as_str().to_string(),
+            ConfigSection::BioProjects => id.parse::<BioProjectAccession>()?.as_str().to_string(),
+            ConfigSection::Proteomes => id.parse::<ProteomeId>()?.as_str().to_string(),
+        })
+    }
+
+    /// Specifier prefix of the section's entries.
+    pub fn kind(self) -> &'static str {
+        match self {
+            ConfigSection::Proteins => "protein",
+            ConfigSection::Genomes => "genome",
+            ConfigSection::Srr => "srr",
+            ConfigSection::Uniprot => "uniprot",
+            ConfigSection::Doi => "doi",
+            ConfigSection::BioProjects => "bioproject",
+            ConfigSection::Proteomes => "uniprot-proteome",
+        }
+    }
+}
+
+impl Config {
+    /// Raw ids of a section's entries, in file order.
+    /// The project's `defaults`, with the top-level `include_categories`
+    /// of older files standing in when `defaults` does not set it.
+    pub fn project_defaults(&self) -> DefaultsConfig {
+        let mut defaults = self.defaults.clone().unwrap_or_default();
+        if defaults.include_categories.is_none() && !self.include_categories.is_empty() {
+            defaults.include_categories = Some(self.include_categories.clone());
+        }
+        defaults
+    }
+
+    pub fn entry_ids(&self, section: ConfigSection) -> Vec<String> {
+        match section {
+            ConfigSection::Proteins => self
+                .proteins
+                .iter()
+                .map(|entry| match entry {
+                    ProteinEntry::Shorthand(id) => id.clone(),
+                    ProteinEntry::Detailed(obj) => obj.id.clone(),
+                })
+                .collect(),
+            ConfigSection::Genomes => self
+                .genomes
+                .iter()
+                .map(|entry| match entry {
+                    GenomeEntry::Shorthand(id) => id.clone(),
+                    GenomeEntry::Detailed(obj) => obj.accession.clone(),
+                })
+                .collect(),
+            ConfigSection::Srr => self
+                .srr
+                .iter()
+                .map(|entry| match entry {
+                    SrrEntry::Shorthand(id) => id.clone(),
+                    SrrEntry::Detailed(obj) => obj.id.clone(),
+                })
+                .collect(),
+            ConfigSection::Uniprot => self
+                .uniprot
+                .iter()
+                .map(|entry| match entry {
+                    UniprotEntry::Shorthand(id) => id.clone(),
+                    UniprotEntry::Detailed(obj) => obj.id.clone(),
+                })
+                .collect(),
+            ConfigSection::Doi => self
+                .doi
+                .iter()
+                .map(|entry| match entry {
+                    DoiEntry::Shorthand(id) => id.clone(),
+                    DoiEntry::Detailed(obj) => obj.id.clone(),
+                })
+                .collect(),
+            ConfigSection::BioProjects => self
+                .bioprojects
+                .iter()
+                .map(|entry| match entry {
+                    BioProjectEntry::Shorthand(id) => id.clone(),
+                    BioProjectEntry::Detailed(obj) => obj.accession.clone(),
+                })
+                .collect(),
+            ConfigSection::Proteomes => self
+                .proteomes
+                .iter()
+                .map(|entry| match entry {
+                    ProteomeEntry::Shorthand(id) => id.clone(),
+                    ProteomeEntry::Detailed(obj) => obj.id.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Ids that do not parse and ids listed twice in a section, as
+    /// `section[index]: problem`, so a fetch does not find them midway.
+    pub fn issues(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        for section in ConfigSection::ALL {
+            let key = section.key();
+            let mut first_seen = BTreeMap::<String, usize>::new();
+            for (index, id) in self.entry_ids(section).into_iter().enumerate() {
+                let Ok(canonical) = section.canonical_id(&id) else {
+                    issues.push(format!(
+                        "{key}[{index}]: '{id}' is not a valid {}",
+                        section.id_label()
+                    ));
+                    continue;
+                };
+                match first_seen.entry(canonical) {
+                    Entry::Occupied(first) => issues.push(format!(
+                        "{key}[{index}]: '{id}' duplicates {key}[{}]",
+                        first.get()
+                    )),
+                    Entry::Vacant(slot) => {
+                        slot.insert(index);
+                    }
+                }
+            }
+        }
+        issues
+    }
+
+    /// `(type:id, alias)` of every entry that has an alias, in file order.
+    pub fn aliases(&self) -> Vec<(String, String)> {
+        let mut aliases = Vec::new();
+        let mut add = |kind: &str, id: &str, alias: &Option<String>| {
+            if let Some(alias) = alias {
+                aliases.push((format!("{kind}:{id}"), alias.clone()));
+            }
+        };
+        for entry in &self.proteins {
+            if let ProteinEntry::Detailed(obj) = entry {
+                add("protein", &obj.id, &obj.alias);
+            }
+        }
+        for entry in &self.genomes {
+            if let GenomeEntry::Detailed(obj) = entry {
+                add("genome", &obj.accession, &obj.alias);
+            }
+        }
+        for entry in &self.srr {
+            if let SrrEntry::Detailed(obj) = entry {
+                add("srr", &obj.id, &obj.alias);
+            }
+        }
+        for entry in &self.uniprot {
+            if let UniprotEntry::Detailed(obj) = entry {
+                add("uniprot", &obj.id, &obj.alias);
+            }
+        }
+        for entry in &self.doi {
+            if let DoiEntry::Detailed(obj) = entry {
+                add("doi", &obj.id, &obj.alias);
+            }
+        }
+        for entry in &self.bioprojects {
+            if let BioProjectEntry::Detailed(obj) = entry {
+                add("bioproject", &obj.accession, &obj.alias);
+            }
+        }
+        for entry in &self.proteomes {
+            if let ProteomeEntry::Detailed(obj) = entry {
+                add("uniprot-proteome", &obj.id, &obj.alias);
+            }
+        }
+        aliases
+    }
+
+    /// Rejects aliases given to more than one entry, naming every entry
+    /// that shares one.
+    fn check_aliases(&self) -> Result<(), KiraError> {
+        let mut owners = BTreeMap::<String, Vec<String>>::new();
+        for (spec, alias) in self.aliases() {
+            owners.entry(alias).or_default().push(spec);
+        }
+        let duplicates = owners
+            .into_iter()
+            .filter(|(_, specs)| specs.len() > 1)
+            .map(|(alias, specs)| format!("{alias} ({})", specs.join(", ")))
+            .collect::<Vec<_>>();
+        if duplicates.is_empty() {
+            Ok(())
+        } else {
+            Err(KiraError::ConfigEntry(format!(
+                "aliases must be unique, duplicated: {}",
+                duplicates.join("; ")
+            )))
+        }
+    }
+
+    /// Adds a validated specifier as a shorthand entry of its section.
+    pub fn add_entry(&mut self, specifier: &DatasetSpecifier) -> Result<ConfigSection, KiraError> {
+        let (section, id) = match specifier {
+            DatasetSpecifier::Protein(id) => (ConfigSection::Proteins, id.as_str()),
+            DatasetSpecifier::Genome(acc) => (ConfigSection::Genomes, acc.as_str()),
+            DatasetSpecifier::Srr(id) => (ConfigSection::Srr, id.as_str()),
+            DatasetSpecifier::Uniprot(id) => (ConfigSection::Uniprot, id.as_str()),
+            DatasetSpecifier::Doi(doi) => (ConfigSection::Doi, doi.as_str()),
+            DatasetSpecifier::BioProject(acc) => (ConfigSection::BioProjects, acc.as_str()),
+            DatasetSpecifier::Proteome(id) => (ConfigSection::Proteomes, id.as_str()),
+            other => {
+                return Err(KiraError::ConfigEntry(format!(
+                    "{} datasets cannot be listed in kira-bm.json",
+                    other.dataset_type()
+                )));
+            }
+        };
+        if self
+            .entry_ids(section)
+            .iter()
+            .any(|existing| existing.eq_ignore_ascii_case(id))
+        {
+            return Err(KiraError::ConfigEntry(format!(
+                "{}:{id} is already listed",
+                section.kind()
+            )));
+        }
+        let id = id.to_string();
+        match section {
+            ConfigSection::Proteins => self.proteins.push(ProteinEntry::Shorthand(id)),
+            ConfigSection::Genomes => self.genomes.push(GenomeEntry::Shorthand(id)),
+            ConfigSection::Srr => self.srr.push(SrrEntry::Shorthand(id)),
+            ConfigSection::Uniprot => self.uniprot.push(UniprotEntry::Shorthand(id)),
+            ConfigSection::Doi => self.doi.push(DoiEntry::Shorthand(id)),
+            ConfigSection::BioProjects => self.bioprojects.push(BioProjectEntry::Shorthand(id)),
+            ConfigSection::Proteomes => self.proteomes.push(ProteomeEntry::Shorthand(id)),
+        }
+        Ok(section)
+    }
+
+    /// Appends the entries of `other` whose id this config does not list
+    /// yet, leaving existing entries as they are; returns the specifiers
+    /// added.
+    pub fn merge_entries(&mut self, other: Config) -> Vec<String> {
+        let mut added = Vec::new();
+        merge_section(
+            &mut self.proteins,
+            other.proteins,
+            ConfigSection::Proteins,
+            |entry| match entry {
+                ProteinEntry::Shorthand(id) => id,
+                ProteinEntry::Detailed(obj) => &obj.id,
+            },
+            &mut added,
+        );
+        merge_section(
+            &mut self.genomes,
+            other.genomes,
+            ConfigSection::Genomes,
+            |entry| match entry {
+                GenomeEntry::Shorthand(id) => id,
+                GenomeEntry::Detailed(obj) => &obj.accession,
+            },
+            &mut added,
+        );
+        merge_section(
+            &mut self.srr,
+            other.srr,
+            ConfigSection::Srr,
+            |entry| match entry {
+                SrrEntry::Shorthand(id) => id,
+                SrrEntry::Detailed(obj) => &obj.id,
+            },
+            &mut added,
+        );
+        merge_section(
+            &mut self.uniprot,
+            other.uniprot,
+            ConfigSection::Uniprot,
+            |entry| match entry {
+                UniprotEntry::Shorthand(id) => id,
+                UniprotEntry::Detailed(obj) => &obj.id,
+            },
+            &mut added,
+        );
+        merge_section(
+            &mut self.doi,
+            other.doi,
+            ConfigSection::Doi,
+            |entry| match entry {
+                DoiEntry::Shorthand(id) => id,
+                DoiEntry::Detailed(obj) => &obj.id,
+            },
+            &mut added,
+        );
+        merge_section(
+            &mut self.bioprojects,
+            other.bioprojects,
+            ConfigSection::BioProjects,
+            |entry| match entry {
+                BioProjectEntry::Shorthand(id) => id,
+                BioProjectEntry::Detailed(obj) => &obj.accession,
+            },
+            &mut added,
+        );
+        merge_section(
+            &mut self.proteomes,
+            other.proteomes,
+            ConfigSection::Proteomes,
+            |entry| match entry {
+                ProteomeEntry::Shorthand(id) => id,
+                ProteomeEntry::Detailed(obj) => &obj.id,
+            },
+            &mut added,
+        );
+        added
+    }
+
+    /// Removes an entry (and any exclusion for it); returns its specifier.
+    pub fn remove_entry(&mut self, section: ConfigSection, index: usize) -> Option<String> {
+        let id = self.entry_ids(section).get(index)?.clone();
+        match section {
+            ConfigSection::Proteins => drop(self.proteins.remove(index)),
+            ConfigSection::Genomes => drop(self.genomes.remove(index)),
+            ConfigSection::Srr => drop(self.srr.remove(index)),
+            ConfigSection::Uniprot => drop(self.uniprot.remove(index)),
+            ConfigSection::Doi => drop(self.doi.remove(index)),
+            ConfigSection::BioProjects => drop(self.bioprojects.remove(index)),
+            ConfigSection::Proteomes => drop(self.proteomes.remove(index)),
+        }
+        let spec = format!("{}:{id}", section.kind());
+        self.exclude
+            .retain(|entry| !entry.eq_ignore_ascii_case(&spec));
+        Some(spec)
+    }
+
+    pub fn is_excluded(&self, section: ConfigSection, id: &str) -> bool {
+        let spec = format!("{}:{id}", section.kind());
+        self.exclude
+            .iter()
+            .any(|entry| entry.eq_ignore_ascii_case(&spec))
+    }
+
+    /// Flips whether an entry takes part in config-driven fetches; returns
+    /// `true` when it is now excluded.
+    pub fn toggle_excluded(&mut self, section: ConfigSection, id: &str) -> bool {
+        let spec = format!("{}:{id}", section.kind());
+        if self.is_excluded(section, id) {
+            self.exclude
+                .retain(|entry| !entry.eq_ignore_ascii_case(&spec));
+            false
+        } else {
+            self.exclude.push(spec);
+            true
+        }
+    }
+}
+
+fn merge_section<E>(
+    ours: &mut Vec<E>,
+    theirs: Vec<E>,
+    section: ConfigSection,
+    id: impl Fn(&E) -> &str,
+    added: &mut Vec<String>,
+) {
+    for entry in theirs {
+        if !ours
+            .iter()
+            .any(|existing| id(existing).eq_ignore_ascii_case(id(&entry)))
+        {
+            added.push(format!("{}:{}", section.kind(), id(&entry)));
+            ours.push(entry);
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//! Cooperative pause/resume for long-running batches.
+//!
+//! A running batch owns `batch.json` in the project store and keeps its
+//! heartbeat fresh; `kira-bm batch pause|resume` write `control.json`
+//! addressed to that batch's id. Requests addressed to any other id (for
+//! example a batch that died while paused) are stale and ignored.
+
+use std::fs;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{ProgressEvent, ProgressLevel, ProgressSink, check_cancelled};
+use crate::error::KiraError;
+
+pub const CONTROL_FILE: &str = "control.json";
+pub const BATCH_FILE: &str = "batch.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchState {
+    Running,
+    Paused,
+}
+
+impl std::fmt::Display for BatchState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BatchState::Running => write!(f, "running"),
+            BatchState::Paused => write!(f, "paused"),
+        }
+    }
+}
+
+/// Contents of `control.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ControlRequest {
+    pub state: BatchState,
+    pub batch_id: String,
+    pub requested_at: u64,
+}
+
+/// Contents of `batch.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct BatchStatus {
+    pub id: String,
+    pub pid: u32,
+    pub started_at: u64,
+    pub heartbeat_at: u64,
+    pub state: BatchState,
+    pub completed: usize,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ControlReport {
+    pub batch: Option<BatchStatus>,
+    pub stale: bool,
+    pub request: Option<ControlRequest>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ControlSettings {
+    pub poll_interval: Duration,
+    pub stale_after: Duration,
+}
+
+impl Default for ControlSettings {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(1),
+            stale_after: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Reports the running batch (if any) and the pending control request.
+pub fn status(root: &Utf8Path, settings: ControlSettings) -> Result<ControlReport, KiraError> {
+    let batch = read_json::<BatchStatus>(&root.join(BATCH_FILE))?;
+    let stale = batch
+        .as_ref()
+        .is_some_and(|batch| is_stale(batch, settings.stale_after));
+    let request = read_json::<ControlRequest>(&root.join(CONTROL_FILE))?.filter(|request| {
+        batch
+            .as_ref()
+            .is_some_and(|batch| batch.id == request.batch_id)
+    });
+    Ok(ControlReport {
+        batch,
+        stale,
+        request,
+    })
+}
+
+/// Asks the running batch to pause or resume.
+pub fn request(
+    root: &Utf8Path,
+    state: BatchState,
+    settings: ControlSettings,
+) -> Result<ControlReport, KiraError> {
+    let report = status(root, settings)?;
+    let Some(batch) = report.batch.as_ref().filter(|_| !report.stale) else {
+        return Err(KiraError::NoActiveBatch);
+    };
+    let request = ControlRequest {
+        state,
+        batch_id: batch.id.clone(),
+        requested_at: unix_now(),
+    };
+    write_json(&root.join(CONTROL_FILE), &request)?;
+    Ok(ControlReport {
+        request: Some(request),
+        ..report
+    })
+}
+
+/// Registration of a running batch; dropped when the batch finishes.
+pub struct BatchControl {
+    root: Utf8PathBuf,
+    settings: ControlSettings,
+    pause_timeout: Option<Duration>,
+    status: Arc<Mutex<BatchStatus>>,
+    heartbeat: Option<(Sender<()>, JoinHandle<()>)>,
+}
+
+impl BatchControl {
+    pub fn start(
+        root: &Utf8Path,
+        settings: ControlSettings,
+        pause_timeout: Option<Duration>,
+    ) -> Result<Self, KiraError> {
+        fs::create_dir_all(root).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let now = unix_now();
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|elapsed| elapsed.subsec_nanos())
+            .unwrap_or(0);
+        let status = BatchStatus {
+            id: format!("{}-{now}-{nanos}", std::process::id()),
+            pid: std::process::id(),
+            started_at: now,
+            heartbeat_at: now,
+            state: BatchState::Running,
+            completed: 0,
+        };
+        write_json(&root.join(BATCH_FILE), &status)?;
+        // Anything left in control.json belongs to an earlier batch.
+        let _ = fs::remove_file(root.join(CONTROL_FILE));
+
+        let status = Arc::new(Mutex::new(status));
+        let (stop, ticks) = mpsc::channel::<()>();
+        let beat_root = root.to_path_buf();
+        let beat_status = status.clone();
+        let interval = settings.poll_interval;
+        let handle = std::thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = ticks.recv_timeout(interval) {
+                let mut status = beat_status
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                status.heartbeat_at = unix_now();
+                let _ = write_json(&beat_root.join(BATCH_FILE), &*status);
+            }
+        });
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            settings,
+            pause_timeout,
+            status,
+            heartbeat: Some((stop, handle)),
+        })
+    }
+
+    pub fn id(&self) -> String {
+        self.lock_status().id.clone()
+    }
+
+    /// Called between items: blocks while a pause request for this batch is
+    /// pending, up to the configured pause timeout or until cancelled.
+    pub fn checkpoint(&self, sink: &dyn ProgressSink) -> Result<(), KiraError> {
+        let id = self.id();
+        let mut paused_since: Option<Instant> = None;
+        loop {
+            check_cancelled(sink)?;
+            let paused = read_json::<ControlRequest>(&self.root.join(CONTROL_FILE))?.is_some_and(
+                |request| request.batch_id == id && request.state == BatchState::Paused,
+            );
+            if !paused {
+                if paused_since.is_some() {
+                    self.set_state(BatchState::Running)?;
+                    sink.event(ProgressEvent {
+                        message: "batch.resumed".to_string(),
+                        elapsed: None,
+                        level: ProgressLevel::Phase,
+                    });
+                }
+                return Ok(());
+            }
+            let since = match paused_since {
+                Some(since) => since,
+                None => {
+                    self.set_state(BatchState::Paused)?;
+                    sink.event(ProgressEvent {
+                        message: "batch.paused".to_string(),
+                        elapsed: None,
+                        level: ProgressLevel::Phase,
+                    });
+                    *paused_since.insert(Instant::now())
+                }
+            };
+            if let Some(timeout) = self.pause_timeout
+                && since.elapsed() >= timeout
+            {
+                sink.event(ProgressEvent {
+                    message: "batch.pause_timeout".to_string(),
+                    elapsed: Some(since.elapsed()),
+                    level: ProgressLevel::Phase,
+                });
+                return Err(KiraError::BatchPauseTimeout {
+                    waited_secs: timeout.as_secs(),
+                });
+            }
+            std::thread::sleep(self.settings.poll_interval);
+        }
+    }
+
+    /// Records a finished item in `batch.json`.
+    pub fn item_done(&self) -> Result<(), KiraError> {
+        let mut status = self.lock_status();
+        status.completed += 1;
+        status.heartbeat_at = unix_now();
+        write_json(&self.root.join(BATCH_FILE), &*status)
+    }
+
+    fn set_state(&self, state: BatchState) -> Result<(), KiraError> {
+        let mut status = self.lock_status();
+        status.state = state;
+        status.heartbeat_at = unix_now();
+        write_json(&self.root.join(BATCH_FILE), &*status)
+    }
+
+    fn lock_status(&self) -> std::sync::MutexGuard<'_, BatchStatus> {
+        self.status
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Drop for BatchControl {
+    fn drop(&mut self) {
+        if let Some((stop, handle)) = self.heartbeat.take() {
+            drop(stop);
+            let _ = handle.join();
+        }
+        let id = self.id();
+        let owned = |path: Utf8PathBuf, batch_id: Option<String>| {
+            if batch_id.as_deref() == Some(id.as_str()) {
+                let _ = fs::remove_file(path);
+            }
+        };
+        let batch_path = self.root.join(BATCH_FILE);
+        let batch_id = read_json::<BatchStatus>(&batch_path)
+            .ok()
+            .flatten()
+            .map(|batch| batch.id);
+        owned(batch_path, batch_id);
+        let control_path = self.root.join(CONTROL_FILE);
+        let control_id = read_json::<ControlRequest>(&control_path)
+            .ok()
+            .flatten()
+            .map(|request| request.batch_id);
+        owned(control_path, control_id);
+    }
+}
+
+fn is_stale(batch: &BatchStatus, stale_after: Duration) -> bool {
+    unix_now().saturating_sub(batch.heartbeat_at) > stale_after.as_secs()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Utf8Path) -> Result<Option<T>, KiraError> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(KiraError::Filesystem(err.to_string())),
+    };
+    // A half-written or foreign file is treated as absent rather than fatal.
+    Ok(serde_json::from_str(&text).ok())
+}
+
+fn write_json<T: Serialize>(path: &Utf8Path, value: &T) -> Result<(), KiraError> {
+    let bytes =
+        serde_json::to_vec_pretty(value).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, bytes).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    fs::rename(&tmp, path).map_err(|err| KiraError::Filesystem(err.to_string()))
+}
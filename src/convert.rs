@@ -0,0 +1,896 @@
+//! Offline conversion between stored protein structure formats.
+//!
+//! BinaryCIF is decoded to text mmCIF losslessly. mmCIF (or BinaryCIF) to
+//! PDB is best-effort: only CRYST1 and ATOM/HETATM records are written,
+//! atoms are renumbered from 1 and no TER, CONECT, SEQRES, HELIX/SHEET or
+//! REMARK records are produced. Structures that do not fit the fixed-width
+//! PDB columns (more than 99,999 atoms, several models, chain ids longer
+//! than one character, residue names longer than three) are rejected
+//! instead of being truncated.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use crate::domain::ProteinFormat;
+use crate::error::KiraError;
+
+/// Atom serial numbers have five columns in the PDB format.
+pub const PDB_MAX_ATOMS: usize = 99_999;
+
+/// Whether `from` can be converted to `to` offline.
+pub fn can_convert(from: ProteinFormat, to: ProteinFormat) -> bool {
+    matches!(
+        (from, to),
+        (ProteinFormat::Bcif, ProteinFormat::Cif)
+            | (ProteinFormat::Bcif, ProteinFormat::Pdb)
+            | (ProteinFormat::Cif, ProteinFormat::Pdb)
+    )
+}
+
+/// What a conversion to `to` leaves out, for the command's report.
+pub fn lossy_notes(to: ProteinFormat) -> Vec<String> {
+    match to {
+        ProteinFormat::Pdb => vec![
+            "PDB output keeps only CRYST1 and ATOM/HETATM records".to_string(),
+            "atom serial numbers are renumbered from 1".to_string(),
+        ],
+        ProteinFormat::Cif | ProteinFormat::Bcif => Vec::new(),
+    }
+}
+
+pub fn convert_structure(
+    bytes: &[u8],
+    from: ProteinFormat,
+    to: ProteinFormat,
+) -> Result<Vec<u8>, KiraError> {
+    let fail = |reason: String| KiraError::Conversion {
+        from: from.to_string(),
+        to: to.to_string(),
+        reason,
+    };
+    if !can_convert(from, to) {
+        return Err(fail("this conversion is not supported".to_string()));
+    }
+    let cif = match from {
+        ProteinFormat::Bcif => bcif_to_cif(bytes).map_err(fail)?,
+        _ => String::from_utf8(bytes.to_vec())
+            .map_err(|_| fail("the CIF file is not valid UTF-8".to_string()))?,
+    };
+    match to {
+        ProteinFormat::Cif => Ok(cif.into_bytes()),
+        _ => cif_to_pdb(&cif).map(String::into_bytes).map_err(fail),
+    }
+}
+
+/// Decodes a BinaryCIF file into text mmCIF.
+pub fn bcif_to_cif(bytes: &[u8]) -> Result<String, String> {
+    let mut reader = MsgReader { bytes, pos: 0 };
+    let root = reader.value()?;
+    let blocks = root
+        .get("dataBlocks")
+        .and_then(Msg::as_array)
+        .ok_or("BinaryCIF file has no dataBlocks")?;
+    let mut out = String::new();
+    for block in blocks {
+        let header = block.get("header").and_then(Msg::as_str).unwrap_or("");
+        let _ = writeln!(out, "data_{header}");
+        let categories = block
+            .get("categories")
+            .and_then(Msg::as_array)
+            .ok_or("BinaryCIF data block has no categories")?;
+        for category in categories {
+            write_category(&mut out, category)?;
+        }
+        out.push_str("#\n");
+    }
+    Ok(out)
+}
+
+fn write_category(out: &mut String, category: &Msg) -> Result<(), String> {
+    let name = category
+        .get("name")
+        .and_then(Msg::as_str)
+        .ok_or("BinaryCIF category has no name")?;
+    let name = name.strip_prefix('_').unwrap_or(name);
+    let rows = category
+        .get("rowCount")
+        .and_then(Msg::as_i64)
+        .and_then(|rows| usize::try_from(rows).ok())
+        .ok_or_else(|| format!("BinaryCIF category {name} has no rowCount"))?;
+    let mut columns = Vec::new();
+    for column in category
+        .get("columns")
+        .and_then(Msg::as_array)
+        .ok_or_else(|| format!("BinaryCIF category {name} has no columns"))?
+    {
+        let column_name = column
+            .get("name")
+            .and_then(Msg::as_str)
+            .ok_or_else(|| format!("BinaryCIF column in {name} has no name"))?;
+        let values =
+            decode_column(column).map_err(|reason| format!("{name}.{column_name}: {reason}"))?;
+        if values.len() != rows {
+            return Err(format!(
+                "{name}.{column_name} has {} values for {rows} rows",
+                values.len()
+            ));
+        }
+        columns.push((column_name, values));
+    }
+
+    out.push_str("#\n");
+    if rows == 1 {
+        let width = columns
+            .iter()
+            .map(|(column, _)| column.len())
+            .max()
+            .unwrap_or(0);
+        for (column, values) in &columns {
+            let value = &values[0];
+            if value.contains('\n') {
+                let _ = write!(out, "_{name}.{column}\n{}", cif_token(value));
+            } else {
+                let _ = writeln!(out, "_{name}.{column:<width$} {}", cif_token(value));
+            }
+        }
+    } else {
+        out.push_str("loop_\n");
+        for (column, _) in &columns {
+            let _ = writeln!(out, "_{name}.{column}");
+        }
+        for row in 0..rows {
+            let mut line = String::new();
+            for (_, values) in &columns {
+                let token = cif_token(&values[row]);
+                if token.starts_with('\n') {
+                    out.push_str(&line);
+                    out.push_str(&token);
+                    line.clear();
+                } else {
+                    if !line.is_empty() {
+                        line.push(' ');
+                    }
+                    line.push_str(&token);
+                }
+            }
+            if !line.is_empty() {
+                out.push_str(&line);
+                out.push('\n');
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Quotes a value for text CIF. Unknown (`?`) and inapplicable (`.`)
+/// values arrive already written as such from the column mask.
+fn cif_token(value: &str) -> String {
+    if value.contains('\n') {
+        return format!("\n;{value}\n;\n");
+    }
+    let lower = value.to_ascii_lowercase();
+    let needs_quotes = value.is_empty()
+        || value.chars().any(char::is_whitespace)
+        || value.starts_with(['_', '#', '$', '\'', '"', '[', ']', ';'])
+        || lower.starts_with("data_")
+        || lower.starts_with("save_")
+        || matches!(lower.as_str(), "loop_" | "global_" | "stop_");
+    if !needs_quotes {
+        value.to_string()
+    } else if !value.contains('\'') {
+        format!("'{value}'")
+    } else if !value.contains('"') {
+        format!("\"{value}\"")
+    } else {
+        format!("\n;{value}\n;\n")
+    }
+}
+
+/// One column's values as CIF text, with `.` and `?` for masked entries.
+fn decode_column(column: &Msg) -> Result<Vec<String>, String> {
+    let data = column.get("data").ok_or("column has no data")?;
+    let values = decode_data(data)?;
+    let mut text = match values {
+        Data::Bytes(bytes) => bytes.iter().map(u8::to_string).collect(),
+        Data::Ints(values) => values.iter().map(i64::to_string).collect(),
+        Data::F32(values) => values.iter().map(f32::to_string).collect(),
+        Data::F64(values) => values.iter().map(f64::to_string).collect(),
+        Data::Strings(values) => values,
+    };
+    if let Some(mask) = column.get("mask").filter(|mask| !matches!(mask, Msg::Nil)) {
+        let mask = decode_data(mask)?.ints()?;
+        for (value, flag) in text.iter_mut().zip(mask) {
+            match flag {
+                1 => *value = ".".to_string(),
+                2 => *value = "?".to_string(),
+                _ => {}
+            }
+        }
+    }
+    Ok(text)
+}
+
+#[derive(Debug)]
+enum Data {
+    Bytes(Vec<u8>),
+    Ints(Vec<i64>),
+    F32(Vec<f32>),
+    F64(Vec<f64>),
+    Strings(Vec<String>),
+}
+
+impl Data {
+    fn ints(self) -> Result<Vec<i64>, String> {
+        match self {
+            Data::Ints(values) => Ok(values),
+            Data::Bytes(bytes) => Ok(bytes.into_iter().map(i64::from).collect()),
+            _ => Err("expected integer data".to_string()),
+        }
+    }
+}
+
+/// Applies an encoded data object's encodings in reverse order.
+fn decode_data(encoded: &Msg) -> Result<Data, String> {
+    let bytes = encoded
+        .get("data")
+        .and_then(Msg::as_bin)
+        .ok_or("encoded data has no bytes")?;
+    let encodings = encoded
+        .get("encoding")
+        .and_then(Msg::as_array)
+        .ok_or("encoded data has no encoding")?;
+    let mut data = Data::Bytes(bytes.to_vec());
+    for encoding in encodings.iter().rev() {
+        data = apply_encoding(data, encoding)?;
+    }
+    Ok(data)
+}
+
+fn apply_encoding(data: Data, encoding: &Msg) -> Result<Data, String> {
+    let kind = encoding
+        .get("kind")
+        .and_then(Msg::as_str)
+        .ok_or("encoding has no kind")?;
+    let int_param = |key: &str| {
+        encoding
+            .get(key)
+            .and_then(Msg::as_i64)
+            .ok_or_else(|| format!("{kind} encoding has no {key}"))
+    };
+    let float_param = |key: &str| {
+        encoding
+            .get(key)
+            .and_then(Msg::as_f64)
+            .ok_or_else(|| format!("{kind} encoding has no {key}"))
+    };
+    match kind {
+        "ByteArray" => {
+            let Data::Bytes(bytes) = data else {
+                return Err("ByteArray expects raw bytes".to_string());
+            };
+            decode_byte_array(&bytes, int_param("type")?)
+        }
+        "FixedPoint" => {
+            let factor = float_param("factor")?;
+            let values = data.ints()?;
+            Ok(Data::F64(
+                values.into_iter().map(|v| v as f64 / factor).collect(),
+            ))
+        }
+        "IntervalQuantization" => {
+            let min = float_param("min")?;
+            let max = float_param("max")?;
+            let steps = int_param("numSteps")?;
+            let delta = if steps > 1 {
+                (max - min) / (steps - 1) as f64
+            } else {
+                0.0
+            };
+            let values = data.ints()?;
+            Ok(Data::F64(
+                values.into_iter().map(|v| min + delta * v as f64).collect(),
+            ))
+        }
+        "RunLength" => {
+            let values = data.ints()?;
+            let size = usize::try_from(int_param("srcSize")?).map_err(|err| err.to_string())?;
+            let mut out = Vec::with_capacity(size);
+            for pair in values.chunks(2) {
+                let [value, count] = pair else {
+                    return Err("RunLength data has an odd length".to_string());
+                };
+                let count = usize::try_from(*count).map_err(|err| err.to_string())?;
+                out.extend(std::iter::repeat_n(*value, count));
+            }
+            Ok(Data::Ints(out))
+        }
+        "Delta" => {
+            let mut current = int_param("origin")?;
+            let values = data.ints()?;
+            Ok(Data::Ints(
+                values
+                    .into_iter()
+                    .map(|value| {
+                        current += value;
+                        current
+                    })
+                    .collect(),
+            ))
+        }
+        "IntegerPacking" => {
+            let byte_count = int_param("byteCount")?;
+            let unsigned = matches!(encoding.get("isUnsigned"), Some(Msg::Bool(true)));
+            let (upper, lower) = match (byte_count, unsigned) {
+                (1, true) => (0xff, i64::MIN),
+                (1, false) => (0x7f, -0x80),
+                (2, true) => (0xffff, i64::MIN),
+                (2, false) => (0x7fff, -0x8000),
+                _ => return Err(format!("IntegerPacking byteCount {byte_count}")),
+            };
+            let values = data.ints()?;
+            let mut out = Vec::new();
+            let mut sum = 0;
+            for value in values {
+                sum += value;
+                if value != upper && value != lower {
+                    out.push(sum);
+                    sum = 0;
+                }
+            }
+            Ok(Data::Ints(out))
+        }
+        "StringArray" => {
+            let Data::Bytes(bytes) = data else {
+                return Err("StringArray expects raw bytes".to_string());
+            };
+            let strings = encoding
+                .get("stringData")
+                .and_then(Msg::as_str)
+                .ok_or("StringArray encoding has no stringData")?;
+            let offsets = decode_data(&Msg::Map(vec![
+                (
+                    Msg::Str("data".to_string()),
+                    encoding.get("offsets").cloned().unwrap_or(Msg::Nil),
+                ),
+                (
+                    Msg::Str("encoding".to_string()),
+                    encoding.get("offsetEncoding").cloned().unwrap_or(Msg::Nil),
+                ),
+            ]))?
+            .ints()?;
+            let indices = decode_data(&Msg::Map(vec![
+                (Msg::Str("data".to_string()), Msg::Bin(bytes)),
+                (
+                    Msg::Str("encoding".to_string()),
+                    encoding.get("dataEncoding").cloned().unwrap_or(Msg::Nil),
+                ),
+            ]))?
+            .ints()?;
+            let mut out = Vec::with_capacity(indices.len());
+            for index in indices {
+                if index < 0 {
+                    out.push(String::new());
+                    continue;
+                }
+                let index = index as usize;
+                let (Some(&start), Some(&end)) = (offsets.get(index), offsets.get(index + 1))
+                else {
+                    return Err(format!("string index {index} is out of range"));
+                };
+                let text = usize::try_from(start)
+                    .ok()
+                    .zip(usize::try_from(end).ok())
+                    .and_then(|(start, end)| strings.get(start..end))
+                    .ok_or_else(|| format!("string offsets {start}..{end} are out of range"))?;
+                out.push(text.to_string());
+            }
+            Ok(Data::Strings(out))
+        }
+        other => Err(format!("unknown encoding {other}")),
+    }
+}
+
+fn decode_byte_array(bytes: &[u8], kind: i64) -> Result<Data, String> {
+    fn chunks<const N: usize>(bytes: &[u8]) -> Result<impl Iterator<Item = [u8; N]>, String> {
+        if !bytes.len().is_multiple_of(N) {
+            return Err(format!(
+                "{} bytes do not divide into {N}-byte values",
+                bytes.len()
+            ));
+        }
+        Ok(bytes
+            .chunks_exact(N)
+            .map(|chunk| chunk.try_into().expect("chunk has N bytes")))
+    }
+    let ints = |values: Vec<i64>| Ok(Data::Ints(values));
+    match kind {
+        1 => ints(bytes.iter().map(|&b| i64::from(b as i8)).collect()),
+        2 => ints(
+            chunks::<2>(bytes)?
+                .map(|c| i64::from(i16::from_le_bytes(c)))
+                .collect(),
+        ),
+        3 => ints(
+            chunks::<4>(bytes)?
+                .map(|c| i64::from(i32::from_le_bytes(c)))
+                .collect(),
+        ),
+        4 => ints(bytes.iter().map(|&b| i64::from(b)).collect()),
+        5 => ints(
+            chunks::<2>(bytes)?
+                .map(|c| i64::from(u16::from_le_bytes(c)))
+                .collect(),
+        ),
+        6 => ints(
+            chunks::<4>(bytes)?
+                .map(|c| i64::from(u32::from_le_bytes(c)))
+                .collect(),
+        ),
+        32 => Ok(Data::F32(
+            chunks::<4>(bytes)?.map(f32::from_le_bytes).collect(),
+        )),
+        33 => Ok(Data::F64(
+            chunks::<8>(bytes)?.map(f64::from_le_bytes).collect(),
+        )),
+        other => Err(format!("unknown ByteArray type {other}")),
+    }
+}
+
+/// A decoded MessagePack value.
+#[derive(Debug, Clone)]
+enum Msg {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bin(Vec<u8>),
+    Array(Vec<Msg>),
+    Map(Vec<(Msg, Msg)>),
+}
+
+impl Msg {
+    fn get(&self, key: &str) -> Option<&Msg> {
+        let Msg::Map(entries) = self else {
+            return None;
+        };
+        entries
+            .iter()
+            .find(|(name, _)| matches!(name, Msg::Str(name) if name == key))
+            .map(|(_, value)| value)
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Msg::Str(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn as_i64(&self) -> Option<i64> {
+        match self {
+            Msg::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Msg::Int(value) => Some(*value as f64),
+            Msg::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn as_bin(&self) -> Option<&[u8]> {
+        match self {
+            Msg::Bin(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> Option<&[Msg]> {
+        match self {
+            Msg::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+struct MsgReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl MsgReader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or("BinaryCIF file ends in the middle of a value")?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn uint(&mut self, len: usize) -> Result<u64, String> {
+        Ok(self
+            .take(len)?
+            .iter()
+            .fold(0, |acc, byte| (acc << 8) | u64::from(*byte)))
+    }
+
+    fn len(&mut self, len: usize) -> Result<usize, String> {
+        usize::try_from(self.uint(len)?).map_err(|err| err.to_string())
+    }
+
+    fn value(&mut self) -> Result<Msg, String> {
+        let marker = self.take(1)?[0];
+        match marker {
+            0x00..=0x7f => Ok(Msg::Int(i64::from(marker))),
+            0x80..=0x8f => self.map(usize::from(marker & 0x0f)),
+            0x90..=0x9f => self.array(usize::from(marker & 0x0f)),
+            0xa0..=0xbf => self.string(usize::from(marker & 0x1f)),
+            0xc0 => Ok(Msg::Nil),
+            0xc2 => Ok(Msg::Bool(false)),
+            0xc3 => Ok(Msg::Bool(true)),
+            0xc4..=0xc6 => {
+                let len = self.len(1 << (marker - 0xc4))?;
+                Ok(Msg::Bin(self.take(len)?.to_vec()))
+            }
+            0xca => Ok(Msg::Float(f64::from(f32::from_bits(self.uint(4)? as u32)))),
+            0xcb => Ok(Msg::Float(f64::from_bits(self.uint(8)?))),
+            0xcc..=0xcf => {
+                let value = self.uint(1 << (marker - 0xcc))?;
+                i64::try_from(value)
+                    .map(Msg::Int)
+                    .map_err(|_| format!("integer {value} is too large"))
+            }
+            0xd0..=0xd3 => {
+                let len = 1 << (marker - 0xd0);
+                let value = self.uint(len)?;
+                let shift = 64 - 8 * len as u32;
+                Ok(Msg::Int(((value << shift) as i64) >> shift))
+            }
+            0xd9..=0xdb => {
+                let len = self.len(1 << (marker - 0xd9))?;
+                self.string(len)
+            }
+            0xdc | 0xdd => {
+                let len = self.len(if marker == 0xdc { 2 } else { 4 })?;
+                self.array(len)
+            }
+            0xde | 0xdf => {
+                let len = self.len(if marker == 0xde { 2 } else { 4 })?;
+                self.map(len)
+            }
+            0xe0..=0xff => Ok(Msg::Int(i64::from(marker as i8))),
+            other => Err(format!("unsupported MessagePack type 0x{other:02x}")),
+        }
+    }
+
+    fn string(&mut self, len: usize) -> Result<Msg, String> {
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec())
+            .map(Msg::Str)
+            .map_err(|_| "BinaryCIF string is not valid UTF-8".to_string())
+    }
+
+    fn array(&mut self, len: usize) -> Result<Msg, String> {
+        (0..len)
+            .map(|_| self.value())
+            .collect::<Result<_, _>>()
+            .map(Msg::Array)
+    }
+
+    fn map(&mut self, len: usize) -> Result<Msg, String> {
+        (0..len)
+            .map(|_| Ok((self.value()?, self.value()?)))
+            .collect::<Result<_, String>>()
+            .map(Msg::Map)
+    }
+}
+
+/// The rows of one category of the first data block, by column name.
+/// Unknown and inapplicable values are `None`.
+#[derive(Debug, Default)]
+struct CifTable {
+    columns: Vec<String>,
+    rows: Vec<Vec<Option<String>>>,
+}
+
+impl CifTable {
+    fn column(&self, names: &[&str]) -> Option<usize> {
+        names
+            .iter()
+            .find_map(|name| self.columns.iter().position(|column| column == name))
+    }
+
+    fn value<'a>(&'a self, row: &'a [Option<String>], names: &[&str]) -> Option<&'a str> {
+        names
+            .iter()
+            .filter_map(|name| self.column(&[name]))
+            .find_map(|index| row.get(index).and_then(Option::as_deref))
+    }
+}
+
+struct CifToken<'a> {
+    text: &'a str,
+    quoted: bool,
+}
+
+fn cif_tokens(text: &str) -> Result<Vec<CifToken<'_>>, String> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let byte = bytes[pos];
+        let line_start = pos == 0 || bytes[pos - 1] == b'\n';
+        if byte.is_ascii_whitespace() {
+            pos += 1;
+        } else if byte == b'#' {
+            pos = text[pos..].find('\n').map_or(bytes.len(), |end| pos + end);
+        } else if byte == b';' && line_start {
+            let end = text[pos + 1..]
+                .find("\n;")
+                .map(|end| pos + 1 + end)
+                .ok_or("unterminated CIF text field")?;
+            tokens.push(CifToken {
+                text: text[pos + 1..end].trim_start_matches(['\r', '\n']),
+                quoted: true,
+            });
+            pos = end + 2;
+        } else if byte == b'\'' || byte == b'"' {
+            let mut end = pos + 1;
+            loop {
+                if end >= bytes.len() {
+                    return Err("unterminated CIF quoted value".to_string());
+                }
+                if bytes[end] == byte
+                    && bytes
+                        .get(end + 1)
+                        .is_none_or(|next| next.is_ascii_whitespace())
+                {
+                    break;
+                }
+                end += 1;
+            }
+            tokens.push(CifToken {
+                text: &text[pos + 1..end],
+                quoted: true,
+            });
+            pos = end + 1;
+        } else {
+            let end = text[pos..]
+                .find(|ch: char| ch.is_ascii_whitespace())
+                .map_or(bytes.len(), |end| pos + end);
+            tokens.push(CifToken {
+                text: &text[pos..end],
+                quoted: false,
+            });
+            pos = end;
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_cif(text: &str) -> Result<BTreeMap<String, CifTable>, String> {
+    let tokens = cif_tokens(text)?;
+    let mut tables = BTreeMap::<String, CifTable>::new();
+    let is_keyword = |token: &CifToken| {
+        !token.quoted
+            && (token.text.starts_with('_')
+                || token.text.eq_ignore_ascii_case("loop_")
+                || token.text.to_ascii_lowercase().starts_with("data_"))
+    };
+    let value = |token: &CifToken| {
+        (token.quoted || !matches!(token.text, "." | "?")).then(|| token.text.to_string())
+    };
+    let split = |tag: &str| {
+        let tag = tag.trim_start_matches('_');
+        match tag.split_once('.') {
+            Some((category, column)) => (category.to_string(), column.to_string()),
+            None => (String::new(), tag.to_string()),
+        }
+    };
+
+    let mut seen_block = false;
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = &tokens[index];
+        if !token.quoted && token.text.to_ascii_lowercase().starts_with("data_") {
+            if seen_block {
+                break;
+            }
+            seen_block = true;
+            index += 1;
+        } else if !token.quoted && token.text.eq_ignore_ascii_case("loop_") {
+            index += 1;
+            let mut category = None;
+            let mut columns = Vec::new();
+            while let Some(tag) = tokens
+                .get(index)
+                .filter(|tag| !tag.quoted && tag.text.starts_with('_'))
+            {
+                let (name, column) = split(tag.text);
+                category.get_or_insert(name);
+                columns.push(column);
+                index += 1;
+            }
+            let start = index;
+            while tokens.get(index).is_some_and(|token| !is_keyword(token)) {
+                index += 1;
+            }
+            let values = &tokens[start..index];
+            if columns.is_empty() || !values.len().is_multiple_of(columns.len()) {
+                return Err(format!(
+                    "CIF loop {} has {} values for {} columns",
+                    category.unwrap_or_default(),
+                    values.len(),
+                    columns.len()
+                ));
+            }
+            let table = tables.entry(category.unwrap_or_default()).or_default();
+            table.columns = columns;
+            table.rows = values
+                .chunks(table.columns.len())
+                .map(|row| row.iter().map(value).collect())
+                .collect();
+        } else if !token.quoted && token.text.starts_with('_') {
+            let (category, column) = split(token.text);
+            let item = tokens
+                .get(index + 1)
+                .filter(|token| !is_keyword(token))
+                .ok_or_else(|| format!("CIF item {} has no value", token.text))?;
+            let table = tables.entry(category).or_default();
+            if table.rows.is_empty() {
+                table.rows.push(Vec::new());
+            }
+            table.columns.push(column);
+            table.rows[0].push(value(item));
+            index += 2;
+        } else {
+            return Err(format!("unexpected CIF value {}", token.text));
+        }
+    }
+    Ok(tables)
+}
+
+/// Writes the first model of an mmCIF structure as PDB records.
+pub fn cif_to_pdb(text: &str) -> Result<String, String> {
+    let tables = parse_cif(text)?;
+    let atoms = tables
+        .get("atom_site")
+        .filter(|table| !table.rows.is_empty())
+        .ok_or("the structure has no _atom_site records")?;
+    if atoms.rows.len() > PDB_MAX_ATOMS {
+        return Err(format!(
+            "{} atoms exceed the PDB limit of {PDB_MAX_ATOMS}; keep the mmCIF file",
+            atoms.rows.len()
+        ));
+    }
+    let models = atoms
+        .rows
+        .iter()
+        .filter_map(|row| atoms.value(row, &["pdbx_PDB_model_num"]))
+        .collect::<std::collections::BTreeSet<_>>();
+    if models.len() > 1 {
+        return Err(format!(
+            "the structure has {} models; only single-model structures convert to PDB",
+            models.len()
+        ));
+    }
+
+    let mut out = String::new();
+    if let Some(cell) = tables.get("cell").filter(|cell| !cell.rows.is_empty()) {
+        let row = &cell.rows[0];
+        let number = |name: &str| {
+            cell.value(row, &[name])
+                .and_then(|value| value.parse::<f64>().ok())
+        };
+        if let (Some(a), Some(b), Some(c), Some(alpha), Some(beta), Some(gamma)) = (
+            number("length_a"),
+            number("length_b"),
+            number("length_c"),
+            number("angle_alpha"),
+            number("angle_beta"),
+            number("angle_gamma"),
+        ) {
+            let group = tables
+                .get("symmetry")
+                .and_then(|symmetry| {
+                    symmetry
+                        .rows
+                        .first()
+                        .and_then(|row| symmetry.value(row, &["space_group_name_H-M"]))
+                })
+                .unwrap_or("P 1");
+            let z = cell.value(row, &["Z_PDB"]).unwrap_or("1");
+            let _ = writeln!(
+                out,
+                "CRYST1{a:9.3}{b:9.3}{c:9.3}{alpha:7.2}{beta:7.2}{gamma:7.2} {group:<11}{z:>4}"
+            );
+        }
+    }
+
+    for (index, row) in atoms.rows.iter().enumerate() {
+        let field = |names: &[&str]| atoms.value(row, names).unwrap_or("");
+        let record = match field(&["group_PDB"]) {
+            "HETATM" => "HETATM",
+            _ => "ATOM",
+        };
+        let name = field(&["auth_atom_id", "label_atom_id"]);
+        let residue = field(&["auth_comp_id", "label_comp_id"]);
+        let chain = field(&["auth_asym_id", "label_asym_id"]);
+        let sequence = field(&["auth_seq_id", "label_seq_id"]);
+        let element = field(&["type_symbol"]);
+        if name.len() > 4 {
+            return Err(format!("atom name {name} is longer than 4 characters"));
+        }
+        if residue.len() > 3 {
+            return Err(format!(
+                "residue name {residue} is longer than 3 characters"
+            ));
+        }
+        if chain.len() > 1 {
+            return Err(format!("chain id {chain} is longer than 1 character"));
+        }
+        let sequence = if sequence.is_empty() {
+            0
+        } else {
+            sequence
+                .parse::<i64>()
+                .map_err(|_| format!("residue number {sequence} is not an integer"))?
+        };
+        if !(-999..=9999).contains(&sequence) {
+            return Err(format!("residue number {sequence} does not fit 4 columns"));
+        }
+        let coordinate = |names: &[&str]| -> Result<f64, String> {
+            let value = field(names);
+            let parsed = value
+                .parse::<f64>()
+                .map_err(|_| format!("coordinate {value:?} is not a number"))?;
+            if !(-999.999..=9999.999).contains(&parsed) {
+                return Err(format!("coordinate {value} does not fit 8 columns"));
+            }
+            Ok(parsed)
+        };
+        let (x, y, z) = (
+            coordinate(&["Cartn_x"])?,
+            coordinate(&["Cartn_y"])?,
+            coordinate(&["Cartn_z"])?,
+        );
+        let number = |names: &[&str], default: f64| {
+            atoms
+                .value(row, names)
+                .and_then(|value| value.parse::<f64>().ok())
+                .unwrap_or(default)
+        };
+        let occupancy = number(&["occupancy"], 1.0);
+        let b_factor = number(&["B_iso_or_equiv"], 0.0);
+        let alt = field(&["label_alt_id"]);
+        let insertion = field(&["pdbx_PDB_ins_code"]);
+        let charge = match field(&["pdbx_formal_charge"]).parse::<i64>() {
+            Ok(charge) if charge > 0 => format!("{charge}+"),
+            Ok(charge) if charge < 0 => format!("{}-", -charge),
+            _ => String::new(),
+        };
+        // Atom names of one-letter elements start in column 14.
+        let name = if name.len() < 4 && element.len() == 1 {
+            format!(" {name:<3}")
+        } else {
+            format!("{name:<4}")
+        };
+        let _ = writeln!(
+            out,
+            "{record:<6}{serial:>5} {name}{alt:1}{residue:>3} {chain:1}{sequence:>4}{insertion:1}   \
+             {x:8.3}{y:8.3}{z:8.3}{occupancy:6.2}{b_factor:6.2}          {element:>2}{charge:<2}",
+            serial = index + 1,
+            alt = alt.chars().next().unwrap_or(' '),
+            insertion = insertion.chars().next().unwrap_or(' '),
+        );
+    }
+    out.push_str("END\n");
+    Ok(out)
+}
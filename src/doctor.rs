@@ -0,0 +1,419 @@
+//! Environment checks behind `kira-bm doctor`.
+//!
+//! Each check names one prerequisite (an external tool, a registry, a store
+//! root) and reports pass, warn or fail with a hint on how to fix it. Fetches
+//! fail in confusing ways when these are missing; the doctor finds them all
+//! in one pass.
+
+use std::cmp::Reverse;
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use camino::Utf8Path;
+use chrono::{DateTime, TimeDelta, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::fs_util::{available_space, bytes_to_human};
+use crate::http::{
+    HealthEndpoint, HttpSettings, health_endpoint, redact_credentials, registry_base,
+};
+use crate::ping::{EndpointProbe, PingResult, PingStatus, ping_one};
+pub use crate::srr::parse_version;
+use crate::srr::{MIN_SRA_TOOLKIT, SrrClient, SrrToolStatus};
+use crate::store::{CACHE_DIR_ENV, NO_CACHE_DIR, Store};
+
+/// Registries fetches cannot do without; `kira-bm ping` covers the rest.
+const PROBED_REGISTRIES: [&str; 5] = ["rcsb", "ncbi", "uniprot", "geo", "crossref"];
+
+const SLOW_LATENCY: Duration = Duration::from_secs(3);
+/// TLS tolerates little skew; beyond this certificates start to look
+/// not-yet-valid or expired.
+const MAX_CLOCK_SKEW_SECS: i64 = 300;
+const LOW_DISK_WARN: u64 = 5 * 1024 * 1024 * 1024;
+const LOW_DISK_FAIL: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            CheckStatus::Pass => "pass",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => "fail",
+        };
+        f.write_str(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl Check {
+    pub fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Pass, detail, None)
+    }
+
+    pub fn warn(
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        hint: impl Into<String>,
+    ) -> Self {
+        Self::new(name, CheckStatus::Warn, detail, Some(hint.into()))
+    }
+
+    pub fn fail(
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        hint: impl Into<String>,
+    ) -> Self {
+        Self::new(name, CheckStatus::Fail, detail, Some(hint.into()))
+    }
+
+    fn new(
+        name: impl Into<String>,
+        status: CheckStatus,
+        detail: impl Into<String>,
+        hint: Option<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+            hint,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct DoctorReport {
+    pub checks: Vec<Check>,
+}
+
+impl DoctorReport {
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.status == status)
+            .count()
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.count(CheckStatus::Fail) == 0
+    }
+}
+
+/// Runs every check. `now` is compared against registry clocks.
+pub fn run<S: SrrClient>(
+    store: &Store,
+    srr: &S,
+    http: &HttpSettings,
+    probe: &dyn EndpointProbe,
+    now: DateTime<Utc>,
+) -> DoctorReport {
+    let mut checks = vec![
+        sra_toolkit(srr),
+        proxy(http),
+        ca_bundle(http),
+        registry_bases(http),
+    ];
+    let mut dates = Vec::new();
+    for endpoint in PROBED_REGISTRIES
+        .iter()
+        .filter_map(|name| health_endpoint(name))
+    {
+        let result = ping_one(probe, endpoint);
+        if let Some(date) = result.date {
+            dates.push((endpoint.name, date));
+        }
+        checks.push(reachable(endpoint, &result));
+    }
+    checks.push(clock(&dates, now));
+    let mut stores = vec![("project store", store.project_root())];
+    if store.cache_disabled() {
+        checks.push(Check::pass(
+            "cache store",
+            format!("disabled by {CACHE_DIR_ENV}={NO_CACHE_DIR}"),
+        ));
+    } else {
+        stores.push(("cache store", store.cache_root()));
+    }
+    for (name, root) in stores {
+        checks.push(writable(name, root));
+        checks.push(disk_space(name, root));
+    }
+    DoctorReport { checks }
+}
+
+fn sra_toolkit<S: SrrClient>(srr: &S) -> Check {
+    const NAME: &str = "SRA Toolkit";
+    if let SrrToolStatus::Missing { message } = srr.tool_status() {
+        return Check::warn(
+            NAME,
+            message,
+            "only needed for srr: datasets; run `kira-bm tools install-sra` for instructions",
+        );
+    }
+    let info = srr.tool_info();
+    let Some(output) = info.sra_toolkit else {
+        return Check::warn(
+            NAME,
+            "installed, version unknown",
+            "`fasterq-dump --version` failed; check that the toolkit runs",
+        );
+    };
+    let datasets = info
+        .datasets
+        .as_deref()
+        .and_then(parse_version)
+        .map(|(major, minor, patch)| format!(", datasets {major}.{minor}.{patch}"))
+        .unwrap_or_default();
+    match parse_version(&output) {
+        Some((major, minor, patch)) if (major, minor) >= MIN_SRA_TOOLKIT => Check::pass(
+            NAME,
+            format!("fasterq-dump {major}.{minor}.{patch}{datasets}"),
+        ),
+        Some((major, minor, patch)) => Check::warn(
+            NAME,
+            format!(
+                "fasterq-dump {major}.{minor}.{patch} is older than {}.{}",
+                MIN_SRA_TOOLKIT.0, MIN_SRA_TOOLKIT.1
+            ),
+            "older toolkits cannot read current SRA runs; install a current release",
+        ),
+        None => Check::warn(
+            NAME,
+            format!(
+                "unrecognised version output: {}",
+                output.lines().next().unwrap_or_default()
+            ),
+            "`fasterq-dump --version` should print a version number",
+        ),
+    }
+}
+
+/// The proxies requests will actually use, so a probe failure can be read
+/// against them.
+fn proxy(http: &HttpSettings) -> Check {
+    const NAME: &str = "proxy";
+    let proxy = http.proxy();
+    if proxy.is_empty() {
+        return Check::pass(NAME, "none, registries are reached directly");
+    }
+    let mut routes = Vec::new();
+    if let Some(https) = &proxy.https {
+        routes.push(format!("https via {}", redact_credentials(https)));
+    }
+    if let Some(http) = &proxy.http {
+        routes.push(format!("http via {}", redact_credentials(http)));
+    }
+    if !proxy.no_proxy.is_empty() {
+        routes.push(format!("direct for {}", proxy.no_proxy.join(",")));
+    }
+    Check::pass(NAME, routes.join("; "))
+}
+
+fn ca_bundle(http: &HttpSettings) -> Check {
+    const NAME: &str = "CA bundle";
+    match http.ca_bundle() {
+        None => Check::pass(NAME, "none, system roots only"),
+        Some(Ok(bundle)) => Check::pass(
+            NAME,
+            format!(
+                "{} certificate{} from {}",
+                bundle.certificates,
+                if bundle.certificates == 1 { "" } else { "s" },
+                bundle.path.display()
+            ),
+        ),
+        Some(Err(err)) => Check::fail(
+            NAME,
+            err.to_string(),
+            "point --ca-bundle (or ca_bundle in kira-bm.json) at a PEM file of CA certificates",
+        ),
+    }
+}
+
+/// Mirrors standing in for registries; the reachability checks below go
+/// through them.
+fn registry_bases(http: &HttpSettings) -> Check {
+    const NAME: &str = "registry base URLs";
+    if let Err(err) = http.mirrors() {
+        return Check::fail(
+            NAME,
+            err.to_string(),
+            "set the KIRA_BM_*_BASE variable (or `registries` in the global config) to an absolute http(s) URL",
+        );
+    }
+    let mirrored = http
+        .effective_registry_bases()
+        .into_iter()
+        .filter(|(registry, base)| {
+            registry_base(registry).is_some_and(|registry| registry.defaults[0] != base)
+        })
+        .map(|(registry, base)| format!("{registry} via {base}"))
+        .collect::<Vec<_>>();
+    if mirrored.is_empty() {
+        return Check::pass(NAME, "none overridden, registries' own endpoints");
+    }
+    Check::pass(NAME, mirrored.join("; "))
+}
+
+fn reachable(endpoint: &HealthEndpoint, result: &PingResult) -> Check {
+    let registry = endpoint.name;
+    let name = format!("{registry} reachable");
+    let answered = || {
+        format!(
+            "HTTP {} in {} ms",
+            result.http_status.unwrap_or_default(),
+            result.latency_ms.unwrap_or_default()
+        )
+    };
+    let error = result.error.clone().unwrap_or_default();
+    match result.status {
+        PingStatus::ServerError => Check::warn(
+            name,
+            format!(
+                "{} answered HTTP {}",
+                result.url,
+                result.http_status.unwrap_or_default()
+            ),
+            format!("{registry} is having problems; fetches from it may fail until it recovers"),
+        ),
+        PingStatus::Ok | PingStatus::ClientError
+            if result.latency_ms.unwrap_or_default() > SLOW_LATENCY.as_millis() as u64 =>
+        {
+            Check::warn(
+                name,
+                answered(),
+                "the connection is slow; large downloads may hit timeouts",
+            )
+        }
+        // Any answer, even a 4xx, proves the registry is reachable through
+        // DNS, proxies and TLS.
+        PingStatus::Ok | PingStatus::ClientError => Check::pass(name, answered()),
+        PingStatus::Tls if error.contains("scheme is not http") => Check::fail(
+            name,
+            error,
+            "this kira-bm build has no HTTPS support; rebuild it with a TLS backend for reqwest",
+        ),
+        PingStatus::Tls => Check::fail(
+            name,
+            error,
+            "TLS verification failed; check the system clock and any intercepting proxy",
+        ),
+        PingStatus::Timeout => Check::fail(
+            name,
+            error,
+            "the registry did not answer in time; check for a firewall or a slow proxy",
+        ),
+        PingStatus::Unreachable => Check::fail(
+            name,
+            error,
+            "check network access, DNS and the HTTPS_PROXY setting",
+        ),
+    }
+}
+
+fn clock(dates: &[(&str, DateTime<Utc>)], now: DateTime<Utc>) -> Check {
+    const NAME: &str = "system clock";
+    let Some((registry, skew)) = dates
+        .iter()
+        .map(|(registry, date)| (*registry, now - *date))
+        .min_by_key(|(_, skew)| Reverse(skew.num_seconds().abs()))
+    else {
+        return Check::warn(
+            NAME,
+            "no registry answered with a date to compare against",
+            "fix network access first; clock skew breaks TLS",
+        );
+    };
+    let seconds = skew.num_seconds();
+    if seconds.abs() > MAX_CLOCK_SKEW_SECS {
+        let direction = if seconds > 0 { "ahead of" } else { "behind" };
+        return Check::fail(
+            NAME,
+            format!("{} {direction} {registry}", format_skew(skew)),
+            "synchronise the clock (NTP); TLS certificates fail to validate",
+        );
+    }
+    Check::pass(NAME, format!("within {} of {registry}", format_skew(skew)))
+}
+
+fn format_skew(skew: TimeDelta) -> String {
+    let seconds = skew.num_seconds().unsigned_abs();
+    if seconds >= 3600 {
+        format!("{}h {}m", seconds / 3600, seconds % 3600 / 60)
+    } else if seconds >= 60 {
+        format!("{}m {}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+fn writable(name: &str, root: &Utf8Path) -> Check {
+    let name = format!("{name} writable");
+    // Nothing is created for the check: a missing root is tested through
+    // the directory it would be created in.
+    let dir = match existing_ancestor(root.as_std_path()) {
+        Ok(dir) => dir,
+        Err(message) => {
+            return Check::fail(
+                name,
+                message,
+                "check the path and that its filesystem is mounted",
+            );
+        }
+    };
+    let result = tempfile::Builder::new()
+        .prefix(".kira-bm-doctor")
+        .tempfile_in(dir);
+    match result {
+        Ok(_) if dir == root.as_std_path() => Check::pass(name, root.to_string()),
+        Ok(_) => Check::pass(name, format!("{root} (created on first fetch)")),
+        Err(err) => Check::fail(
+            name,
+            format!("{}: {err}", dir.display()),
+            "the filesystem may be read-only or owned by another user; fix its permissions or run from a writable directory",
+        ),
+    }
+}
+
+fn disk_space(name: &str, root: &Utf8Path) -> Check {
+    let name = format!("{name} disk space");
+    let space = existing_ancestor(root.as_std_path())
+        .and_then(|dir| available_space(dir).map_err(|err| err.to_string()));
+    let hint = "genomes and sequencing runs need several GB; free space or move the store";
+    match space {
+        Ok(bytes) if bytes < LOW_DISK_FAIL => {
+            Check::fail(name, format!("{} free", bytes_to_human(bytes)), hint)
+        }
+        Ok(bytes) if bytes < LOW_DISK_WARN => {
+            Check::warn(name, format!("{} free", bytes_to_human(bytes)), hint)
+        }
+        Ok(bytes) => Check::pass(name, format!("{} free", bytes_to_human(bytes))),
+        Err(err) => Check::warn(name, format!("unknown: {err}"), "check free space manually"),
+    }
+}
+
+/// The directory `path` is, or the one it would be created in.
+fn existing_ancestor(path: &Path) -> Result<&Path, String> {
+    match path.ancestors().find(|ancestor| ancestor.exists()) {
+        Some(ancestor) if ancestor.is_dir() => Ok(ancestor),
+        Some(ancestor) => Err(format!("{} is not a directory", ancestor.display())),
+        None => Err(format!("{}: no existing parent directory", path.display())),
+    }
+}
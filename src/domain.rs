@@ -50,15 +50,36 @@ pub enum FetchFormat {
     Fasta,
 }
 
+/// A PDB entry id: a classic 4-character id (`1LYZ`, kept uppercase) or a
+/// wwPDB extended id (`pdb_00001abc`, kept lowercase). An extended id with
+/// four leading zeros names a classic entry and becomes its 4-character id,
+/// so both spellings share one dataset. A `_v<major>[-<minor>]` entry-version
+/// suffix is accepted and dropped: registries serve the current version.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ProteinId(String);
 
+const EXTENDED_PDB_PREFIX: &str = "pdb_";
+
 impl ProteinId {
     pub fn as_str(&self) -> &str {
         &self.0
     }
 }
 
+/// `body` without a trailing `_v1` or `_v1-2`.
+fn strip_entry_version(body: &str) -> &str {
+    let Some((base, version)) = body.rsplit_once("_v") else {
+        return body;
+    };
+    let (major, minor) = version.split_once('-').unwrap_or((version, "0"));
+    let numeric = |part: &str| !part.is_empty() && part.chars().all(|ch| ch.is_ascii_digit());
+    if numeric(major) && numeric(minor) {
+        base
+    } else {
+        body
+    }
+}
+
 impl fmt::Display for ProteinId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0)
@@ -69,16 +90,32 @@ impl FromStr for ProteinId {
     type Err = KiraError;
 
     fn from_str(value: &str) -> Result<Self, Self::Err> {
-        let normalized = value.trim().to_uppercase();
-        let is_valid =
-            normalized.len() == 4 && normalized.chars().all(|ch| ch.is_ascii_alphanumeric());
-        if !is_valid {
+        let lower = value.trim().to_ascii_lowercase();
+        let id = strip_entry_version(&lower);
+        let alphanumeric = |text: &str| text.chars().all(|ch| ch.is_ascii_alphanumeric());
+        if let Some(code) = id.strip_prefix(EXTENDED_PDB_PREFIX) {
+            if code.len() != 8 || !alphanumeric(code) {
+                return Err(KiraError::InvalidProteinId(value.to_string()));
+            }
+            return Ok(match code.strip_prefix("0000") {
+                Some(classic) => Self(classic.to_ascii_uppercase()),
+                None => Self(id.to_string()),
+            });
+        }
+        if id.len() != 4 || !alphanumeric(id) {
             return Err(KiraError::InvalidProteinId(value.to_string()));
         }
-        Ok(Self(normalized))
+        Ok(Self(id.to_ascii_uppercase()))
     }
 }
 
+/// Whether `value` looks like a PDB id in either form.
+fn is_pdb_id_like(value: &str) -> bool {
+    let lower = value.trim().to_ascii_lowercase();
+    lower.starts_with(EXTENDED_PDB_PREFIX)
+        || (lower.len() == 4 && lower.chars().all(|ch| ch.is_ascii_alphanumeric()))
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct GenomeAccession(String);
 
@@ -88,6 +125,65 @@ impl GenomeAccession {
     }
 }
 
+/// An assembly name such as `GRCh38`, given in place of an accession and
+/// resolved against NCBI before fetching.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AssemblyName(String);
+
+impl AssemblyName {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// An NCBI taxid or taxon name whose assemblies a `genome:taxon:` fetch
+/// lists.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TaxonQuery(String);
+
+impl TaxonQuery {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// A genome from Ensembl: the species as Ensembl names it (`homo_sapiens`)
+/// and the release to take it from, written `homo_sapiens@110`. Without a
+/// release the current one is resolved and pinned at fetch time.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EnsemblGenome {
+    species: String,
+    release: Option<u32>,
+}
+
+impl EnsemblGenome {
+    pub fn species(&self) -> &str {
+        &self.species
+    }
+
+    pub fn release(&self) -> Option<u32> {
+        self.release
+    }
+
+    /// The same species at a concrete release.
+    pub fn pinned(&self, release: u32) -> Self {
+        Self {
+            species: self.species.clone(),
+            release: Some(release),
+        }
+    }
+}
+
+/// A logical name given to a dataset in `kira-bm.json`, e.g. `ecoli_ref`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct DatasetAlias(String);
+
+impl DatasetAlias {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct SrrId(String);
 
@@ -97,15 +193,88 @@ impl SrrId {
     }
 }
 
+/// An SRA experiment (`SRX`, `ERX` or `DRX`), which groups the runs
+/// sequenced from one library.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct UniprotId(String);
+pub struct SraExperimentAccession(String);
+
+impl SraExperimentAccession {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// A UniProtKB accession (`P69905`, `A0A023GPI8`), optionally naming one
+/// of the entry's isoforms (`P69905-2`). Serialised as written.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct UniprotId {
+    id: String,
+    accession: String,
+    isoform: Option<u32>,
+}
 
 impl UniprotId {
+    /// The accession with its isoform suffix, if any.
+    pub fn as_str(&self) -> &str {
+        &self.id
+    }
+
+    /// The entry's accession, without an isoform suffix.
+    pub fn accession(&self) -> &str {
+        &self.accession
+    }
+
+    pub fn isoform(&self) -> Option<u32> {
+        self.isoform
+    }
+}
+
+impl TryFrom<String> for UniprotId {
+    type Error = KiraError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<UniprotId> for String {
+    fn from(id: UniprotId) -> Self {
+        id.id
+    }
+}
+
+/// A UniProt proteome identifier: `UP` and nine digits.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProteomeId(String);
+
+impl ProteomeId {
     pub fn as_str(&self) -> &str {
         &self.0
     }
 }
 
+impl fmt::Display for ProteomeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for ProteomeId {
+    type Err = KiraError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.trim().to_uppercase();
+        let is_valid = normalized.strip_prefix("UP").is_some_and(|digits| {
+            digits.len() == 9 && digits.chars().all(|ch| ch.is_ascii_digit())
+        });
+        if !is_valid {
+            return Err(KiraError::InvalidProteomeId(value.to_string()));
+        }
+        Ok(Self(normalized))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Doi(String);
 
@@ -123,7 +292,7 @@ impl fmt::Display for Doi {
 
 impl fmt::Display for UniprotId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.id)
     }
 }
 
@@ -132,13 +301,88 @@ impl FromStr for UniprotId {
 
     fn from_str(value: &str) -> Result<Self, Self::Err> {
         let normalized = value.trim().to_uppercase();
-        let is_valid = normalized.len() >= 6
-            && normalized.len() <= 10
-            && normalized.chars().all(|ch| ch.is_ascii_alphanumeric());
-        if !is_valid {
+        let (accession, isoform) = match normalized.split_once('-') {
+            Some((accession, isoform)) => {
+                let isoform = Some(isoform)
+                    .filter(|digits| digits.chars().all(|ch| ch.is_ascii_digit()))
+                    .filter(|digits| !digits.starts_with('0'))
+                    .and_then(|digits| digits.parse::<u32>().ok())
+                    .ok_or_else(|| KiraError::InvalidUniprotId(value.to_string()))?;
+                (accession, Some(isoform))
+            }
+            None => (normalized.as_str(), None),
+        };
+        if !is_uniprot_accession(accession) {
             return Err(KiraError::InvalidUniprotId(value.to_string()));
         }
-        Ok(Self(normalized))
+        Ok(Self {
+            accession: accession.to_string(),
+            isoform,
+            id: normalized.clone(),
+        })
+    }
+}
+
+/// A KEGG organism code such as `hsa` or `mmu`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KeggOrganism(String);
+
+impl KeggOrganism {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for KeggOrganism {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for KeggOrganism {
+    type Err = KiraError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let code = value.trim();
+        let is_valid =
+            (3..=4).contains(&code.len()) && code.chars().all(|ch| ch.is_ascii_lowercase());
+        if !is_valid {
+            return Err(KiraError::InvalidKeggOrganism(value.to_string()));
+        }
+        Ok(Self(code.to_string()))
+    }
+}
+
+/// A species with a GOA annotation file, named as in `goa_<species>.gaf.gz`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct GoaSpecies(String);
+
+impl GoaSpecies {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for GoaSpecies {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for GoaSpecies {
+    type Err = KiraError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let name = value.trim().to_ascii_lowercase();
+        let is_valid = name.starts_with(|ch: char| ch.is_ascii_lowercase())
+            && name.len() <= 32
+            && name
+                .chars()
+                .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_');
+        if !is_valid {
+            return Err(KiraError::InvalidGoaSpecies(value.to_string()));
+        }
+        Ok(Self(name))
     }
 }
 
@@ -157,6 +401,21 @@ impl fmt::Display for GeoSeriesAccession {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GeoSampleAccession(String);
+
+impl GeoSampleAccession {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for GeoSampleAccession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 impl fmt::Display for SrrId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0)
@@ -168,7 +427,9 @@ impl FromStr for SrrId {
 
     fn from_str(value: &str) -> Result<Self, Self::Err> {
         let normalized = value.trim().to_uppercase();
-        let is_valid = (normalized.starts_with("SRR") || normalized.starts_with("ERR"))
+        let is_valid = ["SRR", "ERR", "DRR"]
+            .iter()
+            .any(|prefix| normalized.starts_with(prefix))
             && normalized.len() > 3
             && normalized[3..].chars().all(|ch| ch.is_ascii_digit());
         if !is_valid {
@@ -178,6 +439,30 @@ impl FromStr for SrrId {
     }
 }
 
+impl fmt::Display for SraExperimentAccession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for SraExperimentAccession {
+    type Err = KiraError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.trim().to_uppercase();
+        let digits = ["SRX", "ERX", "DRX"]
+            .iter()
+            .find_map(|prefix| normalized.strip_prefix(prefix));
+        let is_valid = digits
+            .map(|rest| !rest.is_empty() && rest.chars().all(|ch| ch.is_ascii_digit()))
+            .unwrap_or(false);
+        if !is_valid {
+            return Err(KiraError::InvalidSraExperimentAccession(value.to_string()));
+        }
+        Ok(Self(normalized))
+    }
+}
+
 impl fmt::Display for GenomeAccession {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0)
@@ -203,6 +488,116 @@ impl FromStr for GenomeAccession {
     }
 }
 
+impl fmt::Display for AssemblyName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for AssemblyName {
+    type Err = KiraError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.trim().to_string();
+        let is_valid = !normalized.is_empty()
+            && normalized
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-' | ' '));
+        if !is_valid {
+            return Err(KiraError::InvalidGenomeAccession(value.to_string()));
+        }
+        Ok(Self(normalized))
+    }
+}
+
+impl fmt::Display for DatasetAlias {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Aliases name export directories, so they stay plain file names.
+impl FromStr for DatasetAlias {
+    type Err = KiraError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.trim();
+        let is_valid = normalized
+            .chars()
+            .next()
+            .is_some_and(|ch| ch.is_ascii_alphanumeric())
+            && normalized
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-'));
+        if !is_valid {
+            return Err(KiraError::InvalidAlias(value.to_string()));
+        }
+        Ok(Self(normalized.to_string()))
+    }
+}
+
+impl fmt::Display for EnsemblGenome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.release {
+            Some(release) => write!(f, "{}@{release}", self.species),
+            None => write!(f, "{}", self.species),
+        }
+    }
+}
+
+impl FromStr for EnsemblGenome {
+    type Err = KiraError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || KiraError::InvalidSpecifier(format!("genome:ensembl:{value}"));
+        let normalized = value.trim().to_ascii_lowercase();
+        let (species, release) = match normalized.split_once('@') {
+            Some((species, release)) => {
+                let release = Some(release)
+                    .filter(|digits| digits.chars().all(|ch| ch.is_ascii_digit()))
+                    .and_then(|digits| digits.parse::<u32>().ok())
+                    .filter(|release| *release > 0)
+                    .ok_or_else(invalid)?;
+                (species, Some(release))
+            }
+            None => (normalized.as_str(), None),
+        };
+        let is_valid = species.starts_with(|ch: char| ch.is_ascii_lowercase())
+            && species
+                .chars()
+                .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_');
+        if !is_valid {
+            return Err(invalid());
+        }
+        Ok(Self {
+            species: species.to_string(),
+            release,
+        })
+    }
+}
+
+impl fmt::Display for TaxonQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for TaxonQuery {
+    type Err = KiraError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.trim().to_string();
+        let is_valid = !normalized.is_empty()
+            && normalized
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-' | ' '));
+        if !is_valid {
+            return Err(KiraError::InvalidSpecifier(format!("genome:taxon:{value}")));
+        }
+        Ok(Self(normalized))
+    }
+}
+
 impl FromStr for Doi {
     type Err = KiraError;
 
@@ -232,34 +627,334 @@ impl FromStr for GeoSeriesAccession {
     }
 }
 
+impl FromStr for GeoSampleAccession {
+    type Err = KiraError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.trim().to_uppercase();
+        let is_valid = normalized
+            .strip_prefix("GSM")
+            .is_some_and(|rest| !rest.is_empty() && rest.chars().all(|ch| ch.is_ascii_digit()));
+        if !is_valid {
+            return Err(KiraError::InvalidExpressionSampleAccession(
+                value.to_string(),
+            ));
+        }
+        Ok(Self(normalized))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BioProjectAccession(String);
+
+impl BioProjectAccession {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_ena(&self) -> bool {
+        self.0.starts_with("PRJEB")
+    }
+}
+
+impl fmt::Display for BioProjectAccession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for BioProjectAccession {
+    type Err = KiraError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.trim().to_uppercase();
+        let digits = ["PRJNA", "PRJEB", "PRJDB"]
+            .iter()
+            .find_map(|prefix| normalized.strip_prefix(prefix));
+        let is_valid = digits
+            .map(|rest| !rest.is_empty() && rest.chars().all(|ch| ch.is_ascii_digit()))
+            .unwrap_or(false);
+        if !is_valid {
+            return Err(KiraError::InvalidBioProjectAccession(value.to_string()));
+        }
+        Ok(Self(normalized))
+    }
+}
+
+/// A UniProt accession with an optional 1-based, inclusive residue range,
+/// written `P69905[50-120]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StructureQuery {
+    pub accession: UniprotId,
+    pub range: Option<(u64, u64)>,
+}
+
+impl fmt::Display for StructureQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.range {
+            Some((start, end)) => write!(f, "{}[{start}-{end}]", self.accession),
+            None => write!(f, "{}", self.accession),
+        }
+    }
+}
+
+impl FromStr for StructureQuery {
+    type Err = KiraError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let trimmed = value.trim();
+        let invalid = || KiraError::InvalidSpecifier(format!("structure-for:{value}"));
+        let Some((accession, rest)) = trimmed.split_once('[') else {
+            return Ok(Self {
+                accession: trimmed.parse()?,
+                range: None,
+            });
+        };
+        let (start, end) = rest
+            .strip_suffix(']')
+            .and_then(|range| range.split_once('-'))
+            .ok_or_else(invalid)?;
+        let start = start.trim().parse::<u64>().map_err(|_| invalid())?;
+        let end = end.trim().parse::<u64>().map_err(|_| invalid())?;
+        if start == 0 || start > end {
+            return Err(invalid());
+        }
+        Ok(Self {
+            accession: accession.parse()?,
+            range: Some((start, end)),
+        })
+    }
+}
+
+/// Dataset types a [`SpecifierPattern`] can name: those with records in
+/// the store.
+const PATTERN_TYPES: [&str; 12] = [
+    "protein",
+    "genome",
+    "srr",
+    "uniprot",
+    "uniprot-proteome",
+    "expression",
+    "expression10x",
+    "expressionsample",
+    "bioproject",
+    "go",
+    "kegg",
+    "reactome",
+];
+
+/// `type:glob`, naming every stored dataset of a type whose id matches the
+/// glob. `*` matches any run of characters and `?` exactly one; ids are
+/// compared ignoring ASCII case. Only commands that work on the local
+/// store take patterns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecifierPattern {
+    pub dataset_type: String,
+    pub glob: String,
+}
+
+impl SpecifierPattern {
+    /// Whether `value` is meant as a pattern rather than a specifier: its
+    /// id has a wildcard. DOIs may contain `?` and are never patterns.
+    pub fn is_pattern(value: &str) -> bool {
+        unquote(value.trim())
+            .split_once(':')
+            .is_some_and(|(kind, glob)| kind != "doi" && glob.contains(['*', '?']))
+    }
+
+    pub fn matches(&self, dataset_type: &str, id: &str) -> bool {
+        dataset_type == self.dataset_type
+            && glob_matches(
+                &self.glob.to_ascii_uppercase().chars().collect::<Vec<_>>(),
+                &id.to_ascii_uppercase().chars().collect::<Vec<_>>(),
+            )
+    }
+}
+
+impl fmt::Display for SpecifierPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.dataset_type, self.glob)
+    }
+}
+
+impl FromStr for SpecifierPattern {
+    type Err = KiraError;
+
+    /// Quotes around the whole pattern are dropped: `cmd.exe` passes
+    /// `'protein:*'` on with its single quotes.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| KiraError::InvalidSpecifier(format!("{value} ({reason})"));
+        let unquoted = unquote(value.trim());
+        if unquoted.starts_with(['\'', '"']) || unquoted.ends_with(['\'', '"']) {
+            return Err(invalid("unbalanced quotes"));
+        }
+        let (kind, glob) = unquoted
+            .split_once(':')
+            .ok_or_else(|| invalid("expected <type>:<glob>, e.g. protein:1L*"))?;
+        let kind = kind.trim().to_ascii_lowercase();
+        if !PATTERN_TYPES.contains(&kind.as_str()) {
+            return Err(invalid(&format!(
+                "patterns name one of {}",
+                PATTERN_TYPES.join(", ")
+            )));
+        }
+        let glob = glob.trim();
+        if glob.is_empty() {
+            return Err(invalid("empty glob"));
+        }
+        Ok(Self {
+            dataset_type: kind,
+            glob: glob.to_string(),
+        })
+    }
+}
+
+/// `value` without one pair of matching quotes around it.
+fn unquote(value: &str) -> &str {
+    for quote in ['\'', '"'] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+fn glob_matches(glob: &[char], text: &[char]) -> bool {
+    match glob.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|skip| glob_matches(rest, &text[skip..])),
+        Some((&expected, rest)) => text.split_first().is_some_and(|(&actual, tail)| {
+            (expected == '?' || expected == actual) && glob_matches(rest, tail)
+        }),
+    }
+}
+
+/// How a value typed on the command line names a dataset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpecifierReading {
+    /// Written with its type, or a dataset named by its type alone.
+    Explicit(DatasetSpecifier),
+    /// A bare id only one dataset type fits.
+    Inferred(DatasetSpecifier),
+    /// A bare id several dataset types fit, most likely first.
+    Ambiguous(Vec<DatasetSpecifier>),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DatasetSpecifier {
     Protein(ProteinId),
     Genome(GenomeAccession),
+    GenomeName(AssemblyName),
+    /// Several assemblies of one taxon.
+    GenomeTaxon(TaxonQuery),
+    GenomeEnsembl(EnsemblGenome),
     Srr(SrrId),
+    /// The runs of an SRA experiment, each fetched as an `srr` dataset.
+    SrrExperiment(SraExperimentAccession),
     Uniprot(UniprotId),
+    /// A whole UniProt proteome, fetched as one FASTA.
+    Proteome(ProteomeId),
     Doi(Doi),
     Expression(GeoSeriesAccession),
     Expression10x(GeoSeriesAccession),
+    ExpressionSample(GeoSampleAccession),
+    BioProject(BioProjectAccession),
+    StructureFor(StructureQuery),
     Go,
-    Kegg,
+    /// The generic KEGG pathways, or one organism's with a code.
+    Kegg(Option<KeggOrganism>),
     Reactome,
+    /// A dataset named by the `alias` its config entry gives it, resolved
+    /// against the project's metadata.
+    Alias(DatasetAlias),
 }
 
 impl DatasetSpecifier {
     pub fn dataset_type(&self) -> &'static str {
         match self {
             DatasetSpecifier::Protein(_) => "protein",
-            DatasetSpecifier::Genome(_) => "genome",
+            DatasetSpecifier::Genome(_)
+            | DatasetSpecifier::GenomeName(_)
+            | DatasetSpecifier::GenomeTaxon(_)
+            | DatasetSpecifier::GenomeEnsembl(_) => "genome",
             DatasetSpecifier::Srr(_) => "srr",
+            DatasetSpecifier::SrrExperiment(_) => "srx",
             DatasetSpecifier::Uniprot(_) => "uniprot",
+            DatasetSpecifier::Proteome(_) => "uniprot-proteome",
             DatasetSpecifier::Doi(_) => "doi",
             DatasetSpecifier::Expression(_) => "expression",
             DatasetSpecifier::Expression10x(_) => "expression10x",
+            DatasetSpecifier::ExpressionSample(_) => "expressionsample",
+            DatasetSpecifier::BioProject(_) => "bioproject",
+            DatasetSpecifier::StructureFor(_) => "structure-for",
             DatasetSpecifier::Go => "go",
-            DatasetSpecifier::Kegg => "kegg",
+            DatasetSpecifier::Kegg(_) => "kegg",
             DatasetSpecifier::Reactome => "reactome",
+            DatasetSpecifier::Alias(_) => "alias",
+        }
+    }
+
+    /// Whether `--metadata-only` can fetch this dataset's metadata alone.
+    /// `doi:`, `bioproject:`, `structure-for:` and `genome:taxon:` pass the
+    /// flag on to the datasets they resolve to.
+    pub fn supports_metadata_only(&self) -> bool {
+        !matches!(
+            self,
+            DatasetSpecifier::Proteome(_)
+                | DatasetSpecifier::GenomeEnsembl(_)
+                | DatasetSpecifier::Expression10x(_)
+                | DatasetSpecifier::ExpressionSample(_)
+                | DatasetSpecifier::Go
+                | DatasetSpecifier::Kegg(_)
+                | DatasetSpecifier::Reactome
+        )
+    }
+
+    /// Parses `value` as a specifier, or infers the dataset type of a bare
+    /// identifier from its shape: SRR/ERR/DRR runs, SRX/ERX/DRX experiments,
+    /// GSE series, GSM samples, GCF_/GCA_ assemblies, PRJ BioProjects, `10.`
+    /// DOIs, PDB ids (classic or `pdb_` extended), UniProt accessions and
+    /// `UP` proteome ids.
+    pub fn infer(value: &str) -> Result<Self, KiraError> {
+        let trimmed = value.trim();
+        if let Ok(specifier) = trimmed.parse::<DatasetSpecifier>() {
+            return Ok(specifier);
+        }
+        if trimmed.contains(':') && !trimmed.starts_with("10.") {
+            return trimmed.parse();
+        }
+        let mut first_error = None;
+        for reading in bare_readings(trimmed) {
+            match reading {
+                Ok(specifier) => return Ok(specifier),
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
         }
+        Err(first_error.unwrap_or_else(|| KiraError::InvalidSpecifier(value.to_string())))
+    }
+
+    /// Every specifier a bare identifier could stand for, most likely
+    /// first. Empty for values written with their type, and for ids no
+    /// pattern fits.
+    pub fn candidates(value: &str) -> Vec<Self> {
+        let trimmed = value.trim();
+        if trimmed.parse::<DatasetSpecifier>().is_ok()
+            || (trimmed.contains(':') && !trimmed.starts_with("10."))
+        {
+            return Vec::new();
+        }
+        let mut candidates = Vec::new();
+        for specifier in bare_readings(trimmed).into_iter().flatten() {
+            if !candidates.contains(&specifier) {
+                candidates.push(specifier);
+            }
+        }
+        candidates
     }
 
     pub fn resolve_registry(&self, format: Option<ProteinFormat>) -> Registry {
@@ -267,15 +962,23 @@ impl DatasetSpecifier {
             DatasetSpecifier::Protein(_) => match format.unwrap_or(ProteinFormat::Cif) {
                 ProteinFormat::Cif | ProteinFormat::Pdb | ProteinFormat::Bcif => Registry::Rcsb,
             },
-            DatasetSpecifier::Genome(_) => Registry::Ncbi,
-            DatasetSpecifier::Srr(_) => Registry::Ncbi,
-            DatasetSpecifier::Uniprot(_) => Registry::Uniprot,
+            DatasetSpecifier::Genome(_)
+            | DatasetSpecifier::GenomeName(_)
+            | DatasetSpecifier::GenomeTaxon(_) => Registry::Ncbi,
+            DatasetSpecifier::GenomeEnsembl(_) => Registry::Ensembl,
+            DatasetSpecifier::Srr(_) | DatasetSpecifier::SrrExperiment(_) => Registry::Ncbi,
+            DatasetSpecifier::Uniprot(_) | DatasetSpecifier::Proteome(_) => Registry::Uniprot,
             DatasetSpecifier::Doi(_) => Registry::Doi,
             DatasetSpecifier::Expression(_) => Registry::Geo,
             DatasetSpecifier::Expression10x(_) => Registry::Geo,
+            DatasetSpecifier::ExpressionSample(_) => Registry::Geo,
+            DatasetSpecifier::BioProject(acc) if acc.is_ena() => Registry::Ena,
+            DatasetSpecifier::BioProject(_) => Registry::Ncbi,
+            DatasetSpecifier::StructureFor(_) => Registry::Uniprot,
             DatasetSpecifier::Go => Registry::Go,
-            DatasetSpecifier::Kegg => Registry::Kegg,
+            DatasetSpecifier::Kegg(_) => Registry::Kegg,
             DatasetSpecifier::Reactome => Registry::Reactome,
+            DatasetSpecifier::Alias(_) => Registry::Alias,
         }
     }
 }
@@ -287,9 +990,48 @@ pub enum Registry {
     Uniprot,
     Doi,
     Geo,
+    Ena,
+    Ensembl,
     Go,
     Kegg,
     Reactome,
+    /// Stands for the registry of the dataset an alias resolves to.
+    Alias,
+}
+
+impl fmt::Display for DatasetSpecifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id = match self {
+            DatasetSpecifier::Protein(id) => id.as_str(),
+            DatasetSpecifier::Genome(acc) => acc.as_str(),
+            DatasetSpecifier::GenomeName(name) => name.as_str(),
+            DatasetSpecifier::GenomeTaxon(taxon) => {
+                return write!(f, "genome:taxon:{taxon}");
+            }
+            DatasetSpecifier::GenomeEnsembl(genome) => {
+                return write!(f, "genome:ensembl:{genome}");
+            }
+            DatasetSpecifier::Srr(id) => id.as_str(),
+            DatasetSpecifier::SrrExperiment(acc) => acc.as_str(),
+            DatasetSpecifier::Uniprot(id) => id.as_str(),
+            DatasetSpecifier::Proteome(id) => id.as_str(),
+            DatasetSpecifier::Doi(doi) => doi.as_str(),
+            DatasetSpecifier::Expression(acc) | DatasetSpecifier::Expression10x(acc) => {
+                acc.as_str()
+            }
+            DatasetSpecifier::ExpressionSample(acc) => acc.as_str(),
+            DatasetSpecifier::BioProject(acc) => acc.as_str(),
+            DatasetSpecifier::StructureFor(query) => {
+                return write!(f, "{}:{query}", self.dataset_type());
+            }
+            DatasetSpecifier::Kegg(Some(organism)) => organism.as_str(),
+            DatasetSpecifier::Alias(alias) => alias.as_str(),
+            DatasetSpecifier::Go | DatasetSpecifier::Kegg(None) | DatasetSpecifier::Reactome => {
+                return write!(f, "{}", self.dataset_type());
+            }
+        };
+        write!(f, "{}:{id}", self.dataset_type())
+    }
 }
 
 impl FromStr for DatasetSpecifier {
@@ -300,20 +1042,123 @@ impl FromStr for DatasetSpecifier {
         if let Some((kind, rest)) = trimmed.split_once(':') {
             return match kind {
                 "protein" => Ok(DatasetSpecifier::Protein(rest.parse()?)),
-                "genome" => Ok(DatasetSpecifier::Genome(rest.parse()?)),
+                "genome" if rest.trim_start().starts_with("taxon:") => Ok(
+                    DatasetSpecifier::GenomeTaxon(rest.trim_start()["taxon:".len()..].parse()?),
+                ),
+                "genome" if rest.trim_start().starts_with("ensembl:") => Ok(
+                    DatasetSpecifier::GenomeEnsembl(rest.trim_start()["ensembl:".len()..].parse()?),
+                ),
+                "genome" if is_genome_accession_like(rest) => {
+                    Ok(DatasetSpecifier::Genome(rest.parse()?))
+                }
+                "genome" => Ok(DatasetSpecifier::GenomeName(rest.parse()?)),
+                "srr" if is_experiment_accession_like(rest) => {
+                    Ok(DatasetSpecifier::SrrExperiment(rest.parse()?))
+                }
                 "srr" => Ok(DatasetSpecifier::Srr(rest.parse()?)),
+                "srx" => Ok(DatasetSpecifier::SrrExperiment(rest.parse()?)),
                 "uniprot" => Ok(DatasetSpecifier::Uniprot(rest.parse()?)),
+                "uniprot-proteome" => Ok(DatasetSpecifier::Proteome(rest.parse()?)),
                 "doi" => Ok(DatasetSpecifier::Doi(rest.parse()?)),
+                "expression" if rest.trim().to_uppercase().starts_with("GSM") => {
+                    Ok(DatasetSpecifier::ExpressionSample(rest.parse()?))
+                }
                 "expression" => Ok(DatasetSpecifier::Expression(rest.parse()?)),
                 "expression10x" => Ok(DatasetSpecifier::Expression10x(rest.parse()?)),
+                "expressionsample" => Ok(DatasetSpecifier::ExpressionSample(rest.parse()?)),
+                "bioproject" => Ok(DatasetSpecifier::BioProject(rest.parse()?)),
+                "structure-for" => Ok(DatasetSpecifier::StructureFor(rest.parse()?)),
+                "kegg" => Ok(DatasetSpecifier::Kegg(Some(rest.parse()?))),
+                "alias" => Ok(DatasetSpecifier::Alias(rest.parse()?)),
                 _ => Err(KiraError::InvalidSpecifier(value.to_string())),
             };
         }
         match trimmed {
             "go" => Ok(DatasetSpecifier::Go),
-            "kegg" => Ok(DatasetSpecifier::Kegg),
+            "kegg" => Ok(DatasetSpecifier::Kegg(None)),
             "reactome" => Ok(DatasetSpecifier::Reactome),
             _ => Err(KiraError::InvalidSpecifier(value.to_string())),
         }
     }
 }
+
+/// A bare identifier read as each dataset type whose pattern it fits, in
+/// order of preference; a reading is an error when the id fits the pattern
+/// but not the type's own rules.
+fn bare_readings(trimmed: &str) -> Vec<Result<DatasetSpecifier, KiraError>> {
+    let upper = trimmed.to_ascii_uppercase();
+    let mut readings = Vec::new();
+    if upper.starts_with("SRR") || upper.starts_with("ERR") || upper.starts_with("DRR") {
+        readings.push(trimmed.parse().map(DatasetSpecifier::Srr));
+    }
+    if is_experiment_accession_like(trimmed) {
+        readings.push(trimmed.parse().map(DatasetSpecifier::SrrExperiment));
+    }
+    if upper.starts_with("GSE") {
+        readings.push(trimmed.parse().map(DatasetSpecifier::Expression));
+    }
+    if upper.starts_with("GSM") {
+        readings.push(trimmed.parse().map(DatasetSpecifier::ExpressionSample));
+    }
+    if is_genome_accession_like(trimmed) {
+        readings.push(trimmed.parse().map(DatasetSpecifier::Genome));
+    }
+    if upper.starts_with("PRJ") {
+        readings.push(trimmed.parse().map(DatasetSpecifier::BioProject));
+    }
+    if trimmed.starts_with("10.") {
+        readings.push(trimmed.parse().map(DatasetSpecifier::Doi));
+    }
+    if is_pdb_id_like(trimmed) {
+        readings.push(trimmed.parse().map(DatasetSpecifier::Protein));
+    }
+    if upper.starts_with("UP") && upper[2..].chars().all(|ch| ch.is_ascii_digit()) {
+        readings.push(trimmed.parse().map(DatasetSpecifier::Proteome));
+    }
+    if is_uniprot_accession(
+        upper
+            .split_once('-')
+            .map_or(upper.as_str(), |(base, _)| base),
+    ) {
+        readings.push(trimmed.parse().map(DatasetSpecifier::Uniprot));
+    }
+    readings
+}
+
+/// The UniProt accession pattern: `[OPQ][0-9][A-Z0-9]{3}[0-9]` or
+/// `[A-NR-Z][0-9]([A-Z][A-Z0-9]{2}[0-9]){1,2}`.
+fn is_uniprot_accession(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    let alnum = |byte: &u8| byte.is_ascii_uppercase() || byte.is_ascii_digit();
+    match bytes {
+        [b'O' | b'P' | b'Q', digit, middle @ .., last] if bytes.len() == 6 => {
+            digit.is_ascii_digit() && middle.iter().all(alnum) && last.is_ascii_digit()
+        }
+        [b'O' | b'P' | b'Q', ..] => false,
+        [first, digit, blocks @ ..] => {
+            first.is_ascii_uppercase()
+                && digit.is_ascii_digit()
+                && (blocks.len() == 4 || blocks.len() == 8)
+                && blocks.chunks(4).all(|block| {
+                    block[0].is_ascii_uppercase()
+                        && block[1..3].iter().all(alnum)
+                        && block[3].is_ascii_digit()
+                })
+        }
+        _ => false,
+    }
+}
+
+/// Values starting like an accession are parsed as one, so a mistyped
+/// accession is reported instead of being looked up as a name.
+fn is_genome_accession_like(value: &str) -> bool {
+    let upper = value.trim().to_ascii_uppercase();
+    upper.starts_with("GCF_") || upper.starts_with("GCA_")
+}
+
+fn is_experiment_accession_like(value: &str) -> bool {
+    let upper = value.trim().to_ascii_uppercase();
+    ["SRX", "ERX", "DRX"]
+        .iter()
+        .any(|prefix| upper.starts_with(prefix))
+}
@@ -1,13 +1,27 @@
+use std::fmt::Display;
 use std::path::PathBuf;
 
 use miette::Diagnostic;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-#[derive(Debug, Error, Diagnostic)]
+use crate::http::NCBI_API_KEY_ENV;
+
+#[derive(Debug, Error)]
 pub enum KiraError {
     #[error("invalid dataset specifier: {0}")]
     InvalidSpecifier(String),
 
+    #[error(
+        "{value} could be any of {}; write it with its type",
+        .candidates.join(", ")
+    )]
+    AmbiguousSpecifier {
+        value: String,
+        candidates: Vec<String>,
+    },
+
     #[error("invalid protein id: {0}")]
     InvalidProteinId(String),
 
@@ -17,66 +31,259 @@ pub enum KiraError {
     #[error("invalid SRR accession: {0}")]
     InvalidSrrId(String),
 
-    #[error("invalid UniProt accession: {0}")]
+    #[error("invalid SRA experiment accession: {0} (expected SRX, ERX or DRX followed by digits)")]
+    InvalidSraExperimentAccession(String),
+
+    #[error(
+        "invalid UniProt accession: {0} (expected e.g. P69905 or A0A023GPI8, optionally with an isoform number such as P69905-2)"
+    )]
     InvalidUniprotId(String),
 
+    #[error(
+        "invalid UniProt proteome id: {0} (expected UP followed by 9 digits, e.g. UP000005640)"
+    )]
+    InvalidProteomeId(String),
+
     #[error("invalid DOI: {0}")]
     InvalidDoi(String),
 
     #[error("invalid GEO series accession: {0}")]
     InvalidExpressionAccession(String),
 
-    #[error("missing config file kira-bm.json in current directory")]
+    #[error("invalid GEO sample accession: {0}")]
+    InvalidExpressionSampleAccession(String),
+
+    #[error("invalid BioProject accession: {0}")]
+    InvalidBioProjectAccession(String),
+
+    #[error("invalid KEGG organism code: {0} (expected 3-4 lowercase letters, e.g. hsa)")]
+    InvalidKeggOrganism(String),
+
+    #[error("invalid alias: {0} (use letters, digits, '.', '_' and '-')")]
+    InvalidAlias(String),
+
+    #[error("invalid GO annotation species: {0} (expected a GOA species name, e.g. human)")]
+    InvalidGoaSpecies(String),
+
+    #[error("invalid proxy URL: {0}")]
+    InvalidProxy(String),
+
+    #[error("invalid {registry} base URL {url}: {message}")]
+    InvalidRegistryBase {
+        registry: String,
+        url: String,
+        message: String,
+    },
+
+    #[error("cannot load CA bundle {path}: {message}")]
+    CaBundle { path: PathBuf, message: String },
+
+    #[error("missing config file kira-bm.json in the current directory or its parents")]
     MissingConfig,
 
     #[error("failed to read config file at {0}")]
     ConfigRead(PathBuf),
 
+    #[error("{path} is gzip-compressed but its stream is corrupt: {message}")]
+    CorruptGzip { path: PathBuf, message: String },
+
+    #[error("refusing to unpack {path}: entry {entry:?} is unsafe ({reason})")]
+    UnsafeArchiveEntry {
+        path: PathBuf,
+        entry: String,
+        reason: String,
+    },
+
+    #[error(
+        "refusing to unpack {path}: it {message}; raise `archives` in the user config to allow it"
+    )]
+    ArchiveTooLarge { path: PathBuf, message: String },
+
     #[error("NCBI request failed: {0}")]
     NcbiHttp(String),
 
-    #[error("NCBI returned status {status}: {message}")]
-    NcbiStatus { status: u16, message: String },
+    #[error("NCBI returned status {status} for {url}: {message}")]
+    NcbiStatus {
+        status: u16,
+        url: String,
+        dataset: String,
+        message: String,
+    },
+
+    #[error(
+        "NCBI refused genome:{accession} with include {} (status {status}): {message}",
+        .include.join(",")
+    )]
+    NcbiGenomeRejected {
+        accession: String,
+        include: Vec<String>,
+        status: u16,
+        url: String,
+        message: String,
+    },
 
     #[error("RCSB request failed: {0}")]
     RcsbHttp(String),
 
-    #[error("RCSB returned status {status}: {message}")]
-    RcsbStatus { status: u16, message: String },
+    #[error("RCSB returned status {status} for {url}: {message}")]
+    RcsbStatus {
+        status: u16,
+        url: String,
+        dataset: String,
+        message: String,
+    },
 
     #[error("Crossref request failed: {0}")]
     CrossrefHttp(String),
 
-    #[error("Crossref returned status {status}: {message}")]
-    CrossrefStatus { status: u16, message: String },
+    #[error("Crossref returned status {status} for {url}: {message}")]
+    CrossrefStatus {
+        status: u16,
+        url: String,
+        dataset: String,
+        message: String,
+    },
+
+    #[error("DataCite request failed: {0}")]
+    DataciteHttp(String),
+
+    #[error("DataCite returned status {status} for {url}: {message}")]
+    DataciteStatus {
+        status: u16,
+        url: String,
+        dataset: String,
+        message: String,
+    },
 
     #[error("GEO request failed: {0}")]
     GeoHttp(String),
 
-    #[error("GEO returned status {status}: {message}")]
-    GeoStatus { status: u16, message: String },
+    #[error("GEO returned status {status} for {url}: {message}")]
+    GeoStatus {
+        status: u16,
+        url: String,
+        dataset: String,
+        message: String,
+    },
 
     #[error("{0}")]
     GeoResolution(String),
 
+    #[error("Ensembl request failed: {0}")]
+    EnsemblHttp(String),
+
+    #[error("Ensembl returned status {status} for {url}: {message}")]
+    EnsemblStatus {
+        status: u16,
+        url: String,
+        dataset: String,
+        message: String,
+    },
+
+    #[error("Ensembl release {release} has no {file} file for {species} in {url}")]
+    EnsemblFileMissing {
+        species: String,
+        release: u32,
+        file: String,
+        url: String,
+    },
+
     #[error("knowledge base request failed: {0}")]
     KnowledgeHttp(String),
 
-    #[error("knowledge base returned status {status}: {message}")]
-    KnowledgeStatus { status: u16, message: String },
+    #[error("knowledge base returned status {status} for {url}: {message}")]
+    KnowledgeStatus {
+        status: u16,
+        url: String,
+        dataset: String,
+        message: String,
+    },
+
+    #[error("knowledge base download incomplete: {0}")]
+    KnowledgeIncomplete(String),
+
+    #[error(
+        "Reactome lists no pathways for species {0:?} (use the name in ReactomePathways.txt, e.g. \"Homo sapiens\")"
+    )]
+    UnknownReactomeSpecies(String),
+
+    #[error("cannot convert {from} to {to}: {reason}")]
+    Conversion {
+        from: String,
+        to: String,
+        reason: String,
+    },
+
+    #[error("downloaded file for {dataset} is not valid: {reason}")]
+    InvalidDownload { dataset: String, reason: String },
+
+    #[error("cannot import {dataset}: {reason}")]
+    InvalidImport { dataset: String, reason: String },
 
     #[error("dataset not found locally: {0}")]
     DatasetNotFound(String),
 
+    #[error("no dataset in the project store matches {0}")]
+    NoMatchingDatasets(String),
+
+    #[error("{0} is pinned; unpin it first or pass --include-pinned")]
+    DatasetPinned(String),
+
+    #[error("no run manifest {0} (see `kira-bm runs list`)")]
+    RunNotFound(String),
+
     #[error("failed to parse JSON config: {0}")]
     ConfigParse(String),
 
+    #[error("invalid config entry: {0}")]
+    ConfigEntry(String),
+
+    #[error("{path} is invalid:\n{}", .errors.join("\n"))]
+    InvalidConfig { path: PathBuf, errors: Vec<String> },
+
+    #[error("failed to read ID list at {0}")]
+    IdListRead(PathBuf),
+
+    #[error("{path} has unparseable entries:\n{}", .errors.join("\n"))]
+    InvalidIdList { path: PathBuf, errors: Vec<String> },
+
     #[error("filesystem error: {0}")]
     Filesystem(String),
 
     #[error("invalid include value: {0}")]
     InvalidInclude(String),
 
+    #[error("no running batch in this project")]
+    NoActiveBatch,
+
+    #[error(
+        "batch stayed paused for {waited_secs}s; finished items are kept, re-run the same fetch to continue"
+    )]
+    BatchPauseTimeout { waited_secs: u64 },
+
+    #[error("cancelled; finished items are kept, re-run the same fetch to continue")]
+    Cancelled,
+
+    #[error("{failed} of {total} datasets failed; the rest were fetched")]
+    FetchIncomplete { failed: usize, total: usize },
+
+    #[error(
+        "{dataset} is locked by another kira-bm process ({holder}); gave up after {waited_secs}s, retry later or raise --lock-timeout"
+    )]
+    StoreLocked {
+        dataset: String,
+        holder: String,
+        waited_secs: u64,
+    },
+
+    #[error("invalid NCBI dataset catalog: {0}")]
+    InvalidCatalog(String),
+
+    #[error(
+        "genome {accession} is missing requested artifact {artifact}; re-run with --allow-missing-artifacts to keep the package"
+    )]
+    MissingArtifact { accession: String, artifact: String },
+
     #[error("invalid format for dataset type: {0}")]
     InvalidFormat(String),
 
@@ -86,15 +293,282 @@ pub enum KiraError {
     #[error("sra conversion failed: {0}")]
     SrrConversion(String),
 
+    #[error(
+        "not enough free space for fasterq-dump in {}: about {} needed, {} available; pass --srr-temp-dir to use a larger disk",
+        .path.display(),
+        crate::fs_util::bytes_to_human(*.required),
+        crate::fs_util::bytes_to_human(*.available)
+    )]
+    InsufficientScratchSpace {
+        path: PathBuf,
+        required: u64,
+        available: u64,
+    },
+
+    #[error("ENA request failed: {0}")]
+    EnaHttp(String),
+
     #[error("uniprot request failed: {0}")]
     UniprotHttp(String),
 
-    #[error("uniprot returned status {status}: {message}")]
-    UniprotStatus { status: u16, message: String },
+    #[error("uniprot returned status {status} for {url}: {message}")]
+    UniprotStatus {
+        status: u16,
+        url: String,
+        dataset: String,
+        message: String,
+    },
+
+    #[error("{0} doctor checks failed")]
+    ChecksFailed(usize),
+
+    #[error("unreachable: {}", .0.join(", "))]
+    RegistriesUnreachable(Vec<String>),
+
+    #[error("older than supported: {}", .0.join(", "))]
+    OutdatedTools(Vec<String>),
 
     #[error("{0}")]
     DoiResolution(String),
 
+    #[error("{0}")]
+    NoCoveringStructure(String),
+
+    #[error("no genome assembly named {0}")]
+    UnknownGenomeName(String),
+
+    #[error(
+        "genome name {name} matches several assemblies: {}; fetch one by accession",
+        .candidates.join(", ")
+    )]
+    AmbiguousGenomeName {
+        name: String,
+        candidates: Vec<String>,
+    },
+
+    #[error("no RefSeq assemblies match {0}")]
+    NoMatchingAssemblies(String),
+
+    #[error("--metadata-only is not supported for {0} datasets")]
+    MetadataOnlyUnsupported(String),
+
     #[error("protein format not supported by NCBI MMDB: {0}")]
     UnsupportedProteinFormat(String),
+
+    #[error("{specifier} [{phase}]: {error}")]
+    Contextual {
+        specifier: String,
+        phase: String,
+        error: Box<KiraError>,
+    },
+}
+
+impl KiraError {
+    /// Names the dataset and fetch phase an error escaped from. An error
+    /// that already carries context keeps it: the innermost fetch knows best.
+    pub fn with_context(self, specifier: impl Into<String>, phase: impl Into<String>) -> Self {
+        if matches!(self, KiraError::Contextual { .. }) {
+            return self;
+        }
+        KiraError::Contextual {
+            specifier: specifier.into(),
+            phase: phase.into(),
+            error: Box::new(self),
+        }
+    }
+
+    /// The underlying error, without its context.
+    pub fn root(&self) -> &KiraError {
+        match self {
+            KiraError::Contextual { error, .. } => error.root(),
+            other => other,
+        }
+    }
+
+    /// Whether trying again later may succeed: the registry could not be
+    /// reached or timed out, answered 429 or a server error, or the dataset
+    /// was locked by another process.
+    pub fn is_retryable(&self) -> bool {
+        match self.root() {
+            KiraError::NcbiHttp(_)
+            | KiraError::RcsbHttp(_)
+            | KiraError::UniprotHttp(_)
+            | KiraError::CrossrefHttp(_)
+            | KiraError::DataciteHttp(_)
+            | KiraError::GeoHttp(_)
+            | KiraError::KnowledgeHttp(_)
+            | KiraError::EnsemblHttp(_)
+            | KiraError::EnaHttp(_)
+            | KiraError::RegistriesUnreachable(_)
+            | KiraError::StoreLocked { .. } => true,
+            err => err
+                .status()
+                .is_some_and(|(_, status, _, _)| status == 429 || status >= 500),
+        }
+    }
+
+    /// What to try next, shown under the error.
+    pub fn help(&self) -> Option<String> {
+        if let KiraError::Contextual { error, .. } = self {
+            return error.help();
+        }
+        let (registry, status, url, dataset) = self.status()?;
+        let help = match (self, status) {
+            (KiraError::RcsbStatus { .. }, 404) if url.ends_with(".cif") => {
+                "RCSB has no mmCIF file for this entry; it may still be served in another format, try `--format pdb`".to_string()
+            }
+            (KiraError::RcsbStatus { .. }, 404) if url.ends_with(".pdb") => {
+                "large structures have no legacy PDB-format file; try `--format cif`".to_string()
+            }
+            (KiraError::RcsbStatus { .. }, 404) => {
+                format!("check that {dataset} is a released PDB entry")
+            }
+            (KiraError::NcbiStatus { .. }, 404) => format!(
+                "check that {dataset} is a current assembly accession; suppressed or replaced assemblies are not served"
+            ),
+            (KiraError::NcbiGenomeRejected { include, .. }, 400) => format!(
+                "check the genome include values ({}) in kira-bm.json or --include; kira-bm knows genome, rna, protein, cds, gff3, gtf, gbff and seq-report",
+                include.join(",")
+            ),
+            (KiraError::NcbiGenomeRejected { .. }, 404) => format!(
+                "check that genome:{dataset} is a current assembly accession; suppressed or replaced assemblies are not served"
+            ),
+            (KiraError::NcbiStatus { .. }, 401 | 403) => {
+                format!("NCBI rejected the request; check {NCBI_API_KEY_ENV}")
+            }
+            (KiraError::UniprotStatus { .. }, 404) => format!(
+                "check that {dataset} is an active UniProtKB accession; merged or deleted entries are not served"
+            ),
+            (KiraError::CrossrefStatus { .. }, 404) => {
+                "neither Crossref nor DataCite has a record of this DOI; DOIs registered with other agencies cannot be resolved".to_string()
+            }
+            (KiraError::GeoStatus { .. }, 404) => {
+                format!("GEO has no {dataset}; check that the accession is public and not withdrawn")
+            }
+            (KiraError::EnsemblStatus { .. }, 404) => {
+                "check the species as Ensembl names it (e.g. homo_sapiens) and that the release exists; species from Ensembl Genomes (plants, fungi, ...) are not served".to_string()
+            }
+            (KiraError::KnowledgeStatus { .. }, 404) => format!(
+                "the {dataset} download location may have moved; check for a newer kira-bm release"
+            ),
+            (_, 429) => format!("{registry} is rate limiting requests; wait a minute and retry"),
+            (_, 500..) => {
+                format!("{registry} reported a server error; this is usually temporary, retry later")
+            }
+            _ => return None,
+        };
+        Some(help)
+    }
+
+    /// Registry, status, URL and dataset of an HTTP status error.
+    fn status(&self) -> Option<(&'static str, u16, &str, &str)> {
+        let (registry, status, url, dataset) = match self {
+            KiraError::NcbiStatus {
+                status,
+                url,
+                dataset,
+                ..
+            } => ("NCBI", status, url, dataset),
+            KiraError::NcbiGenomeRejected {
+                status,
+                url,
+                accession,
+                ..
+            } => ("NCBI", status, url, accession),
+            KiraError::RcsbStatus {
+                status,
+                url,
+                dataset,
+                ..
+            } => ("RCSB", status, url, dataset),
+            KiraError::UniprotStatus {
+                status,
+                url,
+                dataset,
+                ..
+            } => ("UniProt", status, url, dataset),
+            KiraError::CrossrefStatus {
+                status,
+                url,
+                dataset,
+                ..
+            } => ("Crossref", status, url, dataset),
+            KiraError::DataciteStatus {
+                status,
+                url,
+                dataset,
+                ..
+            } => ("DataCite", status, url, dataset),
+            KiraError::GeoStatus {
+                status,
+                url,
+                dataset,
+                ..
+            } => ("GEO", status, url, dataset),
+            KiraError::EnsemblStatus {
+                status,
+                url,
+                dataset,
+                ..
+            } => ("Ensembl", status, url, dataset),
+            KiraError::KnowledgeStatus {
+                status,
+                url,
+                dataset,
+                ..
+            } => ("the knowledge base server", status, url, dataset),
+            _ => return None,
+        };
+        Some((registry, *status, url, dataset))
+    }
+
+    /// The dataset specifier and phase attached by [`KiraError::with_context`].
+    pub fn context(&self) -> Option<(&str, &str)> {
+        match self {
+            KiraError::Contextual {
+                specifier, phase, ..
+            } => Some((specifier, phase)),
+            _ => None,
+        }
+    }
+}
+
+impl Diagnostic for KiraError {
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        KiraError::help(self).map(|help| Box::new(help) as Box<dyn Display>)
+    }
+}
+
+pub trait ErrorContext<T> {
+    fn with_context(self, specifier: &str, phase: &str) -> Result<T, KiraError>;
+}
+
+impl<T> ErrorContext<T> for Result<T, KiraError> {
+    fn with_context(self, specifier: &str, phase: &str) -> Result<T, KiraError> {
+        self.map_err(|err| err.with_context(specifier, phase))
+    }
+}
+
+/// An error as printed by `--non-interactive` mode.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ErrorReport {
+    pub error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub specifier: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+}
+
+impl From<&KiraError> for ErrorReport {
+    fn from(error: &KiraError) -> Self {
+        let context = error.context();
+        Self {
+            error: error.root().to_string(),
+            specifier: context.map(|(specifier, _)| specifier.to_string()),
+            phase: context.map(|(_, phase)| phase.to_string()),
+            help: error.help(),
+        }
+    }
 }
@@ -1,29 +1,113 @@
-use std::fs;
-use std::io;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::path::Path;
 
+use camino::Utf8Path;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
 use zip::ZipArchive;
 
 use crate::error::KiraError;
 
-pub fn extract_zip(zip_path: &Path, target_dir: &Path) -> Result<(), KiraError> {
+/// How much a zip archive may unpack to before extraction gives up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZipLimits {
+    /// Total bytes of all entries once decompressed.
+    pub max_unpacked_bytes: u64,
+    pub max_entries: usize,
+}
+
+impl ZipLimits {
+    /// Generous enough for a full NCBI genome package with annotation.
+    pub const DEFAULT_MAX_UNPACKED_BYTES: u64 = 64 * 1024 * 1024 * 1024;
+    pub const DEFAULT_MAX_ENTRIES: usize = 100_000;
+}
+
+impl Default for ZipLimits {
+    fn default() -> Self {
+        Self {
+            max_unpacked_bytes: Self::DEFAULT_MAX_UNPACKED_BYTES,
+            max_entries: Self::DEFAULT_MAX_ENTRIES,
+        }
+    }
+}
+
+/// SHA-256 digests of files kira-bm wrote, keyed by path relative to the
+/// directory they were written into, `/`-separated.
+pub type Digests = BTreeMap<String, String>;
+
+/// Unpacks a zip archive into `target_dir`, streaming each entry to disk,
+/// and returns the digests of the files written.
+/// Absolute names, names that leave `target_dir` and symlinks are refused
+/// before anything is written; the limits are checked against the sizes the
+/// archive declares up front and against the bytes actually decompressed.
+pub fn extract_zip(
+    zip_path: &Path,
+    target_dir: &Path,
+    limits: &ZipLimits,
+) -> Result<Digests, KiraError> {
     let file = fs::File::open(zip_path)
         .map_err(|err| KiraError::Filesystem(format!("open zip {}: {err}", zip_path.display())))?;
     let mut archive =
         ZipArchive::new(file).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    let too_large = |message: String| KiraError::ArchiveTooLarge {
+        path: zip_path.to_path_buf(),
+        message,
+    };
+    if archive.len() > limits.max_entries {
+        return Err(too_large(format!(
+            "has {} entries, more than the limit of {}",
+            archive.len(),
+            limits.max_entries
+        )));
+    }
 
+    let mut declared = 0u64;
     for i in 0..archive.len() {
+        let entry = archive
+            .by_index_raw(i)
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let unsafe_entry = |reason: &str| KiraError::UnsafeArchiveEntry {
+            path: zip_path.to_path_buf(),
+            entry: entry.name().to_string(),
+            reason: reason.to_string(),
+        };
+        if is_absolute_name(entry.name()) {
+            return Err(unsafe_entry("absolute path"));
+        }
+        if entry.enclosed_name().is_none() {
+            return Err(unsafe_entry("path escapes the extraction directory"));
+        }
+        if entry.is_symlink() {
+            return Err(unsafe_entry("symbolic link"));
+        }
+        declared = declared.saturating_add(entry.size());
+    }
+    if declared > limits.max_unpacked_bytes {
+        return Err(too_large(format!(
+            "declares {} unpacked, more than the limit of {}",
+            bytes_to_human(declared),
+            bytes_to_human(limits.max_unpacked_bytes)
+        )));
+    }
+
+    // Entries are written in name order rather than archive order.
+    let mut order = (0..archive.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| archive.name_for_index(i).map(str::to_string));
+    let mut remaining = limits.max_unpacked_bytes;
+    let mut digests = Digests::new();
+    for i in order {
         let mut entry = archive
             .by_index(i)
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        let entry_path = match entry.enclosed_name() {
-            Some(path) => target_dir.join(path),
-            None => {
-                return Err(KiraError::Filesystem(
-                    "zip entry path traversal detected".to_string(),
-                ));
-            }
+        let Some(relative) = entry.enclosed_name() else {
+            return Err(KiraError::Filesystem(
+                "zip entry path traversal detected".to_string(),
+            ));
         };
+        let entry_path = target_dir.join(&relative);
 
         if entry.is_dir() {
             fs::create_dir_all(&entry_path)
@@ -34,11 +118,40 @@ pub fn extract_zip(zip_path: &Path, target_dir: &Path) -> Result<(), KiraError>
         if let Some(parent) = entry_path.parent() {
             fs::create_dir_all(parent).map_err(|err| KiraError::Filesystem(err.to_string()))?;
         }
-        let mut outfile =
-            fs::File::create(&entry_path).map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        io::copy(&mut entry, &mut outfile).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let mut outfile = HashingWriter::new(
+            fs::File::create(&entry_path).map_err(|err| KiraError::Filesystem(err.to_string()))?,
+        );
+        // One byte past the budget tells an entry that understated its size
+        // from one that used the budget up exactly.
+        let written = io::copy(&mut (&mut entry).take(remaining + 1), &mut outfile)
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        if written > remaining {
+            drop(outfile);
+            let _ = fs::remove_file(&entry_path);
+            return Err(too_large(format!(
+                "unpacks to more than the limit of {}",
+                bytes_to_human(limits.max_unpacked_bytes)
+            )));
+        }
+        remaining -= written;
+        let (outfile, digest) = outfile.finish();
+        drop(outfile);
+        let relative = relative
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        digests.insert(relative, digest);
     }
-    Ok(())
+    Ok(digests)
+}
+
+/// Whether a zip entry name is rooted, on Unix or Windows terms alike.
+fn is_absolute_name(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    name.starts_with('/')
+        || name.starts_with('\\')
+        || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
 }
 
 pub fn validate_zip(zip_path: &Path) -> Result<(), KiraError> {
@@ -59,3 +172,358 @@ pub fn validate_zip(zip_path: &Path) -> Result<(), KiraError> {
     }
     Ok(())
 }
+
+/// Unpacks a tar archive, gzip-compressed or not, into `target_dir` and
+/// returns the extracted files' paths relative to it, in archive order.
+/// Only regular files and directories are written; links and devices are
+/// skipped, and entries that would land outside `target_dir` are refused.
+pub fn extract_tar(archive_path: &Path, target_dir: &Path) -> Result<Vec<String>, KiraError> {
+    let open_err = |err: io::Error| {
+        KiraError::Filesystem(format!("open tar {}: {err}", archive_path.display()))
+    };
+    let mut file = fs::File::open(archive_path).map_err(open_err)?;
+    let mut magic = [0u8; 2];
+    let gzipped = file.read_exact(&mut magic).is_ok() && magic == [0x1f, 0x8b];
+    file.seek(SeekFrom::Start(0)).map_err(open_err)?;
+    let mut reader: Box<dyn Read> = if gzipped {
+        Box::new(io::BufReader::new(flate2::read::GzDecoder::new(file)))
+    } else {
+        Box::new(io::BufReader::new(file))
+    };
+
+    let tar_err = |message: String| {
+        KiraError::Filesystem(format!("tar {}: {message}", archive_path.display()))
+    };
+    let mut extracted = Vec::new();
+    let mut long_name = None;
+    let mut header = [0u8; 512];
+    loop {
+        if let Err(err) = reader.read_exact(&mut header) {
+            return Err(tar_err(format!("truncated archive: {err}")));
+        }
+        if header.iter().all(|byte| *byte == 0) {
+            break;
+        }
+        let size = tar_octal(&header[124..136])
+            .ok_or_else(|| tar_err("invalid entry size".to_string()))?;
+        let padded = size.div_ceil(512) * 512;
+        let mut body = reader.by_ref().take(padded);
+        let name = match long_name.take() {
+            Some(name) => name,
+            None => {
+                let name = tar_str(&header[0..100]);
+                let prefix = tar_str(&header[345..500]);
+                if &header[257..262] == b"ustar" && !prefix.is_empty() {
+                    format!("{prefix}/{name}")
+                } else {
+                    name
+                }
+            }
+        };
+        match header[156] {
+            // GNU long name, and pax headers that may carry one.
+            b'L' | b'x' => {
+                let mut data = Vec::new();
+                body.read_to_end(&mut data)
+                    .map_err(|err| tar_err(err.to_string()))?;
+                let data = &data[..data.len().min(size as usize)];
+                long_name = if header[156] == b'L' {
+                    Some(tar_str(data))
+                } else {
+                    pax_path(data)
+                };
+                continue;
+            }
+            b'0' | 0 | b'5' => {}
+            _ => {
+                io::copy(&mut body, &mut io::sink()).map_err(|err| tar_err(err.to_string()))?;
+                continue;
+            }
+        }
+        let relative = enclosed_tar_path(&name)
+            .ok_or_else(|| tar_err(format!("entry {name} escapes the target directory")))?;
+        let entry_path = target_dir.join(&relative);
+        if header[156] == b'5' || name.ends_with('/') || relative.is_empty() {
+            fs::create_dir_all(&entry_path)
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            io::copy(&mut body, &mut io::sink()).map_err(|err| tar_err(err.to_string()))?;
+            continue;
+        }
+        if let Some(parent) = entry_path.parent() {
+            fs::create_dir_all(parent).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        }
+        let mut outfile =
+            fs::File::create(&entry_path).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let written = io::copy(&mut body.by_ref().take(size), &mut outfile)
+            .map_err(|err| tar_err(err.to_string()))?;
+        if written != size {
+            return Err(tar_err(format!("truncated entry {name}")));
+        }
+        io::copy(&mut body, &mut io::sink()).map_err(|err| tar_err(err.to_string()))?;
+        extracted.push(relative);
+    }
+    Ok(extracted)
+}
+
+fn tar_str(field: &[u8]) -> String {
+    let end = field
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+fn tar_octal(field: &[u8]) -> Option<u64> {
+    let text = tar_str(field);
+    let text = text.trim_matches(|c: char| c == ' ' || c == '\0');
+    if text.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(text, 8).ok()
+}
+
+/// The `path` record of a pax extended header.
+fn pax_path(data: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(data).lines().find_map(|record| {
+        let (_, field) = record.split_once(' ')?;
+        field.strip_prefix("path=").map(str::to_string)
+    })
+}
+
+/// `name` as a relative path with no `..` (empty for the root itself), or
+/// `None` if it leaves the root.
+fn enclosed_tar_path(name: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    for part in name.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            part => parts.push(part),
+        }
+    }
+    (!name.starts_with('/')).then(|| parts.join("/"))
+}
+
+/// Total size of the files under `path`.
+pub fn dir_size(path: &Path) -> Option<u64> {
+    let mut total = 0u64;
+    let mut stack = vec![path.to_path_buf()];
+    while let Some(path) = stack.pop() {
+        let entries = fs::read_dir(&path).ok()?;
+        for entry in entries.flatten() {
+            let p = entry.path();
+            if p.is_dir() {
+                stack.push(p);
+            } else if let Ok(meta) = entry.metadata() {
+                total = total.saturating_add(meta.len());
+            }
+        }
+    }
+    Some(total)
+}
+
+pub fn path_size(path: &Path) -> Option<u64> {
+    let meta = fs::metadata(path).ok()?;
+    if meta.is_file() {
+        Some(meta.len())
+    } else {
+        dir_size(path)
+    }
+}
+
+pub fn sha256_file(path: &Path) -> Result<String, KiraError> {
+    let mut file = fs::File::open(path).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+fn to_hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Hashes what is written through it, so a file's SHA-256 is known once it
+/// is written rather than read back afterwards.
+pub struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// The writer back, with the digest of everything written through it.
+    pub fn finish(self) -> (W, String) {
+        (self.inner, to_hex(&self.hasher.finalize()))
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The SHA-256 of `bytes`, as [`sha256_file`] would report it.
+pub fn sha256_bytes(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+/// Writes `bytes` to `path` and returns their digest for the download
+/// record.
+pub fn write_hashed(path: &Path, bytes: &[u8]) -> io::Result<String> {
+    fs::write(path, bytes)?;
+    Ok(sha256_bytes(bytes))
+}
+
+pub fn bytes_to_human(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    const MB: f64 = KB * 1024.0;
+    const GB: f64 = MB * 1024.0;
+    let value = bytes as f64;
+    if value >= GB {
+        format!("{:.1} GB", value / GB)
+    } else if value >= MB {
+        format!("{:.1} MB", value / MB)
+    } else if value >= KB {
+        format!("{:.1} KB", value / KB)
+    } else {
+        format!("{bytes} B")
+    }
+}
+
+/// Bytes available to unprivileged users on the filesystem holding `path`.
+#[cfg(unix)]
+pub fn available_space(path: &Path) -> io::Result<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is NUL-terminated and `stat` is only read after
+    // statvfs(3) reports that it filled it in.
+    let stat = unsafe {
+        if libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        stat.assume_init()
+    };
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn available_space(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+/// Whether `a` and `b` are the same file, as hard links to one another
+/// are. `false` where that cannot be told.
+#[cfg(unix)]
+pub fn same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn same_file(_a: &Path, _b: &Path) -> bool {
+    false
+}
+
+/// How many directory entries link to the file at `path`.
+#[cfg(unix)]
+pub fn link_count(path: &Path) -> io::Result<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    fs::metadata(path).map(|meta| meta.nlink())
+}
+
+#[cfg(not(unix))]
+pub fn link_count(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+/// Appends `value` as one JSON line; the whole line goes out in one
+/// `O_APPEND` write so concurrent writers do not interleave.
+pub fn append_json_line<T: Serialize>(path: &Utf8Path, value: &T) -> Result<(), KiraError> {
+    let mut line = Vec::new();
+    // Start on a fresh line if an earlier writer died mid-record.
+    if ends_torn(path) {
+        line.push(b'\n');
+    }
+    serde_json::to_writer(&mut line, value)
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    line.push(b'\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    file.write_all(&line)
+        .map_err(|err| KiraError::Filesystem(err.to_string()))
+}
+
+/// Pretty JSON with object keys sorted and a trailing newline, so equal
+/// values are written as equal bytes whatever order their fields were built
+/// in. Every metadata, manifest and index file goes through this.
+pub fn to_canonical_json<T: Serialize>(value: &T) -> Result<Vec<u8>, KiraError> {
+    let mut value =
+        serde_json::to_value(value).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    // `preserve_order` keeps objects in insertion order, so they are sorted
+    // here.
+    value.sort_all_objects();
+    let mut bytes =
+        serde_json::to_vec_pretty(&value).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    bytes.push(b'\n');
+    Ok(bytes)
+}
+
+/// Reads the newest `limit` records of a JSON-lines file, oldest first. A
+/// missing file has no records; torn or foreign lines are skipped.
+pub fn read_json_lines<T: DeserializeOwned>(
+    path: &Utf8Path,
+    limit: Option<usize>,
+) -> Result<Vec<T>, KiraError> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(KiraError::Filesystem(err.to_string())),
+    };
+    let records = text
+        .lines()
+        .filter_map(|line| serde_json::from_str::<T>(line).ok())
+        .collect::<Vec<_>>();
+    let skip = limit.map_or(0, |limit| records.len().saturating_sub(limit));
+    Ok(records.into_iter().skip(skip).collect())
+}
+
+fn ends_torn(path: &Utf8Path) -> bool {
+    let Ok(mut file) = fs::File::open(path) else {
+        return false;
+    };
+    let mut last = [0u8; 1];
+    file.seek(SeekFrom::End(-1)).is_ok() && file.read_exact(&mut last).is_ok() && last[0] != b'\n'
+}
@@ -1,39 +1,119 @@
-use std::fs::File;
-use std::io::Read;
+use std::fs;
+use std::io::{self, BufRead, Read};
 use std::path::Path;
-use std::time::Duration;
 
+use clap::ValueEnum;
 use flate2::read::GzDecoder;
-use reqwest::blocking::Client;
-use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+use regex::Regex;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderValue, LOCATION, USER_AGENT};
+use reqwest::redirect::Policy;
+use serde::{Deserialize, Serialize};
+use tracing::field::Empty;
 
-use crate::domain::GeoSeriesAccession;
+use crate::app::ProgressSink;
+use crate::cancel;
+use crate::domain::{GeoSampleAccession, GeoSeriesAccession};
 use crate::error::KiraError;
+use crate::http::{FailedResponse, HttpSettings, Mirrors, record_bytes, record_response};
+use crate::progress::Metered;
+pub use crate::soft::SeriesRelation;
+use crate::soft::SoftRecord;
+
+/// How many hops a supplementary download follows, `Location` redirects
+/// and HTML meta refreshes together.
+const MAX_REDIRECTS: usize = 10;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum FileCategory {
+    Data,
+    Document,
+    Image,
+    Other,
+}
+
+impl std::fmt::Display for FileCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileCategory::Data => write!(f, "data"),
+            FileCategory::Document => write!(f, "document"),
+            FileCategory::Image => write!(f, "image"),
+            FileCategory::Other => write!(f, "other"),
+        }
+    }
+}
 
 pub trait GeoClient: Send + Sync {
     fn fetch_soft_text(&self, accession: &GeoSeriesAccession) -> Result<String, KiraError>;
-    fn download_url(&self, url: &str, destination: &Path) -> Result<(), KiraError>;
+    fn fetch_sample_soft_text(&self, accession: &GeoSampleAccession) -> Result<String, KiraError>;
+    /// Downloads `url` to `destination` and returns the file's SHA-256.
+    fn download_url(
+        &self,
+        url: &str,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError>;
+
+    fn content_length(&self, _url: &str) -> Option<u64> {
+        None
+    }
 }
 
 #[derive(Clone)]
 pub struct GeoHttpClient {
     client: Client,
+    /// Follows no redirects itself, so that each hop's target goes through
+    /// the ftp rewrite and the mirrors.
+    downloads: Client,
+    settings: HttpSettings,
+    mirrors: Mirrors,
 }
 
 impl GeoHttpClient {
     pub fn new() -> Result<Self, KiraError> {
+        Self::with_settings(HttpSettings::from_env())
+    }
+
+    pub fn with_settings(settings: HttpSettings) -> Result<Self, KiraError> {
         let mut headers = HeaderMap::new();
         headers.insert(
             USER_AGENT,
             HeaderValue::from_str(&format!("kira-bm/{}", env!("CARGO_PKG_VERSION")))
                 .map_err(|err| KiraError::Filesystem(err.to_string()))?,
         );
-        let client = Client::builder()
+        let client = settings
+            .client_builder("geo")
+            .default_headers(headers.clone())
+            .build()
+            .map_err(|err| KiraError::GeoHttp(err.to_string()))?;
+        let downloads = settings
+            .client_builder("geo")
             .default_headers(headers)
-            .timeout(Duration::from_secs(60))
+            .redirect(Policy::none())
             .build()
             .map_err(|err| KiraError::GeoHttp(err.to_string()))?;
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            downloads,
+            mirrors: settings.mirrors()?,
+            settings,
+        })
+    }
+
+    /// Sends every request, supplementary downloads included, to
+    /// `base_url` instead of the NCBI hosts.
+    pub fn with_base_url(settings: HttpSettings, base_url: &str) -> Result<Self, KiraError> {
+        Ok(Self {
+            mirrors: Mirrors::everywhere(base_url),
+            ..Self::with_settings(settings)?
+        })
+    }
+
+    fn request_url(&self, url: &str) -> String {
+        self.mirrors.rewrite(&Self::normalize_url(url))
     }
 
     fn soft_url(accession: &GeoSeriesAccession) -> String {
@@ -44,52 +124,140 @@ impl GeoHttpClient {
         )
     }
 
-    fn normalize_url(url: &str) -> String {
-        if let Some(rest) = url.strip_prefix("ftp://ftp.ncbi.nlm.nih.gov/") {
-            return format!("https://ftp.ncbi.nlm.nih.gov/{}", rest);
+    fn sample_soft_url(accession: &GeoSampleAccession) -> String {
+        format!(
+            "https://www.ncbi.nlm.nih.gov/geo/query/acc.cgi?acc={}&targ=self&form=text&view=quick",
+            accession.as_str()
+        )
+    }
+
+    /// NCBI's FTP site serves the same paths over HTTPS, which the client
+    /// speaks; `ftp.ncbi.nih.gov` is an alias of it.
+    pub fn normalize_url(url: &str) -> String {
+        for host in ["ftp.ncbi.nlm.nih.gov", "ftp.ncbi.nih.gov"] {
+            if let Some(rest) = url
+                .strip_prefix("ftp://")
+                .and_then(|url| url.strip_prefix(host))
+                .and_then(|url| url.strip_prefix('/'))
+            {
+                return format!("https://ftp.ncbi.nlm.nih.gov/{rest}");
+            }
         }
         url.to_string()
     }
 
+    /// Where a response sends the download next: its `Location`, or the
+    /// target of the meta refresh on an HTML page. The page is returned
+    /// when it refreshes nowhere.
+    fn next_hop(response: Response) -> Result<Hop, KiraError> {
+        if response.status().is_redirection()
+            && let Some(location) = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|location| response.url().join(location).ok())
+        {
+            record_response(&response);
+            return Ok(Hop::Redirect(location.to_string()));
+        }
+        let html = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.to_ascii_lowercase().starts_with("text/html"));
+        if !html || !response.status().is_success() {
+            return Ok(Hop::Body(response));
+        }
+        record_response(&response);
+        let url = response.url().clone();
+        let page = response
+            .text()
+            .map_err(|err| KiraError::GeoHttp(err.to_string()))?;
+        Ok(
+            match meta_refresh(&page).and_then(|target| url.join(&target).ok()) {
+                Some(target) => Hop::Redirect(target.to_string()),
+                None => Hop::Page(url.to_string(), page),
+            },
+        )
+    }
+
     fn write_response_to_file(
         &self,
-        mut response: reqwest::blocking::Response,
+        response: Response,
         destination: &Path,
-    ) -> Result<(), KiraError> {
-        if !response.status().is_success() {
-            let status = response.status().as_u16();
-            let message = response
-                .text()
-                .unwrap_or_else(|_| "GEO request failed".to_string());
-            return Err(KiraError::GeoStatus { status, message });
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        let url = response.url().to_string();
+        let response = Self::check_status(response, file_name(&url))?;
+        let total = response.content_length();
+        let (bytes, digest) = cancel::copy_to_file(
+            &mut Metered::new(response, sink, destination, total),
+            destination,
+        )?;
+        record_bytes(bytes);
+        Self::check_download(&url, destination, total)?;
+        Ok(digest)
+    }
+
+    /// Removes a download that came back empty or shorter than its
+    /// `Content-Length`, so it is never stored.
+    fn check_download(url: &str, destination: &Path, total: Option<u64>) -> Result<(), KiraError> {
+        let written = fs::metadata(destination)
+            .map_err(|err| KiraError::Filesystem(format!("{}: {err}", destination.display())))?
+            .len();
+        let defect = if written == 0 {
+            Some(format!("{url} returned an empty file"))
+        } else {
+            total
+                .filter(|total| *total != written)
+                .map(|total| format!("{url} returned {written} of {total} bytes"))
+        };
+        match defect {
+            Some(defect) => {
+                let _ = fs::remove_file(destination);
+                Err(KiraError::GeoHttp(defect))
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn check_status(
+        response: reqwest::blocking::Response,
+        dataset: String,
+    ) -> Result<reqwest::blocking::Response, KiraError> {
+        record_response(&response);
+        if response.status().is_success() {
+            return Ok(response);
         }
-        let mut file =
-            File::create(destination).map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        std::io::copy(&mut response, &mut file)
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        Ok(())
+        let failed = FailedResponse::read(response);
+        Err(KiraError::GeoStatus {
+            status: failed.status,
+            url: failed.url,
+            dataset,
+            message: failed.message,
+        })
     }
 }
 
 impl GeoClient for GeoHttpClient {
+    #[tracing::instrument(
+        name = "geo.fetch_soft_text",
+        skip_all,
+        fields(registry = "geo", dataset = %accession.as_str(), endpoint = Empty, status = Empty, bytes = Empty)
+    )]
     fn fetch_soft_text(&self, accession: &GeoSeriesAccession) -> Result<String, KiraError> {
-        let url = Self::soft_url(accession);
+        let url = self.request_url(&Self::soft_url(accession));
+        self.settings.limiter("ncbi").acquire();
         let response = self
             .client
             .get(url)
             .send()
             .map_err(|err| KiraError::GeoHttp(err.to_string()))?;
-        if !response.status().is_success() {
-            let status = response.status().as_u16();
-            let message = response
-                .text()
-                .unwrap_or_else(|_| "GEO request failed".to_string());
-            return Err(KiraError::GeoStatus { status, message });
-        }
-        let bytes = response
-            .bytes()
-            .map_err(|err| KiraError::GeoHttp(err.to_string()))?;
-        let mut decoder = GzDecoder::new(bytes.as_ref());
+        let mut response =
+            Self::check_status(response, format!("expression:{}", accession.as_str()))?;
+        let mut bytes = Vec::new();
+        record_bytes(cancel::copy(&mut response, &mut bytes, KiraError::GeoHttp)?);
+        let mut decoder = GzDecoder::new(bytes.as_slice());
         let mut text = String::new();
         decoder
             .read_to_string(&mut text)
@@ -97,48 +265,222 @@ impl GeoClient for GeoHttpClient {
         Ok(text)
     }
 
-    fn download_url(&self, url: &str, destination: &Path) -> Result<(), KiraError> {
-        let url = Self::normalize_url(url);
+    #[tracing::instrument(
+        name = "geo.fetch_sample_soft_text",
+        skip_all,
+        fields(registry = "geo", dataset = %accession.as_str(), endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    fn fetch_sample_soft_text(&self, accession: &GeoSampleAccession) -> Result<String, KiraError> {
+        let url = self.request_url(&Self::sample_soft_url(accession));
+        self.settings.limiter("ncbi").acquire();
         let response = self
             .client
             .get(url)
+            .timeout(self.settings.timeouts_for("geo").request)
             .send()
             .map_err(|err| KiraError::GeoHttp(err.to_string()))?;
-        self.write_response_to_file(response, destination)
+        let response =
+            Self::check_status(response, format!("expressionsample:{}", accession.as_str()))?;
+        let text = response
+            .text()
+            .map_err(|err| KiraError::GeoHttp(err.to_string()))?;
+        record_bytes(text.len() as u64);
+        if !text.contains("^SAMPLE") {
+            return Err(KiraError::GeoResolution(format!(
+                "GEO returned no SOFT record for {}",
+                accession.as_str()
+            )));
+        }
+        Ok(text)
     }
-}
 
-pub fn extract_supplementary_urls(soft_text: &str) -> Vec<String> {
-    let mut urls = Vec::new();
-    for line in soft_text.lines() {
-        if !line.contains("supplementary_file") {
-            continue;
-        }
-        if let Some((_, value)) = line.split_once('=') {
-            let url = value.trim();
-            if !url.is_empty() {
-                urls.push(url.to_string());
+    #[tracing::instrument(
+        name = "geo.download_url",
+        skip_all,
+        fields(registry = "geo", dataset = Empty, endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    fn download_url(
+        &self,
+        url: &str,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        // Supplementary files are fetched by URL alone; the file name stands
+        // in for the dataset.
+        tracing::Span::current().record("dataset", file_name(url).as_str());
+        let mut url = self.request_url(url);
+        for _ in 0..=MAX_REDIRECTS {
+            self.settings.limiter("ncbi").acquire();
+            let response = self
+                .downloads
+                .get(&url)
+                .send()
+                .map_err(|err| KiraError::GeoHttp(err.to_string()))?;
+            match Self::next_hop(response)? {
+                Hop::Redirect(next) => url = self.request_url(&next),
+                Hop::Body(response) => {
+                    return self.write_response_to_file(response, destination, sink);
+                }
+                Hop::Page(url, page) => {
+                    let (bytes, digest) = cancel::copy_to_file(&mut page.as_bytes(), destination)?;
+                    record_bytes(bytes);
+                    Self::check_download(&url, destination, None)?;
+                    return Ok(digest);
+                }
             }
         }
+        Err(KiraError::GeoHttp(format!(
+            "{url}: more than {MAX_REDIRECTS} redirects"
+        )))
+    }
+
+    fn content_length(&self, url: &str) -> Option<u64> {
+        let url = self.request_url(url);
+        self.settings.limiter("ncbi").acquire();
+        let response = self
+            .client
+            .head(url)
+            .timeout(self.settings.timeouts_for("geo").request)
+            .send()
+            .ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        response
+            .headers()
+            .get(reqwest::header::CONTENT_LENGTH)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()
     }
-    urls
 }
 
-pub fn extract_organism(soft_text: &str) -> Option<String> {
-    for line in soft_text.lines() {
-        if line.starts_with("!Series_organism_ch1")
-            || line.starts_with("!Series_organism")
-            || line.starts_with("!Sample_organism_ch1")
-        {
-            if let Some((_, value)) = line.split_once('=') {
-                let value = value.trim();
-                if !value.is_empty() {
-                    return Some(value.to_string());
-                }
+enum Hop {
+    Redirect(String),
+    Body(Response),
+    /// An HTML page that is the file itself, with its URL.
+    Page(String, String),
+}
+
+fn file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    path.rsplit('/').next().unwrap_or_default().to_string()
+}
+
+/// The target of an HTML `<meta http-equiv="refresh" content="0; url=...">`.
+fn meta_refresh(page: &str) -> Option<String> {
+    let tag = Regex::new(r"(?is)<meta\b[^>]*>").ok()?;
+    let target = Regex::new(r#"(?i)\burl\s*=\s*['"]?([^'">\s]+)"#).ok()?;
+    tag.find_iter(page)
+        .map(|tag| tag.as_str())
+        .filter(|tag| tag.to_ascii_lowercase().contains("refresh"))
+        .find_map(|tag| target.captures(tag).map(|found| found[1].to_string()))
+}
+
+/// Supplementary file URLs of the series and its samples, in SOFT order.
+pub fn extract_supplementary_urls(soft_text: &str) -> Vec<String> {
+    SoftRecord::parse(soft_text).supplementary_urls
+}
+
+const COMPRESSION_SUFFIXES: &[&str] = &["gz", "bz2", "xz", "zip", "tgz"];
+const DOCUMENT_EXTENSIONS: &[&str] = &["pdf", "doc", "docx", "ppt", "pptx", "rtf", "odt"];
+const IMAGE_EXTENSIONS: &[&str] = &["tif", "tiff", "png", "jpg", "jpeg", "gif", "bmp", "svg"];
+const OTHER_EXTENSIONS: &[&str] = &["html", "htm", "exe", "dmg", "jar", "log"];
+
+/// Classifies a supplementary file by name. Unknown extensions count as data.
+pub fn classify_supplementary(url: &str) -> FileCategory {
+    let name = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(url)
+        .rsplit('/')
+        .next()
+        .unwrap_or(url)
+        .to_lowercase();
+    let mut parts = name.split('.').collect::<Vec<_>>();
+    while parts.len() > 1
+        && parts
+            .last()
+            .is_some_and(|ext| COMPRESSION_SUFFIXES.contains(ext))
+    {
+        parts.pop();
+    }
+    let stem = parts.first().copied().unwrap_or_default();
+    let ext = if parts.len() > 1 {
+        parts.last().copied().unwrap_or_default()
+    } else {
+        ""
+    };
+    let is_readme = stem
+        .split(['_', '-', ' '])
+        .any(|token| token.starts_with("readme"));
+    if DOCUMENT_EXTENSIONS.contains(&ext) || is_readme {
+        FileCategory::Document
+    } else if IMAGE_EXTENSIONS.contains(&ext) {
+        FileCategory::Image
+    } else if OTHER_EXTENSIONS.contains(&ext) {
+        FileCategory::Other
+    } else {
+        FileCategory::Data
+    }
+}
+
+pub fn extract_series_relations(soft_text: &str) -> Vec<SeriesRelation> {
+    SoftRecord::parse(soft_text).relations
+}
+
+pub fn extract_series_samples(soft_text: &str) -> Vec<String> {
+    SoftRecord::parse(soft_text).sample_ids
+}
+
+/// Series a GEO sample belongs to, in SOFT order.
+pub fn extract_sample_series(soft_text: &str) -> Vec<GeoSeriesAccession> {
+    let mut series = Vec::new();
+    for sample in SoftRecord::parse(soft_text).samples {
+        for accession in sample.series {
+            if !series.contains(&accession) {
+                series.push(accession);
             }
         }
     }
-    None
+    series
+}
+
+pub fn extract_sample_title(soft_text: &str) -> Option<String> {
+    SoftRecord::parse(soft_text)
+        .samples
+        .into_iter()
+        .find_map(|sample| sample.title)
+}
+
+pub fn filter_urls_by_samples(urls: &[String], samples: &[String]) -> Vec<String> {
+    urls.iter()
+        .filter(|url| {
+            let name = url.rsplit('/').next().unwrap_or(url);
+            samples
+                .iter()
+                .any(|sample| mentions_accession(name, sample))
+        })
+        .cloned()
+        .collect()
+}
+
+fn mentions_accession(name: &str, accession: &str) -> bool {
+    name.match_indices(accession).any(|(idx, _)| {
+        !name[idx + accession.len()..]
+            .chars()
+            .next()
+            .is_some_and(|ch| ch.is_ascii_digit())
+    })
+}
+
+pub fn extract_series_title(soft_text: &str) -> Option<String> {
+    SoftRecord::parse(soft_text).series_title
+}
+
+pub fn extract_organism(soft_text: &str) -> Option<String> {
+    SoftRecord::parse(soft_text).organism
 }
 
 pub fn geo_series_prefix(accession: &GeoSeriesAccession) -> String {
@@ -149,3 +491,406 @@ pub fn geo_series_prefix(accession: &GeoSeriesAccession) -> String {
     let head = &digits[..digits.len() - 3];
     format!("GSE{}nnn", head)
 }
+
+/// Splits a 10x file name into its sample prefix and component, so both
+/// `matrix.mtx.gz` and `GSM123_matrix.mtx.gz` are recognised.
+pub fn tenx_component(file_name: &str) -> Option<(&str, &'static str)> {
+    [
+        "matrix.mtx.gz",
+        "barcodes.tsv.gz",
+        "features.tsv.gz",
+        "genes.tsv.gz",
+    ]
+    .into_iter()
+    .find_map(|component| {
+        let prefix = file_name.strip_suffix(component)?;
+        (prefix.is_empty() || prefix.ends_with(['_', '-', '.']))
+            .then(|| (prefix.trim_end_matches(['_', '-', '.']), component))
+    })
+}
+
+/// Whether a supplementary file looks like a tar of a 10x matrix bundle,
+/// e.g. `GSM123_filtered_feature_bc_matrix.tar.gz`.
+pub fn is_10x_archive(url: &str) -> bool {
+    let name = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(url)
+        .rsplit('/')
+        .next()
+        .unwrap_or(url)
+        .to_lowercase();
+    let is_tar = [".tar", ".tar.gz", ".tgz"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix));
+    is_tar
+        && ["matrix", "feature_bc", "10x"]
+            .iter()
+            .any(|hint| name.contains(hint))
+}
+
+/// One sample's 10x files, as paths relative to the directory scanned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenxBundle {
+    pub sample: String,
+    pub matrix: String,
+    pub barcodes: String,
+    /// `features.tsv.gz`, or `genes.tsv.gz` from Cell Ranger 2 and older.
+    pub features: String,
+}
+
+/// Groups 10x files by directory and name prefix and keeps the groups with
+/// a matrix, barcodes and features. A bundle is named after its prefix, or
+/// else the GSM its path mentions, or else its directory; repeated names
+/// get a `_2`, `_3`, … suffix.
+pub fn detect_10x_files(paths: &[String]) -> Vec<TenxBundle> {
+    let mut groups = std::collections::BTreeMap::<(&str, &str), [Option<&str>; 3]>::new();
+    for path in paths {
+        let (dir, file_name) = path.rsplit_once('/').unwrap_or(("", path));
+        let Some((prefix, component)) = tenx_component(file_name) else {
+            continue;
+        };
+        let slot = match component {
+            "matrix.mtx.gz" => 0,
+            "barcodes.tsv.gz" => 1,
+            _ => 2,
+        };
+        let parts = groups.entry((dir, prefix)).or_default();
+        // Prefer `features.tsv.gz` when both feature files are present.
+        if parts[slot].is_none() || component == "features.tsv.gz" {
+            parts[slot] = Some(path);
+        }
+    }
+
+    let mut taken = std::collections::BTreeSet::new();
+    let mut bundles = Vec::new();
+    for ((dir, prefix), parts) in groups {
+        let [Some(matrix), Some(barcodes), Some(features)] = parts else {
+            continue;
+        };
+        bundles.push(TenxBundle {
+            sample: bundle_sample(dir, prefix, &mut taken),
+            matrix: matrix.to_string(),
+            barcodes: barcodes.to_string(),
+            features: features.to_string(),
+        });
+    }
+    bundles
+}
+
+/// A bundle's prefix, or else the GSM its directory mentions, or else the
+/// directory name, made unique among `taken`.
+fn bundle_sample(
+    dir: &str,
+    prefix: &str,
+    taken: &mut std::collections::BTreeSet<String>,
+) -> String {
+    let base = if prefix.is_empty() {
+        sample_in_path(dir)
+            .or_else(|| dir.rsplit('/').next().filter(|name| !name.is_empty()))
+            .unwrap_or("sample")
+    } else {
+        prefix
+    };
+    let mut sample = base.to_string();
+    let mut n = 1;
+    while !taken.insert(sample.clone()) {
+        n += 1;
+        sample = format!("{base}_{n}");
+    }
+    sample
+}
+
+/// The first `GSM<digits>` in `path`.
+fn sample_in_path(path: &str) -> Option<&str> {
+    path.match_indices("GSM").find_map(|(idx, _)| {
+        let digits = path[idx + 3..]
+            .bytes()
+            .take_while(u8::is_ascii_digit)
+            .count();
+        (digits > 0).then(|| &path[idx..idx + 3 + digits])
+    })
+}
+
+/// Moves each bundle under `dir` to `bundles/<sample>/matrix.mtx.gz`,
+/// `barcodes.tsv.gz` and `features.tsv.gz`. Returns a note for each
+/// `genes.tsv.gz` renamed on the way.
+pub fn normalize_10x_bundles(dir: &Path, bundles: &[TenxBundle]) -> Result<Vec<String>, KiraError> {
+    let mut notes = Vec::new();
+    for bundle in bundles {
+        let target = dir.join("bundles").join(&bundle.sample);
+        fs::create_dir_all(&target).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        for (source, name) in [
+            (&bundle.matrix, "matrix.mtx.gz"),
+            (&bundle.barcodes, "barcodes.tsv.gz"),
+            (&bundle.features, "features.tsv.gz"),
+        ] {
+            fs::rename(dir.join(source), target.join(name))
+                .map_err(|err| KiraError::Filesystem(format!("{source}: {err}")))?;
+        }
+        if bundle.features.ends_with("genes.tsv.gz") {
+            notes.push(format!(
+                "{}: {} renamed to features.tsv.gz",
+                bundle.sample, bundle.features
+            ));
+        }
+    }
+    Ok(notes)
+}
+
+/// Cells, genes and stored entries of a normalized MTX bundle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleDimensions {
+    pub sample: String,
+    pub cells: u64,
+    pub genes: u64,
+    pub nnz: u64,
+}
+
+/// Reads a MatrixMarket coordinate header, the `%%MatrixMarket` banner and
+/// the `rows cols entries` line after any comments, and stops there.
+pub fn read_mtx_header(mut reader: impl BufRead) -> Result<(u64, u64, u64), String> {
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|err| err.to_string())?;
+    let banner = line.split_whitespace().collect::<Vec<_>>();
+    if banner.len() < 3
+        || !banner[0].eq_ignore_ascii_case("%%MatrixMarket")
+        || !banner[2].eq_ignore_ascii_case("coordinate")
+    {
+        return Err("no MatrixMarket coordinate banner".to_string());
+    }
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).map_err(|err| err.to_string())? == 0 {
+            return Err("no size line".to_string());
+        }
+        let size = line.trim();
+        if size.is_empty() || size.starts_with('%') {
+            continue;
+        }
+        let numbers = size
+            .split_whitespace()
+            .map(str::parse::<u64>)
+            .collect::<Result<Vec<_>, _>>();
+        return match numbers.as_deref() {
+            Ok([rows, cols, entries]) => Ok((*rows, *cols, *entries)),
+            _ => Err(format!("invalid size line `{size}`")),
+        };
+    }
+}
+
+/// Lines of a text file, decompressing it if it is gzip. A last line
+/// without a newline counts.
+pub fn count_lines(path: &Path) -> io::Result<u64> {
+    let mut reader = crate::io_util::open_maybe_gzip(path)?;
+    let mut lines = 0;
+    let mut open_line = false;
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        lines += buf.iter().filter(|byte| **byte == b'\n').count() as u64;
+        open_line = buf.last() != Some(&b'\n');
+        let len = buf.len();
+        reader.consume(len);
+    }
+    Ok(lines + u64::from(open_line))
+}
+
+/// Measures the bundle in `bundles/<sample>/` under `dir` from its matrix
+/// header, and checks that `barcodes.tsv.gz` has a line per cell and
+/// `features.tsv.gz` one per gene. Returns the dimensions, when the header
+/// could be read, and a warning for each check that failed.
+pub fn measure_10x_bundle(dir: &Path, sample: &str) -> (Option<BundleDimensions>, Vec<String>) {
+    let bundle = dir.join("bundles").join(sample);
+    let header = crate::io_util::open_maybe_gzip(&bundle.join("matrix.mtx.gz"))
+        .map_err(|err| err.to_string())
+        .and_then(read_mtx_header);
+    let (genes, cells, nnz) = match header {
+        Ok(dims) => dims,
+        Err(err) => return (None, vec![format!("{sample}: matrix.mtx.gz: {err}")]),
+    };
+    let mut warnings = Vec::new();
+    for (name, expected, what) in [
+        ("barcodes.tsv.gz", cells, "cells"),
+        ("features.tsv.gz", genes, "genes"),
+    ] {
+        match count_lines(&bundle.join(name)) {
+            Ok(lines) if lines == expected => {}
+            Ok(lines) => warnings.push(format!(
+                "{sample}: {name} has {lines} lines but the matrix has {expected} {what}"
+            )),
+            Err(err) => warnings.push(format!("{sample}: {name}: {err}")),
+        }
+    }
+    let dims = BundleDimensions {
+        sample: sample.to_string(),
+        cells,
+        genes,
+        nnz,
+    };
+    (Some(dims), warnings)
+}
+
+/// CellRanger HDF5 matrix names, filtered before raw, without the
+/// `.h5`/`.hdf5` extension.
+const TENX_H5_SUFFIXES: [&str; 6] = [
+    "filtered_feature_bc_matrix",
+    "filtered_gene_bc_matrices_h5",
+    "raw_feature_bc_matrix",
+    "raw_gene_bc_matrices_h5",
+    "feature_bc_matrix",
+    "matrix",
+];
+
+/// Splits a CellRanger HDF5 matrix name into its sample prefix and whether
+/// it holds the filtered matrix, e.g. `GSM1_s1_filtered_feature_bc_matrix.h5`
+/// into `("GSM1_s1", true)`.
+pub fn tenx_h5_component(file_name: &str) -> Option<(&str, bool)> {
+    let lower = file_name.to_ascii_lowercase();
+    let stem_len = [".h5", ".hdf5"]
+        .iter()
+        .find_map(|ext| lower.strip_suffix(ext))?
+        .len();
+    let lower = &lower[..stem_len];
+    TENX_H5_SUFFIXES.iter().find_map(|suffix| {
+        let prefix = lower.strip_suffix(suffix)?;
+        (prefix.is_empty() || prefix.ends_with(['_', '-', '.'])).then(|| {
+            (
+                file_name[..prefix.len()].trim_end_matches(['_', '-', '.']),
+                !suffix.starts_with("raw"),
+            )
+        })
+    })
+}
+
+/// One sample's CellRanger HDF5 matrix, as a path relative to the directory
+/// scanned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenxH5 {
+    pub sample: String,
+    pub matrix: String,
+}
+
+/// Finds HDF5 matrices, one per directory and name prefix, preferring the
+/// filtered matrix to the raw one. Samples are named as in
+/// [`detect_10x_files`].
+pub fn detect_10x_h5(paths: &[String]) -> Vec<TenxH5> {
+    let mut groups = std::collections::BTreeMap::<(&str, &str), (&str, bool)>::new();
+    for path in paths {
+        let (dir, file_name) = path.rsplit_once('/').unwrap_or(("", path));
+        let Some((prefix, filtered)) = tenx_h5_component(file_name) else {
+            continue;
+        };
+        let entry = groups.entry((dir, prefix)).or_insert((path, filtered));
+        if filtered && !entry.1 {
+            *entry = (path, filtered);
+        }
+    }
+
+    let mut taken = std::collections::BTreeSet::new();
+    groups
+        .into_iter()
+        .map(|((dir, prefix), (path, _))| TenxH5 {
+            sample: bundle_sample(dir, prefix, &mut taken),
+            matrix: path.to_string(),
+        })
+        .collect()
+}
+
+/// Which layout a sample keeps when a series publishes it both as an MTX
+/// triplet and as an HDF5 matrix.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum BundlePreference {
+    #[default]
+    Mtx,
+    H5,
+}
+
+impl std::fmt::Display for BundlePreference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BundlePreference::Mtx => write!(f, "mtx"),
+            BundlePreference::H5 => write!(f, "h5"),
+        }
+    }
+}
+
+/// A bundle's layout and files, as recorded in `metadata.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleFiles {
+    pub sample: String,
+    pub format: BundlePreference,
+    pub files: Vec<String>,
+}
+
+impl From<&TenxBundle> for BundleFiles {
+    fn from(bundle: &TenxBundle) -> Self {
+        Self {
+            sample: bundle.sample.clone(),
+            format: BundlePreference::Mtx,
+            files: vec![
+                bundle.matrix.clone(),
+                bundle.barcodes.clone(),
+                bundle.features.clone(),
+            ],
+        }
+    }
+}
+
+impl From<&TenxH5> for BundleFiles {
+    fn from(bundle: &TenxH5) -> Self {
+        Self {
+            sample: bundle.sample.clone(),
+            format: BundlePreference::H5,
+            files: vec![bundle.matrix.clone()],
+        }
+    }
+}
+
+/// Drops the layout `prefer` does not name from every sample published in
+/// both, matching samples by name or by the GSM their names share. Returns
+/// the dropped bundles.
+pub fn resolve_10x_layouts(
+    mtx: &mut Vec<TenxBundle>,
+    h5: &mut Vec<TenxH5>,
+    prefer: BundlePreference,
+) -> Vec<BundleFiles> {
+    let same = |a: &str, b: &str| {
+        a == b
+            || sample_in_path(a)
+                .zip(sample_in_path(b))
+                .is_some_and(|(a, b)| a == b)
+    };
+    let mut skipped = Vec::new();
+    match prefer {
+        BundlePreference::Mtx => h5.retain(|h5| {
+            let keep = !mtx.iter().any(|mtx| same(&mtx.sample, &h5.sample));
+            if !keep {
+                skipped.push(BundleFiles::from(h5));
+            }
+            keep
+        }),
+        BundlePreference::H5 => mtx.retain(|mtx| {
+            let keep = !h5.iter().any(|h5| same(&mtx.sample, &h5.sample));
+            if !keep {
+                skipped.push(BundleFiles::from(mtx));
+            }
+            keep
+        }),
+    }
+    skipped
+}
+
+/// Moves each HDF5 matrix under `dir` to `bundles/<sample>/matrix.h5`.
+pub fn normalize_10x_h5(dir: &Path, bundles: &[TenxH5]) -> Result<(), KiraError> {
+    for bundle in bundles {
+        let target = dir.join("bundles").join(&bundle.sample);
+        fs::create_dir_all(&target).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        fs::rename(dir.join(&bundle.matrix), target.join("matrix.h5"))
+            .map_err(|err| KiraError::Filesystem(format!("{}: {err}", bundle.matrix)))?;
+    }
+    Ok(())
+}
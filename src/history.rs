@@ -0,0 +1,66 @@
+//! Log of fetched datasets.
+//!
+//! Every fetched item appends one record to `history.jsonl` in the project
+//! store: where the data came from, how large it is and, for failures, the
+//! phase and reason. Store analytics are derived from it.
+
+use camino::Utf8Path;
+use serde::{Deserialize, Serialize};
+
+use crate::error::KiraError;
+use crate::fs_util::{append_json_line, read_json_lines};
+
+pub const HISTORY_FILE: &str = "history.jsonl";
+pub const ACTION_FAILED: &str = "failed";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    pub timestamp: String,
+    pub specifier: String,
+    /// The fetch result's action (`download`, `cache`, `project`), or
+    /// `failed`.
+    pub action: String,
+    #[serde(default)]
+    pub bytes: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HistoryRecord {
+    pub fn fetched(specifier: &str, action: &str, bytes: u64) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            specifier: specifier.to_string(),
+            action: action.to_string(),
+            bytes,
+            phase: None,
+            error: None,
+        }
+    }
+
+    pub fn failed(specifier: &str, phase: &str, error: &str) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            specifier: specifier.to_string(),
+            action: ACTION_FAILED.to_string(),
+            bytes: 0,
+            phase: Some(phase.to_string()),
+            error: Some(error.to_string()),
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        self.action == ACTION_FAILED
+    }
+}
+
+pub fn append(root: &Utf8Path, record: &HistoryRecord) -> Result<(), KiraError> {
+    append_json_line(&root.join(HISTORY_FILE), record)
+}
+
+/// Reads the newest `limit` records, oldest first.
+pub fn read(root: &Utf8Path, limit: Option<usize>) -> Result<Vec<HistoryRecord>, KiraError> {
+    read_json_lines(&root.join(HISTORY_FILE), limit)
+}
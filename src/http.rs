@@ -0,0 +1,1022 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use reqwest::blocking::{ClientBuilder, RequestBuilder};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Proxy, Url};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::KiraError;
+
+pub const NCBI_API_KEY_ENV: &str = "NCBI_API_KEY";
+const PEM_CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----";
+
+const NCBI_RPS: f64 = 3.0;
+const NCBI_RPS_WITH_KEY: f64 = 10.0;
+const DEFAULT_RPS: f64 = 10.0;
+// KEGG asks for no more than 3 requests per second.
+const KEGG_RPS: f64 = 3.0;
+const TOKEN_EPSILON: f64 = 1e-6;
+const EUTILS_HOST: &str = "eutils.ncbi.nlm.nih.gov";
+const BODY_EXCERPT_CHARS: usize = 200;
+
+const DEFAULT_TIMEOUTS: Timeouts = Timeouts {
+    connect: Duration::from_secs(15),
+    request: Duration::from_secs(60),
+    stall: Duration::from_secs(60),
+};
+// The DOI resolver checks every linked accession; one hung registry must
+// not cost a minute per id.
+const VALIDATION_TIMEOUTS: Timeouts = Timeouts {
+    connect: Duration::from_secs(5),
+    request: Duration::from_secs(10),
+    stall: Duration::from_secs(10),
+};
+const PING_TIMEOUTS: Timeouts = Timeouts {
+    connect: Duration::from_secs(10),
+    request: Duration::from_secs(10),
+    stall: Duration::from_secs(10),
+};
+
+/// Names accepted as per-registry keys of the `timeouts` config section:
+/// one per client, `kb` for the GO, KEGG and Reactome downloads,
+/// `validation` for the DOI resolver's accession checks and `ping` for
+/// connectivity probes.
+pub const TIMEOUT_REGISTRIES: [&str; 11] = [
+    "ncbi",
+    "geo",
+    "rcsb",
+    "uniprot",
+    "ena",
+    "ensembl",
+    "crossref",
+    "datacite",
+    "kb",
+    "validation",
+    "ping",
+];
+
+/// A registry whose base URL can point at a mirror.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegistryBase {
+    /// Key in the global config's `registries` object.
+    pub key: &'static str,
+    pub env: &'static str,
+    /// The registry's own bases; a mirror base replaces any of them.
+    pub defaults: &'static [&'static str],
+}
+
+pub const REGISTRY_BASES: [RegistryBase; 8] = [
+    RegistryBase {
+        key: "rcsb",
+        env: "KIRA_BM_RCSB_BASE",
+        defaults: &[
+            "https://files.rcsb.org",
+            "https://data.rcsb.org",
+            "https://search.rcsb.org",
+            "https://www.rcsb.org",
+        ],
+    },
+    RegistryBase {
+        key: "ncbi_datasets",
+        env: "KIRA_BM_NCBI_DATASETS_BASE",
+        defaults: &["https://api.ncbi.nlm.nih.gov/datasets/v2"],
+    },
+    RegistryBase {
+        key: "eutils",
+        env: "KIRA_BM_EUTILS_BASE",
+        defaults: &["https://eutils.ncbi.nlm.nih.gov/entrez/eutils"],
+    },
+    RegistryBase {
+        key: "uniprot",
+        env: "KIRA_BM_UNIPROT_BASE",
+        defaults: &["https://rest.uniprot.org"],
+    },
+    RegistryBase {
+        key: "geo",
+        env: "KIRA_BM_GEO_BASE",
+        defaults: &[
+            "https://ftp.ncbi.nlm.nih.gov/geo",
+            "https://www.ncbi.nlm.nih.gov/geo",
+        ],
+    },
+    RegistryBase {
+        key: "crossref",
+        env: "KIRA_BM_CROSSREF_BASE",
+        defaults: &["https://api.crossref.org"],
+    },
+    RegistryBase {
+        key: "datacite",
+        env: "KIRA_BM_DATACITE_BASE",
+        defaults: &["https://api.datacite.org"],
+    },
+    RegistryBase {
+        key: "ensembl",
+        env: "KIRA_BM_ENSEMBL_BASE",
+        defaults: &["https://ftp.ensembl.org/pub"],
+    },
+];
+
+pub fn registry_base(key: &str) -> Option<&'static RegistryBase> {
+    REGISTRY_BASES.iter().find(|registry| registry.key == key)
+}
+
+/// Base URL overrides set in the environment, by registry key.
+pub fn registry_bases_from_env() -> BTreeMap<String, String> {
+    REGISTRY_BASES
+        .iter()
+        .filter_map(|registry| {
+            let value = std::env::var(registry.env).ok()?;
+            let value = value.trim();
+            (!value.is_empty()).then(|| (registry.key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Where registry requests really go. A request to one of a registry's
+/// own bases is moved onto its mirror, keeping the rest of the path; the
+/// mirror is expected to serve the same paths. Built by
+/// [`HttpSettings::mirrors`], or by [`Mirrors::everywhere`] to send every
+/// request to one server.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mirrors {
+    bases: BTreeMap<&'static str, String>,
+    everywhere: Option<String>,
+}
+
+impl Mirrors {
+    /// Sends every request, whatever its host, to `base` (see
+    /// [`rebase_url`]).
+    pub fn everywhere(base: &str) -> Self {
+        Self {
+            bases: BTreeMap::new(),
+            everywhere: Some(base.to_string()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bases.is_empty() && self.everywhere.is_none()
+    }
+
+    pub fn rewrite(&self, url: &str) -> String {
+        if let Some(base) = &self.everywhere {
+            return rebase_url(url, Some(base));
+        }
+        for registry in &REGISTRY_BASES {
+            let Some(base) = self.bases.get(registry.key) else {
+                continue;
+            };
+            for default in registry.defaults {
+                if let Some(rest) = url.strip_prefix(default)
+                    && (rest.is_empty() || rest.starts_with(['/', '?']))
+                {
+                    return format!("{}{rest}", base.trim_end_matches('/'));
+                }
+            }
+        }
+        url.to_string()
+    }
+}
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration);
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    state: Mutex<BucketState>,
+    clock: Arc<dyn Clock>,
+}
+
+struct BucketState {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: f64) -> Self {
+        Self::with_clock(requests_per_second, 1.0, Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(requests_per_second: f64, burst: f64, clock: Arc<dyn Clock>) -> Self {
+        let burst = burst.max(1.0);
+        Self {
+            rate: requests_per_second,
+            burst,
+            state: Mutex::new(BucketState {
+                tokens: burst,
+                updated: clock.now(),
+            }),
+            clock,
+        }
+    }
+
+    pub fn requests_per_second(&self) -> f64 {
+        self.rate
+    }
+
+    pub fn acquire(&self) {
+        if self.rate <= 0.0 {
+            return;
+        }
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        loop {
+            let now = self.clock.now();
+            let elapsed = now.saturating_duration_since(state.updated).as_secs_f64();
+            state.tokens = (state.tokens + elapsed * self.rate).min(self.burst);
+            state.updated = now;
+            if state.tokens >= 1.0 - TOKEN_EPSILON {
+                state.tokens = (state.tokens - 1.0).max(0.0);
+                return;
+            }
+            let wait_nanos = ((1.0 - state.tokens) / self.rate * 1e9).ceil() as u64;
+            self.clock.sleep(Duration::from_nanos(wait_nanos.max(1)));
+        }
+    }
+}
+
+impl std::fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("rate", &self.rate)
+            .field("burst", &self.burst)
+            .finish()
+    }
+}
+
+/// Where requests go: `https` URLs through the `https` proxy, `http` ones
+/// through the `http` proxy, and hosts matching `no_proxy` directly.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProxySettings {
+    pub http: Option<String>,
+    pub https: Option<String>,
+    /// Host suffixes, IP addresses, or `*` for every host.
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxySettings {
+    /// `HTTPS_PROXY`, `HTTP_PROXY` and `NO_PROXY`, each also accepted in
+    /// lower case; the upper-case name wins.
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let read = |name: &str| {
+            var(name)
+                .or_else(|| var(&name.to_ascii_lowercase()))
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            http: read("HTTP_PROXY"),
+            https: read("HTTPS_PROXY"),
+            no_proxy: read("NO_PROXY")
+                .map(|list| {
+                    list.split(',')
+                        .map(|entry| entry.trim().to_ascii_lowercase())
+                        .filter(|entry| !entry.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.http.is_none() && self.https.is_none()
+    }
+
+    /// The proxy a request to `url` goes through, if any.
+    pub fn proxy_for(&self, url: &Url) -> Option<&str> {
+        let proxy = match url.scheme() {
+            "https" => self.https.as_deref(),
+            "http" => self.http.as_deref(),
+            _ => None,
+        }?;
+        let host = url
+            .host_str()?
+            .trim_matches(['[', ']'])
+            .to_ascii_lowercase();
+        (!self.bypasses(&host)).then_some(proxy)
+    }
+
+    fn bypasses(&self, host: &str) -> bool {
+        self.no_proxy.iter().any(|entry| {
+            let entry = entry.split(':').next().unwrap_or(entry);
+            let entry = entry.trim_start_matches("*.").trim_start_matches('.');
+            entry == "*"
+                || host == entry
+                || host
+                    .strip_suffix(entry)
+                    .is_some_and(|rest| rest.ends_with('.'))
+        })
+    }
+}
+
+/// PEM certificates to trust besides the system roots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaBundle {
+    pub path: PathBuf,
+    pub certificates: usize,
+    pub pem: Vec<u8>,
+}
+
+impl CaBundle {
+    pub fn load(path: &Path) -> Result<Self, KiraError> {
+        let failed = |message: String| KiraError::CaBundle {
+            path: path.to_path_buf(),
+            message,
+        };
+        let pem = fs::read(path).map_err(|err| failed(err.to_string()))?;
+        let text = std::str::from_utf8(&pem).map_err(|_| failed("not a PEM file".to_string()))?;
+        let certificates = text.matches(PEM_CERTIFICATE).count();
+        if certificates == 0 {
+            return Err(failed("no PEM certificates found".to_string()));
+        }
+        if text.matches("-----END CERTIFICATE-----").count() != certificates {
+            return Err(failed("a PEM certificate is truncated".to_string()));
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            certificates,
+            pem,
+        })
+    }
+}
+
+/// How long a client waits: to connect, for a whole API response, and
+/// between two reads of a download. Downloads have no overall limit, so a
+/// slow but steady transfer of any size finishes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeouts {
+    pub connect: Duration,
+    pub request: Duration,
+    pub stall: Duration,
+}
+
+impl Timeouts {
+    /// Built-in values for a registry client.
+    pub fn default_for(registry: &str) -> Self {
+        match registry {
+            "validation" => VALIDATION_TIMEOUTS,
+            "ping" => PING_TIMEOUTS,
+            _ => DEFAULT_TIMEOUTS,
+        }
+    }
+
+    fn apply(self, overrides: &TimeoutOverrides) -> Self {
+        let secs = |secs: Option<u64>, fallback| secs.map_or(fallback, Duration::from_secs);
+        Self {
+            connect: secs(overrides.connect_secs, self.connect),
+            request: secs(overrides.request_secs, self.request),
+            stall: secs(overrides.stall_secs, self.stall),
+        }
+    }
+}
+
+/// Timeouts set by the config file or flags, in seconds; unset ones keep
+/// the built-in value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TimeoutOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stall_secs: Option<u64>,
+}
+
+impl TimeoutOverrides {
+    /// `self`, with the values it leaves unset taken from `fallback`.
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            connect_secs: self.connect_secs.or(fallback.connect_secs),
+            request_secs: self.request_secs.or(fallback.request_secs),
+            stall_secs: self.stall_secs.or(fallback.stall_secs),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct HttpSettings {
+    ncbi_api_key: Option<String>,
+    rates: BTreeMap<String, f64>,
+    clock: Arc<dyn Clock>,
+    limiters: Arc<Mutex<BTreeMap<String, Arc<RateLimiter>>>>,
+    proxy: ProxySettings,
+    ca_bundle: Option<PathBuf>,
+    timeouts: TimeoutOverrides,
+    registry_timeouts: BTreeMap<String, TimeoutOverrides>,
+    registry_bases: BTreeMap<String, String>,
+}
+
+impl HttpSettings {
+    pub fn new(ncbi_api_key: Option<String>) -> Self {
+        Self {
+            ncbi_api_key: ncbi_api_key
+                .map(|key| key.trim().to_string())
+                .filter(|key| !key.is_empty()),
+            rates: BTreeMap::new(),
+            clock: Arc::new(SystemClock),
+            limiters: Arc::new(Mutex::new(BTreeMap::new())),
+            proxy: ProxySettings::default(),
+            ca_bundle: None,
+            timeouts: TimeoutOverrides::default(),
+            registry_timeouts: BTreeMap::new(),
+            registry_bases: BTreeMap::new(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let mut settings = Self::new(std::env::var(NCBI_API_KEY_ENV).ok());
+        settings.proxy = ProxySettings::from_env();
+        settings.with_registry_bases(registry_bases_from_env())
+    }
+
+    /// Mirror base URLs by registry key; values set here replace earlier
+    /// ones. They are checked when a client is built from the settings.
+    pub fn with_registry_bases(mut self, bases: BTreeMap<String, String>) -> Self {
+        self.registry_bases.extend(bases);
+        self
+    }
+
+    /// The base every registry is reached at: its mirror, or its own
+    /// first base.
+    pub fn effective_registry_bases(&self) -> Vec<(&'static str, String)> {
+        REGISTRY_BASES
+            .iter()
+            .map(|registry| {
+                let base = self
+                    .registry_bases
+                    .get(registry.key)
+                    .cloned()
+                    .unwrap_or_else(|| registry.defaults[0].to_string());
+                (registry.key, base)
+            })
+            .collect()
+    }
+
+    /// The configured mirrors; fails on an unknown registry or a base that
+    /// is not an absolute http(s) URL.
+    pub fn mirrors(&self) -> Result<Mirrors, KiraError> {
+        let mut mirrors = Mirrors::default();
+        for (key, url) in &self.registry_bases {
+            let invalid = |message: &str| KiraError::InvalidRegistryBase {
+                registry: key.clone(),
+                url: url.clone(),
+                message: message.to_string(),
+            };
+            let registry = registry_base(key).ok_or_else(|| {
+                let known = REGISTRY_BASES.map(|registry| registry.key).join(", ");
+                invalid(&format!("unknown registry (expected one of {known})"))
+            })?;
+            let parsed = Url::parse(url).map_err(|err| invalid(&err.to_string()))?;
+            if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+                return Err(invalid("expected an absolute http(s) URL"));
+            }
+            if parsed.query().is_some() || parsed.fragment().is_some() {
+                return Err(invalid("a base URL takes no query or fragment"));
+            }
+            mirrors.bases.insert(registry.key, url.clone());
+        }
+        Ok(mirrors)
+    }
+
+    /// Sends both http and https requests through `proxy`, which may carry
+    /// `user:password@` credentials. `NO_PROXY` still applies.
+    pub fn with_proxy(mut self, proxy: Option<&str>) -> Result<Self, KiraError> {
+        if let Some(proxy) = proxy.map(str::trim).filter(|proxy| !proxy.is_empty()) {
+            let url = Url::parse(proxy)
+                .map_err(|err| KiraError::InvalidProxy(format!("{proxy}: {err}")))?;
+            if !matches!(url.scheme(), "http" | "https" | "socks5" | "socks5h")
+                || url.host_str().is_none()
+            {
+                return Err(KiraError::InvalidProxy(proxy.to_string()));
+            }
+            self.proxy.http = Some(proxy.to_string());
+            self.proxy.https = Some(proxy.to_string());
+        }
+        Ok(self)
+    }
+
+    pub fn with_proxy_settings(mut self, proxy: ProxySettings) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    pub fn with_ca_bundle(mut self, path: Option<PathBuf>) -> Self {
+        if path.is_some() {
+            self.ca_bundle = path;
+        }
+        self
+    }
+
+    pub fn proxy(&self) -> &ProxySettings {
+        &self.proxy
+    }
+
+    /// Reads the configured CA bundle, if there is one.
+    pub fn ca_bundle(&self) -> Option<Result<CaBundle, KiraError>> {
+        self.ca_bundle.as_deref().map(CaBundle::load)
+    }
+
+    /// Timeouts for every registry; values set here replace earlier ones.
+    pub fn with_timeouts(mut self, timeouts: TimeoutOverrides) -> Self {
+        self.timeouts = timeouts.or(self.timeouts);
+        self
+    }
+
+    /// Timeouts for one registry, taking precedence over the global ones.
+    pub fn with_registry_timeouts(mut self, registry: &str, timeouts: TimeoutOverrides) -> Self {
+        let entry = self
+            .registry_timeouts
+            .entry(registry.to_string())
+            .or_default();
+        *entry = timeouts.or(*entry);
+        self
+    }
+
+    /// The registry's own overrides, then the global ones, then the
+    /// registry's built-in values.
+    pub fn timeouts_for(&self, registry: &str) -> Timeouts {
+        let overrides = self
+            .registry_timeouts
+            .get(registry)
+            .copied()
+            .unwrap_or_default()
+            .or(self.timeouts);
+        Timeouts::default_for(registry).apply(&overrides)
+    }
+
+    /// A client builder every registry client starts from, so they all
+    /// route through the same proxies. reqwest's own proxy lookup is turned
+    /// off: it reads the environment once per process and ignores
+    /// `--proxy`.
+    ///
+    /// The client's own timeout is the registry's stall timeout: the
+    /// blocking client applies it to the response headers and to each read
+    /// of the body separately. API calls add the registry's request timeout
+    /// as an overall deadline (see [`HttpSettings::timeouts_for`]).
+    ///
+    /// The CA bundle is checked by `doctor` and before fetches but cannot be
+    /// attached here: this build of reqwest has no TLS backend to hand
+    /// roots to.
+    pub fn client_builder(&self, registry: &str) -> ClientBuilder {
+        let timeouts = self.timeouts_for(registry);
+        let builder = reqwest::blocking::Client::builder()
+            .no_proxy()
+            .connect_timeout(timeouts.connect)
+            .timeout(timeouts.stall);
+        if self.proxy.is_empty() {
+            return builder;
+        }
+        let proxy = self.proxy.clone();
+        builder.proxy(Proxy::custom(move |url| {
+            proxy.proxy_for(url).map(str::to_string)
+        }))
+    }
+
+    pub fn with_ncbi_api_key(mut self, api_key: Option<String>) -> Self {
+        if let Some(key) = api_key.map(|key| key.trim().to_string())
+            && !key.is_empty()
+        {
+            self.ncbi_api_key = Some(key);
+        }
+        self
+    }
+
+    pub fn with_rate(mut self, registry: &str, requests_per_second: f64) -> Self {
+        self.rates.insert(registry.to_string(), requests_per_second);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn ncbi_api_key(&self) -> Option<&str> {
+        self.ncbi_api_key.as_deref()
+    }
+
+    pub fn rate_for(&self, registry: &str) -> f64 {
+        if let Some(rate) = self.rates.get(registry) {
+            return *rate;
+        }
+        match registry {
+            "ncbi" if self.ncbi_api_key.is_some() => NCBI_RPS_WITH_KEY,
+            "ncbi" => NCBI_RPS,
+            "kegg" => KEGG_RPS,
+            _ => DEFAULT_RPS,
+        }
+    }
+
+    pub fn limiter(&self, registry: &str) -> Arc<RateLimiter> {
+        let mut limiters = self
+            .limiters
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        limiters
+            .entry(registry.to_string())
+            .or_insert_with(|| {
+                Arc::new(RateLimiter::with_clock(
+                    self.rate_for(registry),
+                    1.0,
+                    self.clock.clone(),
+                ))
+            })
+            .clone()
+    }
+
+    pub fn limiter_for_url(&self, url: &Url) -> Arc<RateLimiter> {
+        self.limiter(registry_for_host(url.host_str().unwrap_or_default()))
+    }
+
+    /// Appends `api_key=` to E-utilities URLs when a key is configured.
+    pub fn authorize(&self, url: &mut Url) {
+        if let Some(key) = self.ncbi_api_key.as_deref()
+            && url.host_str() == Some(EUTILS_HOST)
+            && !url.query_pairs().any(|(name, _)| name == "api_key")
+        {
+            url.query_pairs_mut().append_pair("api_key", key);
+        }
+    }
+}
+
+pub fn registry_for_host(host: &str) -> &'static str {
+    let host = host.to_ascii_lowercase();
+    if host == "ncbi.nlm.nih.gov" || host.ends_with(".ncbi.nlm.nih.gov") {
+        "ncbi"
+    } else if host.ends_with("crossref.org") {
+        "crossref"
+    } else if host.ends_with("datacite.org") {
+        "datacite"
+    } else if host.ends_with("rcsb.org") {
+        "rcsb"
+    } else if host.ends_with("uniprot.org") {
+        "uniprot"
+    } else if host.ends_with("ebi.ac.uk") {
+        "ebi"
+    } else if host.ends_with("kegg.jp") {
+        "kegg"
+    } else if host.ends_with("ensembl.org") {
+        "ensembl"
+    } else {
+        "default"
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ProbeMethod {
+    Head,
+    Get,
+}
+
+/// A cheap request whose answer shows whether a registry is up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthEndpoint {
+    /// Name accepted by `kira-bm ping`.
+    pub registry: &'static str,
+    pub name: &'static str,
+    pub method: ProbeMethod,
+    pub url: &'static str,
+}
+
+/// One endpoint per registry, each a known record or a tiny document so a
+/// probe costs the registry next to nothing.
+pub const HEALTH_ENDPOINTS: [HealthEndpoint; 9] = [
+    HealthEndpoint {
+        registry: "rcsb",
+        name: "RCSB",
+        method: ProbeMethod::Head,
+        url: "https://data.rcsb.org/rest/v1/core/entry/4HHB",
+    },
+    HealthEndpoint {
+        registry: "uniprot",
+        name: "UniProt",
+        method: ProbeMethod::Get,
+        url: "https://rest.uniprot.org/uniprotkb/P69905.json?fields=accession",
+    },
+    HealthEndpoint {
+        registry: "ncbi",
+        name: "NCBI",
+        method: ProbeMethod::Get,
+        url: "https://api.ncbi.nlm.nih.gov/datasets/v2/version",
+    },
+    HealthEndpoint {
+        registry: "eutils",
+        name: "E-utilities",
+        method: ProbeMethod::Get,
+        url: "https://eutils.ncbi.nlm.nih.gov/entrez/eutils/einfo.fcgi?retmode=json",
+    },
+    HealthEndpoint {
+        registry: "geo",
+        name: "GEO",
+        method: ProbeMethod::Head,
+        url: "https://www.ncbi.nlm.nih.gov/geo/query/acc.cgi?acc=GSE1",
+    },
+    HealthEndpoint {
+        registry: "crossref",
+        name: "Crossref",
+        method: ProbeMethod::Head,
+        url: "https://api.crossref.org/works/10.5555/12345678",
+    },
+    HealthEndpoint {
+        registry: "go",
+        name: "Gene Ontology",
+        method: ProbeMethod::Head,
+        url: "http://purl.obolibrary.org/obo/go/go-basic.obo",
+    },
+    HealthEndpoint {
+        registry: "kegg",
+        name: "KEGG",
+        method: ProbeMethod::Get,
+        url: "https://rest.kegg.jp/info/pathway",
+    },
+    HealthEndpoint {
+        registry: "reactome",
+        name: "Reactome",
+        method: ProbeMethod::Head,
+        url: "https://reactome.org/download/current/ReactomePathways.txt",
+    },
+];
+
+pub fn health_endpoint(registry: &str) -> Option<&'static HealthEndpoint> {
+    HEALTH_ENDPOINTS
+        .iter()
+        .find(|endpoint| endpoint.registry.eq_ignore_ascii_case(registry))
+}
+
+/// The `ETag` and `Last-Modified` of a stored download, sent back as
+/// `If-None-Match` and `If-Modified-Since` when it is fetched again.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Validators {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    pub fn of(response: &reqwest::blocking::Response) -> Self {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    /// These validators as the only entry of a record, or no entry when
+    /// the server sent none.
+    pub fn for_url(self, url: impl Into<String>) -> StoredValidators {
+        let mut stored = StoredValidators::new();
+        if !self.is_empty() {
+            stored.insert(url.into(), self);
+        }
+        stored
+    }
+
+    /// Makes `request` conditional on the stored copy being out of date.
+    pub fn apply(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if let Some(etag) = &self.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &self.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        request
+    }
+}
+
+/// Validators by the registry URL they were served with, as stored in a
+/// dataset's download record.
+pub type StoredValidators = BTreeMap<String, Validators>;
+
+/// What a conditional fetch got: the resource with the validators of each
+/// URL it came from, or word that the stored copy is current.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Conditional<T> {
+    Modified(T, StoredValidators),
+    NotModified,
+}
+
+impl<T> Conditional<T> {
+    /// A fetch whose client sends no validators.
+    pub fn unconditional(value: T) -> Self {
+        Self::Modified(value, StoredValidators::new())
+    }
+}
+
+/// The parts of an unsuccessful response that go into an error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedResponse {
+    pub status: u16,
+    pub url: String,
+    pub message: String,
+}
+
+impl FailedResponse {
+    pub fn read(response: reqwest::blocking::Response) -> Self {
+        let status = response.status().as_u16();
+        let url = redact_url(response.url());
+        let message = body_excerpt(&response.text().unwrap_or_default());
+        Self {
+            status,
+            url,
+            message,
+        }
+    }
+}
+
+/// `url` moved onto `base`: its path and query appended to `base`, which
+/// replaces the scheme and host. Clients built with a base URL send every
+/// registry request this way, so one local server can stand in for all
+/// registries. Without a base, or for a string that is no URL, `url` is
+/// returned unchanged.
+pub fn rebase_url(url: &str, base: Option<&str>) -> String {
+    let (Some(base), Ok(parsed)) = (base, Url::parse(url)) else {
+        return url.to_string();
+    };
+    let mut rebased = format!("{}{}", base.trim_end_matches('/'), parsed.path());
+    if let Some(query) = parsed.query() {
+        rebased.push('?');
+        rebased.push_str(query);
+    }
+    rebased
+}
+
+/// Fills in the `endpoint`, `status` and, when announced, `bytes` fields
+/// of the current span. Registry calls run in spans declaring them empty.
+pub fn record_response(response: &reqwest::blocking::Response) {
+    let span = tracing::Span::current();
+    span.record("endpoint", endpoint_of(response.url()).as_str());
+    span.record("status", response.status().as_u16());
+    if let Some(bytes) = response.content_length() {
+        span.record("bytes", bytes);
+    }
+}
+
+/// Records how much a registry call stored, once it is known.
+pub fn record_bytes(bytes: u64) {
+    tracing::Span::current().record("bytes", bytes);
+}
+
+/// Logs a request that is about to be sent again; `reason` is the status
+/// code or the transport error that failed it.
+pub fn trace_retry(
+    registry: &str,
+    attempt: usize,
+    delay: Duration,
+    reason: &dyn std::fmt::Display,
+) {
+    tracing::warn!(
+        registry,
+        attempt,
+        delay_ms = delay.as_millis() as u64,
+        %reason,
+        "retrying registry request"
+    );
+}
+
+/// `scheme://host[:port]/path`: the query is left out, as it may hold an
+/// API key or a whole search term.
+fn endpoint_of(url: &Url) -> String {
+    let mut endpoint = url.clone();
+    endpoint.set_query(None);
+    endpoint.set_fragment(None);
+    let _ = endpoint.set_password(None);
+    let _ = endpoint.set_username("");
+    endpoint.to_string()
+}
+
+/// A proxy URL with its password masked, fit for reports and manifests.
+/// Strings that are not URLs are returned unchanged.
+pub fn redact_credentials(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut parsed) if parsed.password().is_some() => {
+            let _ = parsed.set_password(Some("***"));
+            parsed.to_string()
+        }
+        _ => url.to_string(),
+    }
+}
+
+/// `url` without the NCBI API key, fit for error messages.
+pub fn redact_url(url: &Url) -> String {
+    if !url.query_pairs().any(|(name, _)| name == "api_key") {
+        return url.to_string();
+    }
+    let mut redacted = url.clone();
+    let pairs = url
+        .query_pairs()
+        .filter(|(name, _)| name != "api_key")
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    if pairs.is_empty() {
+        redacted.set_query(None);
+    } else {
+        redacted.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    redacted.to_string()
+}
+
+/// A one-line excerpt of an error response body. Registries answer with
+/// whole HTML pages, so markup is stripped and the text cut short.
+pub fn body_excerpt(body: &str) -> String {
+    let body = body.trim();
+    let text = if body.starts_with('<') {
+        strip_tags(body)
+    } else {
+        body.to_string()
+    };
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return "no response body".to_string();
+    }
+    match text.char_indices().nth(BODY_EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text,
+    }
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        text.push(' ');
+        let tag = &rest[start..];
+        // Scripts and styles are not text.
+        let skip_to = ["<script", "<style", "<head"]
+            .iter()
+            .find(|open| {
+                tag.get(..open.len())
+                    .is_some_and(|t| t.eq_ignore_ascii_case(open))
+            })
+            .and_then(|open| {
+                let close = format!("</{}", &open[1..]);
+                tag.to_ascii_lowercase().find(&close)
+            });
+        let tag = &tag[skip_to.unwrap_or(0)..];
+        rest = match tag.find('>') {
+            Some(end) => &tag[end + 1..],
+            None => "",
+        };
+    }
+    text.push_str(rest);
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+impl std::fmt::Debug for HttpSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpSettings")
+            .field("ncbi_api_key", &self.ncbi_api_key.as_ref().map(|_| "***"))
+            .field("rates", &self.rates)
+            .field("timeouts", &self.timeouts)
+            .field("registry_timeouts", &self.registry_timeouts)
+            .finish()
+    }
+}
@@ -0,0 +1,278 @@
+//! Local files taken into the project store by `kira-bm import`.
+//!
+//! Imports land where a fetch of the same specifier would have stored the
+//! dataset, so every other command treats them alike. Before anything is
+//! copied, the content is checked against the specifier: a structure must
+//! name the protein, a genome must hold sequences and a run must hold reads.
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use flate2::read::MultiGzDecoder;
+
+use crate::domain::{ProteinFormat, ProteinId};
+use crate::error::KiraError;
+use crate::rcsb::structure_file_defect;
+
+const SEQUENCE_EXTENSIONS: [&str; 4] = ["fa", "fna", "fasta", "fas"];
+const READ_EXTENSIONS: [&str; 2] = ["fastq", "fq"];
+
+/// The structure file to import for `id` and its format: `from` itself, or
+/// the `<id>.cif`, `.pdb` or `.bcif` file in the directory `from`.
+pub fn protein_file(from: &Path, id: &ProteinId) -> Result<(PathBuf, ProteinFormat), String> {
+    if from.is_file() {
+        let format = structure_format(from).ok_or_else(|| {
+            format!(
+                "{} is not a structure file (expected .cif, .pdb or .bcif)",
+                from.display()
+            )
+        })?;
+        return Ok((from.to_path_buf(), format));
+    }
+    let wanted = [ProteinFormat::Cif, ProteinFormat::Pdb, ProteinFormat::Bcif];
+    let mut found = files_under(from)?
+        .into_iter()
+        .filter(|path| path.parent() == Some(from))
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_str()?;
+            let format = structure_format(&path)?;
+            stem.eq_ignore_ascii_case(id.as_str())
+                .then_some((path, format))
+        })
+        .collect::<Vec<_>>();
+    found.sort_by_key(|(_, format)| wanted.iter().position(|wanted| wanted == format));
+    found.into_iter().next().ok_or_else(|| {
+        format!(
+            "{} holds no {id}.cif, {id}.pdb or {id}.bcif",
+            from.display()
+        )
+    })
+}
+
+fn structure_format(path: &Path) -> Option<ProteinFormat> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "cif" | "mmcif" => Some(ProteinFormat::Cif),
+        "pdb" | "ent" => Some(ProteinFormat::Pdb),
+        "bcif" => Some(ProteinFormat::Bcif),
+        _ => None,
+    }
+}
+
+/// Why the structure at `path` cannot be imported as `id`, if it cannot.
+/// BinaryCIF is checked for shape only: its entry id is not read.
+pub fn protein_defect(
+    path: &Path,
+    format: ProteinFormat,
+    id: &ProteinId,
+) -> Result<Option<String>, KiraError> {
+    if let Some(defect) = structure_file_defect(path, format)? {
+        return Ok(Some(defect));
+    }
+    let entry = match format {
+        ProteinFormat::Cif => first_line(path, |line| {
+            line.strip_prefix("_entry.id")
+                .map(|rest| rest.trim().trim_matches(['\'', '"']).to_string())
+        })?,
+        ProteinFormat::Pdb => first_line(path, |line| {
+            line.starts_with("HEADER")
+                .then(|| line.get(62..66).unwrap_or_default().trim().to_string())
+        })?,
+        ProteinFormat::Bcif => return Ok(None),
+    };
+    let named = entry
+        .as_deref()
+        .filter(|entry| !entry.is_empty())
+        .and_then(|entry| entry.parse::<ProteinId>().ok());
+    Ok(match (entry, named) {
+        (_, Some(named)) if named == *id => None,
+        (Some(entry), _) if !entry.is_empty() => {
+            Some(format!("the file is entry {entry}, not {id}"))
+        }
+        _ => Some(format!(
+            "the file does not name its entry, so it cannot be checked against {id}"
+        )),
+    })
+}
+
+/// Why the files at `from` are not a genome, if they are not: at least one
+/// FASTA file (`.fa`, `.fna`, `.fasta`, optionally gzipped) must start with
+/// a `>` header.
+pub fn genome_defect(from: &Path) -> Result<Option<String>, KiraError> {
+    data_defect(from, &SEQUENCE_EXTENSIONS, b'>', "FASTA sequence file")
+}
+
+/// Why the files at `from` are not a sequencing run, if they are not: at
+/// least one FASTQ file (`.fastq`, `.fq`, optionally gzipped) must start
+/// with an `@` read header.
+pub fn reads_defect(from: &Path) -> Result<Option<String>, KiraError> {
+    data_defect(from, &READ_EXTENSIONS, b'@', "FASTQ read file")
+}
+
+/// Why the files at `from` are not the UniProt entry `accession`: a FASTA
+/// or JSON file must mention it.
+pub fn uniprot_defect(from: &Path, accession: &str) -> Result<Option<String>, KiraError> {
+    for path in files(from)? {
+        let extension = data_extension(&path);
+        if !matches!(extension.as_deref(), Some("fasta" | "fa" | "json")) {
+            continue;
+        }
+        let mut text = String::new();
+        open_data(&path)?
+            .take(64 * 1024)
+            .read_to_string(&mut text)
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        if text.contains(accession) {
+            return Ok(None);
+        }
+    }
+    Ok(Some(format!("no FASTA or JSON file mentions {accession}")))
+}
+
+/// Why `from` holds nothing to import, if it does not.
+pub fn empty_defect(from: &Path) -> Result<Option<String>, KiraError> {
+    Ok(files(from)?
+        .is_empty()
+        .then(|| format!("{} holds no files", from.display())))
+}
+
+fn data_defect(
+    from: &Path,
+    extensions: &[&str],
+    marker: u8,
+    kind: &str,
+) -> Result<Option<String>, KiraError> {
+    let candidates = files(from)?
+        .into_iter()
+        .filter(|path| {
+            data_extension(path).is_some_and(|extension| extensions.contains(&extension.as_str()))
+        })
+        .collect::<Vec<_>>();
+    if candidates.is_empty() {
+        return Ok(Some(format!("no {kind} found")));
+    }
+    for path in &candidates {
+        let mut first = [0u8; 1];
+        let read = open_data(path)?
+            .read(&mut first)
+            .map_err(|err| KiraError::Filesystem(format!("{}: {err}", path.display())))?;
+        if read == 1 && first[0] == marker {
+            return Ok(None);
+        }
+    }
+    Ok(Some(format!(
+        "{} does not start with '{}'",
+        candidates[0].display(),
+        marker as char
+    )))
+}
+
+/// The extension of a data file, looking past a trailing `.gz`.
+fn data_extension(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+    let name = name.strip_suffix(".gz").unwrap_or(&name);
+    Some(name.rsplit_once('.')?.1.to_string())
+}
+
+fn open_data(path: &Path) -> Result<Box<dyn Read>, KiraError> {
+    let file = File::open(path)
+        .map_err(|err| KiraError::Filesystem(format!("{}: {err}", path.display())))?;
+    let gzipped = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("gz"));
+    Ok(if gzipped {
+        Box::new(MultiGzDecoder::new(file))
+    } else {
+        Box::new(file)
+    })
+}
+
+fn first_line(
+    path: &Path,
+    find: impl Fn(&str) -> Option<String>,
+) -> Result<Option<String>, KiraError> {
+    let file = File::open(path).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        if let Some(found) = find(&line) {
+            return Ok(Some(found));
+        }
+    }
+    Ok(None)
+}
+
+/// `from` itself when it is a file, else every file below it.
+fn files(from: &Path) -> Result<Vec<PathBuf>, KiraError> {
+    if from.is_file() {
+        return Ok(vec![from.to_path_buf()]);
+    }
+    files_under(from).map_err(KiraError::Filesystem)
+}
+
+fn files_under(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(current) = stack.pop() {
+        let entries =
+            fs::read_dir(&current).map_err(|err| format!("{}: {err}", current.display()))?;
+        for entry in entries {
+            let path = entry.map_err(|err| err.to_string())?.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Puts `from` (a file, or a directory's contents) at `dest`, hard-linking
+/// when `link` is set and the filesystem allows it, copying otherwise.
+/// Returns the paths written, relative to `dest` for a directory, and
+/// whether every file was linked.
+pub fn place(from: &Path, dest: &Path, link: bool) -> Result<(Vec<String>, bool), KiraError> {
+    let failed = |err: std::io::Error| KiraError::Filesystem(format!("{}: {err}", dest.display()));
+    if from.is_file() {
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(failed)?;
+        }
+        let linked = put(from, dest, link).map_err(failed)?;
+        let name = dest
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        return Ok((vec![name], linked));
+    }
+    let mut written = Vec::new();
+    let mut all_linked = link;
+    for path in files_under(from).map_err(KiraError::Filesystem)? {
+        let Ok(relative) = path.strip_prefix(from) else {
+            continue;
+        };
+        let target = dest.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(failed)?;
+        }
+        all_linked &= put(&path, &target, link).map_err(failed)?;
+        written.push(
+            relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+        );
+    }
+    Ok((written, all_linked))
+}
+
+/// Whether the file was linked rather than copied.
+fn put(from: &Path, to: &Path, link: bool) -> std::io::Result<bool> {
+    if link && fs::hard_link(from, to).is_ok() {
+        return Ok(true);
+    }
+    fs::copy(from, to)?;
+    Ok(false)
+}
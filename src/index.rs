@@ -0,0 +1,282 @@
+//! The cache index.
+//!
+//! `index.json` in the cache root lists every cached dataset with its size,
+//! a checksum over its files and the projects that have fetched it, so that
+//! `list --global` and `cache stats --by-project` need not visit each
+//! project. Fetches update it whenever they write to or copy from the
+//! cache; `kira-bm cache reindex` rebuilds it from the cache's metadata
+//! records, keeping only the project references that still hold the
+//! dataset.
+//!
+//! Updates take the `index` lock and replace the file with a rename, so
+//! parallel fetches neither lose each other's entries nor leave a partly
+//! written file behind for readers.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::app::ProgressSink;
+use crate::error::KiraError;
+use crate::fs_util::{path_size, to_canonical_json};
+use crate::store::{DownloadRecord, Metadata, Store};
+
+pub const INDEX_FILE: &str = "index.json";
+
+/// Attempts at replacing the index before giving up, for platforms where a
+/// reader holding the file open makes the rename fail.
+const REPLACE_ATTEMPTS: u32 = 5;
+const REPLACE_BACKOFF: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CacheIndex {
+    /// Sorted by type, then id.
+    pub datasets: Vec<IndexEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct IndexEntry {
+    pub dataset_type: String,
+    pub id: String,
+    pub bytes: u64,
+    /// SHA-256 over the digests of the dataset's files; absent for records
+    /// that predate file digests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Store roots of the projects holding a copy.
+    #[serde(default)]
+    pub projects: BTreeSet<String>,
+}
+
+impl IndexEntry {
+    pub fn new(
+        dataset_type: &str,
+        id: &str,
+        cache_path: &Path,
+        download: Option<&DownloadRecord>,
+    ) -> Self {
+        Self {
+            dataset_type: dataset_type.to_string(),
+            id: id.to_string(),
+            bytes: download
+                .map(|download| download.bytes_written)
+                .or_else(|| path_size(cache_path))
+                .unwrap_or(0),
+            sha256: download.and_then(dataset_checksum),
+            projects: BTreeSet::new(),
+        }
+    }
+}
+
+impl CacheIndex {
+    /// Adds `entry`, or refreshes the size and checksum of the one with its
+    /// key and adds its projects to those already recorded.
+    pub fn upsert(&mut self, entry: IndexEntry) {
+        let key = (entry.dataset_type.as_str(), entry.id.as_str());
+        match self
+            .datasets
+            .binary_search_by(|known| (known.dataset_type.as_str(), known.id.as_str()).cmp(&key))
+        {
+            Ok(at) => {
+                let known = &mut self.datasets[at];
+                known.bytes = entry.bytes;
+                known.sha256 = entry.sha256;
+                known.projects.extend(entry.projects);
+            }
+            Err(at) => self.datasets.insert(at, entry),
+        }
+    }
+
+    /// Every project the index names, with the datasets it holds and their
+    /// size in the cache.
+    pub fn usage(&self) -> CacheUsage {
+        let mut projects = BTreeMap::<&str, ProjectUsage>::new();
+        for entry in &self.datasets {
+            for project in &entry.projects {
+                let usage = projects.entry(project).or_insert_with(|| ProjectUsage {
+                    project: project.clone(),
+                    datasets: 0,
+                    bytes: 0,
+                });
+                usage.datasets += 1;
+                usage.bytes += entry.bytes;
+            }
+        }
+        CacheUsage {
+            projects: projects.into_values().collect(),
+        }
+    }
+}
+
+/// What `cache stats --by-project` prints.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct CacheUsage {
+    pub projects: Vec<ProjectUsage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct ProjectUsage {
+    pub project: String,
+    pub datasets: usize,
+    /// Cache bytes of those datasets; a dataset shared by several projects
+    /// counts for each.
+    pub bytes: u64,
+}
+
+/// What `cache reindex` prints.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct ReindexReport {
+    pub datasets: usize,
+    pub projects: usize,
+    /// Project references dropped because the project no longer holds the
+    /// dataset.
+    pub dropped_references: usize,
+}
+
+/// A digest of the file digests a download recorded, stable across stores.
+pub fn dataset_checksum(download: &DownloadRecord) -> Option<String> {
+    if download.sha256.is_empty() {
+        return None;
+    }
+    let mut hasher = Sha256::new();
+    for (path, digest) in &download.sha256 {
+        hasher.update(format!("{digest}  {path}\n").as_bytes());
+    }
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+/// The index under `cache_root`, or `None` when there is none yet.
+pub fn read(cache_root: &Utf8Path) -> Result<Option<CacheIndex>, KiraError> {
+    let path = cache_root.join(INDEX_FILE);
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        // A disabled cache (`/dev/null`) has no index either.
+        Err(err)
+            if matches!(
+                err.kind(),
+                io::ErrorKind::NotFound | io::ErrorKind::NotADirectory
+            ) =>
+        {
+            return Ok(None);
+        }
+        Err(err) => return Err(KiraError::Filesystem(format!("{path}: {err}"))),
+    };
+    serde_json::from_slice(&bytes)
+        .map(Some)
+        .map_err(|err| KiraError::Filesystem(format!("{path}: {err}")))
+}
+
+/// The index, built by scanning the cache when there is none yet.
+pub fn load(
+    store: &Store,
+    timeout: Duration,
+    sink: &dyn ProgressSink,
+) -> Result<CacheIndex, KiraError> {
+    match read(store.cache_root())? {
+        Some(index) => Ok(index),
+        None => rebuild(store, timeout, sink).map(|(index, _)| index),
+    }
+}
+
+/// Applies `change` to the index under its lock. An index that cannot be
+/// read is started afresh; `cache reindex` recovers what it knew.
+pub fn update(
+    store: &Store,
+    timeout: Duration,
+    sink: &dyn ProgressSink,
+    change: impl FnOnce(&mut CacheIndex),
+) -> Result<CacheIndex, KiraError> {
+    let _lock = store.lock_dataset("index", "index", timeout, sink)?;
+    let mut index = read(store.cache_root()).ok().flatten().unwrap_or_default();
+    change(&mut index);
+    write(store.cache_root(), &index)?;
+    Ok(index)
+}
+
+/// Rebuilds the index from the cache's metadata records. A project keeps
+/// its reference to a dataset while its store still has a record of it.
+pub fn rebuild(
+    store: &Store,
+    timeout: Duration,
+    sink: &dyn ProgressSink,
+) -> Result<(CacheIndex, ReindexReport), KiraError> {
+    let _lock = store.lock_dataset("index", "index", timeout, sink)?;
+    let previous = read(store.cache_root()).ok().flatten().unwrap_or_default();
+    let mut known = previous
+        .datasets
+        .into_iter()
+        .map(|entry| ((entry.dataset_type, entry.id), entry.projects))
+        .collect::<BTreeMap<_, _>>();
+    let mut report = ReindexReport::default();
+    let mut index = CacheIndex::default();
+    for (_, metadata) in Store::list_metadata_files(store.cache_root())? {
+        let cache_path = Path::new(&metadata.resolved_path);
+        if metadata.is_expired() || !cache_path.exists() {
+            continue;
+        }
+        let mut entry = IndexEntry::new(
+            &metadata.dataset_type,
+            &metadata.id,
+            cache_path,
+            metadata.download.as_ref(),
+        );
+        let mut candidates = known
+            .remove(&(metadata.dataset_type.clone(), metadata.id.clone()))
+            .unwrap_or_default();
+        candidates.insert(store.project_root().to_string());
+        for project in candidates {
+            if holds(store, &project, &metadata) {
+                entry.projects.insert(project);
+            } else if project != store.project_root().as_str() {
+                report.dropped_references += 1;
+            }
+        }
+        index.upsert(entry);
+    }
+    report.dropped_references += known.values().map(BTreeSet::len).sum::<usize>();
+    report.datasets = index.datasets.len();
+    report.projects = index.usage().projects.len();
+    write(store.cache_root(), &index)?;
+    Ok((index, report))
+}
+
+fn holds(store: &Store, project: &str, metadata: &Metadata) -> bool {
+    let project =
+        Store::new_with_paths(Utf8PathBuf::from(project), store.cache_root().to_path_buf());
+    project
+        .project_metadata_path(&metadata.dataset_type, &metadata.id)
+        .as_std_path()
+        .exists()
+}
+
+/// Replaces the index through a temp file of this process's own, so two
+/// writers never share one.
+fn write(cache_root: &Utf8Path, index: &CacheIndex) -> Result<(), KiraError> {
+    fs::create_dir_all(cache_root.as_std_path())
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    let path = cache_root.join(INDEX_FILE);
+    let tmp_path = cache_root.join(format!("{INDEX_FILE}.{}.tmp", std::process::id()));
+    fs::write(tmp_path.as_std_path(), to_canonical_json(index)?)
+        .map_err(|err| KiraError::Filesystem(format!("{tmp_path}: {err}")))?;
+    let mut attempt = 1;
+    loop {
+        match fs::rename(tmp_path.as_std_path(), path.as_std_path()) {
+            Ok(()) => return Ok(()),
+            Err(_) if attempt < REPLACE_ATTEMPTS => {
+                attempt += 1;
+                std::thread::sleep(REPLACE_BACKOFF);
+            }
+            Err(err) => {
+                let _ = fs::remove_file(tmp_path.as_std_path());
+                return Err(KiraError::Filesystem(format!("{path}: {err}")));
+            }
+        }
+    }
+}
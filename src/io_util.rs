@@ -0,0 +1,89 @@
+//! Reading user-supplied text inputs.
+//!
+//! Any input may be gzip-compressed regardless of its name: compression is
+//! detected from the magic bytes and undone while the input is read.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+use std::path::Path;
+
+use flate2::bufread::MultiGzDecoder;
+use serde::de::DeserializeOwned;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// A text input, decompressed on the fly when it is gzip.
+pub struct TextReader {
+    inner: Box<dyn BufRead>,
+    gzip: bool,
+}
+
+impl TextReader {
+    pub fn is_gzip(&self) -> bool {
+        self.gzip
+    }
+}
+
+impl Read for TextReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl BufRead for TextReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.inner.consume(amount)
+    }
+}
+
+pub fn open_maybe_gzip(path: &Path) -> io::Result<TextReader> {
+    maybe_gzip(BufReader::new(File::open(path)?))
+}
+
+/// Wraps an already open input such as stdin.
+pub fn maybe_gzip(mut reader: impl BufRead + 'static) -> io::Result<TextReader> {
+    // Pipes may deliver a single byte at first, so the magic is read out and
+    // put back in front rather than peeked.
+    let mut head = Vec::with_capacity(GZIP_MAGIC.len());
+    reader
+        .by_ref()
+        .take(GZIP_MAGIC.len() as u64)
+        .read_to_end(&mut head)?;
+    let gzip = head == GZIP_MAGIC;
+    let reader = Cursor::new(head).chain(reader);
+    let inner: Box<dyn BufRead> = if gzip {
+        Box::new(BufReader::new(MultiGzDecoder::new(reader)))
+    } else {
+        Box::new(reader)
+    };
+    Ok(TextReader { inner, gzip })
+}
+
+/// Why a text input could not be used.
+#[derive(Debug)]
+pub enum InputError {
+    /// The file could not be opened or read.
+    Read(io::Error),
+    /// The input is gzip but its stream does not decompress.
+    CorruptGzip(String),
+    /// The content, after any decompression, is not what was expected.
+    Malformed { message: String, gzip: bool },
+}
+
+/// Parses a JSON input, streaming it through gzip decompression if needed.
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, InputError> {
+    let reader = open_maybe_gzip(path).map_err(InputError::Read)?;
+    let gzip = reader.is_gzip();
+    serde_json::from_reader(reader).map_err(|err| match (err.is_io(), gzip) {
+        (true, true) => InputError::CorruptGzip(err.to_string()),
+        (true, false) => InputError::Read(io::Error::other(err)),
+        (false, gzip) => InputError::Malformed {
+            message: err.to_string(),
+            gzip,
+        },
+    })
+}
@@ -1,58 +1,290 @@
 use std::fs::File;
-use std::io::Write;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
 use std::path::Path;
 use std::time::Duration;
 
+use clap::ValueEnum;
+use flate2::read::GzDecoder;
 use reqwest::blocking::Client;
 use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+use serde::{Deserialize, Serialize};
+use tracing::field::Empty;
 
+use crate::app::ProgressSink;
+use crate::cancel;
+use crate::domain::{GoaSpecies, KeggOrganism};
 use crate::error::KiraError;
+use crate::fs_util::{HashingWriter, sha256_bytes};
+use crate::http::{
+    Conditional, FailedResponse, HttpSettings, Mirrors, StoredValidators, Validators, record_bytes,
+    record_response, registry_for_host,
+};
+use crate::progress::Metered;
+
+const KEGG_REST: &str = "https://rest.kegg.jp";
+pub const REACTOME_DOWNLOAD: &str = "https://reactome.org/download/current";
+const GOA_FTP: &str = "https://ftp.ebi.ac.uk/pub/databases/GO/goa";
+
+/// Which edition of the Gene Ontology a `go` fetch downloads.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum GoFlavor {
+    /// `go-basic.obo`: acyclic, only relations that propagate annotations.
+    #[default]
+    Basic,
+    /// `go.obo`: every relation type.
+    Full,
+}
+
+impl GoFlavor {
+    pub fn file_name(self) -> &'static str {
+        match self {
+            GoFlavor::Basic => "go-basic.obo",
+            GoFlavor::Full => "go.obo",
+        }
+    }
+
+    pub fn url(self) -> &'static str {
+        match self {
+            GoFlavor::Basic => "http://purl.obolibrary.org/obo/go/go-basic.obo",
+            GoFlavor::Full => "http://purl.obolibrary.org/obo/go.obo",
+        }
+    }
+}
+
+impl std::fmt::Display for GoFlavor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GoFlavor::Basic => write!(f, "basic"),
+            GoFlavor::Full => write!(f, "full"),
+        }
+    }
+}
+
+/// File name of a species' GOA annotations, e.g. `goa_human.gaf.gz`.
+pub fn goa_file_name(species: &GoaSpecies) -> String {
+    format!("goa_{species}.gaf.gz")
+}
+
+/// EBI GOA location of a species' annotations; directories are upper case.
+pub fn goa_url(species: &GoaSpecies) -> String {
+    format!(
+        "{GOA_FTP}/{}/{}",
+        species.as_str().to_ascii_uppercase(),
+        goa_file_name(species)
+    )
+}
 
 pub trait KnowledgeClient: Send + Sync {
-    fn download_go(&self, destination: &Path) -> Result<Vec<u8>, KiraError>;
-    fn download_kegg_pathways(&self, destination: &Path) -> Result<(), KiraError>;
-    fn download_kegg_pathway_links(&self, destination: &Path) -> Result<(), KiraError>;
-    fn download_reactome_pathways(&self, destination: &Path) -> Result<(), KiraError>;
-    fn download_reactome_mappings(&self, destination: &Path) -> Result<(), KiraError>;
+    fn download_go_ontology(
+        &self,
+        flavor: GoFlavor,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<Vec<u8>, KiraError>;
+    /// Downloads the ontology unless `stored` validators show the stored
+    /// copy is current. Clients that cannot ask always download.
+    fn download_go_ontology_if_modified(
+        &self,
+        flavor: GoFlavor,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+        _stored: &StoredValidators,
+    ) -> Result<Conditional<Vec<u8>>, KiraError> {
+        self.download_go_ontology(flavor, destination, sink)
+            .map(Conditional::unconditional)
+    }
+    /// A species' gzipped GAF file from EBI GOA.
+    fn download_go_annotations(
+        &self,
+        species: &GoaSpecies,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<Vec<u8>, KiraError> {
+        Err(KiraError::KnowledgeHttp(format!(
+            "GO annotations for {species} are not available from this client"
+        )))
+    }
+    fn download_kegg_pathways(
+        &self,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError>;
+    fn download_kegg_pathway_links(
+        &self,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError>;
+    fn download_reactome_pathways(
+        &self,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError>;
+    fn download_reactome_mappings(
+        &self,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError>;
+    /// `ReactomePathwaysRelation.txt`: parent-child pairs of the pathway
+    /// hierarchy.
+    fn download_reactome_relations(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp(
+            "the Reactome pathway hierarchy is not available from this client".to_string(),
+        ))
+    }
+    /// Body of `info/<database>`, which declares the database's entry count.
+    fn fetch_kegg_info(&self, database: &str) -> Result<String, KiraError>;
+    /// Body of `link/<target>/<id1>+<id2>...`; empty when KEGG has no links.
+    fn fetch_kegg_links(&self, target: &str, ids: &[String]) -> Result<String, KiraError>;
+    /// `list/pathway/<org>`: the organism's pathways.
+    fn download_kegg_organism_pathways(
+        &self,
+        organism: &KeggOrganism,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp(format!(
+            "KEGG pathways for {organism} are not available from this client"
+        )))
+    }
+    /// `link/<org>/pathway`: the organism's gene-pathway pairs.
+    fn download_kegg_gene_links(
+        &self,
+        organism: &KeggOrganism,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp(format!(
+            "KEGG gene links for {organism} are not available from this client"
+        )))
+    }
 }
 
 #[derive(Clone)]
 pub struct KnowledgeHttpClient {
     client: Client,
+    settings: HttpSettings,
+    mirrors: Mirrors,
 }
 
 impl KnowledgeHttpClient {
     pub fn new() -> Result<Self, KiraError> {
+        Self::with_settings(HttpSettings::from_env())
+    }
+
+    pub fn with_settings(settings: HttpSettings) -> Result<Self, KiraError> {
         let mut headers = HeaderMap::new();
         headers.insert(
             USER_AGENT,
             HeaderValue::from_str(&format!("kira-bm/{}", env!("CARGO_PKG_VERSION")))
                 .map_err(|err| KiraError::Filesystem(err.to_string()))?,
         );
-        let client = Client::builder()
+        let client = settings
+            .client_builder("kb")
             .default_headers(headers)
-            .timeout(Duration::from_secs(60))
             .build()
             .map_err(|err| KiraError::KnowledgeHttp(err.to_string()))?;
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            mirrors: settings.mirrors()?,
+            settings,
+        })
+    }
+
+    /// Sends every request to `base_url` instead of the GO, KEGG and
+    /// Reactome hosts.
+    pub fn with_base_url(settings: HttpSettings, base_url: &str) -> Result<Self, KiraError> {
+        Ok(Self {
+            mirrors: Mirrors::everywhere(base_url),
+            ..Self::with_settings(settings)?
+        })
     }
 
-    fn download(&self, url: &str, destination: &Path) -> Result<Vec<u8>, KiraError> {
-        let response = self
-            .client
-            .get(url)
+    /// A GET with no overall deadline, only the stall timeout, unless
+    /// `deadline` is set.
+    fn get(
+        &self,
+        url: &str,
+        deadline: Option<Duration>,
+        validators: &Validators,
+    ) -> Result<reqwest::blocking::Response, KiraError> {
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        self.settings.limiter(registry_for_host(&host)).acquire();
+        let mut request = validators.apply(self.client.get(self.mirrors.rewrite(url)));
+        if let Some(deadline) = deadline {
+            request = request.timeout(deadline);
+        }
+        let response = request
             .send()
             .map_err(|err| KiraError::KnowledgeHttp(err.to_string()))?;
+        record_response(&response);
+        Ok(response)
+    }
+
+    fn download(
+        &self,
+        dataset: &str,
+        url: &str,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<Vec<u8>, KiraError> {
+        match self.download_if_modified(dataset, url, destination, sink, &Validators::default())? {
+            Conditional::Modified(bytes, _) => Ok(bytes),
+            Conditional::NotModified => Err(KiraError::KnowledgeHttp(format!(
+                "{url} answered an unconditional request with 304"
+            ))),
+        }
+    }
+
+    #[tracing::instrument(
+        name = "kb.download",
+        skip_all,
+        fields(registry = "kb", dataset = dataset, endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    fn download_if_modified(
+        &self,
+        dataset: &str,
+        url: &str,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+        validators: &Validators,
+    ) -> Result<Conditional<Vec<u8>>, KiraError> {
+        let response = self.get(url, None, validators)?;
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(Conditional::NotModified);
+        }
         if !response.status().is_success() {
-            let status = response.status().as_u16();
-            let message = response
-                .text()
-                .unwrap_or_else(|_| "knowledge base request failed".to_string());
-            return Err(KiraError::KnowledgeStatus { status, message });
-        }
-        let bytes = response
-            .bytes()
-            .map_err(|err| KiraError::KnowledgeHttp(err.to_string()))?;
+            return Err(status_error(FailedResponse::read(response), dataset));
+        }
+        let validators = Validators::of(&response);
+        let total = response.content_length();
+        let mut bytes = Vec::new();
+        cancel::copy(
+            &mut Metered::new(response, sink, destination, total),
+            &mut bytes,
+            KiraError::KnowledgeHttp,
+        )?;
+        record_bytes(bytes.len() as u64);
         if let Some(parent) = destination.parent() {
             std::fs::create_dir_all(parent)
                 .map_err(|err| KiraError::Filesystem(err.to_string()))?;
@@ -61,56 +293,342 @@ impl KnowledgeHttpClient {
             File::create(destination).map_err(|err| KiraError::Filesystem(err.to_string()))?;
         file.write_all(&bytes)
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        Ok(bytes.to_vec())
+        Ok(Conditional::Modified(bytes, validators.for_url(url)))
+    }
+
+    /// Streams the body to `destination` rather than holding it in memory;
+    /// Reactome's mapping files run to hundreds of megabytes. Returns the
+    /// file's SHA-256.
+    #[tracing::instrument(
+        name = "kb.save",
+        skip_all,
+        fields(registry = "kb", dataset = dataset, endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    fn save(
+        &self,
+        dataset: &str,
+        url: &str,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        let response = self.get(url, None, &Validators::default())?;
+        if !response.status().is_success() {
+            return Err(status_error(FailedResponse::read(response), dataset));
+        }
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        }
+        let mut file = HashingWriter::new(
+            File::create(destination).map_err(|err| KiraError::Filesystem(err.to_string()))?,
+        );
+        let total = response.content_length();
+        let result = cancel::copy(
+            &mut Metered::new(response, sink, destination, total),
+            &mut file,
+            KiraError::KnowledgeHttp,
+        );
+        let (file, digest) = file.finish();
+        drop(file);
+        if result.is_err() {
+            let _ = std::fs::remove_file(destination);
+        }
+        record_bytes(result?);
+        Ok(digest)
+    }
+
+    #[tracing::instrument(
+        name = "kb.fetch_text",
+        skip_all,
+        fields(registry = "kb", dataset = "kegg", endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    fn fetch_text(&self, url: &str) -> Result<String, KiraError> {
+        let response = self.get(
+            url,
+            Some(self.settings.timeouts_for("kb").request),
+            &Validators::default(),
+        )?;
+        let status = response.status();
+        // KEGG answers 404 with an empty body when a query has no results.
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(String::new());
+        }
+        if !status.is_success() {
+            return Err(status_error(FailedResponse::read(response), "kegg"));
+        }
+        let text = response
+            .text()
+            .map_err(|err| KiraError::KnowledgeHttp(err.to_string()))?;
+        record_bytes(text.len() as u64);
+        Ok(text)
+    }
+}
+
+fn status_error(failed: FailedResponse, dataset: &str) -> KiraError {
+    KiraError::KnowledgeStatus {
+        status: failed.status,
+        url: failed.url,
+        dataset: dataset.to_string(),
+        message: failed.message,
     }
 }
 
 impl KnowledgeClient for KnowledgeHttpClient {
-    fn download_go(&self, destination: &Path) -> Result<Vec<u8>, KiraError> {
-        self.download(
-            "http://purl.obolibrary.org/obo/go/go-basic.obo",
+    fn download_go_ontology(
+        &self,
+        flavor: GoFlavor,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<Vec<u8>, KiraError> {
+        self.download("go", flavor.url(), destination, sink)
+    }
+
+    fn download_go_ontology_if_modified(
+        &self,
+        flavor: GoFlavor,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+        stored: &StoredValidators,
+    ) -> Result<Conditional<Vec<u8>>, KiraError> {
+        let validators = stored.get(flavor.url()).cloned().unwrap_or_default();
+        self.download_if_modified("go", flavor.url(), destination, sink, &validators)
+    }
+
+    fn download_go_annotations(
+        &self,
+        species: &GoaSpecies,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<Vec<u8>, KiraError> {
+        self.download("go", &goa_url(species), destination, sink)
+    }
+
+    fn download_kegg_pathways(
+        &self,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        let bytes = self.download(
+            "kegg",
+            &format!("{KEGG_REST}/list/pathway"),
+            destination,
+            sink,
+        )?;
+        Ok(sha256_bytes(&bytes))
+    }
+
+    fn download_kegg_pathway_links(
+        &self,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        let bytes = self.download(
+            "kegg",
+            &format!("{KEGG_REST}/link/pathway/ko"),
+            destination,
+            sink,
+        )?;
+        Ok(sha256_bytes(&bytes))
+    }
+
+    fn download_reactome_pathways(
+        &self,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        self.save(
+            "reactome",
+            &format!("{REACTOME_DOWNLOAD}/ReactomePathways.txt"),
             destination,
+            sink,
         )
     }
 
-    fn download_kegg_pathways(&self, destination: &Path) -> Result<(), KiraError> {
-        let _ = self.download("https://rest.kegg.jp/list/pathway", destination)?;
-        Ok(())
+    fn download_reactome_mappings(
+        &self,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        self.save(
+            "reactome",
+            &format!("{REACTOME_DOWNLOAD}/UniProt2Reactome.txt"),
+            destination,
+            sink,
+        )
+    }
+
+    fn download_reactome_relations(
+        &self,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        self.save(
+            "reactome",
+            &format!("{REACTOME_DOWNLOAD}/ReactomePathwaysRelation.txt"),
+            destination,
+            sink,
+        )
     }
 
-    fn download_kegg_pathway_links(&self, destination: &Path) -> Result<(), KiraError> {
-        let _ = self.download("https://rest.kegg.jp/link/pathway/ko", destination)?;
-        Ok(())
+    fn fetch_kegg_info(&self, database: &str) -> Result<String, KiraError> {
+        self.fetch_text(&format!("{KEGG_REST}/info/{database}"))
     }
 
-    fn download_reactome_pathways(&self, destination: &Path) -> Result<(), KiraError> {
-        let _ = self.download(
-            "https://reactome.org/download/current/ReactomePathways.txt",
+    fn fetch_kegg_links(&self, target: &str, ids: &[String]) -> Result<String, KiraError> {
+        self.fetch_text(&format!("{KEGG_REST}/link/{target}/{}", ids.join("+")))
+    }
+
+    fn download_kegg_organism_pathways(
+        &self,
+        organism: &KeggOrganism,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        let bytes = self.download(
+            &format!("kegg:{organism}"),
+            &format!("{KEGG_REST}/list/pathway/{organism}"),
             destination,
+            sink,
         )?;
-        Ok(())
+        Ok(sha256_bytes(&bytes))
     }
 
-    fn download_reactome_mappings(&self, destination: &Path) -> Result<(), KiraError> {
-        let _ = self.download(
-            "https://reactome.org/download/current/UniProt2Reactome.txt",
+    fn download_kegg_gene_links(
+        &self,
+        organism: &KeggOrganism,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        let bytes = self.download(
+            &format!("kegg:{organism}"),
+            &format!("{KEGG_REST}/link/{organism}/pathway"),
             destination,
+            sink,
         )?;
-        Ok(())
+        Ok(sha256_bytes(&bytes))
     }
 }
 
+/// Version and date from the header of an OBO file or a (gzipped) GAF file,
+/// whose header lines start with `!`.
 pub fn parse_go_header(content: &[u8]) -> (Option<String>, Option<String>) {
     let mut version = None;
     let mut date = None;
-    let text = String::from_utf8_lossy(content);
+    let text = if content.starts_with(&[0x1f, 0x8b]) {
+        let mut head = Vec::new();
+        // A GAF file is large and its header short; a truncated read is fine.
+        let _ = GzDecoder::new(content)
+            .take(64 * 1024)
+            .read_to_end(&mut head);
+        String::from_utf8_lossy(&head).into_owned()
+    } else {
+        String::from_utf8_lossy(content).into_owned()
+    };
     for line in text.lines().take(50) {
-        if let Some(value) = line.strip_prefix("data-version:") {
+        let line = line.trim_start_matches('!').trim_start();
+        if let Some(value) = line
+            .strip_prefix("data-version:")
+            .or_else(|| line.strip_prefix("go-version:"))
+        {
             version = Some(value.trim().to_string());
         }
-        if let Some(value) = line.strip_prefix("date:") {
+        if let Some(value) = line
+            .strip_prefix("date:")
+            .or_else(|| line.strip_prefix("date-generated:"))
+            .or_else(|| line.strip_prefix("Generated:"))
+        {
             date = Some(value.trim().to_string());
         }
     }
     (version, date)
 }
+
+/// Copies the lines of a Reactome export whose last tab-separated column is
+/// `species` (compared case-insensitively), one line at a time. Returns the
+/// number of lines read and written and the SHA-256 of what was written.
+pub fn filter_reactome_species(
+    source: &Path,
+    destination: &Path,
+    species: &str,
+) -> Result<(usize, usize, String), KiraError> {
+    let io_error = |err: std::io::Error| KiraError::Filesystem(err.to_string());
+    let reader = BufReader::new(File::open(source).map_err(io_error)?);
+    let mut writer =
+        HashingWriter::new(BufWriter::new(File::create(destination).map_err(io_error)?));
+    let (mut read, mut written) = (0, 0);
+    for line in reader.lines() {
+        let line = line.map_err(io_error)?;
+        if line.is_empty() {
+            continue;
+        }
+        read += 1;
+        let column = line.rsplit('\t').next().unwrap_or_default();
+        if column.trim().eq_ignore_ascii_case(species.trim()) {
+            writer.write_all(line.as_bytes()).map_err(io_error)?;
+            writer.write_all(b"\n").map_err(io_error)?;
+            written += 1;
+        }
+    }
+    writer.flush().map_err(io_error)?;
+    let (writer, digest) = writer.finish();
+    drop(writer);
+    Ok((read, written, digest))
+}
+
+/// Entry count declared by a KEGG `info/<database>` response, e.g.
+/// `pathway          Release 110.0+/05-01, May 24\n                 574 entries`.
+pub fn parse_kegg_info_entries(text: &str) -> Option<usize> {
+    text.lines().find_map(|line| {
+        let mut words = line.split_whitespace();
+        let count = words.next()?;
+        let unit = words.next()?;
+        (unit == "entries").then_some(())?;
+        count.replace(',', "").parse().ok()
+    })
+}
+
+/// Pathway ids from `list/pathway`, with any `path:` prefix removed.
+pub fn kegg_list_ids(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| line.split('\t').next())
+        .map(|id| id.trim().trim_start_matches("path:").to_string())
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+/// Why a two-column link response cannot be trusted, if anything: a missing
+/// trailing newline or a malformed line means the body was cut off.
+pub fn kegg_links_defect(text: &str) -> Option<String> {
+    if text.is_empty() {
+        return None;
+    }
+    if !text.ends_with('\n') {
+        return Some("response ends mid-line".to_string());
+    }
+    text.lines()
+        .enumerate()
+        .find(|(_, line)| {
+            let mut columns = line.split('\t');
+            let well_formed = matches!(
+                (columns.next(), columns.next(), columns.next()),
+                (Some(left), Some(right), None) if left.contains(':') && right.contains(':')
+            );
+            !well_formed
+        })
+        .map(|(index, _)| format!("malformed line {}", index + 1))
+}
+
+/// Rewrites `link/ko/<pathways>` lines into the `ko:K00001\tpath:map00010`
+/// orientation of the bulk `link/pathway/ko` file.
+pub fn normalize_kegg_ko_links(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(left, right)| {
+            if left.starts_with("path:") {
+                format!("{right}\t{left}")
+            } else {
+                format!("{left}\t{right}")
+            }
+        })
+        .collect()
+}
@@ -1,15 +1,40 @@
 pub mod app;
+pub mod audit;
+pub mod cancel;
+pub mod completion;
 pub mod config;
+pub mod control;
+pub mod convert;
+pub mod doctor;
 pub mod domain;
 pub mod error;
 pub mod fs_util;
 pub mod geo;
+pub mod history;
+pub mod http;
+pub mod import;
+pub mod index;
+pub mod io_util;
 pub mod knowledge;
+pub mod lock;
+pub mod manifest;
 pub mod ncbi;
 pub mod output;
+pub mod ping;
+pub mod progress;
+pub mod project;
 pub mod providers;
 pub mod rcsb;
+pub mod readme;
+pub mod retention;
+pub mod runs;
+pub mod schema;
+pub mod search;
+pub mod soft;
 pub mod srr;
+pub mod stats;
 pub mod store;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
 pub mod tui;
 pub mod uniprot;
@@ -0,0 +1,226 @@
+//! Per-dataset advisory locks.
+//!
+//! Every fetch holds `locks/<type>/<id>.lock` under the cache root while it
+//! downloads and installs, so two processes (or threads) fetching the same
+//! dataset take turns and the second finds the first one's result. The lock
+//! is an OS file lock (`flock` on Unix); the file itself names the holder.
+//! Where the filesystem has no file locking, a `.pid` file created
+//! exclusively takes its place and is reclaimed once its process is gone.
+
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{ErrorKind, Write};
+use std::time::{Duration, Instant};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{ProgressEvent, ProgressLevel, ProgressSink, check_cancelled};
+use crate::error::KiraError;
+
+pub const LOCKS_DIR: &str = "locks";
+/// How long a fetch waits for another holder before giving up.
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// PID lock files whose process cannot be checked are reclaimed after this.
+const PID_LOCK_STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Contents of a lock file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockHolder {
+    pub pid: u32,
+    pub acquired_at: String,
+}
+
+impl LockHolder {
+    fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            acquired_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+impl std::fmt::Display for LockHolder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "pid {} since {}", self.pid, self.acquired_at)
+    }
+}
+
+/// A held dataset lock; released on drop.
+#[derive(Debug)]
+pub struct DatasetLock {
+    path: Utf8PathBuf,
+    /// `None` when the lock is a PID file rather than an OS lock.
+    file: Option<File>,
+}
+
+impl DatasetLock {
+    /// Takes the lock at `path`, polling until `timeout` while another
+    /// holder has it. `label` names the dataset in events and errors.
+    pub fn acquire(
+        path: &Utf8Path,
+        label: &str,
+        timeout: Duration,
+        sink: &dyn ProgressSink,
+    ) -> Result<Self, KiraError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        }
+        let started = Instant::now();
+        let mut announced = false;
+        loop {
+            if let Some(lock) = Self::try_acquire(path, label, sink)? {
+                return Ok(lock);
+            }
+            let holder = read_holder(path)
+                .or_else(|| read_holder(&pid_path(path)))
+                .map(|holder| holder.to_string())
+                .unwrap_or_else(|| "unknown process".to_string());
+            let waited = started.elapsed();
+            if waited >= timeout {
+                return Err(KiraError::StoreLocked {
+                    dataset: label.to_string(),
+                    holder,
+                    waited_secs: waited.as_secs(),
+                });
+            }
+            if !announced {
+                sink.event(ProgressEvent {
+                    message: format!("lock.wait {label}; held by {holder}"),
+                    elapsed: None,
+                    level: ProgressLevel::Phase,
+                });
+                announced = true;
+            }
+            check_cancelled(sink)?;
+            std::thread::sleep(POLL_INTERVAL.min(timeout - waited));
+        }
+    }
+
+    fn try_acquire(
+        path: &Utf8Path,
+        label: &str,
+        sink: &dyn ProgressSink,
+    ) -> Result<Option<Self>, KiraError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(|err| KiraError::Filesystem(format!("{path}: {err}")))?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Ok(None),
+            Err(TryLockError::Error(err)) if err.kind() == ErrorKind::Unsupported => {
+                return Self::try_acquire_pid_file(path, label, sink);
+            }
+            Err(TryLockError::Error(err)) => {
+                return Err(KiraError::Filesystem(format!("{path}: {err}")));
+            }
+        }
+        // A clean release empties the file, so a named holder here is a
+        // process that died with the lock.
+        if let Some(stale) = read_holder(path) {
+            sink.event(ProgressEvent {
+                message: format!("lock.reclaimed {label}; stale lock from {stale}"),
+                elapsed: None,
+                level: ProgressLevel::Detail,
+            });
+        }
+        write_holder(&mut file, path)?;
+        Ok(Some(Self {
+            path: path.to_path_buf(),
+            file: Some(file),
+        }))
+    }
+
+    fn try_acquire_pid_file(
+        path: &Utf8Path,
+        label: &str,
+        sink: &dyn ProgressSink,
+    ) -> Result<Option<Self>, KiraError> {
+        let pid_path = pid_path(path);
+        let mut reclaimed = false;
+        loop {
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&pid_path)
+            {
+                Ok(mut file) => {
+                    write_holder(&mut file, &pid_path)?;
+                    return Ok(Some(Self {
+                        path: pid_path,
+                        file: None,
+                    }));
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists && !reclaimed => {
+                    let Some(stale) = read_holder(&pid_path).filter(is_stale) else {
+                        return Ok(None);
+                    };
+                    sink.event(ProgressEvent {
+                        message: format!("lock.reclaimed {label}; stale lock from {stale}"),
+                        elapsed: None,
+                        level: ProgressLevel::Detail,
+                    });
+                    let _ = fs::remove_file(&pid_path);
+                    reclaimed = true;
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => return Ok(None),
+                Err(err) => return Err(KiraError::Filesystem(format!("{pid_path}: {err}"))),
+            }
+        }
+    }
+}
+
+impl Drop for DatasetLock {
+    fn drop(&mut self) {
+        match self.file.take() {
+            Some(file) => {
+                let _ = file.set_len(0);
+                let _ = file.unlock();
+            }
+            None => {
+                let _ = fs::remove_file(&self.path);
+            }
+        }
+    }
+}
+
+fn pid_path(path: &Utf8Path) -> Utf8PathBuf {
+    path.with_extension("lock.pid")
+}
+
+fn read_holder(path: &Utf8Path) -> Option<LockHolder> {
+    let bytes = fs::read(path).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+fn write_holder(file: &mut File, path: &Utf8Path) -> Result<(), KiraError> {
+    let bytes = serde_json::to_vec(&LockHolder::current())
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    file.set_len(0)
+        .and_then(|_| file.write_all(&bytes))
+        .and_then(|_| file.flush())
+        .map_err(|err| KiraError::Filesystem(format!("{path}: {err}")))
+}
+
+/// A PID lock is stale when its process is gone or, where that cannot be
+/// checked, once it is older than [`PID_LOCK_STALE_AFTER`].
+fn is_stale(holder: &LockHolder) -> bool {
+    if holder.pid == std::process::id() {
+        return false;
+    }
+    if cfg!(target_os = "linux") {
+        return !Utf8Path::new(&format!("/proc/{}", holder.pid)).exists();
+    }
+    DateTime::parse_from_rfc3339(&holder.acquired_at).is_ok_and(|acquired| {
+        Utc::now()
+            .signed_duration_since(acquired.with_timezone(&Utc))
+            .to_std()
+            .is_ok_and(|age| age > PID_LOCK_STALE_AFTER)
+    })
+}
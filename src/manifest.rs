@@ -0,0 +1,114 @@
+//! `MANIFEST.json` files for multi-file dataset directories.
+//!
+//! Genome, SRR, expression and knowledge-base datasets are directories of
+//! many files. When one is stored, kira-bm writes a manifest at its root
+//! naming the dataset and listing every data file with its size and
+//! SHA-256. The digests come from the download record, which takes them
+//! from the writers that streamed the files to staging, so writing the
+//! manifest reads no data. The manifest travels with the directory between
+//! cache and project, so the files can be checked without the store's
+//! records, and a cache check reads it before falling back to the record.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::VerifyCache;
+use crate::error::KiraError;
+use crate::fs_util::{sha256_file, to_canonical_json};
+use crate::store::{DownloadRecord, FAST_VERIFY_MAX_BYTES};
+
+pub const MANIFEST_FILE: &str = "MANIFEST.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub dataset_type: String,
+    pub id: String,
+    /// The kira-bm that wrote it, e.g. `kira-bm/0.1.0`.
+    pub tool: String,
+    /// Sorted by path.
+    pub files: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Relative to the dataset directory, with `/` separators.
+    pub path: String,
+    pub bytes: u64,
+    /// Absent when the file was listed without a digest; it is then
+    /// checked by size alone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl Manifest {
+    /// The manifest of the files `download` recorded under `dir`. Only
+    /// their sizes are read here.
+    pub fn of(dataset_type: &str, id: &str, dir: &Path, download: &DownloadRecord) -> Self {
+        let files = download
+            .sha256
+            .iter()
+            .map(|(path, digest)| ManifestEntry {
+                path: path.clone(),
+                bytes: fs::metadata(dir.join(path)).map_or(0, |meta| meta.len()),
+                sha256: Some(digest.clone()),
+            })
+            .collect();
+        Self {
+            dataset_type: dataset_type.to_string(),
+            id: id.to_string(),
+            tool: format!("kira-bm/{}", env!("CARGO_PKG_VERSION")),
+            files,
+        }
+    }
+
+    /// The listed files under `dir` that are missing or no longer match.
+    /// `Fast` compares the size of files over [`FAST_VERIFY_MAX_BYTES`]
+    /// instead of hashing them.
+    pub fn check(&self, dir: &Path, mode: VerifyCache) -> Result<Vec<String>, KiraError> {
+        let mut bad = Vec::new();
+        if mode == VerifyCache::Off {
+            return Ok(bad);
+        }
+        for entry in &self.files {
+            let file = dir.join(&entry.path);
+            let matches = match fs::metadata(&file) {
+                Ok(meta) if meta.len() != entry.bytes => false,
+                Ok(_) => match &entry.sha256 {
+                    Some(digest)
+                        if mode == VerifyCache::Full || entry.bytes <= FAST_VERIFY_MAX_BYTES =>
+                    {
+                        sha256_file(&file)? == *digest
+                    }
+                    _ => true,
+                },
+                Err(_) => false,
+            };
+            if !matches {
+                bad.push(entry.path.clone());
+            }
+        }
+        Ok(bad)
+    }
+}
+
+/// Writes the manifest of `download` into the dataset directory `dir`.
+pub fn write(
+    dataset_type: &str,
+    id: &str,
+    dir: &Path,
+    download: &DownloadRecord,
+) -> Result<(), KiraError> {
+    let path = dir.join(MANIFEST_FILE);
+    let manifest = Manifest::of(dataset_type, id, dir, download);
+    fs::write(&path, to_canonical_json(&manifest)?)
+        .map_err(|err| KiraError::Filesystem(format!("{}: {err}", path.display())))
+}
+
+/// The manifest in `dir`, or `None` when there is none or it cannot be
+/// read, as in directories stored before manifests were written.
+pub fn read(dir: &Path) -> Option<Manifest> {
+    let bytes = fs::read(dir.join(MANIFEST_FILE)).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
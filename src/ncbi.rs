@@ -1,13 +1,24 @@
-use std::fs::File;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Write};
 use std::path::Path;
 use std::thread;
 use std::time::Duration;
 
+use clap::ValueEnum;
 use reqwest::blocking::Client;
-use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderValue, RANGE, USER_AGENT};
+use serde::{Deserialize, Serialize};
+use tracing::field::Empty;
 
-use crate::domain::{GenomeAccession, ProteinFormat, ProteinId};
+use crate::app::{ProgressEvent, ProgressLevel, ProgressSink};
+use crate::cancel;
+use crate::domain::{AssemblyName, GenomeAccession, ProteinFormat, ProteinId, TaxonQuery};
 use crate::error::KiraError;
+use crate::http::{
+    FailedResponse, HttpSettings, Mirrors, record_bytes, record_response, trace_retry,
+};
+use crate::progress::Metered;
 
 #[derive(Debug, Clone, Copy)]
 pub struct DownloadInfo {
@@ -21,22 +32,289 @@ pub trait NcbiClient: Send + Sync {
         format: ProteinFormat,
         destination: &Path,
     ) -> Result<DownloadInfo, KiraError>;
+    /// Downloads the assembly's dataset package to `destination`. A partial
+    /// package already there is resumed where the server allows it.
     fn download_genome(
         &self,
         accession: &GenomeAccession,
         include: &[String],
         destination: &Path,
+        sink: &dyn ProgressSink,
     ) -> Result<DownloadInfo, KiraError>;
+
+    /// Writes the assembly's dataset report (JSON) to `destination`, for
+    /// `--metadata-only` fetches.
+    fn download_genome_report(
+        &self,
+        accession: &GenomeAccession,
+        _destination: &Path,
+    ) -> Result<(), KiraError> {
+        Err(KiraError::NcbiHttp(format!(
+            "no dataset report source for genome:{}",
+            accession.as_str()
+        )))
+    }
+
+    /// Assemblies NCBI lists under `name`. Unless `exact`, a name that is
+    /// no assembly's is also tried as a taxon, returning its reference
+    /// assemblies.
+    fn find_assemblies(
+        &self,
+        name: &AssemblyName,
+        _exact: bool,
+    ) -> Result<Vec<AssemblyCandidate>, KiraError> {
+        Err(KiraError::NcbiHttp(format!(
+            "no assembly search for genome:{name}"
+        )))
+    }
+
+    /// Current RefSeq assemblies of a taxon, in NCBI's order, at most
+    /// `filter.limit` of them.
+    fn find_taxon_assemblies(
+        &self,
+        taxon: &TaxonQuery,
+        _filter: &TaxonFilter,
+    ) -> Result<TaxonAssemblies, KiraError> {
+        Err(KiraError::NcbiHttp(format!(
+            "no assembly search for genome:taxon:{taxon}"
+        )))
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum AssemblyLevel {
+    Complete,
+    Chromosome,
+    Scaffold,
+    Contig,
+}
+
+impl AssemblyLevel {
+    /// The value of the datasets API's `filters.assembly_level`.
+    pub fn api_value(self) -> &'static str {
+        match self {
+            AssemblyLevel::Complete => "complete_genome",
+            AssemblyLevel::Chromosome => "chromosome",
+            AssemblyLevel::Scaffold => "scaffold",
+            AssemblyLevel::Contig => "contig",
+        }
+    }
+}
+
+impl std::fmt::Display for AssemblyLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssemblyLevel::Complete => write!(f, "complete"),
+            AssemblyLevel::Chromosome => write!(f, "chromosome"),
+            AssemblyLevel::Scaffold => write!(f, "scaffold"),
+            AssemblyLevel::Contig => write!(f, "contig"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaxonFilter {
+    pub level: Option<AssemblyLevel>,
+    pub reference_only: bool,
+    pub limit: usize,
+}
+
+/// One page of a taxon's assemblies and how many matched in all.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaxonAssemblies {
+    pub total_count: usize,
+    pub assemblies: Vec<AssemblyCandidate>,
+}
+
+/// Reference assemblies known by name, resolved without a registry lookup.
+pub const GENOME_ALIASES: [(&str, &str); 4] = [
+    ("GRCh38", "GCF_000001405.40"),
+    ("GRCm39", "GCF_000001635.27"),
+    ("TAIR10", "GCF_000001735.4"),
+    ("R64", "GCF_000146045.2"),
+];
+
+/// The built-in accession for `name`; case-insensitive unless `exact`.
+pub fn genome_alias(name: &str, exact: bool) -> Option<GenomeAccession> {
+    GENOME_ALIASES
+        .iter()
+        .find(|(alias, _)| {
+            if exact {
+                *alias == name
+            } else {
+                alias.eq_ignore_ascii_case(name)
+            }
+        })
+        .and_then(|(_, accession)| accession.parse().ok())
+}
+
+/// One assembly from a genome dataset report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssemblyCandidate {
+    pub accession: String,
+    pub name: String,
+    pub organism: Option<String>,
+    pub level: Option<String>,
+    pub reference: bool,
+    pub current: bool,
+}
+
+impl std::fmt::Display for AssemblyCandidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.organism {
+            Some(organism) => write!(f, "{} ({}, {organism})", self.accession, self.name),
+            None => write!(f, "{} ({})", self.accession, self.name),
+        }
+    }
 }
 
+#[derive(Deserialize)]
+struct RawAssemblyReports {
+    #[serde(default)]
+    reports: Vec<RawAssemblyReport>,
+    #[serde(default)]
+    total_count: usize,
+}
+
+#[derive(Deserialize)]
+struct RawAssemblyReport {
+    accession: String,
+    #[serde(default)]
+    assembly_info: RawAssemblyInfo,
+    #[serde(default)]
+    organism: RawOrganism,
+}
+
+#[derive(Deserialize, Default)]
+struct RawAssemblyInfo {
+    #[serde(default)]
+    assembly_name: String,
+    #[serde(default)]
+    assembly_level: Option<String>,
+    #[serde(default)]
+    refseq_category: Option<String>,
+    #[serde(default)]
+    assembly_status: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawOrganism {
+    #[serde(default)]
+    organism_name: Option<String>,
+}
+
+pub fn parse_assembly_reports(text: &str) -> Result<Vec<AssemblyCandidate>, KiraError> {
+    parse_taxon_assemblies(text).map(|page| page.assemblies)
+}
+
+/// A dataset report page with its `total_count`; reports without one
+/// count their own entries.
+pub fn parse_taxon_assemblies(text: &str) -> Result<TaxonAssemblies, KiraError> {
+    let raw: RawAssemblyReports = serde_json::from_str(text)
+        .map_err(|err| KiraError::NcbiHttp(format!("invalid dataset report: {err}")))?;
+    let total_count = raw.total_count.max(raw.reports.len());
+    let assemblies = raw
+        .reports
+        .into_iter()
+        .map(|report| AssemblyCandidate {
+            accession: report.accession,
+            name: report.assembly_info.assembly_name,
+            organism: report.organism.organism_name,
+            level: report.assembly_info.assembly_level,
+            reference: report.assembly_info.refseq_category.as_deref() == Some("reference genome"),
+            current: report
+                .assembly_info
+                .assembly_status
+                .as_deref()
+                .is_none_or(|status| status == "current"),
+        })
+        .collect();
+    Ok(TaxonAssemblies {
+        total_count,
+        assemblies,
+    })
+}
+
+/// Picks the assembly `name` refers to. Without `exact`, names compare
+/// case-insensitively and a patch release (`GRCh38.p14`) matches its base
+/// name; when nothing matches by name, the registry's own matches (such as
+/// a taxon's reference assemblies) are used. Current assemblies are
+/// preferred over replaced ones, reference genomes over the rest and
+/// RefSeq over GenBank.
+pub fn select_assembly(
+    name: &AssemblyName,
+    exact: bool,
+    candidates: Vec<AssemblyCandidate>,
+) -> Result<AssemblyCandidate, KiraError> {
+    let wanted = name.as_str();
+    let matches_name = |candidate: &AssemblyCandidate| {
+        if exact {
+            return candidate.name == wanted;
+        }
+        let base = candidate
+            .name
+            .rsplit_once(".p")
+            .filter(|(_, patch)| !patch.is_empty() && patch.chars().all(|ch| ch.is_ascii_digit()))
+            .map_or(candidate.name.as_str(), |(base, _)| base);
+        candidate.name.eq_ignore_ascii_case(wanted) || base.eq_ignore_ascii_case(wanted)
+    };
+    let named = candidates
+        .iter()
+        .filter(|candidate| matches_name(candidate))
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut pool = if named.is_empty() && !exact {
+        candidates
+    } else {
+        named
+    };
+    pool.sort_by(|a, b| a.accession.cmp(&b.accession));
+    pool.dedup_by(|a, b| a.accession == b.accession);
+    narrow(&mut pool, |candidate| candidate.current);
+    narrow(&mut pool, |candidate| candidate.reference);
+    narrow(&mut pool, |candidate| {
+        candidate.accession.starts_with("GCF_")
+    });
+    match pool.len() {
+        0 => Err(KiraError::UnknownGenomeName(wanted.to_string())),
+        1 => Ok(pool.remove(0)),
+        _ => Err(KiraError::AmbiguousGenomeName {
+            name: wanted.to_string(),
+            candidates: pool.iter().map(ToString::to_string).collect(),
+        }),
+    }
+}
+
+/// Keeps the candidates passing `keep`, unless none do.
+fn narrow(pool: &mut Vec<AssemblyCandidate>, keep: impl Fn(&AssemblyCandidate) -> bool) {
+    if pool.iter().any(&keep) {
+        pool.retain(keep);
+    }
+}
+
+const DATASETS_API: &str = "https://api.ncbi.nlm.nih.gov/datasets/v2";
+
+/// Attempts at a genome package before a dropped connection is reported;
+/// each waits twice as long as the one before.
+const PACKAGE_ATTEMPTS: u32 = 5;
+const PACKAGE_RETRY_DELAY: Duration = Duration::from_millis(500);
+
 #[derive(Clone)]
 pub struct NcbiHttpClient {
     client: Client,
     base_url: String,
+    settings: HttpSettings,
 }
 
 impl NcbiHttpClient {
     pub fn new() -> Result<Self, KiraError> {
+        Self::with_settings(HttpSettings::from_env())
+    }
+
+    pub fn with_settings(settings: HttpSettings) -> Result<Self, KiraError> {
         let mut headers = HeaderMap::new();
         headers.insert(
             USER_AGENT,
@@ -60,39 +338,65 @@ impl NcbiHttpClient {
                 .map_err(|err| KiraError::Filesystem(err.to_string()))?,
         );
 
-        if let Ok(api_key) = std::env::var("NCBI_API_KEY") {
-            if !api_key.trim().is_empty() {
-                headers.insert(
-                    "api-key",
-                    HeaderValue::from_str(api_key.trim())
-                        .map_err(|err| KiraError::Filesystem(err.to_string()))?,
-                );
-            }
+        if let Some(api_key) = settings.ncbi_api_key() {
+            headers.insert(
+                "api-key",
+                HeaderValue::from_str(api_key)
+                    .map_err(|err| KiraError::Filesystem(err.to_string()))?,
+            );
         }
 
-        let client = Client::builder()
+        let client = settings
+            .client_builder("ncbi")
             .default_headers(headers)
-            .timeout(Duration::from_secs(60))
             .build()
             .map_err(|err| KiraError::NcbiHttp(err.to_string()))?;
 
         Ok(Self {
             client,
-            base_url: "https://api.ncbi.nlm.nih.gov/datasets/v2".to_string(),
+            base_url: settings.mirrors()?.rewrite(DATASETS_API),
+            settings,
+        })
+    }
+
+    /// Sends every request to `base_url` instead of api.ncbi.nlm.nih.gov.
+    pub fn with_base_url(settings: HttpSettings, base_url: &str) -> Result<Self, KiraError> {
+        Ok(Self {
+            base_url: Mirrors::everywhere(base_url).rewrite(DATASETS_API),
+            ..Self::with_settings(settings)?
         })
     }
 
+    pub fn genome_url(
+        accession: &GenomeAccession,
+        include: &[String],
+    ) -> Result<String, KiraError> {
+        let url = format!(
+            "{DATASETS_API}/genome/accession/{}/download",
+            accession.as_str()
+        );
+        Ok(append_query_multi(
+            &url,
+            "include_annotation_type",
+            &map_genome_include(include)?,
+        ))
+    }
+
     fn write_response_to_file(
         &self,
         mut response: reqwest::blocking::Response,
+        dataset: String,
         destination: &Path,
+        sink: Option<&dyn ProgressSink>,
     ) -> Result<DownloadInfo, KiraError> {
         if !response.status().is_success() {
-            let status = response.status().as_u16();
-            let message = response
-                .text()
-                .unwrap_or_else(|_| "NCBI request failed".to_string());
-            return Err(KiraError::NcbiStatus { status, message });
+            let failed = FailedResponse::read(response);
+            return Err(KiraError::NcbiStatus {
+                status: failed.status,
+                url: failed.url,
+                dataset,
+                message: failed.message,
+            });
         }
         let is_zip = response
             .headers()
@@ -101,13 +405,123 @@ impl NcbiHttpClient {
             .map(|value| value.contains("zip"))
             .unwrap_or(false);
 
-        let mut file =
-            File::create(destination).map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        std::io::copy(&mut response, &mut file)
+        match sink {
+            Some(sink) => {
+                let total = response.content_length();
+                let mut body = Metered::new(response, sink, destination, total);
+                record_bytes(cancel::copy_to_file(&mut body, destination)?.0);
+            }
+            None => {
+                record_bytes(cancel::copy_to_file(&mut response, destination)?.0);
+            }
+        }
+        Ok(DownloadInfo { is_zip })
+    }
+
+    /// One attempt at a genome package, continuing after the bytes already
+    /// at `destination` when the server answers the range request.
+    fn download_package(
+        &self,
+        url: &str,
+        accession: &GenomeAccession,
+        include: &[String],
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<DownloadInfo, KiraError> {
+        let offset = fs::metadata(destination).map_or(0, |meta| meta.len());
+        let response = self.send_with_retries(|| {
+            let request = self.client.get(url);
+            if offset > 0 {
+                request.header(RANGE, format!("bytes={offset}-"))
+            } else {
+                request
+            }
+        })?;
+        let status = response.status().as_u16();
+        if status == 416 && offset > 0 {
+            // The earlier attempt got it all.
+            return Ok(DownloadInfo { is_zip: true });
+        }
+        if matches!(status, 400 | 404) {
+            let failed = FailedResponse::read(response);
+            return Err(KiraError::NcbiGenomeRejected {
+                accession: accession.as_str().to_string(),
+                include: include.to_vec(),
+                status: failed.status,
+                url: failed.url,
+                message: failed.message,
+            });
+        }
+        if !response.status().is_success() {
+            let failed = FailedResponse::read(response);
+            return Err(KiraError::NcbiStatus {
+                status: failed.status,
+                url: failed.url,
+                dataset: format!("genome:{}", accession.as_str()),
+                message: failed.message,
+            });
+        }
+        let is_zip = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.contains("zip"));
+        let resumed = status == 206;
+        let expected = response.content_length();
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(destination)
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let mut body = Metered::new(response, sink, destination, expected);
+        let written = append_body(&mut body, &mut file)?;
+        record_bytes(written);
+        if let Some(expected) = expected
+            && written < expected
+        {
+            return Err(KiraError::NcbiHttp(format!(
+                "connection closed after {written} of {expected} bytes"
+            )));
+        }
         Ok(DownloadInfo { is_zip })
     }
 
+    /// Reports listed at `url`; a 404 (no such name or taxon) lists none.
+    fn fetch_assembly_reports(
+        &self,
+        url: &str,
+        dataset: &str,
+    ) -> Result<Vec<AssemblyCandidate>, KiraError> {
+        match self.fetch_report_text(url, dataset)? {
+            Some(text) => parse_assembly_reports(&text),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// The body of a dataset report, or `None` when NCBI answers 404.
+    fn fetch_report_text(&self, url: &str, dataset: &str) -> Result<Option<String>, KiraError> {
+        let timeout = self.settings.timeouts_for("ncbi").request;
+        let response = self.send_with_retries(|| self.client.get(url).timeout(timeout))?;
+        if response.status().as_u16() == 404 {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            let failed = FailedResponse::read(response);
+            return Err(KiraError::NcbiStatus {
+                status: failed.status,
+                url: failed.url,
+                dataset: dataset.to_string(),
+                message: failed.message,
+            });
+        }
+        response
+            .text()
+            .map(Some)
+            .map_err(|err| KiraError::NcbiHttp(err.to_string()))
+    }
+
     fn send_with_retries<F>(
         &self,
         mut make_req: F,
@@ -117,15 +531,19 @@ impl NcbiHttpClient {
     {
         const MAX_RETRIES: usize = 3;
         const BASE_DELAY_MS: u64 = 200;
+        let limiter = self.settings.limiter("ncbi");
         let mut attempt = 0usize;
         loop {
+            limiter.acquire();
             let response = make_req().send();
             match response {
                 Ok(resp) => {
+                    record_response(&resp);
                     let status = resp.status().as_u16();
                     if attempt < MAX_RETRIES && is_retryable_status(status) {
-                        let delay = BASE_DELAY_MS * (attempt as u64 + 1);
-                        thread::sleep(Duration::from_millis(delay));
+                        let delay = Duration::from_millis(BASE_DELAY_MS * (attempt as u64 + 1));
+                        trace_retry("ncbi", attempt + 1, delay, &status);
+                        thread::sleep(delay);
                         attempt += 1;
                         continue;
                     }
@@ -133,8 +551,9 @@ impl NcbiHttpClient {
                 }
                 Err(err) => {
                     if attempt < MAX_RETRIES && is_retryable_error(&err) {
-                        let delay = BASE_DELAY_MS * (attempt as u64 + 1);
-                        thread::sleep(Duration::from_millis(delay));
+                        let delay = Duration::from_millis(BASE_DELAY_MS * (attempt as u64 + 1));
+                        trace_retry("ncbi", attempt + 1, delay, &err);
+                        thread::sleep(delay);
                         attempt += 1;
                         continue;
                     }
@@ -157,21 +576,136 @@ impl NcbiClient for NcbiHttpClient {
         ))
     }
 
+    #[tracing::instrument(
+        name = "ncbi.download_genome",
+        skip_all,
+        fields(registry = "ncbi", dataset = %accession.as_str(), endpoint = Empty, status = Empty, bytes = Empty)
+    )]
     fn download_genome(
         &self,
         accession: &GenomeAccession,
         include: &[String],
         destination: &Path,
+        sink: &dyn ProgressSink,
     ) -> Result<DownloadInfo, KiraError> {
-        let include_params = map_genome_include(include)?;
+        let url = Self::genome_url(accession, include)?.replacen(DATASETS_API, &self.base_url, 1);
+        let mut attempt = 0;
+        loop {
+            match self.download_package(&url, accession, include, destination, sink) {
+                Ok(info) => return Ok(info),
+                Err(err) if attempt + 1 < PACKAGE_ATTEMPTS && is_retryable(&err) => {
+                    let delay = PACKAGE_RETRY_DELAY * 2u32.pow(attempt);
+                    trace_retry("ncbi", attempt as usize + 1, delay, &err);
+                    sink.event(ProgressEvent {
+                        message: format!(
+                            "ncbi.retry genome:{} attempt={} delay_ms={}: {err}",
+                            accession.as_str(),
+                            attempt + 2,
+                            delay.as_millis()
+                        ),
+                        elapsed: None,
+                        level: ProgressLevel::Detail,
+                    });
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    #[tracing::instrument(
+        name = "ncbi.download_genome_report",
+        skip_all,
+        fields(registry = "ncbi", dataset = %accession.as_str(), endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    fn download_genome_report(
+        &self,
+        accession: &GenomeAccession,
+        destination: &Path,
+    ) -> Result<(), KiraError> {
         let url = format!(
-            "{}/genome/accession/{}/download",
+            "{}/genome/accession/{}/dataset_report",
             self.base_url,
             accession.as_str()
         );
-        let url = append_query_multi(&url, "include_annotation_type", &include_params);
-        let response = self.send_with_retries(|| self.client.get(&url))?;
-        self.write_response_to_file(response, destination)
+        let timeout = self.settings.timeouts_for("ncbi").request;
+        let response = self.send_with_retries(|| self.client.get(&url).timeout(timeout))?;
+        self.write_response_to_file(
+            response,
+            format!("genome:{}", accession.as_str()),
+            destination,
+            None,
+        )
+        .map(|_| ())
+    }
+
+    #[tracing::instrument(
+        name = "ncbi.find_assemblies",
+        skip_all,
+        fields(registry = "ncbi", dataset = %name, endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    fn find_assemblies(
+        &self,
+        name: &AssemblyName,
+        exact: bool,
+    ) -> Result<Vec<AssemblyCandidate>, KiraError> {
+        let dataset = format!("genome:{name}");
+        let by_name = format!(
+            "{}/genome/assembly_name/{}/dataset_report",
+            self.base_url,
+            encode_component(name.as_str())
+        );
+        let found = self.fetch_assembly_reports(&by_name, &dataset)?;
+        if !found.is_empty() || exact {
+            return Ok(found);
+        }
+        let by_taxon = format!(
+            "{}/genome/taxon/{}/dataset_report?filters.reference_only=true",
+            self.base_url,
+            encode_component(name.as_str())
+        );
+        self.fetch_assembly_reports(&by_taxon, &dataset)
+    }
+
+    #[tracing::instrument(
+        name = "ncbi.find_taxon_assemblies",
+        skip_all,
+        fields(registry = "ncbi", dataset = %taxon, endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    fn find_taxon_assemblies(
+        &self,
+        taxon: &TaxonQuery,
+        filter: &TaxonFilter,
+    ) -> Result<TaxonAssemblies, KiraError> {
+        let mut params = vec![
+            ("filters.assembly_source", "refseq".to_string()),
+            ("filters.assembly_version", "current".to_string()),
+            ("page_size", filter.limit.to_string()),
+        ];
+        if let Some(level) = filter.level {
+            params.push(("filters.assembly_level", level.api_value().to_string()));
+        }
+        if filter.reference_only {
+            params.push(("filters.reference_only", "true".to_string()));
+        }
+        let url = reqwest::Url::parse_with_params(
+            &format!(
+                "{}/genome/taxon/{}/dataset_report",
+                self.base_url,
+                encode_component(taxon.as_str())
+            ),
+            &params,
+        )
+        .map_err(|err| KiraError::NcbiHttp(err.to_string()))?;
+        let dataset = format!("genome:taxon:{taxon}");
+        let text = self.fetch_report_text(url.as_str(), &dataset)?;
+        let Some(text) = text else {
+            return Ok(TaxonAssemblies::default());
+        };
+        let mut page = parse_taxon_assemblies(&text)?;
+        page.assemblies.truncate(filter.limit);
+        Ok(page)
     }
 }
 
@@ -200,6 +734,149 @@ pub fn map_genome_include(include: &[String]) -> Result<Vec<String>, KiraError>
     Ok(mapped)
 }
 
+/// Path of the catalog inside an extracted genome package.
+pub const DATASET_CATALOG_PATH: &str = "ncbi_dataset/data/dataset_catalog.json";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatasetCatalog {
+    pub api_version: Option<String>,
+    pub files: Vec<CatalogFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogFile {
+    pub accession: Option<String>,
+    pub path: String,
+    pub file_type: String,
+}
+
+// Current catalogs use camelCase keys (`apiVersion`, `filePath`, `fileType`);
+// older packages were written with snake_case keys.
+#[derive(Deserialize)]
+struct RawCatalog {
+    #[serde(rename = "apiVersion", alias = "api_version", default)]
+    api_version: Option<String>,
+    #[serde(default)]
+    assemblies: Vec<RawCatalogAssembly>,
+}
+
+#[derive(Deserialize)]
+struct RawCatalogAssembly {
+    #[serde(default)]
+    accession: Option<String>,
+    #[serde(default)]
+    files: Vec<RawCatalogFile>,
+}
+
+#[derive(Deserialize)]
+struct RawCatalogFile {
+    #[serde(rename = "filePath", alias = "file_path")]
+    file_path: String,
+    #[serde(rename = "fileType", alias = "file_type")]
+    file_type: String,
+}
+
+pub fn parse_dataset_catalog(text: &str) -> Result<DatasetCatalog, KiraError> {
+    let raw: RawCatalog =
+        serde_json::from_str(text).map_err(|err| KiraError::InvalidCatalog(err.to_string()))?;
+    let files = raw
+        .assemblies
+        .into_iter()
+        .flat_map(|assembly| {
+            let accession = assembly.accession;
+            assembly.files.into_iter().map(move |file| CatalogFile {
+                accession: accession.clone(),
+                path: file.file_path,
+                file_type: file.file_type,
+            })
+        })
+        .collect();
+    Ok(DatasetCatalog {
+        api_version: raw.api_version,
+        files,
+    })
+}
+
+impl DatasetCatalog {
+    /// Files grouped by artifact, using include names where the type has one.
+    pub fn inventory(&self) -> BTreeMap<String, Vec<String>> {
+        let mut inventory = BTreeMap::<String, Vec<String>>::new();
+        for file in &self.files {
+            let artifact = artifact_for_file_type(&file.file_type)
+                .map(str::to_string)
+                .unwrap_or_else(|| file.file_type.to_lowercase().replace('_', "-"));
+            inventory
+                .entry(artifact)
+                .or_default()
+                .push(file.path.clone());
+        }
+        inventory
+    }
+
+    /// Requested include values with no matching file in the catalog.
+    pub fn missing_artifacts(&self, include: &[String]) -> Vec<String> {
+        include
+            .iter()
+            .filter(|item| item.as_str() != "default")
+            .filter(|item| {
+                !self
+                    .files
+                    .iter()
+                    .any(|file| artifact_for_file_type(&file.file_type) == Some(item.as_str()))
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+pub fn artifact_for_file_type(file_type: &str) -> Option<&'static str> {
+    match file_type {
+        "GENOMIC_NUCLEOTIDE_FASTA" => Some("genome"),
+        "GFF3" => Some("gff3"),
+        "GENBANK_FLATFILE" => Some("gbff"),
+        "GTF" => Some("gtf"),
+        "RNA_NUCLEOTIDE_FASTA" => Some("rna"),
+        "PROTEIN_FASTA" => Some("protein"),
+        "CDS_NUCLEOTIDE_FASTA" => Some("cds"),
+        "SEQUENCE_REPORT" => Some("seq-report"),
+        _ => None,
+    }
+}
+
+/// Whether a failed package download is worth another attempt: dropped
+/// connections, timeouts and server errors are; refusals are not.
+pub fn is_retryable(err: &KiraError) -> bool {
+    match err {
+        KiraError::NcbiHttp(_) => true,
+        KiraError::NcbiStatus { status, .. } => is_retryable_status(*status),
+        _ => false,
+    }
+}
+
+/// Copies a response body to `file`. What arrived before the connection
+/// dropped stays there for the next attempt to resume from; a read error is
+/// the network's and a write error the disk's.
+fn append_body<R: Read>(reader: &mut R, file: &mut fs::File) -> Result<u64, KiraError> {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut written = 0u64;
+    loop {
+        cancel::check_current()?;
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(written),
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => {
+                return Err(KiraError::NcbiHttp(format!(
+                    "download interrupted after {written} bytes: {err}"
+                )));
+            }
+        };
+        file.write_all(&buffer[..read])
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        written += read as u64;
+    }
+}
+
 fn is_retryable_status(status: u16) -> bool {
     matches!(status, 429 | 500 | 502 | 503 | 504)
 }
@@ -1,41 +1,192 @@
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
 
 use serde::Serialize;
+use serde_json::Value;
 
-use crate::app::{ClearResult, FetchResult, InfoResult, InitResult, ListResult};
+use crate::app::{
+    ClearResult, ConvertResult, DoiDatasets, DoiTargets, FetchItemResult, FetchResult,
+    FetchSummary, FindResult, GcEntry, GcResult, ImportResult, InfoMatches, InfoResult, InitCounts,
+    InitResult, ListResult, PinMatches, PinResult, ProgressEvent, ProgressLevel,
+    StructureSelection,
+};
+use crate::audit::AuditRecord;
+use crate::config::ConfigSettings;
+use crate::control::ControlReport;
+use crate::doctor::{CheckStatus, DoctorReport};
+use crate::error::ErrorReport;
+use crate::fs_util::bytes_to_human;
+use crate::index::{CacheIndex, CacheUsage, ReindexReport};
+use crate::ping::{PingReport, PingStatus};
+use crate::progress::DownloadProgress;
+use crate::retention::RetentionReport;
+use crate::runs::{RunManifest, RunSummary};
+use crate::schema::{ConfigValidation, OutputDocument, Versioned};
+use crate::srr::ToolsReport;
+use crate::stats::{CacheSavings, CacheStats, millis_to_human};
+use crate::store::{DedupReport, Materialization};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OutputMode {
     Interactive,
     NonInteractive,
+    /// ASCII text without color, emoji or TUI, for logs and CI.
+    Plain,
+}
+
+impl OutputMode {
+    /// `--non-interactive` wins over `--plain`; without either flag the TUI is
+    /// only used on a terminal that has not asked for `NO_COLOR`.
+    pub fn select(non_interactive: bool, plain: bool, terminal: bool, no_color: bool) -> Self {
+        if non_interactive {
+            OutputMode::NonInteractive
+        } else if plain || !terminal || no_color {
+            OutputMode::Plain
+        } else {
+            OutputMode::Interactive
+        }
+    }
+
+    pub fn detect(non_interactive: bool, plain: bool) -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        Self::select(non_interactive, plain, io::stdout().is_terminal(), no_color)
+    }
 }
 
 pub struct JsonOutput;
 
 impl JsonOutput {
     pub fn print_list(result: &ListResult) -> io::Result<()> {
-        Self::print_json(result)
+        Self::print_document(result)
+    }
+
+    pub fn print_find(result: &FindResult) -> io::Result<()> {
+        Self::print_document(result)
     }
 
     pub fn print_info(result: &InfoResult) -> io::Result<()> {
-        Self::print_json(result)
+        Self::print_document(result)
+    }
+
+    pub fn print_info_matches(result: &InfoMatches) -> io::Result<()> {
+        Self::print_document(result)
     }
 
     pub fn print_fetch(result: &FetchResult) -> io::Result<()> {
-        Self::print_json(result)
+        Self::print_document(result)
     }
 
     pub fn print_clear(result: &ClearResult) -> io::Result<()> {
-        Self::print_json(result)
+        Self::print_document(result)
     }
 
     pub fn print_init(result: &InitResult) -> io::Result<()> {
-        Self::print_json(result)
+        Self::print_document(result)
+    }
+
+    pub fn print_gc(result: &GcResult) -> io::Result<()> {
+        Self::print_document(result)
+    }
+
+    pub fn print_pin(result: &PinResult) -> io::Result<()> {
+        Self::print_document(result)
+    }
+
+    pub fn print_pin_matches(result: &PinMatches) -> io::Result<()> {
+        Self::print_document(result)
+    }
+
+    pub fn print_convert(result: &ConvertResult) -> io::Result<()> {
+        Self::print_document(result)
+    }
+
+    pub fn print_import(result: &ImportResult) -> io::Result<()> {
+        Self::print_document(result)
+    }
+
+    pub fn print_cache_stats(stats: &CacheStats) -> io::Result<()> {
+        Self::print_document(stats)
+    }
+
+    pub fn print_cache_dedup(report: &DedupReport) -> io::Result<()> {
+        Self::print_document(report)
+    }
+
+    pub fn print_cache_index(index: &CacheIndex) -> io::Result<()> {
+        Self::print_document(index)
+    }
+
+    pub fn print_cache_usage(usage: &CacheUsage) -> io::Result<()> {
+        Self::print_document(usage)
+    }
+
+    pub fn print_reindex(report: &ReindexReport) -> io::Result<()> {
+        Self::print_document(report)
+    }
+
+    pub fn print_control(report: &ControlReport) -> io::Result<()> {
+        Self::print_document(report)
+    }
+
+    pub fn print_audit(records: &[AuditRecord]) -> io::Result<()> {
+        Self::print_json(&records)
+    }
+
+    pub fn print_runs(runs: &[RunSummary]) -> io::Result<()> {
+        Self::print_json(&runs)
+    }
+
+    pub fn print_run(manifest: &RunManifest) -> io::Result<()> {
+        Self::print_document(manifest)
+    }
+
+    pub fn print_retention(report: &RetentionReport) -> io::Result<()> {
+        Self::print_document(report)
+    }
+
+    pub fn print_doctor(report: &DoctorReport) -> io::Result<()> {
+        Self::print_document(report)
+    }
+
+    pub fn print_ping(report: &PingReport) -> io::Result<()> {
+        Self::print_document(report)
+    }
+
+    pub fn print_tools(report: &ToolsReport) -> io::Result<()> {
+        Self::print_document(report)
+    }
+
+    pub fn print_doi_datasets(result: &DoiDatasets) -> io::Result<()> {
+        Self::print_document(result)
+    }
+
+    pub fn print_config_valid(path: &Path) -> io::Result<()> {
+        Self::print_document(&ConfigValidation {
+            path: path.display().to_string(),
+            valid: true,
+        })
+    }
+
+    pub fn print_config_settings(settings: &ConfigSettings) -> io::Result<()> {
+        Self::print_document(settings)
+    }
+
+    /// Errors go to stderr so stdout stays a single result document.
+    pub fn print_error(report: &ErrorReport) -> io::Result<()> {
+        let json =
+            serde_json::to_string_pretty(&Versioned::new(report)).map_err(io::Error::other)?;
+        let mut stderr = io::stderr();
+        stderr.write_all(json.as_bytes())?;
+        stderr.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn print_document<T: OutputDocument>(result: &T) -> io::Result<()> {
+        Self::print_json(&Versioned::new(result))
     }
 
     fn print_json<T: Serialize>(value: &T) -> io::Result<()> {
-        let json = serde_json::to_string_pretty(value)
-            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        let json = serde_json::to_string_pretty(value).map_err(io::Error::other)?;
         let mut stdout = io::stdout();
         stdout.write_all(json.as_bytes())?;
         stdout.write_all(b"\n")?;
@@ -44,5 +195,1357 @@ impl JsonOutput {
 }
 
 impl crate::app::ProgressSink for JsonOutput {
-    fn event(&self, _event: crate::app::ProgressEvent) {}
+    fn event(&self, event: ProgressEvent) {
+        match DownloadProgress::from_event(&event) {
+            Some(progress) => eprintln!("{}", progress_json(&progress)),
+            None => report_event(&event),
+        }
+    }
+}
+
+impl crate::app::ProgressSink for PlainOutput {
+    fn event(&self, event: ProgressEvent) {
+        report_event(&event);
+    }
+}
+
+/// Warnings and batch state changes that headless runs surface on stderr.
+fn report_event(event: &ProgressEvent) {
+    if let Some(line) = notice(&event.message) {
+        eprintln!("{line}");
+    }
+}
+
+fn notice(message: &str) -> Option<String> {
+    if let Some(rest) = message.strip_prefix("genome.missing_artifact ")
+        && let Some((accession, artifact)) = rest.split_once(' ')
+    {
+        return Some(format!(
+            "warning: genome {accession} has no {artifact} files in its dataset catalog"
+        ));
+    }
+    if let Some(rest) = message.strip_prefix("srr.read_count_mismatch ")
+        && let [id, counted, expected] = rest.split(' ').collect::<Vec<_>>()[..]
+    {
+        return Some(format!(
+            "warning: srr {id} FASTQ has {counted} reads; ENA reports {expected} spots"
+        ));
+    }
+    if let Some(rest) = message.strip_prefix("srr.run_info_unavailable ")
+        && let Some((id, error)) = rest.split_once(' ')
+    {
+        return Some(format!(
+            "warning: srr {id} stored without run info: {error}"
+        ));
+    }
+    if let Some(rest) = message.strip_prefix("srx.runs ")
+        && let Some((experiment, runs)) = rest.split_once(' ')
+    {
+        let runs = runs.split(',').collect::<Vec<_>>();
+        return Some(format!(
+            "experiment {experiment} has {} run{}: {}",
+            runs.len(),
+            if runs.len() == 1 { "" } else { "s" },
+            runs.join(", ")
+        ));
+    }
+    if let Some(rest) = message.strip_prefix("cache.fallback ")
+        && let Some((root, reason)) = rest.split_once("; ")
+    {
+        return Some(format!(
+            "warning: user cache unavailable ({reason}); caching in {root}"
+        ));
+    }
+    if let Some(dir) = message.strip_prefix("readme.kept ") {
+        return Some(format!(
+            "warning: {dir} has a README.md of its own; not replaced"
+        ));
+    }
+    if let Some(root) = message.strip_prefix("project.root ") {
+        return Some(format!("using project at {root}"));
+    }
+    match message {
+        "batch.paused" => Some("batch paused; run `kira-bm batch resume` to continue".to_string()),
+        "batch.resumed" => Some("batch resumed".to_string()),
+        _ => None,
+    }
+}
+
+/// Fetch progress for headless runs: one stderr line per event that reaches
+/// it, so it goes behind a [`crate::app::ProgressFilter`] choosing which.
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressLines {
+    json: bool,
+}
+
+impl ProgressLines {
+    pub fn plain() -> Self {
+        Self { json: false }
+    }
+
+    /// Every event as one NDJSON object, as `JsonOutput` reports it.
+    pub fn json() -> Self {
+        Self { json: true }
+    }
+
+    pub fn line(&self, event: &ProgressEvent) -> String {
+        if let Some(progress) = DownloadProgress::from_event(event) {
+            return if self.json {
+                progress_json(&progress).to_string()
+            } else {
+                match progress.total {
+                    Some(total) => format!(
+                        "{}: {} of {total} bytes",
+                        progress.file, progress.downloaded
+                    ),
+                    None => format!("{}: {} bytes", progress.file, progress.downloaded),
+                }
+            };
+        }
+        if self.json {
+            event_json(event).to_string()
+        } else {
+            Self::text(&event.message)
+        }
+    }
+
+    fn text(message: &str) -> String {
+        if let Some(line) = notice(message) {
+            return line;
+        }
+        if let Some(rest) = message.strip_prefix("phase=") {
+            return match rest.split_once(';') {
+                Some((phase, detail)) => format!("[{phase}] {}", detail.trim()),
+                None => format!("[{rest}]"),
+            };
+        }
+        if let Some(label) = message.strip_prefix("item.begin ") {
+            return format!("fetching {label}");
+        }
+        if let Some(label) = message.strip_prefix("item.end ") {
+            return format!("fetched {label}");
+        }
+        if let Some(rest) = message.strip_prefix("item.failed ") {
+            return format!("failed {rest}");
+        }
+        message.to_string()
+    }
+}
+
+impl crate::app::ProgressSink for ProgressLines {
+    fn event(&self, event: ProgressEvent) {
+        eprintln!("{}", self.line(&event));
+    }
+
+    fn item_completed(&self, item: &FetchItemResult) {
+        if let Some(line) = self.item_line(item) {
+            eprintln!("{line}");
+        }
+    }
+}
+
+impl ProgressLines {
+    /// A fetched dataset as one NDJSON line: the item as in the final
+    /// result, tagged `"event": "item"`. Plain text has said `fetched
+    /// <label>` already.
+    pub fn item_line(&self, item: &FetchItemResult) -> Option<String> {
+        if !self.json {
+            return None;
+        }
+        let mut value = serde_json::json!({ "event": "item" });
+        if let (Some(line), Ok(serde_json::Value::Object(fields))) =
+            (value.as_object_mut(), serde_json::to_value(item))
+        {
+            line.extend(fields);
+        }
+        Some(value.to_string())
+    }
+}
+
+/// Any other event as one NDJSON line: its kind under `"event"`, the fields
+/// the plain line is made of, and its level.
+fn event_json(event: &ProgressEvent) -> serde_json::Value {
+    let message = event.message.as_str();
+    let mut value = if let Some(rest) = message.strip_prefix("phase=") {
+        let (phase, detail) = rest.split_once(';').unwrap_or((rest, ""));
+        serde_json::json!({ "event": "phase", "phase": phase, "detail": detail.trim() })
+    } else if let Some(label) = message.strip_prefix("item.begin ") {
+        serde_json::json!({ "event": "item.begin", "item": label })
+    } else if let Some(label) = message.strip_prefix("item.end ") {
+        serde_json::json!({ "event": "item.end", "item": label })
+    } else if let Some(rest) = message.strip_prefix("item.failed ") {
+        serde_json::json!({ "event": "item.failed", "message": rest })
+    } else {
+        let text = ProgressLines::text(message);
+        match text.strip_prefix("warning: ") {
+            Some(warning) => serde_json::json!({ "event": "warning", "message": warning }),
+            None => serde_json::json!({ "event": "message", "message": text }),
+        }
+    };
+    value["level"] = match event.level {
+        ProgressLevel::Phase => "phase",
+        ProgressLevel::Request => "request",
+        ProgressLevel::Detail => "detail",
+    }
+    .into();
+    if let Some(elapsed) = event.elapsed {
+        value["elapsed_ms"] = (elapsed.as_millis() as u64).into();
+    }
+    value
+}
+
+/// A download's progress as one NDJSON line on stderr, beside the single
+/// result document on stdout.
+fn progress_json(progress: &DownloadProgress) -> serde_json::Value {
+    serde_json::json!({
+        "event": "download.progress",
+        "file": progress.file,
+        "downloaded": progress.downloaded,
+        "total": progress.total,
+        "elapsed_ms": progress.elapsed.as_millis() as u64,
+        "bytes_per_sec": progress.bytes_per_sec(),
+        "eta_ms": progress.eta().map(|eta| eta.as_millis() as u64),
+    })
+}
+
+const DOI_DOWNLOAD_HINT: &str = "nothing selected for download; pass --download-all, --only, --limit-per-type, --exclude or --select";
+
+/// Every target of a `doi:` fetch with its known size and, for those not
+/// downloaded, why.
+fn doi_targets_text(targets: &DoiTargets) -> String {
+    let mut out = format!(
+        "Selected {} of {} targets\n",
+        targets.selected.len(),
+        targets.selected.len() + targets.skipped.len()
+    );
+    let mut all = targets
+        .selected
+        .iter()
+        .chain(&targets.skipped)
+        .collect::<Vec<_>>();
+    all.sort_by(|a, b| a.specifier.cmp(&b.specifier));
+    let rows = all
+        .iter()
+        .map(|target| {
+            vec![
+                target.specifier.clone(),
+                target
+                    .bytes
+                    .map(bytes_to_human)
+                    .unwrap_or_else(|| "-".to_string()),
+                or_dash(target.reason.as_deref()),
+            ]
+        })
+        .collect::<Vec<_>>();
+    out.push_str(&table(&["TARGET", "SIZE", "SKIPPED"], &rows));
+    if targets.selected.is_empty() && !targets.skipped.is_empty() {
+        out.push_str(DOI_DOWNLOAD_HINT);
+        out.push('\n');
+    }
+    out
+}
+
+/// Identifiers a summary's source matched, before any limit.
+fn matched_count(summary: &FetchSummary) -> usize {
+    summary.id_counts.iter().map(|count| count.count).sum()
+}
+
+pub struct PlainOutput;
+
+impl PlainOutput {
+    pub fn render_fetch(result: &FetchResult) -> String {
+        let mut out = format!("Fetched {} datasets\n", result.items.len());
+        if let Some(summary) = &result.summary {
+            if let (Some(accession), Some(counts)) = (&summary.uniprot, &summary.structures) {
+                out.push_str(&format!(
+                    "{} {accession}: {} listed, {} downloaded, {} cached, {} skipped\n",
+                    summary.kind, counts.listed, counts.downloaded, counts.cached, counts.skipped
+                ));
+            } else if let Some(taxon) = &summary.taxon {
+                out.push_str(&format!(
+                    "{} {taxon}: {} matched, {} fetched\n",
+                    summary.kind,
+                    matched_count(summary),
+                    summary.resolved_targets
+                ));
+            } else {
+                let source = summary
+                    .doi
+                    .clone()
+                    .or(summary.bioproject.clone())
+                    .or(summary.experiment.clone())
+                    .or(summary.selection.as_ref().map(selection_label))
+                    .unwrap_or_else(|| "-".to_string());
+                out.push_str(&format!(
+                    "{} {source}: {} resolved, {} unresolved\n",
+                    summary.kind, summary.resolved_targets, summary.unresolved
+                ));
+            }
+            if let Some(targets) = &summary.doi_selection {
+                out.push_str(&doi_targets_text(targets));
+            }
+        }
+        let rows = result
+            .items
+            .iter()
+            .map(|item| {
+                vec![
+                    item.dataset_type.clone(),
+                    item.id.clone(),
+                    item.action.clone(),
+                    or_dash(item.project_path.as_deref()),
+                    or_dash(item.cache_path.as_deref()),
+                ]
+            })
+            .collect::<Vec<_>>();
+        out.push_str(&table(&["TYPE", "ID", "ACTION", "PROJECT", "CACHE"], &rows));
+        if !result.errors.is_empty() {
+            out.push_str(&format!("Failed {} datasets\n", result.errors.len()));
+            let rows = result
+                .errors
+                .iter()
+                .map(|error| {
+                    vec![
+                        error.specifier.clone(),
+                        if error.retryable { "yes" } else { "no" }.to_string(),
+                        error.message.clone(),
+                    ]
+                })
+                .collect::<Vec<_>>();
+            out.push_str(&table(&["SPECIFIER", "RETRYABLE", "ERROR"], &rows));
+        }
+        if let Some(saved) =
+            savings_label(result.items.iter().filter_map(|item| item.cache_savings))
+        {
+            out.push_str(&format!("Cache saved {saved} of downloading\n"));
+        }
+        out.push_str(&timings_footer(result));
+        if let Some(selection) = result
+            .summary
+            .as_ref()
+            .and_then(|summary| summary.selection.as_ref())
+        {
+            out.push_str(&format!(
+                "Structures for {} (min coverage {:.0}%)\n",
+                selection_label(selection),
+                selection.min_coverage * 100.0
+            ));
+            let rows = selection
+                .candidates
+                .iter()
+                .map(|candidate| {
+                    vec![
+                        candidate.id.clone(),
+                        or_dash(candidate.method.as_deref()),
+                        candidate
+                            .resolution
+                            .map(|resolution| format!("{resolution:.2}"))
+                            .unwrap_or_else(|| "-".to_string()),
+                        format!("{:.0}%", candidate.coverage * 100.0),
+                        if candidate.selected { "yes" } else { "-" }.to_string(),
+                    ]
+                })
+                .collect::<Vec<_>>();
+            out.push_str(&table(
+                &["PDB", "METHOD", "RESOLUTION", "COVERAGE", "SELECTED"],
+                &rows,
+            ));
+        }
+        out
+    }
+
+    pub fn render_list(result: &ListResult) -> String {
+        if result.datasets.is_empty() {
+            return "No datasets found\n".to_string();
+        }
+        list_table(result)
+    }
+
+    pub fn render_find(result: &FindResult) -> String {
+        render_find(result, None)
+    }
+
+    pub fn render_clear(result: &ClearResult) -> String {
+        let datasets = match result.datasets {
+            1 => "1 dataset".to_string(),
+            count => format!("{count} datasets"),
+        };
+        let size = bytes_to_human(result.bytes);
+        let mut out = if result.dry_run {
+            format!("Would clear {datasets} ({size})\n")
+        } else {
+            format!("Project store cleared: {datasets} ({size})\n")
+        };
+        let width = result
+            .types
+            .iter()
+            .map(|kind| kind.dataset_type.len())
+            .max()
+            .unwrap_or(0);
+        for kind in &result.types {
+            out.push_str(&format!(
+                "  {:<width$}  {:>5}  {}\n",
+                kind.dataset_type,
+                kind.datasets,
+                bytes_to_human(kind.bytes)
+            ));
+        }
+        if !result.skipped_pinned.is_empty() {
+            out.push_str(&format!(
+                "Kept pinned: {} (pass --include-pinned to clear them)\n",
+                result.skipped_pinned.join(", ")
+            ));
+        }
+        out
+    }
+
+    pub fn render_pin(result: &PinResult) -> String {
+        let state = if result.pinned { "pinned" } else { "unpinned" };
+        if result.records.is_empty() {
+            format!("{} was already {state}\n", result.specifier)
+        } else {
+            format!(
+                "{} {state} ({} records)\n",
+                result.specifier,
+                result.records.len()
+            )
+        }
+    }
+
+    pub fn print_pin(result: &PinResult) -> io::Result<()> {
+        print_text(&Self::render_pin(result))
+    }
+
+    pub fn render_pin_matches(result: &PinMatches) -> String {
+        let mut out = format!("{} matched {} datasets\n", result.pattern, result.matched);
+        for dataset in &result.datasets {
+            out.push_str(&Self::render_pin(dataset));
+        }
+        out
+    }
+
+    pub fn print_pin_matches(result: &PinMatches) -> io::Result<()> {
+        print_text(&Self::render_pin_matches(result))
+    }
+
+    pub fn render_init(result: &InitResult) -> String {
+        let verb = if result.dry_run && result.diff.as_deref().is_some_and(|diff| !diff.is_empty())
+        {
+            "Would write"
+        } else if result.written {
+            "Wrote"
+        } else {
+            "Unchanged"
+        };
+        let mut out = format!(
+            "{verb} {}: {}\n  added {}\n  kept {}\n",
+            result.path,
+            init_counts(&result.counts),
+            init_counts(&result.added),
+            init_counts(&result.preserved)
+        );
+        if let Some(diff) = &result.diff {
+            out.push('\n');
+            out.push_str(diff);
+        }
+        out
+    }
+
+    pub fn render_convert(result: &ConvertResult) -> String {
+        let mut out = if result.action == "present" {
+            format!(
+                "{}:{} already has {} in the {}: {}\n",
+                result.dataset_type, result.id, result.to, result.location, result.path
+            )
+        } else {
+            format!(
+                "Converted {}:{} {} -> {} in the {}: {}\n",
+                result.dataset_type,
+                result.id,
+                result.from,
+                result.to,
+                result.location,
+                result.path
+            )
+        };
+        for note in &result.notes {
+            out.push_str(&format!("note: {note}\n"));
+        }
+        out
+    }
+
+    pub fn print_convert(result: &ConvertResult) -> io::Result<()> {
+        print_text(&Self::render_convert(result))
+    }
+
+    pub fn render_import(result: &ImportResult) -> String {
+        let mut out = format!(
+            "Imported {}:{} from {} ({} files, {}{}): {}\n",
+            result.dataset_type,
+            result.id,
+            result.imported_from,
+            result.files.len(),
+            bytes_to_human(result.bytes),
+            if result.linked { ", linked" } else { "" },
+            result.project_path
+        );
+        if !result.verified {
+            out.push_str("note: content was not verified\n");
+        }
+        for note in &result.notes {
+            out.push_str(&format!("note: {note}\n"));
+        }
+        out
+    }
+
+    pub fn print_import(result: &ImportResult) -> io::Result<()> {
+        print_text(&Self::render_import(result))
+    }
+
+    pub fn render_cache_stats(stats: &CacheStats) -> String {
+        format!(
+            "Cache hits: {}\nBytes avoided: {}\nTime saved: {}\n",
+            stats.cache_hits,
+            bytes_to_human(stats.bytes_avoided),
+            millis_to_human(stats.time_saved_ms)
+        )
+    }
+
+    pub fn print_cache_stats(stats: &CacheStats) -> io::Result<()> {
+        print_text(&Self::render_cache_stats(stats))
+    }
+
+    pub fn render_cache_dedup(report: &DedupReport) -> String {
+        let mut out = format!(
+            "Datasets: {} ({} files: {} linked, {} copied)\nBlobs: {} [{}]\nBytes reclaimed: {}\n",
+            report.datasets,
+            report.files,
+            report.linked,
+            report.copied,
+            report.blobs,
+            bytes_to_human(report.blob_bytes),
+            bytes_to_human(report.bytes_reclaimed)
+        );
+        if !report.skipped.is_empty() {
+            out.push_str(&format!(
+                "Left as they are (no file digests recorded): {}\n",
+                report.skipped.join(", ")
+            ));
+        }
+        out
+    }
+
+    pub fn print_cache_dedup(report: &DedupReport) -> io::Result<()> {
+        print_text(&Self::render_cache_dedup(report))
+    }
+
+    pub fn render_cache_index(index: &CacheIndex) -> String {
+        if index.datasets.is_empty() {
+            return "No datasets found\n".to_string();
+        }
+        let rows = index
+            .datasets
+            .iter()
+            .map(|entry| {
+                vec![
+                    entry.dataset_type.clone(),
+                    entry.id.clone(),
+                    bytes_to_human(entry.bytes),
+                    if entry.projects.is_empty() {
+                        "-".to_string()
+                    } else {
+                        entry
+                            .projects
+                            .iter()
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    },
+                ]
+            })
+            .collect::<Vec<_>>();
+        table(&["TYPE", "ID", "SIZE", "PROJECTS"], &rows)
+    }
+
+    pub fn print_cache_index(index: &CacheIndex) -> io::Result<()> {
+        print_text(&Self::render_cache_index(index))
+    }
+
+    pub fn render_cache_usage(usage: &CacheUsage) -> String {
+        if usage.projects.is_empty() {
+            return "No project has fetched from this cache\n".to_string();
+        }
+        let rows = usage
+            .projects
+            .iter()
+            .map(|project| {
+                vec![
+                    project.project.clone(),
+                    project.datasets.to_string(),
+                    bytes_to_human(project.bytes),
+                ]
+            })
+            .collect::<Vec<_>>();
+        table(&["PROJECT", "DATASETS", "SIZE"], &rows)
+    }
+
+    pub fn print_cache_usage(usage: &CacheUsage) -> io::Result<()> {
+        print_text(&Self::render_cache_usage(usage))
+    }
+
+    pub fn render_reindex(report: &ReindexReport) -> String {
+        let mut out = format!(
+            "Indexed {} datasets held by {} projects\n",
+            report.datasets, report.projects
+        );
+        if report.dropped_references > 0 {
+            out.push_str(&format!(
+                "Dropped {} references from projects that no longer hold the dataset\n",
+                report.dropped_references
+            ));
+        }
+        out
+    }
+
+    pub fn print_reindex(report: &ReindexReport) -> io::Result<()> {
+        print_text(&Self::render_reindex(report))
+    }
+
+    pub fn render_runs(runs: &[RunSummary]) -> String {
+        if runs.is_empty() {
+            return "No fetch runs recorded\n".to_string();
+        }
+        let rows = runs
+            .iter()
+            .map(|run| {
+                vec![
+                    run.id.clone(),
+                    run.started_at
+                        .get(..19)
+                        .unwrap_or(&run.started_at)
+                        .replace('T', " "),
+                    millis_to_human(run.duration_ms),
+                    run.items.to_string(),
+                    if run.failed { "failed" } else { "ok" }.to_string(),
+                    if run.specifiers.is_empty() {
+                        "-".to_string()
+                    } else {
+                        run.specifiers.join(",")
+                    },
+                ]
+            })
+            .collect::<Vec<_>>();
+        table(
+            &["ID", "STARTED", "DURATION", "ITEMS", "STATUS", "SPECIFIERS"],
+            &rows,
+        )
+    }
+
+    pub fn print_runs(runs: &[RunSummary]) -> io::Result<()> {
+        print_text(&Self::render_runs(runs))
+    }
+
+    pub fn print_fetch(result: &FetchResult) -> io::Result<()> {
+        print_text(&Self::render_fetch(result))
+    }
+
+    pub fn print_list(result: &ListResult) -> io::Result<()> {
+        print_skipped(result);
+        print_text(&Self::render_list(result))
+    }
+
+    pub fn print_find(result: &FindResult) -> io::Result<()> {
+        print_text(&Self::render_find(result))
+    }
+
+    pub fn print_clear(result: &ClearResult) -> io::Result<()> {
+        print_text(&Self::render_clear(result))
+    }
+
+    pub fn print_init(result: &InitResult) -> io::Result<()> {
+        print_text(&Self::render_init(result))
+    }
+
+    pub fn render_gc(result: &GcResult) -> String {
+        if result.is_empty() {
+            return "Nothing to collect\n".to_string();
+        }
+        let mut out = String::new();
+        gc_section(&mut out, "Stale temp directories", &result.temp_dirs);
+        gc_section(&mut out, "Metadata without data", &result.dangling_metadata);
+        gc_section(&mut out, "Orphaned dataset directories", &result.orphans);
+        gc_section(&mut out, "Unreferenced blobs", &result.unreferenced_blobs);
+        if !result.skipped_pinned.is_empty() {
+            out.push_str(&format!(
+                "Kept pinned: {} (pass --include-pinned to delete their records)\n",
+                result.skipped_pinned.join(", ")
+            ));
+        }
+        let (removed, bytes) = [
+            &result.temp_dirs,
+            &result.dangling_metadata,
+            &result.orphans,
+            &result.unreferenced_blobs,
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.removed)
+        .fold((0, 0), |(count, bytes), entry| {
+            (count + 1, bytes + entry.bytes)
+        });
+        if result.dry_run {
+            out.push_str("Dry run: nothing deleted; pass --yes to delete");
+            if !result.orphans.is_empty() {
+                out.push_str(" (orphans also need --prune-orphans)");
+            }
+            out.push('\n');
+        } else {
+            out.push_str(&format!(
+                "Deleted {removed} entries, {}\n",
+                bytes_to_human(bytes)
+            ));
+        }
+        out
+    }
+
+    pub fn print_gc(result: &GcResult) -> io::Result<()> {
+        print_text(&Self::render_gc(result))
+    }
+
+    pub fn print_config_valid(path: &Path) -> io::Result<()> {
+        print_text(&format!("{} is valid\n", path.display()))
+    }
+
+    pub fn render_config_settings(settings: &ConfigSettings) -> String {
+        let rows = settings
+            .settings
+            .iter()
+            .map(|setting| {
+                let value = match &setting.value {
+                    Value::String(text) => text.clone(),
+                    Value::Array(items) => items
+                        .iter()
+                        .map(|item| {
+                            item.as_str()
+                                .map_or_else(|| item.to_string(), str::to_string)
+                        })
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    other => other.to_string(),
+                };
+                let origin = serde_json::to_value(setting.origin)
+                    .ok()
+                    .and_then(|origin| origin.as_str().map(str::to_string))
+                    .unwrap_or_default();
+                vec![
+                    setting.key.clone(),
+                    or_dash(Some(value.as_str()).filter(|value| !value.is_empty())),
+                    origin,
+                ]
+            })
+            .collect::<Vec<_>>();
+        table(&["SETTING", "VALUE", "ORIGIN"], &rows)
+    }
+
+    pub fn print_config_settings(settings: &ConfigSettings) -> io::Result<()> {
+        print_text(&Self::render_config_settings(settings))
+    }
+
+    pub fn render_doctor(report: &DoctorReport) -> String {
+        let mut out = doctor_table(report);
+        out.push_str(&doctor_footer(report));
+        out
+    }
+
+    pub fn print_doctor(report: &DoctorReport) -> io::Result<()> {
+        print_text(&Self::render_doctor(report))
+    }
+
+    pub fn render_ping(report: &PingReport) -> String {
+        let mut out = ping_table(report);
+        out.push_str(&ping_errors(report));
+        out
+    }
+
+    pub fn print_ping(report: &PingReport) -> io::Result<()> {
+        print_text(&Self::render_ping(report))
+    }
+
+    /// The tools table, then how to fix each tool that is not `ok`.
+    pub fn render_tools(report: &ToolsReport) -> String {
+        let rows = report
+            .tools
+            .iter()
+            .map(|check| {
+                vec![
+                    check.tool.clone(),
+                    check.version.clone().unwrap_or_else(|| "-".to_string()),
+                    check.minimum.clone(),
+                    check.status.to_string(),
+                    check.path.clone().unwrap_or_else(|| "-".to_string()),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let mut out = table(&["TOOL", "VERSION", "MINIMUM", "STATUS", "PATH"], &rows);
+        for check in &report.tools {
+            if let Some(hint) = &check.hint {
+                out.push_str(&format!("{}: {hint}\n", check.tool));
+            }
+        }
+        out
+    }
+
+    pub fn print_tools(report: &ToolsReport) -> io::Result<()> {
+        print_text(&Self::render_tools(report))
+    }
+
+    pub fn render_doi_datasets(result: &DoiDatasets) -> String {
+        if result.datasets.is_empty() {
+            return format!("No datasets traced to doi:{}\n", result.doi);
+        }
+        doi_datasets_table(result)
+    }
+
+    pub fn print_doi_datasets(result: &DoiDatasets) -> io::Result<()> {
+        print_text(&Self::render_doi_datasets(result))
+    }
+}
+
+/// Colored, emoji-decorated text for interactive terminals.
+pub struct StyledOutput;
+
+impl StyledOutput {
+    pub fn render_fetch(result: &FetchResult) -> String {
+        let green = "\x1b[32m";
+        let yellow = "\x1b[33m";
+        let cyan = "\x1b[36m";
+        let reset = "\x1b[0m";
+
+        let mut out = format!("{cyan}📦 KIRA-BM summary{reset}\n");
+        out.push_str(&format!(
+            "{green}✅ Downloaded datasets: {}{reset}\n",
+            result.items.len()
+        ));
+        out.push_str(&format!(
+            "{yellow}⚠️ Errors: {}{reset}\n",
+            result.errors.len()
+        ));
+        for error in &result.errors {
+            out.push_str(&format!(
+                "{yellow}✖ {}: {}{reset}\n",
+                error.specifier, error.message
+            ));
+        }
+        for item in &result.items {
+            let action = item.action.as_str();
+            let (icon, color) = if action.contains("download") || action.contains("fetched") {
+                ("⬇️", cyan)
+            } else if action.contains("cache") || action == "not-modified" {
+                ("♻️", green)
+            } else {
+                ("•", yellow)
+            };
+            out.push_str(&format!(
+                "{color}{icon} {} {} ({}){reset}\n",
+                item.dataset_type, item.id, action
+            ));
+            if let Some(path) = &item.project_path {
+                out.push_str(&format!("{color}   📁 project: {path}{reset}\n"));
+            }
+            if let Some(path) = &item.cache_path {
+                out.push_str(&format!("{color}   🗃️  cache: {path}{reset}\n"));
+            }
+            if let Some(saved) = item
+                .cache_savings
+                .and_then(|savings| savings_label([savings]))
+            {
+                out.push_str(&format!("{color}   ⏱️  saved: {saved}{reset}\n"));
+            }
+        }
+        if let Some(summary) = &result.summary
+            && let Some(taxon) = &summary.taxon
+        {
+            out.push_str(&format!(
+                "{cyan}🧬 Taxon {taxon}: {} assemblies matched, {} fetched{reset}\n",
+                matched_count(summary),
+                summary.resolved_targets
+            ));
+        }
+        if let Some(targets) = result
+            .summary
+            .as_ref()
+            .and_then(|summary| summary.doi_selection.as_ref())
+        {
+            out.push_str(&format!(
+                "{cyan}🔎 DOI targets: {} selected, {} skipped{reset}\n",
+                targets.selected.len(),
+                targets.skipped.len()
+            ));
+            if targets.selected.is_empty() && !targets.skipped.is_empty() {
+                out.push_str(&format!("{yellow}   {DOI_DOWNLOAD_HINT}{reset}\n"));
+            }
+        }
+        if let Some(summary) = &result.summary
+            && let (Some(accession), Some(counts)) = (&summary.uniprot, &summary.structures)
+        {
+            out.push_str(&format!(
+                "{cyan}🧬 Structures of {accession}: {} listed, {} downloaded, {} cached, {} skipped{reset}\n",
+                counts.listed, counts.downloaded, counts.cached, counts.skipped
+            ));
+        }
+        if let Some(selection) = result
+            .summary
+            .as_ref()
+            .and_then(|summary| summary.selection.as_ref())
+        {
+            out.push_str(&format!(
+                "{cyan}🧬 Structures for {}{reset}\n",
+                selection_label(selection)
+            ));
+            for candidate in &selection.candidates {
+                let mut line = format!("{} {:.0}%", candidate.id, candidate.coverage * 100.0);
+                if let Some(method) = &candidate.method {
+                    line.push_str(&format!(" {method}"));
+                }
+                if let Some(resolution) = candidate.resolution {
+                    line.push_str(&format!(" {resolution:.2} Å"));
+                }
+                if candidate.selected {
+                    out.push_str(&format!("{green}   ✅ {line}{reset}\n"));
+                } else {
+                    out.push_str(&format!("   • {line}\n"));
+                }
+            }
+        }
+        out
+    }
+
+    pub fn render_list(result: &ListResult) -> String {
+        if result.datasets.is_empty() {
+            return "\x1b[33mNo datasets found\x1b[0m\n".to_string();
+        }
+        let table = list_table(result);
+        let mut lines = table.lines();
+        let mut out = format!("\x1b[36m{}\x1b[0m\n", lines.next().unwrap_or_default());
+        for (line, entry) in lines.zip(&result.datasets) {
+            if entry.status.is_some() {
+                out.push_str(&format!("\x1b[90m{line}\x1b[0m\n"));
+            } else {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    pub fn render_find(result: &FindResult) -> String {
+        render_find(result, Some(("\x1b[1;33m", "\x1b[0m")))
+    }
+
+    pub fn print_fetch(result: &FetchResult) -> io::Result<()> {
+        print_text(&Self::render_fetch(result))
+    }
+
+    pub fn print_find(result: &FindResult) -> io::Result<()> {
+        print_text(&Self::render_find(result))
+    }
+
+    pub fn print_list(result: &ListResult) -> io::Result<()> {
+        print_skipped(result);
+        print_text(&Self::render_list(result))
+    }
+
+    pub fn render_doctor(report: &DoctorReport) -> String {
+        let table = doctor_table(report);
+        let mut lines = table.lines();
+        let mut out = format!("\x1b[36m{}\x1b[0m\n", lines.next().unwrap_or_default());
+        for (line, check) in lines.zip(&report.checks) {
+            let color = match check.status {
+                CheckStatus::Pass => "\x1b[32m",
+                CheckStatus::Warn => "\x1b[33m",
+                CheckStatus::Fail => "\x1b[31m",
+            };
+            out.push_str(&format!("{color}{line}\x1b[0m\n"));
+        }
+        out.push_str(&doctor_footer(report));
+        out
+    }
+
+    pub fn print_doctor(report: &DoctorReport) -> io::Result<()> {
+        print_text(&Self::render_doctor(report))
+    }
+
+    pub fn render_ping(report: &PingReport) -> String {
+        let table = ping_table(report);
+        let mut lines = table.lines();
+        let mut out = format!("\x1b[36m{}\x1b[0m\n", lines.next().unwrap_or_default());
+        for (line, result) in lines.zip(&report.results) {
+            let color = match result.status {
+                PingStatus::Ok => "\x1b[32m",
+                PingStatus::ClientError | PingStatus::ServerError => "\x1b[33m",
+                PingStatus::Timeout | PingStatus::Tls | PingStatus::Unreachable => "\x1b[31m",
+            };
+            out.push_str(&format!("{color}{line}\x1b[0m\n"));
+        }
+        out.push_str(&ping_errors(report));
+        out
+    }
+
+    pub fn print_ping(report: &PingReport) -> io::Result<()> {
+        print_text(&Self::render_ping(report))
+    }
+
+    pub fn render_doi_datasets(result: &DoiDatasets) -> String {
+        if result.datasets.is_empty() {
+            return format!("\x1b[33mNo datasets traced to doi:{}\x1b[0m\n", result.doi);
+        }
+        let table = doi_datasets_table(result);
+        let mut lines = table.lines();
+        let mut out = format!("\x1b[36m{}\x1b[0m\n", lines.next().unwrap_or_default());
+        for line in lines {
+            out.push_str(line);
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn print_doi_datasets(result: &DoiDatasets) -> io::Result<()> {
+        print_text(&Self::render_doi_datasets(result))
+    }
+}
+
+fn doi_datasets_table(result: &DoiDatasets) -> String {
+    let rows = result
+        .datasets
+        .iter()
+        .map(|meta| {
+            let provenance = meta.provenance.as_ref();
+            vec![
+                meta.dataset_type.clone(),
+                meta.id.clone(),
+                provenance
+                    .map(|provenance| provenance.extracted_from.to_string())
+                    .unwrap_or_default(),
+                provenance
+                    .and_then(|provenance| provenance.via.clone())
+                    .unwrap_or_else(|| "-".to_string()),
+                meta.resolved_path.clone(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    table(&["TYPE", "ID", "FOUND IN", "VIA", "PATH"], &rows)
+}
+
+fn ping_table(report: &PingReport) -> String {
+    let rows = report
+        .results
+        .iter()
+        .map(|result| {
+            vec![
+                result.registry.clone(),
+                result.status.to_string(),
+                result
+                    .http_status
+                    .map(|status| status.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                result
+                    .latency_ms
+                    .map(|latency| format!("{latency} ms"))
+                    .unwrap_or_else(|| "-".to_string()),
+                if result.tls { "yes" } else { "no" }.to_string(),
+                result.url.clone(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    table(
+        &["REGISTRY", "STATUS", "HTTP", "LATENCY", "TLS", "URL"],
+        &rows,
+    )
+}
+
+fn ping_errors(report: &PingReport) -> String {
+    report
+        .results
+        .iter()
+        .filter_map(|result| {
+            let error = result.error.as_ref()?;
+            Some(format!("{}: {error}\n", result.registry))
+        })
+        .collect()
+}
+
+fn doctor_table(report: &DoctorReport) -> String {
+    let rows = report
+        .checks
+        .iter()
+        .map(|check| {
+            vec![
+                check.status.to_string().to_ascii_uppercase(),
+                check.name.clone(),
+                check.detail.clone(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    table(&["STATUS", "CHECK", "DETAIL"], &rows)
+}
+
+/// Hints for every check that did not pass, then the tally.
+fn doctor_footer(report: &DoctorReport) -> String {
+    let mut out = String::new();
+    for check in &report.checks {
+        if let Some(hint) = &check.hint {
+            out.push_str(&format!("{}: {hint}\n", check.name));
+        }
+    }
+    out.push_str(&format!(
+        "{} passed, {} warnings, {} failed\n",
+        report.count(CheckStatus::Pass),
+        report.count(CheckStatus::Warn),
+        report.count(CheckStatus::Fail)
+    ));
+    out
+}
+
+fn list_table(result: &ListResult) -> String {
+    // The alias column only appears once a project uses aliases.
+    let aliased = result.datasets.iter().any(|entry| entry.alias.is_some());
+    let rows = result
+        .datasets
+        .iter()
+        .map(|entry| {
+            let mut row = vec![entry.dataset_type.clone(), entry.id.clone()];
+            if aliased {
+                row.push(or_dash(entry.alias.as_deref()));
+            }
+            row.extend([
+                if entry.formats.is_empty() {
+                    or_dash(entry.format.as_deref())
+                } else {
+                    entry.formats.join(",")
+                },
+                match (&entry.status, entry.materialization, entry.pinned) {
+                    (Some(status), _, true) => format!("{status},pinned"),
+                    (Some(status), _, false) => status.clone(),
+                    (None, Materialization::MetadataOnly, true) => {
+                        format!("{},pinned", entry.materialization)
+                    }
+                    (None, Materialization::MetadataOnly, false) => {
+                        entry.materialization.to_string()
+                    }
+                    (None, Materialization::Full, true) => "pinned".to_string(),
+                    (None, Materialization::Full, false) => "-".to_string(),
+                },
+                entry
+                    .bytes
+                    .map(bytes_to_human)
+                    .unwrap_or_else(|| "-".to_string()),
+                or_dash(
+                    entry
+                        .downloaded_at
+                        .as_deref()
+                        .map(|value| value.get(..10).unwrap_or(value)),
+                ),
+                or_dash(entry.project_path.as_deref()),
+                or_dash(entry.cache_path.as_deref()),
+            ]);
+            row
+        })
+        .collect::<Vec<_>>();
+    let mut headers = vec!["TYPE", "ID"];
+    if aliased {
+        headers.push("ALIAS");
+    }
+    headers.extend(["FORMAT", "STATUS", "SIZE", "DOWNLOADED", "PROJECT", "CACHE"]);
+    table(&headers, &rows)
+}
+
+fn render_find(result: &FindResult, emphasis: Option<(&str, &str)>) -> String {
+    if result.matches.is_empty() {
+        return format!("No datasets match \"{}\"\n", result.pattern);
+    }
+    let type_width = result
+        .matches
+        .iter()
+        .map(|item| item.dataset_type.len())
+        .max()
+        .unwrap_or(0)
+        .max("TYPE".len());
+    let id_width = result
+        .matches
+        .iter()
+        .map(|item| item.id.len())
+        .max()
+        .unwrap_or(0)
+        .max("ID".len());
+    let mut out = format!(
+        "{:<type_width$}  {:<id_width$}  {:<9}  MATCH\n",
+        "TYPE", "ID", "FIELD"
+    );
+    for item in &result.matches {
+        let field = serde_json::to_value(item.matched_field)
+            .ok()
+            .and_then(|value| value.as_str().map(|v| v.to_string()))
+            .unwrap_or_default();
+        let text = match emphasis {
+            Some((start, end)) => highlight(&item.matched_text, &result.pattern, start, end),
+            None => item.matched_text.clone(),
+        };
+        out.push_str(&format!(
+            "{:<type_width$}  {:<id_width$}  {:<9}  {}\n",
+            item.dataset_type, item.id, field, text
+        ));
+    }
+    out
+}
+
+fn highlight(text: &str, pattern: &str, start: &str, end: &str) -> String {
+    let lower = text.to_lowercase();
+    let needle = pattern.trim().to_lowercase();
+    match lower.find(&needle) {
+        Some(idx) if !needle.is_empty() && lower.len() == text.len() => format!(
+            "{}{start}{}{end}{}",
+            &text[..idx],
+            &text[idx..idx + needle.len()],
+            &text[idx + needle.len()..]
+        ),
+        _ => text.to_string(),
+    }
+}
+
+/// Left-aligned columns separated by two spaces; the last column is not
+/// padded so lines carry no trailing whitespace.
+fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths = headers
+        .iter()
+        .map(|header| header.chars().count())
+        .collect::<Vec<_>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: &mut dyn Iterator<Item = &str>| {
+        let mut line = String::new();
+        for (index, cell) in cells.enumerate() {
+            if index + 1 == widths.len() {
+                line.push_str(cell);
+            } else {
+                let pad = widths[index] - cell.chars().count();
+                line.push_str(cell);
+                line.push_str(&" ".repeat(pad + 2));
+            }
+        }
+        line.push('\n');
+        line
+    };
+    let mut out = line(&mut headers.iter().copied());
+    for row in rows {
+        out.push_str(&line(&mut row.iter().map(String::as_str)));
+    }
+    out
+}
+
+fn selection_label(selection: &StructureSelection) -> String {
+    format!(
+        "{}[{}-{}]",
+        selection.accession, selection.start, selection.end
+    )
+}
+
+/// `1.2 MB and 3.4 s` for the summed savings, or just the bytes when no
+/// download recorded its duration; `None` without savings.
+/// How long the fetch took and where the time went, with the slowest
+/// items when there were several. Empty for results that were not timed.
+fn timings_footer(result: &FetchResult) -> String {
+    if result.total_duration_ms == 0 {
+        return String::new();
+    }
+    let timings = &result.timings;
+    let mut out = format!(
+        "Took {}: resolve {}, download {}, verify {}, store {}\n",
+        millis_to_human(result.total_duration_ms),
+        millis_to_human(timings.resolve_ms),
+        millis_to_human(timings.download_ms),
+        millis_to_human(timings.verify_ms),
+        millis_to_human(timings.store_ms),
+    );
+    if result.items.len() > 1 {
+        let slowest = result
+            .slowest(3)
+            .iter()
+            .map(|item| {
+                format!(
+                    "{}:{} {}",
+                    item.dataset_type,
+                    item.id,
+                    millis_to_human(item.timings.total_ms())
+                )
+            })
+            .collect::<Vec<_>>();
+        out.push_str(&format!("Slowest: {}\n", slowest.join(", ")));
+    }
+    out
+}
+
+pub fn savings_label(savings: impl IntoIterator<Item = CacheSavings>) -> Option<String> {
+    let mut bytes = None;
+    let mut time = None;
+    for saving in savings {
+        *bytes.get_or_insert(0) += saving.bytes_avoided;
+        if let Some(ms) = saving.time_saved_ms {
+            *time.get_or_insert(0) += ms;
+        }
+    }
+    let bytes = bytes_to_human(bytes?);
+    Some(match time {
+        Some(ms) => format!("{bytes} and {}", millis_to_human(ms)),
+        None => bytes,
+    })
+}
+
+fn or_dash(value: Option<&str>) -> String {
+    value.unwrap_or("-").to_string()
+}
+
+fn print_skipped(result: &ListResult) {
+    for warning in &result.warnings {
+        eprintln!("warning: skipped {warning}");
+    }
+}
+
+fn print_text(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(text.as_bytes())?;
+    stdout.flush()
+}
+
+fn gc_section(out: &mut String, title: &str, entries: &[GcEntry]) {
+    if entries.is_empty() {
+        return;
+    }
+    out.push_str(&format!("{title}:\n"));
+    for entry in entries {
+        let mark = if entry.removed { "deleted" } else { "found" };
+        let label = match (&entry.specifier, &entry.missing_path) {
+            (Some(specifier), Some(missing)) => format!("{specifier} ({missing} is gone)"),
+            _ => entry.path.clone(),
+        };
+        out.push_str(&format!(
+            "  {mark:<8} {label} [{}]\n",
+            bytes_to_human(entry.bytes)
+        ));
+    }
+}
+
+fn init_counts(counts: &InitCounts) -> String {
+    format!(
+        "{} proteins, {} genomes, {} srr, {} uniprot, {} doi, {} bioprojects, {} proteomes",
+        counts.proteins,
+        counts.genomes,
+        counts.srr,
+        counts.uniprot,
+        counts.doi,
+        counts.bioprojects,
+        counts.proteomes
+    )
 }
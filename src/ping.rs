@@ -0,0 +1,238 @@
+//! Registry connectivity probes behind `kira-bm ping` and the doctor's
+//! network checks.
+//!
+//! Each registry is probed with the cheap request from
+//! [`HEALTH_ENDPOINTS`](crate::http::HEALTH_ENDPOINTS), through the same
+//! rate limiters, API key, proxy settings and registry mirrors as fetches.
+
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use reqwest::blocking::Client;
+use reqwest::header::{DATE, HeaderMap, HeaderValue, USER_AGENT};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::http::{HealthEndpoint, HttpSettings, ProbeMethod};
+
+/// What a registry answered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeResponse {
+    pub status: u16,
+    pub latency: Duration,
+    /// The response's `Date` header, for comparing clocks.
+    pub date: Option<DateTime<Utc>>,
+}
+
+/// Why a registry did not answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeError {
+    Timeout(String),
+    /// The TLS handshake failed, or this build cannot speak TLS at all.
+    Tls(String),
+    Unreachable(String),
+}
+
+pub trait EndpointProbe {
+    fn probe(&self, endpoint: &HealthEndpoint) -> Result<ProbeResponse, ProbeError>;
+}
+
+pub struct HttpProbe {
+    client: Client,
+    settings: HttpSettings,
+}
+
+impl HttpProbe {
+    pub fn with_settings(settings: HttpSettings) -> reqwest::Result<Self> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            USER_AGENT,
+            HeaderValue::from_static(concat!("kira-bm/", env!("CARGO_PKG_VERSION"))),
+        );
+        let client = settings
+            .client_builder("ping")
+            .default_headers(headers)
+            .timeout(settings.timeouts_for("ping").request)
+            .build()?;
+        Ok(Self { client, settings })
+    }
+}
+
+impl EndpointProbe for HttpProbe {
+    fn probe(&self, endpoint: &HealthEndpoint) -> Result<ProbeResponse, ProbeError> {
+        let mirrors = self
+            .settings
+            .mirrors()
+            .map_err(|err| ProbeError::Unreachable(err.to_string()))?;
+        let mut url = reqwest::Url::parse(endpoint.url)
+            .map_err(|err| ProbeError::Unreachable(err.to_string()))?;
+        self.settings.authorize(&mut url);
+        self.settings.limiter_for_url(&url).acquire();
+        let url = reqwest::Url::parse(&mirrors.rewrite(url.as_str()))
+            .map_err(|err| ProbeError::Unreachable(err.to_string()))?;
+        let request = match endpoint.method {
+            ProbeMethod::Head => self.client.head(url),
+            ProbeMethod::Get => self.client.get(url),
+        };
+        let started = Instant::now();
+        let response = request.send().map_err(|err| classify(&err))?;
+        let latency = started.elapsed();
+        let date = response
+            .headers()
+            .get(DATE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+            .map(|date| date.with_timezone(&Utc));
+        Ok(ProbeResponse {
+            status: response.status().as_u16(),
+            latency,
+            date,
+        })
+    }
+}
+
+fn classify(err: &reqwest::Error) -> ProbeError {
+    let message = error_chain(err);
+    let lower = message.to_ascii_lowercase();
+    if err.is_timeout() {
+        ProbeError::Timeout(message)
+    } else if [
+        "certificate",
+        "tls",
+        "ssl",
+        "handshake",
+        "scheme is not http",
+    ]
+    .iter()
+    .any(|needle| lower.contains(needle))
+    {
+        ProbeError::Tls(message)
+    } else {
+        ProbeError::Unreachable(message)
+    }
+}
+
+/// reqwest hides the TLS or DNS cause behind "error sending request".
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum PingStatus {
+    Ok,
+    /// Answered with 4xx: reachable, but the probe record looks wrong.
+    ClientError,
+    ServerError,
+    Timeout,
+    Tls,
+    Unreachable,
+}
+
+impl PingStatus {
+    pub fn is_reachable(self) -> bool {
+        matches!(
+            self,
+            PingStatus::Ok | PingStatus::ClientError | PingStatus::ServerError
+        )
+    }
+}
+
+impl std::fmt::Display for PingStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            PingStatus::Ok => "ok",
+            PingStatus::ClientError => "client-error",
+            PingStatus::ServerError => "server-error",
+            PingStatus::Timeout => "timeout",
+            PingStatus::Tls => "tls",
+            PingStatus::Unreachable => "unreachable",
+        };
+        f.write_str(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct PingResult {
+    pub registry: String,
+    pub method: ProbeMethod,
+    pub url: String,
+    pub status: PingStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Whether the endpoint is reached over HTTPS.
+    pub tls: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip)]
+    pub date: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct PingReport {
+    pub results: Vec<PingResult>,
+}
+
+impl PingReport {
+    pub fn unreachable(&self) -> Vec<&str> {
+        self.results
+            .iter()
+            .filter(|result| !result.status.is_reachable())
+            .map(|result| result.registry.as_str())
+            .collect()
+    }
+}
+
+pub fn ping(probe: &dyn EndpointProbe, endpoints: &[&HealthEndpoint]) -> PingReport {
+    PingReport {
+        results: endpoints
+            .iter()
+            .map(|endpoint| ping_one(probe, endpoint))
+            .collect(),
+    }
+}
+
+pub fn ping_one(probe: &dyn EndpointProbe, endpoint: &HealthEndpoint) -> PingResult {
+    let mut result = PingResult {
+        registry: endpoint.registry.to_string(),
+        method: endpoint.method,
+        url: endpoint.url.to_string(),
+        status: PingStatus::Ok,
+        http_status: None,
+        latency_ms: None,
+        tls: endpoint.url.starts_with("https://"),
+        error: None,
+        date: None,
+    };
+    match probe.probe(endpoint) {
+        Ok(response) => {
+            result.status = match response.status {
+                500.. => PingStatus::ServerError,
+                400..=499 => PingStatus::ClientError,
+                _ => PingStatus::Ok,
+            };
+            result.http_status = Some(response.status);
+            result.latency_ms = Some(response.latency.as_millis() as u64);
+            result.date = response.date;
+        }
+        Err(err) => {
+            let (status, message) = match err {
+                ProbeError::Timeout(message) => (PingStatus::Timeout, message),
+                ProbeError::Tls(message) => (PingStatus::Tls, message),
+                ProbeError::Unreachable(message) => (PingStatus::Unreachable, message),
+            };
+            result.status = status;
+            result.error = Some(message);
+        }
+    }
+    result
+}
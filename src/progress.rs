@@ -0,0 +1,154 @@
+//! Byte-level download progress.
+//!
+//! Clients stream response bodies through [`Metered`], which reports the
+//! bytes received so far as `download.progress` events on the fetch's
+//! progress sink. Reports are throttled to one every [`REPORT_INTERVAL`],
+//! plus one when the body ends, so a fast link cannot flood the sink. Each
+//! event carries the time since the download started, from which front ends
+//! derive the transfer rate and the time left.
+
+use std::fmt;
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::app::{ProgressEvent, ProgressLevel, ProgressSink};
+
+pub const REPORT_INTERVAL: Duration = Duration::from_millis(250);
+
+const EVENT_PREFIX: &str = "download.progress ";
+
+/// One report: `download.progress <downloaded> <total|-> <file>`, timed by
+/// the event's `elapsed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadProgress {
+    pub file: String,
+    pub downloaded: u64,
+    /// Absent when the server sent no `Content-Length`.
+    pub total: Option<u64>,
+    pub elapsed: Duration,
+}
+
+impl DownloadProgress {
+    pub fn from_event(event: &ProgressEvent) -> Option<Self> {
+        let rest = event.message.strip_prefix(EVENT_PREFIX)?;
+        let mut fields = rest.splitn(3, ' ');
+        let downloaded = fields.next()?.parse().ok()?;
+        let total = match fields.next()? {
+            "-" => None,
+            total => Some(total.parse().ok()?),
+        };
+        Some(Self {
+            file: fields.next()?.to_string(),
+            downloaded,
+            total,
+            elapsed: event.elapsed.unwrap_or_default(),
+        })
+    }
+
+    pub fn to_event(&self) -> ProgressEvent {
+        ProgressEvent {
+            message: self.to_string(),
+            elapsed: Some(self.elapsed),
+            level: ProgressLevel::Request,
+        }
+    }
+
+    /// Share received, from 0 to 1, when the size is known.
+    pub fn fraction(&self) -> Option<f64> {
+        self.total
+            .filter(|total| *total > 0)
+            .map(|total| (self.downloaded as f64 / total as f64).min(1.0))
+    }
+
+    /// Average bytes per second since the download started.
+    pub fn bytes_per_sec(&self) -> Option<u64> {
+        let secs = self.elapsed.as_secs_f64();
+        (secs > 0.0).then(|| (self.downloaded as f64 / secs) as u64)
+    }
+
+    /// Time left at the average rate so far.
+    pub fn eta(&self) -> Option<Duration> {
+        let remaining = self.total?.saturating_sub(self.downloaded);
+        let rate = self.bytes_per_sec().filter(|rate| *rate > 0)?;
+        Some(Duration::from_secs_f64(remaining as f64 / rate as f64))
+    }
+}
+
+impl fmt::Display for DownloadProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{EVENT_PREFIX}{} ", self.downloaded)?;
+        match self.total {
+            Some(total) => write!(f, "{total}")?,
+            None => f.write_str("-")?,
+        }
+        write!(f, " {}", self.file)
+    }
+}
+
+/// A response body that reports its progress while it is read.
+pub struct Metered<'a, R> {
+    inner: R,
+    sink: &'a dyn ProgressSink,
+    progress: DownloadProgress,
+    started: Instant,
+    reported_at: Instant,
+    finished: bool,
+}
+
+impl<'a, R: Read> Metered<'a, R> {
+    /// Reports under `destination`'s file name; `total` is the expected
+    /// size, if the server announced one. The first report, of zero bytes,
+    /// is sent straight away.
+    pub fn new(
+        inner: R,
+        sink: &'a dyn ProgressSink,
+        destination: &Path,
+        total: Option<u64>,
+    ) -> Self {
+        let file = destination
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let now = Instant::now();
+        let metered = Self {
+            inner,
+            sink,
+            progress: DownloadProgress {
+                file,
+                downloaded: 0,
+                total,
+                elapsed: Duration::ZERO,
+            },
+            started: now,
+            reported_at: now,
+            finished: false,
+        };
+        metered.sink.event(metered.progress.to_event());
+        metered
+    }
+
+    fn report(&mut self) {
+        self.reported_at = Instant::now();
+        self.progress.elapsed = self.started.elapsed();
+        self.sink.event(self.progress.to_event());
+    }
+}
+
+impl<R: Read> Read for Metered<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if read == 0 {
+            if !self.finished {
+                self.finished = true;
+                self.report();
+            }
+        } else {
+            self.progress.downloaded += read as u64;
+            if self.reported_at.elapsed() >= REPORT_INTERVAL {
+                self.report();
+            }
+        }
+        Ok(read)
+    }
+}
@@ -0,0 +1,91 @@
+//! The project a command works in.
+//!
+//! A project is a directory holding `kira-bm.json`, with its store in
+//! `.kira-bm` next to it. Run from a subdirectory, kira-bm walks up to the
+//! nearest directory with a `kira-bm.json`, stopping at the root of the
+//! enclosing git repository; with neither above it, the current directory
+//! is the project.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+use crate::error::KiraError;
+
+pub const CONFIG_FILE: &str = "kira-bm.json";
+pub const STORE_DIR: &str = ".kira-bm";
+const REPOSITORY_MARKER: &str = ".git";
+
+static SELECTED: OnceLock<Project> = OnceLock::new();
+
+/// Why a directory was taken as the project root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    /// It holds `kira-bm.json`.
+    Config,
+    /// It is the root of the git repository the search started in, and no
+    /// directory below it has a `kira-bm.json`.
+    Repository,
+    /// Discovery found nothing, or was turned off.
+    WorkingDirectory,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    pub root: PathBuf,
+    pub anchor: Anchor,
+}
+
+impl Project {
+    /// The nearest directory from `start` upwards with a `kira-bm.json`,
+    /// not searching past a `.git` boundary.
+    pub fn discover(start: &Path) -> Self {
+        for dir in start.ancestors() {
+            if dir.join(CONFIG_FILE).is_file() {
+                return Self::anchored(dir, Anchor::Config);
+            }
+            if dir.join(REPOSITORY_MARKER).exists() {
+                return Self::anchored(dir, Anchor::Repository);
+            }
+        }
+        Self::at(start)
+    }
+
+    /// `dir` itself, without discovery.
+    pub fn at(dir: &Path) -> Self {
+        Self::anchored(dir, Anchor::WorkingDirectory)
+    }
+
+    fn anchored(dir: &Path, anchor: Anchor) -> Self {
+        Self {
+            root: dir.to_path_buf(),
+            anchor,
+        }
+    }
+
+    /// The project chosen for this process by [`Project::select`], or else
+    /// the one discovered from the current directory.
+    pub fn current() -> Result<Self, KiraError> {
+        if let Some(project) = SELECTED.get() {
+            return Ok(project.clone());
+        }
+        let cwd = std::env::current_dir().map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        Ok(Self::discover(&cwd))
+    }
+
+    /// Fixes the project every later [`Project::current`] returns. Only the
+    /// first call has an effect.
+    pub fn select(self) {
+        let _ = SELECTED.set(self);
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        self.root.join(CONFIG_FILE)
+    }
+
+    pub fn store_root(&self) -> PathBuf {
+        self.root.join(STORE_DIR)
+    }
+}
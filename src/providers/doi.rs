@@ -1,41 +1,88 @@
 use std::collections::{BTreeMap, BTreeSet};
-use std::time::Duration;
 
 use regex::Regex;
-use reqwest::blocking::Client;
+use reqwest::Url;
+use reqwest::blocking::{Client, RequestBuilder, Response};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tracing::field::Empty;
 
-use crate::domain::{DatasetSpecifier, Doi};
+use crate::domain::{
+    BioProjectAccession, DatasetSpecifier, Doi, ProteinId, SraExperimentAccession,
+};
 use crate::error::KiraError;
+use crate::http::{FailedResponse, HttpSettings, Mirrors, record_response, registry_for_host};
+use crate::store::{ExtractionSource, Provenance};
 
 const CROSSREF_BASE: &str = "https://api.crossref.org";
+const DATACITE_BASE: &str = "https://api.datacite.org";
 const EUTILS_BASE: &str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils";
 const RCSB_BASE: &str = "https://data.rcsb.org/rest/v1/core/entry";
+const RCSB_GRAPHQL: &str = "https://data.rcsb.org/graphql";
 const UNIPROT_BASE: &str = "https://rest.uniprot.org/uniprotkb";
 const NCBI_DATASETS_BASE: &str = "https://api.ncbi.nlm.nih.gov/datasets/v2";
 const ENA_PORTAL_BASE: &str = "https://www.ebi.ac.uk/ena/portal/api";
 const GEO_TEXT_BASE: &str = "https://www.ncbi.nlm.nih.gov/geo/query/acc.cgi";
+const VALIDATION_BATCH_SIZE: usize = 50;
+const ESEARCH_RETMAX: &str = "500";
 
 #[derive(Debug, Clone)]
 pub struct DoiResolver {
     client: Client,
+    /// For the registry lookups that validate and expand the accessions a
+    /// paper cites, which get the short `validation` timeouts.
+    probes: Client,
+    settings: HttpSettings,
+    mirrors: Mirrors,
+    follow_related: bool,
 }
 
 impl DoiResolver {
     pub fn new() -> Result<Self, KiraError> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .user_agent(format!("kira-bm/{}", env!("CARGO_PKG_VERSION")))
-            .build()
-            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
-        Ok(Self { client })
+        Self::with_settings(HttpSettings::from_env())
+    }
+
+    pub fn with_settings(settings: HttpSettings) -> Result<Self, KiraError> {
+        let build = |registry| {
+            settings
+                .client_builder(registry)
+                .user_agent(format!("kira-bm/{}", env!("CARGO_PKG_VERSION")))
+                .build()
+                .map_err(|err| KiraError::CrossrefHttp(err.to_string()))
+        };
+        Ok(Self {
+            client: build("crossref")?,
+            probes: build("validation")?,
+            mirrors: settings.mirrors()?,
+            settings,
+            follow_related: false,
+        })
+    }
+
+    /// Sends Crossref and every registry lookup to `base_url` instead.
+    pub fn with_base_url(settings: HttpSettings, base_url: &str) -> Result<Self, KiraError> {
+        Ok(Self {
+            mirrors: Mirrors::everywhere(base_url),
+            ..Self::with_settings(settings)?
+        })
+    }
+
+    /// Also searches the records of the DOIs a DataCite record declares it
+    /// supplements or references, one level deep.
+    pub fn follow_related(mut self, follow: bool) -> Self {
+        self.follow_related = follow;
+        self
     }
 
     pub fn resolve(&self, doi: &Doi) -> Result<DoiResolution, KiraError> {
         self.resolve_with_progress(doi, |_| {})
     }
 
+    #[tracing::instrument(
+        name = "doi.resolve",
+        skip_all,
+        fields(registry = "crossref", dataset = %doi.as_str())
+    )]
     pub fn resolve_with_progress<F>(
         &self,
         doi: &Doi,
@@ -44,10 +91,30 @@ impl DoiResolver {
     where
         F: FnMut(&str),
     {
-        progress("doi.crossref.start");
-        let crossref = self.fetch_crossref(doi)?;
-        progress("doi.crossref.done");
-        let (source, texts) = collect_source(&crossref);
+        let SourceRecord {
+            agency,
+            mut source,
+            mut texts,
+            mut related,
+        } = self.fetch_record(doi, &mut progress)?;
+        if self.follow_related {
+            for item in &mut related {
+                progress(&format!("doi.related {}", item.doi));
+                let record = item
+                    .doi
+                    .parse::<Doi>()
+                    .and_then(|related| self.fetch_record(&related, &mut progress));
+                match record {
+                    Ok(record) => {
+                        // What a related record names counts as referenced.
+                        source.references.extend(record.texts.iter().cloned());
+                        texts.extend(record.texts);
+                        item.followed = true;
+                    }
+                    Err(err) => progress(&format!("doi.related.failed {}: {err}", item.doi)),
+                }
+            }
+        }
         let extracted = extract_ids(&texts);
         progress(&format!(
             "doi.extract gse={} gsm={} srr={} err={} bioproject={} ena_project={} assembly={} pdb={} uniprot={}",
@@ -63,19 +130,32 @@ impl DoiResolver {
         ));
 
         if extracted.is_empty() {
-            return Err(KiraError::DoiResolution(
-                "DOI resolved successfully, but no supported public dataset identifiers were found"
-                    .to_string(),
-            ));
+            let unfollowed = related.iter().filter(|item| !item.followed).count();
+            let hint = if unfollowed > 0 {
+                format!(
+                    "; the record relates to {unfollowed} other DOI(s), which --follow-related searches too"
+                )
+            } else {
+                String::new()
+            };
+            return Err(KiraError::DoiResolution(format!(
+                "DOI resolved successfully, but no supported public dataset identifiers were found{hint}"
+            )));
         }
 
         let mut validation = ValidationSummary::default();
         let mut hydrated = HydratedSummary::default();
         let mut resolved_targets = BTreeSet::new();
         let mut unresolved = Vec::new();
+        let mut known_runs = BTreeMap::new();
+        let mut known_assemblies = BTreeMap::new();
+
         progress("doi.validate.pdb");
+        let found = self.validate_in_batches("pdb", &extracted.pdb, &mut progress, |ids| {
+            self.lookup_pdb(ids)
+        })?;
         for id in &extracted.pdb {
-            let exists = self.validate_pdb(id)?;
+            let exists = found.contains(id);
             validation.pdb.push(IdStatus::new("pdb", id, exists, None));
             if exists {
                 resolved_targets.insert(ResolvedTarget::new("protein", id));
@@ -85,8 +165,12 @@ impl DoiResolver {
         }
 
         progress("doi.validate.uniprot");
+        let found =
+            self.validate_in_batches("uniprot", &extracted.uniprot, &mut progress, |ids| {
+                self.lookup_uniprot(ids)
+            })?;
         for id in &extracted.uniprot {
-            let exists = self.validate_uniprot(id)?;
+            let exists = found.contains(id);
             validation
                 .uniprot
                 .push(IdStatus::new("uniprot", id, exists, None));
@@ -98,8 +182,9 @@ impl DoiResolver {
         }
 
         progress("doi.validate.assembly");
+        self.validate_assemblies(&extracted.assemblies, &mut known_assemblies, &mut progress)?;
         for id in &extracted.assemblies {
-            let exists = self.validate_assembly(id)?;
+            let exists = known_assemblies[id];
             validation
                 .assemblies
                 .push(IdStatus::new("assembly", id, exists, None));
@@ -111,8 +196,9 @@ impl DoiResolver {
         }
 
         progress("doi.validate.srr");
+        self.validate_runs("srr", &extracted.sra_runs, &mut known_runs, &mut progress)?;
         for id in &extracted.sra_runs {
-            let exists = self.validate_sra_run(id)?;
+            let exists = known_runs[id];
             validation
                 .sra_runs
                 .push(IdStatus::new("srr", id, exists, None));
@@ -124,8 +210,9 @@ impl DoiResolver {
         }
 
         progress("doi.validate.err");
+        self.validate_runs("err", &extracted.ena_runs, &mut known_runs, &mut progress)?;
         for id in &extracted.ena_runs {
-            let exists = self.validate_sra_run(id)?;
+            let exists = known_runs[id];
             validation
                 .ena_runs
                 .push(IdStatus::new("err", id, exists, None));
@@ -137,8 +224,13 @@ impl DoiResolver {
         }
 
         progress("doi.hydrate.geo_series");
+        let found =
+            self.validate_in_batches("gse", &extracted.geo_series, &mut progress, |ids| {
+                self.lookup_geo(ids)
+            })?;
+        let mut series = Vec::new();
         for gse in &extracted.geo_series {
-            let exists = self.validate_geo(gse)?;
+            let exists = found.contains(gse);
             validation
                 .geo_series
                 .push(IdStatus::new("gse", gse, exists, None));
@@ -146,30 +238,8 @@ impl DoiResolver {
                 unresolved.push(UnresolvedId::new("gse", gse, "not found"));
                 continue;
             }
-
             match self.hydrate_geo_series(gse) {
-                Ok(geo) => {
-                    for gsm in &geo.gsm {
-                        validation
-                            .geo_samples
-                            .push(IdStatus::new("gsm", gsm, true, None));
-                    }
-                    for run in &geo.srr {
-                        let exists = self.validate_sra_run(run)?;
-                        validation.sra_runs.push(IdStatus::new(
-                            "srr",
-                            run,
-                            exists,
-                            Some("from gse".to_string()),
-                        ));
-                        if exists {
-                            resolved_targets.insert(ResolvedTarget::new("srr", run));
-                        } else {
-                            unresolved.push(UnresolvedId::new("srr", run, "not found"));
-                        }
-                    }
-                    hydrated.geo.push(geo);
-                }
+                Ok(geo) => series.push(geo),
                 Err(err) => {
                     unresolved.push(UnresolvedId::new(
                         "gse",
@@ -179,10 +249,42 @@ impl DoiResolver {
                 }
             }
         }
+        let runs = series
+            .iter()
+            .flat_map(|geo| geo.srr.iter().cloned())
+            .collect::<Vec<_>>();
+        self.validate_runs("srr", &runs, &mut known_runs, &mut progress)?;
+        for geo in series {
+            for gsm in &geo.gsm {
+                validation
+                    .geo_samples
+                    .push(IdStatus::new("gsm", gsm, true, None));
+            }
+            for run in &geo.srr {
+                let exists = known_runs[run];
+                validation.sra_runs.push(IdStatus::new(
+                    "srr",
+                    run,
+                    exists,
+                    Some("from gse".to_string()),
+                ));
+                if exists {
+                    resolved_targets.insert(ResolvedTarget::new("srr", run));
+                } else {
+                    unresolved.push(UnresolvedId::new("srr", run, "not found"));
+                }
+            }
+            hydrated.geo.push(geo);
+        }
 
         progress("doi.hydrate.geo_samples");
+        let found =
+            self.validate_in_batches("gsm", &extracted.geo_samples, &mut progress, |ids| {
+                self.lookup_geo(ids)
+            })?;
+        let mut samples = Vec::new();
         for gsm in &extracted.geo_samples {
-            let exists = self.validate_geo(gsm)?;
+            let exists = found.contains(gsm);
             validation
                 .geo_samples
                 .push(IdStatus::new("gsm", gsm, exists, None));
@@ -191,22 +293,7 @@ impl DoiResolver {
                 continue;
             }
             match self.hydrate_geo_sample(gsm) {
-                Ok(runs) => {
-                    for run in runs {
-                        let exists = self.validate_sra_run(&run)?;
-                        validation.sra_runs.push(IdStatus::new(
-                            "srr",
-                            &run,
-                            exists,
-                            Some("from gsm".to_string()),
-                        ));
-                        if exists {
-                            resolved_targets.insert(ResolvedTarget::new("srr", &run));
-                        } else {
-                            unresolved.push(UnresolvedId::new("srr", &run, "not found"));
-                        }
-                    }
-                }
+                Ok(runs) => samples.push(runs),
                 Err(err) => {
                     unresolved.push(UnresolvedId::new(
                         "gsm",
@@ -216,52 +303,39 @@ impl DoiResolver {
                 }
             }
         }
+        let runs = samples.concat();
+        self.validate_runs("srr", &runs, &mut known_runs, &mut progress)?;
+        for run in &runs {
+            let exists = known_runs[run];
+            validation.sra_runs.push(IdStatus::new(
+                "srr",
+                run,
+                exists,
+                Some("from gsm".to_string()),
+            ));
+            if exists {
+                resolved_targets.insert(ResolvedTarget::new("srr", run));
+            } else {
+                unresolved.push(UnresolvedId::new("srr", run, "not found"));
+            }
+        }
 
         progress("doi.hydrate.bioproject");
+        let project_uids = self.lookup_bioprojects(&extracted.bioprojects, &mut progress)?;
+        let mut projects = Vec::new();
         for project in &extracted.bioprojects {
-            let ids = self.esearch_ids("bioproject", &format!("{project}[Accession]"))?;
-            if ids.is_empty() {
+            let Some(ids) = project_uids.get(project) else {
                 validation
                     .bioprojects
                     .push(IdStatus::new("bioproject", project, false, None));
                 unresolved.push(UnresolvedId::new("bioproject", project, "not found"));
                 continue;
-            }
+            };
             validation
                 .bioprojects
                 .push(IdStatus::new("bioproject", project, true, None));
-            match self.hydrate_bioproject(project, &ids) {
-                Ok(item) => {
-                    for run in &item.srr {
-                        let exists = self.validate_sra_run(run)?;
-                        validation.sra_runs.push(IdStatus::new(
-                            "srr",
-                            run,
-                            exists,
-                            Some("from bioproject".to_string()),
-                        ));
-                        if exists {
-                            resolved_targets.insert(ResolvedTarget::new("srr", run));
-                        } else {
-                            unresolved.push(UnresolvedId::new("srr", run, "not found"));
-                        }
-                    }
-                    for acc in &item.assemblies {
-                        let exists = self.validate_assembly(acc)?;
-                        validation.assemblies.push(IdStatus::new(
-                            "assembly",
-                            acc,
-                            exists,
-                            Some("from bioproject".to_string()),
-                        ));
-                        if exists {
-                            resolved_targets.insert(ResolvedTarget::new("genome", acc));
-                        } else {
-                            unresolved.push(UnresolvedId::new("assembly", acc, "not found"));
-                        }
-                    }
-                    hydrated.bioprojects.push(item);
-                }
+            match self.hydrate_bioproject(project, ids) {
+                Ok(item) => projects.push(item),
                 Err(err) => {
                     unresolved.push(UnresolvedId::new(
                         "bioproject",
@@ -271,8 +345,50 @@ impl DoiResolver {
                 }
             }
         }
+        let runs = projects
+            .iter()
+            .flat_map(|item| item.srr.iter().cloned())
+            .collect::<Vec<_>>();
+        self.validate_runs("srr", &runs, &mut known_runs, &mut progress)?;
+        let assemblies = projects
+            .iter()
+            .flat_map(|item| item.assemblies.iter().cloned())
+            .collect::<Vec<_>>();
+        self.validate_assemblies(&assemblies, &mut known_assemblies, &mut progress)?;
+        for item in projects {
+            for run in &item.srr {
+                let exists = known_runs[run];
+                validation.sra_runs.push(IdStatus::new(
+                    "srr",
+                    run,
+                    exists,
+                    Some("from bioproject".to_string()),
+                ));
+                if exists {
+                    resolved_targets.insert(ResolvedTarget::new("srr", run));
+                } else {
+                    unresolved.push(UnresolvedId::new("srr", run, "not found"));
+                }
+            }
+            for acc in &item.assemblies {
+                let exists = known_assemblies[acc];
+                validation.assemblies.push(IdStatus::new(
+                    "assembly",
+                    acc,
+                    exists,
+                    Some("from bioproject".to_string()),
+                ));
+                if exists {
+                    resolved_targets.insert(ResolvedTarget::new("genome", acc));
+                } else {
+                    unresolved.push(UnresolvedId::new("assembly", acc, "not found"));
+                }
+            }
+            hydrated.bioprojects.push(item);
+        }
 
         progress("doi.hydrate.ena_project");
+        let mut ena_projects = Vec::new();
         for project in &extracted.ena_projects {
             match self.hydrate_ena_project(project) {
                 Ok(item) => {
@@ -289,21 +405,7 @@ impl DoiResolver {
                     validation
                         .ena_projects
                         .push(IdStatus::new("ena_project", project, true, None));
-                    for run in &item.runs {
-                        let exists = self.validate_sra_run(run)?;
-                        validation.ena_runs.push(IdStatus::new(
-                            "err",
-                            run,
-                            exists,
-                            Some("from ena_project".to_string()),
-                        ));
-                        if exists {
-                            resolved_targets.insert(ResolvedTarget::new("srr", run));
-                        } else {
-                            unresolved.push(UnresolvedId::new("err", run, "not found"));
-                        }
-                    }
-                    hydrated.ena_projects.push(item);
+                    ena_projects.push(item);
                 }
                 Err(err) => {
                     unresolved.push(UnresolvedId::new(
@@ -314,6 +416,28 @@ impl DoiResolver {
                 }
             }
         }
+        let runs = ena_projects
+            .iter()
+            .flat_map(|item| item.runs.iter().cloned())
+            .collect::<Vec<_>>();
+        self.validate_runs("err", &runs, &mut known_runs, &mut progress)?;
+        for item in ena_projects {
+            for run in &item.runs {
+                let exists = known_runs[run];
+                validation.ena_runs.push(IdStatus::new(
+                    "err",
+                    run,
+                    exists,
+                    Some("from ena_project".to_string()),
+                ));
+                if exists {
+                    resolved_targets.insert(ResolvedTarget::new("srr", run));
+                } else {
+                    unresolved.push(UnresolvedId::new("err", run, "not found"));
+                }
+            }
+            hydrated.ena_projects.push(item);
+        }
 
         progress("doi.done");
         Ok(DoiResolution {
@@ -324,25 +448,177 @@ impl DoiResolver {
             hydrated,
             resolved_targets: resolved_targets.into_iter().collect(),
             unresolved,
+            resolved_at: Some(chrono::Utc::now().to_rfc3339()),
+            agency: Some(agency),
+            related,
+        })
+    }
+
+    #[tracing::instrument(
+        name = "doi.hydrate_project",
+        skip_all,
+        fields(registry = "validation", dataset = %acc)
+    )]
+    pub fn hydrate_project<F>(
+        &self,
+        acc: &BioProjectAccession,
+        mut progress: F,
+    ) -> Result<HydratedBioProject, KiraError>
+    where
+        F: FnMut(&str),
+    {
+        if acc.is_ena() {
+            progress("bioproject.hydrate ena");
+            let item = self.hydrate_ena_project(acc.as_str())?;
+            if item.runs.is_empty() {
+                return Err(KiraError::DatasetNotFound(format!("bioproject:{acc}")));
+            }
+            return Ok(HydratedBioProject {
+                bioproject: item.ena_project,
+                srr: item.runs,
+                assemblies: Vec::new(),
+            });
+        }
+        progress("bioproject.hydrate ncbi");
+        let ids = vec![acc.as_str().to_string()];
+        let uids = self.lookup_bioprojects(&ids, &mut progress)?;
+        let Some(uids) = uids.get(acc.as_str()) else {
+            return Err(KiraError::DatasetNotFound(format!("bioproject:{acc}")));
+        };
+        self.hydrate_bioproject(acc.as_str(), uids)
+    }
+
+    /// The runs of an SRA experiment, found through E-utilities.
+    #[tracing::instrument(
+        name = "doi.experiment_runs",
+        skip_all,
+        fields(registry = "validation", dataset = %acc)
+    )]
+    pub fn experiment_runs<F>(
+        &self,
+        acc: &SraExperimentAccession,
+        mut progress: F,
+    ) -> Result<Vec<String>, KiraError>
+    where
+        F: FnMut(&str),
+    {
+        progress("srx.hydrate ncbi");
+        let runs = self.sra_runs_from_experiments(&[acc.as_str().to_string()])?;
+        if runs.is_empty() {
+            return Err(KiraError::DatasetNotFound(format!("srx:{acc}")));
+        }
+        Ok(runs)
+    }
+
+    /// Each request gets its own span under the resolution or hydration
+    /// that issued it.
+    #[tracing::instrument(
+        name = "doi.request",
+        skip_all,
+        fields(registry = Empty, endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    fn send(&self, request: RequestBuilder) -> Result<Response, KiraError> {
+        let mut request = request
+            .build()
+            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
+        self.settings.authorize(request.url_mut());
+        let (client, registry) = match request.url().host_str().map(registry_for_host) {
+            Some(registry @ ("crossref" | "datacite")) => (&self.client, registry),
+            _ => (&self.probes, "validation"),
+        };
+        // Mirrors share the rate limit of the registry they stand in for.
+        let limiter = self.settings.limiter_for_url(request.url());
+        if !self.mirrors.is_empty() {
+            *request.url_mut() = Url::parse(&self.mirrors.rewrite(request.url().as_str()))
+                .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
+        }
+        tracing::Span::current().record("registry", registry);
+        *request.timeout_mut() = Some(self.settings.timeouts_for(registry).request);
+        limiter.acquire();
+        let response = client
+            .execute(request)
+            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
+        record_response(&response);
+        Ok(response)
+    }
+
+    /// The DOI's record from Crossref or, for DOIs Crossref does not know
+    /// (most data deposits), from DataCite.
+    fn fetch_record<F>(&self, doi: &Doi, progress: &mut F) -> Result<SourceRecord, KiraError>
+    where
+        F: FnMut(&str),
+    {
+        progress("doi.crossref.start");
+        let not_in_crossref = match self.fetch_crossref(doi) {
+            Ok(message) => {
+                progress("doi.crossref.done");
+                let (source, texts) = collect_source(&message);
+                return Ok(SourceRecord {
+                    agency: DoiAgency::Crossref,
+                    source,
+                    texts,
+                    related: Vec::new(),
+                });
+            }
+            Err(err @ KiraError::CrossrefStatus { status: 404, .. }) => err,
+            Err(err) => return Err(err),
+        };
+        progress("doi.datacite.start");
+        let attributes = match self.fetch_datacite(doi) {
+            Ok(attributes) => attributes,
+            Err(KiraError::DataciteStatus { status: 404, .. }) => return Err(not_in_crossref),
+            Err(err) => return Err(err),
+        };
+        progress("doi.datacite.done");
+        let (source, texts, related) = collect_datacite_source(&attributes);
+        Ok(SourceRecord {
+            agency: DoiAgency::Datacite,
+            source,
+            texts,
+            related,
         })
     }
+
+    fn fetch_datacite(&self, doi: &Doi) -> Result<DataciteAttributes, KiraError> {
+        let url = format!(
+            "{}/dois/{}",
+            DATACITE_BASE,
+            encode_url_component(doi.as_str())
+        );
+        let response = self.send(self.client.get(&url)).map_err(|err| match err {
+            KiraError::CrossrefHttp(message) => KiraError::DataciteHttp(message),
+            other => other,
+        })?;
+        if !response.status().is_success() {
+            let failed = FailedResponse::read(response);
+            return Err(KiraError::DataciteStatus {
+                status: failed.status,
+                url: failed.url,
+                dataset: format!("doi:{}", doi.as_str()),
+                message: failed.message,
+            });
+        }
+        let payload: DataciteResponse = response
+            .json()
+            .map_err(|err| KiraError::DataciteHttp(err.to_string()))?;
+        Ok(payload.data.attributes)
+    }
+
     fn fetch_crossref(&self, doi: &Doi) -> Result<CrossrefMessage, KiraError> {
         let url = format!(
             "{}/works/{}",
             CROSSREF_BASE,
             encode_url_component(doi.as_str())
         );
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
+        let response = self.send(self.client.get(&url))?;
         if !response.status().is_success() {
-            let status = response.status().as_u16();
-            let message = response
-                .text()
-                .unwrap_or_else(|_| "Crossref request failed".to_string());
-            return Err(KiraError::CrossrefStatus { status, message });
+            let failed = FailedResponse::read(response);
+            return Err(KiraError::CrossrefStatus {
+                status: failed.status,
+                url: failed.url,
+                dataset: format!("doi:{}", doi.as_str()),
+                message: failed.message,
+            });
         }
         let payload: CrossrefResponse = response
             .json()
@@ -350,23 +626,224 @@ impl DoiResolver {
         Ok(payload.message)
     }
 
+    fn validate_in_batches<F, L>(
+        &self,
+        kind: &str,
+        ids: &[String],
+        progress: &mut F,
+        lookup: L,
+    ) -> Result<BTreeSet<String>, KiraError>
+    where
+        F: FnMut(&str),
+        L: Fn(&[String]) -> Result<BTreeSet<String>, KiraError>,
+    {
+        let mut found = BTreeSet::new();
+        let mut done = 0;
+        for chunk in ids.chunks(VALIDATION_BATCH_SIZE) {
+            found.extend(lookup(chunk)?);
+            done += chunk.len();
+            progress(&format!(
+                "doi.validate.{kind} validated {done}/{}",
+                ids.len()
+            ));
+        }
+        Ok(found)
+    }
+
+    fn validate_runs<F>(
+        &self,
+        kind: &str,
+        ids: &[String],
+        known: &mut BTreeMap<String, bool>,
+        progress: &mut F,
+    ) -> Result<(), KiraError>
+    where
+        F: FnMut(&str),
+    {
+        let pending = pending_ids(ids, known);
+        let found = self.validate_in_batches(kind, &pending, progress, |chunk| {
+            self.lookup_sra_runs(chunk)
+        })?;
+        for id in pending {
+            let exists = found.contains(&id);
+            known.insert(id, exists);
+        }
+        Ok(())
+    }
+
+    fn validate_assemblies<F>(
+        &self,
+        ids: &[String],
+        known: &mut BTreeMap<String, bool>,
+        progress: &mut F,
+    ) -> Result<(), KiraError>
+    where
+        F: FnMut(&str),
+    {
+        let pending = pending_ids(ids, known);
+        let found = self.validate_in_batches("assembly", &pending, progress, |chunk| {
+            self.lookup_assemblies(chunk)
+        })?;
+        for id in pending {
+            let exists = found.contains(&id);
+            known.insert(id, exists);
+        }
+        Ok(())
+    }
+
+    fn lookup_pdb(&self, ids: &[String]) -> Result<BTreeSet<String>, KiraError> {
+        let body = serde_json::json!({
+            "query": "query($ids: [String!]!) { entries(entry_ids: $ids) { rcsb_id } }",
+            "variables": { "ids": ids },
+        });
+        let response = self.send(self.client.post(RCSB_GRAPHQL).json(&body))?;
+        if !response.status().is_success() {
+            return self.lookup_each(ids, |id| self.validate_pdb(id));
+        }
+        let payload: Value = response
+            .json()
+            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
+        let mut found = BTreeSet::new();
+        if let Some(entries) = payload["data"]["entries"].as_array() {
+            for entry in entries {
+                if let Some(id) = entry["rcsb_id"].as_str() {
+                    found.insert(
+                        id.parse::<ProteinId>()
+                            .map_or_else(|_| id.to_uppercase(), |id| id.as_str().to_string()),
+                    );
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    fn lookup_uniprot(&self, ids: &[String]) -> Result<BTreeSet<String>, KiraError> {
+        let accessions = ids.join(",");
+        let size = ids.len().to_string();
+        let response = self.send(self.client.get(build_query_url(
+            &format!("{UNIPROT_BASE}/accessions"),
+            &[
+                ("accessions", accessions.as_str()),
+                ("fields", "accession"),
+                ("format", "json"),
+                ("size", size.as_str()),
+            ],
+        )))?;
+        if !response.status().is_success() {
+            return self.lookup_each(ids, |id| self.validate_uniprot(id));
+        }
+        let payload: Value = response
+            .json()
+            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
+        let mut found = BTreeSet::new();
+        if let Some(results) = payload["results"].as_array() {
+            for result in results {
+                if let Some(acc) = result["primaryAccession"].as_str() {
+                    found.insert(acc.to_string());
+                }
+                if let Some(secondary) = result["secondaryAccessions"].as_array() {
+                    found.extend(
+                        secondary
+                            .iter()
+                            .filter_map(|v| v.as_str().map(String::from)),
+                    );
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    fn lookup_assemblies(&self, ids: &[String]) -> Result<BTreeSet<String>, KiraError> {
+        let accessions = ids
+            .iter()
+            .map(|id| encode_url_component(id))
+            .collect::<Vec<_>>()
+            .join(",");
+        let page_size = ids.len().to_string();
+        let response = self.send(self.client.get(build_query_url(
+            &format!("{NCBI_DATASETS_BASE}/genome/accession/{accessions}/dataset_report"),
+            &[("page_size", page_size.as_str())],
+        )))?;
+        if !response.status().is_success() {
+            return self.lookup_each(ids, |id| self.validate_assembly(id));
+        }
+        let payload: Value = response
+            .json()
+            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
+        let mut found = BTreeSet::new();
+        if let Some(reports) = payload["reports"].as_array() {
+            for report in reports {
+                for key in ["accession", "current_accession", "paired_accession"] {
+                    if let Some(acc) = report[key].as_str() {
+                        found.insert(acc.to_string());
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    fn lookup_sra_runs(&self, ids: &[String]) -> Result<BTreeSet<String>, KiraError> {
+        let uids = self.esearch_ids("sra", &accession_term(ids))?;
+        Ok(self.esummary_sra_runs(&uids)?.into_iter().collect())
+    }
+
+    fn lookup_geo(&self, ids: &[String]) -> Result<BTreeSet<String>, KiraError> {
+        let uids = self.esearch_ids("gds", &accession_term(ids))?;
+        let found = self
+            .esummary_field("gds", &uids, "accession")?
+            .into_iter()
+            .map(|(_, acc)| acc)
+            .collect();
+        Ok(found)
+    }
+
+    fn lookup_bioprojects<F>(
+        &self,
+        ids: &[String],
+        progress: &mut F,
+    ) -> Result<BTreeMap<String, Vec<String>>, KiraError>
+    where
+        F: FnMut(&str),
+    {
+        let mut output: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut done = 0;
+        for chunk in ids.chunks(VALIDATION_BATCH_SIZE) {
+            let uids = self.esearch_ids("bioproject", &accession_term(chunk))?;
+            for (uid, acc) in self.esummary_field("bioproject", &uids, "project_acc")? {
+                output.entry(acc).or_default().push(uid);
+            }
+            done += chunk.len();
+            progress(&format!(
+                "doi.validate.bioproject validated {done}/{}",
+                ids.len()
+            ));
+        }
+        Ok(output)
+    }
+
+    fn lookup_each<L>(&self, ids: &[String], lookup: L) -> Result<BTreeSet<String>, KiraError>
+    where
+        L: Fn(&str) -> Result<bool, KiraError>,
+    {
+        let mut found = BTreeSet::new();
+        for id in ids {
+            if lookup(id)? {
+                found.insert(id.clone());
+            }
+        }
+        Ok(found)
+    }
+
     fn validate_pdb(&self, id: &str) -> Result<bool, KiraError> {
         let url = format!("{}/{}", RCSB_BASE, id);
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
+        let response = self.send(self.client.get(&url))?;
         Ok(response.status().is_success())
     }
 
     fn validate_uniprot(&self, id: &str) -> Result<bool, KiraError> {
         let url = format!("{}/{}.json", UNIPROT_BASE, id);
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
+        let response = self.send(self.client.get(&url))?;
         Ok(response.status().is_success())
     }
 
@@ -375,39 +852,20 @@ impl DoiResolver {
             "{}/genome/accession/{}/dataset_report",
             NCBI_DATASETS_BASE, acc
         );
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
+        let response = self.send(self.client.get(&url))?;
         Ok(response.status().is_success())
     }
 
-    fn validate_sra_run(&self, acc: &str) -> Result<bool, KiraError> {
-        let ids = self.esearch_ids("sra", &format!("{acc}[Accession]"))?;
-        Ok(!ids.is_empty())
-    }
-
-    fn validate_geo(&self, acc: &str) -> Result<bool, KiraError> {
-        let ids = self.esearch_ids("gds", &format!("{acc}[Accession]"))?;
-        Ok(!ids.is_empty())
-    }
-
     fn hydrate_geo_series(&self, gse: &str) -> Result<HydratedGeo, KiraError> {
         let text = self.fetch_geo_text(gse)?;
         let gsm = extract_matches(&text, &Regex::new(r"GSM\d+").unwrap());
-        let mut runs = BTreeSet::new();
-        for sample in &gsm {
-            for run in self.hydrate_geo_sample(sample)? {
-                runs.insert(run);
-            }
-        }
+        let mut runs = self.sample_runs(&gsm)?.into_iter().collect::<BTreeSet<_>>();
 
-        if runs.is_empty() {
-            if let Ok(runs_from_links) = self.sra_runs_from_gds(gse) {
-                for run in runs_from_links {
-                    runs.insert(run);
-                }
+        if runs.is_empty()
+            && let Ok(runs_from_links) = self.sra_runs_from_gds(gse)
+        {
+            for run in runs_from_links {
+                runs.insert(run);
             }
         }
 
@@ -419,13 +877,23 @@ impl DoiResolver {
     }
 
     fn hydrate_geo_sample(&self, gsm: &str) -> Result<Vec<String>, KiraError> {
-        let text = self.fetch_geo_text(gsm)?;
-        let mut runs = extract_matches(&text, &Regex::new(r"(SRR\d+|ERR\d+)").unwrap());
-        let srx = extract_matches(&text, &Regex::new(r"SRX\d+").unwrap());
-        for accession in srx {
-            for run in self.sra_runs_from_srx(&accession)? {
-                runs.push(run);
-            }
+        self.sample_runs(&[gsm.to_string()])
+    }
+
+    fn sample_runs(&self, samples: &[String]) -> Result<Vec<String>, KiraError> {
+        let run_re = Regex::new(r"(SRR\d+|ERR\d+)").unwrap();
+        let srx_re = Regex::new(r"SRX\d+").unwrap();
+        let mut runs = Vec::new();
+        let mut experiments = Vec::new();
+        for sample in samples {
+            let text = self.fetch_geo_text(sample)?;
+            runs.extend(extract_matches(&text, &run_re));
+            experiments.extend(extract_matches(&text, &srx_re));
+        }
+        experiments.sort();
+        experiments.dedup();
+        for chunk in experiments.chunks(VALIDATION_BATCH_SIZE) {
+            runs.extend(self.sra_runs_from_experiments(chunk)?);
         }
         runs.sort();
         runs.dedup();
@@ -441,8 +909,8 @@ impl DoiResolver {
         self.esummary_sra_runs(&sra_ids)
     }
 
-    fn sra_runs_from_srx(&self, srx: &str) -> Result<Vec<String>, KiraError> {
-        let ids = self.esearch_ids("sra", &format!("{srx}[Accession]"))?;
+    fn sra_runs_from_experiments(&self, experiments: &[String]) -> Result<Vec<String>, KiraError> {
+        let ids = self.esearch_ids("sra", &accession_term(experiments))?;
         self.esummary_sra_runs(&ids)
     }
 
@@ -454,7 +922,13 @@ impl DoiResolver {
         let sra_ids = self.elink_ids("bioproject", "sra", ids)?;
         let assembly_ids = self.elink_ids("bioproject", "assembly", ids)?;
         let srr = self.esummary_sra_runs(&sra_ids)?;
-        let assemblies = self.esummary_assembly_accessions(&assembly_ids)?;
+        let mut assemblies = self
+            .esummary_field("assembly", &assembly_ids, "assemblyaccession")?
+            .into_iter()
+            .map(|(_, acc)| acc)
+            .collect::<Vec<_>>();
+        assemblies.sort();
+        assemblies.dedup();
         Ok(HydratedBioProject {
             bioproject: acc.to_string(),
             srr,
@@ -463,19 +937,15 @@ impl DoiResolver {
     }
 
     fn hydrate_ena_project(&self, acc: &str) -> Result<HydratedEnaProject, KiraError> {
-        let response = self
-            .client
-            .get(&build_query_url(
-                &format!("{ENA_PORTAL_BASE}/filereport"),
-                &[
-                    ("accession", acc),
-                    ("result", "read_run"),
-                    ("fields", "run_accession"),
-                    ("format", "tsv"),
-                ],
-            ))
-            .send()
-            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
+        let response = self.send(self.client.get(build_query_url(
+            &format!("{ENA_PORTAL_BASE}/filereport"),
+            &[
+                ("accession", acc),
+                ("result", "read_run"),
+                ("fields", "run_accession"),
+                ("format", "tsv"),
+            ],
+        )))?;
         if !response.status().is_success() {
             return Err(KiraError::CrossrefHttp(format!(
                 "ENA portal returned status {}",
@@ -502,19 +972,15 @@ impl DoiResolver {
     }
 
     fn fetch_geo_text(&self, acc: &str) -> Result<String, KiraError> {
-        let response = self
-            .client
-            .get(&build_query_url(
-                GEO_TEXT_BASE,
-                &[
-                    ("acc", acc),
-                    ("targ", "self"),
-                    ("form", "text"),
-                    ("view", "quick"),
-                ],
-            ))
-            .send()
-            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
+        let response = self.send(self.client.get(build_query_url(
+            GEO_TEXT_BASE,
+            &[
+                ("acc", acc),
+                ("targ", "self"),
+                ("form", "text"),
+                ("view", "quick"),
+            ],
+        )))?;
         if !response.status().is_success() {
             return Err(KiraError::CrossrefHttp(format!(
                 "GEO returned status {}",
@@ -527,14 +993,15 @@ impl DoiResolver {
     }
 
     fn esearch_ids(&self, db: &str, term: &str) -> Result<Vec<String>, KiraError> {
-        let response = self
-            .client
-            .get(&build_query_url(
-                &format!("{EUTILS_BASE}/esearch.fcgi"),
-                &[("db", db), ("term", term), ("retmode", "json")],
-            ))
-            .send()
-            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
+        let response = self.send(self.client.get(build_query_url(
+            &format!("{EUTILS_BASE}/esearch.fcgi"),
+            &[
+                ("db", db),
+                ("term", term),
+                ("retmax", ESEARCH_RETMAX),
+                ("retmode", "json"),
+            ],
+        )))?;
         if !response.status().is_success() {
             return Ok(Vec::new());
         }
@@ -557,19 +1024,15 @@ impl DoiResolver {
             return Ok(Vec::new());
         }
         let id_list = ids.join(",");
-        let response = self
-            .client
-            .get(&build_query_url(
-                &format!("{EUTILS_BASE}/elink.fcgi"),
-                &[
-                    ("dbfrom", dbfrom),
-                    ("db", db),
-                    ("id", id_list.as_str()),
-                    ("retmode", "json"),
-                ],
-            ))
-            .send()
-            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
+        let response = self.send(self.client.get(build_query_url(
+            &format!("{EUTILS_BASE}/elink.fcgi"),
+            &[
+                ("dbfrom", dbfrom),
+                ("db", db),
+                ("id", id_list.as_str()),
+                ("retmode", "json"),
+            ],
+        )))?;
         if !response.status().is_success() {
             return Ok(Vec::new());
         }
@@ -599,35 +1062,38 @@ impl DoiResolver {
         Ok(output)
     }
 
-    fn esummary_sra_runs(&self, ids: &[String]) -> Result<Vec<String>, KiraError> {
+    fn esummary(&self, db: &str, ids: &[String]) -> Result<Option<Value>, KiraError> {
         if ids.is_empty() {
-            return Ok(Vec::new());
+            return Ok(None);
         }
         let id_list = ids.join(",");
-        let response = self
-            .client
-            .get(&build_query_url(
-                &format!("{EUTILS_BASE}/esummary.fcgi"),
-                &[("db", "sra"), ("id", id_list.as_str()), ("retmode", "json")],
-            ))
-            .send()
-            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
+        let response = self.send(self.client.get(build_query_url(
+            &format!("{EUTILS_BASE}/esummary.fcgi"),
+            &[("db", db), ("id", id_list.as_str()), ("retmode", "json")],
+        )))?;
         if !response.status().is_success() {
-            return Ok(Vec::new());
+            return Ok(None);
         }
         let payload: Value = response
             .json()
             .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
+        Ok(Some(payload))
+    }
+
+    fn esummary_sra_runs(&self, ids: &[String]) -> Result<Vec<String>, KiraError> {
+        let Some(payload) = self.esummary("sra", ids)? else {
+            return Ok(Vec::new());
+        };
         let mut runs = Vec::new();
-        let run_re = Regex::new(r#"acc=\"(SRR\d+|ERR\d+)\""#).unwrap();
+        let run_re = Regex::new(r#"acc=\"([SED]RR\d+)\""#).unwrap();
         if let Some(uids) = payload["result"]["uids"].as_array() {
             for uid in uids {
-                if let Some(uid) = uid.as_str() {
-                    if let Some(runs_xml) = payload["result"][uid]["runs"].as_str() {
-                        for cap in run_re.captures_iter(runs_xml) {
-                            if let Some(m) = cap.get(1) {
-                                runs.push(m.as_str().to_string());
-                            }
+                if let Some(uid) = uid.as_str()
+                    && let Some(runs_xml) = payload["result"][uid]["runs"].as_str()
+                {
+                    for cap in run_re.captures_iter(runs_xml) {
+                        if let Some(m) = cap.get(1) {
+                            runs.push(m.as_str().to_string());
                         }
                     }
                 }
@@ -638,44 +1104,29 @@ impl DoiResolver {
         Ok(runs)
     }
 
-    fn esummary_assembly_accessions(&self, ids: &[String]) -> Result<Vec<String>, KiraError> {
-        if ids.is_empty() {
-            return Ok(Vec::new());
-        }
-        let id_list = ids.join(",");
-        let response = self
-            .client
-            .get(&build_query_url(
-                &format!("{EUTILS_BASE}/esummary.fcgi"),
-                &[
-                    ("db", "assembly"),
-                    ("id", id_list.as_str()),
-                    ("retmode", "json"),
-                ],
-            ))
-            .send()
-            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
-        if !response.status().is_success() {
+    fn esummary_field(
+        &self,
+        db: &str,
+        ids: &[String],
+        field: &str,
+    ) -> Result<Vec<(String, String)>, KiraError> {
+        let Some(payload) = self.esummary(db, ids)? else {
             return Ok(Vec::new());
-        }
-        let payload: Value = response
-            .json()
-            .map_err(|err| KiraError::CrossrefHttp(err.to_string()))?;
-        let mut accs = Vec::new();
+        };
+        let mut values = Vec::new();
         if let Some(uids) = payload["result"]["uids"].as_array() {
             for uid in uids {
-                if let Some(uid) = uid.as_str() {
-                    if let Some(acc) = payload["result"][uid]["assemblyaccession"].as_str() {
-                        accs.push(acc.to_string());
-                    }
+                if let Some(uid) = uid.as_str()
+                    && let Some(value) = payload["result"][uid][field].as_str()
+                {
+                    values.push((uid.to_string(), value.to_string()));
                 }
             }
         }
-        accs.sort();
-        accs.dedup();
-        Ok(accs)
+        Ok(values)
     }
 }
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DoiResolution {
     pub doi: String,
@@ -685,9 +1136,92 @@ pub struct DoiResolution {
     pub hydrated: HydratedSummary,
     pub resolved_targets: Vec<ResolvedTarget>,
     pub unresolved: Vec<UnresolvedId>,
+    /// Missing from resolutions written before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<String>,
+    /// The registration agency whose record was read; missing from
+    /// resolutions written before DataCite was consulted, which all came
+    /// from Crossref.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agency: Option<DoiAgency>,
+    /// DOIs the record says it supplements or references.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<RelatedDoi>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DoiAgency {
+    Crossref,
+    Datacite,
+}
+
+impl DoiAgency {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Crossref => "crossref",
+            Self::Datacite => "datacite",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelatedDoi {
+    pub doi: String,
+    /// The DataCite relation type, `IsSupplementTo` or `References`.
+    pub relation: String,
+    /// Whether its record was searched (`--follow-related`).
+    pub followed: bool,
 }
 
 impl DoiResolution {
+    /// How the paper led to `target`: the section naming it, or the series
+    /// or project it was expanded from.
+    pub fn provenance(&self, target: &ResolvedTarget) -> Provenance {
+        let (extracted_from, via) = match self.source.mentions(&target.id) {
+            Some(section) => (section, None),
+            None => match self.expanded_from(&target.id) {
+                Some(parent) => (
+                    self.source
+                        .mentions(parent)
+                        .unwrap_or(ExtractionSource::Unknown),
+                    Some(parent.to_string()),
+                ),
+                None => (ExtractionSource::Unknown, None),
+            },
+        };
+        Provenance {
+            doi: self.doi.clone(),
+            resolved_at: self.resolved_at.clone().unwrap_or_default(),
+            extracted_from,
+            via,
+        }
+    }
+
+    fn expanded_from(&self, id: &str) -> Option<&str> {
+        let named = |ids: &[String]| ids.iter().any(|value| value == id);
+        let hydrated = &self.hydrated;
+        hydrated
+            .geo
+            .iter()
+            .find(|geo| named(&geo.srr))
+            .map(|geo| geo.gse.as_str())
+            .or_else(|| {
+                hydrated
+                    .bioprojects
+                    .iter()
+                    .find(|project| named(&project.srr) || named(&project.assemblies))
+                    .map(|project| project.bioproject.as_str())
+            })
+            .or_else(|| {
+                hydrated
+                    .ena_projects
+                    .iter()
+                    .find(|project| named(&project.runs))
+                    .map(|project| project.ena_project.as_str())
+            })
+    }
+
     pub fn resolved_specifiers(&self) -> Result<Vec<DatasetSpecifier>, KiraError> {
         let mut output = Vec::new();
         for target in &self.resolved_targets {
@@ -707,6 +1241,26 @@ pub struct DoiSourceMetadata {
     pub data_availability: Vec<String>,
 }
 
+impl DoiSourceMetadata {
+    /// The first section of the record that names `id`.
+    pub fn mentions(&self, id: &str) -> Option<ExtractionSource> {
+        let pattern = Regex::new(&format!(r"(?i)\b{}\b", regex::escape(id))).ok()?;
+        [
+            (ExtractionSource::Title, self.title.as_slice()),
+            (ExtractionSource::Abstract, self.abstract_text.as_slice()),
+            (
+                ExtractionSource::DataAvailability,
+                self.data_availability.as_slice(),
+            ),
+            (ExtractionSource::Reference, self.references.as_slice()),
+            (ExtractionSource::Link, self.links.as_slice()),
+        ]
+        .into_iter()
+        .find(|(_, texts)| texts.iter().any(|text| pattern.is_match(text)))
+        .map(|(section, _)| section)
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ExtractedIds {
     pub geo_series: Vec<String>,
@@ -890,6 +1444,131 @@ struct CrossrefAssertion {
     value: Option<String>,
 }
 
+/// A DOI's record as read from its registration agency.
+struct SourceRecord {
+    agency: DoiAgency,
+    source: DoiSourceMetadata,
+    /// Every text ids are extracted from.
+    texts: Vec<String>,
+    related: Vec<RelatedDoi>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DataciteResponse {
+    data: DataciteRecord,
+}
+
+#[derive(Debug, Deserialize)]
+struct DataciteRecord {
+    attributes: DataciteAttributes,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DataciteAttributes {
+    titles: Option<Vec<DataciteTitle>>,
+    descriptions: Option<Vec<DataciteDescription>>,
+    related_identifiers: Option<Vec<DataciteRelatedIdentifier>>,
+    url: Option<String>,
+    content_url: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DataciteTitle {
+    title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DataciteDescription {
+    description: Option<String>,
+    description_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DataciteRelatedIdentifier {
+    related_identifier: Option<String>,
+    related_identifier_type: Option<String>,
+    relation_type: Option<String>,
+}
+
+/// Maps a DataCite record onto the sections of a paper: the abstract is
+/// the `Abstract` description, other descriptions (methods, technical
+/// info) stand in for data availability, related identifiers for
+/// references and the landing and content URLs for links.
+fn collect_datacite_source(
+    attributes: &DataciteAttributes,
+) -> (DoiSourceMetadata, Vec<String>, Vec<RelatedDoi>) {
+    let title = attributes
+        .titles
+        .iter()
+        .flatten()
+        .find_map(|title| title.title.clone());
+
+    let mut abstract_text = None;
+    let mut data_availability = Vec::new();
+    for item in attributes.descriptions.iter().flatten() {
+        let Some(text) = &item.description else {
+            continue;
+        };
+        if abstract_text.is_none() && item.description_type.as_deref() == Some("Abstract") {
+            abstract_text = Some(text.clone());
+        } else {
+            data_availability.push(text.clone());
+        }
+    }
+
+    let mut references = Vec::new();
+    let mut related = Vec::new();
+    for item in attributes.related_identifiers.iter().flatten() {
+        let Some(identifier) = &item.related_identifier else {
+            continue;
+        };
+        references.push(identifier.clone());
+        let relation = item.relation_type.as_deref().unwrap_or_default();
+        if item
+            .related_identifier_type
+            .as_deref()
+            .is_some_and(|kind| kind.eq_ignore_ascii_case("doi"))
+            && matches!(relation, "IsSupplementTo" | "References")
+            && let Ok(doi) = identifier.parse::<Doi>()
+        {
+            related.push(RelatedDoi {
+                doi: doi.as_str().to_string(),
+                relation: relation.to_string(),
+                followed: false,
+            });
+        }
+    }
+
+    let links = attributes
+        .url
+        .iter()
+        .chain(attributes.content_url.iter().flatten())
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut texts = Vec::new();
+    texts.extend(title.clone());
+    texts.extend(abstract_text.clone());
+    texts.extend(references.clone());
+    texts.extend(links.clone());
+    texts.extend(data_availability.clone());
+
+    (
+        DoiSourceMetadata {
+            title,
+            abstract_text,
+            references,
+            links,
+            data_availability,
+        },
+        texts,
+        related,
+    )
+}
+
 fn collect_source(message: &CrossrefMessage) -> (DoiSourceMetadata, Vec<String>) {
     let title = message
         .title
@@ -923,12 +1602,11 @@ fn collect_source(message: &CrossrefMessage) -> (DoiSourceMetadata, Vec<String>)
             }
         }
     }
-    if let Some(resource) = &message.resource {
-        if let Some(primary) = &resource.primary {
-            if let Some(url) = &primary.url {
-                links.push(url.clone());
-            }
-        }
+    if let Some(resource) = &message.resource
+        && let Some(primary) = &resource.primary
+        && let Some(url) = &primary.url
+    {
+        links.push(url.clone());
     }
 
     let mut data_availability = Vec::new();
@@ -937,14 +1615,13 @@ fn collect_source(message: &CrossrefMessage) -> (DoiSourceMetadata, Vec<String>)
             let label = assertion
                 .label
                 .as_ref()
-                .or_else(|| assertion.name.as_ref())
+                .or(assertion.name.as_ref())
                 .map(|value| value.to_lowercase());
-            if let Some(label) = label {
-                if label.contains("data availability") || label.contains("data") {
-                    if let Some(value) = &assertion.value {
-                        data_availability.push(value.clone());
-                    }
-                }
+            if let Some(label) = label
+                && (label.contains("data availability") || label.contains("data"))
+                && let Some(value) = &assertion.value
+            {
+                data_availability.push(value.clone());
             }
         }
     }
@@ -982,7 +1659,11 @@ pub fn extract_ids(texts: &[String]) -> ExtractedIds {
     let re_gca = Regex::new(r"\bGCA_\d+\.\d+\b").unwrap();
     let re_gcf = Regex::new(r"\bGCF_\d+\.\d+\b").unwrap();
     let re_pdb = Regex::new(r"\b[0-9][A-Z0-9]{3}\b").unwrap();
-    let re_uniprot = Regex::new(r"\b[OPQ][0-9][A-Z0-9]{3}[0-9]\b").unwrap();
+    let re_pdb_extended = Regex::new(r"\bPDB_[0-9A-Z]{8}\b").unwrap();
+    let re_uniprot = Regex::new(
+        r"\b(?:[OPQ][0-9][A-Z0-9]{3}[0-9]|[A-NR-Z][0-9](?:[A-Z][A-Z0-9]{2}[0-9]){1,2})(?:-[1-9][0-9]*)?\b",
+    )
+    .unwrap();
 
     let mut geo_series = BTreeSet::new();
     let mut geo_samples = BTreeSet::new();
@@ -1023,6 +1704,11 @@ pub fn extract_ids(texts: &[String]) -> ExtractedIds {
         for value in re_pdb.find_iter(&upper) {
             pdb.insert(value.as_str().to_string());
         }
+        for value in re_pdb_extended.find_iter(&upper) {
+            if let Ok(id) = value.as_str().parse::<ProteinId>() {
+                pdb.insert(id.as_str().to_string());
+            }
+        }
         for value in re_uniprot.find_iter(&upper) {
             uniprot.insert(value.as_str().to_string());
         }
@@ -1051,6 +1737,24 @@ fn extract_matches(text: &str, regex: &Regex) -> Vec<String> {
     output
 }
 
+fn pending_ids(ids: &[String], known: &BTreeMap<String, bool>) -> Vec<String> {
+    let mut pending = ids
+        .iter()
+        .filter(|id| !known.contains_key(*id))
+        .cloned()
+        .collect::<Vec<_>>();
+    pending.sort();
+    pending.dedup();
+    pending
+}
+
+pub fn accession_term(ids: &[String]) -> String {
+    ids.iter()
+        .map(|id| format!("{id}[Accession]"))
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
 fn encode_url_component(value: &str) -> String {
     let mut out = String::new();
     for byte in value.as_bytes() {
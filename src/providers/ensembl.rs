@@ -0,0 +1,276 @@
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use flate2::read::MultiGzDecoder;
+use regex::Regex;
+use reqwest::blocking::{Client, Response};
+use serde::{Deserialize, Serialize};
+use tracing::field::Empty;
+
+use crate::app::ProgressSink;
+use crate::cancel;
+use crate::error::KiraError;
+use crate::fs_util::HashingWriter;
+use crate::http::{FailedResponse, HttpSettings, Mirrors, record_bytes, record_response};
+use crate::progress::Metered;
+
+pub const ENSEMBL_FTP: &str = "https://ftp.ensembl.org/pub";
+
+/// A file of an Ensembl genome a `genome:ensembl:` fetch can download.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum EnsemblFile {
+    /// The primary assembly, or the top-level sequences of species that
+    /// have no primary assembly.
+    Dna,
+    /// Transcript sequences.
+    Cdna,
+    Gtf,
+    Gff3,
+}
+
+impl EnsemblFile {
+    /// What a fetch downloads unless told otherwise: the sequences and the
+    /// gene annotations RNA-seq aligners take.
+    pub const DEFAULT: [EnsemblFile; 2] = [EnsemblFile::Dna, EnsemblFile::Gtf];
+
+    /// The release directory listing this file, relative to the release.
+    fn directory(self, species: &str) -> String {
+        match self {
+            EnsemblFile::Dna => format!("fasta/{species}/dna"),
+            EnsemblFile::Cdna => format!("fasta/{species}/cdna"),
+            EnsemblFile::Gtf => format!("gtf/{species}"),
+            EnsemblFile::Gff3 => format!("gff3/{species}"),
+        }
+    }
+
+    /// File name suffixes in order of preference; the part before them is
+    /// `<Species>.<assembly>`.
+    fn suffixes(self, release: u32) -> Vec<String> {
+        match self {
+            EnsemblFile::Dna => vec![
+                ".dna.primary_assembly.fa.gz".to_string(),
+                ".dna.toplevel.fa.gz".to_string(),
+            ],
+            EnsemblFile::Cdna => vec![".cdna.all.fa.gz".to_string()],
+            EnsemblFile::Gtf => vec![format!(".{release}.gtf.gz")],
+            EnsemblFile::Gff3 => vec![format!(".{release}.gff3.gz")],
+        }
+    }
+}
+
+impl std::fmt::Display for EnsemblFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EnsemblFile::Dna => write!(f, "dna"),
+            EnsemblFile::Cdna => write!(f, "cdna"),
+            EnsemblFile::Gtf => write!(f, "gtf"),
+            EnsemblFile::Gff3 => write!(f, "gff3"),
+        }
+    }
+}
+
+/// One file chosen from a release directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnsemblListing {
+    pub name: String,
+    pub url: String,
+    /// The assembly the file name carries, e.g. `GRCh38`.
+    pub assembly: String,
+}
+
+/// Index URL of the directory holding `file` for `species` in `release`.
+pub fn directory_url(release: u32, species: &str, file: EnsemblFile) -> String {
+    format!(
+        "{ENSEMBL_FTP}/release-{release}/{}/",
+        file.directory(species)
+    )
+}
+
+/// The file of kind `file` among the names of a directory listing: the
+/// first suffix in order of preference that some name ends with. Names of
+/// other species (Ensembl lists none, but mirrors may) are skipped.
+pub fn choose_file(
+    names: &[String],
+    species: &str,
+    release: u32,
+    file: EnsemblFile,
+) -> Option<(String, String)> {
+    file.suffixes(release).iter().find_map(|suffix| {
+        names.iter().find_map(|name| {
+            let stem = name.strip_suffix(suffix.as_str())?;
+            let (prefix, assembly) = stem.split_once('.')?;
+            (prefix.eq_ignore_ascii_case(species) && !assembly.is_empty())
+                .then(|| (name.clone(), assembly.to_string()))
+        })
+    })
+}
+
+/// File names linked from an HTML directory index.
+pub fn listed_names(html: &str) -> Vec<String> {
+    let href = Regex::new(r#"href="([^"?/]+)""#).expect("href pattern");
+    href.captures_iter(html)
+        .map(|captures| captures[1].to_string())
+        .collect()
+}
+
+/// Ensembl's FTP site, read over HTTPS.
+#[derive(Debug, Clone)]
+pub struct EnsemblClient {
+    client: Client,
+    settings: HttpSettings,
+    mirrors: Mirrors,
+}
+
+impl EnsemblClient {
+    pub fn with_settings(settings: HttpSettings) -> Result<Self, KiraError> {
+        let client = settings
+            .client_builder("ensembl")
+            .user_agent(format!("kira-bm/{}", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|err| KiraError::EnsemblHttp(err.to_string()))?;
+        Ok(Self {
+            client,
+            mirrors: settings.mirrors()?,
+            settings,
+        })
+    }
+
+    /// Sends every request to `base_url` instead of Ensembl.
+    pub fn with_base_url(settings: HttpSettings, base_url: &str) -> Result<Self, KiraError> {
+        Ok(Self {
+            mirrors: Mirrors::everywhere(base_url),
+            ..Self::with_settings(settings)?
+        })
+    }
+
+    fn get(&self, url: &str, deadline: Option<Duration>) -> Result<Response, KiraError> {
+        self.settings.limiter("ensembl").acquire();
+        let mut request = self.client.get(self.mirrors.rewrite(url));
+        if let Some(deadline) = deadline {
+            request = request.timeout(deadline);
+        }
+        let response = request
+            .send()
+            .map_err(|err| KiraError::EnsemblHttp(err.to_string()))?;
+        record_response(&response);
+        if !response.status().is_success() {
+            let failed = FailedResponse::read(response);
+            return Err(KiraError::EnsemblStatus {
+                status: failed.status,
+                url: failed.url,
+                dataset: url.to_string(),
+                message: failed.message,
+            });
+        }
+        Ok(response)
+    }
+
+    fn text(&self, url: &str) -> Result<String, KiraError> {
+        let response = self.get(url, Some(self.settings.timeouts_for("ensembl").request))?;
+        let text = response
+            .text()
+            .map_err(|err| KiraError::EnsemblHttp(err.to_string()))?;
+        record_bytes(text.len() as u64);
+        Ok(text)
+    }
+
+    /// The release `pub/current` points at, from `pub/VERSION`.
+    #[tracing::instrument(
+        name = "ensembl.current_release",
+        skip_all,
+        fields(registry = "ensembl", endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    pub fn current_release(&self) -> Result<u32, KiraError> {
+        let url = format!("{ENSEMBL_FTP}/VERSION");
+        let body = self.text(&url)?;
+        body.trim().parse().map_err(|_| {
+            KiraError::EnsemblHttp(format!("{url} names no release: {:?}", body.trim()))
+        })
+    }
+
+    /// The file of kind `file` for `species` in `release`.
+    #[tracing::instrument(
+        name = "ensembl.locate",
+        skip_all,
+        fields(registry = "ensembl", dataset = species, endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    pub fn locate(
+        &self,
+        species: &str,
+        release: u32,
+        file: EnsemblFile,
+    ) -> Result<EnsemblListing, KiraError> {
+        let directory = directory_url(release, species, file);
+        let names = listed_names(&self.text(&directory)?);
+        let (name, assembly) = choose_file(&names, species, release, file).ok_or_else(|| {
+            KiraError::EnsemblFileMissing {
+                species: species.to_string(),
+                release,
+                file: file.to_string(),
+                url: directory.clone(),
+            }
+        })?;
+        Ok(EnsemblListing {
+            url: format!("{directory}{name}"),
+            name,
+            assembly,
+        })
+    }
+
+    /// Streams `url` to `destination` as served, gzip and all, and returns
+    /// the bytes written and their SHA-256.
+    #[tracing::instrument(
+        name = "ensembl.download",
+        skip_all,
+        fields(registry = "ensembl", endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    pub fn download(
+        &self,
+        url: &str,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<(u64, String), KiraError> {
+        let response = self.get(url, None)?;
+        let mut file = HashingWriter::new(
+            File::create(destination).map_err(|err| KiraError::Filesystem(err.to_string()))?,
+        );
+        let total = response.content_length();
+        let result = cancel::copy(
+            &mut Metered::new(response, sink, destination, total),
+            &mut file,
+            KiraError::EnsemblHttp,
+        );
+        let (file, digest) = file.finish();
+        drop(file);
+        if result.is_err() {
+            let _ = std::fs::remove_file(destination);
+        }
+        let bytes = result?;
+        record_bytes(bytes);
+        Ok((bytes, digest))
+    }
+}
+
+/// Reads a downloaded `.gz` file to its end, so a transfer cut short is
+/// caught before the file is stored.
+pub fn check_gzip(path: &Path) -> Result<(), KiraError> {
+    let file = File::open(path).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    cancel::copy(
+        &mut MultiGzDecoder::new(file),
+        &mut std::io::sink(),
+        KiraError::Filesystem,
+    )
+    .map_err(|err| match err {
+        KiraError::Filesystem(message) => KiraError::CorruptGzip {
+            path: path.to_path_buf(),
+            message,
+        },
+        other => other,
+    })?;
+    Ok(())
+}
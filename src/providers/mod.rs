@@ -1 +1,2 @@
 pub mod doi;
+pub mod ensembl;
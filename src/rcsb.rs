@@ -1,15 +1,25 @@
 use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
 use std::path::Path;
 use std::thread;
 use std::time::Duration;
 
+use reqwest::StatusCode;
 use reqwest::blocking::Client;
 use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tracing::field::Empty;
 
+use crate::app::ProgressSink;
+use crate::cancel;
 use crate::domain::{ProteinFormat, ProteinId};
 use crate::error::KiraError;
+use crate::http::{
+    Conditional, FailedResponse, HttpSettings, Mirrors, StoredValidators, Validators, record_bytes,
+    record_response, trace_retry,
+};
+use crate::progress::Metered;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct RcsbMetadata {
@@ -31,29 +41,76 @@ pub trait RcsbClient: Send + Sync {
         id: &ProteinId,
         format: ProteinFormat,
         destination: &Path,
+        sink: &dyn ProgressSink,
     ) -> Result<(), KiraError>;
     fn fetch_metadata(&self, id: &ProteinId) -> Result<RcsbMetadata, KiraError>;
+
+    /// The entry's polymer sequences as FASTA, `None` when RCSB has none
+    /// for it. Clients without a FASTA endpoint never have any.
+    fn fetch_fasta(&self, _id: &ProteinId) -> Result<Option<String>, KiraError> {
+        Ok(None)
+    }
+
+    /// Downloads the structure unless `stored` validators show the stored
+    /// copy is current. Clients that cannot ask always download.
+    fn download_structure_if_modified(
+        &self,
+        id: &ProteinId,
+        format: ProteinFormat,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+        _stored: &StoredValidators,
+    ) -> Result<Conditional<()>, KiraError> {
+        self.download_structure(id, format, destination, sink)
+            .map(Conditional::unconditional)
+    }
+
+    fn fetch_metadata_if_modified(
+        &self,
+        id: &ProteinId,
+        _stored: &StoredValidators,
+    ) -> Result<Conditional<RcsbMetadata>, KiraError> {
+        self.fetch_metadata(id).map(Conditional::unconditional)
+    }
 }
 
 #[derive(Clone)]
 pub struct RcsbHttpClient {
     client: Client,
+    request_timeout: Duration,
+    mirrors: Mirrors,
 }
 
 impl RcsbHttpClient {
     pub fn new() -> Result<Self, KiraError> {
+        Self::with_settings(HttpSettings::from_env())
+    }
+
+    pub fn with_settings(settings: HttpSettings) -> Result<Self, KiraError> {
         let mut headers = HeaderMap::new();
         headers.insert(
             USER_AGENT,
             HeaderValue::from_str(&format!("kira-bm/{}", env!("CARGO_PKG_VERSION")))
                 .map_err(|err| KiraError::Filesystem(err.to_string()))?,
         );
-        let client = Client::builder()
+        let client = settings
+            .client_builder("rcsb")
             .default_headers(headers)
-            .timeout(Duration::from_secs(30))
             .build()
             .map_err(|err| KiraError::RcsbHttp(err.to_string()))?;
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            request_timeout: settings.timeouts_for("rcsb").request,
+            mirrors: settings.mirrors()?,
+        })
+    }
+
+    /// Sends every request to `base_url` instead of the RCSB hosts.
+    pub fn with_base_url(settings: HttpSettings, base_url: &str) -> Result<Self, KiraError> {
+        Ok(Self {
+            mirrors: Mirrors::everywhere(base_url),
+            ..Self::with_settings(settings)?
+        })
     }
 
     pub fn structure_url(id: &ProteinId, format: ProteinFormat) -> String {
@@ -65,21 +122,28 @@ impl RcsbHttpClient {
         format!("https://files.rcsb.org/download/{}.{}", id.as_str(), ext)
     }
 
-    fn metadata_url(id: &ProteinId) -> String {
+    pub fn metadata_url(id: &ProteinId) -> String {
         format!("https://data.rcsb.org/rest/v1/core/entry/{}", id.as_str())
     }
 
+    pub fn fasta_url(id: &ProteinId) -> String {
+        format!("https://www.rcsb.org/fasta/entry/{}", id.as_str())
+    }
+
     fn handle_status(
         response: reqwest::blocking::Response,
+        id: &ProteinId,
     ) -> Result<reqwest::blocking::Response, KiraError> {
         if response.status().is_success() {
             return Ok(response);
         }
-        let status = response.status().as_u16();
-        let message = response
-            .text()
-            .unwrap_or_else(|_| "RCSB request failed".to_string());
-        Err(KiraError::RcsbStatus { status, message })
+        let failed = FailedResponse::read(response);
+        Err(KiraError::RcsbStatus {
+            status: failed.status,
+            url: failed.url,
+            dataset: format!("protein:{}", id.as_str()),
+            message: failed.message,
+        })
     }
 
     fn send_with_retries<F>(
@@ -96,10 +160,12 @@ impl RcsbHttpClient {
             let response = make_req().send();
             match response {
                 Ok(resp) => {
+                    record_response(&resp);
                     let status = resp.status().as_u16();
                     if attempt < MAX_RETRIES && is_retryable_status(status) {
-                        let delay = BASE_DELAY_MS * (attempt as u64 + 1);
-                        thread::sleep(Duration::from_millis(delay));
+                        let delay = Duration::from_millis(BASE_DELAY_MS * (attempt as u64 + 1));
+                        trace_retry("rcsb", attempt + 1, delay, &status);
+                        thread::sleep(delay);
                         attempt += 1;
                         continue;
                     }
@@ -107,8 +173,9 @@ impl RcsbHttpClient {
                 }
                 Err(err) => {
                     if attempt < MAX_RETRIES && is_retryable_error(&err) {
-                        let delay = BASE_DELAY_MS * (attempt as u64 + 1);
-                        thread::sleep(Duration::from_millis(delay));
+                        let delay = Duration::from_millis(BASE_DELAY_MS * (attempt as u64 + 1));
+                        trace_retry("rcsb", attempt + 1, delay, &err);
+                        thread::sleep(delay);
                         attempt += 1;
                         continue;
                     }
@@ -125,21 +192,92 @@ impl RcsbClient for RcsbHttpClient {
         id: &ProteinId,
         format: ProteinFormat,
         destination: &Path,
+        sink: &dyn ProgressSink,
     ) -> Result<(), KiraError> {
-        let url = Self::structure_url(id, format);
-        let response = self.send_with_retries(|| self.client.get(&url))?;
-        let mut response = Self::handle_status(response)?;
-        let mut file =
-            File::create(destination).map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        std::io::copy(&mut response, &mut file)
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        Ok(())
+        self.download_structure_if_modified(id, format, destination, sink, &StoredValidators::new())
+            .map(|_| ())
+    }
+
+    #[tracing::instrument(
+        name = "rcsb.download_structure",
+        skip_all,
+        fields(registry = "rcsb", dataset = %id.as_str(), endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    fn download_structure_if_modified(
+        &self,
+        id: &ProteinId,
+        format: ProteinFormat,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+        stored: &StoredValidators,
+    ) -> Result<Conditional<()>, KiraError> {
+        let source = Self::structure_url(id, format);
+        let url = self.mirrors.rewrite(&source);
+        let validators = stored.get(&source).cloned().unwrap_or_default();
+        let response = self.send_with_retries(|| validators.apply(self.client.get(&url)))?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Conditional::NotModified);
+        }
+        let response = Self::handle_status(response, id)?;
+        let validators = Validators::of(&response);
+        let total = response.content_length();
+        let (written, _) = cancel::copy_to_file(
+            &mut Metered::new(response, sink, destination, total),
+            destination,
+        )?;
+        record_bytes(written);
+        Ok(Conditional::Modified((), validators.for_url(source)))
+    }
+
+    #[tracing::instrument(
+        name = "rcsb.fetch_fasta",
+        skip_all,
+        fields(registry = "rcsb", dataset = %id.as_str(), endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    fn fetch_fasta(&self, id: &ProteinId) -> Result<Option<String>, KiraError> {
+        let url = self.mirrors.rewrite(&Self::fasta_url(id));
+        let response =
+            self.send_with_retries(|| self.client.get(&url).timeout(self.request_timeout))?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let text = Self::handle_status(response, id)?
+            .text()
+            .map_err(|err| KiraError::RcsbHttp(err.to_string()))?;
+        record_bytes(text.len() as u64);
+        Ok((!text.trim().is_empty()).then_some(text))
     }
 
     fn fetch_metadata(&self, id: &ProteinId) -> Result<RcsbMetadata, KiraError> {
+        match self.fetch_metadata_if_modified(id, &StoredValidators::new())? {
+            Conditional::Modified(metadata, _) => Ok(metadata),
+            Conditional::NotModified => Err(KiraError::RcsbHttp(format!(
+                "RCSB answered an unconditional request for {id} with 304"
+            ))),
+        }
+    }
+
+    #[tracing::instrument(
+        name = "rcsb.fetch_metadata",
+        skip_all,
+        fields(registry = "rcsb", dataset = %id.as_str(), endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    fn fetch_metadata_if_modified(
+        &self,
+        id: &ProteinId,
+        stored: &StoredValidators,
+    ) -> Result<Conditional<RcsbMetadata>, KiraError> {
         let url = Self::metadata_url(id);
-        let response = self.send_with_retries(|| self.client.get(&url))?;
-        let response = Self::handle_status(response)?;
+        let request_url = self.mirrors.rewrite(&url);
+        let validators = stored.get(&url).cloned().unwrap_or_default();
+        let response = self.send_with_retries(|| {
+            validators.apply(self.client.get(&request_url).timeout(self.request_timeout))
+        })?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Conditional::NotModified);
+        }
+        let response = Self::handle_status(response, id)?;
+        let validators = Validators::of(&response);
         let raw_json: Value = response
             .json()
             .map_err(|err| KiraError::RcsbHttp(err.to_string()))?;
@@ -173,19 +311,138 @@ impl RcsbClient for RcsbHttpClient {
             .and_then(|value| value.as_str())
             .map(|value| value.to_string());
 
-        Ok(RcsbMetadata {
-            registry: "rcsb".to_string(),
-            pdb_id: id.as_str().to_string(),
-            title,
-            experimental_method,
-            resolution,
-            deposition_date,
-            release_date,
-            source_structure_url: Self::structure_url(id, ProteinFormat::Cif),
-            source_metadata_url: url,
-            raw_json,
+        Ok(Conditional::Modified(
+            RcsbMetadata {
+                registry: "rcsb".to_string(),
+                pdb_id: id.as_str().to_string(),
+                title,
+                experimental_method,
+                resolution,
+                deposition_date,
+                release_date,
+                source_structure_url: Self::structure_url(id, ProteinFormat::Cif),
+                source_metadata_url: url.clone(),
+                raw_json,
+            },
+            validators.for_url(url),
+        ))
+    }
+}
+
+/// One record of an RCSB FASTA file, whose headers read
+/// `>1LYZ_1|Chain A|LYSOZYME|Gallus gallus (9031)`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FastaSequence {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity_id: Option<String>,
+    /// Label chain ids; author ids given as `A[auth C]` are dropped.
+    pub chain_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organism: Option<String>,
+    pub length: usize,
+}
+
+pub fn parse_fasta(text: &str) -> Vec<FastaSequence> {
+    let mut sequences: Vec<FastaSequence> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if let Some(header) = line.strip_prefix('>') {
+            let mut fields = header.split('|').map(str::trim);
+            let entity_id = fields
+                .next()
+                .and_then(|name| name.rsplit_once('_'))
+                .map(|(_, entity)| entity.to_string());
+            let chain_ids = fields
+                .next()
+                .map(|chains| {
+                    chains
+                        .trim_start_matches("Chains")
+                        .trim_start_matches("Chain")
+                        .split(',')
+                        .filter_map(|chain| chain.split('[').next())
+                        .map(str::trim)
+                        .filter(|chain| !chain.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default();
+            let description = fields
+                .next()
+                .filter(|value| !value.is_empty())
+                .map(str::to_string);
+            let organism = fields
+                .next()
+                .filter(|value| !value.is_empty())
+                .map(str::to_string);
+            sequences.push(FastaSequence {
+                entity_id,
+                chain_ids,
+                description,
+                organism,
+                length: 0,
+            });
+        } else if let Some(sequence) = sequences.last_mut() {
+            sequence.length += line.len();
+        }
+    }
+    sequences
+}
+
+/// A polymer entity of an entry: its id from the entry JSON, with chains
+/// and source organism from the entry's FASTA when it was fetched.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RcsbEntity {
+    pub entity_id: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub chain_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organism: Option<String>,
+}
+
+/// The polymer entity count an entry JSON declares and its entities.
+pub fn polymer_entities(
+    raw_json: &Value,
+    sequences: &[FastaSequence],
+) -> (Option<u64>, Vec<RcsbEntity>) {
+    let count = raw_json
+        .pointer("/rcsb_entry_info/polymer_entity_count")
+        .and_then(Value::as_u64);
+    let mut ids = raw_json
+        .pointer("/rcsb_entry_container_identifiers/polymer_entity_ids")
+        .and_then(Value::as_array)
+        .map(|ids| {
+            ids.iter()
+                .filter_map(|id| match id {
+                    Value::String(id) => Some(id.clone()),
+                    Value::Number(id) => Some(id.to_string()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
         })
+        .unwrap_or_default();
+    for entity in sequences.iter().filter_map(|seq| seq.entity_id.as_ref()) {
+        if !ids.contains(entity) {
+            ids.push(entity.clone());
+        }
     }
+    let entities = ids
+        .into_iter()
+        .map(|entity_id| {
+            let sequence = sequences
+                .iter()
+                .find(|seq| seq.entity_id.as_deref() == Some(entity_id.as_str()));
+            RcsbEntity {
+                chain_ids: sequence
+                    .map(|seq| seq.chain_ids.clone())
+                    .unwrap_or_default(),
+                organism: sequence.and_then(|seq| seq.organism.clone()),
+                entity_id,
+            }
+        })
+        .collect::<Vec<_>>();
+    let count = count.or_else(|| (!entities.is_empty()).then_some(entities.len() as u64));
+    (count, entities)
 }
 
 fn is_retryable_status(status: u16) -> bool {
@@ -195,3 +452,99 @@ fn is_retryable_status(status: u16) -> bool {
 fn is_retryable_error(err: &reqwest::Error) -> bool {
     err.is_timeout() || err.is_connect() || err.is_request()
 }
+
+/// Why a downloaded structure file cannot be what was asked for, if
+/// anything: an HTML error page or a truncated body served in its place.
+/// Reads the file line by line; only the envelope of BinaryCIF is checked.
+pub fn structure_file_defect(
+    path: &Path,
+    format: ProteinFormat,
+) -> Result<Option<String>, KiraError> {
+    let file = File::open(path).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    let reader = BufReader::new(file);
+    match format {
+        ProteinFormat::Cif => cif_defect(reader),
+        ProteinFormat::Pdb => pdb_defect(reader),
+        ProteinFormat::Bcif => bcif_defect(reader),
+    }
+}
+
+fn cif_defect(reader: impl BufRead) -> Result<Option<String>, KiraError> {
+    let mut lines = reader.lines();
+    let first = lines
+        .by_ref()
+        .map(|line| line.map_err(|err| KiraError::Filesystem(err.to_string())))
+        .find(|line| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+        .transpose()?;
+    if !first.is_some_and(|line| line.starts_with("data_")) {
+        return Ok(Some("CIF file does not start with data_".to_string()));
+    }
+    // Tags of the loop being read, then whether its rows are atom sites.
+    let mut in_loop_header = false;
+    let mut atom_site_loop = false;
+    let mut seen_atom_site = false;
+    for line in lines {
+        let line = line.map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let line = line.trim();
+        if line == "loop_" {
+            in_loop_header = true;
+            atom_site_loop = false;
+        } else if line.starts_with('_') {
+            if in_loop_header && line.starts_with("_atom_site.") {
+                atom_site_loop = true;
+                seen_atom_site = true;
+            }
+            if !in_loop_header {
+                atom_site_loop = false;
+            }
+        } else if line.is_empty() || line.starts_with('#') {
+            if !in_loop_header {
+                atom_site_loop = false;
+            }
+        } else {
+            in_loop_header = false;
+            if atom_site_loop {
+                return Ok(None);
+            }
+        }
+    }
+    Ok(Some(if seen_atom_site {
+        "CIF _atom_site loop has no rows".to_string()
+    } else {
+        "CIF file has no _atom_site loop".to_string()
+    }))
+}
+
+fn pdb_defect(reader: impl BufRead) -> Result<Option<String>, KiraError> {
+    let mut atoms = false;
+    let mut end = false;
+    for line in reader.lines() {
+        let line = line.map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let record = line.get(..6).unwrap_or(&line).trim_end();
+        match record {
+            "ATOM" | "HETATM" => atoms = true,
+            "END" => end = true,
+            _ => {}
+        }
+    }
+    Ok(match (atoms, end) {
+        (true, true) => None,
+        (false, _) => Some("PDB file has no ATOM or HETATM records".to_string()),
+        (true, false) => Some("PDB file has no END record".to_string()),
+    })
+}
+
+/// BinaryCIF is a MessagePack map whose keys include `dataBlocks`.
+fn bcif_defect(reader: impl Read) -> Result<Option<String>, KiraError> {
+    let mut head = Vec::new();
+    reader
+        .take(4096)
+        .read_to_end(&mut head)
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    let is_map = matches!(head.first(), Some(0x80..=0x8f | 0xde | 0xdf));
+    if !is_map {
+        return Ok(Some("BinaryCIF file is not a MessagePack map".to_string()));
+    }
+    let has_blocks = head.windows(10).any(|window| window == b"dataBlocks");
+    Ok((!has_blocks).then(|| "BinaryCIF file has no dataBlocks".to_string()))
+}
@@ -0,0 +1,215 @@
+//! `README.md` files for dataset directories.
+//!
+//! `fetch --write-readme` (or `"write_readme": true` in `kira-bm.json`)
+//! leaves a short README in each project dataset it fetches, so that a
+//! collaborator handed the store can tell what a directory holds without
+//! kira-bm. The text is rendered from the dataset's metadata record, the
+//! registry metadata stored beside its files and, for datasets found
+//! through a DOI, the stored resolution; it is rewritten whole on every
+//! fetch, so fetching again never adds to it.
+
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::audit::specifier_for;
+use crate::error::KiraError;
+use crate::fs_util::bytes_to_human;
+use crate::manifest::MANIFEST_FILE;
+use crate::providers::doi::DoiResolution;
+use crate::store::{Materialization, Metadata, Store};
+
+pub const README_FILE: &str = "README.md";
+
+/// First line of every README kira-bm writes; a `README.md` without it
+/// came with the data and is left alone.
+const MARKER: &str = "<!-- Written by kira-bm from the dataset's metadata and rewritten on every fetch; edits are lost. -->";
+
+/// Files listed before the rest are summarised in one line.
+const LISTED_FILES: usize = 100;
+
+/// What a README says beyond the dataset's [`Metadata`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadmeDetails {
+    /// The registry's name for the dataset: an RCSB entry title, a UniProt
+    /// protein name, a GEO series title, a GO release.
+    pub title: Option<String>,
+    /// Paths relative to the dataset directory with their sizes, sorted.
+    pub files: Vec<(String, u64)>,
+    /// The paper a `doi:` fetch found the dataset in.
+    pub citation: Option<Citation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Citation {
+    pub doi: String,
+    pub title: Option<String>,
+}
+
+impl Citation {
+    pub fn of(resolution: &DoiResolution) -> Self {
+        Self {
+            doi: resolution.doi.clone(),
+            title: resolution.source.title.clone(),
+        }
+    }
+}
+
+/// The README of a dataset.
+pub fn render(metadata: &Metadata, details: &ReadmeDetails) -> String {
+    let mut out = format!("{MARKER}\n\n# {}\n\n", specifier_for(metadata));
+    if let Some(title) = &details.title {
+        out.push_str(&format!("{title}\n\n"));
+    }
+    out.push_str(&format!("- Type: {}\n", metadata.dataset_type));
+    out.push_str(&format!("- Id: {}\n", metadata.id));
+    if let Some(assembly) = &metadata.assembly_name {
+        out.push_str(&format!("- Assembly: {assembly}\n"));
+    }
+    if let Some(format) = &metadata.format {
+        out.push_str(&format!("- Format: {format}\n"));
+    }
+    out.push_str(&format!(
+        "- Downloaded: {} from {} by {}\n",
+        metadata.downloaded_at, metadata.source, metadata.tool
+    ));
+    if let Some(imported) = &metadata.imported_from {
+        out.push_str(&format!("- Imported from: {imported}\n"));
+    }
+    for url in metadata
+        .download
+        .iter()
+        .flat_map(|download| &download.source_url)
+    {
+        out.push_str(&format!("- Source: <{url}>\n"));
+    }
+
+    out.push_str("\n## Files\n\n");
+    if metadata.materialization == Materialization::MetadataOnly {
+        out.push_str(
+            "Only metadata was fetched; fetching without `--metadata-only` adds the data.\n\n",
+        );
+    }
+    if details.files.is_empty() {
+        out.push_str("No files.\n");
+    } else {
+        out.push_str("| File | Size |\n| --- | ---: |\n");
+        for (path, bytes) in details.files.iter().take(LISTED_FILES) {
+            out.push_str(&format!("| `{path}` | {} |\n", bytes_to_human(*bytes)));
+        }
+        if details.files.len() > LISTED_FILES {
+            let rest = &details.files[LISTED_FILES..];
+            out.push_str(&format!(
+                "\n…and {} more files of {}.\n",
+                rest.len(),
+                bytes_to_human(rest.iter().map(|(_, bytes)| bytes).sum())
+            ));
+        }
+    }
+
+    if let Some(citation) = &details.citation {
+        out.push_str("\n## Citation\n\n");
+        let link = format!("<https://doi.org/{}>", citation.doi);
+        match &citation.title {
+            Some(title) => out.push_str(&format!("{title}. {link}\n")),
+            None => out.push_str(&format!("{link}\n")),
+        }
+        if metadata.dataset_type != "doi" {
+            out.push_str("\nThis dataset was found in the paper above; cite it with the data.\n");
+        }
+    }
+    out
+}
+
+/// Reads what [`render`] needs from the dataset directory `dir` and the
+/// project's DOI resolutions.
+pub fn details(metadata: &Metadata, dir: &Path, store: &Store) -> ReadmeDetails {
+    let citation = if metadata.dataset_type == "doi" {
+        read_resolution(&dir.join("doi_resolution.json"))
+    } else {
+        metadata.provenance.as_ref().map(|provenance| {
+            let stored = provenance.doi.parse().ok().and_then(|doi| {
+                read_resolution(store.project_doi_resolution_path(&doi).as_std_path())
+            });
+            stored.unwrap_or_else(|| Citation {
+                doi: provenance.doi.clone(),
+                title: None,
+            })
+        })
+    };
+    ReadmeDetails {
+        title: title(&metadata.dataset_type, dir),
+        files: files(dir),
+        citation,
+    }
+}
+
+/// Writes the README into `dir`. Returns false, writing nothing, when the
+/// directory holds a `README.md` of its own.
+pub fn write(dir: &Path, metadata: &Metadata, store: &Store) -> Result<bool, KiraError> {
+    let path = dir.join(README_FILE);
+    if let Ok(existing) = fs::read_to_string(&path)
+        && !existing.starts_with(MARKER)
+    {
+        return Ok(false);
+    }
+    let text = render(metadata, &details(metadata, dir, store));
+    fs::write(&path, text)
+        .map(|()| true)
+        .map_err(|err| KiraError::Filesystem(format!("{}: {err}", path.display())))
+}
+
+fn title(dataset_type: &str, dir: &Path) -> Option<String> {
+    let value = fs::read(dir.join("metadata.json"))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<Value>(&bytes).ok())?;
+    let text = |key: &str| {
+        value[key]
+            .as_str()
+            .filter(|text| !text.is_empty())
+            .map(str::to_string)
+    };
+    match dataset_type {
+        "protein" | "expression" | "expression10x" | "expressionsample" => text("title"),
+        "uniprot" => text("protein_name"),
+        "uniprot-proteome" => text("description"),
+        "go" => text("version").map(|version| format!("Gene Ontology {version}")),
+        _ => None,
+    }
+}
+
+fn read_resolution(path: &Path) -> Option<Citation> {
+    let bytes = fs::read(path).ok()?;
+    let resolution = serde_json::from_slice::<DoiResolution>(&bytes).ok()?;
+    Some(Citation::of(&resolution))
+}
+
+/// Every file under `dir` but the READMEs and manifests, so rewriting one
+/// leaves the listing as it was.
+fn files(dir: &Path) -> Vec<(String, u64)> {
+    let mut files = Vec::new();
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(path) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&path) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                stack.push(path);
+            } else if entry.file_name() != README_FILE
+                && entry.file_name() != MANIFEST_FILE
+                && let Ok(relative) = path.strip_prefix(dir)
+            {
+                let relative = relative.to_string_lossy().replace('\\', "/");
+                files.push((relative, meta.len()));
+            }
+        }
+    }
+    files.sort();
+    files
+}
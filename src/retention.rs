@@ -0,0 +1,254 @@
+//! Age-based expiry of project datasets.
+//!
+//! `kira-bm retention apply` deletes the data of project datasets older than
+//! their type's policy. Each metadata record is kept, marked `expired`, with
+//! the size and SHA-256 of every deleted file; a later fetch re-downloads.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use camino::Utf8PathBuf;
+use chrono::{DateTime, Duration, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::audit::{self, AuditRecord, AuditTarget, specifier_for};
+use crate::config::RetentionConfig;
+use crate::error::KiraError;
+use crate::fs_util::{path_size, sha256_file};
+use crate::store::{DATASET_TYPES, ExpiredFile, Expiry, Metadata, STATUS_EXPIRED, Store, walk_dir};
+
+/// Policy key covering every knowledge base without a key of its own.
+pub(crate) const KB_KEY: &str = "kb";
+pub(crate) const KB_TYPES: [&str; 3] = ["go", "kegg", "reactome"];
+
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPolicy {
+    /// Key -> (value as configured, parsed limit).
+    max_age: BTreeMap<String, (String, Duration)>,
+}
+
+impl RetentionPolicy {
+    pub fn from_config(config: &RetentionConfig) -> Result<Self, KiraError> {
+        let mut max_age = BTreeMap::new();
+        for (key, value) in &config.max_age {
+            let key = key.to_ascii_lowercase();
+            if key != KB_KEY && !DATASET_TYPES.contains(&key.as_str()) {
+                return Err(KiraError::ConfigParse(format!(
+                    "retention.{key}: unknown dataset type"
+                )));
+            }
+            let limit = parse_max_age(value).ok_or_else(|| {
+                KiraError::ConfigParse(format!(
+                    "retention.{key}: expected an age like \"180d\", \"12w\" or \"36h\", got {value:?}"
+                ))
+            })?;
+            max_age.insert(key, (value.trim().to_string(), limit));
+        }
+        Ok(Self { max_age })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.max_age.is_empty()
+    }
+
+    /// The policy that applies to a dataset type, as `key=value`, and its limit.
+    pub fn limit_for(&self, dataset_type: &str) -> Option<(String, Duration)> {
+        let key = if self.max_age.contains_key(dataset_type) {
+            dataset_type
+        } else if KB_TYPES.contains(&dataset_type) {
+            KB_KEY
+        } else {
+            return None;
+        };
+        self.max_age
+            .get(key)
+            .map(|(value, limit)| (format!("{key}={value}"), *limit))
+    }
+}
+
+/// Parses `<N>h`, `<N>d` or `<N>w`.
+pub fn parse_max_age(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let unit = value.chars().last()?;
+    let count = value[..value.len() - unit.len_utf8()].parse::<i64>().ok()?;
+    if count < 0 {
+        return None;
+    }
+    match unit.to_ascii_lowercase() {
+        'h' => Duration::try_hours(count),
+        'd' => Duration::try_days(count),
+        'w' => Duration::try_weeks(count),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct RetentionReport {
+    pub dry_run: bool,
+    pub expired: Vec<RetentionEntry>,
+    /// Specifiers past their policy that were kept because they are pinned.
+    pub pinned: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct RetentionEntry {
+    pub specifier: String,
+    pub downloaded_at: String,
+    pub age_days: i64,
+    pub policy: String,
+    pub bytes: u64,
+    pub path: String,
+}
+
+/// Expires every project dataset older than its policy allows, except
+/// those pinned with `kira-bm pin` unless `include_pinned`. With `dry_run`
+/// nothing is touched and nothing is logged.
+pub fn apply(
+    store: &Store,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+    dry_run: bool,
+    include_pinned: bool,
+) -> Result<RetentionReport, KiraError> {
+    let records = Store::list_metadata_files(store.project_root())?;
+    let mut report = RetentionReport {
+        dry_run,
+        expired: Vec::new(),
+        pinned: Vec::new(),
+    };
+    let mut due = Vec::new();
+    for (path, metadata) in &records {
+        if metadata.is_expired() {
+            continue;
+        }
+        let Some((label, limit)) = policy.limit_for(&metadata.dataset_type) else {
+            continue;
+        };
+        let Ok(downloaded) = DateTime::parse_from_rfc3339(&metadata.downloaded_at) else {
+            continue;
+        };
+        let age = now.signed_duration_since(downloaded.with_timezone(&Utc));
+        if age <= limit {
+            continue;
+        }
+        if metadata.pinned && !include_pinned {
+            report.pinned.push(specifier_for(metadata));
+            continue;
+        }
+        report.expired.push(RetentionEntry {
+            specifier: specifier_for(metadata),
+            downloaded_at: metadata.downloaded_at.clone(),
+            age_days: age.num_days(),
+            policy: label.clone(),
+            bytes: path_size(Path::new(&metadata.resolved_path)).unwrap_or(0),
+            path: metadata.resolved_path.clone(),
+        });
+        due.push((path.clone(), metadata.clone(), label));
+    }
+    if dry_run || due.is_empty() {
+        return Ok(report);
+    }
+
+    let targets = due
+        .iter()
+        .map(|(_, metadata, _)| {
+            AuditTarget::from_metadata(metadata, Some(audit::refetch_hint(metadata)))
+        })
+        .collect::<Vec<_>>();
+    let record_paths = records
+        .iter()
+        .map(|(path, _)| path.as_std_path().to_path_buf())
+        .collect::<HashSet<_>>();
+    let expired_at = now.to_rfc3339();
+    for (path, mut metadata, label) in due {
+        let files = delete_data(store, &metadata, &record_paths)?;
+        metadata.status = Some(STATUS_EXPIRED.to_string());
+        metadata.expiry = Some(Expiry {
+            expired_at: expired_at.clone(),
+            policy: label,
+            bytes: files.iter().map(|file| file.bytes).sum(),
+            files,
+        });
+        Store::write_metadata(&path, &metadata)?;
+    }
+    audit::append(
+        store.project_root(),
+        &AuditRecord::new("expire", targets, Vec::new()),
+    )?;
+    Ok(report)
+}
+
+/// Deletes the files behind a record, sparing metadata records that live in
+/// the same directory (knowledge bases), and returns what was deleted.
+fn delete_data(
+    store: &Store,
+    metadata: &Metadata,
+    record_paths: &HashSet<PathBuf>,
+) -> Result<Vec<ExpiredFile>, KiraError> {
+    let root = store.project_root();
+    let resolved = Utf8PathBuf::from(&metadata.resolved_path);
+    if !resolved.starts_with(root) || resolved == root || resolved == root.join("metadata") {
+        return Err(KiraError::Filesystem(format!(
+            "refusing to expire {resolved}: not a dataset in the project store"
+        )));
+    }
+    let path = resolved.as_std_path();
+    let files = if path.is_file() {
+        vec![path.to_path_buf()]
+    } else if path.is_dir() {
+        walk_dir(path)?
+            .into_iter()
+            .filter(|entry| entry.is_file())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut expired = Vec::new();
+    for file in files {
+        if record_paths.contains(&file) {
+            continue;
+        }
+        let bytes = fs::metadata(&file)
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?
+            .len();
+        let sha256 = sha256_file(&file)?;
+        let relative = file
+            .strip_prefix(path)
+            .ok()
+            .filter(|relative| !relative.as_os_str().is_empty())
+            .or_else(|| file.file_name().map(Path::new))
+            .unwrap_or(&file)
+            .to_string_lossy()
+            .replace('\\', "/");
+        fs::remove_file(&file).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        expired.push(ExpiredFile {
+            path: relative,
+            bytes,
+            sha256,
+        });
+    }
+    expired.sort_by(|a, b| a.path.cmp(&b.path));
+    if path.is_dir() {
+        prune_empty_dirs(path);
+    } else if let Some(parent) = resolved.parent().filter(|parent| *parent != root) {
+        prune_empty_dirs(parent.as_std_path());
+    }
+    Ok(expired)
+}
+
+/// Removes empty directories under and including `dir`, deepest first.
+fn prune_empty_dirs(dir: &Path) {
+    let mut dirs = walk_dir(dir)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|entry| entry.is_dir())
+        .collect::<Vec<_>>();
+    dirs.sort_by_key(|entry| std::cmp::Reverse(entry.components().count()));
+    for entry in dirs {
+        let _ = fs::remove_dir(entry);
+    }
+    let _ = fs::remove_dir(dir);
+}
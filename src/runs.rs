@@ -0,0 +1,298 @@
+//! Run manifests.
+//!
+//! Every `kira-bm fetch` writes `runs/<timestamp>-<rand>.json` into the
+//! project store: the invocation, the config it read, the fetch result or
+//! the error that stopped it, how long it took and which registries it
+//! talked to. Only the newest manifests are kept. Secrets never reach a
+//! manifest: arguments and config keys that look like credentials are
+//! redacted, and endpoints are recorded without their query.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::time::SystemTime;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::app::FetchResult;
+use crate::config::Config;
+use crate::error::{ErrorReport, KiraError};
+use crate::fs_util::to_canonical_json;
+use crate::http::redact_credentials;
+use crate::store::Store;
+
+pub const RUNS_DIR: &str = "runs";
+pub const DEFAULT_RUNS_KEPT: usize = 50;
+pub const REDACTED: &str = "<redacted>";
+
+/// Names of arguments and config keys whose values are never recorded.
+const SECRET_MARKERS: [&str; 4] = ["key", "token", "secret", "password"];
+
+/// What a fetch was asked to do, captured before it starts.
+#[derive(Debug, Clone)]
+pub struct RunInvocation {
+    pub started_at: DateTime<Utc>,
+    /// Command-line arguments after the program name.
+    pub arguments: Vec<String>,
+    pub specifiers: Vec<String>,
+    pub config: Option<Value>,
+}
+
+impl RunInvocation {
+    pub fn new(arguments: Vec<String>, specifiers: Vec<String>, config: Option<&Config>) -> Self {
+        Self {
+            started_at: Utc::now(),
+            arguments: redact_arguments(arguments),
+            specifiers,
+            config: config.map(config_snapshot),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RunManifest {
+    pub id: String,
+    pub started_at: String,
+    pub duration_ms: u64,
+    pub tool_version: String,
+    pub arguments: Vec<String>,
+    pub specifiers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<Value>,
+    /// The fetch result, absent when the fetch failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorReport>,
+    /// Registries the fetched items came from.
+    #[serde(default)]
+    pub registries: Vec<String>,
+    /// Origins (`scheme://host`) of every downloaded URL.
+    #[serde(default)]
+    pub endpoints: Vec<String>,
+}
+
+/// One line of `kira-bm runs list`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunSummary {
+    pub id: String,
+    pub started_at: String,
+    pub duration_ms: u64,
+    pub specifiers: Vec<String>,
+    pub items: usize,
+    pub failed: bool,
+}
+
+impl RunManifest {
+    pub fn finish(
+        invocation: RunInvocation,
+        outcome: &Result<FetchResult, KiraError>,
+    ) -> Result<Self, KiraError> {
+        let finished = Utc::now();
+        let (result, error) = match outcome {
+            Ok(result) => (
+                Some(
+                    serde_json::to_value(result)
+                        .map_err(|err| KiraError::Filesystem(err.to_string()))?,
+                ),
+                None,
+            ),
+            Err(err) => (None, Some(ErrorReport::from(err))),
+        };
+        let items = outcome.as_ref().map(|result| result.items.as_slice());
+        let items = items.unwrap_or_default();
+        let registries = items
+            .iter()
+            .map(|item| item.source.clone())
+            .collect::<BTreeSet<_>>();
+        let endpoints = items
+            .iter()
+            .filter_map(|item| item.download.as_ref())
+            .flat_map(|download| &download.source_url)
+            .filter_map(|url| origin(url))
+            .collect::<BTreeSet<_>>();
+        Ok(Self {
+            id: run_id(invocation.started_at),
+            started_at: invocation.started_at.to_rfc3339(),
+            duration_ms: (finished - invocation.started_at).num_milliseconds().max(0) as u64,
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            arguments: invocation.arguments,
+            specifiers: invocation.specifiers,
+            config: invocation.config,
+            result,
+            error,
+            registries: registries.into_iter().collect(),
+            endpoints: endpoints.into_iter().collect(),
+        })
+    }
+
+    pub fn summary(&self) -> RunSummary {
+        RunSummary {
+            id: self.id.clone(),
+            started_at: self.started_at.clone(),
+            duration_ms: self.duration_ms,
+            specifiers: self.specifiers.clone(),
+            items: self
+                .result
+                .as_ref()
+                .and_then(|result| result.get("items"))
+                .and_then(Value::as_array)
+                .map_or(0, Vec::len),
+            failed: self.error.is_some(),
+        }
+    }
+}
+
+/// Writes `manifest` under `root`, then deletes the oldest manifests beyond
+/// `keep`.
+pub fn write(
+    root: &Utf8Path,
+    manifest: &RunManifest,
+    keep: usize,
+) -> Result<Utf8PathBuf, KiraError> {
+    let path = root.join(RUNS_DIR).join(format!("{}.json", manifest.id));
+    Store::write_bytes_atomic(&path, &to_canonical_json(manifest)?)?;
+    let ids = ids(root)?;
+    for id in &ids[..ids.len().saturating_sub(keep)] {
+        // A concurrent fetch may have pruned it already.
+        match fs::remove_file(root.join(RUNS_DIR).join(format!("{id}.json")).as_std_path()) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                return Err(KiraError::Filesystem(err.to_string()));
+            }
+            _ => {}
+        }
+    }
+    Ok(path)
+}
+
+/// Every manifest under `root`, oldest first. Files that are not manifests
+/// are skipped.
+pub fn list(root: &Utf8Path) -> Result<Vec<RunManifest>, KiraError> {
+    Ok(ids(root)?
+        .iter()
+        .filter_map(|id| show(root, id).ok())
+        .collect())
+}
+
+pub fn show(root: &Utf8Path, id: &str) -> Result<RunManifest, KiraError> {
+    let id = id.strip_suffix(".json").unwrap_or(id);
+    if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+        return Err(KiraError::RunNotFound(id.to_string()));
+    }
+    let path = root.join(RUNS_DIR).join(format!("{id}.json"));
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(KiraError::RunNotFound(id.to_string()));
+        }
+        Err(err) => return Err(KiraError::Filesystem(format!("{path}: {err}"))),
+    };
+    serde_json::from_slice(&bytes).map_err(|err| KiraError::Filesystem(format!("{path}: {err}")))
+}
+
+/// Manifest ids under `root`, oldest first: ids start with their UTC
+/// timestamp, so they sort by age.
+fn ids(root: &Utf8Path) -> Result<Vec<String>, KiraError> {
+    let entries = match fs::read_dir(root.join(RUNS_DIR).as_std_path()) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(KiraError::Filesystem(err.to_string())),
+    };
+    let mut ids = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_suffix(".json"))
+                .map(str::to_string)
+        })
+        .collect::<Vec<_>>();
+    ids.sort();
+    Ok(ids)
+}
+
+/// `20261015T093012.123456Z-4f2a9c`: the start time to the microsecond, then
+/// six hex digits that keep runs started together apart.
+fn run_id(started_at: DateTime<Utc>) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos())
+        .unwrap_or(0);
+    let salt = (nanos ^ std::process::id().rotate_left(13)) & 0xff_ffff;
+    format!("{}-{salt:06x}", started_at.format("%Y%m%dT%H%M%S%.6fZ"))
+}
+
+fn origin(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let host = url.host_str()?;
+    Some(match url.port() {
+        Some(port) => format!("{}://{host}:{port}", url.scheme()),
+        None => format!("{}://{host}", url.scheme()),
+    })
+}
+
+fn is_secret(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SECRET_MARKERS.iter().any(|marker| name.contains(marker))
+}
+
+/// `args` with the values of credential-like options replaced, in both the
+/// `--name value` and `--name=value` forms, and URL passwords (a proxy's)
+/// masked.
+pub fn redact_arguments(args: Vec<String>) -> Vec<String> {
+    let mut redacted = Vec::with_capacity(args.len());
+    let mut hide_next = false;
+    for arg in args {
+        if hide_next {
+            redacted.push(REDACTED.to_string());
+            hide_next = false;
+            continue;
+        }
+        match arg.strip_prefix("--") {
+            Some(option) => match option.split_once('=') {
+                Some((name, _)) if is_secret(name) => {
+                    redacted.push(format!("--{name}={REDACTED}"));
+                }
+                Some((name, value)) => {
+                    redacted.push(format!("--{name}={}", redact_credentials(value)));
+                }
+                None => {
+                    hide_next = is_secret(option);
+                    redacted.push(arg);
+                }
+            },
+            None => redacted.push(redact_credentials(&arg)),
+        }
+    }
+    redacted
+}
+
+/// The config as read, with credential-like keys redacted and URL
+/// passwords masked at any depth.
+pub fn config_snapshot(config: &Config) -> Value {
+    let mut value = serde_json::to_value(config).unwrap_or(Value::Null);
+    redact_value(&mut value);
+    value
+}
+
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret(key) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        Value::String(text) => *text = redact_credentials(text),
+        _ => {}
+    }
+}
@@ -0,0 +1,223 @@
+//! JSON Schemas of the `--non-interactive` results, printed by
+//! `kira-bm schema <command>`.
+//!
+//! Every result document carries an `output_version`. Bump a type's
+//! [`OutputDocument::OUTPUT_VERSION`] whenever a field is removed, renamed
+//! or changes type; adding an optional field does not need a bump. The
+//! snapshots under `tests/fixtures/schema/` fail until they are regenerated.
+
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use schemars::generate::SchemaSettings;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::app::{
+    ClearResult, ConvertResult, DoiDatasets, FetchResult, FindResult, GcResult, ImportResult,
+    InfoMatches, InfoResult, InitResult, ListResult, PinMatches, PinResult,
+};
+use crate::config::ConfigSettings;
+use crate::control::ControlReport;
+use crate::doctor::DoctorReport;
+use crate::error::ErrorReport;
+use crate::index::{CacheIndex, CacheUsage, ReindexReport};
+use crate::ping::PingReport;
+use crate::retention::RetentionReport;
+use crate::runs::RunManifest;
+use crate::srr::ToolsReport;
+use crate::stats::CacheStats;
+use crate::store::DedupReport;
+
+/// A top-level `--non-interactive` result.
+pub trait OutputDocument: Serialize + JsonSchema {
+    const OUTPUT_VERSION: u32;
+}
+
+macro_rules! output_documents {
+    ($($document:ty => $version:literal),* $(,)?) => {
+        $(impl OutputDocument for $document {
+            const OUTPUT_VERSION: u32 = $version;
+        })*
+    };
+}
+
+output_documents! {
+    FetchResult => 1,
+    ListResult => 1,
+    FindResult => 1,
+    InfoResult => 1,
+    InfoMatches => 1,
+    DoiDatasets => 1,
+    ConvertResult => 1,
+    ImportResult => 1,
+    ClearResult => 1,
+    InitResult => 1,
+    PinResult => 1,
+    PinMatches => 1,
+    GcResult => 1,
+    CacheStats => 1,
+    CacheUsage => 1,
+    CacheIndex => 1,
+    ReindexReport => 1,
+    DedupReport => 1,
+    ControlReport => 1,
+    RunManifest => 1,
+    RetentionReport => 1,
+    DoctorReport => 1,
+    PingReport => 1,
+    ToolsReport => 1,
+    ConfigValidation => 1,
+    ConfigSettings => 1,
+    ErrorReport => 1,
+}
+
+/// A result as printed: its fields next to its `output_version`.
+#[derive(Serialize, JsonSchema)]
+pub struct Versioned<'a, T: OutputDocument> {
+    pub output_version: u32,
+    #[serde(flatten)]
+    pub result: &'a T,
+}
+
+impl<'a, T: OutputDocument> Versioned<'a, T> {
+    pub fn new(result: &'a T) -> Self {
+        Self {
+            output_version: T::OUTPUT_VERSION,
+            result,
+        }
+    }
+}
+
+/// What `config validate` prints for a valid file.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ConfigValidation {
+    pub path: String,
+    pub valid: bool,
+}
+
+/// The commands whose output has a schema, named as `kira-bm schema`
+/// takes them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SchemaCommand {
+    /// `fetch` and `add`
+    Fetch,
+    List,
+    /// `list --global`
+    ListGlobal,
+    Find,
+    Info,
+    /// `info doi:<DOI> --datasets`
+    InfoDatasets,
+    /// `info <type>:<glob>`
+    InfoPattern,
+    Convert,
+    Import,
+    Clear,
+    Init,
+    /// `pin` and `unpin`
+    Pin,
+    /// `pin <type>:<glob>` and `unpin <type>:<glob>`
+    PinPattern,
+    Gc,
+    CacheStats,
+    /// `cache stats --by-project`
+    CacheStatsByProject,
+    CacheDedup,
+    CacheReindex,
+    /// `batch status`, `batch pause` and `batch resume`
+    Batch,
+    RunsShow,
+    RetentionApply,
+    Doctor,
+    Ping,
+    ToolsCheck,
+    ConfigValidate,
+    ConfigShow,
+    /// The error document written to stderr
+    Error,
+}
+
+impl SchemaCommand {
+    /// The command line the schema describes.
+    pub fn command(self) -> &'static str {
+        match self {
+            Self::Fetch => "fetch",
+            Self::List => "list",
+            Self::ListGlobal => "list --global",
+            Self::Find => "find",
+            Self::Info => "info",
+            Self::InfoDatasets => "info --datasets",
+            Self::InfoPattern => "info <type>:<glob>",
+            Self::Convert => "convert",
+            Self::Import => "import",
+            Self::Clear => "clear",
+            Self::Init => "init",
+            Self::Pin => "pin",
+            Self::PinPattern => "pin <type>:<glob>",
+            Self::Gc => "gc",
+            Self::CacheStats => "cache stats",
+            Self::CacheStatsByProject => "cache stats --by-project",
+            Self::CacheDedup => "cache dedup",
+            Self::CacheReindex => "cache reindex",
+            Self::Batch => "batch",
+            Self::RunsShow => "runs show",
+            Self::RetentionApply => "retention apply",
+            Self::Doctor => "doctor",
+            Self::Ping => "ping",
+            Self::ToolsCheck => "tools check",
+            Self::ConfigValidate => "config validate",
+            Self::ConfigShow => "config show",
+            Self::Error => "error",
+        }
+    }
+
+    pub fn schema(self) -> Value {
+        let mut schema = match self {
+            Self::Fetch => document::<FetchResult>(),
+            Self::List => document::<ListResult>(),
+            Self::ListGlobal => document::<CacheIndex>(),
+            Self::Find => document::<FindResult>(),
+            Self::Info => document::<InfoResult>(),
+            Self::InfoDatasets => document::<DoiDatasets>(),
+            Self::InfoPattern => document::<InfoMatches>(),
+            Self::Convert => document::<ConvertResult>(),
+            Self::Import => document::<ImportResult>(),
+            Self::Clear => document::<ClearResult>(),
+            Self::Init => document::<InitResult>(),
+            Self::Pin => document::<PinResult>(),
+            Self::PinPattern => document::<PinMatches>(),
+            Self::Gc => document::<GcResult>(),
+            Self::CacheStats => document::<CacheStats>(),
+            Self::CacheStatsByProject => document::<CacheUsage>(),
+            Self::CacheDedup => document::<DedupReport>(),
+            Self::CacheReindex => document::<ReindexReport>(),
+            Self::Batch => document::<ControlReport>(),
+            Self::RunsShow => document::<RunManifest>(),
+            Self::RetentionApply => document::<RetentionReport>(),
+            Self::Doctor => document::<DoctorReport>(),
+            Self::Ping => document::<PingReport>(),
+            Self::ToolsCheck => document::<ToolsReport>(),
+            Self::ConfigValidate => document::<ConfigValidation>(),
+            Self::ConfigShow => document::<ConfigSettings>(),
+            Self::Error => document::<ErrorReport>(),
+        };
+        schema["title"] = format!("kira-bm {} output", self.command()).into();
+        // Sorted keys keep the printed schema stable as fields are moved.
+        schema.sort_all_objects();
+        schema
+    }
+}
+
+fn document<T: OutputDocument>() -> Value {
+    // Fields skipped when empty are optional in what is printed.
+    let root = SchemaSettings::default()
+        .for_serialize()
+        .into_generator()
+        .into_root_schema_for::<Versioned<T>>();
+    let mut schema = serde_json::to_value(root).unwrap_or_default();
+    if let Some(object) = schema.as_object_mut() {
+        object.remove("description");
+    }
+    schema["properties"]["output_version"]["const"] = T::OUTPUT_VERSION.into();
+    schema
+}
@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::geo::{extract_organism, extract_sample_title, extract_series_title};
+use crate::store::Metadata;
+
+const FILE_NAME_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchField {
+    Id,
+    Title,
+    Organism,
+    FileName,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchDocument {
+    pub dataset_type: String,
+    pub id: String,
+    pub titles: Vec<String>,
+    pub organisms: Vec<String>,
+    pub file_names: Vec<String>,
+    pub project_path: Option<String>,
+    pub cache_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct SearchMatch {
+    pub dataset_type: String,
+    pub id: String,
+    pub matched_field: SearchField,
+    pub matched_text: String,
+    pub score: u32,
+    pub project_path: Option<String>,
+    pub cache_path: Option<String>,
+}
+
+pub fn search(documents: &[SearchDocument], pattern: &str) -> Vec<SearchMatch> {
+    let needle = pattern.trim().to_lowercase();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    let mut matches = documents
+        .iter()
+        .filter_map(|doc| {
+            let (field, text, score) = best_match(doc, &needle)?;
+            Some(SearchMatch {
+                dataset_type: doc.dataset_type.clone(),
+                id: doc.id.clone(),
+                matched_field: field,
+                matched_text: text,
+                score,
+                project_path: doc.project_path.clone(),
+                cache_path: doc.cache_path.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.dataset_type.cmp(&b.dataset_type))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    matches
+}
+
+fn best_match(doc: &SearchDocument, needle: &str) -> Option<(SearchField, String, u32)> {
+    let id = doc.id.to_lowercase();
+    let specifier = format!("{}:{}", doc.dataset_type, id);
+    if id == needle || specifier == needle {
+        return Some((SearchField::Id, doc.id.clone(), 100));
+    }
+
+    let mut best: Option<(SearchField, String, u32)> = None;
+    let mut consider = |field: SearchField, text: &str, score: u32| {
+        if best.as_ref().is_none_or(|(_, _, current)| score > *current) {
+            best = Some((field, text.to_string(), score));
+        }
+    };
+
+    if id.contains(needle) {
+        consider(SearchField::Id, &doc.id, 80);
+    }
+    for title in &doc.titles {
+        if let Some(score) = text_score(title, needle, 60, 50) {
+            consider(SearchField::Title, title, score);
+        }
+    }
+    for organism in &doc.organisms {
+        if let Some(score) = text_score(organism, needle, 45, 40) {
+            consider(SearchField::Organism, organism, score);
+        }
+    }
+    for name in &doc.file_names {
+        if name.to_lowercase().contains(needle) {
+            consider(SearchField::FileName, name, 20);
+        }
+    }
+    best
+}
+
+fn text_score(text: &str, needle: &str, word: u32, substring: u32) -> Option<u32> {
+    let lower = text.to_lowercase();
+    if !lower.contains(needle) {
+        return None;
+    }
+    let is_word = lower
+        .split(|ch: char| !ch.is_alphanumeric())
+        .any(|token| token == needle);
+    Some(if is_word { word } else { substring })
+}
+
+pub fn collect_documents(project: Vec<Metadata>, cache: Vec<Metadata>) -> Vec<SearchDocument> {
+    let mut docs = BTreeMap::<(String, String), SearchDocument>::new();
+    for (entry, is_project) in project
+        .into_iter()
+        .map(|entry| (entry, true))
+        .chain(cache.into_iter().map(|entry| (entry, false)))
+    {
+        let key = (entry.dataset_type.clone(), entry.id.clone());
+        let doc = docs.entry(key).or_insert_with(|| SearchDocument {
+            dataset_type: entry.dataset_type.clone(),
+            id: entry.id.clone(),
+            ..SearchDocument::default()
+        });
+        if is_project {
+            doc.project_path = Some(entry.resolved_path.clone());
+        } else {
+            doc.cache_path = Some(entry.resolved_path.clone());
+        }
+        if doc.titles.is_empty() && doc.organisms.is_empty() && doc.file_names.is_empty() {
+            load_details(doc, Path::new(&entry.resolved_path));
+        }
+    }
+    docs.into_values().collect()
+}
+
+fn load_details(doc: &mut SearchDocument, resolved_path: &Path) {
+    let dir = if resolved_path.is_file() {
+        resolved_path.parent().unwrap_or(resolved_path)
+    } else {
+        resolved_path
+    };
+    match doc.dataset_type.as_str() {
+        "protein" => {
+            if let Some(value) = read_json(&dir.join("metadata.json")) {
+                push_str(&mut doc.titles, &value["title"]);
+            }
+        }
+        "uniprot" => {
+            if let Some(value) = read_json(&dir.join("metadata.json")) {
+                push_str(&mut doc.titles, &value["protein_name"]);
+                if let Some(genes) = value["gene_names"].as_array() {
+                    for gene in genes {
+                        push_str(&mut doc.titles, gene);
+                    }
+                }
+                push_str(&mut doc.organisms, &value["organism"]);
+            }
+        }
+        "uniprot-proteome" => {
+            if let Some(value) = read_json(&dir.join("metadata.json")) {
+                push_str(&mut doc.titles, &value["description"]);
+                push_str(&mut doc.organisms, &value["organism"]);
+            }
+        }
+        "expression" | "expression10x" | "expressionsample" => {
+            if let Ok(soft) = fs::read_to_string(dir.join("metadata").join("geo_soft.txt")) {
+                if let Some(title) =
+                    extract_series_title(&soft).or_else(|| extract_sample_title(&soft))
+                {
+                    doc.titles.push(title);
+                }
+                if let Some(organism) = extract_organism(&soft) {
+                    doc.organisms.push(organism);
+                }
+            }
+        }
+        "doi" => {
+            if let Some(value) = read_json(&dir.join("doi_resolution.json")) {
+                push_str(&mut doc.titles, &value["source"]["title"]);
+            }
+        }
+        _ => {}
+    }
+    doc.file_names = file_names(dir);
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn push_str(target: &mut Vec<String>, value: &Value) {
+    if let Some(text) = value.as_str()
+        && !text.is_empty()
+    {
+        target.push(text.to_string());
+    }
+}
+
+fn file_names(dir: &Path) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut stack = vec![PathBuf::from(dir)];
+    'walk: while let Some(path) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&path) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                names.push(name.to_string());
+                if names.len() >= FILE_NAME_LIMIT {
+                    break 'walk;
+                }
+            }
+        }
+    }
+    names.sort();
+    names
+}
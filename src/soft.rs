@@ -0,0 +1,178 @@
+//! Parsing of GEO SOFT text.
+//!
+//! SOFT is a line format: `^ENTITY = accession` opens a series, sample or
+//! platform, and `!Entity_attribute = value` lines describe it. Attributes
+//! may repeat; a multi-paragraph `!Series_summary`, for instance, comes as
+//! one line per paragraph. [`SoftRecord::parse`] reads a series family file
+//! (`GSE..._family.soft`) or a single sample's SOFT into typed fields, and
+//! the `extract_*` helpers in [`crate::geo`] read their field from it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::GeoSeriesAccession;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeriesRelation {
+    pub relation: String,
+    pub accession: String,
+}
+
+/// What a SOFT document says about a series and its samples.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SoftRecord {
+    pub series_accession: Option<String>,
+    pub series_title: Option<String>,
+    /// The `!Series_summary` paragraphs, joined by newlines.
+    pub summary: Option<String>,
+    /// The first organism given for the series, or failing that for a
+    /// sample.
+    pub organism: Option<String>,
+    /// Platform accessions (`GPL...`), in order of first mention.
+    pub platforms: Vec<String>,
+    /// The series' `!Series_sample_id` list, which a family file may not
+    /// describe in full.
+    pub sample_ids: Vec<String>,
+    /// The `^SAMPLE` entities, in SOFT order. A sample's own SOFT without
+    /// the header still yields one.
+    pub samples: Vec<SoftSample>,
+    pub relations: Vec<SeriesRelation>,
+    /// Every supplementary file URL, series and samples alike.
+    pub supplementary_urls: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SoftSample {
+    pub accession: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organism: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<String>,
+    /// Series the sample belongs to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub series: Vec<GeoSeriesAccession>,
+}
+
+enum Entity {
+    Series,
+    Sample,
+    Other,
+}
+
+impl SoftRecord {
+    pub fn parse(text: &str) -> Self {
+        let mut record = Self::default();
+        let mut summary = Vec::new();
+        let mut entity = Entity::Other;
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            if key.contains("supplementary_file") {
+                if !value.is_empty() && !value.eq_ignore_ascii_case("none") {
+                    record.supplementary_urls.push(value.to_string());
+                }
+                continue;
+            }
+            match key {
+                "^SERIES" => {
+                    entity = Entity::Series;
+                    set_once(&mut record.series_accession, value);
+                }
+                "^SAMPLE" => {
+                    entity = Entity::Sample;
+                    record.samples.push(SoftSample {
+                        accession: value.to_string(),
+                        ..SoftSample::default()
+                    });
+                }
+                "^PLATFORM" => {
+                    entity = Entity::Other;
+                    push_unique(&mut record.platforms, value);
+                }
+                _ if key.starts_with('^') => entity = Entity::Other,
+                "!Series_geo_accession" => set_once(&mut record.series_accession, value),
+                "!Series_title" => set_once(&mut record.series_title, value),
+                "!Series_summary" if !value.is_empty() => summary.push(value),
+                "!Series_platform_id" => push_unique(&mut record.platforms, value),
+                "!Series_sample_id" => push_unique(&mut record.sample_ids, value),
+                "!Series_relation" => record.relations.extend(series_relation(value)),
+                _ if key.starts_with("!Series_organism") => {
+                    set_once(&mut record.organism, value);
+                }
+                _ if key.starts_with("!Sample_") => {
+                    if !matches!(entity, Entity::Sample) {
+                        entity = Entity::Sample;
+                        record.samples.push(SoftSample::default());
+                    }
+                    let Some(sample) = record.samples.last_mut() else {
+                        continue;
+                    };
+                    match key {
+                        "!Sample_geo_accession" if sample.accession.is_empty() => {
+                            sample.accession = value.to_string();
+                        }
+                        "!Sample_title" => set_once(&mut sample.title, value),
+                        "!Sample_organism_ch1" => {
+                            set_once(&mut sample.organism, value);
+                            set_once(&mut record.organism, value);
+                        }
+                        "!Sample_platform_id" => {
+                            set_once(&mut sample.platform, value);
+                            push_unique(&mut record.platforms, value);
+                        }
+                        "!Sample_series_id" => {
+                            if let Ok(series) = value.parse::<GeoSeriesAccession>()
+                                && !sample.series.contains(&series)
+                            {
+                                sample.series.push(series);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !summary.is_empty() {
+            record.summary = Some(summary.join("\n"));
+        }
+        record
+    }
+
+    /// How many samples the series has: its sample id list, or the samples
+    /// described when it has none.
+    pub fn sample_count(&self) -> usize {
+        if self.sample_ids.is_empty() {
+            self.samples.len()
+        } else {
+            self.sample_ids.len()
+        }
+    }
+}
+
+/// A `SuperSeries of: GSE...` or `SubSeries of: GSE...` relation; other
+/// relations (BioProject, SRA) are not series.
+fn series_relation(value: &str) -> Option<SeriesRelation> {
+    let (relation, accession) = value.split_once(':')?;
+    let (relation, accession) = (relation.trim(), accession.trim());
+    ((relation == "SuperSeries of" || relation == "SubSeries of") && accession.starts_with("GSE"))
+        .then(|| SeriesRelation {
+            relation: relation.to_string(),
+            accession: accession.to_string(),
+        })
+}
+
+fn set_once(field: &mut Option<String>, value: &str) {
+    if field.is_none() && !value.is_empty() {
+        *field = Some(value.to_string());
+    }
+}
+
+fn push_unique(values: &mut Vec<String>, value: &str) {
+    if !value.is_empty() && !values.iter().any(|known| known == value) {
+        values.push(value.to_string());
+    }
+}
@@ -1,16 +1,249 @@
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::Duration;
 
+use regex::Regex;
+use schemars::JsonSchema;
+use serde_json::Value;
+
+use crate::cancel::{self, CancellationToken};
 use crate::domain::SrrId;
 use crate::error::KiraError;
-use crate::fs_util;
-use serde::Serialize;
+use crate::fs_util::{self, ZipLimits};
+use crate::http::{HttpSettings, rebase_url};
+use serde::{Deserialize, Serialize};
 
+/// What `--version` printed for each tool, and the version read from it.
 #[derive(Debug, Clone, Serialize)]
 pub struct ToolInfo {
     pub datasets: Option<String>,
     pub sra_toolkit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub datasets_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sra_toolkit_version: Option<String>,
+}
+
+impl ToolInfo {
+    pub fn from_output(datasets: Option<String>, sra_toolkit: Option<String>) -> Self {
+        let version = |output: &Option<String>| {
+            output
+                .as_deref()
+                .and_then(parse_version)
+                .map(|(major, minor, patch)| format!("{major}.{minor}.{patch}"))
+        };
+        Self {
+            datasets_version: version(&datasets),
+            sra_toolkit_version: version(&sra_toolkit),
+            datasets,
+            sra_toolkit,
+        }
+    }
+}
+
+/// Oldest SRA Toolkit release whose fasterq-dump reads current runs.
+pub const MIN_SRA_TOOLKIT: (u64, u64) = (3, 0);
+/// Oldest NCBI datasets CLI release kira-bm drives.
+pub const MIN_DATASETS: (u64, u64) = (16, 0);
+
+const SRA_TOOLKIT_INSTALL: &str =
+    "https://github.com/ncbi/sra-tools/wiki/02.-Installing-SRA-Toolkit";
+const DATASETS_INSTALL: &str =
+    "https://www.ncbi.nlm.nih.gov/datasets/docs/v2/command-line-tools/download-and-install/";
+
+/// `major.minor.patch` out of a tool's `--version` output. sra-tools 3.x
+/// prints `fasterq-dump : 3.0.10` (some builds `"fasterq-dump" version
+/// 3.1.1`), 2.x builds often the full path of the binary, and the datasets
+/// CLI `datasets version: 16.22.1`. The number after the last ` : ` or
+/// `version` wins over one in a path; a missing patch reads as 0.
+pub fn parse_version(output: &str) -> Option<(u64, u64, u64)> {
+    let pattern = Regex::new(r"(\d+)\.(\d+)(?:\.(\d+))?").ok()?;
+    let labelled = output.lines().find_map(|line| {
+        line.rsplit_once(" : ")
+            .or_else(|| line.rsplit_once("version"))
+            .map(|(_, version)| version)
+    });
+    let captures = labelled
+        .and_then(|version| pattern.captures(version))
+        .or_else(|| pattern.captures(output))?;
+    let part = |index| {
+        captures
+            .get(index)
+            .map_or(Some(0), |value: regex::Match<'_>| {
+                value.as_str().parse().ok()
+            })
+    };
+    Some((part(1)?, part(2)?, part(3)?))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolCheckStatus {
+    Ok,
+    /// Older than the minimum supported release.
+    Outdated,
+    /// Found, but `--version` failed or printed no version.
+    Unknown,
+    Missing,
+}
+
+impl std::fmt::Display for ToolCheckStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ToolCheckStatus::Ok => "ok",
+            ToolCheckStatus::Outdated => "outdated",
+            ToolCheckStatus::Unknown => "unknown",
+            ToolCheckStatus::Missing => "missing",
+        })
+    }
+}
+
+/// One external tool as `kira-bm tools check` found it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct ToolCheck {
+    pub tool: String,
+    pub path: Option<String>,
+    /// The parsed `major.minor.patch`.
+    pub version: Option<String>,
+    /// Oldest supported `major.minor`.
+    pub minimum: String,
+    pub status: ToolCheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl ToolCheck {
+    /// Judges `tool` at `path` from what its `--version` printed.
+    pub fn evaluate(
+        tool: &str,
+        path: Option<&Path>,
+        output: Option<&str>,
+        minimum: (u64, u64),
+    ) -> Self {
+        let install = if tool == "datasets" {
+            DATASETS_INSTALL
+        } else {
+            SRA_TOOLKIT_INSTALL
+        };
+        let version = output.and_then(parse_version);
+        let (status, hint) = match (path, version) {
+            (None, _) => (
+                ToolCheckStatus::Missing,
+                Some(format!(
+                    "install {}.{} or newer: {install}",
+                    minimum.0, minimum.1
+                )),
+            ),
+            (Some(_), None) => (
+                ToolCheckStatus::Unknown,
+                Some(format!(
+                    "`{tool} --version` printed no version; check that it runs"
+                )),
+            ),
+            (Some(_), Some((major, minor, _))) if (major, minor) < minimum => (
+                ToolCheckStatus::Outdated,
+                Some(format!(
+                    "upgrade to {}.{} or newer: {install}",
+                    minimum.0, minimum.1
+                )),
+            ),
+            (Some(_), Some(_)) => (ToolCheckStatus::Ok, None),
+        };
+        Self {
+            tool: tool.to_string(),
+            path: path.map(|path| path.display().to_string()),
+            version: version.map(|(major, minor, patch)| format!("{major}.{minor}.{patch}")),
+            minimum: format!("{}.{}", minimum.0, minimum.1),
+            status,
+            hint,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct ToolsReport {
+    pub tools: Vec<ToolCheck>,
+}
+
+/// What ENA reports about a run: its size, how it was sequenced and how
+/// its library was built. ENA sends every field as a string; fields it
+/// leaves empty stay absent.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SrrRunInfo {
+    /// Spots: read pairs for a paired run, reads otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instrument_platform: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instrument_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub library_layout: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub library_strategy: Option<String>,
+}
+
+impl SrrRunInfo {
+    /// Reads the fields out of one ENA `read_run` record.
+    pub fn from_ena(record: &Value) -> Self {
+        let text = |key: &str| {
+            record
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let count = |key: &str| match record.get(key) {
+            Some(Value::Number(number)) => number.as_u64(),
+            Some(Value::String(value)) => value.trim().parse().ok(),
+            _ => None,
+        };
+        Self {
+            read_count: count("read_count"),
+            base_count: count("base_count"),
+            instrument_platform: text("instrument_platform"),
+            instrument_model: text("instrument_model"),
+            library_layout: text("library_layout"),
+            library_strategy: text("library_strategy"),
+        }
+    }
+
+    /// `1200000 spots, 240000000 bases, ILLUMINA, PAIRED, RNA-Seq`, leaving
+    /// out what is unknown.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(reads) = self.read_count {
+            parts.push(format!("{reads} spots"));
+        }
+        if let Some(bases) = self.base_count {
+            parts.push(format!("{bases} bases"));
+        }
+        parts.extend(
+            [
+                &self.instrument_platform,
+                &self.library_layout,
+                &self.library_strategy,
+            ]
+            .into_iter()
+            .flatten()
+            .cloned(),
+        );
+        parts.join(", ")
+    }
+}
+
+/// How fasterq-dump runs: its thread count and the directory it keeps its
+/// scratch files in. Unset fields leave the tool's own defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SrrDownloadOptions {
+    pub threads: Option<u32>,
+    pub temp_dir: Option<PathBuf>,
 }
 
 pub trait SrrClient: Send + Sync {
@@ -19,8 +252,23 @@ pub trait SrrClient: Send + Sync {
         id: &SrrId,
         paired: bool,
         destination_dir: &Path,
+        options: &SrrDownloadOptions,
     ) -> Result<Vec<PathBuf>, KiraError>;
     fn tool_info(&self) -> ToolInfo;
+
+    /// The run's ENA run report, for `--metadata-only` fetches.
+    fn fetch_run_info(&self, id: &SrrId) -> Result<Value, KiraError> {
+        Err(KiraError::EnaHttp(format!(
+            "no run info source for srr:{}",
+            id.as_str()
+        )))
+    }
+
+    /// Whether the external tools downloads need are installed. Clients that
+    /// run no tools are always ready.
+    fn tool_status(&self) -> SrrToolStatus {
+        SrrToolStatus::Ready
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -29,11 +277,34 @@ pub enum SrrToolStatus {
     Missing { message: String },
 }
 
+/// How often a running tool is checked for exit while waiting on a token.
+const CHILD_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+const ENA_FILEREPORT: &str = "https://www.ebi.ac.uk/ena/portal/api/filereport";
+/// Run fields stored by `--metadata-only`; `fastq_bytes` sizes the download.
+const RUN_INFO_FIELDS: &str = "run_accession,study_accession,sample_accession,experiment_accession,\
+instrument_platform,instrument_model,library_layout,library_strategy,read_count,base_count,\
+fastq_bytes,fastq_ftp";
+/// fasterq-dump's scratch files and output together take up to this many
+/// times the size of the `.sra` archive it converts.
+pub const SCRATCH_FACTOR: u64 = 10;
+
 #[derive(Clone)]
 pub struct SystemSrrClient {
     datasets: Option<PathBuf>,
     prefetch: Option<PathBuf>,
     fasterq_dump: Option<PathBuf>,
+    vdb_dump: Option<PathBuf>,
+    http: HttpSettings,
+    /// Replaces the ENA host in run-info lookups.
+    base_url: Option<String>,
+    zip_limits: ZipLimits,
+}
+
+impl Default for SystemSrrClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SystemSrrClient {
@@ -42,21 +313,72 @@ impl SystemSrrClient {
             datasets: find_in_path("datasets"),
             prefetch: find_in_path("prefetch"),
             fasterq_dump: find_in_path("fasterq-dump"),
+            vdb_dump: find_in_path("vdb-dump"),
+            http: HttpSettings::from_env(),
+            base_url: None,
+            zip_limits: ZipLimits::default(),
         }
     }
 
-    pub fn tool_status(&self) -> SrrToolStatus {
-        if self.fasterq_dump.is_none() {
-            return SrrToolStatus::Missing {
-                message: "missing fasterq-dump (SRA Toolkit)".to_string(),
-            };
-        }
-        if self.datasets.is_none() && self.prefetch.is_none() {
-            return SrrToolStatus::Missing {
-                message: "missing prefetch or datasets (SRA download tool)".to_string(),
-            };
+    /// Routes ENA lookups, and the SRA tools through their proxy
+    /// variables, the way the registry clients go.
+    pub fn with_http_settings(mut self, http: HttpSettings) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Caps what the packages `datasets` downloads may unpack to.
+    pub fn with_zip_limits(mut self, limits: ZipLimits) -> Self {
+        self.zip_limits = limits;
+        self
+    }
+
+    /// A client using the given tool binaries instead of searching `PATH`.
+    pub fn with_tools(
+        datasets: Option<PathBuf>,
+        prefetch: Option<PathBuf>,
+        fasterq_dump: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            datasets,
+            prefetch,
+            fasterq_dump,
+            vdb_dump: None,
+            http: HttpSettings::from_env(),
+            base_url: None,
+            zip_limits: ZipLimits::default(),
         }
-        SrrToolStatus::Ready
+    }
+
+    /// Looks run info up at `base_url` instead of www.ebi.ac.uk.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    /// Runs `--version` on prefetch, fasterq-dump and datasets and checks
+    /// each against the oldest supported release.
+    pub fn check_tools(&self) -> ToolsReport {
+        let tools = [
+            ("prefetch", &self.prefetch, MIN_SRA_TOOLKIT),
+            ("fasterq-dump", &self.fasterq_dump, MIN_SRA_TOOLKIT),
+            ("datasets", &self.datasets, MIN_DATASETS),
+        ]
+        .into_iter()
+        .map(|(tool, path, minimum)| {
+            let output = path
+                .as_deref()
+                .and_then(|path| tool_version(path, &["--version"]));
+            ToolCheck::evaluate(tool, path.as_deref(), output.as_deref(), minimum)
+        })
+        .collect();
+        ToolsReport { tools }
+    }
+
+    /// Sizes runs with `vdb-dump --info` for the scratch-space check.
+    pub fn with_vdb_dump(mut self, vdb_dump: Option<PathBuf>) -> Self {
+        self.vdb_dump = vdb_dump;
+        self
     }
 
     fn require_fasterq(&self) -> Result<&PathBuf, KiraError> {
@@ -71,18 +393,49 @@ impl SystemSrrClient {
         args: &[String],
         cwd: Option<&Path>,
     ) -> Result<(), KiraError> {
+        cancel::check_current()?;
         let mut cmd = Command::new(program);
-        cmd.args(args);
+        cmd.args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
         if let Some(dir) = cwd {
             cmd.current_dir(dir);
         }
-        let output = cmd
-            .output()
+        let proxy = self.http.proxy();
+        for (name, value) in [("HTTP_PROXY", &proxy.http), ("HTTPS_PROXY", &proxy.https)] {
+            if let Some(value) = value {
+                cmd.env(name, value).env(name.to_ascii_lowercase(), value);
+            }
+        }
+        // A group of its own keeps Ctrl-C away from the tool and lets a
+        // cancellation kill it together with anything it spawned.
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+        let mut child = cmd
+            .spawn()
             .map_err(|err| KiraError::SrrConversion(err.to_string()))?;
-        if output.status.success() {
+        let stderr = child.stderr.take().map(|mut pipe| {
+            std::thread::spawn(move || {
+                let mut text = String::new();
+                let _ = pipe.read_to_string(&mut text);
+                text
+            })
+        });
+        let status = match cancel::current() {
+            Some(token) => wait_cancellable(&mut child, &token)?,
+            None => child
+                .wait()
+                .map_err(|err| KiraError::SrrConversion(err.to_string()))?,
+        };
+        if status.success() {
             return Ok(());
         }
-        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let stderr = stderr
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default()
+            .trim()
+            .to_string();
         let message = if stderr.is_empty() {
             format!("command failed: {}", program.display())
         } else {
@@ -128,8 +481,10 @@ impl SystemSrrClient {
         sra_path: &Path,
         paired: bool,
         out_dir: &Path,
+        options: &SrrDownloadOptions,
     ) -> Result<Vec<PathBuf>, KiraError> {
         let fasterq = self.require_fasterq()?;
+        self.check_scratch_space(sra_path, options.temp_dir.as_deref().unwrap_or(out_dir))?;
         let mut args = vec![
             sra_path.to_string_lossy().to_string(),
             "--outdir".to_string(),
@@ -138,9 +493,45 @@ impl SystemSrrClient {
         if paired {
             args.push("--split-files".to_string());
         }
+        if let Some(threads) = options.threads {
+            args.push("--threads".to_string());
+            args.push(threads.to_string());
+        }
+        if let Some(temp_dir) = &options.temp_dir {
+            fs::create_dir_all(temp_dir).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            args.push("--temp".to_string());
+            args.push(temp_dir.to_string_lossy().to_string());
+        }
         self.run_cmd(fasterq.as_path(), &args, None)?;
         Ok(find_exts(out_dir, "fastq"))
     }
+
+    /// Fails before the conversion starts when `scratch_dir`'s filesystem
+    /// cannot hold it. The run's size comes from `vdb-dump --info` when the
+    /// tool is installed, else from the archive itself; a filesystem whose
+    /// free space cannot be read is not checked.
+    fn check_scratch_space(&self, sra_path: &Path, scratch_dir: &Path) -> Result<(), KiraError> {
+        let run_size = self
+            .vdb_dump
+            .as_ref()
+            .and_then(|vdb_dump| vdb_dump_size(vdb_dump, sra_path))
+            .or_else(|| fs::metadata(sra_path).ok().map(|meta| meta.len()));
+        let Some(run_size) = run_size else {
+            return Ok(());
+        };
+        let Ok(available) = fs_util::available_space(existing_ancestor(scratch_dir)) else {
+            return Ok(());
+        };
+        let required = run_size.saturating_mul(SCRATCH_FACTOR);
+        if available < required {
+            return Err(KiraError::InsufficientScratchSpace {
+                path: scratch_dir.to_path_buf(),
+                required,
+                available,
+            });
+        }
+        Ok(())
+    }
 }
 
 impl SrrClient for SystemSrrClient {
@@ -149,11 +540,90 @@ impl SrrClient for SystemSrrClient {
         id: &SrrId,
         paired: bool,
         destination_dir: &Path,
+        options: &SrrDownloadOptions,
     ) -> Result<Vec<PathBuf>, KiraError> {
         fs::create_dir_all(destination_dir)
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let result = self.download_into(id, paired, destination_dir, options);
+        if matches!(result, Err(KiraError::Cancelled)) {
+            // Partial archives and reads are useless to a later attempt.
+            let _ = fs::remove_dir_all(destination_dir);
+        }
+        result
+    }
+
+    fn tool_info(&self) -> ToolInfo {
+        ToolInfo::from_output(
+            self.datasets
+                .as_ref()
+                .and_then(|path| tool_version(path, &["--version"])),
+            self.fasterq_dump
+                .as_ref()
+                .and_then(|path| tool_version(path, &["--version"])),
+        )
+    }
 
-        if let Some(_) = self.datasets {
+    fn tool_status(&self) -> SrrToolStatus {
+        if self.fasterq_dump.is_none() {
+            return SrrToolStatus::Missing {
+                message: "missing fasterq-dump (SRA Toolkit)".to_string(),
+            };
+        }
+        if self.datasets.is_none() && self.prefetch.is_none() {
+            return SrrToolStatus::Missing {
+                message: "missing prefetch or datasets (SRA download tool)".to_string(),
+            };
+        }
+        SrrToolStatus::Ready
+    }
+
+    fn fetch_run_info(&self, id: &SrrId) -> Result<Value, KiraError> {
+        let client = self
+            .http
+            .client_builder("ena")
+            .user_agent(concat!("kira-bm/", env!("CARGO_PKG_VERSION")))
+            .timeout(self.http.timeouts_for("ena").request)
+            .build()
+            .map_err(|err| KiraError::EnaHttp(err.to_string()))?;
+        let url = reqwest::Url::parse_with_params(
+            &rebase_url(ENA_FILEREPORT, self.base_url.as_deref()),
+            [
+                ("accession", id.as_str()),
+                ("result", "read_run"),
+                ("fields", RUN_INFO_FIELDS),
+                ("format", "json"),
+            ],
+        )
+        .map_err(|err| KiraError::EnaHttp(err.to_string()))?;
+        let response = client
+            .get(url)
+            .send()
+            .map_err(|err| KiraError::EnaHttp(err.to_string()))?;
+        if !response.status().is_success() {
+            return Err(KiraError::EnaHttp(format!(
+                "ENA returned status {} for srr:{}",
+                response.status().as_u16(),
+                id.as_str()
+            )));
+        }
+        let runs: Vec<Value> = response
+            .json()
+            .map_err(|err| KiraError::EnaHttp(err.to_string()))?;
+        runs.into_iter()
+            .next()
+            .ok_or_else(|| KiraError::EnaHttp(format!("ENA has no run {}", id.as_str())))
+    }
+}
+
+impl SystemSrrClient {
+    fn download_into(
+        &self,
+        id: &SrrId,
+        paired: bool,
+        destination_dir: &Path,
+        options: &SrrDownloadOptions,
+    ) -> Result<Vec<PathBuf>, KiraError> {
+        if self.datasets.is_some() {
             let zip_path = destination_dir.join(format!("{}.zip", id.as_str()));
             self.datasets_download(id, &zip_path)?;
             if zip_path.exists() {
@@ -161,35 +631,63 @@ impl SrrClient for SystemSrrClient {
                 fs::create_dir_all(&extract_dir)
                     .map_err(|err| KiraError::Filesystem(err.to_string()))?;
                 fs_util::validate_zip(&zip_path)?;
-                fs_util::extract_zip(&zip_path, &extract_dir)?;
+                fs_util::extract_zip(&zip_path, &extract_dir, &self.zip_limits)?;
                 let fastq_files = find_exts(&extract_dir, "fastq");
                 if !fastq_files.is_empty() {
                     return Ok(fastq_files);
                 }
                 if let Some(sra_path) = find_first_ext(&extract_dir, "sra") {
-                    return self.fasterq_dump(&sra_path, paired, destination_dir);
+                    return self.fasterq_dump(&sra_path, paired, destination_dir, options);
                 }
             }
         }
 
         let sra_path = self.prefetch_download(id, destination_dir)?;
-        self.fasterq_dump(&sra_path, paired, destination_dir)
+        self.fasterq_dump(&sra_path, paired, destination_dir, options)
     }
+}
 
-    fn tool_info(&self) -> ToolInfo {
-        ToolInfo {
-            datasets: self
-                .datasets
-                .as_ref()
-                .and_then(|path| tool_version(path, &["--version"])),
-            sra_toolkit: self
-                .fasterq_dump
-                .as_ref()
-                .and_then(|path| tool_version(path, &["--version"])),
+/// Waits for `child`, killing its process group as soon as `token` fires.
+fn wait_cancellable(
+    child: &mut Child,
+    token: &CancellationToken,
+) -> Result<std::process::ExitStatus, KiraError> {
+    let cancelled = token.cancelled_channel();
+    loop {
+        if let Some(status) = child
+            .try_wait()
+            .map_err(|err| KiraError::SrrConversion(err.to_string()))?
+        {
+            return Ok(status);
+        }
+        match cancelled.recv_timeout(CHILD_POLL_INTERVAL) {
+            Err(RecvTimeoutError::Timeout) if !token.is_cancelled() => {}
+            _ => {
+                kill_process_group(child);
+                return Err(KiraError::Cancelled);
+            }
         }
     }
 }
 
+#[cfg(unix)]
+fn kill_process_group(child: &mut Child) {
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: kill(2) with a negative pid signals the group the child
+        // leads (it was spawned with process_group(0)); no memory is shared.
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    let _ = child.wait();
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
 fn find_in_path(name: &str) -> Option<PathBuf> {
     let path_var = std::env::var_os("PATH")?;
     for path in std::env::split_paths(&path_var) {
@@ -218,6 +716,30 @@ fn tool_version(path: &Path, args: &[&str]) -> Option<String> {
     }
 }
 
+/// The run size `vdb-dump --info` reports, from its `size : 1,234,567` line.
+fn vdb_dump_size(vdb_dump: &Path, sra_path: &Path) -> Option<u64> {
+    let info = tool_version(vdb_dump, &["--info", &sra_path.to_string_lossy()])?;
+    parse_vdb_size(&info)
+}
+
+fn parse_vdb_size(info: &str) -> Option<u64> {
+    info.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        if key.trim() != "size" {
+            return None;
+        }
+        value.trim().replace(',', "").parse().ok()
+    })
+}
+
+/// `path`, or its nearest ancestor that exists yet, for a free-space query
+/// on a directory that will be created later.
+fn existing_ancestor(path: &Path) -> &Path {
+    path.ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(path)
+}
+
 fn find_first_ext(root: &Path, ext: &str) -> Option<PathBuf> {
     let mut stack = vec![root.to_path_buf()];
     while let Some(path) = stack.pop() {
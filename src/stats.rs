@@ -0,0 +1,101 @@
+//! Cache savings.
+//!
+//! A download records how long it took; a later fetch served from the cache
+//! saves that download's bytes and, less the time the cache hit itself
+//! took, its duration. Every saving is added to `stats.json` in the cache
+//! root, which `kira-bm cache stats` reports.
+
+use std::fs;
+use std::io;
+use std::time::Duration;
+
+use camino::Utf8Path;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::KiraError;
+use crate::fs_util::to_canonical_json;
+use crate::store::{DownloadRecord, Store};
+
+pub const STATS_FILE: &str = "stats.json";
+
+/// What one cache hit saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CacheSavings {
+    pub bytes_avoided: u64,
+    /// Absent when the original download predates duration records.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_saved_ms: Option<u64>,
+}
+
+impl CacheSavings {
+    /// Savings of serving `download` from the cache in `elapsed`.
+    pub fn of(download: &DownloadRecord, elapsed: Duration) -> Self {
+        Self {
+            bytes_avoided: download.bytes_written,
+            time_saved_ms: download
+                .duration_ms
+                .map(|duration| duration.saturating_sub(elapsed.as_millis() as u64)),
+        }
+    }
+}
+
+/// Lifetime totals kept in `stats.json`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CacheStats {
+    #[serde(default)]
+    pub cache_hits: u64,
+    #[serde(default)]
+    pub bytes_avoided: u64,
+    #[serde(default)]
+    pub time_saved_ms: u64,
+}
+
+impl CacheStats {
+    fn add(&mut self, savings: &CacheSavings) {
+        self.cache_hits += 1;
+        self.bytes_avoided += savings.bytes_avoided;
+        self.time_saved_ms += savings.time_saved_ms.unwrap_or(0);
+    }
+}
+
+/// The totals under `cache_root`; a cache that never had a hit has none.
+pub fn read(cache_root: &Utf8Path) -> Result<CacheStats, KiraError> {
+    let path = cache_root.join(STATS_FILE);
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        // Under a cache turned off with `/dev/null` there is nothing to read.
+        Err(err)
+            if matches!(
+                err.kind(),
+                io::ErrorKind::NotFound | io::ErrorKind::NotADirectory
+            ) =>
+        {
+            return Ok(CacheStats::default());
+        }
+        Err(err) => return Err(KiraError::Filesystem(format!("{path}: {err}"))),
+    };
+    serde_json::from_slice(&bytes).map_err(|err| KiraError::Filesystem(format!("{path}: {err}")))
+}
+
+/// Adds one hit to the totals. Callers hold the stats lock.
+pub fn record(cache_root: &Utf8Path, savings: &CacheSavings) -> Result<CacheStats, KiraError> {
+    let mut stats = read(cache_root)?;
+    stats.add(savings);
+    Store::write_bytes_atomic(&cache_root.join(STATS_FILE), &to_canonical_json(&stats)?)?;
+    Ok(stats)
+}
+
+/// `850 ms`, `3.4 s`, `2m 05s` or `1h 02m`.
+pub fn millis_to_human(ms: u64) -> String {
+    let secs = ms / 1000;
+    if ms < 1000 {
+        format!("{ms} ms")
+    } else if secs < 60 {
+        format!("{:.1} s", ms as f64 / 1000.0)
+    } else if secs < 3600 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {:02}m", secs / 3600, secs % 3600 / 60)
+    }
+}
@@ -1,49 +1,103 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
 
 use camino::{Utf8Path, Utf8PathBuf};
+use chrono::{DateTime, TimeDelta, Utc};
+use clap::ValueEnum;
 use directories::BaseDirs;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use tempfile::Builder;
 
-use crate::domain::{Doi, GenomeAccession, GeoSeriesAccession, ProteinFormat, ProteinId};
-use crate::domain::{SrrId, UniprotId};
+use crate::app::{ACTION_REFETCHED_CORRUPT, ClearOptions, ProgressSink, VerifyCache};
+use crate::audit::{self, AUDIT_FILE, AuditRecord, AuditTarget, specifier_for};
+use crate::config::CacheDecision;
+use crate::domain::{
+    BioProjectAccession, DatasetSpecifier, Doi, GenomeAccession, GeoSampleAccession,
+    GeoSeriesAccession, ProteinFormat, ProteinId, ProteomeId,
+};
+use crate::domain::{SraExperimentAccession, SrrId, UniprotId};
 use crate::error::KiraError;
+use crate::fs_util::{Digests, link_count, path_size, same_file, sha256_file, to_canonical_json};
+use crate::history::{HISTORY_FILE, HistoryRecord};
+use crate::http::StoredValidators;
+use crate::lock::{DatasetLock, LOCKS_DIR};
+use crate::manifest::{self, MANIFEST_FILE};
+use crate::ncbi::genome_alias;
+use crate::project::Project;
+use crate::runs::RUNS_DIR;
+
+/// Set to [`NO_CACHE_DIR`], every fetch runs as with `--no-cache`; set to
+/// another path, the cache lives there instead of the user cache.
+pub const CACHE_DIR_ENV: &str = "KIRA_BM_CACHE_DIR";
+pub const NO_CACHE_DIR: &str = "/dev/null";
+
+/// The cache of a project store whose user cache cannot be used.
+pub const LOCAL_CACHE_DIR: &str = ".cache";
 
 #[derive(Debug, Clone)]
 pub struct Store {
     project_root: Utf8PathBuf,
-    cache_root: Utf8PathBuf,
+    user_cache_root: Utf8PathBuf,
+    cache_disabled: bool,
+    /// The project-local cache standing in for `user_cache_root`, and why.
+    cache_fallback: OnceLock<(Utf8PathBuf, String)>,
 }
 
 impl Store {
+    /// The store of the current [`Project`] and the user's cache. Nothing
+    /// about the cache is checked here: commands that only read the project
+    /// store work without one, and fetches call [`Store::prepare_cache`].
     pub fn new() -> Result<Self, KiraError> {
-        let cwd = std::env::current_dir().map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        let project_root = Utf8PathBuf::from_path_buf(cwd.join(".kira-bm"))
+        let project_root = Utf8PathBuf::from_path_buf(Project::current()?.store_root())
             .map_err(|_| KiraError::Filesystem("invalid project path".to_string()))?;
 
-        let cache_root = BaseDirs::new()
-            .and_then(|dirs| {
+        let configured = std::env::var(CACHE_DIR_ENV)
+            .ok()
+            .filter(|dir| !dir.is_empty());
+        let cache_disabled = configured.as_deref() == Some(NO_CACHE_DIR);
+        let user_cache = configured.map(Utf8PathBuf::from).or_else(|| {
+            BaseDirs::new().and_then(|dirs| {
                 Utf8PathBuf::from_path_buf(
                     dirs.home_dir().join(".cache").join("kira-biodata-manager"),
                 )
                 .ok()
             })
-            .ok_or_else(|| {
-                KiraError::Filesystem("unable to resolve cache directory".to_string())
-            })?;
-
-        Ok(Self {
+        });
+        let store = Self {
+            user_cache_root: user_cache
+                .clone()
+                .unwrap_or_else(|| project_root.join(LOCAL_CACHE_DIR)),
             project_root,
-            cache_root,
-        })
+            cache_disabled,
+            cache_fallback: OnceLock::new(),
+        };
+        if user_cache.is_none() {
+            store.fall_back("no home directory to hold the user cache".to_string());
+        }
+        Ok(store)
     }
 
     pub fn new_with_paths(project_root: Utf8PathBuf, cache_root: Utf8PathBuf) -> Self {
         Self {
             project_root,
-            cache_root,
+            user_cache_root: cache_root,
+            cache_disabled: false,
+            cache_fallback: OnceLock::new(),
+        }
+    }
+
+    /// A store without a cache, as with `KIRA_BM_CACHE_DIR=/dev/null`.
+    pub fn without_cache(project_root: Utf8PathBuf) -> Self {
+        Self {
+            project_root,
+            user_cache_root: Utf8PathBuf::from(NO_CACHE_DIR),
+            cache_disabled: true,
+            cache_fallback: OnceLock::new(),
         }
     }
 
@@ -51,80 +105,197 @@ impl Store {
         &self.project_root
     }
 
+    /// The cache in use: the user cache, or the project-local one once
+    /// [`Store::prepare_cache`] found the user cache unusable.
     pub fn cache_root(&self) -> &Utf8Path {
-        &self.cache_root
+        match self.cache_fallback.get() {
+            Some((root, _)) => root,
+            None => &self.user_cache_root,
+        }
+    }
+
+    /// True when `KIRA_BM_CACHE_DIR` turned the cache off.
+    pub fn cache_disabled(&self) -> bool {
+        self.cache_disabled
+    }
+
+    /// Makes sure a fetch can write to the cache, switching to the
+    /// project-local cache (`.kira-bm/.cache`) when the user cache cannot be
+    /// created or written. Returns why, when the switch has been made.
+    pub fn prepare_cache(&self) -> Option<&str> {
+        if !self.cache_disabled
+            && self.cache_fallback.get().is_none()
+            && let Err(err) = ensure_writable(&self.user_cache_root)
+        {
+            self.fall_back(format!("{}: {err}", self.user_cache_root));
+        }
+        self.cache_fallback.get().map(|(_, reason)| reason.as_str())
+    }
+
+    fn fall_back(&self, reason: String) {
+        let _ = self
+            .cache_fallback
+            .set((self.project_root.join(LOCAL_CACHE_DIR), reason));
     }
 
     pub fn project_protein_dir(&self, id: &ProteinId) -> Utf8PathBuf {
-        self.project_root.join("proteins").join(id.as_str())
+        Layout(&self.project_root).protein_dir(id)
     }
 
     pub fn cache_protein_dir(&self, id: &ProteinId) -> Utf8PathBuf {
-        self.cache_root.join("proteins").join(id.as_str())
+        Layout(self.cache_root()).protein_dir(id)
     }
 
     pub fn project_protein_path(&self, id: &ProteinId, format: ProteinFormat) -> Utf8PathBuf {
-        let dir = self.project_protein_dir(id);
-        dir.join(format!("{id}.{}", protein_ext(format)))
+        Layout(&self.project_root).protein_path(id, format)
     }
 
     pub fn cache_protein_path(&self, id: &ProteinId, format: ProteinFormat) -> Utf8PathBuf {
-        let dir = self.cache_protein_dir(id);
-        dir.join(format!("{id}.{}", protein_ext(format)))
+        Layout(self.cache_root()).protein_path(id, format)
+    }
+
+    /// The structure files in a protein directory, keyed by format.
+    pub fn protein_files(dir: &Utf8Path, id: &ProteinId) -> BTreeMap<String, String> {
+        ProteinFormat::value_variants()
+            .iter()
+            .map(|format| (format, dir.join(format!("{id}.{}", protein_ext(*format)))))
+            .filter(|(_, path)| path.as_std_path().is_file())
+            .map(|(format, path)| (format.to_string(), path.to_string()))
+            .collect()
     }
 
     pub fn project_genome_dir(&self, acc: &GenomeAccession) -> Utf8PathBuf {
-        self.project_root.join("genomes").join(acc.as_str())
+        Layout(&self.project_root).genome_dir(acc)
     }
 
     pub fn cache_genome_dir(&self, acc: &GenomeAccession) -> Utf8PathBuf {
-        self.cache_root.join("genomes").join(acc.as_str())
+        Layout(self.cache_root()).genome_dir(acc)
+    }
+
+    /// `genomes/ensembl/<species>/<release>/`: each release is a dataset of
+    /// its own.
+    pub fn project_ensembl_dir(&self, species: &str, release: u32) -> Utf8PathBuf {
+        Layout(&self.project_root).ensembl_dir(species, release)
+    }
+
+    pub fn cache_ensembl_dir(&self, species: &str, release: u32) -> Utf8PathBuf {
+        Layout(self.cache_root()).ensembl_dir(species, release)
+    }
+
+    /// Where a genome package is downloaded and kept until it is stored, so
+    /// that the next fetch can resume or reuse it. Named like a staging
+    /// directory, so `gc` clears packages nobody came back for.
+    pub fn cache_genome_package_dir(
+        &self,
+        acc: &GenomeAccession,
+        include: &[String],
+    ) -> Utf8PathBuf {
+        let key = format!("{}_{}", acc.as_str(), include.join("-"));
+        self.cache_root()
+            .join("genomes")
+            .join(format!("kira-bm-package-{key}.0"))
     }
 
     pub fn project_srr_dir(&self, id: &SrrId) -> Utf8PathBuf {
-        self.project_root.join("srr").join(id.as_str())
+        Layout(&self.project_root).srr_dir(id)
     }
 
     pub fn cache_srr_dir(&self, id: &SrrId) -> Utf8PathBuf {
-        self.cache_root.join("srr").join(id.as_str())
+        Layout(self.cache_root()).srr_dir(id)
     }
 
     pub fn project_uniprot_dir(&self, id: &UniprotId) -> Utf8PathBuf {
-        self.project_root.join("uniprot").join(id.as_str())
+        Layout(&self.project_root).uniprot_dir(id)
     }
 
     pub fn cache_uniprot_dir(&self, id: &UniprotId) -> Utf8PathBuf {
-        self.cache_root.join("uniprot").join(id.as_str())
+        Layout(self.cache_root()).uniprot_dir(id)
+    }
+
+    pub fn project_proteome_dir(&self, id: &ProteomeId) -> Utf8PathBuf {
+        Layout(&self.project_root).proteome_dir(id)
+    }
+
+    pub fn cache_proteome_dir(&self, id: &ProteomeId) -> Utf8PathBuf {
+        Layout(self.cache_root()).proteome_dir(id)
     }
 
     pub fn project_doi_dir(&self, doi: &Doi) -> Utf8PathBuf {
-        self.project_root
-            .join("doi")
-            .join(encode_doi_segment(doi.as_str()))
+        Layout(&self.project_root).doi_dir(doi)
     }
 
     pub fn project_expression_dir(&self, acc: &GeoSeriesAccession) -> Utf8PathBuf {
-        self.project_root.join("expression").join(acc.as_str())
+        Layout(&self.project_root).expression_dir(acc)
     }
 
     pub fn cache_expression_dir(&self, acc: &GeoSeriesAccession) -> Utf8PathBuf {
-        self.cache_root.join("expression").join(acc.as_str())
+        Layout(self.cache_root()).expression_dir(acc)
     }
 
     pub fn project_expression10x_dir(&self, acc: &GeoSeriesAccession) -> Utf8PathBuf {
-        self.project_root.join("expression10x").join(acc.as_str())
+        Layout(&self.project_root).expression10x_dir(acc)
     }
 
     pub fn cache_expression10x_dir(&self, acc: &GeoSeriesAccession) -> Utf8PathBuf {
-        self.cache_root.join("expression10x").join(acc.as_str())
+        Layout(self.cache_root()).expression10x_dir(acc)
+    }
+
+    pub fn project_expression_sample_dir(
+        &self,
+        acc: &GeoSampleAccession,
+        series: Option<&GeoSeriesAccession>,
+    ) -> Utf8PathBuf {
+        Layout(&self.project_root).expression_sample_dir(acc, series)
+    }
+
+    pub fn cache_expression_sample_dir(
+        &self,
+        acc: &GeoSampleAccession,
+        series: Option<&GeoSeriesAccession>,
+    ) -> Utf8PathBuf {
+        Layout(self.cache_root()).expression_sample_dir(acc, series)
+    }
+
+    pub fn project_bioproject_dir(&self, acc: &BioProjectAccession) -> Utf8PathBuf {
+        Layout(&self.project_root).bioproject_dir(acc)
+    }
+
+    pub fn project_bioproject_manifest_path(&self, acc: &BioProjectAccession) -> Utf8PathBuf {
+        self.project_bioproject_dir(acc).join("manifest.json")
     }
 
     pub fn project_kb_dir(&self, name: &str) -> Utf8PathBuf {
-        self.project_root.join("metadata").join(name)
+        Layout(&self.project_root).kb_dir(name)
     }
 
     pub fn cache_kb_dir(&self, name: &str) -> Utf8PathBuf {
-        self.cache_root.join("metadata").join(name)
+        Layout(self.cache_root()).kb_dir(name)
+    }
+
+    /// The project's record of a dataset, if it has a readable one.
+    pub fn read_project_record(&self, dataset_type: &str, id: &str) -> Option<Metadata> {
+        Layout(&self.project_root).record(dataset_type, id)
+    }
+
+    /// Where the project keeps `specifier`'s data: the structure file of a
+    /// protein (in `format`, or the format fetched last), otherwise the
+    /// dataset's directory. `None` when the project has no copy, or when
+    /// the specifier does not name one dataset (`genome:taxon:`).
+    pub fn resolve_dataset_path(
+        &self,
+        specifier: &DatasetSpecifier,
+        format: Option<ProteinFormat>,
+    ) -> Option<Utf8PathBuf> {
+        Layout(&self.project_root).resolve(specifier, format)
+    }
+
+    /// [`Store::resolve_dataset_path`] in the cache.
+    pub fn resolve_cache_dataset_path(
+        &self,
+        specifier: &DatasetSpecifier,
+        format: Option<ProteinFormat>,
+    ) -> Option<Utf8PathBuf> {
+        Layout(self.cache_root()).resolve(specifier, format)
     }
 
     pub fn project_doi_resolution_path(&self, doi: &Doi) -> Utf8PathBuf {
@@ -135,7 +306,7 @@ impl Store {
         self.project_root
             .join("metadata")
             .join(dataset_type)
-            .join(format!("{id}.json"))
+            .join(format!("{}.json", file_stem(id)))
     }
 
     pub fn project_doi_metadata_path(&self, doi: &Doi) -> Utf8PathBuf {
@@ -146,10 +317,38 @@ impl Store {
     }
 
     pub fn cache_metadata_path(&self, dataset_type: &str, id: &str) -> Utf8PathBuf {
-        self.cache_root
+        self.cache_root()
             .join("metadata")
             .join(dataset_type)
-            .join(format!("{id}.json"))
+            .join(format!("{}.json", file_stem(id)))
+    }
+
+    /// Lock files live in the cache because it is shared between projects;
+    /// without a cache, in the project store.
+    pub fn lock_path(&self, dataset_type: &str, id: &str) -> Utf8PathBuf {
+        let root = if self.cache_disabled {
+            &self.project_root
+        } else {
+            self.cache_root()
+        };
+        root.join(LOCKS_DIR)
+            .join(dataset_type)
+            .join(format!("{}.lock", file_stem(id)))
+    }
+
+    pub fn lock_dataset(
+        &self,
+        dataset_type: &str,
+        id: &str,
+        timeout: Duration,
+        sink: &dyn ProgressSink,
+    ) -> Result<DatasetLock, KiraError> {
+        let label = if dataset_type == id {
+            dataset_type.to_string()
+        } else {
+            format!("{dataset_type}:{id}")
+        };
+        DatasetLock::acquire(&self.lock_path(dataset_type, id), &label, timeout, sink)
     }
 
     pub fn ensure_project_root(&self) -> Result<(), KiraError> {
@@ -158,7 +357,10 @@ impl Store {
     }
 
     pub fn ensure_cache_root(&self) -> Result<(), KiraError> {
-        fs::create_dir_all(self.cache_root.as_std_path())
+        if self.cache_disabled {
+            return Ok(());
+        }
+        fs::create_dir_all(self.cache_root().as_std_path())
             .map_err(|err| KiraError::Filesystem(err.to_string()))
     }
 
@@ -170,22 +372,307 @@ impl Store {
         path.as_std_path().exists()
     }
 
-    pub fn clear_project(&self) -> Result<(), KiraError> {
-        if self.project_root.as_std_path().exists() {
-            fs::remove_dir_all(self.project_root.as_std_path())
-                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    /// The download recorded in a metadata record, if the record has one.
+    pub fn recorded_download(metadata_path: &Utf8Path) -> Option<DownloadRecord> {
+        fs::read(metadata_path.as_std_path())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Metadata>(&bytes).ok())
+            .and_then(|meta| meta.download)
+    }
+
+    /// Stamps how long a dataset's download took on its project and cache
+    /// records.
+    pub fn record_download_duration(
+        &self,
+        dataset_type: &str,
+        id: &str,
+        duration_ms: u64,
+        timeout: Duration,
+        sink: &dyn ProgressSink,
+    ) -> Result<(), KiraError> {
+        let _lock = self.lock_dataset(dataset_type, id, timeout, sink)?;
+        for path in [
+            self.project_metadata_path(dataset_type, id),
+            self.cache_metadata_path(dataset_type, id),
+        ] {
+            let Ok(bytes) = fs::read(path.as_std_path()) else {
+                continue;
+            };
+            let mut meta = serde_json::from_slice::<Metadata>(&bytes)
+                .map_err(|err| KiraError::Filesystem(format!("{path}: {err}")))?;
+            if let Some(download) = meta.download.as_mut() {
+                download.duration_ms = Some(duration_ms);
+                Self::write_metadata(&path, &meta)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The alias recorded on the project copy of a dataset.
+    pub fn recorded_alias(&self, dataset_type: &str, id: &str) -> Option<String> {
+        fs::read(self.project_metadata_path(dataset_type, id).as_std_path())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Metadata>(&bytes).ok())
+            .and_then(|meta| meta.alias)
+    }
+
+    /// Records `alias` on the project copy of a dataset, taking it from any
+    /// other project dataset that had it. `None` clears the alias.
+    pub fn set_alias(
+        &self,
+        dataset_type: &str,
+        id: &str,
+        alias: Option<&str>,
+        timeout: Duration,
+        sink: &dyn ProgressSink,
+    ) -> Result<(), KiraError> {
+        let _lock = self.lock_dataset(dataset_type, id, timeout, sink)?;
+        if let Some(alias) = alias {
+            for (path, mut meta) in Self::list_metadata_files(&self.project_root)? {
+                let is_other = meta.dataset_type != dataset_type || meta.id != id;
+                if is_other && meta.alias.as_deref() == Some(alias) {
+                    meta.alias = None;
+                    Self::write_metadata(&path, &meta)?;
+                }
+            }
+        }
+        let path = self.project_metadata_path(dataset_type, id);
+        let Ok(bytes) = fs::read(path.as_std_path()) else {
+            return Ok(());
+        };
+        let mut meta = serde_json::from_slice::<Metadata>(&bytes)
+            .map_err(|err| KiraError::Filesystem(format!("{path}: {err}")))?;
+        if meta.alias.as_deref() != alias {
+            meta.alias = alias.map(str::to_string);
+            Self::write_metadata(&path, &meta)?;
         }
         Ok(())
     }
 
+    /// Records on the project copy of `run` that it was fetched as part of
+    /// `experiment`.
+    pub fn record_experiment(
+        &self,
+        run: &SrrId,
+        experiment: &SraExperimentAccession,
+    ) -> Result<(), KiraError> {
+        let path = self.project_metadata_path("srr", run.as_str());
+        let Ok(bytes) = fs::read(path.as_std_path()) else {
+            return Ok(());
+        };
+        let mut meta = serde_json::from_slice::<Metadata>(&bytes)
+            .map_err(|err| KiraError::Filesystem(format!("{path}: {err}")))?;
+        if meta.experiment.as_deref() != Some(experiment.as_str()) {
+            meta.experiment = Some(experiment.as_str().to_string());
+            Self::write_metadata(&path, &meta)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the project copy of a dataset is only its metadata, left by a
+    /// `--metadata-only` fetch.
+    pub fn is_metadata_only(&self, dataset_type: &str, id: &str) -> bool {
+        fs::read(self.project_metadata_path(dataset_type, id).as_std_path())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Metadata>(&bytes).ok())
+            .is_some_and(|meta| meta.materialization == Materialization::MetadataOnly)
+    }
+
+    /// Whether a stored copy of a dataset, in the project or the cache, is
+    /// pinned. Fetches carry the pin over to the records they write.
+    pub fn recorded_pin(&self, dataset_type: &str, id: &str) -> bool {
+        [self.project_root(), self.cache_root()]
+            .into_iter()
+            .any(|root| {
+                fs::read(record_path(root, dataset_type, id).as_std_path())
+                    .ok()
+                    .and_then(|bytes| serde_json::from_slice::<Metadata>(&bytes).ok())
+                    .is_some_and(|meta| meta.pinned)
+            })
+    }
+
+    /// Pins or unpins every stored copy of a dataset. Returns the records
+    /// that were changed, which is none when they already agreed.
+    pub fn set_pinned(
+        &self,
+        dataset_type: &str,
+        id: &str,
+        pinned: bool,
+        timeout: Duration,
+        sink: &dyn ProgressSink,
+    ) -> Result<Vec<Utf8PathBuf>, KiraError> {
+        let _lock = self.lock_dataset(dataset_type, id, timeout, sink)?;
+        let mut found = false;
+        let mut changed = Vec::new();
+        for root in [self.project_root(), self.cache_root()] {
+            for (path, mut meta) in Self::list_metadata_files(root)? {
+                if meta.dataset_type != dataset_type || meta.id != id {
+                    continue;
+                }
+                found = true;
+                if meta.pinned != pinned {
+                    meta.pinned = pinned;
+                    Self::write_metadata(&path, &meta)?;
+                    changed.push(path);
+                }
+            }
+        }
+        if !found {
+            return Err(KiraError::DatasetNotFound(format!("{dataset_type}:{id}")));
+        }
+        Ok(changed)
+    }
+
+    /// Wipes the project store except its audit and fetch history logs, its
+    /// run manifests and, unless `include_pinned`, pinned datasets, then
+    /// records the wipe. With `types`, only datasets of those types are
+    /// removed; with `keep_metadata`, their records stay behind. A dry run
+    /// removes and records nothing.
+    pub fn clear_project(&self, options: &ClearOptions) -> Result<ProjectClear, KiraError> {
+        let mut clear = ProjectClear::default();
+        if !self.project_root.as_std_path().exists() {
+            return Ok(clear);
+        }
+        let selected = |entry: &Metadata| {
+            options.types.is_empty()
+                || options
+                    .types
+                    .iter()
+                    .any(|kind| kind.eq_ignore_ascii_case(&entry.dataset_type))
+        };
+        let mut keep = [AUDIT_FILE, HISTORY_FILE, RUNS_DIR]
+            .iter()
+            .map(|name| self.project_root.join(name))
+            .collect::<Vec<_>>();
+        let mut cleared = Vec::new();
+        for (path, entry) in Self::list_metadata_files(&self.project_root)? {
+            if !selected(&entry) || (entry.pinned && !options.include_pinned) {
+                if selected(&entry) {
+                    clear.kept.push(entry.clone());
+                }
+                keep.push(path);
+                keep.push(dataset_dir(Utf8Path::new(&entry.resolved_path)));
+                continue;
+            }
+            if options.keep_metadata {
+                keep.push(path.clone());
+            }
+            cleared.push((path, entry));
+        }
+        let targets = cleared
+            .iter()
+            .map(|(_, entry)| AuditTarget::from_metadata(entry, Some(audit::refetch_hint(entry))))
+            .collect::<Vec<_>>();
+        clear.cleared = cleared
+            .iter()
+            .zip(&targets)
+            .map(|((_, entry), target)| (entry.clone(), target.bytes))
+            .collect();
+        if options.dry_run {
+            return Ok(clear);
+        }
+        if options.types.is_empty() {
+            remove_all_except(&self.project_root, &keep)?;
+        } else {
+            for (path, entry) in &cleared {
+                let dir = dataset_dir(Utf8Path::new(&entry.resolved_path));
+                if dir.starts_with(&self.project_root)
+                    && dir != self.project_root
+                    && dir != self.project_root.join("metadata")
+                {
+                    remove_except(&dir, &keep)?;
+                }
+                if !options.keep_metadata {
+                    remove_except(path, &keep)?;
+                }
+            }
+        }
+        let mut flags = Vec::new();
+        if options.include_pinned {
+            flags.push("--include-pinned".to_string());
+        }
+        if !options.types.is_empty() {
+            flags.push(format!("--type {}", options.types.join(",")));
+        }
+        if options.keep_metadata {
+            flags.push("--keep-metadata".to_string());
+        }
+        audit::append(
+            &self.project_root,
+            &AuditRecord::new("clear", targets, flags),
+        )?;
+        Ok(clear)
+    }
+
+    /// Removes a project dataset and every metadata entry that pointed into
+    /// the removed directory. Returns the entries that were dropped.
+    /// Refuses while any of those entries is pinned, unless
+    /// `include_pinned`.
+    pub fn remove_project_dataset(
+        &self,
+        metadata: &Metadata,
+        include_pinned: bool,
+    ) -> Result<Vec<Metadata>, KiraError> {
+        let resolved = Utf8PathBuf::from(&metadata.resolved_path);
+        if !resolved.starts_with(&self.project_root) {
+            return Err(KiraError::Filesystem(format!(
+                "{resolved} is outside the project store"
+            )));
+        }
+        let target = dataset_dir(&resolved);
+        if target == self.project_root || target == self.project_root.join("metadata") {
+            return Err(KiraError::Filesystem(format!(
+                "refusing to remove store directory {target}"
+            )));
+        }
+        let doomed = Self::list_metadata_files(&self.project_root)?
+            .into_iter()
+            .filter(|(_, entry)| {
+                let same = entry.dataset_type == metadata.dataset_type && entry.id == metadata.id;
+                same || Utf8Path::new(&entry.resolved_path).starts_with(&target)
+            })
+            .collect::<Vec<_>>();
+        let pinned = doomed
+            .iter()
+            .filter(|(_, entry)| entry.pinned)
+            .map(|(_, entry)| specifier_for(entry))
+            .collect::<Vec<_>>();
+        if !pinned.is_empty() && !include_pinned {
+            return Err(KiraError::DatasetPinned(pinned.join(", ")));
+        }
+        let targets = doomed
+            .iter()
+            .map(|(_, entry)| AuditTarget::from_metadata(entry, Some(audit::refetch_hint(entry))))
+            .collect::<Vec<_>>();
+
+        if target.as_std_path().is_dir() {
+            fs::remove_dir_all(target.as_std_path())
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        } else if target.as_std_path().exists() {
+            fs::remove_file(target.as_std_path())
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        }
+
+        let mut removed = Vec::new();
+        for (path, entry) in doomed {
+            fs::remove_file(path.as_std_path())
+                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            removed.push(entry);
+        }
+        audit::append(
+            &self.project_root,
+            &AuditRecord::new("remove", targets, Vec::new()),
+        )?;
+        Ok(removed)
+    }
+
     pub fn write_metadata(path: &Utf8Path, metadata: &Metadata) -> Result<(), KiraError> {
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent.as_std_path())
                 .map_err(|err| KiraError::Filesystem(err.to_string()))?;
         }
         let tmp_path = path.with_extension("json.tmp");
-        let content = serde_json::to_vec_pretty(metadata)
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let content = to_canonical_json(metadata)?;
         fs::write(tmp_path.as_std_path(), &content)
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
         fs::rename(tmp_path.as_std_path(), path.as_std_path())
@@ -227,64 +714,651 @@ impl Store {
     }
 
     pub fn copy_dir_atomic(source: &Utf8Path, dest: &Utf8Path) -> Result<(), KiraError> {
-        let parent = dest
-            .parent()
-            .ok_or_else(|| KiraError::Filesystem("invalid destination path".to_string()))?;
-        fs::create_dir_all(parent.as_std_path())
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        let temp_dir = Builder::new()
-            .prefix("kira-bm-copy")
-            .tempdir_in(parent.as_std_path())
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        let temp_path = Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf())
-            .map_err(|_| KiraError::Filesystem("invalid temp dir".to_string()))?;
-        Self::copy_dir_recursive(source, &temp_path)?;
-        atomic_rename_dir(temp_path.as_std_path(), dest.as_std_path())
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        Ok(())
+        Self::copy_dir_staged(source, dest, None)
     }
 
     pub fn copy_file_atomic(source: &Utf8Path, dest: &Utf8Path) -> Result<(), KiraError> {
-        let parent = dest
-            .parent()
-            .ok_or_else(|| KiraError::Filesystem("invalid destination path".to_string()))?;
-        fs::create_dir_all(parent.as_std_path())
+        Self::copy_file_staged(source, dest, None)
+    }
+
+    /// Copies a cached dataset into the project with every file's mtime set
+    /// to the record's `downloaded_at`, so that the copy does not depend on
+    /// when it was made.
+    pub fn materialize_dir(
+        source: &Utf8Path,
+        dest: &Utf8Path,
+        downloaded_at: &str,
+    ) -> Result<(), KiraError> {
+        Self::copy_dir_staged(source, dest, parse_mtime(downloaded_at))
+    }
+
+    /// Like [`Store::materialize_dir`], for a single-file dataset.
+    pub fn materialize_file(
+        source: &Utf8Path,
+        dest: &Utf8Path,
+        downloaded_at: &str,
+    ) -> Result<(), KiraError> {
+        Self::copy_file_staged(source, dest, parse_mtime(downloaded_at))
+    }
+
+    fn copy_dir_staged(
+        source: &Utf8Path,
+        dest: &Utf8Path,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), KiraError> {
+        let (parent, name) = split_dest(dest)?;
+        let staging = StagingDir::create_in(parent.as_std_path(), "kira-bm-copy", name)
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        let temp = tempfile::Builder::new()
-            .prefix("kira-bm-file")
-            .tempfile_in(parent.as_std_path())
+        let staged = Utf8Path::from_path(staging.path())
+            .ok_or_else(|| KiraError::Filesystem("invalid temp dir".to_string()))?;
+        Self::copy_dir_recursive(source, staged)?;
+        if let Some(mtime) = mtime {
+            set_mtimes(staging.path(), mtime)?;
+        }
+        staging
+            .persist(dest.as_std_path())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+
+    fn copy_file_staged(
+        source: &Utf8Path,
+        dest: &Utf8Path,
+        mtime: Option<SystemTime>,
+    ) -> Result<(), KiraError> {
+        let (parent, name) = split_dest(dest)?;
+        let staging = StagingDir::create_in(parent.as_std_path(), "kira-bm-file", name)
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        fs::copy(source.as_std_path(), temp.path())
+        let staged = staging.path().join(name);
+        fs::copy(source.as_std_path(), &staged)
             .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        if dest.as_std_path().exists() {
-            fs::remove_file(dest.as_std_path())
-                .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        if let Some(mtime) = mtime {
+            set_mtimes(&staged, mtime)?;
         }
-        temp.persist(dest.as_std_path())
-            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-        Ok(())
+        // Renaming over a file replaces it in one step, so the old copy
+        // stays until the new one is complete. The staging directory goes
+        // with the staged file on any failure.
+        replace_file(&staged, dest.as_std_path()).map_err(|err| {
+            KiraError::Filesystem(format!(
+                "unable to move {} to {dest}: {err}",
+                staged.display()
+            ))
+        })
     }
 
+    /// Metadata records under `root`. Unreadable entries are skipped and
+    /// logged; see [`Store::scan_metadata`].
     pub fn list_metadata(root: &Utf8Path) -> Result<Vec<Metadata>, KiraError> {
+        Ok(Self::list_metadata_files(root)?
+            .into_iter()
+            .map(|(_, metadata)| metadata)
+            .collect())
+    }
+
+    /// Like `list_metadata`, but keeps the path of each metadata file.
+    pub fn list_metadata_files(root: &Utf8Path) -> Result<Vec<(Utf8PathBuf, Metadata)>, KiraError> {
+        let (entries, warnings) = Self::scan_metadata(root);
+        for warning in &warnings {
+            tracing::warn!("skipped {warning}");
+        }
+        Ok(entries)
+    }
+
+    /// Metadata records under `root`, plus a warning for every entry that
+    /// was skipped: directories and files that cannot be read, and files
+    /// that are not valid JSON. Valid JSON of another shape (knowledge base
+    /// files) is not a metadata record and is skipped silently.
+    pub fn scan_metadata(root: &Utf8Path) -> (Vec<(Utf8PathBuf, Metadata)>, Vec<StoreWarning>) {
         let metadata_root = root.join("metadata");
+        let mut warnings = Vec::new();
         if !metadata_root.as_std_path().exists() {
-            return Ok(Vec::new());
+            return (Vec::new(), warnings);
         }
         let mut entries = Vec::new();
-        for path in walk_dir(metadata_root.as_std_path())? {
-            if path.is_file() && path.extension().map(|ext| ext == "json").unwrap_or(false) {
-                let content = fs::read_to_string(&path)
-                    .map_err(|err| KiraError::Filesystem(err.to_string()))?;
-                if let Ok(metadata) = serde_json::from_str::<Metadata>(&content) {
-                    entries.push(metadata);
+        for path in walk_dir_lenient(metadata_root.as_std_path(), &mut warnings) {
+            if !path.is_file() || path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let value = fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|content| {
+                    serde_json::from_str::<serde_json::Value>(&content)
+                        .map_err(|err| format!("invalid JSON: {err}"))
+                });
+            let value = match value {
+                Ok(value) => value,
+                Err(error) => {
+                    warnings.push(StoreWarning::new(&path, error));
+                    continue;
                 }
+            };
+            if let Ok(metadata) = serde_json::from_value::<Metadata>(value)
+                && let Ok(path) = Utf8PathBuf::from_path_buf(path)
+            {
+                entries.push((path, metadata));
             }
         }
-        Ok(entries)
+        (entries, warnings)
+    }
+
+    /// Staging directories under `root` left by runs that died: nothing in
+    /// them has changed for `min_age` before `now`. A fetch that is still
+    /// running keeps writing into its own.
+    pub fn stale_staging_dirs(
+        root: &Utf8Path,
+        min_age: Duration,
+        now: SystemTime,
+    ) -> Vec<Utf8PathBuf> {
+        let mut found = Vec::new();
+        let mut stack = vec![(root.to_path_buf(), 0)];
+        while let Some((dir, depth)) = stack.pop() {
+            let Ok(entries) = fs::read_dir(dir.as_std_path()) else {
+                continue;
+            };
+            for entry in entries.filter_map(Result::ok) {
+                let Ok(path) = Utf8PathBuf::from_path_buf(entry.path()) else {
+                    continue;
+                };
+                if !entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                    continue;
+                }
+                if !is_staging_dir(&path) {
+                    if depth + 1 < STAGING_SEARCH_DEPTH {
+                        stack.push((path, depth + 1));
+                    }
+                    continue;
+                }
+                let idle = newest_mtime(path.as_std_path())
+                    .and_then(|mtime| now.duration_since(mtime).ok())
+                    .unwrap_or_default();
+                if idle >= min_age {
+                    found.push(path);
+                }
+            }
+        }
+        found.sort();
+        found
+    }
+
+    /// Dataset directories under `root` that none of `records` points into,
+    /// such as what is left of a dataset whose metadata was deleted by hand.
+    pub fn orphan_dataset_dirs(
+        root: &Utf8Path,
+        records: &[(Utf8PathBuf, Metadata)],
+    ) -> Vec<Utf8PathBuf> {
+        let resolved = records
+            .iter()
+            .map(|(_, metadata)| Utf8Path::new(&metadata.resolved_path))
+            .collect::<Vec<_>>();
+        let mut orphans = Vec::new();
+        for kind in DATASET_DIRS {
+            let Ok(entries) = fs::read_dir(root.join(kind).as_std_path()) else {
+                continue;
+            };
+            for entry in entries.filter_map(Result::ok) {
+                let Ok(path) = Utf8PathBuf::from_path_buf(entry.path()) else {
+                    continue;
+                };
+                if !entry.file_type().is_ok_and(|kind| kind.is_dir()) || is_staging_dir(&path) {
+                    continue;
+                }
+                let claimed = resolved
+                    .iter()
+                    .any(|target| target.starts_with(&path) || path.starts_with(target));
+                if !claimed {
+                    orphans.push(path);
+                }
+            }
+        }
+        orphans.sort();
+        orphans
+    }
+
+    /// The content-addressable store that cache files are linked into once
+    /// `cache dedup` has created it.
+    pub fn blobs_root(&self) -> Utf8PathBuf {
+        self.cache_root().join(BLOBS_DIR)
+    }
+
+    pub fn blob_path(&self, digest: &str) -> Utf8PathBuf {
+        let prefix = digest.get(..2).unwrap_or(digest);
+        self.blobs_root().join(prefix).join(digest)
+    }
+
+    /// Whether cache writes go through the blob store.
+    pub fn uses_blobs(&self) -> bool {
+        self.blobs_root().as_std_path().is_dir()
+    }
+
+    /// Adds the file at `path` to the blob store, linking it in when the
+    /// filesystem allows, and returns its digest. A blob that is already
+    /// there is left alone.
+    pub fn store_blob(&self, path: &Utf8Path) -> Result<String, KiraError> {
+        let digest = sha256_file(path.as_std_path())?;
+        let blob = self.blob_path(&digest);
+        if blob.as_std_path().exists() {
+            return Ok(digest);
+        }
+        let (parent, _) = split_dest(&blob)?;
+        let staged = temp_sibling(parent, &digest);
+        link_or_copy(path.as_std_path(), staged.as_std_path())
+            .and_then(|_| fs::rename(staged.as_std_path(), blob.as_std_path()))
+            .map_err(|err| {
+                let _ = fs::remove_file(staged.as_std_path());
+                KiraError::Filesystem(format!("{blob}: {err}"))
+            })?;
+        Ok(digest)
+    }
+
+    /// Replaces `dest` with a hard link to the blob `digest`, or with a
+    /// copy of it where links are refused. Returns whether it linked; a
+    /// missing blob leaves `dest` as it is.
+    pub fn link_blob(&self, digest: &str, dest: &Utf8Path) -> Result<bool, KiraError> {
+        let blob = self.blob_path(digest);
+        if same_file(blob.as_std_path(), dest.as_std_path()) {
+            return Ok(true);
+        }
+        if !blob.as_std_path().is_file() {
+            return Ok(false);
+        }
+        let (parent, name) = split_dest(dest)?;
+        let staged = temp_sibling(parent, name);
+        let linked = link_or_copy(blob.as_std_path(), staged.as_std_path())
+            .and_then(|linked| {
+                fs::rename(staged.as_std_path(), dest.as_std_path())?;
+                Ok(linked)
+            })
+            .map_err(|err| {
+                let _ = fs::remove_file(staged.as_std_path());
+                KiraError::Filesystem(format!("{dest}: {err}"))
+            })?;
+        Ok(linked)
+    }
+
+    /// Moves the files a cache record lists in its digests into the blob
+    /// store and links them back into the dataset.
+    pub fn dedup_cache_dataset(&self, metadata: &Metadata) -> Result<DedupReport, KiraError> {
+        let mut report = DedupReport::default();
+        let digests = metadata
+            .download
+            .as_ref()
+            .map(|download| &download.sha256)
+            .filter(|digests| !digests.is_empty());
+        let Some(digests) = digests else {
+            report.skipped.push(audit::specifier_for(metadata));
+            return Ok(report);
+        };
+        report.datasets = 1;
+        let root = dataset_dir(Utf8Path::new(&metadata.resolved_path));
+        for relative in digests.keys() {
+            let file = root.join(relative);
+            let Ok(size) = fs::metadata(file.as_std_path()).map(|meta| meta.len()) else {
+                continue;
+            };
+            report.files += 1;
+            let digest = self.store_blob(&file)?;
+            if same_file(self.blob_path(&digest).as_std_path(), file.as_std_path()) {
+                report.linked += 1;
+            } else if self.link_blob(&digest, &file)? {
+                report.linked += 1;
+                report.bytes_reclaimed += size;
+            } else {
+                report.copied += 1;
+            }
+        }
+        Ok(report)
+    }
+
+    /// The files of a cache dataset that no longer match the digests in its
+    /// `MANIFEST.json` or, without one, its record: missing, truncated or
+    /// changed. `Fast` hashes only files up to [`FAST_VERIFY_MAX_BYTES`]
+    /// and checks the rest by size. Recorded files the manifest does not
+    /// list only need to exist; a record without digests has nothing to
+    /// check.
+    pub fn verify_cache_dataset(
+        &self,
+        dataset_type: &str,
+        id: &str,
+        mode: VerifyCache,
+    ) -> Result<Vec<String>, KiraError> {
+        if mode == VerifyCache::Off {
+            return Ok(Vec::new());
+        }
+        let record = self.cache_metadata_path(dataset_type, id);
+        let Some(metadata) = fs::read(record.as_std_path())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Metadata>(&bytes).ok())
+        else {
+            return Ok(Vec::new());
+        };
+        let root = dataset_dir(Utf8Path::new(&metadata.resolved_path));
+        if let Some(manifest) = manifest::read(root.as_std_path()) {
+            let mut bad = manifest.check(root.as_std_path(), mode)?;
+            let listed = manifest
+                .files
+                .iter()
+                .map(|entry| entry.path.as_str())
+                .collect::<BTreeSet<_>>();
+            for relative in metadata
+                .download
+                .iter()
+                .flat_map(|download| download.sha256.keys())
+            {
+                if !listed.contains(relative.as_str()) && !root.join(relative).exists() {
+                    bad.push(relative.clone());
+                }
+            }
+            return Ok(bad);
+        }
+        let Some(download) = metadata
+            .download
+            .filter(|download| !download.sha256.is_empty())
+        else {
+            return Ok(Vec::new());
+        };
+        let mut bad = Vec::new();
+        let mut unhashed = Vec::new();
+        let mut total = 0;
+        for (relative, digest) in &download.sha256 {
+            let file = root.join(relative);
+            let Ok(size) = fs::metadata(file.as_std_path()).map(|meta| meta.len()) else {
+                bad.push(relative.clone());
+                continue;
+            };
+            total += size;
+            if mode == VerifyCache::Fast && size > FAST_VERIFY_MAX_BYTES {
+                unhashed.push(relative.clone());
+            } else if sha256_file(file.as_std_path())? != *digest {
+                bad.push(relative.clone());
+            }
+        }
+        if bad.is_empty() && total != download.bytes_written {
+            bad = unhashed;
+        }
+        Ok(bad)
+    }
+
+    /// Removes a cache dataset and its record, so the next fetch downloads
+    /// it again. Datasets stored inside its directory (KEGG organisms under
+    /// `kegg`) are left alone.
+    pub fn evict_cache_dataset(&self, dataset_type: &str, id: &str) -> Result<(), KiraError> {
+        let record = self.cache_metadata_path(dataset_type, id);
+        let metadata = fs::read(record.as_std_path())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Metadata>(&bytes).ok());
+        if let Some(metadata) = metadata {
+            let target = dataset_dir(Utf8Path::new(&metadata.resolved_path));
+            if target.starts_with(self.cache_root()) && target != self.cache_root() {
+                let nested = Self::list_metadata_files(self.cache_root())?
+                    .into_iter()
+                    .filter(|(path, _)| *path != record)
+                    .map(|(_, entry)| dataset_dir(Utf8Path::new(&entry.resolved_path)))
+                    .filter(|dir| dir.starts_with(&target) && *dir != target)
+                    .collect::<Vec<_>>();
+                remove_except(&target, &nested)?;
+            }
+        }
+        remove_except(&record, &[])
+    }
+
+    /// Writes a cache record, first moving its files into the blob store
+    /// if the cache has one.
+    pub fn write_cache_metadata(
+        &self,
+        path: &Utf8Path,
+        metadata: &Metadata,
+    ) -> Result<(), KiraError> {
+        if self.uses_blobs() {
+            self.dedup_cache_dataset(metadata)?;
+        }
+        Self::write_metadata(path, metadata)
+    }
+
+    /// Converts the cache to the blob layout in place, one dataset at a
+    /// time under its lock. Later cache writes keep using the blob store.
+    pub fn dedup_cache(
+        &self,
+        timeout: Duration,
+        sink: &dyn ProgressSink,
+    ) -> Result<DedupReport, KiraError> {
+        fs::create_dir_all(self.blobs_root().as_std_path())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let mut report = DedupReport::default();
+        for (_, metadata) in Self::list_metadata_files(self.cache_root())? {
+            if metadata.is_expired() || !Path::new(&metadata.resolved_path).exists() {
+                continue;
+            }
+            let _lock = self.lock_dataset(&metadata.dataset_type, &metadata.id, timeout, sink)?;
+            report.merge(self.dedup_cache_dataset(&metadata)?);
+        }
+        let blobs = self.blob_files();
+        report.blobs = blobs.len();
+        report.blob_bytes = blobs
+            .iter()
+            .filter_map(|blob| path_size(blob.as_std_path()))
+            .sum();
+        Ok(report)
+    }
+
+    /// Blobs that no cache file links to any more. Blobs whose link count
+    /// cannot be read are kept.
+    pub fn unreferenced_blobs(&self) -> Vec<Utf8PathBuf> {
+        self.blob_files()
+            .into_iter()
+            .filter(|blob| link_count(blob.as_std_path()).is_ok_and(|count| count <= 1))
+            .collect()
+    }
+
+    /// Every blob in the store; half-written ones are not blobs yet.
+    fn blob_files(&self) -> Vec<Utf8PathBuf> {
+        let Ok(prefixes) = fs::read_dir(self.blobs_root().as_std_path()) else {
+            return Vec::new();
+        };
+        let mut blobs = prefixes
+            .filter_map(Result::ok)
+            .filter_map(|prefix| fs::read_dir(prefix.path()).ok())
+            .flatten()
+            .filter_map(Result::ok)
+            .filter_map(|entry| Utf8PathBuf::from_path_buf(entry.path()).ok())
+            .filter(|path| path.file_name().is_some_and(is_digest))
+            .collect::<Vec<_>>();
+        blobs.sort();
+        blobs
+    }
+}
+
+/// What `cache dedup` did.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct DedupReport {
+    /// Cache datasets whose files are now in the blob store.
+    pub datasets: usize,
+    pub files: usize,
+    /// Files that are hard links to their blob.
+    pub linked: usize,
+    /// Files left as copies because the filesystem refused a link.
+    pub copied: usize,
+    /// Bytes freed by replacing duplicate files with links.
+    pub bytes_reclaimed: u64,
+    pub blobs: usize,
+    pub blob_bytes: u64,
+    /// Datasets whose record lists no file digests to go by.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+}
+
+impl DedupReport {
+    fn merge(&mut self, other: DedupReport) {
+        self.datasets += other.datasets;
+        self.files += other.files;
+        self.linked += other.linked;
+        self.copied += other.copied;
+        self.bytes_reclaimed += other.bytes_reclaimed;
+        self.skipped.extend(other.skipped);
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+const BLOBS_DIR: &str = "blobs";
+
+fn is_digest(name: &str) -> bool {
+    name.len() == 64 && name.chars().all(|ch| ch.is_ascii_hexdigit())
+}
+
+/// A name beside `name` in `parent` for staging one file; unique within
+/// the process as well as between processes.
+fn temp_sibling(parent: &Utf8Path, name: &str) -> Utf8PathBuf {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let n = NEXT.fetch_add(1, Ordering::Relaxed);
+    parent.join(format!(".{name}.{}-{n}.tmp", std::process::id()))
+}
+
+/// Whether the file was linked rather than copied.
+fn link_or_copy(from: &Path, to: &Path) -> io::Result<bool> {
+    if fs::hard_link(from, to).is_ok() {
+        return Ok(true);
+    }
+    fs::copy(from, to)?;
+    Ok(false)
+}
+
+/// The record file of a dataset under a store root.
+fn record_path(root: &Utf8Path, dataset_type: &str, id: &str) -> Utf8PathBuf {
+    let name = if dataset_type == "doi" {
+        encode_doi_segment(id)
+    } else {
+        file_stem(id)
+    };
+    root.join("metadata")
+        .join(dataset_type)
+        .join(format!("{name}.json"))
+}
+
+/// The directory holding a dataset whose record points at `resolved`: the
+/// path itself, or the parent of a single stored file.
+/// Creates `dir` if needed and checks that files can be created in it.
+fn ensure_writable(dir: &Utf8Path) -> io::Result<()> {
+    fs::create_dir_all(dir.as_std_path())?;
+    tempfile::tempfile_in(dir.as_std_path()).map(drop)
+}
+
+fn dataset_dir(resolved: &Utf8Path) -> Utf8PathBuf {
+    if resolved.as_std_path().is_file() {
+        resolved.parent().unwrap_or(resolved).to_path_buf()
+    } else {
+        resolved.to_path_buf()
+    }
+}
+
+/// Deletes `path` unless it is in `keep`; of a directory leading to a kept
+/// path, only the rest is deleted.
+fn remove_except(path: &Utf8Path, keep: &[Utf8PathBuf]) -> Result<(), KiraError> {
+    let std_path = path.as_std_path();
+    if keep.iter().any(|kept| kept == path) || !std_path.exists() {
+        return Ok(());
+    }
+    if std_path.is_dir() && keep.iter().any(|kept| kept.starts_with(path)) {
+        return remove_all_except(path, keep);
+    }
+    if std_path.is_dir() {
+        fs::remove_dir_all(std_path)
+    } else {
+        fs::remove_file(std_path)
+    }
+    .map_err(|err| KiraError::Filesystem(err.to_string()))
+}
+
+/// Deletes everything under `dir` but the paths in `keep` and the
+/// directories leading to them.
+fn remove_all_except(dir: &Utf8Path, keep: &[Utf8PathBuf]) -> Result<(), KiraError> {
+    let entries =
+        fs::read_dir(dir.as_std_path()).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    for entry in entries {
+        let entry = entry.map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let Ok(path) = Utf8PathBuf::from_path_buf(entry.path()) else {
+            continue;
+        };
+        if keep.contains(&path) {
+            continue;
+        }
+        let is_dir = entry.file_type().is_ok_and(|kind| kind.is_dir());
+        if is_dir && keep.iter().any(|kept| kept.starts_with(&path)) {
+            remove_all_except(&path, keep)?;
+            continue;
+        }
+        if is_dir {
+            fs::remove_dir_all(path.as_std_path())
+        } else {
+            fs::remove_file(path.as_std_path())
+        }
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    }
+    Ok(())
+}
+
+/// What [`Store::clear_project`] removed, or would remove.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectClear {
+    /// Cleared datasets and their size on disk.
+    pub cleared: Vec<(Metadata, u64)>,
+    /// Pinned datasets that were left in place.
+    pub kept: Vec<Metadata>,
+}
+
+/// Every `dataset_type` a metadata record can have.
+pub const DATASET_TYPES: [&str; 13] = [
+    "protein",
+    "genome",
+    "srr",
+    "uniprot",
+    "uniprot-proteome",
+    "doi",
+    "bioproject",
+    "expression",
+    "expression10x",
+    "expressionsample",
+    "go",
+    "kegg",
+    "reactome",
+];
+
+/// Store directories that hold one directory per dataset.
+const DATASET_DIRS: [&str; 10] = [
+    "proteins",
+    "genomes",
+    "srr",
+    "uniprot",
+    "proteomes",
+    "doi",
+    "expression",
+    "expression10x",
+    "expression_samples",
+    "bioprojects",
+];
+
+/// Staging directories sit beside what they stage, at most as deep as
+/// `expression/<series>/`.
+const STAGING_SEARCH_DEPTH: usize = 3;
+
+/// Whether `path` is named like a [`StagingDir`]: `kira-bm-<kind>-<key>.<n>`.
+fn is_staging_dir(path: &Utf8Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.strip_prefix("kira-bm-"))
+        .and_then(|rest| rest.rsplit_once('.'))
+        .is_some_and(|(_, counter)| {
+            !counter.is_empty() && counter.chars().all(|ch| ch.is_ascii_digit())
+        })
+}
+
+/// The latest modification time of `path` or anything under it.
+fn newest_mtime(path: &Path) -> Option<SystemTime> {
+    let mut newest = fs::metadata(path).and_then(|meta| meta.modified()).ok()?;
+    for entry in walk_dir_lenient(path, &mut Vec::new()) {
+        if let Ok(mtime) = fs::symlink_metadata(&entry).and_then(|meta| meta.modified()) {
+            newest = newest.max(mtime);
+        }
+    }
+    Some(newest)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Metadata {
     pub source: String,
     pub dataset_type: String,
@@ -293,30 +1367,850 @@ pub struct Metadata {
     pub downloaded_at: String,
     pub tool: String,
     pub resolved_path: String,
+    /// `expired` once retention has deleted the data behind this record.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiry: Option<Expiry>,
+    /// The DOI this dataset was fetched for, when it came from `doi:`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+    #[serde(default, skip_serializing_if = "Materialization::is_full")]
+    pub materialization: Materialization,
+    /// The name a `genome:<NAME>` fetch was resolved from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assembly_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download: Option<DownloadRecord>,
+    /// Protein formats written next to the fetched file by `kira-bm convert`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub converted_formats: Vec<String>,
+    /// Every format a protein is stored in, fetched or converted, with its
+    /// file. `format` is the one fetched last.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub formats: BTreeMap<String, String>,
+    /// The logical name the project's config gives this dataset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// Set by `kira-bm pin`: destructive commands leave the dataset alone
+    /// unless told `--include-pinned`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// The local file or directory `kira-bm import` copied the data from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imported_from: Option<String>,
+    /// The SRA experiment an `srx:` fetch fetched this run for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub experiment: Option<String>,
+    /// Whether the fetch that wrote this project copy also copied it into
+    /// the cache.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheDecision>,
+    /// The invocation and environment that wrote this record.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acquisition: Option<Acquisition>,
+}
+
+/// How a dataset came to be in the store, for audits long after the fact.
+/// Secrets are redacted before they get here.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Acquisition {
+    pub tool_version: String,
+    /// Command-line arguments after the program name.
+    pub arguments: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+    /// SHA-256 of the config file a config-driven fetch read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_sha256: Option<String>,
+    /// The base URL each registry was reached at, mirrors included.
+    pub registries: BTreeMap<String, String>,
+    pub os: String,
+    pub arch: String,
 }
 
-fn walk_dir(root: &Path) -> Result<Vec<PathBuf>, KiraError> {
+impl std::fmt::Display for Acquisition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "kira-bm {}", self.tool_version)?;
+        if !self.arguments.is_empty() {
+            write!(f, " `{}`", self.arguments.join(" "))?;
+        }
+        if let Some(dir) = &self.working_dir {
+            write!(f, " in {dir}")?;
+        }
+        write!(f, " on {}/{}", self.os, self.arch)
+    }
+}
+
+pub const STATUS_EXPIRED: &str = "expired";
+
+impl Metadata {
+    pub fn is_expired(&self) -> bool {
+        self.status.as_deref() == Some(STATUS_EXPIRED)
+    }
+}
+
+/// How much of a dataset the store holds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Materialization {
+    #[default]
+    Full,
+    /// Descriptive metadata without the data files; a fetch without
+    /// `--metadata-only` completes it in place.
+    MetadataOnly,
+}
+
+impl Materialization {
+    pub fn is_full(&self) -> bool {
+        *self == Materialization::Full
+    }
+}
+
+impl std::fmt::Display for Materialization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Materialization::Full => "full",
+            Materialization::MetadataOnly => "metadata-only",
+        };
+        f.write_str(value)
+    }
+}
+
+/// Where in a paper's Crossref record a dataset's identifier was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractionSource {
+    Title,
+    Abstract,
+    DataAvailability,
+    Reference,
+    Link,
+    /// Reached through a sample whose own mention was not recorded.
+    Unknown,
+}
+
+impl std::fmt::Display for ExtractionSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            ExtractionSource::Title => "title",
+            ExtractionSource::Abstract => "abstract",
+            ExtractionSource::DataAvailability => "data availability",
+            ExtractionSource::Reference => "reference",
+            ExtractionSource::Link => "link",
+            ExtractionSource::Unknown => "unknown",
+        };
+        f.write_str(value)
+    }
+}
+
+/// Links a dataset back to the paper it was fetched for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Provenance {
+    pub doi: String,
+    pub resolved_at: String,
+    pub extracted_from: ExtractionSource,
+    /// The series or project the paper named, when the dataset was found
+    /// by expanding it rather than named directly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub via: Option<String>,
+}
+
+impl std::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "doi:{} ({}", self.doi, self.extracted_from)?;
+        if let Some(via) = &self.via {
+            write!(f, ", via {via}")?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// What a download wrote, recorded when it happened so that later cache
+/// and project hits report it without hashing the files again.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct DownloadRecord {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_url: Vec<String>,
+    pub bytes_written: u64,
+    /// Digest of each data file, keyed by its path relative to the dataset.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sha256: BTreeMap<String, String>,
+    /// How long the download took, set once it is stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// The `ETag`/`Last-Modified` of each `source_url` that sent them, so a
+    /// forced refetch can ask whether anything changed.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub validators: StoredValidators,
+}
+
+impl DownloadRecord {
+    /// Records `files`, given relative to `root`, with the digests taken
+    /// while they were written; the rest are hashed.
+    pub fn of_files(
+        root: &Path,
+        files: &[String],
+        source_url: Vec<String>,
+        digests: &Digests,
+    ) -> Result<Self, KiraError> {
+        let mut record = Self {
+            source_url,
+            ..Self::default()
+        };
+        for file in files {
+            record.add(root, file, digests.get(file).cloned())?;
+        }
+        Ok(record)
+    }
+
+    /// Records every file under `dir` except kira-bm's own records: a
+    /// top-level `metadata.json`, the `metadata/` directory and manifests.
+    pub fn of_dir(
+        dir: &Path,
+        source_url: Vec<String>,
+        digests: &Digests,
+    ) -> Result<Self, KiraError> {
+        let mut files = Vec::new();
+        let mut stack = vec![dir.to_path_buf()];
+        while let Some(current) = stack.pop() {
+            let entries =
+                fs::read_dir(&current).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            for entry in entries {
+                let path = entry
+                    .map_err(|err| KiraError::Filesystem(err.to_string()))?
+                    .path();
+                let Ok(relative) = path.strip_prefix(dir) else {
+                    continue;
+                };
+                let relative = relative
+                    .components()
+                    .map(|part| part.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                if relative == "metadata.json"
+                    || relative == "metadata"
+                    || path.file_name().is_some_and(|name| name == MANIFEST_FILE)
+                {
+                    continue;
+                }
+                if path.is_dir() {
+                    stack.push(path);
+                } else {
+                    files.push(relative);
+                }
+            }
+        }
+        Self::of_files(dir, &files, source_url, digests)
+    }
+
+    /// Adds `file`, relative to `root`, with the `sha256` taken while it
+    /// was written. Files without one, such as `fasterq-dump` output, are
+    /// read back to hash them.
+    pub fn add(
+        &mut self,
+        root: &Path,
+        file: &str,
+        sha256: Option<String>,
+    ) -> Result<(), KiraError> {
+        let path = root.join(file);
+        self.bytes_written += path_size(&path).unwrap_or(0);
+        let digest = match sha256 {
+            Some(digest) => digest,
+            None => sha256_file(&path)?,
+        };
+        self.sha256.insert(file.to_string(), digest);
+        Ok(())
+    }
+}
+
+/// Files larger than this are not hashed by a `--verify-cache fast` check.
+pub const FAST_VERIFY_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// What retention deleted, kept so the record still describes the data.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Expiry {
+    pub expired_at: String,
+    /// The policy that applied, e.g. `srr=180d`.
+    pub policy: String,
+    pub bytes: u64,
+    pub files: Vec<ExpiredFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ExpiredFile {
+    /// Relative to the dataset's `resolved_path`.
+    pub path: String,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+/// Days counted as "recent" by [`analytics`].
+pub const ANALYTICS_WINDOW_DAYS: i64 = 7;
+/// Fetches the cache hit ratio is computed over.
+pub const ANALYTICS_RECENT_FETCHES: usize = 50;
+
+/// Store figures for the TUI details panel; each is `None` (or zero) when
+/// the store or history has nothing to derive it from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StoreAnalytics {
+    /// Project datasets downloaded in the last week.
+    pub added_last_week: usize,
+    pub largest: Option<DatasetSize>,
+    /// Project plus cache.
+    pub total_bytes: u64,
+    /// Bytes downloaded in the last week, per the fetch history.
+    pub downloaded_last_week: Option<u64>,
+    /// Share of recent fetches served by the cache or the project store.
+    pub cache_hit_ratio: Option<f64>,
+    pub recent_fetches: usize,
+    pub last_failure: Option<HistoryRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DatasetSize {
+    pub specifier: String,
+    pub bytes: u64,
+}
+
+pub fn analytics(
+    project: &[Metadata],
+    cache: &[Metadata],
+    history: &[HistoryRecord],
+) -> StoreAnalytics {
+    analytics_at(project, cache, history, Utc::now())
+}
+
+/// [`analytics`] as of `now`.
+pub fn analytics_at(
+    project: &[Metadata],
+    cache: &[Metadata],
+    history: &[HistoryRecord],
+    now: DateTime<Utc>,
+) -> StoreAnalytics {
+    let since = now - TimeDelta::days(ANALYTICS_WINDOW_DAYS);
+    let recent = |timestamp: &str| {
+        DateTime::parse_from_rfc3339(timestamp).is_ok_and(|at| at.with_timezone(&Utc) >= since)
+    };
+    let live = |metadata: &&Metadata| !metadata.is_expired();
+
+    let mut largest: Option<DatasetSize> = None;
+    let mut total_bytes = 0u64;
+    for metadata in project.iter().chain(cache).filter(live) {
+        let bytes = path_size(Path::new(&metadata.resolved_path)).unwrap_or(0);
+        total_bytes = total_bytes.saturating_add(bytes);
+        if largest.as_ref().is_none_or(|largest| bytes > largest.bytes) {
+            largest = Some(DatasetSize {
+                specifier: specifier_for(metadata),
+                bytes,
+            });
+        }
+    }
+
+    let fetches = history
+        .iter()
+        .filter(|record| !record.is_failure())
+        .rev()
+        .take(ANALYTICS_RECENT_FETCHES)
+        .collect::<Vec<_>>();
+    let hits = fetches
+        .iter()
+        .filter(|record| record.action == "cache" || record.action == "project")
+        .count();
+
+    StoreAnalytics {
+        added_last_week: project
+            .iter()
+            .filter(live)
+            .filter(|metadata| recent(&metadata.downloaded_at))
+            .count(),
+        largest: largest.filter(|largest| largest.bytes > 0),
+        total_bytes,
+        downloaded_last_week: (!history.is_empty()).then(|| {
+            history
+                .iter()
+                .filter(|record| {
+                    matches!(
+                        record.action.as_str(),
+                        "download" | ACTION_REFETCHED_CORRUPT
+                    ) && recent(&record.timestamp)
+                })
+                .map(|record| record.bytes)
+                .sum()
+        }),
+        cache_hit_ratio: (!fetches.is_empty()).then(|| hits as f64 / fetches.len() as f64),
+        recent_fetches: fetches.len(),
+        last_failure: history
+            .iter()
+            .rev()
+            .find(|record| record.is_failure())
+            .cloned(),
+    }
+}
+
+/// An entry skipped while scanning a store.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct StoreWarning {
+    pub path: String,
+    pub error: String,
+}
+
+impl StoreWarning {
+    fn new(path: &Path, error: impl ToString) -> Self {
+        Self {
+            path: path.display().to_string(),
+            error: error.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for StoreWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.error)
+    }
+}
+
+/// Every path under `root`; fails on the first entry that cannot be read.
+pub(crate) fn walk_dir(root: &Path) -> Result<Vec<PathBuf>, KiraError> {
+    let mut warnings = Vec::new();
+    let items = walk_dir_lenient(root, &mut warnings);
+    match warnings.into_iter().next() {
+        Some(warning) => Err(KiraError::Filesystem(warning.to_string())),
+        None => Ok(items),
+    }
+}
+
+/// Every readable path under `root`. Entries that cannot be read, including
+/// dangling symlinks, are left out and reported in `warnings`; a directory
+/// reached again through a symlink is not descended into twice.
+pub(crate) fn walk_dir_lenient(root: &Path, warnings: &mut Vec<StoreWarning>) -> Vec<PathBuf> {
     let mut items = Vec::new();
+    let mut visited = HashSet::new();
     let mut stack = vec![root.to_path_buf()];
-    while let Some(path) = stack.pop() {
-        let entries = fs::read_dir(&path).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    while let Some(dir) = stack.pop() {
+        if let Ok(canonical) = fs::canonicalize(&dir)
+            && !visited.insert(canonical)
+        {
+            continue;
+        }
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                warnings.push(StoreWarning::new(&dir, err));
+                continue;
+            }
+        };
         for entry in entries {
-            let entry = entry.map_err(|err| KiraError::Filesystem(err.to_string()))?;
-            let path = entry.path();
-            if path.is_dir() {
-                stack.push(path.clone());
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(err) => {
+                    warnings.push(StoreWarning::new(&dir, err));
+                    continue;
+                }
+            };
+            match fs::metadata(&path) {
+                Ok(meta) if meta.is_dir() => stack.push(path.clone()),
+                Ok(_) => {}
+                Err(err) => {
+                    warnings.push(StoreWarning::new(&path, err));
+                    continue;
+                }
             }
             items.push(path);
         }
     }
-    Ok(items)
+    // Sorted so that copies and listings do not depend on directory order.
+    items.sort();
+    items
+}
+
+/// Where each dataset type lives under a store root; the project and the
+/// cache share one layout.
+struct Layout<'a>(&'a Utf8Path);
+
+impl Layout<'_> {
+    fn protein_dir(&self, id: &ProteinId) -> Utf8PathBuf {
+        self.0.join("proteins").join(id.as_str())
+    }
+
+    fn protein_path(&self, id: &ProteinId, format: ProteinFormat) -> Utf8PathBuf {
+        self.protein_dir(id)
+            .join(format!("{id}.{}", protein_ext(format)))
+    }
+
+    fn genome_dir(&self, acc: &GenomeAccession) -> Utf8PathBuf {
+        self.0.join("genomes").join(acc.as_str())
+    }
+
+    fn ensembl_dir(&self, species: &str, release: u32) -> Utf8PathBuf {
+        self.0
+            .join("genomes")
+            .join("ensembl")
+            .join(species)
+            .join(release.to_string())
+    }
+
+    fn srr_dir(&self, id: &SrrId) -> Utf8PathBuf {
+        self.0.join("srr").join(id.as_str())
+    }
+
+    fn uniprot_dir(&self, id: &UniprotId) -> Utf8PathBuf {
+        self.0.join("uniprot").join(id.as_str())
+    }
+
+    fn proteome_dir(&self, id: &ProteomeId) -> Utf8PathBuf {
+        self.0.join("proteomes").join(id.as_str())
+    }
+
+    fn doi_dir(&self, doi: &Doi) -> Utf8PathBuf {
+        self.0.join("doi").join(encode_doi_segment(doi.as_str()))
+    }
+
+    fn expression_dir(&self, acc: &GeoSeriesAccession) -> Utf8PathBuf {
+        self.0.join("expression").join(acc.as_str())
+    }
+
+    fn expression10x_dir(&self, acc: &GeoSeriesAccession) -> Utf8PathBuf {
+        self.0.join("expression10x").join(acc.as_str())
+    }
+
+    /// Inside its series' directory when the sample belongs to one.
+    fn expression_sample_dir(
+        &self,
+        acc: &GeoSampleAccession,
+        series: Option<&GeoSeriesAccession>,
+    ) -> Utf8PathBuf {
+        match series {
+            Some(series) => self.expression_dir(series).join(acc.as_str()),
+            None => self.0.join("expression_samples").join(acc.as_str()),
+        }
+    }
+
+    fn bioproject_dir(&self, acc: &BioProjectAccession) -> Utf8PathBuf {
+        self.0.join("bioprojects").join(acc.as_str())
+    }
+
+    fn kb_dir(&self, name: &str) -> Utf8PathBuf {
+        self.0.join("metadata").join(name)
+    }
+
+    fn record(&self, dataset_type: &str, id: &str) -> Option<Metadata> {
+        let bytes = fs::read(record_path(self.0, dataset_type, id)).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    fn resolve(
+        &self,
+        specifier: &DatasetSpecifier,
+        format: Option<ProteinFormat>,
+    ) -> Option<Utf8PathBuf> {
+        let path = match specifier {
+            DatasetSpecifier::Protein(id) => {
+                let format = format.or_else(|| {
+                    let fetched = self.record("protein", id.as_str())?.format?;
+                    ProteinFormat::value_variants()
+                        .iter()
+                        .copied()
+                        .find(|format| format.to_string() == fetched)
+                });
+                match format {
+                    Some(format) => self.protein_path(id, format),
+                    None => ProteinFormat::value_variants()
+                        .iter()
+                        .map(|format| self.protein_path(id, *format))
+                        .find(|path| path.as_std_path().is_file())?,
+                }
+            }
+            DatasetSpecifier::Genome(acc) => self.genome_dir(acc),
+            DatasetSpecifier::GenomeName(name) => {
+                let accession = match genome_alias(name.as_str(), false) {
+                    Some(accession) => accession,
+                    None => self
+                        .records("genome")
+                        .into_iter()
+                        .find(|meta| {
+                            meta.assembly_name
+                                .as_deref()
+                                .is_some_and(|stored| stored.eq_ignore_ascii_case(name.as_str()))
+                        })?
+                        .id
+                        .parse()
+                        .ok()?,
+                };
+                self.genome_dir(&accession)
+            }
+            // Neither is stored as one dataset; their members are.
+            DatasetSpecifier::GenomeTaxon(_) | DatasetSpecifier::SrrExperiment(_) => return None,
+            DatasetSpecifier::GenomeEnsembl(genome) => {
+                let release = match genome.release() {
+                    Some(release) => release,
+                    None => {
+                        let prefix = format!("ensembl:{}@", genome.species());
+                        self.records("genome")
+                            .into_iter()
+                            .filter_map(|meta| meta.id.strip_prefix(&prefix)?.parse::<u32>().ok())
+                            .max()?
+                    }
+                };
+                self.ensembl_dir(genome.species(), release)
+            }
+            DatasetSpecifier::Srr(id) => self.srr_dir(id),
+            DatasetSpecifier::Uniprot(id) => self.uniprot_dir(id),
+            DatasetSpecifier::StructureFor(query) => self.uniprot_dir(&query.accession),
+            DatasetSpecifier::Proteome(id) => self.proteome_dir(id),
+            DatasetSpecifier::Doi(doi) => self.doi_dir(doi),
+            DatasetSpecifier::Expression(acc) => self.expression_dir(acc),
+            DatasetSpecifier::Expression10x(acc) => self.expression10x_dir(acc),
+            DatasetSpecifier::ExpressionSample(acc) => {
+                let alone = self.expression_sample_dir(acc, None);
+                if alone.as_std_path().is_dir() {
+                    alone
+                } else {
+                    // The series a sample was stored under is only known
+                    // from its directory.
+                    fs::read_dir(self.0.join("expression"))
+                        .ok()?
+                        .flatten()
+                        .filter_map(|entry| Utf8PathBuf::from_path_buf(entry.path()).ok())
+                        .map(|series| series.join(acc.as_str()))
+                        .find(|dir| dir.as_std_path().is_dir())?
+                }
+            }
+            DatasetSpecifier::BioProject(acc) => self.bioproject_dir(acc),
+            DatasetSpecifier::Go => self.kb_dir("go"),
+            DatasetSpecifier::Kegg(None) => self.kb_dir("kegg"),
+            DatasetSpecifier::Kegg(Some(organism)) => self.kb_dir("kegg").join(organism.as_str()),
+            DatasetSpecifier::Reactome => self.kb_dir("reactome"),
+            DatasetSpecifier::Alias(alias) => {
+                let aliased = Store::list_metadata(self.0)
+                    .ok()?
+                    .into_iter()
+                    .find(|meta| meta.alias.as_deref() == Some(alias.as_str()))?;
+                let specifier = specifier_for(&aliased).parse::<DatasetSpecifier>().ok()?;
+                if matches!(specifier, DatasetSpecifier::Alias(_)) {
+                    return None;
+                }
+                return self.resolve(&specifier, format);
+            }
+        };
+        path.as_std_path().exists().then_some(path)
+    }
+
+    fn records(&self, dataset_type: &str) -> Vec<Metadata> {
+        Store::list_metadata(self.0)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|meta| meta.dataset_type == dataset_type)
+            .collect()
+    }
+}
+
+fn split_dest(dest: &Utf8Path) -> Result<(&Utf8Path, &str), KiraError> {
+    let (Some(parent), Some(name)) = (dest.parent(), dest.file_name()) else {
+        return Err(KiraError::Filesystem(
+            "invalid destination path".to_string(),
+        ));
+    };
+    fs::create_dir_all(parent.as_std_path())
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    Ok((parent, name))
+}
+
+fn parse_mtime(downloaded_at: &str) -> Option<SystemTime> {
+    DateTime::parse_from_rfc3339(downloaded_at)
+        .ok()
+        .map(SystemTime::from)
+}
+
+/// Sets the mtime of `path`, or of every file under it. Directory mtimes
+/// are left alone: they cannot be set portably.
+fn set_mtimes(path: &Path, mtime: SystemTime) -> Result<(), KiraError> {
+    let files = if path.is_dir() {
+        walk_dir(path)?
+    } else {
+        vec![path.to_path_buf()]
+    };
+    for file in files.iter().filter(|file| file.is_file()) {
+        fs::OpenOptions::new()
+            .write(true)
+            .open(file)
+            .and_then(|handle| handle.set_modified(mtime))
+            .map_err(|err| KiraError::Filesystem(format!("{}: {err}", file.display())))?;
+    }
+    Ok(())
+}
+
+const MAX_STAGING_DIRS: u32 = 1000;
+
+/// A staging directory beside a store entry, removed on drop unless
+/// persisted. It is named `{prefix}-{key}.{n}` with the lowest free `n`
+/// rather than random characters, so that a fetch stages under the same
+/// path on every run.
+pub struct StagingDir {
+    path: PathBuf,
+    persisted: bool,
+}
+
+impl StagingDir {
+    pub fn create_in(parent: &Path, prefix: &str, key: &str) -> io::Result<Self> {
+        let key = key
+            .chars()
+            .map(|ch| {
+                if ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.') {
+                    ch
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>();
+        for counter in 0..MAX_STAGING_DIRS {
+            let path = parent.join(format!("{prefix}-{key}.{counter}"));
+            match fs::create_dir(&path) {
+                Ok(()) => {
+                    return Ok(Self {
+                        path,
+                        persisted: false,
+                    });
+                }
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "{MAX_STAGING_DIRS} staging directories for {key} already exist in {}",
+                parent.display()
+            ),
+        ))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Moves the staged directory to `to`, replacing whatever is there.
+    pub fn persist(mut self, to: &Path) -> io::Result<()> {
+        atomic_rename_dir(&self.path, to)?;
+        self.persisted = true;
+        Ok(())
+    }
+
+    /// Leaves the directory where it is and returns its path.
+    pub fn keep(mut self) -> PathBuf {
+        self.persisted = true;
+        self.path.clone()
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
 }
 
+/// Moves the directory `from` to `to`. A directory already at `to` is moved
+/// aside and removed only once `from` has taken its place, so a failed
+/// rename leaves it where it was. Should it not go back either, it is kept
+/// aside and the error names where.
 pub fn atomic_rename_dir(from: &Path, to: &Path) -> io::Result<()> {
-    if to.exists() {
-        fs::remove_dir_all(to)?;
+    let Some(parent) = to.parent() else {
+        return fs::rename(from, to);
+    };
+    fs::create_dir_all(parent)?;
+    if fs::symlink_metadata(to).is_err() {
+        return fs::rename(from, to);
+    }
+    let name = to
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let aside = StagingDir::create_in(parent, "kira-bm-previous", &name)?;
+    let previous = aside.path().join("previous");
+    fs::rename(to, &previous)?;
+    if let Err(err) = fs::rename(from, to) {
+        if let Err(restore) = fs::rename(&previous, to) {
+            let kept = aside.keep().join("previous");
+            return Err(io::Error::new(
+                restore.kind(),
+                format!(
+                    "{err}; the previous {} could not be moved back ({restore}) and is kept at {}",
+                    to.display(),
+                    kept.display()
+                ),
+            ));
+        }
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Attempts at moving a finished file into place. Windows refuses to
+/// replace a file an indexer or virus scanner has open until it lets go.
+const REPLACE_ATTEMPTS: u32 = 5;
+const REPLACE_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Renames `from` over the file `to`, retrying with a doubling delay while
+/// the target is held open (see [`is_transient_replace_error`]). A rename
+/// the filesystem cannot do (`EXDEV`, e.g. across an overlay mount) becomes
+/// a copy beside `to` that is then renamed over it.
+fn replace_file(from: &Path, to: &Path) -> io::Result<()> {
+    let mut delay = REPLACE_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        let result = match fs::rename(from, to) {
+            Err(err) if err.kind() == io::ErrorKind::CrossesDevices => copy_then_rename(from, to),
+            result => result,
+        };
+        match result {
+            Ok(()) => return Ok(()),
+            Err(err) if attempt < REPLACE_ATTEMPTS && is_transient_replace_error(&err) => {
+                tracing::debug!("retrying the move to {}: {err}", to.display());
+                attempt += 1;
+                std::thread::sleep(delay);
+                delay *= 2;
+            }
+            Err(err) => return Err(err),
+        }
     }
-    fs::rename(from, to)
+}
+
+/// Errors a file held open by another process shows up as. Anything else,
+/// a missing source or a full disk, will not pass by waiting.
+fn is_transient_replace_error(err: &io::Error) -> bool {
+    /// `ERROR_SHARING_VIOLATION` and `ERROR_LOCK_VIOLATION`.
+    #[cfg(windows)]
+    const SHARING_ERRORS: [i32; 2] = [32, 33];
+    #[cfg(not(windows))]
+    const SHARING_ERRORS: [i32; 0] = [];
+    matches!(
+        err.kind(),
+        io::ErrorKind::PermissionDenied | io::ErrorKind::AlreadyExists
+    ) || err
+        .raw_os_error()
+        .is_some_and(|code| SHARING_ERRORS.contains(&code))
+}
+
+fn copy_then_rename(from: &Path, to: &Path) -> io::Result<()> {
+    let mut name = to.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".kira-bm-copy.{}", std::process::id()));
+    let copy = to.with_file_name(name);
+    // The copy keeps the mtime a materialized file was given.
+    let result = fs::copy(from, &copy)
+        .and_then(|_| fs::metadata(from)?.modified())
+        .and_then(|mtime| {
+            fs::OpenOptions::new()
+                .write(true)
+                .open(&copy)?
+                .set_modified(mtime)
+        })
+        .and_then(|()| fs::rename(&copy, to));
+    if result.is_err() {
+        let _ = fs::remove_file(&copy);
+    }
+    result
 }
 
 fn protein_ext(format: ProteinFormat) -> &'static str {
@@ -327,6 +2221,12 @@ fn protein_ext(format: ProteinFormat) -> &'static str {
     }
 }
 
+/// An id as a file name. Windows refuses the colon of
+/// `ensembl:homo_sapiens@110`, so it is percent-encoded as in DOI records.
+fn file_stem(id: &str) -> String {
+    id.replace(':', "%3A")
+}
+
 fn encode_doi_segment(value: &str) -> String {
     let mut out = String::new();
     for byte in value.as_bytes() {
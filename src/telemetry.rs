@@ -0,0 +1,309 @@
+//! OTLP export of registry spans.
+//!
+//! Built with the `telemetry` feature, `--otlp-endpoint <url>` installs an
+//! [`OtlpLayer`] next to the log output. It collects every span the tool
+//! closes (fetched items and the registry calls beneath them, with their
+//! events) and, when the command ends, posts them in one OTLP/HTTP JSON
+//! request to `<url>/v1/traces`. A run is one trace.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::Url;
+use serde_json::{Value, json};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+const SERVICE_NAME: &str = "kira-bm";
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// OTLP span kinds: registry calls are clients, everything else internal.
+const KIND_INTERNAL: u8 = 1;
+const KIND_CLIENT: u8 = 3;
+const STATUS_ERROR: u8 = 2;
+
+type Finished = Arc<Mutex<Vec<Value>>>;
+
+/// Collects closed spans as OTLP JSON.
+pub struct OtlpLayer {
+    trace_id: String,
+    finished: Finished,
+}
+
+/// Sends the collected spans when dropped, at the end of the command.
+pub struct OtlpGuard {
+    endpoint: Url,
+    finished: Finished,
+}
+
+/// A layer and the guard that exports what it collects to `endpoint`.
+pub fn otlp(endpoint: &Url) -> (OtlpLayer, OtlpGuard) {
+    let finished = Finished::default();
+    let layer = OtlpLayer {
+        trace_id: format!("{:016x}{:016x}", next_id(), next_id()),
+        finished: finished.clone(),
+    };
+    let guard = OtlpGuard {
+        endpoint: endpoint.clone(),
+        finished,
+    };
+    (layer, guard)
+}
+
+impl OtlpGuard {
+    /// Posts the spans collected so far; returns how many were sent.
+    pub fn export(&self) -> Result<usize, String> {
+        let spans = std::mem::take(&mut *lock(&self.finished));
+        if spans.is_empty() {
+            return Ok(0);
+        }
+        let count = spans.len();
+        let url = traces_url(&self.endpoint);
+        let response = reqwest::blocking::Client::new()
+            .post(url.clone())
+            .timeout(EXPORT_TIMEOUT)
+            .json(&export_request(spans))
+            .send()
+            .map_err(|err| format!("{url}: {err}"))?;
+        if !response.status().is_success() {
+            return Err(format!("{url}: HTTP {}", response.status().as_u16()));
+        }
+        Ok(count)
+    }
+}
+
+impl Drop for OtlpGuard {
+    fn drop(&mut self) {
+        if let Err(err) = self.export() {
+            eprintln!("warning: unable to export traces: {err}");
+        }
+    }
+}
+
+/// What is known of an open span.
+struct SpanData {
+    span_id: String,
+    parent_span_id: Option<String>,
+    started: SystemTime,
+    attributes: Vec<(String, Value)>,
+    events: Vec<Value>,
+}
+
+impl<S> Layer<S> for OtlpLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let parent_span_id = span.parent().and_then(|parent| {
+            parent
+                .extensions()
+                .get::<SpanData>()
+                .map(|data| data.span_id.clone())
+        });
+        let mut attributes = Vec::new();
+        attrs.record(&mut FieldRecorder(&mut attributes));
+        span.extensions_mut().insert(SpanData {
+            span_id: format!("{:016x}", next_id()),
+            parent_span_id,
+            started: SystemTime::now(),
+            attributes,
+            events: Vec::new(),
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id)
+            && let Some(data) = span.extensions_mut().get_mut::<SpanData>()
+        {
+            values.record(&mut FieldRecorder(&mut data.attributes));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.event_span(event) else {
+            return;
+        };
+        let mut attributes = Vec::new();
+        event.record(&mut FieldRecorder(&mut attributes));
+        let name = take_attribute(&mut attributes, "message")
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_else(|| event.metadata().name().to_string());
+        attributes.push((
+            "level".to_string(),
+            Value::String(event.metadata().level().to_string()),
+        ));
+        if let Some(data) = span.extensions_mut().get_mut::<SpanData>() {
+            data.events.push(json!({
+                "timeUnixNano": unix_nanos(SystemTime::now()),
+                "name": name,
+                "attributes": key_values(&attributes),
+            }));
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(data) = span.extensions_mut().remove::<SpanData>() else {
+            return;
+        };
+        let attribute = |key: &str| {
+            data.attributes
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value)
+        };
+        let kind = if attribute("endpoint").is_some() {
+            KIND_CLIENT
+        } else {
+            KIND_INTERNAL
+        };
+        let failed = attribute("status")
+            .and_then(Value::as_u64)
+            .is_some_and(|status| status >= 400);
+        let mut otlp = json!({
+            "traceId": self.trace_id,
+            "spanId": data.span_id,
+            "name": span.name(),
+            "kind": kind,
+            "startTimeUnixNano": unix_nanos(data.started),
+            "endTimeUnixNano": unix_nanos(SystemTime::now()),
+            "attributes": key_values(&data.attributes),
+            "events": data.events,
+        });
+        if let Some(parent) = data.parent_span_id {
+            otlp["parentSpanId"] = Value::String(parent);
+        }
+        if failed {
+            otlp["status"] = json!({ "code": STATUS_ERROR });
+        }
+        lock(&self.finished).push(otlp);
+    }
+}
+
+/// Records fields as attributes, a later value replacing an earlier one.
+struct FieldRecorder<'a>(&'a mut Vec<(String, Value)>);
+
+impl FieldRecorder<'_> {
+    fn set(&mut self, field: &Field, value: Value) {
+        match self.0.iter_mut().find(|(name, _)| name == field.name()) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((field.name().to_string(), value)),
+        }
+    }
+}
+
+impl Visit for FieldRecorder<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set(field, Value::String(value.to_string()));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.set(field, Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.set(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.set(field, Value::Bool(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.set(field, Value::String(format!("{value:?}")));
+    }
+}
+
+fn take_attribute(attributes: &mut Vec<(String, Value)>, key: &str) -> Option<Value> {
+    let index = attributes.iter().position(|(name, _)| name == key)?;
+    Some(attributes.remove(index).1)
+}
+
+/// OTLP `KeyValue`s; integers travel as strings, per the JSON mapping.
+fn key_values(attributes: &[(String, Value)]) -> Vec<Value> {
+    attributes
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::Bool(value) => json!({ "boolValue": value }),
+                Value::Number(number) if number.is_i64() || number.is_u64() => {
+                    json!({ "intValue": number.to_string() })
+                }
+                Value::Number(number) => json!({ "doubleValue": number.as_f64() }),
+                Value::String(text) => json!({ "stringValue": text }),
+                other => json!({ "stringValue": other.to_string() }),
+            };
+            json!({ "key": key, "value": value })
+        })
+        .collect()
+}
+
+pub fn export_request(spans: Vec<Value>) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": key_values(&[
+                    ("service.name".to_string(), Value::from(SERVICE_NAME)),
+                    ("service.version".to_string(), Value::from(env!("CARGO_PKG_VERSION"))),
+                ]),
+            },
+            "scopeSpans": [{
+                "scope": { "name": SERVICE_NAME },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+/// `<endpoint>/v1/traces`, unless the endpoint names the path already.
+pub fn traces_url(endpoint: &Url) -> Url {
+    if endpoint.path().ends_with("/v1/traces") {
+        return endpoint.clone();
+    }
+    let mut url = endpoint.clone();
+    let path = format!("{}/v1/traces", endpoint.path().trim_end_matches('/'));
+    url.set_path(&path);
+    url
+}
+
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or(0)
+        .to_string()
+}
+
+/// Non-zero ids from a splitmix64 sequence seeded by the clock and the pid.
+fn next_id() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or(0)
+        ^ (u64::from(std::process::id()) << 32);
+    let mut z = seed.wrapping_add(
+        COUNTER
+            .fetch_add(1, Ordering::Relaxed)
+            .wrapping_add(1)
+            .wrapping_mul(0x9e37_79b9_7f4a_7c15),
+    );
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (z ^ (z >> 31)).max(1)
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::io::{self, Write};
 use std::time::{Duration, Instant, SystemTime};
 use std::{
@@ -7,6 +7,7 @@ use std::{
     thread,
 };
 
+use camino::Utf8PathBuf;
 use crossterm::ExecutableCommand;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
 use crossterm::terminal::{
@@ -18,20 +19,36 @@ use ratatui::backend::CrosstermBackend;
 use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, Wrap};
 use serde_json::Value;
 
-use crate::app::{ProgressEvent, ProgressSink, ProgressSinkKind};
+use crate::app::{
+    ClearResult, DoiTarget, ProgressEvent, ProgressSink, ProgressSinkKind, dataset_details,
+    stored_run_info,
+};
+use crate::audit::specifier_for;
+use crate::cancel::CancellationToken;
+use crate::config::{Config, ConfigLoader, ConfigSection, write_config_atomic};
+use crate::control::{self, BatchState, ControlSettings};
+use crate::domain::DatasetSpecifier;
 use crate::error::KiraError;
-use crate::store::Store;
+use crate::fs_util::{bytes_to_human, dir_size, path_size};
+use crate::history;
+use crate::output::PlainOutput;
+use crate::progress::DownloadProgress;
+use crate::project::{CONFIG_FILE, Project};
+use crate::stats::{CacheSavings, millis_to_human};
+use crate::store::{self, Metadata, Store, StoreAnalytics};
 
 const EVENTS_MAX: usize = 6;
 const LOGS_MAX: usize = 200;
+const BROWSER_PAGE: usize = 10;
+const STRUCTURES_SHOWN: usize = 5;
 const HINTS: &[&str] = &[
     "Tip: use TAB to autocomplete commands and specifiers",
     "Tip: try protein:1LYZ or genome:GCF_000005845.2",
     "Tip: / starts history search, : starts command mode",
-    "Tip: F2 local browser, F4 logs, F5 config",
+    "Tip: F2 local browser, F4 logs, F5 config, F6 pause batch",
 ];
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -97,6 +114,17 @@ struct DatasetInfo {
     source: Option<String>,
     name: Option<String>,
     organism: Option<String>,
+    structures: Vec<String>,
+    /// Chains and length of each sequence of a protein.
+    sequences: Vec<String>,
+    /// Size, platform and layout of an SRR run.
+    run_info: Option<String>,
+    /// Cells and genes of each bundle of a 10x series.
+    bundles: Vec<String>,
+    /// What the fetch that opened the view saved by hitting the cache.
+    savings: Option<CacheSavings>,
+    /// The kira-bm version, command line and platform that fetched it.
+    acquired: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -106,6 +134,51 @@ struct StoreSummary {
     cache_count: usize,
     cache_bytes: u64,
     cache_ok: bool,
+    analytics: StoreAnalytics,
+}
+
+#[derive(Debug, Clone)]
+struct BrowserRow {
+    location: &'static str,
+    metadata: Metadata,
+    size: Option<u64>,
+}
+
+impl BrowserRow {
+    /// Where the row's data is, as `kira-bm path` resolves it.
+    fn path(&self) -> Option<Utf8PathBuf> {
+        let specifier = specifier_for(&self.metadata)
+            .parse::<DatasetSpecifier>()
+            .ok()?;
+        let store = Store::new().ok()?;
+        if self.location == "cache" {
+            store.resolve_cache_dataset_path(&specifier, None)
+        } else {
+            store.resolve_dataset_path(&specifier, None)
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Browser {
+    rows: Vec<BrowserRow>,
+    // Keyed by (resolved_path, downloaded_at) so a re-fetch invalidates the entry.
+    sizes: HashMap<(String, String), Option<u64>>,
+    filter: String,
+    filtering: bool,
+    selected: usize,
+    confirm_delete: bool,
+    details: Option<Value>,
+}
+
+#[derive(Debug, Default)]
+struct ConfigEditor {
+    path: std::path::PathBuf,
+    config: Config,
+    selected: usize,
+    adding: Option<String>,
+    error: Option<String>,
+    notice: Option<String>,
 }
 
 #[derive(Debug)]
@@ -133,6 +206,25 @@ struct AppState {
     request_count: u64,
     hint_index: usize,
     last_hint_update: Instant,
+    paused: bool,
+    items: Vec<ItemProgress>,
+    /// Latest byte report of the download in flight.
+    download: Option<DownloadProgress>,
+    confirm_cancel: bool,
+    cancelling: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ItemStatus {
+    Active,
+    Done,
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+struct ItemProgress {
+    label: String,
+    status: ItemStatus,
 }
 
 pub struct Tui {
@@ -143,24 +235,55 @@ pub struct Tui {
     history: Vec<String>,
     history_index: Option<usize>,
     log_scroll: u16,
+    browser: Browser,
+    config_editor: ConfigEditor,
+    cancel: CancellationToken,
+    /// Held between [`Tui::open`] and [`Tui::close`], so commands run one
+    /// after another on the same screen.
+    terminal: Option<TuiTerminal>,
 }
 
+type TuiTerminal = Terminal<CrosstermBackend<io::Stdout>>;
+
 struct TuiProgress {
     state: Arc<Mutex<AppState>>,
+    cancel: CancellationToken,
 }
 
 impl ProgressSink for TuiProgress {
     fn event(&self, event: ProgressEvent) {
         if let Ok(mut state) = self.state.lock() {
+            // Byte reports only move the download bar; they would drown the
+            // event list and the log.
+            if let Some(progress) = DownloadProgress::from_event(&event) {
+                state.download = Some(progress);
+                return;
+            }
             let message = event.message.trim().to_string();
             let display = humanize_event(&message);
             if let Some((phase, payload)) = parse_phase(&message) {
                 state.phase = phase;
+                state.download = None;
                 state.status = payload.to_string();
                 state.confidence = confidence_for(phase);
                 state.status_level = StatusLevel::Info;
             } else if let Some(latency) = parse_latency(&message) {
                 state.latency_ms = Some(latency);
+            } else if let Some((label, status)) = parse_item_event(&message) {
+                state.download = None;
+                match state.items.iter_mut().find(|item| item.label == label) {
+                    Some(item) => item.status = status,
+                    None => state.items.push(ItemProgress {
+                        label: label.to_string(),
+                        status,
+                    }),
+                }
+                state.status = display.clone();
+                state.status_level = StatusLevel::Info;
+            } else if message == "batch.paused" || message == "batch.resumed" {
+                state.paused = message == "batch.paused";
+                state.status = display.clone();
+                state.status_level = StatusLevel::Warning;
             } else if message.contains("retry") {
                 state.retries = state.retries.saturating_add(1);
             } else {
@@ -182,16 +305,21 @@ impl ProgressSink for TuiProgress {
             append_log_line(&line);
         }
     }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
 }
 
 impl Tui {
     pub fn new(kind: ProgressSinkKind) -> Self {
-        let summary = compute_store_summary().unwrap_or_else(|| StoreSummary {
+        let summary = compute_store_summary().unwrap_or(StoreSummary {
             project_count: 0,
             project_bytes: 0,
             cache_count: 0,
             cache_bytes: 0,
             cache_ok: false,
+            analytics: StoreAnalytics::default(),
         });
         let logs = load_log_history();
         Self {
@@ -220,12 +348,67 @@ impl Tui {
                 request_count: 0,
                 hint_index: 0,
                 last_hint_update: Instant::now(),
+                paused: false,
+                items: Vec::new(),
+                download: None,
+                confirm_cancel: false,
+                cancelling: false,
             })),
             input: String::new(),
             cursor: 0,
             history: Vec::new(),
             history_index: None,
             log_scroll: 0,
+            browser: Browser::default(),
+            config_editor: ConfigEditor::default(),
+            cancel: CancellationToken::default(),
+            terminal: None,
+        }
+    }
+
+    /// Keeps the screen up until [`Tui::close`]: `run`, `idle_command` and
+    /// the prompts draw on it instead of entering and leaving their own.
+    pub fn open(&mut self) -> miette::Result<()> {
+        if self.terminal.is_none() {
+            self.terminal = Some(enter_screen()?);
+        }
+        Ok(())
+    }
+
+    pub fn close(&mut self) -> miette::Result<()> {
+        match self.terminal.take() {
+            Some(_) => leave_screen(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.terminal.is_some()
+    }
+
+    /// What the header calls the running operation.
+    pub fn set_kind(&mut self, kind: ProgressSinkKind) {
+        self.kind = kind;
+    }
+
+    /// Lends `f` the open session's terminal, or one set up and torn down
+    /// around the call.
+    fn with_terminal<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self, &mut TuiTerminal) -> miette::Result<T>,
+    ) -> miette::Result<T> {
+        match self.terminal.take() {
+            Some(mut terminal) => {
+                let result = f(self, &mut terminal);
+                self.terminal = Some(terminal);
+                result
+            }
+            None => {
+                let mut terminal = enter_screen()?;
+                let result = f(self, &mut terminal);
+                leave_screen()?;
+                result
+            }
         }
     }
 
@@ -234,58 +417,70 @@ impl Tui {
         F: FnOnce(&dyn ProgressSink) -> Result<R, KiraError> + Send + 'static,
         R: Send + 'static,
     {
-        self.set_active(true);
-
-        let mut stdout = io::stdout();
-        enable_raw_mode().into_diagnostic()?;
-        stdout.execute(EnterAlternateScreen).into_diagnostic()?;
+        // An open session goes back to its prompt, which keeps the
+        // checklist on screen; a one-off run waits for it to be read.
+        let session = self.is_open();
+        self.with_terminal(|tui, terminal| {
+            let (result, tick) = tui.execute(terminal, f)?;
+            if !session && tui.needs_dismiss(result.is_ok()) {
+                tui.await_dismiss(terminal, tick)?;
+            }
+            result.map_err(miette::Report::new)
+        })
+    }
 
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend).into_diagnostic()?;
-        terminal.clear().into_diagnostic()?;
+    /// Runs `f` on a worker thread, drawing its progress on `terminal` until
+    /// it returns. The terminal is left as it is.
+    fn execute<F, R>(
+        &mut self,
+        terminal: &mut TuiTerminal,
+        f: F,
+    ) -> miette::Result<(Result<R, KiraError>, usize)>
+    where
+        F: FnOnce(&dyn ProgressSink) -> Result<R, KiraError> + Send + 'static,
+        R: Send + 'static,
+    {
+        self.set_active(true);
+        self.cancel = CancellationToken::default();
 
         let (tx, rx) = std::sync::mpsc::channel();
         let state = self.state.clone();
         let sink = TuiProgress {
             state: state.clone(),
+            cancel: self.cancel.clone(),
         };
-        let handle = thread::spawn(move || tx.send(f(&sink)));
+        let cancel = self.cancel.clone();
+        let handle = thread::spawn(move || {
+            let _scope = cancel.enter();
+            tx.send(f(&sink))
+        });
 
         let mut tick = 0usize;
         loop {
             self.refresh_metrics();
             if let Ok(state) = self.state.lock() {
-                let elapsed = state.started.elapsed();
                 terminal
-                    .draw(|frame| draw_ui(frame, self, &state, tick, elapsed))
+                    .draw(|frame| draw_ui(frame, self, &state, tick))
                     .into_diagnostic()?;
             }
 
             if let Ok(result) = rx.try_recv() {
                 self.set_active(false);
-                disable_raw_mode().into_diagnostic()?;
-                let mut stdout = io::stdout();
-                stdout.execute(LeaveAlternateScreen).into_diagnostic()?;
                 handle.join().ok();
-                return result.map_err(|err| miette::Report::new(err));
+                return Ok((result, tick));
             }
 
-            if event::poll(Duration::from_millis(120)).into_diagnostic()? {
-                if let Event::Key(key) = event::read().into_diagnostic()? {
-                    if self.handle_key(key) {
-                        break;
-                    }
-                }
+            // The worker owns the store until it returns, so leaving is
+            // always a cancellation that waits for it.
+            if event::poll(Duration::from_millis(120)).into_diagnostic()?
+                && let Event::Key(key) = event::read().into_diagnostic()?
+                && self.handle_key(key)
+            {
+                self.cancel_active();
             }
 
             tick = tick.wrapping_add(1);
         }
-
-        self.set_active(false);
-        disable_raw_mode().into_diagnostic()?;
-        let mut stdout = io::stdout();
-        stdout.execute(LeaveAlternateScreen).into_diagnostic()?;
-        Err(miette::Report::msg("aborted"))
     }
 
     pub fn note(&self, message: &str) {
@@ -332,71 +527,76 @@ impl Tui {
         self.set_active(false);
         if let Ok(mut state) = self.state.lock() {
             state.status = "ready".to_string();
-            state.view = View::Operational;
+            // The dataset a session command just showed stays up.
+            if state.view != View::DataFocus {
+                state.view = View::Operational;
+            }
             state.input_mode = InputMode::Command;
         }
 
-        let mut stdout = io::stdout();
-        enable_raw_mode().into_diagnostic()?;
-        stdout.execute(EnterAlternateScreen).into_diagnostic()?;
-
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend).into_diagnostic()?;
-        terminal.clear().into_diagnostic()?;
-
-        let mut tick = 0usize;
-        let mut command: Option<String> = None;
-        loop {
-            self.refresh_metrics();
-            if let Ok(state) = self.state.lock() {
-                let elapsed = state.started.elapsed();
-                terminal
-                    .draw(|frame| draw_ui(frame, self, &state, tick, elapsed))
-                    .into_diagnostic()?;
-            }
+        self.with_terminal(|tui, terminal| {
+            let mut tick = 0usize;
+            loop {
+                tui.refresh_metrics();
+                if let Ok(state) = tui.state.lock() {
+                    terminal
+                        .draw(|frame| draw_ui(frame, tui, &state, tick))
+                        .into_diagnostic()?;
+                }
 
-            if event::poll(Duration::from_millis(120)).into_diagnostic()? {
-                if let Event::Key(key) = event::read().into_diagnostic()? {
-                    if matches!(key.code, KeyCode::Enter) {
-                        if let Some(cmd) = self.take_command() {
-                            command = Some(cmd);
-                            break;
-                        }
+                if event::poll(Duration::from_millis(120)).into_diagnostic()?
+                    && let Event::Key(key) = event::read().into_diagnostic()?
+                {
+                    if matches!(key.code, KeyCode::Enter)
+                        && let Some(cmd) = tui.take_command()
+                    {
+                        return Ok(Some(cmd));
                     }
-                    if self.handle_key(key) {
-                        break;
+                    if tui.handle_key(key) {
+                        return Ok(None);
                     }
                 }
-            }
-
-            tick = tick.wrapping_add(1);
-        }
 
-        disable_raw_mode().into_diagnostic()?;
-        let mut stdout = io::stdout();
-        stdout.execute(LeaveAlternateScreen).into_diagnostic()?;
-        Ok(command)
+                tick = tick.wrapping_add(1);
+            }
+        })
     }
 
     pub fn finish_fetch(&mut self, result: &crate::app::FetchResult) -> miette::Result<()> {
-        if let Some(item) = result.items.first() {
-            if let Ok(mut state) = self.state.lock() {
-                let (name, organism) = load_uniprot_details(
+        // Batches stay on the checklist; only a single dataset gets focus.
+        if let [item] = result.items.as_slice()
+            && result.errors.is_empty()
+            && let Ok(mut state) = self.state.lock()
+        {
+            let (name, organism) = load_uniprot_details(
+                item.dataset_type.as_str(),
+                item.project_path.as_deref(),
+                item.cache_path.as_deref(),
+            );
+            state.dataset = Some(DatasetInfo {
+                dataset_type: item.dataset_type.clone(),
+                id: item.id.clone(),
+                format: item.format.clone(),
+                source: Some(item.source.clone()),
+                name,
+                organism,
+                structures: Vec::new(),
+                sequences: Vec::new(),
+                run_info: load_run_info(
                     item.dataset_type.as_str(),
                     item.project_path.as_deref(),
                     item.cache_path.as_deref(),
-                );
-                state.dataset = Some(DatasetInfo {
-                    dataset_type: item.dataset_type.clone(),
-                    id: item.id.clone(),
-                    format: item.format.clone(),
-                    source: Some(item.source.clone()),
-                    name,
-                    organism,
-                });
-                state.view = View::DataFocus;
-                state.input_mode = InputMode::Command;
-            }
+                ),
+                bundles: load_bundle_rows(
+                    item.dataset_type.as_str(),
+                    item.project_path.as_deref(),
+                    item.cache_path.as_deref(),
+                ),
+                savings: item.cache_savings,
+                acquired: None,
+            });
+            state.view = View::DataFocus;
+            state.input_mode = InputMode::Command;
         }
         Ok(())
     }
@@ -418,10 +618,31 @@ impl Tui {
             state.dataset = Some(DatasetInfo {
                 dataset_type: result.dataset_type.clone(),
                 id: result.id.clone(),
-                format: result.format.clone(),
+                format: if result.formats.is_empty() {
+                    result.format.clone()
+                } else {
+                    Some(
+                        result
+                            .formats
+                            .keys()
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    )
+                },
                 source: result.source.clone(),
                 name,
                 organism,
+                structures: structure_rows(result.details.as_ref()),
+                sequences: sequence_rows(result.details.as_ref()),
+                run_info: load_run_info(
+                    result.dataset_type.as_str(),
+                    result.project_path.as_deref(),
+                    result.cache_path.as_deref(),
+                ),
+                bundles: bundle_rows(result.details.as_ref()),
+                savings: None,
+                acquired: result.acquisition.as_ref().map(ToString::to_string),
             });
             state.view = View::DataFocus;
             state.input_mode = InputMode::Command;
@@ -437,47 +658,172 @@ impl Tui {
         Ok(())
     }
 
-    pub fn confirm_clear(&mut self) -> miette::Result<bool> {
-        let mut stdout = io::stdout();
-        enable_raw_mode().into_diagnostic()?;
-        stdout.execute(EnterAlternateScreen).into_diagnostic()?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend).into_diagnostic()?;
-
-        let confirmed = loop {
-            terminal
-                .draw(|frame| {
-                    let block = Block::default().borders(Borders::ALL).title("Confirm");
-                    let text = Paragraph::new(vec![
-                        Line::from("Clear project store?"),
-                        Line::from("Press y to confirm, n to cancel."),
-                    ])
-                    .alignment(Alignment::Center)
-                    .block(block);
-                    frame.render_widget(text, frame.area());
-                })
-                .into_diagnostic()?;
+    /// Asks before clearing, showing what a dry run of the clear found.
+    pub fn confirm_clear(&mut self, preview: &ClearResult) -> miette::Result<bool> {
+        let mut lines = vec![Line::from("Clear project store?"), Line::from("")];
+        lines.extend(
+            PlainOutput::render_clear(preview)
+                .lines()
+                .map(|line| Line::from(line.to_string())),
+        );
+        lines.push(Line::from(""));
+        lines.push(Line::from("Press y to confirm, n to cancel."));
+        self.with_terminal(|_, terminal| {
+            loop {
+                terminal
+                    .draw(|frame| {
+                        let block = Block::default().borders(Borders::ALL).title("Confirm");
+                        let text = Paragraph::new(lines.clone())
+                            .alignment(Alignment::Center)
+                            .block(block);
+                        frame.render_widget(text, frame.area());
+                    })
+                    .into_diagnostic()?;
 
-            if event::poll(Duration::from_millis(100)).into_diagnostic()? {
-                if let Event::Key(key) = event::read().into_diagnostic()? {
+                if event::poll(Duration::from_millis(100)).into_diagnostic()?
+                    && let Event::Key(key) = event::read().into_diagnostic()?
+                {
                     match key.code {
-                        KeyCode::Char('y') | KeyCode::Char('Y') => break true,
-                        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => break false,
+                        KeyCode::Char('y') | KeyCode::Char('Y') => return Ok(true),
+                        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => return Ok(false),
                         _ => {}
                     }
                 }
             }
-        };
+        })
+    }
+
+    /// Asks which of `candidates` a bare id typed as `value` meant.
+    /// Returns `None` on Esc.
+    pub fn pick_specifier(
+        &mut self,
+        value: &str,
+        candidates: &[DatasetSpecifier],
+    ) -> miette::Result<Option<DatasetSpecifier>> {
+        let mut cursor = 0usize;
+        let picked = self.with_terminal(|_, terminal| {
+            loop {
+                let mut lines = vec![
+                    Line::from(format!("{value} could name more than one dataset:")),
+                    Line::from(""),
+                ];
+                lines.extend(candidates.iter().enumerate().map(|(index, candidate)| {
+                    let style = if index == cursor {
+                        Style::default().add_modifier(Modifier::REVERSED)
+                    } else {
+                        Style::default()
+                    };
+                    Line::from(Span::styled(format!("{}. {candidate}", index + 1), style))
+                }));
+                terminal
+                    .draw(|frame| {
+                        let block = Block::default()
+                            .borders(Borders::ALL)
+                            .title("Which one? | enter pick, esc cancel");
+                        frame.render_widget(Paragraph::new(lines).block(block), frame.area());
+                    })
+                    .into_diagnostic()?;
 
-        disable_raw_mode().into_diagnostic()?;
-        let mut stdout = io::stdout();
-        stdout.execute(LeaveAlternateScreen).into_diagnostic()?;
-        Ok(confirmed)
+                if event::poll(Duration::from_millis(100)).into_diagnostic()?
+                    && let Event::Key(key) = event::read().into_diagnostic()?
+                    && key.kind == KeyEventKind::Press
+                {
+                    match key.code {
+                        KeyCode::Up | KeyCode::Char('k') => cursor = cursor.saturating_sub(1),
+                        KeyCode::Down | KeyCode::Char('j') => {
+                            cursor = (cursor + 1).min(candidates.len().saturating_sub(1));
+                        }
+                        KeyCode::Char(digit @ '1'..='9') => {
+                            let index = digit as usize - '1' as usize;
+                            if index < candidates.len() {
+                                return Ok(Some(index));
+                            }
+                        }
+                        KeyCode::Enter => return Ok(Some(cursor)),
+                        KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
+                        _ => {}
+                    }
+                }
+            }
+        })?;
+        Ok(picked.map(|index| candidates[index].clone()))
+    }
+
+    /// A checklist of a DOI's resolved targets, grouped by type. Returns
+    /// the specifiers ticked when confirmed with Enter, `None` on Esc.
+    pub fn select_targets(
+        &mut self,
+        doi: &str,
+        targets: &[DoiTarget],
+        mut checked: Vec<bool>,
+    ) -> miette::Result<Option<Vec<String>>> {
+        let mut cursor = 0usize;
+        let confirmed = self.with_terminal(|_, terminal| {
+            loop {
+                terminal
+                    .draw(|frame| draw_target_checklist(frame, doi, targets, &checked, cursor))
+                    .into_diagnostic()?;
+
+                if event::poll(Duration::from_millis(100)).into_diagnostic()?
+                    && let Event::Key(key) = event::read().into_diagnostic()?
+                    && key.kind == KeyEventKind::Press
+                {
+                    match key.code {
+                        KeyCode::Up | KeyCode::Char('k') => cursor = cursor.saturating_sub(1),
+                        KeyCode::Down | KeyCode::Char('j') => {
+                            cursor = (cursor + 1).min(targets.len().saturating_sub(1));
+                        }
+                        KeyCode::Char(' ') => {
+                            if let Some(tick) = checked.get_mut(cursor) {
+                                *tick = !*tick;
+                            }
+                        }
+                        KeyCode::Char('a') => {
+                            if let Some(current) = targets.get(cursor) {
+                                let group = targets
+                                    .iter()
+                                    .enumerate()
+                                    .filter(|(_, target)| {
+                                        target.dataset_type == current.dataset_type
+                                    })
+                                    .map(|(index, _)| index)
+                                    .collect::<Vec<_>>();
+                                let tick = group.iter().any(|index| !checked[*index]);
+                                for index in group {
+                                    checked[index] = tick;
+                                }
+                            }
+                        }
+                        KeyCode::Enter => return Ok(true),
+                        KeyCode::Esc | KeyCode::Char('q') => return Ok(false),
+                        _ => {}
+                    }
+                }
+            }
+        })?;
+        Ok(confirmed.then(|| {
+            targets
+                .iter()
+                .zip(&checked)
+                .filter(|(_, tick)| **tick)
+                .map(|(target, _)| target.specifier.clone())
+                .collect()
+        }))
     }
     fn handle_key(&mut self, key: KeyEvent) -> bool {
         if key.kind != KeyEventKind::Press {
             return false;
         }
+        if self.is_active() && self.confirming_cancel() {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => self.cancel_active(),
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                    self.set_confirm_cancel(false)
+                }
+                _ => {}
+            }
+            return false;
+        }
         if matches!(key.code, KeyCode::F(1)) {
             self.set_view(View::Help);
             self.set_input_mode(InputMode::Help);
@@ -485,6 +831,7 @@ impl Tui {
         }
         if matches!(key.code, KeyCode::F(2)) {
             self.set_view(View::Browser);
+            self.browser.refresh();
             return false;
         }
         if matches!(key.code, KeyCode::F(3)) {
@@ -498,8 +845,19 @@ impl Tui {
         }
         if matches!(key.code, KeyCode::F(5)) {
             self.set_view(View::Config);
+            self.config_editor.load();
             return false;
         }
+        if matches!(key.code, KeyCode::F(6)) {
+            self.toggle_pause();
+            return false;
+        }
+        if self.view() == View::Browser {
+            return self.handle_browser_key(key);
+        }
+        if self.view() == View::Config {
+            return self.handle_config_key(key);
+        }
 
         match key.code {
             KeyCode::Char('q') => {
@@ -508,11 +866,16 @@ impl Tui {
                     return false;
                 }
                 if self.is_active() {
+                    self.set_confirm_cancel(true);
                     return false;
                 }
                 return true;
             }
             KeyCode::Esc => {
+                if self.is_active() {
+                    self.set_confirm_cancel(true);
+                    return false;
+                }
                 return true;
             }
             KeyCode::Char(':') => {
@@ -556,11 +919,11 @@ impl Tui {
             KeyCode::Home => self.cursor = 0,
             KeyCode::End => self.cursor = self.input.len(),
             KeyCode::Enter => {
-                if self.input_mode() == InputMode::Search {
-                    if let Some(best) = self.best_history_match() {
-                        self.set_input_text(&best);
-                        self.set_input_mode(InputMode::Command);
-                    }
+                if self.input_mode() == InputMode::Search
+                    && let Some(best) = self.best_history_match()
+                {
+                    self.set_input_text(&best);
+                    self.set_input_mode(InputMode::Command);
                 }
             }
             KeyCode::Backspace => {
@@ -575,6 +938,183 @@ impl Tui {
         false
     }
 
+    /// Sends pause or resume to the running batch through the control file.
+    fn toggle_pause(&mut self) {
+        let settings = ControlSettings::default();
+        let result = Store::new().and_then(|store| {
+            let root = store.project_root();
+            let report = control::status(root, settings)?;
+            let paused = report
+                .request
+                .as_ref()
+                .map(|request| request.state == BatchState::Paused)
+                .or(report
+                    .batch
+                    .as_ref()
+                    .map(|batch| batch.state == BatchState::Paused))
+                .unwrap_or(false);
+            let next = if paused {
+                BatchState::Running
+            } else {
+                BatchState::Paused
+            };
+            control::request(root, next, settings).map(|_| next)
+        });
+        if let Ok(mut state) = self.state.lock() {
+            match result {
+                Ok(BatchState::Paused) => {
+                    state.status = "pause requested; waiting for the current item".to_string();
+                }
+                Ok(BatchState::Running) => state.status = "resume requested".to_string(),
+                Err(err) => state.status = err.to_string(),
+            }
+            state.status_level = StatusLevel::Warning;
+        }
+    }
+
+    fn handle_config_key(&mut self, key: KeyEvent) -> bool {
+        let editor = &mut self.config_editor;
+        if let Some(input) = editor.adding.as_mut() {
+            match key.code {
+                KeyCode::Enter => editor.submit_add(),
+                KeyCode::Esc => {
+                    editor.adding = None;
+                    editor.error = None;
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(ch) => input.push(ch),
+                _ => {}
+            }
+            return false;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => return !self.is_active(),
+            KeyCode::Esc => self.set_view(View::Operational),
+            KeyCode::Up | KeyCode::Char('k') => editor.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => editor.move_selection(1),
+            KeyCode::Char(' ') => editor.toggle_selected(),
+            KeyCode::Char('a') => {
+                editor.adding = Some(String::new());
+                editor.error = None;
+            }
+            KeyCode::Char('d') => editor.remove_selected(),
+            KeyCode::Char('r') => editor.load(),
+            _ => {}
+        }
+        false
+    }
+
+    fn handle_browser_key(&mut self, key: KeyEvent) -> bool {
+        if self.browser.confirm_delete {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    self.browser.confirm_delete = false;
+                    self.delete_selected();
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                    self.browser.confirm_delete = false;
+                }
+                _ => {}
+            }
+            return false;
+        }
+        if self.browser.filtering {
+            match key.code {
+                KeyCode::Enter | KeyCode::Esc => self.browser.filtering = false,
+                KeyCode::Backspace => {
+                    self.browser.filter.pop();
+                    self.browser.selected = 0;
+                }
+                KeyCode::Up => self.browser.move_selection(-1),
+                KeyCode::Down => self.browser.move_selection(1),
+                KeyCode::Char(ch) => {
+                    self.browser.filter.push(ch);
+                    self.browser.selected = 0;
+                }
+                _ => {}
+            }
+            return false;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => {
+                if !self.is_active() {
+                    return true;
+                }
+                self.set_confirm_cancel(true);
+            }
+            KeyCode::Esc => {
+                // Esc closes the info panel first, then leaves the browser.
+                let had_details = self.browser.details.take().is_some();
+                if !had_details {
+                    self.set_view(View::Operational);
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.browser.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.browser.move_selection(1),
+            KeyCode::PageUp => self.browser.move_selection(-(BROWSER_PAGE as isize)),
+            KeyCode::PageDown => self.browser.move_selection(BROWSER_PAGE as isize),
+            KeyCode::Home => self.browser.selected = 0,
+            KeyCode::End => {
+                self.browser.selected = self.browser.visible().len().saturating_sub(1);
+            }
+            KeyCode::Enter => {
+                self.browser.details = match self.browser.details.take() {
+                    Some(_) => None,
+                    None => self.browser.selected_details(),
+                };
+            }
+            KeyCode::Char('d') => match self.browser.selected_row() {
+                Some(row) if row.location == "project" => self.browser.confirm_delete = true,
+                Some(_) => self.note_warning("only project datasets can be deleted"),
+                None => {}
+            },
+            KeyCode::Char('y') => match self.browser.selected_row().map(BrowserRow::path) {
+                Some(Some(path)) => {
+                    self.set_input_text(path.as_str());
+                    self.set_view(View::Operational);
+                    self.set_input_mode(InputMode::Command);
+                }
+                Some(None) => self.note_warning("the dataset has no data on disk"),
+                None => {}
+            },
+            KeyCode::Char('/') => self.browser.filtering = true,
+            KeyCode::Backspace => {
+                self.browser.filter.pop();
+                self.browser.selected = 0;
+            }
+            KeyCode::Char('r') => self.browser.refresh(),
+            _ => {}
+        }
+        false
+    }
+
+    fn delete_selected(&mut self) {
+        let Some(row) = self.browser.selected_row().cloned() else {
+            return;
+        };
+        let result =
+            Store::new().and_then(|store| store.remove_project_dataset(&row.metadata, false));
+        match result {
+            Ok(removed) => self.note(&format!(
+                "deleted {}:{} ({} metadata entr{})",
+                row.metadata.dataset_type,
+                row.metadata.id,
+                removed.len(),
+                if removed.len() == 1 { "y" } else { "ies" }
+            )),
+            Err(err) => self.note_error(&format!("error: {err}")),
+        }
+        self.browser.details = None;
+        self.browser.refresh();
+        if let Ok(mut state) = self.state.lock() {
+            state.store_summary = compute_store_summary().unwrap_or(state.store_summary.clone());
+        }
+    }
+
     fn take_command(&mut self) -> Option<String> {
         let current = self.input.trim().to_string();
         if current.is_empty() {
@@ -600,6 +1140,13 @@ impl Tui {
         }
     }
 
+    fn view(&self) -> View {
+        self.state
+            .lock()
+            .map(|state| state.view)
+            .unwrap_or(View::Operational)
+    }
+
     fn set_view(&self, view: View) {
         if let Ok(mut state) = self.state.lock() {
             state.view = view;
@@ -610,10 +1157,94 @@ impl Tui {
         self.state.lock().map(|state| state.active).unwrap_or(false)
     }
 
+    fn confirming_cancel(&self) -> bool {
+        self.state
+            .lock()
+            .map(|state| state.confirm_cancel)
+            .unwrap_or(false)
+    }
+
+    fn set_confirm_cancel(&self, confirm: bool) {
+        if let Ok(mut state) = self.state.lock()
+            && !state.cancelling
+        {
+            state.confirm_cancel = confirm;
+        }
+    }
+
+    /// Signals the worker to stop; it keeps running until its next check.
+    fn cancel_active(&self) {
+        self.cancel.cancel();
+        if let Ok(mut state) = self.state.lock() {
+            state.confirm_cancel = false;
+            if !state.cancelling {
+                state.cancelling = true;
+                state.status = "cancelling… waiting for the current step to stop".to_string();
+                state.status_level = StatusLevel::Warning;
+            }
+        }
+    }
+
+    /// Multi-item batches and failures keep the checklist on screen until the
+    /// user dismisses it.
+    fn needs_dismiss(&self, ok: bool) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        let done = state
+            .items
+            .iter()
+            .filter(|item| item.status == ItemStatus::Done)
+            .count();
+        let failed = state
+            .items
+            .iter()
+            .filter(|item| matches!(item.status, ItemStatus::Failed(_)))
+            .count();
+        if state.items.len() <= 1 && failed == 0 {
+            return false;
+        }
+        state.status = format!(
+            "{} {done} done, {failed} failed; press Enter to close",
+            if ok { "Finished:" } else { "Stopped:" }
+        );
+        state.status_level = if failed > 0 || !ok {
+            StatusLevel::Error
+        } else {
+            StatusLevel::Info
+        };
+        true
+    }
+
+    fn await_dismiss(&mut self, terminal: &mut TuiTerminal, mut tick: usize) -> miette::Result<()> {
+        loop {
+            self.refresh_metrics();
+            if let Ok(state) = self.state.lock() {
+                terminal
+                    .draw(|frame| draw_ui(frame, self, &state, tick))
+                    .into_diagnostic()?;
+            }
+            if event::poll(Duration::from_millis(120)).into_diagnostic()?
+                && let Event::Key(key) = event::read().into_diagnostic()?
+                && (key.code == KeyCode::Enter || self.handle_key(key))
+            {
+                return Ok(());
+            }
+            tick = tick.wrapping_add(1);
+        }
+    }
+
     fn set_active(&self, active: bool) {
         if let Ok(mut state) = self.state.lock() {
+            if active {
+                state.items.clear();
+            }
+            state.confirm_cancel = false;
+            state.cancelling = false;
             state.active = active;
             state.finished = !active;
+            state.paused = false;
+            state.download = None;
             state.started = Instant::now();
             state.request_count = 0;
             state.retries = 0;
@@ -711,6 +1342,20 @@ impl Tui {
 
     fn autocomplete(&mut self) -> String {
         let current = self.input.trim();
+        // A bare id only one dataset type fits gets its type prefix.
+        let (command, last) = match current.rsplit_once(' ') {
+            Some((command, last)) => (Some(command), last),
+            None => (None, current),
+        };
+        let is_command = matches!(last, "fetch" | "add" | "list" | "find" | "info" | "init");
+        if let [specifier] = DatasetSpecifier::candidates(last).as_slice()
+            && !(command.is_none() && is_command)
+        {
+            return match command {
+                Some(command) => format!("{command} {specifier}"),
+                None => specifier.to_string(),
+            };
+        }
         if current.starts_with("pro") && !current.contains(':') {
             return "protein:".to_string();
         }
@@ -720,18 +1365,30 @@ impl Tui {
         if current.starts_with("srr") && !current.contains(':') {
             return "srr:".to_string();
         }
+        if current.starts_with("srx") && !current.contains(':') {
+            return "srx:".to_string();
+        }
         if current.starts_with("uni") && !current.contains(':') {
             return "uniprot:".to_string();
         }
         if current.starts_with("expr10") && !current.contains(':') {
             return "expression10x:".to_string();
         }
+        if current.starts_with("exprs") && !current.contains(':') {
+            return "expressionsample:".to_string();
+        }
         if current.starts_with("expr") && !current.contains(':') {
             return "expression:".to_string();
         }
         if current.starts_with("doi") && !current.contains(':') {
             return "doi:".to_string();
         }
+        if current.starts_with("bio") && !current.contains(':') {
+            return "bioproject:".to_string();
+        }
+        if current.starts_with("struct") && !current.contains(':') {
+            return "structure-for:".to_string();
+        }
         if current.starts_with("go") && !current.contains(':') {
             return "go".to_string();
         }
@@ -771,18 +1428,30 @@ impl Tui {
         if current.starts_with("fetch srr") {
             return "fetch srr:".to_string();
         }
+        if current.starts_with("fetch srx") {
+            return "fetch srx:".to_string();
+        }
         if current.starts_with("fetch uni") {
             return "fetch uniprot:".to_string();
         }
         if current.starts_with("fetch expr10") {
             return "fetch expression10x:".to_string();
         }
+        if current.starts_with("fetch exprs") {
+            return "fetch expressionsample:".to_string();
+        }
         if current.starts_with("fetch expr") {
             return "fetch expression:".to_string();
         }
         if current.starts_with("fetch doi") {
             return "fetch doi:".to_string();
         }
+        if current.starts_with("fetch bio") {
+            return "fetch bioproject:".to_string();
+        }
+        if current.starts_with("fetch struct") {
+            return "fetch structure-for:".to_string();
+        }
         if current.starts_with("fetch go") {
             return "fetch go".to_string();
         }
@@ -824,30 +1493,213 @@ impl Tui {
     }
 }
 
-fn draw_ui(
-    frame: &mut ratatui::Frame,
-    tui: &Tui,
-    state: &AppState,
-    tick: usize,
-    elapsed: Duration,
-) {
-    match state.view {
-        View::Operational | View::Config | View::Browser => {
-            draw_operational(frame, tui, state, tick, elapsed)
+impl ConfigEditor {
+    fn load(&mut self) {
+        self.path = Project::current()
+            .map(|project| project.config_path())
+            .unwrap_or_else(|_| CONFIG_FILE.into());
+        self.adding = None;
+        self.error = None;
+        self.notice = None;
+        if !self.path.exists() {
+            self.config = Config::default();
+            self.notice = Some("kira-bm.json not found; adding an entry creates it".to_string());
+        } else {
+            match ConfigLoader::load(&self.path) {
+                Ok(config) => {
+                    let issues = config.issues();
+                    if !issues.is_empty() {
+                        self.error = Some(issues.join("; "));
+                    } else if let Err(err) = ConfigLoader::resolve_config(config.clone()) {
+                        self.error = Some(err.to_string());
+                    }
+                    self.config = config;
+                }
+                Err(err) => {
+                    self.config = Config::default();
+                    self.error = Some(err.to_string());
+                }
+            }
         }
+        self.selected = self.selected.min(self.rows().len().saturating_sub(1));
+    }
+
+    /// Entries in display order: (section, index within section, id).
+    fn rows(&self) -> Vec<(ConfigSection, usize, String)> {
+        ConfigSection::ALL
+            .into_iter()
+            .flat_map(|section| {
+                self.config
+                    .entry_ids(section)
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(idx, id)| (section, idx, id))
+            })
+            .collect()
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let max = self.rows().len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(max);
+    }
+
+    fn toggle_selected(&mut self) {
+        let Some((section, _, id)) = self.rows().into_iter().nth(self.selected) else {
+            return;
+        };
+        let excluded = self.config.toggle_excluded(section, &id);
+        let verb = if excluded {
+            "excluded from"
+        } else {
+            "included in"
+        };
+        self.save(format!("{}:{id} {verb} the next fetch", section.kind()));
+    }
+
+    fn remove_selected(&mut self) {
+        let Some((section, idx, _)) = self.rows().into_iter().nth(self.selected) else {
+            return;
+        };
+        if let Some(spec) = self.config.remove_entry(section, idx) {
+            self.selected = self.selected.min(self.rows().len().saturating_sub(1));
+            self.save(format!("removed {spec}"));
+        }
+    }
+
+    fn submit_add(&mut self) {
+        let input = self.adding.clone().unwrap_or_default();
+        let added = input
+            .trim()
+            .parse::<DatasetSpecifier>()
+            .and_then(|spec| self.config.add_entry(&spec));
+        match added {
+            Ok(section) => {
+                self.adding = None;
+                let last = self.config.entry_ids(section).len() - 1;
+                self.selected = self
+                    .rows()
+                    .iter()
+                    .position(|(s, idx, _)| *s == section && *idx == last)
+                    .unwrap_or(0);
+                self.save(format!("added {}", input.trim()));
+            }
+            // Stay in add mode so the entry can be corrected.
+            Err(err) => self.error = Some(err.to_string()),
+        }
+    }
+
+    fn save(&mut self, notice: String) {
+        match write_config_atomic(&self.path, &self.config) {
+            Ok(()) => {
+                self.error = None;
+                self.notice = Some(notice);
+            }
+            Err(err) => self.error = Some(err.to_string()),
+        }
+    }
+}
+
+impl Browser {
+    fn refresh(&mut self) {
+        let Ok(store) = Store::new() else {
+            self.rows.clear();
+            return;
+        };
+        let mut rows = Vec::new();
+        for (location, root) in [
+            ("project", store.project_root()),
+            ("cache", store.cache_root()),
+        ] {
+            for metadata in Store::list_metadata(root).unwrap_or_default() {
+                let key = (
+                    metadata.resolved_path.clone(),
+                    metadata.downloaded_at.clone(),
+                );
+                let size = *self
+                    .sizes
+                    .entry(key)
+                    .or_insert_with(|| path_size(std::path::Path::new(&metadata.resolved_path)));
+                rows.push(BrowserRow {
+                    location,
+                    metadata,
+                    size,
+                });
+            }
+        }
+        rows.sort_by(|a, b| {
+            a.metadata
+                .dataset_type
+                .cmp(&b.metadata.dataset_type)
+                .then_with(|| a.metadata.id.cmp(&b.metadata.id))
+                .then_with(|| a.location.cmp(b.location))
+        });
+        self.sizes.retain(|(path, downloaded_at), _| {
+            rows.iter().any(|row| {
+                &row.metadata.resolved_path == path && &row.metadata.downloaded_at == downloaded_at
+            })
+        });
+        self.rows = rows;
+        self.selected = self.selected.min(self.visible().len().saturating_sub(1));
+    }
+
+    fn visible(&self) -> Vec<&BrowserRow> {
+        let needle = self.filter.trim().to_lowercase();
+        self.rows
+            .iter()
+            .filter(|row| {
+                needle.is_empty()
+                    || format!(
+                        "{}:{} {} {} {}",
+                        row.metadata.dataset_type,
+                        row.metadata.id,
+                        row.metadata.format.as_deref().unwrap_or_default(),
+                        row.location,
+                        row.metadata.resolved_path
+                    )
+                    .to_lowercase()
+                    .contains(&needle)
+            })
+            .collect()
+    }
+
+    fn selected_row(&self) -> Option<&BrowserRow> {
+        self.visible().get(self.selected).copied()
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let max = self.visible().len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(max);
+    }
+
+    fn selected_details(&self) -> Option<Value> {
+        let row = self.selected_row()?;
+        let find = |location: &str| {
+            self.rows
+                .iter()
+                .find(|other| {
+                    other.location == location
+                        && other.metadata.dataset_type == row.metadata.dataset_type
+                        && other.metadata.id == row.metadata.id
+                })
+                .map(|other| &other.metadata)
+        };
+        dataset_details(&row.metadata.dataset_type, find("project"), find("cache"))
+            .or_else(|| serde_json::to_value(&row.metadata).ok())
+    }
+}
+
+fn draw_ui(frame: &mut ratatui::Frame, tui: &Tui, state: &AppState, tick: usize) {
+    match state.view {
+        View::Operational => draw_operational(frame, tui, state, tick),
+        View::Config => draw_config(frame, tui, state, tick),
+        View::Browser => draw_browser(frame, tui, state, tick),
         View::DataFocus => draw_data_focus(frame, tui, state),
         View::Logs => draw_logs(frame, tui, state),
         View::Help => draw_help(frame),
     }
 }
 
-fn draw_operational(
-    frame: &mut ratatui::Frame,
-    tui: &Tui,
-    state: &AppState,
-    tick: usize,
-    elapsed: Duration,
-) {
+fn draw_operational(frame: &mut ratatui::Frame, tui: &Tui, state: &AppState, tick: usize) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -865,15 +1717,326 @@ fn draw_operational(
         .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
         .split(chunks[1]);
 
-    let status = draw_status_panel(state, elapsed);
+    let status = draw_status_panel(state);
     frame.render_widget(status, main[0]);
 
-    let details = draw_details_panel(state);
-    frame.render_widget(details, main[1]);
+    if state.items.is_empty() {
+        let details = draw_details_panel(state);
+        frame.render_widget(details, main[1]);
+    } else {
+        let side = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(main[1]);
+        frame.render_widget(draw_details_panel(state), side[0]);
+        let visible = side[1].height.saturating_sub(3) as usize;
+        frame.render_widget(draw_items_panel(state, visible), side[1]);
+    }
 
     draw_command_line(frame, tui, state, tick, chunks[2]);
 }
 
+fn draw_items_panel(state: &AppState, visible: usize) -> Paragraph<'static> {
+    let done = state
+        .items
+        .iter()
+        .filter(|item| item.status == ItemStatus::Done)
+        .count();
+    let mut lines = vec![Line::from(Span::styled(
+        format!("ITEMS {done}/{}", state.items.len()),
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    ))];
+    // Keep the newest items in view once the list outgrows the panel.
+    let skip = state.items.len().saturating_sub(visible.max(1));
+    for item in state.items.iter().skip(skip) {
+        let line = match &item.status {
+            ItemStatus::Done => Line::from(vec![
+                Span::styled("✓ ", Style::default().fg(Color::Green)),
+                Span::raw(item.label.clone()),
+            ]),
+            ItemStatus::Active => Line::from(vec![
+                Span::styled("➜ ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    item.label.clone(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+            ]),
+            ItemStatus::Failed(error) => Line::from(vec![
+                Span::styled("✗ ", Style::default().fg(Color::Red)),
+                Span::raw(item.label.clone()),
+                Span::styled(
+                    format!(" {}", truncate_text(error, 48)),
+                    Style::default().fg(Color::Red),
+                ),
+            ]),
+        };
+        lines.push(line);
+    }
+    Paragraph::new(lines).block(Block::default().borders(Borders::TOP))
+}
+
+fn draw_browser(frame: &mut ratatui::Frame, tui: &Tui, state: &AppState, tick: usize) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(8),
+            Constraint::Length(4),
+        ])
+        .split(frame.area());
+
+    let header = draw_header(state, tui.kind, tick);
+    frame.render_widget(header, chunks[0]);
+
+    let browser = &tui.browser;
+    let main = if browser.details.is_some() {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(chunks[1])
+    } else {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(100)])
+            .split(chunks[1])
+    };
+
+    let visible = browser.visible();
+    // Header row and block borders take three lines.
+    let height = (main[0].height as usize).saturating_sub(3).max(1);
+    let offset = browser.selected.saturating_sub(height - 1);
+    let rows = visible
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(idx, row)| {
+            let style = if idx == browser.selected {
+                Style::default().fg(Color::Black).bg(Color::Cyan)
+            } else if row.metadata.is_expired() {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+            Row::new(vec![
+                Cell::from(row.metadata.dataset_type.clone()),
+                Cell::from(if row.metadata.pinned {
+                    format!("{} [pinned]", row.metadata.id)
+                } else {
+                    row.metadata.id.clone()
+                }),
+                Cell::from(if row.metadata.formats.is_empty() {
+                    row.metadata
+                        .format
+                        .clone()
+                        .unwrap_or_else(|| "-".to_string())
+                } else {
+                    row.metadata
+                        .formats
+                        .keys()
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(",")
+                }),
+                Cell::from(if row.metadata.is_expired() {
+                    "expired".to_string()
+                } else {
+                    row.size
+                        .map(bytes_to_human)
+                        .unwrap_or_else(|| "?".to_string())
+                }),
+                Cell::from(row.location),
+            ])
+            .style(style)
+        })
+        .collect::<Vec<_>>();
+    let title = if browser.filter.is_empty() {
+        format!("LOCAL DATASETS ({})", visible.len())
+    } else {
+        format!(
+            "LOCAL DATASETS ({}/{}) filter: {}",
+            visible.len(),
+            browser.rows.len(),
+            browser.filter
+        )
+    };
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(16),
+            Constraint::Min(16),
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Length(8),
+        ],
+    )
+    .header(
+        Row::new(vec!["TYPE", "ID", "FORMAT", "SIZE", "LOCATION"]).style(
+            Style::default()
+                .fg(Color::Gray)
+                .add_modifier(Modifier::BOLD),
+        ),
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(Span::styled(title, Style::default().fg(Color::Cyan))),
+    );
+    frame.render_widget(table, main[0]);
+
+    if let Some(details) = &browser.details {
+        let text = serde_json::to_string_pretty(details).unwrap_or_default();
+        let lines = text.lines().map(|line| Line::from(line.to_string()));
+        let panel = Paragraph::new(lines.collect::<Vec<_>>())
+            .block(Block::default().borders(Borders::ALL).title("INFO"))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(panel, main[1]);
+    }
+
+    draw_command_line(frame, tui, state, tick, chunks[2]);
+}
+
+fn draw_config(frame: &mut ratatui::Frame, tui: &Tui, state: &AppState, tick: usize) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(8),
+            Constraint::Length(4),
+        ])
+        .split(frame.area());
+
+    let header = draw_header(state, tui.kind, tick);
+    frame.render_widget(header, chunks[0]);
+
+    let editor = &tui.config_editor;
+    let rows = editor.rows();
+    let mut lines = Vec::new();
+    let mut selected_line = 0;
+    for section in ConfigSection::ALL {
+        let entries = rows
+            .iter()
+            .enumerate()
+            .filter(|(_, (s, _, _))| *s == section)
+            .collect::<Vec<_>>();
+        lines.push(Line::from(Span::styled(
+            format!("{} ({})", section.key(), entries.len()),
+            Style::default()
+                .fg(Color::Gray)
+                .add_modifier(Modifier::BOLD),
+        )));
+        for (pos, (_, _, id)) in entries {
+            let excluded = editor.config.is_excluded(section, id);
+            let mark = if excluded { "[ ]" } else { "[x]" };
+            let mut style = if excluded {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+            if pos == editor.selected {
+                selected_line = lines.len();
+                style = Style::default().fg(Color::Black).bg(Color::Cyan);
+            }
+            lines.push(Line::from(Span::styled(format!("  {mark} {id}"), style)));
+        }
+    }
+    if let Some(error) = &editor.error {
+        lines.push(Line::from(Span::styled(
+            format!("error: {error}"),
+            Style::default().fg(Color::Red),
+        )));
+    } else if let Some(notice) = &editor.notice {
+        lines.push(Line::from(Span::styled(
+            notice.clone(),
+            Style::default().fg(Color::Green),
+        )));
+    }
+    // Block borders take two lines.
+    let height = (chunks[1].height as usize).saturating_sub(2).max(1);
+    let offset = selected_line.saturating_sub(height - 1);
+    let title = format!("CONFIG {}", editor.path.display());
+    let list = Paragraph::new(lines).scroll((offset as u16, 0)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(Span::styled(title, Style::default().fg(Color::Cyan))),
+    );
+    frame.render_widget(list, chunks[1]);
+
+    draw_command_line(frame, tui, state, tick, chunks[2]);
+}
+
+fn draw_target_checklist(
+    frame: &mut ratatui::Frame,
+    doi: &str,
+    targets: &[DoiTarget],
+    checked: &[bool],
+    cursor: usize,
+) {
+    let mut lines = Vec::new();
+    let mut cursor_line = 0;
+    let mut group: Option<&str> = None;
+    for (index, target) in targets.iter().enumerate() {
+        if group != Some(target.dataset_type.as_str()) {
+            group = Some(target.dataset_type.as_str());
+            let members = targets
+                .iter()
+                .filter(|other| other.dataset_type == target.dataset_type)
+                .collect::<Vec<_>>();
+            let ticked = targets
+                .iter()
+                .zip(checked)
+                .filter(|(other, tick)| other.dataset_type == target.dataset_type && **tick)
+                .count();
+            lines.push(Line::from(Span::styled(
+                format!("{} ({ticked}/{})", target.dataset_type, members.len()),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )));
+        }
+        let mark = if checked.get(index).copied().unwrap_or(false) {
+            "[x]"
+        } else {
+            "[ ]"
+        };
+        let size = target
+            .bytes
+            .map(bytes_to_human)
+            .unwrap_or_else(|| "size unknown".to_string());
+        let style = if index == cursor {
+            cursor_line = lines.len();
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(Span::styled(
+            format!("  {mark} {}  {size}", target.specifier),
+            style,
+        )));
+    }
+    let area = frame.area();
+    let visible = usize::from(area.height.saturating_sub(3)).max(1);
+    let scroll = cursor_line.saturating_sub(visible - 1);
+    let selected = checked.iter().filter(|tick| **tick).count();
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        "doi:{doi}: {selected} of {} selected | space toggle, a toggle type, enter fetch, esc cancel",
+        targets.len()
+    ));
+    let list = Paragraph::new(lines)
+        .block(block)
+        .scroll((u16::try_from(scroll).unwrap_or(u16::MAX), 0));
+    frame.render_widget(list, area);
+}
+
+fn config_hint(editor: &ConfigEditor) -> String {
+    if let Some(input) = &editor.adding {
+        return format!("Add specifier: {input}_  (Enter to add, Esc to cancel)");
+    }
+    "Config: space include/exclude  a add  d remove  r reload  Esc back".to_string()
+}
+
 fn draw_data_focus(frame: &mut ratatui::Frame, tui: &Tui, state: &AppState) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -896,12 +2059,12 @@ fn draw_data_focus(frame: &mut ratatui::Frame, tui: &Tui, state: &AppState) {
 
     let body = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(4), Constraint::Min(4)])
+        .constraints([Constraint::Length(5), Constraint::Min(4)])
         .split(chunks[1]);
 
     let dataset = state.dataset.clone();
     let info_lines = if let Some(info) = dataset {
-        vec![
+        let mut lines = vec![
             Line::from(vec![
                 Span::styled("Dataset: ", Style::default().fg(Color::Gray)),
                 Span::styled(
@@ -921,11 +2084,33 @@ fn draw_data_focus(frame: &mut ratatui::Frame, tui: &Tui, state: &AppState) {
             ]),
             Line::from(vec![
                 Span::styled("Cache impact: ", Style::default().fg(Color::Gray)),
-                Span::raw("n/a"),
+                Span::raw(
+                    info.savings
+                        .map(|savings| format!("{} avoided", bytes_to_human(savings.bytes_avoided)))
+                        .unwrap_or_else(|| "n/a".to_string()),
+                ),
                 Span::styled("   Time saved: ", Style::default().fg(Color::Gray)),
-                Span::raw("n/a"),
+                Span::raw(
+                    info.savings
+                        .and_then(|savings| savings.time_saved_ms)
+                        .map(millis_to_human)
+                        .unwrap_or_else(|| "n/a".to_string()),
+                ),
             ]),
-        ]
+        ];
+        if let Some(run_info) = info.run_info {
+            lines.push(Line::from(vec![
+                Span::styled("Run: ", Style::default().fg(Color::Gray)),
+                Span::raw(run_info),
+            ]));
+        }
+        if let Some(acquired) = info.acquired {
+            lines.push(Line::from(vec![
+                Span::styled("Acquired: ", Style::default().fg(Color::Gray)),
+                Span::raw(acquired),
+            ]));
+        }
+        lines
     } else {
         vec![
             Line::from(vec![
@@ -1009,11 +2194,11 @@ fn draw_logs(frame: &mut ratatui::Frame, tui: &Tui, state: &AppState) {
 fn draw_help(frame: &mut ratatui::Frame) {
     let block = Block::default().borders(Borders::ALL).title("Help");
     let lines = vec![
-        Line::from("F1 Help  F2 Browser  F3 Search  F4 Logs  F5 Config"),
+        Line::from("F1 Help  F2 Browser  F3 Search  F4 Logs  F5 Config  F6 Pause/Resume batch"),
         Line::from(": command mode   / search mode   ? help mode"),
         Line::from("Commands: fetch|add|list|info|clear|init; tools install-sra"),
         Line::from(
-            "Specifiers: protein|genome|srr|uniprot|doi|expression|expression10x|go|kegg|reactome",
+            "Specifiers: protein|genome|srr|srx|uniprot|doi|expression|expression10x|expressionsample|bioproject|structure-for|go|kegg|reactome|alias",
         ),
         Line::from("Examples: protein:1LYZ  genome:GCF_000005845.2"),
     ];
@@ -1022,7 +2207,7 @@ fn draw_help(frame: &mut ratatui::Frame) {
 }
 
 fn draw_header(state: &AppState, kind: ProgressSinkKind, tick: usize) -> Paragraph<'static> {
-    let hb = if tick % 2 == 0 { "*" } else { " " };
+    let hb = if tick.is_multiple_of(2) { "*" } else { " " };
     let cache_label = if state.store_summary.cache_ok {
         "cache OK"
     } else {
@@ -1039,7 +2224,7 @@ fn draw_header(state: &AppState, kind: ProgressSinkKind, tick: usize) -> Paragra
         ProgressSinkKind::Info => "Info",
         ProgressSinkKind::Clear => "Clear",
     };
-    let header_line = Line::from(vec![
+    let mut header_line = Line::from(vec![
         Span::styled(
             "KIRA-BM",
             Style::default()
@@ -1053,6 +2238,23 @@ fn draw_header(state: &AppState, kind: ProgressSinkKind, tick: usize) -> Paragra
         Span::raw("   "),
         Span::styled(hb, Style::default().fg(Color::Green)),
     ]);
+    if state.cancelling {
+        header_line.spans.push(Span::styled(
+            "   CANCELLING…",
+            Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+        ));
+    } else if state.paused {
+        header_line.spans.push(Span::styled(
+            "   PAUSED (F6 resume)",
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
     let store_line = Line::from(vec![
         Span::styled(
             format!(
@@ -1077,8 +2279,8 @@ fn draw_header(state: &AppState, kind: ProgressSinkKind, tick: usize) -> Paragra
         .block(Block::default().borders(Borders::BOTTOM))
 }
 
-fn draw_status_panel(state: &AppState, elapsed: Duration) -> Paragraph<'static> {
-    let progress = phase_progress(state.phase, elapsed);
+fn draw_status_panel(state: &AppState) -> Paragraph<'static> {
+    let progress = phase_progress(state.phase);
     let bar = progress_bar(progress);
     let phase_color = if state.active {
         Color::Cyan
@@ -1130,6 +2332,11 @@ fn draw_status_panel(state: &AppState, elapsed: Duration) -> Paragraph<'static>
             Span::raw(bar),
             Span::raw(format!(" {:>3}%", progress)),
         ]),
+    ];
+    if let Some(download) = &state.download {
+        lines.push(download_line(download));
+    }
+    lines.extend([
         Line::from(vec![
             Span::styled("Confidence: ", Style::default().fg(Color::Gray)),
             Span::styled(state.confidence, Style::default().fg(Color::Yellow)),
@@ -1148,7 +2355,7 @@ fn draw_status_panel(state: &AppState, elapsed: Duration) -> Paragraph<'static>
             "Recent events:",
             Style::default().fg(Color::Gray),
         )),
-    ];
+    ]);
 
     for event in state.events.iter().rev().take(3) {
         lines.push(Line::from(format!("- {}", event)));
@@ -1201,28 +2408,45 @@ fn draw_details_panel(state: &AppState) -> Paragraph<'static> {
                     Span::raw(org),
                 ]));
             }
+            if !info.structures.is_empty() {
+                lines.push(Line::from(Span::styled(
+                    format!("Structures ({}):", info.structures.len()),
+                    Style::default().fg(Color::Gray),
+                )));
+                for row in info.structures.iter().take(STRUCTURES_SHOWN) {
+                    lines.push(Line::from(format!("  {row}")));
+                }
+            }
+        }
+        if !info.sequences.is_empty() {
+            lines.push(Line::from(Span::styled(
+                format!("Sequences ({}):", info.sequences.len()),
+                Style::default().fg(Color::Gray),
+            )));
+            for row in info.sequences.iter().take(STRUCTURES_SHOWN) {
+                lines.push(Line::from(format!("  {row}")));
+            }
+        }
+        if !info.bundles.is_empty() {
+            lines.push(Line::from(Span::styled(
+                format!("Bundles ({}):", info.bundles.len()),
+                Style::default().fg(Color::Gray),
+            )));
+            for row in info.bundles.iter().take(STRUCTURES_SHOWN) {
+                lines.push(Line::from(format!("  {row}")));
+            }
         }
         lines.push(Line::from(vec![
             Span::styled("Integrity: ", Style::default().fg(Color::Gray)),
             Span::styled("pending", Style::default().fg(Color::Yellow)),
         ]));
     } else {
-        lines.push(Line::from(vec![
-            Span::styled("Source: ", Style::default().fg(Color::Gray)),
-            Span::raw("NCBI"),
-        ]));
-        lines.push(Line::from(vec![
-            Span::styled("Dataset: ", Style::default().fg(Color::Gray)),
-            Span::raw("n/a"),
-        ]));
-        lines.push(Line::from(vec![
-            Span::styled("Accession: ", Style::default().fg(Color::Gray)),
-            Span::raw("n/a"),
-        ]));
-        lines.push(Line::from(vec![
-            Span::styled("Integrity: ", Style::default().fg(Color::Gray)),
-            Span::raw("n/a"),
-        ]));
+        for (label, value) in analytics_lines(&state.store_summary.analytics) {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{label}: "), Style::default().fg(Color::Gray)),
+                Span::raw(value),
+            ]));
+        }
     }
 
     Paragraph::new(lines).wrap(Wrap { trim: true })
@@ -1261,10 +2485,17 @@ fn draw_command_line(
             Span::styled(preview, Style::default().fg(Color::DarkGray)),
         ]),
     ];
-    if state.view == View::Browser {
-        lines.push(Line::from("Browser: local datasets"));
+    if state.confirm_cancel {
+        lines.push(Line::from(Span::styled(
+            "Cancel the running operation? y = cancel, n = keep going",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )));
+    } else if state.view == View::Browser {
+        lines.push(Line::from(browser_hint(&tui.browser)));
     } else if state.view == View::Config {
-        lines.push(Line::from("Config: kira-bm.json"));
+        lines.push(Line::from(config_hint(&tui.config_editor)));
     } else if state.view == View::Logs {
         lines.push(Line::from("Logs: PgUp/PgDown to scroll"));
     }
@@ -1277,7 +2508,7 @@ fn draw_command_line(
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap_or(Duration::from_millis(0))
         .as_millis();
-    let blink_on = (now_ms / 700) % 2 == 0;
+    let blink_on = (now_ms / 700).is_multiple_of(2);
     if blink_on {
         let mut cursor_x = area
             .x
@@ -1290,6 +2521,25 @@ fn draw_command_line(
     }
 }
 
+fn browser_hint(browser: &Browser) -> String {
+    if browser.confirm_delete
+        && let Some(row) = browser.selected_row()
+    {
+        return format!(
+            "Delete {}:{} from the project store? y to confirm, n to cancel",
+            row.metadata.dataset_type, row.metadata.id
+        );
+    }
+    if browser.filtering {
+        return format!("Filter: {}_  (Enter to apply)", browser.filter);
+    }
+    let path = browser
+        .selected_row()
+        .map(|row| row.metadata.resolved_path.clone())
+        .unwrap_or_default();
+    format!("Browser: Enter info  d delete  y copy path  / filter  r refresh  Esc back  {path}")
+}
+
 fn draw_logs_view(state: &AppState, scroll: u16) -> Paragraph<'static> {
     let total = state.logs.len();
     let visible = 9usize;
@@ -1326,10 +2576,18 @@ fn command_preview(tui: &Tui, state: &AppState) -> String {
     if raw.starts_with("genome:") {
         return format!("fetch {}", raw);
     }
-    if raw.starts_with("doi:") {
+    if raw.starts_with("doi:")
+        || raw.starts_with("bioproject:")
+        || raw.starts_with("srx:")
+        || raw.starts_with("structure-for:")
+        || raw.starts_with("alias:")
+    {
         return format!("fetch {}", raw);
     }
-    if raw.starts_with("expression:") || raw.starts_with("expression10x:") {
+    if raw.starts_with("expression:")
+        || raw.starts_with("expression10x:")
+        || raw.starts_with("expressionsample:")
+    {
         return format!("fetch {}", raw);
     }
     if matches!(raw, "go" | "kegg" | "reactome") {
@@ -1341,10 +2599,44 @@ fn command_preview(tui: &Tui, state: &AppState) -> String {
     raw.to_string()
 }
 
-fn phase_progress(phase: Phase, elapsed: Duration) -> u8 {
-    let base = ((phase.index() + 1) as f64 / 5.0) * 100.0;
-    let wobble = (elapsed.as_millis() % 500) as f64 / 500.0 * 4.0;
-    (base + wobble).min(100.0) as u8
+/// `file [####......]  42% 11.3 MiB of 27.0 MiB · 4.1 MiB/s · ETA 4.0 s`;
+/// without a known size, only the bytes so far and the rate.
+fn download_line(download: &DownloadProgress) -> Line<'static> {
+    let mut spans = vec![
+        Span::styled("Download: ", Style::default().fg(Color::Gray)),
+        Span::raw(format!("{} ", download.file)),
+    ];
+    let mut detail = match (download.fraction(), download.total) {
+        (Some(fraction), Some(total)) => {
+            let percent = (fraction * 100.0) as u8;
+            spans.push(Span::styled(
+                progress_bar(percent),
+                Style::default().fg(Color::Cyan),
+            ));
+            format!(
+                " {percent:>3}% {} of {}",
+                bytes_to_human(download.downloaded),
+                bytes_to_human(total)
+            )
+        }
+        _ => bytes_to_human(download.downloaded),
+    };
+    if let Some(rate) = download.bytes_per_sec() {
+        detail.push_str(&format!(" · {}/s", bytes_to_human(rate)));
+    }
+    if let Some(eta) = download.eta() {
+        detail.push_str(&format!(
+            " · ETA {}",
+            millis_to_human(eta.as_millis() as u64)
+        ));
+    }
+    spans.push(Span::raw(detail));
+    Line::from(spans)
+}
+
+/// Share of the five phases reached; byte progress is shown separately.
+fn phase_progress(phase: Phase) -> u8 {
+    ((phase.index() + 1) * 100 / 5) as u8
 }
 
 fn progress_bar(percent: u8) -> String {
@@ -1442,13 +2734,95 @@ fn parse_latency(message: &str) -> Option<u128> {
         .and_then(|value| value.parse::<u128>().ok())
 }
 
+fn truncate_text(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut out = text.chars().take(max.saturating_sub(1)).collect::<String>();
+    out.push('…');
+    out
+}
+
+fn parse_item_event(message: &str) -> Option<(&str, ItemStatus)> {
+    if let Some(label) = message.strip_prefix("item.begin ") {
+        return Some((label, ItemStatus::Active));
+    }
+    if let Some(label) = message.strip_prefix("item.end ") {
+        return Some((label, ItemStatus::Done));
+    }
+    let rest = message.strip_prefix("item.failed ")?;
+    let (label, error) = rest.split_once(' ').unwrap_or((rest, ""));
+    Some((label, ItemStatus::Failed(error.to_string())))
+}
+
 fn humanize_event(message: &str) -> String {
+    if let Some((label, status)) = parse_item_event(message) {
+        return match status {
+            ItemStatus::Active => format!("Fetching {label}"),
+            ItemStatus::Done => format!("Done {label}"),
+            ItemStatus::Failed(error) => match error.split_once("] ") {
+                Some((phase, error)) if phase.starts_with('[') => {
+                    format!("Failed {label} {phase}]: {error}")
+                }
+                _ => format!("Failed {label}: {error}"),
+            },
+        };
+    }
     if let Some(rest) = message.strip_prefix("doi.extract ") {
         return format!("DOI: extracted identifiers ({rest})");
     }
+    if let Some(rest) = message.strip_prefix("doi.related.failed ") {
+        return format!("DOI: could not read related record {rest}");
+    }
+    if let Some(doi) = message.strip_prefix("doi.related ") {
+        return format!("DOI: reading related record {doi}");
+    }
+    if let Some(rest) = message.strip_prefix("doi.validate.")
+        && let Some((kind, count)) = rest.split_once(" validated ")
+    {
+        return format!("DOI: validated {count} {kind} identifiers");
+    }
+    if let Some(rest) = message.strip_prefix("genome.missing_artifact ")
+        && let Some((accession, artifact)) = rest.split_once(' ')
+    {
+        return format!("Genome: {accession} has no {artifact} files (kept, see metadata.json)");
+    }
+    if let Some(rest) = message.strip_prefix("srr.read_count_mismatch ")
+        && let [id, counted, expected] = rest.split(' ').collect::<Vec<_>>()[..]
+    {
+        return format!("SRR: {id} FASTQ has {counted} reads, ENA reports {expected} spots");
+    }
+    if let Some(rest) = message.strip_prefix("srr.run_info_unavailable ")
+        && let Some((id, error)) = rest.split_once(' ')
+    {
+        return format!("SRR: {id} run info unavailable ({error})");
+    }
+    if let Some(rest) = message.strip_prefix("srx.runs ")
+        && let Some((experiment, runs)) = rest.split_once(' ')
+    {
+        return format!("SRX: {experiment} runs {}", runs.replace(',', ", "));
+    }
+    if let Some(rest) = message.strip_prefix("cache.fallback ")
+        && let Some((root, reason)) = rest.split_once("; ")
+    {
+        return format!("Cache: user cache unavailable ({reason}), using {root}");
+    }
+    if let Some(root) = message.strip_prefix("project.root ") {
+        return format!("Project: {root}");
+    }
+    if let Some(rest) = message.strip_prefix("lock.wait ")
+        && let Some((dataset, holder)) = rest.split_once("; held by ")
+    {
+        return format!("Waiting for {dataset}: another kira-bm is fetching it ({holder})");
+    }
     match message {
+        "batch.paused" => "Batch paused (F6 or `kira-bm batch resume` to continue)".to_string(),
+        "batch.resumed" => "Batch resumed".to_string(),
+        "batch.pause_timeout" => "Batch paused too long; stopping".to_string(),
         "doi.crossref.start" => "DOI: resolving Crossref metadata".to_string(),
         "doi.crossref.done" => "DOI: Crossref metadata resolved".to_string(),
+        "doi.datacite.start" => "DOI: not in Crossref, resolving DataCite metadata".to_string(),
+        "doi.datacite.done" => "DOI: DataCite metadata resolved".to_string(),
         "doi.validate.pdb" => "DOI: validating PDB accessions".to_string(),
         "doi.validate.uniprot" => "DOI: validating UniProt accessions".to_string(),
         "doi.validate.assembly" => "DOI: validating assembly accessions".to_string(),
@@ -1494,7 +2868,7 @@ fn fuzzy_score(needle: &str, hay: &str) -> Option<usize> {
     let mut iter = hay.chars();
     for ch in needle.chars() {
         let mut found = false;
-        while let Some(h) = iter.next() {
+        for h in iter.by_ref() {
             score += 1;
             if h.eq_ignore_ascii_case(&ch) {
                 found = true;
@@ -1511,7 +2885,9 @@ fn fuzzy_score(needle: &str, hay: &str) -> Option<usize> {
 fn compute_store_summary() -> Option<StoreSummary> {
     let store = Store::new().ok()?;
     let project = Store::list_metadata(store.project_root()).ok()?;
-    let cache = Store::list_metadata(store.cache_root()).ok()?;
+    // Without a cache root (no home directory, or a disabled cache) the
+    // project still has its summary.
+    let cache = Store::list_metadata(store.cache_root()).unwrap_or_default();
     let project_bytes = dir_size(store.project_root().as_std_path()).unwrap_or(0);
     let cache_bytes = dir_size(store.cache_root().as_std_path()).unwrap_or(0);
     Some(StoreSummary {
@@ -1520,40 +2896,93 @@ fn compute_store_summary() -> Option<StoreSummary> {
         cache_count: cache.len(),
         cache_bytes,
         cache_ok: store.cache_root().as_std_path().exists(),
+        analytics: store::analytics(
+            &project,
+            &cache,
+            &history::read(store.project_root(), None).unwrap_or_default(),
+        ),
     })
 }
 
-fn dir_size(path: &std::path::Path) -> Option<u64> {
-    let mut total = 0u64;
-    let mut stack = vec![path.to_path_buf()];
-    while let Some(path) = stack.pop() {
-        let entries = std::fs::read_dir(&path).ok()?;
-        for entry in entries.flatten() {
-            let p = entry.path();
-            if p.is_dir() {
-                stack.push(p);
-            } else if let Ok(meta) = entry.metadata() {
-                total = total.saturating_add(meta.len());
-            }
+/// Label and value of each idle DETAILS line.
+impl Drop for Tui {
+    fn drop(&mut self) {
+        // An error that skips `close` must not leave the shell raw.
+        if self.terminal.take().is_some() {
+            let _ = leave_screen();
         }
     }
-    Some(total)
 }
 
-fn bytes_to_human(bytes: u64) -> String {
-    const KB: f64 = 1024.0;
-    const MB: f64 = KB * 1024.0;
-    const GB: f64 = MB * 1024.0;
-    let value = bytes as f64;
-    if value >= GB {
-        format!("{:.1} GB", value / GB)
-    } else if value >= MB {
-        format!("{:.1} MB", value / MB)
-    } else if value >= KB {
-        format!("{:.1} KB", value / KB)
-    } else {
-        format!("{bytes} B")
-    }
+fn enter_screen() -> miette::Result<TuiTerminal> {
+    let mut stdout = io::stdout();
+    enable_raw_mode().into_diagnostic()?;
+    stdout.execute(EnterAlternateScreen).into_diagnostic()?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout)).into_diagnostic()?;
+    terminal.clear().into_diagnostic()?;
+    Ok(terminal)
+}
+
+fn leave_screen() -> miette::Result<()> {
+    disable_raw_mode().into_diagnostic()?;
+    io::stdout()
+        .execute(LeaveAlternateScreen)
+        .into_diagnostic()?;
+    Ok(())
+}
+
+pub fn analytics_lines(analytics: &StoreAnalytics) -> Vec<(&'static str, String)> {
+    let added = match analytics.added_last_week {
+        0 => "none".to_string(),
+        1 => "1 dataset".to_string(),
+        count => format!("{count} datasets"),
+    };
+    let largest = analytics
+        .largest
+        .as_ref()
+        .map(|largest| format!("{} ({})", largest.specifier, bytes_to_human(largest.bytes)))
+        .unwrap_or_else(|| "n/a".to_string());
+    let size = match analytics.downloaded_last_week {
+        Some(bytes) => format!(
+            "{} (+{} this week)",
+            bytes_to_human(analytics.total_bytes),
+            bytes_to_human(bytes)
+        ),
+        None => bytes_to_human(analytics.total_bytes),
+    };
+    let hits = analytics
+        .cache_hit_ratio
+        .map(|ratio| {
+            format!(
+                "{:.0}% of last {} fetches",
+                ratio * 100.0,
+                analytics.recent_fetches
+            )
+        })
+        .unwrap_or_else(|| "no fetches yet".to_string());
+    let failure = analytics
+        .last_failure
+        .as_ref()
+        .map(|record| {
+            let at = chrono::DateTime::parse_from_rfc3339(&record.timestamp)
+                .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|_| record.timestamp.clone());
+            let phase = record
+                .phase
+                .as_ref()
+                .map(|phase| format!(" [{phase}]"))
+                .unwrap_or_default();
+            let error = record.error.as_deref().unwrap_or("unknown error");
+            format!("{}{phase} {error} ({at})", record.specifier)
+        })
+        .unwrap_or_else(|| "none".to_string());
+    vec![
+        ("Added (7d)", added),
+        ("Largest", largest),
+        ("Size", size),
+        ("Cache hits", hits),
+        ("Last failure", failure),
+    ]
 }
 
 fn load_log_history() -> VecDeque<String> {
@@ -1597,6 +3026,112 @@ fn log_file_path() -> Option<std::path::PathBuf> {
     )
 }
 
+/// One line per ranked structure: id, method, resolution and coverage.
+fn structure_rows(details: Option<&Value>) -> Vec<String> {
+    let Some(rows) = details
+        .and_then(|details| details.get("structures"))
+        .and_then(Value::as_array)
+    else {
+        return Vec::new();
+    };
+    rows.iter()
+        .map(|row| {
+            let id = row.get("id").and_then(Value::as_str).unwrap_or("?");
+            let method = row.get("method").and_then(Value::as_str).unwrap_or("n/a");
+            let resolution = row
+                .get("resolution")
+                .and_then(Value::as_f64)
+                .map(|value| format!("{value:.2} Å"))
+                .unwrap_or_else(|| "-".to_string());
+            let coverage = row.get("coverage").and_then(Value::as_u64).unwrap_or(0);
+            format!("{id:<5} {method:<12} {resolution:>7}  {coverage} aa")
+        })
+        .collect()
+}
+
+fn sequence_rows(details: Option<&Value>) -> Vec<String> {
+    let Some(rows) = details
+        .and_then(|details| details.get("sequences"))
+        .and_then(Value::as_array)
+    else {
+        return Vec::new();
+    };
+    rows.iter()
+        .map(|row| {
+            let chains = row
+                .get("chain_ids")
+                .and_then(Value::as_array)
+                .map(|chains| {
+                    chains
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default();
+            let length = row.get("length").and_then(Value::as_u64).unwrap_or(0);
+            format!("{chains:<8} {length} residues")
+        })
+        .collect()
+}
+
+fn bundle_rows(details: Option<&Value>) -> Vec<String> {
+    let Some(rows) = details
+        .and_then(|details| details.get("dimensions"))
+        .and_then(Value::as_array)
+    else {
+        return Vec::new();
+    };
+    rows.iter()
+        .map(|row| {
+            let sample = row.get("sample").and_then(Value::as_str).unwrap_or("?");
+            let count = |key| row.get(key).and_then(Value::as_u64).unwrap_or(0);
+            format!(
+                "{sample:<12} {} cells × {} genes, {} entries",
+                count("cells"),
+                count("genes"),
+                count("nnz")
+            )
+        })
+        .collect()
+}
+
+/// The bundle rows of a fetched 10x series, read from its metadata.
+fn load_bundle_rows(
+    dataset_type: &str,
+    project_path: Option<&str>,
+    cache_path: Option<&str>,
+) -> Vec<String> {
+    if dataset_type != "expression10x" {
+        return Vec::new();
+    }
+    [project_path, cache_path]
+        .into_iter()
+        .flatten()
+        .find_map(|dir| {
+            let path = std::path::Path::new(dir).join("metadata/metadata.json");
+            serde_json::from_slice::<Value>(&std::fs::read(path).ok()?).ok()
+        })
+        .map(|details| bundle_rows(Some(&details)))
+        .unwrap_or_default()
+}
+
+fn load_run_info(
+    dataset_type: &str,
+    project_path: Option<&str>,
+    cache_path: Option<&str>,
+) -> Option<String> {
+    if dataset_type != "srr" {
+        return None;
+    }
+    [project_path, cache_path]
+        .into_iter()
+        .flatten()
+        .find_map(|dir| stored_run_info(std::path::Path::new(dir)))
+        .map(|info| info.summary())
+        .filter(|summary| !summary.is_empty())
+}
+
 fn load_uniprot_details(
     dataset_type: &str,
     project_path: Option<&str>,
@@ -1614,18 +3149,18 @@ fn load_uniprot_details(
     }
     for base in paths {
         let meta_path = std::path::Path::new(&base).join("metadata.json");
-        if let Ok(content) = std::fs::read_to_string(&meta_path) {
-            if let Ok(value) = serde_json::from_str::<Value>(&content) {
-                let name = value
-                    .get("protein_name")
-                    .and_then(|v| v.as_str())
-                    .map(|v| v.to_string());
-                let organism = value
-                    .get("organism")
-                    .and_then(|v| v.as_str())
-                    .map(|v| v.to_string());
-                return (name, organism);
-            }
+        if let Ok(content) = std::fs::read_to_string(&meta_path)
+            && let Ok(value) = serde_json::from_str::<Value>(&content)
+        {
+            let name = value
+                .get("protein_name")
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string());
+            let organism = value
+                .get("organism")
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string());
+            return (name, organism);
         }
     }
     (None, None)
@@ -1,12 +1,30 @@
+use std::fs::File;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
 use std::time::Duration;
 
-use reqwest::blocking::Client;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, Response};
 use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tracing::field::Empty;
 
-use crate::domain::UniprotId;
+use crate::app::ProgressSink;
+use crate::cancel;
+use crate::domain::{ProteomeId, UniprotId};
 use crate::error::KiraError;
+use crate::fs_util::HashingWriter;
+use crate::http::{
+    Conditional, FailedResponse, HttpSettings, Mirrors, StoredValidators, Validators, record_bytes,
+    record_response, trace_retry,
+};
+use crate::progress::Metered;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
 
 #[derive(Debug, Clone)]
 pub struct UniprotRecord {
@@ -77,37 +95,295 @@ pub struct FeatureItem {
 
 #[derive(Debug, Clone, Serialize, Default)]
 pub struct UniprotCrossRefs {
-    pub pdb: Vec<String>,
+    pub pdb: Vec<PdbXref>,
+    /// Plain PDB ids, as written by earlier releases.
+    pub pdb_ids: Vec<String>,
     pub ncbi: Vec<String>,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PdbXref {
+    pub id: String,
+    pub method: Option<String>,
+    /// Resolution in ångströms; absent for NMR and predicted models.
+    pub resolution: Option<f64>,
+    pub chains: Vec<ChainRange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainRange {
+    pub chain: String,
+    pub start: Option<u64>,
+    pub end: Option<u64>,
+}
+
+impl PdbXref {
+    /// Residues of the canonical sequence covered by any chain.
+    pub fn coverage(&self, sequence_length: Option<u64>) -> u64 {
+        self.coverage_of(1, sequence_length.unwrap_or(u64::MAX))
+    }
+
+    /// Residues of `start..=end` covered by any chain.
+    pub fn coverage_of(&self, start: u64, end: u64) -> u64 {
+        let mut ranges = self
+            .chains
+            .iter()
+            .filter_map(|chain| Some((chain.start?, chain.end?)))
+            .map(|(from, to)| (from.max(start), to.min(end)))
+            .filter(|(from, to)| from <= to)
+            .collect::<Vec<_>>();
+        ranges.sort_unstable();
+        let mut covered = 0;
+        let mut reach = 0;
+        for (from, to) in ranges {
+            let from = from.max(reach + 1);
+            if to >= from {
+                covered += to - from + 1;
+                reach = to;
+            }
+        }
+        covered
+    }
+
+    fn method_rank(&self) -> u8 {
+        let method = self.method.as_deref().unwrap_or("").to_ascii_lowercase();
+        if method.contains("x-ray") {
+            0
+        } else if method == "em" || method.contains("electron") {
+            1
+        } else if method.contains("nmr") {
+            2
+        } else if method.contains("predict") || method.contains("model") {
+            4
+        } else {
+            3
+        }
+    }
+
+    /// Method first, then lowest resolution.
+    fn quality_cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.method_rank().cmp(&other.method_rank()).then_with(|| {
+            match (self.resolution, other.resolution) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            }
+        })
+    }
+}
+
+/// Orders structures best-first: X-ray before EM, NMR and predicted models,
+/// then lowest resolution, then longest coverage of the canonical sequence.
+pub fn rank_pdb_xrefs(xrefs: &[PdbXref], sequence_length: Option<u64>) -> Vec<PdbXref> {
+    let mut ranked = xrefs.to_vec();
+    ranked.sort_by(|a, b| {
+        a.quality_cmp(b)
+            .then_with(|| {
+                b.coverage(sequence_length)
+                    .cmp(&a.coverage(sequence_length))
+            })
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    ranked
+}
+
+/// Orders structures by coverage of `start..=end` first, then as
+/// [`rank_pdb_xrefs`] does.
+pub fn rank_pdb_xrefs_for_range(xrefs: &[PdbXref], start: u64, end: u64) -> Vec<PdbXref> {
+    let mut ranked = xrefs.to_vec();
+    ranked.sort_by(|a, b| {
+        b.coverage_of(start, end)
+            .cmp(&a.coverage_of(start, end))
+            .then_with(|| a.quality_cmp(b))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    ranked
+}
+
+/// Parses a PDB xref's `properties` (`Method`, `Resolution`, `Chains`).
+pub fn parse_pdb_xref(xref: &Value) -> Option<PdbXref> {
+    let id = xref.get("id").and_then(|v| v.as_str())?.to_string();
+    let mut method = None;
+    let mut resolution = None;
+    let mut chains = Vec::new();
+    if let Some(props) = xref.get("properties").and_then(|v| v.as_array()) {
+        for prop in props {
+            let key = prop.get("key").and_then(|v| v.as_str()).unwrap_or("");
+            let Some(value) = prop
+                .get("value")
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|value| !value.is_empty() && *value != "-")
+            else {
+                continue;
+            };
+            match key {
+                "Method" => method = Some(value.to_string()),
+                "Resolution" => {
+                    resolution = value
+                        .split_whitespace()
+                        .next()
+                        .and_then(|number| number.parse::<f64>().ok());
+                }
+                "Chains" => chains = parse_chain_ranges(value),
+                _ => {}
+            }
+        }
+    }
+    Some(PdbXref {
+        id,
+        method,
+        resolution,
+        chains,
+    })
+}
+
+/// Parses UniProt chain notation such as `A/C=1-141, B/D=2-146`.
+fn parse_chain_ranges(value: &str) -> Vec<ChainRange> {
+    let mut chains = Vec::new();
+    for group in value.split(',') {
+        let (names, range) = group.split_once('=').unwrap_or((group, ""));
+        let (start, end) = range
+            .trim()
+            .split_once('-')
+            .map(|(start, end)| (start.trim().parse().ok(), end.trim().parse().ok()))
+            .unwrap_or((None, None));
+        for name in names
+            .split('/')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            chains.push(ChainRange {
+                chain: name.to_string(),
+                start,
+                end,
+            });
+        }
+    }
+    chains
+}
+
+/// A UniProt proteome entry with the summary stored beside its FASTA.
+#[derive(Debug, Clone)]
+pub struct ProteomeRecord {
+    pub raw_json: Value,
+    pub metadata: ProteomeMetadata,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProteomeMetadata {
+    pub registry: String,
+    pub id: String,
+    pub description: Option<String>,
+    pub proteome_type: Option<String>,
+    pub organism: Option<String>,
+    pub taxon_id: Option<u64>,
+    /// Scientific names from the root of the taxonomy down.
+    pub lineage: Vec<String>,
+    pub protein_count: Option<u64>,
+    pub gene_count: Option<u64>,
+    pub genome_assembly: Option<String>,
+    pub components: Vec<ProteomeComponent>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProteomeComponent {
+    pub name: String,
+    pub protein_count: Option<u64>,
+}
+
 pub trait UniprotClient: Send + Sync {
     fn fetch(&self, id: &UniprotId) -> Result<UniprotRecord, KiraError>;
+
+    /// Only the sequence, for completing a `--metadata-only` copy.
+    fn fetch_fasta(&self, id: &UniprotId) -> Result<String, KiraError> {
+        self.fetch(id).map(|record| record.fasta)
+    }
+
+    /// Fetches the entry unless `stored` validators show that neither its
+    /// JSON nor its FASTA changed. Clients that cannot ask always fetch.
+    fn fetch_if_modified(
+        &self,
+        id: &UniprotId,
+        _stored: &StoredValidators,
+    ) -> Result<Conditional<UniprotRecord>, KiraError> {
+        self.fetch(id).map(Conditional::unconditional)
+    }
+
+    /// The proteome's entry from `/proteomes`, with its entry counts and
+    /// taxonomy.
+    fn fetch_proteome(&self, id: &ProteomeId) -> Result<ProteomeRecord, KiraError> {
+        Err(KiraError::UniprotHttp(format!(
+            "no proteome source for uniprot-proteome:{id}"
+        )))
+    }
+
+    /// Streams every sequence of the proteome to `destination` as
+    /// gzip-compressed FASTA and returns the file's SHA-256.
+    fn download_proteome(
+        &self,
+        id: &ProteomeId,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::UniprotHttp(format!(
+            "no proteome source for uniprot-proteome:{id}"
+        )))
+    }
 }
 
 #[derive(Clone)]
 pub struct UniprotHttpClient {
     client: Client,
+    request_timeout: Duration,
+    mirrors: Mirrors,
 }
 
 impl UniprotHttpClient {
     pub fn new() -> Result<Self, KiraError> {
+        Self::with_settings(HttpSettings::from_env())
+    }
+
+    pub fn with_settings(settings: HttpSettings) -> Result<Self, KiraError> {
         let mut headers = HeaderMap::new();
         headers.insert(
             USER_AGENT,
             HeaderValue::from_str(&format!("kira-bm/{}", env!("CARGO_PKG_VERSION")))
                 .map_err(|err| KiraError::Filesystem(err.to_string()))?,
         );
-        let client = Client::builder()
+        let client = settings
+            .client_builder("uniprot")
             .default_headers(headers)
-            .timeout(Duration::from_secs(30))
             .build()
             .map_err(|err| KiraError::UniprotHttp(err.to_string()))?;
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            request_timeout: settings.timeouts_for("uniprot").request,
+            mirrors: settings.mirrors()?,
+        })
     }
 
-    fn send_with_retries<F>(
+    /// Sends every request to `base_url` instead of rest.uniprot.org.
+    pub fn with_base_url(settings: HttpSettings, base_url: &str) -> Result<Self, KiraError> {
+        Ok(Self {
+            mirrors: Mirrors::everywhere(base_url),
+            ..Self::with_settings(settings)?
+        })
+    }
+
+    fn send_with_retries<F>(&self, make_req: F) -> Result<reqwest::blocking::Response, KiraError>
+    where
+        F: FnMut() -> reqwest::blocking::RequestBuilder,
+    {
+        self.send_within(Some(self.request_timeout), make_req)
+    }
+
+    /// Sends with retries, bounding each attempt by `deadline`; streamed
+    /// downloads pass `None` and rely on the client's stall timeout.
+    fn send_within<F>(
         &self,
+        deadline: Option<Duration>,
         mut make_req: F,
     ) -> Result<reqwest::blocking::Response, KiraError>
     where
@@ -117,13 +393,19 @@ impl UniprotHttpClient {
         const BASE_DELAY_MS: u64 = 200;
         let mut attempt = 0usize;
         loop {
-            let response = make_req().send();
+            let mut request = make_req();
+            if let Some(deadline) = deadline {
+                request = request.timeout(deadline);
+            }
+            let response = request.send();
             match response {
                 Ok(resp) => {
+                    record_response(&resp);
                     let status = resp.status().as_u16();
                     if attempt < MAX_RETRIES && is_retryable_status(status) {
-                        let delay = BASE_DELAY_MS * (attempt as u64 + 1);
-                        std::thread::sleep(Duration::from_millis(delay));
+                        let delay = Duration::from_millis(BASE_DELAY_MS * (attempt as u64 + 1));
+                        trace_retry("uniprot", attempt + 1, delay, &status);
+                        std::thread::sleep(delay);
                         attempt += 1;
                         continue;
                     }
@@ -131,8 +413,9 @@ impl UniprotHttpClient {
                 }
                 Err(err) => {
                     if attempt < MAX_RETRIES && is_retryable_error(&err) {
-                        let delay = BASE_DELAY_MS * (attempt as u64 + 1);
-                        std::thread::sleep(Duration::from_millis(delay));
+                        let delay = Duration::from_millis(BASE_DELAY_MS * (attempt as u64 + 1));
+                        trace_retry("uniprot", attempt + 1, delay, &err);
+                        std::thread::sleep(delay);
                         attempt += 1;
                         continue;
                     }
@@ -144,53 +427,259 @@ impl UniprotHttpClient {
 
     fn handle_status(
         response: reqwest::blocking::Response,
+        dataset: &str,
     ) -> Result<reqwest::blocking::Response, KiraError> {
         if response.status().is_success() {
             return Ok(response);
         }
-        let status = response.status().as_u16();
-        let message = response
-            .text()
-            .unwrap_or_else(|_| "UniProt request failed".to_string());
-        Err(KiraError::UniprotStatus { status, message })
+        let failed = FailedResponse::read(response);
+        Err(KiraError::UniprotStatus {
+            status: failed.status,
+            url: failed.url,
+            dataset: dataset.to_string(),
+            message: failed.message,
+        })
     }
 
-    fn metadata_url(id: &UniprotId) -> String {
-        format!("https://rest.uniprot.org/uniprotkb/{}.json", id.as_str())
+    /// Isoforms share their entry's annotation, so the entry is fetched by
+    /// its accession; only the sequence is the isoform's own.
+    pub fn metadata_url(id: &UniprotId) -> String {
+        format!("https://rest.uniprot.org/uniprotkb/{}.json", id.accession())
     }
 
-    fn fasta_url(id: &UniprotId) -> String {
+    pub fn fasta_url(id: &UniprotId) -> String {
         format!("https://rest.uniprot.org/uniprotkb/{}.fasta", id.as_str())
     }
+
+    pub fn proteome_url(id: &ProteomeId) -> String {
+        format!("https://rest.uniprot.org/proteomes/{}", id.as_str())
+    }
+
+    /// Every entry of the proteome as one compressed FASTA stream, however
+    /// many there are; the paginated search endpoint stops at 500.
+    pub fn proteome_fasta_url(id: &ProteomeId) -> String {
+        format!(
+            "https://rest.uniprot.org/uniprotkb/stream?query=proteome:{}&format=fasta&compressed=true",
+            id.as_str()
+        )
+    }
+
+    /// A successful response for `source`, or `None` when `validators`
+    /// are still current.
+    fn get(
+        &self,
+        source: &str,
+        validators: &Validators,
+        id: &UniprotId,
+    ) -> Result<Option<Response>, KiraError> {
+        let url = self.mirrors.rewrite(source);
+        let response = self.send_with_retries(|| validators.apply(self.client.get(&url)))?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        Self::handle_status(response, &format!("uniprot:{id}")).map(Some)
+    }
+}
+
+fn unexpected_not_modified(id: &UniprotId) -> KiraError {
+    KiraError::UniprotHttp(format!(
+        "UniProt answered an unconditional request for {id} with 304"
+    ))
 }
 
 impl UniprotClient for UniprotHttpClient {
     fn fetch(&self, id: &UniprotId) -> Result<UniprotRecord, KiraError> {
-        let metadata_url = Self::metadata_url(id);
-        let fasta_url = Self::fasta_url(id);
+        match self.fetch_if_modified(id, &StoredValidators::new())? {
+            Conditional::Modified(record, _) => Ok(record),
+            Conditional::NotModified => Err(unexpected_not_modified(id)),
+        }
+    }
 
-        let response = self.send_with_retries(|| self.client.get(&metadata_url))?;
-        let response = Self::handle_status(response)?;
-        let raw_json: Value = response
+    #[tracing::instrument(
+        name = "uniprot.fetch",
+        skip_all,
+        fields(registry = "uniprot", dataset = %id.as_str(), endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    fn fetch_if_modified(
+        &self,
+        id: &UniprotId,
+        stored: &StoredValidators,
+    ) -> Result<Conditional<UniprotRecord>, KiraError> {
+        let metadata_source = Self::metadata_url(id);
+        let fasta_source = Self::fasta_url(id);
+        let stored_for = |source: &str| stored.get(source).cloned().unwrap_or_default();
+        let unconditional = Validators::default();
+
+        // The record needs both parts, so once one has changed the other
+        // is fetched in full.
+        let (json, fasta) = match self.get(&metadata_source, &stored_for(&metadata_source), id)? {
+            Some(json) => (json, self.get(&fasta_source, &unconditional, id)?),
+            None => match self.get(&fasta_source, &stored_for(&fasta_source), id)? {
+                None => return Ok(Conditional::NotModified),
+                fasta => (
+                    self.get(&metadata_source, &unconditional, id)?
+                        .ok_or_else(|| unexpected_not_modified(id))?,
+                    fasta,
+                ),
+            },
+        };
+        let fasta = fasta.ok_or_else(|| unexpected_not_modified(id))?;
+
+        let mut validators = Validators::of(&json).for_url(metadata_source);
+        validators.extend(Validators::of(&fasta).for_url(fasta_source));
+        let raw_json: Value = json
             .json()
             .map_err(|err| KiraError::UniprotHttp(err.to_string()))?;
+        let fasta = fasta
+            .text()
+            .map_err(|err| KiraError::UniprotHttp(err.to_string()))?;
+        record_bytes(fasta.len() as u64);
+
+        let metadata = extract_metadata(&raw_json)?;
+
+        Ok(Conditional::Modified(
+            UniprotRecord {
+                raw_json,
+                fasta,
+                metadata,
+            },
+            validators,
+        ))
+    }
 
+    #[tracing::instrument(
+        name = "uniprot.fetch_fasta",
+        skip_all,
+        fields(registry = "uniprot", dataset = %id.as_str(), endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    fn fetch_fasta(&self, id: &UniprotId) -> Result<String, KiraError> {
+        let fasta_url = self.mirrors.rewrite(&Self::fasta_url(id));
         let response = self.send_with_retries(|| self.client.get(&fasta_url))?;
-        let response = Self::handle_status(response)?;
-        let fasta = response
+        let fasta = Self::handle_status(response, &format!("uniprot:{id}"))?
             .text()
             .map_err(|err| KiraError::UniprotHttp(err.to_string()))?;
+        record_bytes(fasta.len() as u64);
+        Ok(fasta)
+    }
 
-        let metadata = extract_metadata(&raw_json)?;
+    #[tracing::instrument(
+        name = "uniprot.fetch_proteome",
+        skip_all,
+        fields(registry = "uniprot", dataset = %id.as_str(), endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    fn fetch_proteome(&self, id: &ProteomeId) -> Result<ProteomeRecord, KiraError> {
+        let url = self.mirrors.rewrite(&Self::proteome_url(id));
+        let response = self.send_with_retries(|| {
+            self.client
+                .get(&url)
+                .header(reqwest::header::ACCEPT, "application/json")
+        })?;
+        let raw_json: Value = Self::handle_status(response, &format!("uniprot-proteome:{id}"))?
+            .json()
+            .map_err(|err| KiraError::UniprotHttp(err.to_string()))?;
+        let metadata = extract_proteome_metadata(&raw_json)?;
+        Ok(ProteomeRecord { raw_json, metadata })
+    }
 
-        Ok(UniprotRecord {
-            raw_json,
-            fasta,
-            metadata,
-        })
+    #[tracing::instrument(
+        name = "uniprot.download_proteome",
+        skip_all,
+        fields(registry = "uniprot", dataset = %id.as_str(), endpoint = Empty, status = Empty, bytes = Empty)
+    )]
+    fn download_proteome(
+        &self,
+        id: &ProteomeId,
+        destination: &Path,
+        sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        // Proteomes run to hundreds of megabytes, so the body goes straight
+        // to disk without the usual request deadline.
+        let url = self.mirrors.rewrite(&Self::proteome_fasta_url(id));
+        let response = self.send_within(None, || self.client.get(&url))?;
+        let response = Self::handle_status(response, &format!("uniprot-proteome:{id}"))?;
+        let total = response.content_length();
+        let mut file = HashingWriter::new(
+            File::create(destination).map_err(|err| KiraError::Filesystem(err.to_string()))?,
+        );
+        let result = write_gzip(Metered::new(response, sink, destination, total), &mut file);
+        let (file, digest) = file.finish();
+        drop(file);
+        if result.is_err() {
+            let _ = std::fs::remove_file(destination);
+        }
+        record_bytes(result?);
+        Ok(digest)
     }
 }
 
+/// Copies `body` into `file` as gzip: as it is when the server compressed
+/// it, otherwise compressing on the way. Returns the bytes read.
+fn write_gzip(mut body: impl Read, file: &mut impl Write) -> Result<u64, KiraError> {
+    // The first chunk may be a single byte, so the magic is read out and
+    // put back in front rather than peeked.
+    let mut head = Vec::with_capacity(GZIP_MAGIC.len());
+    body.by_ref()
+        .take(GZIP_MAGIC.len() as u64)
+        .read_to_end(&mut head)
+        .map_err(|err| KiraError::UniprotHttp(err.to_string()))?;
+    let gzipped = head == GZIP_MAGIC;
+    let mut body = Cursor::new(head).chain(body);
+    if gzipped {
+        return cancel::copy(&mut body, file, KiraError::UniprotHttp);
+    }
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    let copied = cancel::copy(&mut body, &mut encoder, KiraError::UniprotHttp)?;
+    encoder
+        .finish()
+        .and_then(|file| file.flush())
+        .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+    Ok(copied)
+}
+
+pub fn extract_proteome_metadata(raw: &Value) -> Result<ProteomeMetadata, KiraError> {
+    let id = raw
+        .get("id")
+        .and_then(Value::as_str)
+        .ok_or_else(|| KiraError::UniprotHttp("proteome entry without an id".to_string()))?;
+    let text = |value: &Value| value.as_str().map(str::to_string);
+    let taxonomy = &raw["taxonomy"];
+    let lineage = raw["taxonLineage"]
+        .as_array()
+        .map(|taxa| {
+            taxa.iter()
+                .filter_map(|taxon| text(&taxon["scientificName"]))
+                .collect()
+        })
+        .unwrap_or_default();
+    let components = raw["components"]
+        .as_array()
+        .map(|components| {
+            components
+                .iter()
+                .filter_map(|component| {
+                    Some(ProteomeComponent {
+                        name: text(&component["name"])?,
+                        protein_count: component["proteinCount"].as_u64(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(ProteomeMetadata {
+        registry: "uniprot".to_string(),
+        id: id.to_string(),
+        description: text(&raw["description"]),
+        proteome_type: text(&raw["proteomeType"]),
+        organism: text(&taxonomy["scientificName"]),
+        taxon_id: taxonomy["taxonId"].as_u64(),
+        lineage,
+        protein_count: raw["proteinCount"].as_u64(),
+        gene_count: raw["geneCount"].as_u64(),
+        genome_assembly: text(&raw["genomeAssembly"]["assemblyId"]),
+        components,
+    })
+}
+
 pub fn extract_metadata(raw: &Value) -> Result<UniprotMetadata, KiraError> {
     let accession = raw
         .get("primaryAccession")
@@ -250,14 +739,14 @@ pub fn extract_metadata(raw: &Value) -> Result<UniprotMetadata, KiraError> {
     let mut canonical = true;
     if let Some(comments) = raw.get("comments").and_then(|v| v.as_array()) {
         for comment in comments {
-            if comment.get("commentType").and_then(|v| v.as_str()) == Some("ALTERNATIVE_PRODUCTS") {
-                if let Some(iso) = comment.get("isoforms").and_then(|v| v.as_array()) {
-                    for item in iso {
-                        if let Some(iso_ids) = item.get("isoformIds").and_then(|v| v.as_array()) {
-                            for iso_id in iso_ids {
-                                if let Some(id) = iso_id.as_str() {
-                                    isoforms.push(id.to_string());
-                                }
+            if comment.get("commentType").and_then(|v| v.as_str()) == Some("ALTERNATIVE_PRODUCTS")
+                && let Some(iso) = comment.get("isoforms").and_then(|v| v.as_array())
+            {
+                for item in iso {
+                    if let Some(iso_ids) = item.get("isoformIds").and_then(|v| v.as_array()) {
+                        for iso_id in iso_ids {
+                            if let Some(id) = iso_id.as_str() {
+                                isoforms.push(id.to_string());
                             }
                         }
                     }
@@ -405,7 +894,12 @@ pub fn extract_metadata(raw: &Value) -> Result<UniprotMetadata, KiraError> {
             let db = xref.get("database").and_then(|v| v.as_str()).unwrap_or("");
             let id = xref.get("id").and_then(|v| v.as_str());
             match (db, id) {
-                ("PDB", Some(value)) => cross_refs.pdb.push(value.to_string()),
+                ("PDB", Some(value)) => {
+                    cross_refs.pdb_ids.push(value.to_string());
+                    if let Some(pdb) = parse_pdb_xref(xref) {
+                        cross_refs.pdb.push(pdb);
+                    }
+                }
                 ("RefSeq", Some(value)) => cross_refs.ncbi.push(value.to_string()),
                 ("GeneID", Some(value)) => cross_refs.ncbi.push(value.to_string()),
                 _ => {}
@@ -468,17 +962,17 @@ fn has_note_keyword(item: &Value, keyword: &str) -> bool {
     if let Some(note) = item.get("note") {
         if let Some(texts) = note.get("texts").and_then(|v| v.as_array()) {
             for text in texts {
-                if let Some(value) = text.get("value").and_then(|v| v.as_str()) {
-                    if value.to_lowercase().contains(keyword) {
-                        return true;
-                    }
+                if let Some(value) = text.get("value").and_then(|v| v.as_str())
+                    && value.to_lowercase().contains(keyword)
+                {
+                    return true;
                 }
             }
         }
-        if let Some(value) = note.get("value").and_then(|v| v.as_str()) {
-            if value.to_lowercase().contains(keyword) {
-                return true;
-            }
+        if let Some(value) = note.get("value").and_then(|v| v.as_str())
+            && value.to_lowercase().contains(keyword)
+        {
+            return true;
         }
     }
     false
@@ -490,18 +984,18 @@ fn has_feature_ref_keyword(item: &Value, keyword: &str) -> bool {
         .and_then(|v| v.as_array())
     {
         for entry in refs {
-            if let Some(id) = entry.get("id").and_then(|v| v.as_str()) {
-                if id.to_lowercase().contains(keyword) {
-                    return true;
-                }
+            if let Some(id) = entry.get("id").and_then(|v| v.as_str())
+                && id.to_lowercase().contains(keyword)
+            {
+                return true;
             }
             if let Some(props) = entry.get("properties").and_then(|v| v.as_array()) {
                 for prop in props {
                     let value = prop.get("value").and_then(|v| v.as_str());
-                    if let Some(value) = value {
-                        if value.to_lowercase().contains(keyword) {
-                            return true;
-                        }
+                    if let Some(value) = value
+                        && value.to_lowercase().contains(keyword)
+                    {
+                        return true;
                     }
                 }
             }
@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use chrono::{DateTime, TimeDelta, Utc};
+
+use kira_biodata_manager::history::{self, HistoryRecord};
+use kira_biodata_manager::store::{self, DatasetSize, Materialization, Metadata, StoreAnalytics};
+use kira_biodata_manager::tui::analytics_lines;
+
+fn now() -> DateTime<Utc> {
+    "2026-10-15T12:00:00Z".parse().unwrap()
+}
+
+fn days_ago(days: i64) -> String {
+    (now() - TimeDelta::days(days)).to_rfc3339()
+}
+
+/// A stored dataset of `bytes` bytes, downloaded `age` days before [`now`].
+fn dataset(root: &Utf8Path, dataset_type: &str, id: &str, bytes: usize, age: i64) -> Metadata {
+    let path = root.join(dataset_type).join(id);
+    std::fs::create_dir_all(&path).unwrap();
+    std::fs::write(path.join("data"), vec![0u8; bytes]).unwrap();
+    Metadata {
+        source: "test".to_string(),
+        dataset_type: dataset_type.to_string(),
+        id: id.to_string(),
+        format: None,
+        downloaded_at: days_ago(age),
+        tool: "kira-bm".to_string(),
+        resolved_path: path.to_string(),
+        status: None,
+        expiry: None,
+        provenance: None,
+        materialization: Materialization::Full,
+        assembly_name: None,
+        download: None,
+        converted_formats: Vec::new(),
+        formats: BTreeMap::new(),
+        alias: None,
+        pinned: false,
+        imported_from: None,
+        experiment: None,
+        cache: None,
+        acquisition: None,
+    }
+}
+
+fn record(specifier: &str, action: &str, bytes: u64, age: i64) -> HistoryRecord {
+    HistoryRecord {
+        timestamp: days_ago(age),
+        ..HistoryRecord::fetched(specifier, action, bytes)
+    }
+}
+
+#[test]
+fn analytics_summarise_store_and_history() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    let project = vec![
+        dataset(&root.join("project"), "protein", "1LYZ", 2048, 1),
+        dataset(&root.join("project"), "genome", "GCF_000005845.2", 8192, 3),
+        dataset(&root.join("project"), "srr", "SRR014966", 1024, 30),
+    ];
+    let cache = vec![dataset(&root.join("cache"), "protein", "1LYZ", 2048, 1)];
+    let history = vec![
+        record("srr:SRR014966", "download", 1024, 30),
+        record("genome:GCF_000005845.2", "download", 8192, 3),
+        HistoryRecord {
+            timestamp: days_ago(2),
+            ..HistoryRecord::failed("protein:4HHB", "Fetch", "RCSB returned status 503")
+        },
+        record("protein:1LYZ", "cache", 2048, 1),
+        record("protein:1LYZ", "project", 2048, 0),
+    ];
+
+    let analytics = store::analytics_at(&project, &cache, &history, now());
+
+    assert_eq!(analytics.added_last_week, 2);
+    assert_eq!(
+        analytics.largest,
+        Some(DatasetSize {
+            specifier: "genome:GCF_000005845.2".to_string(),
+            bytes: 8192,
+        })
+    );
+    assert_eq!(analytics.total_bytes, 2048 + 8192 + 1024 + 2048);
+    assert_eq!(analytics.downloaded_last_week, Some(8192));
+    assert_eq!(analytics.recent_fetches, 4);
+    assert_eq!(analytics.cache_hit_ratio, Some(0.5));
+    let failure = analytics.last_failure.as_ref().unwrap();
+    assert_eq!(failure.specifier, "protein:4HHB");
+    assert_eq!(failure.phase.as_deref(), Some("Fetch"));
+
+    // History round-trips through the log file.
+    let log = root.join("project");
+    for record in &history {
+        history::append(&log, record).unwrap();
+    }
+    assert_eq!(history::read(&log, None).unwrap(), history);
+    assert_eq!(history::read(&log, Some(1)).unwrap(), history[4..]);
+}
+
+#[test]
+fn empty_store_has_no_analytics() {
+    let analytics = store::analytics_at(&[], &[], &[], now());
+    assert_eq!(analytics, StoreAnalytics::default());
+
+    let temp = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    assert!(history::read(&root, None).unwrap().is_empty());
+}
+
+#[test]
+fn details_lines_render_present_and_absent_data() {
+    let analytics = StoreAnalytics {
+        added_last_week: 2,
+        largest: Some(DatasetSize {
+            specifier: "genome:GCF_000005845.2".to_string(),
+            bytes: 3 * 1024 * 1024,
+        }),
+        total_bytes: 5 * 1024 * 1024,
+        downloaded_last_week: Some(1024 * 1024),
+        cache_hit_ratio: Some(2.0 / 3.0),
+        recent_fetches: 6,
+        last_failure: Some(HistoryRecord {
+            timestamp: "2026-10-14T09:30:00+00:00".to_string(),
+            ..HistoryRecord::failed("protein:4HHB", "Fetch", "RCSB returned status 503")
+        }),
+    };
+    assert_eq!(
+        render(&analytics),
+        vec![
+            "Added (7d): 2 datasets",
+            "Largest: genome:GCF_000005845.2 (3.0 MB)",
+            "Size: 5.0 MB (+1.0 MB this week)",
+            "Cache hits: 67% of last 6 fetches",
+            "Last failure: protein:4HHB [Fetch] RCSB returned status 503 (2026-10-14 09:30)",
+        ]
+    );
+
+    assert_eq!(
+        render(&StoreAnalytics::default()),
+        vec![
+            "Added (7d): none",
+            "Largest: n/a",
+            "Size: 0 B",
+            "Cache hits: no fetches yet",
+            "Last failure: none",
+        ]
+    );
+}
+
+fn render(analytics: &StoreAnalytics) -> Vec<String> {
+    analytics_lines(analytics)
+        .into_iter()
+        .map(|(label, value)| format!("{label}: {value}"))
+        .collect()
+}
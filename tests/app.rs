@@ -1,18 +1,30 @@
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::sync::Mutex;
 
+use assert_matches::assert_matches;
 use camino::Utf8PathBuf;
 
-use kira_biodata_manager::app::{App, FetchOptions, FetchOverrides};
+use kira_biodata_manager::app::{
+    App, ClearOptions, FetchOptions, FetchOverrides, GcOptions, ImportOptions, InitOptions,
+    ListLocation, ListOptions, ListSort, ProgressSink, VerifyCache,
+};
+use kira_biodata_manager::config::{
+    CacheConfig, CacheDecision, CachePolicy, CacheSource, RetentionConfig, read_id_list,
+};
 use kira_biodata_manager::domain::{
-    DatasetSpecifier, GenomeAccession, ProteinFormat, ProteinId, SrrId, UniprotId,
+    BioProjectAccession, DatasetSpecifier, GenomeAccession, GeoSampleAccession, GeoSeriesAccession,
+    ProteinFormat, ProteinId, SrrId, UniprotId,
 };
 use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::geo::GeoClient;
+use kira_biodata_manager::knowledge::{GoFlavor, KnowledgeClient};
 use kira_biodata_manager::ncbi::{DownloadInfo, NcbiClient};
 use kira_biodata_manager::output::JsonOutput;
 use kira_biodata_manager::rcsb::{RcsbClient, RcsbMetadata};
-use kira_biodata_manager::srr::{SrrClient, ToolInfo};
-use kira_biodata_manager::store::Store;
+use kira_biodata_manager::retention::{self, RetentionPolicy};
+use kira_biodata_manager::srr::{SrrClient, SrrDownloadOptions, ToolInfo};
+use kira_biodata_manager::store::{DownloadRecord, Materialization, Metadata, Store};
 use kira_biodata_manager::uniprot::{UniprotClient, UniprotRecord};
 
 #[derive(Default)]
@@ -33,6 +45,7 @@ impl NcbiClient for MockNcbi {
         _accession: &GenomeAccession,
         _include: &[String],
         _destination: &Path,
+        _sink: &dyn ProgressSink,
     ) -> Result<DownloadInfo, KiraError> {
         Ok(DownloadInfo { is_zip: true })
     }
@@ -49,6 +62,7 @@ impl RcsbClient for MockRcsb {
         _id: &ProteinId,
         _format: ProteinFormat,
         _destination: &Path,
+        _sink: &dyn ProgressSink,
     ) -> Result<(), KiraError> {
         let mut guard = self.calls.lock().unwrap();
         *guard += 1;
@@ -69,15 +83,13 @@ impl SrrClient for MockSrr {
         _id: &SrrId,
         _paired: bool,
         _destination_dir: &Path,
+        _options: &SrrDownloadOptions,
     ) -> Result<Vec<std::path::PathBuf>, KiraError> {
         Err(KiraError::MissingTool("mock".to_string()))
     }
 
     fn tool_info(&self) -> ToolInfo {
-        ToolInfo {
-            datasets: None,
-            sra_toolkit: None,
-        }
+        ToolInfo::from_output(None, None)
     }
 }
 
@@ -90,6 +102,82 @@ impl UniprotClient for MockUniprot {
     }
 }
 
+#[derive(Default)]
+struct MockGeo;
+
+impl GeoClient for MockGeo {
+    fn fetch_soft_text(&self, _accession: &GeoSeriesAccession) -> Result<String, KiraError> {
+        Err(KiraError::GeoHttp("not implemented".to_string()))
+    }
+
+    fn fetch_sample_soft_text(&self, _accession: &GeoSampleAccession) -> Result<String, KiraError> {
+        Err(KiraError::GeoHttp("not implemented".to_string()))
+    }
+
+    fn download_url(
+        &self,
+        _url: &str,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::GeoHttp("not implemented".to_string()))
+    }
+}
+
+#[derive(Default)]
+struct MockKnowledge;
+
+impl KnowledgeClient for MockKnowledge {
+    fn download_go_ontology(
+        &self,
+        _flavor: GoFlavor,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<Vec<u8>, KiraError> {
+        Err(KiraError::KnowledgeHttp("not implemented".to_string()))
+    }
+
+    fn download_kegg_pathways(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not implemented".to_string()))
+    }
+
+    fn download_kegg_pathway_links(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not implemented".to_string()))
+    }
+
+    fn download_reactome_pathways(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not implemented".to_string()))
+    }
+
+    fn download_reactome_mappings(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not implemented".to_string()))
+    }
+
+    fn fetch_kegg_info(&self, _database: &str) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not implemented".to_string()))
+    }
+
+    fn fetch_kegg_links(&self, _target: &str, _ids: &[String]) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not implemented".to_string()))
+    }
+}
+
 #[test]
 fn fetch_prefers_cache_over_download() {
     let temp = tempfile::tempdir().unwrap();
@@ -108,16 +196,14 @@ fn fetch_prefers_cache_over_download() {
 
     let app = App::new(
         store,
-        MockNcbi::default(),
+        MockNcbi,
         MockRcsb::default(),
-        MockSrr::default(),
-        MockUniprot::default(),
+        MockSrr,
+        MockUniprot,
+        MockGeo,
+        MockKnowledge,
     );
-    let options = FetchOptions {
-        force: false,
-        no_cache: false,
-        dry_run: false,
-    };
+    let options = FetchOptions::default();
 
     let result = app
         .fetch(
@@ -131,3 +217,1262 @@ fn fetch_prefers_cache_over_download() {
 
     assert_eq!(result.items[0].action, "cache");
 }
+
+#[test]
+fn id_list_fetches_every_entry_in_file_order() {
+    let temp = tempfile::tempdir().unwrap();
+    let project_root = Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap();
+    let cache_root = Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let store = Store::new_with_paths(project_root, cache_root);
+    for id in ["4HHB", "1LYZ"] {
+        let cache_path = store.cache_protein_path(&id.parse().unwrap(), ProteinFormat::Cif);
+        std::fs::create_dir_all(cache_path.parent().unwrap().as_std_path()).unwrap();
+        std::fs::write(cache_path.as_std_path(), b"data").unwrap();
+    }
+    let list = temp.path().join("ids.txt");
+    std::fs::write(&list, "# structures for review\n4hhb\n\n  protein:1LYZ\n").unwrap();
+
+    let entries = read_id_list(&list).unwrap();
+    assert_eq!(
+        entries
+            .iter()
+            .map(|entry| (entry.line, entry.specifier.to_string()))
+            .collect::<Vec<_>>(),
+        vec![
+            (2, "protein:4HHB".to_string()),
+            (4, "protein:1LYZ".to_string())
+        ]
+    );
+
+    let app = App::new(
+        store,
+        MockNcbi,
+        MockRcsb::default(),
+        MockSrr,
+        MockUniprot,
+        MockGeo,
+        MockKnowledge,
+    );
+    let result = app
+        .fetch_list(
+            &entries,
+            FetchOverrides::default(),
+            FetchOptions::default(),
+            &JsonOutput,
+        )
+        .unwrap();
+    let fetched = result
+        .items
+        .iter()
+        .map(|item| format!("{}:{} {}", item.dataset_type, item.id, item.action))
+        .collect::<Vec<_>>();
+    assert_eq!(fetched, vec!["protein:4HHB cache", "protein:1LYZ cache"]);
+}
+
+#[test]
+fn cache_hit_reports_the_recorded_download() {
+    let temp = tempfile::tempdir().unwrap();
+    let project_root = Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap();
+    let cache_root = Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let store = Store::new_with_paths(project_root, cache_root);
+    let id: ProteinId = "1LYZ".parse().unwrap();
+    let cache_path = store.cache_protein_path(&id, ProteinFormat::Cif);
+    std::fs::create_dir_all(cache_path.parent().unwrap().as_std_path()).unwrap();
+    std::fs::write(cache_path.as_std_path(), b"data").unwrap();
+    // A digest that cannot be the file's: with the cache check off, the
+    // record is trusted, not redone.
+    let recorded = DownloadRecord {
+        source_url: vec!["https://files.rcsb.org/download/1LYZ.cif".to_string()],
+        bytes_written: 4,
+        sha256: [("1LYZ.cif".to_string(), "recorded".to_string())].into(),
+        duration_ms: None,
+        validators: Default::default(),
+    };
+    let cache_meta = Metadata {
+        source: "rcsb".to_string(),
+        dataset_type: "protein".to_string(),
+        id: "1LYZ".to_string(),
+        format: Some("cif".to_string()),
+        downloaded_at: "2024-01-01T00:00:00Z".to_string(),
+        tool: "kira-bm/test".to_string(),
+        resolved_path: cache_path.to_string(),
+        status: None,
+        expiry: None,
+        provenance: None,
+        materialization: Materialization::Full,
+        assembly_name: None,
+        download: Some(recorded.clone()),
+        converted_formats: Vec::new(),
+        formats: BTreeMap::new(),
+        alias: None,
+        pinned: false,
+        imported_from: None,
+        experiment: None,
+        cache: None,
+        acquisition: None,
+    };
+    Store::write_metadata(&store.cache_metadata_path("protein", "1LYZ"), &cache_meta).unwrap();
+
+    let app = App::new(
+        store.clone(),
+        MockNcbi,
+        MockRcsb::default(),
+        MockSrr,
+        MockUniprot,
+        MockGeo,
+        MockKnowledge,
+    );
+    let fetch = || {
+        app.fetch(
+            Some(DatasetSpecifier::Protein(id.clone())),
+            None,
+            FetchOverrides::default(),
+            FetchOptions::builder()
+                .verify_cache(VerifyCache::Off)
+                .build(),
+            &JsonOutput,
+        )
+        .unwrap()
+        .items
+        .remove(0)
+    };
+    let item = fetch();
+    assert_eq!(item.action, "cache");
+    assert_eq!(item.download.as_ref(), Some(&recorded));
+
+    // The project record carries it on, so a project hit reports it too.
+    let item = fetch();
+    assert_eq!(item.action, "project");
+    assert_eq!(item.download, Some(recorded));
+}
+
+#[test]
+fn fetch_restores_expired_project_dataset() {
+    let temp = tempfile::tempdir().unwrap();
+    let project_root = Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap();
+    let cache_root = Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let store = Store::new_with_paths(project_root, cache_root);
+    let app = App::new(
+        store.clone(),
+        MockNcbi,
+        MockRcsb::default(),
+        MockSrr,
+        MockUniprot,
+        MockGeo,
+        MockKnowledge,
+    );
+    let id: ProteinId = "1LYZ".parse().unwrap();
+    let project_path = store.project_protein_path(&id, ProteinFormat::Cif);
+    let cache_path = store.cache_protein_path(&id, ProteinFormat::Cif);
+    std::fs::create_dir_all(cache_path.parent().unwrap().as_std_path()).unwrap();
+    std::fs::write(cache_path.as_std_path(), b"data").unwrap();
+    let fetch = || {
+        app.fetch(
+            Some(DatasetSpecifier::Protein(id.clone())),
+            None,
+            FetchOverrides::default(),
+            FetchOptions::default(),
+            &JsonOutput,
+        )
+        .unwrap()
+    };
+    fetch();
+    assert!(project_path.exists());
+
+    let record_path = store.project_metadata_path("protein", "1LYZ");
+    let mut record: Metadata =
+        serde_json::from_slice(&std::fs::read(&record_path).unwrap()).unwrap();
+    record.downloaded_at = (chrono::Utc::now() - chrono::Duration::days(30)).to_rfc3339();
+    Store::write_metadata(&record_path, &record).unwrap();
+    let policy = RetentionPolicy::from_config(&RetentionConfig {
+        max_age: [("protein".to_string(), "7d".to_string())].into(),
+    })
+    .unwrap();
+    retention::apply(&store, &policy, chrono::Utc::now(), false, false).unwrap();
+    assert!(!project_path.exists());
+    let listed = app.list(&ListOptions::default(), &JsonOutput).unwrap();
+    assert_eq!(listed.datasets[0].status.as_deref(), Some("expired"));
+
+    fetch();
+    assert!(project_path.exists());
+    let record: Metadata = serde_json::from_slice(&std::fs::read(&record_path).unwrap()).unwrap();
+    assert!(!record.is_expired());
+}
+
+#[test]
+fn list_filters_and_sorts_deterministically() {
+    let temp = tempfile::tempdir().unwrap();
+    let project_root = Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap();
+    let cache_root = Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let store = Store::new_with_paths(project_root, cache_root);
+    let seed = |root: &Utf8PathBuf, dataset_type: &str, id: &str, date: &str, bytes: usize| {
+        let path = root.join(dataset_type).join(id);
+        std::fs::create_dir_all(&path).unwrap();
+        std::fs::write(path.join("data"), vec![b'A'; bytes]).unwrap();
+        Store::write_metadata(
+            &root
+                .join("metadata")
+                .join(dataset_type)
+                .join(format!("{id}.json")),
+            &Metadata {
+                source: "test".to_string(),
+                dataset_type: dataset_type.to_string(),
+                id: id.to_string(),
+                format: None,
+                downloaded_at: date.to_string(),
+                tool: "kira-bm".to_string(),
+                resolved_path: path.to_string(),
+                status: None,
+                expiry: None,
+                provenance: None,
+                materialization: Materialization::Full,
+                assembly_name: None,
+                download: None,
+                converted_formats: Vec::new(),
+                formats: BTreeMap::new(),
+                alias: None,
+                pinned: false,
+                imported_from: None,
+                experiment: None,
+                cache: None,
+                acquisition: None,
+            },
+        )
+        .unwrap();
+    };
+    let project = store.project_root().to_path_buf();
+    let cache = store.cache_root().to_path_buf();
+    seed(&project, "protein", "4HHB", "2024-03-01T00:00:00+00:00", 10);
+    seed(
+        &project,
+        "uniprot",
+        "P69905",
+        "2024-01-01T00:00:00+00:00",
+        300,
+    );
+    seed(&cache, "protein", "1LYZ", "2024-02-01T00:00:00+00:00", 20);
+    seed(&cache, "protein", "4HHB", "2023-12-01T00:00:00+00:00", 10);
+    let app = App::new(
+        store,
+        MockNcbi,
+        MockRcsb::default(),
+        MockSrr,
+        MockUniprot,
+        MockGeo,
+        MockKnowledge,
+    );
+    let ids = |options: ListOptions| {
+        app.list(&options, &JsonOutput)
+            .unwrap()
+            .datasets
+            .into_iter()
+            .map(|entry| entry.id)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(ids(ListOptions::default()), vec!["1LYZ", "4HHB", "P69905"]);
+    assert_eq!(
+        ids(ListOptions::builder().sort(ListSort::Date).build()),
+        vec!["4HHB", "1LYZ", "P69905"]
+    );
+    assert_eq!(
+        ids(ListOptions::builder().sort(ListSort::Size).build()),
+        vec!["P69905", "1LYZ", "4HHB"]
+    );
+    assert_eq!(
+        ids(ListOptions::builder().sort(ListSort::Type).build()),
+        vec!["1LYZ", "4HHB", "P69905"]
+    );
+    assert_eq!(
+        ids(ListOptions::builder().dataset_type("PROTEIN").build()),
+        vec!["1LYZ", "4HHB"]
+    );
+    assert_eq!(
+        ids(ListOptions::builder().location(ListLocation::Cache).build()),
+        vec!["1LYZ", "4HHB"]
+    );
+
+    let listed = app
+        .list(
+            &ListOptions::builder()
+                .location(ListLocation::Project)
+                .dataset_type("protein")
+                .build(),
+            &JsonOutput,
+        )
+        .unwrap();
+    assert_eq!(listed.datasets.len(), 1);
+    let entry = &listed.datasets[0];
+    assert_eq!(
+        entry.downloaded_at.as_deref(),
+        Some("2024-03-01T00:00:00+00:00")
+    );
+    assert_eq!(entry.bytes, Some(10));
+    assert!(entry.cache_path.is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn list_skips_unreadable_store_entries() {
+    use std::os::unix::fs::{PermissionsExt, symlink};
+
+    let temp = tempfile::tempdir().unwrap();
+    let project_root = Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap();
+    let cache_root = Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let store = Store::new_with_paths(project_root, cache_root);
+    let metadata_root = store.project_root().join("metadata");
+    Store::write_metadata(
+        &store.project_metadata_path("protein", "1LYZ"),
+        &Metadata {
+            source: "test".to_string(),
+            dataset_type: "protein".to_string(),
+            id: "1LYZ".to_string(),
+            format: None,
+            downloaded_at: "2024-03-01T00:00:00+00:00".to_string(),
+            tool: "kira-bm".to_string(),
+            resolved_path: store.project_root().join("proteins/1LYZ").to_string(),
+            status: None,
+            expiry: None,
+            provenance: None,
+            materialization: Materialization::Full,
+            assembly_name: None,
+            download: None,
+            converted_formats: Vec::new(),
+            formats: BTreeMap::new(),
+            alias: None,
+            pinned: false,
+            imported_from: None,
+            experiment: None,
+            cache: None,
+            acquisition: None,
+        },
+    )
+    .unwrap();
+    let broken = metadata_root.join("protein").join("4HHB.json");
+    std::fs::write(&broken, b"{\"source\": \"test\", ").unwrap();
+    // Valid JSON that is not a dataset record is not worth a warning.
+    std::fs::create_dir_all(metadata_root.join("go")).unwrap();
+    std::fs::write(metadata_root.join("go").join("terms.json"), b"[]").unwrap();
+    // A symlink to itself cannot be read; one to its parent is a cycle.
+    let loop_path = metadata_root.join("loop");
+    symlink("loop", &loop_path).unwrap();
+    symlink("..", metadata_root.join("protein").join("parent")).unwrap();
+    let locked = metadata_root.join("locked");
+    std::fs::create_dir_all(&locked).unwrap();
+    std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+    // Root reads directories regardless of their mode.
+    let locked_unreadable = std::fs::read_dir(&locked).is_err();
+
+    let app = App::new(
+        store.clone(),
+        MockNcbi,
+        MockRcsb::default(),
+        MockSrr,
+        MockUniprot,
+        MockGeo,
+        MockKnowledge,
+    );
+    let listed = app.list(&ListOptions::default(), &JsonOutput).unwrap();
+    std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let ids = listed
+        .datasets
+        .iter()
+        .map(|entry| entry.id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec!["1LYZ"]);
+    let mut skipped = listed
+        .warnings
+        .iter()
+        .map(|warning| warning.path.as_str())
+        .collect::<Vec<_>>();
+    skipped.sort();
+    let mut expected = vec![broken.as_str(), loop_path.as_str()];
+    if locked_unreadable {
+        expected.push(locked.as_str());
+    }
+    expected.sort();
+    assert_eq!(skipped, expected);
+    let broken_warning = listed
+        .warnings
+        .iter()
+        .find(|warning| warning.path == broken.as_str())
+        .unwrap();
+    assert!(broken_warning.error.starts_with("invalid JSON"));
+    let json = serde_json::to_value(&listed).unwrap();
+    assert_eq!(
+        json["warnings"].as_array().map(Vec::len),
+        Some(expected.len())
+    );
+
+    assert_eq!(Store::list_metadata(store.project_root()).unwrap().len(), 1);
+}
+
+#[test]
+fn bioproject_fetch_reuses_manifest_members() {
+    let temp = tempfile::tempdir().unwrap();
+    let project_root = Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap();
+    let cache_root = Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let store = Store::new_with_paths(project_root, cache_root);
+
+    let acc: BioProjectAccession = "PRJNA680067".parse().unwrap();
+    let manifest_path = store.project_bioproject_manifest_path(&acc);
+    std::fs::create_dir_all(manifest_path.parent().unwrap().as_std_path()).unwrap();
+    std::fs::write(
+        manifest_path.as_std_path(),
+        r#"{
+  "bioproject": "PRJNA680067",
+  "source": "ncbi",
+  "srr": ["SRR13081111", "SRR13081112"],
+  "assemblies": ["GCA_000001405.29"],
+  "members": []
+}"#,
+    )
+    .unwrap();
+
+    let app = App::new(
+        store,
+        MockNcbi,
+        MockRcsb::default(),
+        MockSrr,
+        MockUniprot,
+        MockGeo,
+        MockKnowledge,
+    );
+    let options = FetchOptions::builder().dry_run(true).build();
+
+    let result = app
+        .fetch(
+            Some(DatasetSpecifier::BioProject(acc)),
+            None,
+            FetchOverrides::default(),
+            options,
+            &JsonOutput,
+        )
+        .unwrap();
+
+    let members = result
+        .items
+        .iter()
+        .map(|item| format!("{}:{}", item.dataset_type, item.id))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        members,
+        vec![
+            "srr:SRR13081111",
+            "srr:SRR13081112",
+            "genome:GCA_000001405.29"
+        ]
+    );
+    let summary = result.summary.unwrap();
+    assert_eq!(summary.kind, "bioproject");
+    assert_eq!(summary.bioproject.as_deref(), Some("PRJNA680067"));
+    assert_eq!(summary.resolved_targets, 3);
+}
+
+#[test]
+fn init_merges_store_datasets_into_an_existing_config() {
+    let temp = tempfile::tempdir().unwrap();
+    let project_root = Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap();
+    let cache_root = Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let store = Store::new_with_paths(project_root.clone(), cache_root);
+    for id in ["1LYZ", "4HHB"] {
+        let path = project_root.join("protein").join(id);
+        std::fs::create_dir_all(&path).unwrap();
+        Store::write_metadata(
+            &project_root
+                .join("metadata")
+                .join("protein")
+                .join(format!("{id}.json")),
+            &Metadata {
+                source: "rcsb".to_string(),
+                dataset_type: "protein".to_string(),
+                id: id.to_string(),
+                format: Some("cif".to_string()),
+                downloaded_at: "2024-03-01T00:00:00+00:00".to_string(),
+                tool: "kira-bm".to_string(),
+                resolved_path: path.to_string(),
+                status: None,
+                expiry: None,
+                provenance: None,
+                materialization: Materialization::Full,
+                assembly_name: None,
+                download: None,
+                converted_formats: Vec::new(),
+                formats: BTreeMap::new(),
+                alias: None,
+                pinned: false,
+                imported_from: None,
+                experiment: None,
+                cache: None,
+                acquisition: None,
+            },
+        )
+        .unwrap();
+    }
+    let app = App::new(
+        store,
+        MockNcbi,
+        MockRcsb::default(),
+        MockSrr,
+        MockUniprot,
+        MockGeo,
+        MockKnowledge,
+    );
+    // 1LYZ is listed with options, the genome is declared but not fetched.
+    let path = temp.path().join("kira-bm.json");
+    let original = r#"{
+  "proteins": [{"id": "1lyz", "format": "pdb"}],
+  "genomes": [{"accession": "GCF_000005845.2", "include": ["genome"]}],
+  "ncbi_api_key": "abc",
+  "notes": "hand-written"
+}"#;
+    std::fs::write(&path, original).unwrap();
+    let read =
+        || serde_json::from_slice::<serde_json::Value>(&std::fs::read(&path).unwrap()).unwrap();
+
+    let dry_run = InitOptions {
+        dry_run: true,
+        ..InitOptions::default()
+    };
+    let preview = app.init_config_at(&path, dry_run, &JsonOutput).unwrap();
+    assert!(!preview.written);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), original);
+    assert_eq!((preview.added.proteins, preview.added.genomes), (1, 0));
+    assert_eq!(
+        (preview.preserved.proteins, preview.preserved.genomes),
+        (1, 1)
+    );
+    let diff = preview.diff.unwrap();
+    assert!(diff.lines().any(|line| line == "+     \"4HHB\""), "{diff}");
+    assert!(
+        !diff
+            .lines()
+            .any(|line| line.starts_with("- ") && !line.ends_with('}')),
+        "{diff}"
+    );
+
+    let merged = app
+        .init_config_at(&path, InitOptions::default(), &JsonOutput)
+        .unwrap();
+    assert!(merged.written);
+    assert_eq!((merged.counts.proteins, merged.counts.genomes), (2, 1));
+    let config = read();
+    assert_eq!(config["proteins"][0]["format"], "pdb");
+    assert_eq!(config["proteins"][1], "4HHB");
+    assert_eq!(config["genomes"][0]["include"][0], "genome");
+    assert_eq!(config["ncbi_api_key"], "abc");
+    assert_eq!(config["notes"], "hand-written");
+
+    let again = app
+        .init_config_at(&path, InitOptions::default(), &JsonOutput)
+        .unwrap();
+    assert!(!again.written);
+    assert_eq!(again.added.proteins, 0);
+
+    let force = InitOptions {
+        force: true,
+        ..InitOptions::default()
+    };
+    let replaced = app.init_config_at(&path, force, &JsonOutput).unwrap();
+    assert!(replaced.written);
+    let config = read();
+    assert_eq!(config["proteins"], serde_json::json!(["1LYZ", "4HHB"]));
+    assert!(
+        config
+            .get("genomes")
+            .is_none_or(|genomes| genomes == &serde_json::json!([]))
+    );
+    assert!(config.get("notes").is_none());
+}
+
+#[test]
+fn gc_reports_leftovers_and_deletes_them_when_asked() {
+    let temp = tempfile::tempdir().unwrap();
+    let project_root = Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap();
+    let cache_root = Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let store = Store::new_with_paths(project_root.clone(), cache_root.clone());
+
+    let staging = project_root.join("kira-bm-protein-1LYZ.0");
+    std::fs::create_dir_all(&staging).unwrap();
+    std::fs::write(staging.join("1LYZ.tmp"), b"partial").unwrap();
+    let copy = cache_root.join("proteins").join("kira-bm-copy-4HHB.3");
+    std::fs::create_dir_all(&copy).unwrap();
+    let kept = project_root.join("srr").join("SRR000001");
+    std::fs::create_dir_all(&kept).unwrap();
+    let orphan = project_root.join("genomes").join("GCF_000005845.2");
+    std::fs::create_dir_all(&orphan).unwrap();
+    std::fs::write(orphan.join("genome.fna"), b">chr\nACGT\n").unwrap();
+    let record = |id: &str, dataset_type: &str, path: &Utf8PathBuf| {
+        let metadata_path = store.project_metadata_path(dataset_type, id);
+        Store::write_metadata(
+            &metadata_path,
+            &Metadata {
+                source: "test".to_string(),
+                dataset_type: dataset_type.to_string(),
+                id: id.to_string(),
+                format: None,
+                downloaded_at: "2024-01-01T00:00:00+00:00".to_string(),
+                tool: "kira-bm".to_string(),
+                resolved_path: path.to_string(),
+                status: None,
+                expiry: None,
+                provenance: None,
+                materialization: Materialization::Full,
+                assembly_name: None,
+                download: None,
+                converted_formats: Vec::new(),
+                formats: BTreeMap::new(),
+                alias: None,
+                pinned: false,
+                imported_from: None,
+                experiment: None,
+                cache: None,
+                acquisition: None,
+            },
+        )
+        .unwrap();
+        metadata_path
+    };
+    record("SRR000001", "srr", &kept);
+    let dangling = record(
+        "P69905",
+        "uniprot",
+        &project_root.join("uniprot").join("P69905"),
+    );
+
+    let app = App::new(
+        store,
+        MockNcbi,
+        MockRcsb::default(),
+        MockSrr,
+        MockUniprot,
+        MockGeo,
+        MockKnowledge,
+    );
+    let fresh = app.gc(GcOptions::default(), &JsonOutput).unwrap();
+    assert!(fresh.temp_dirs.is_empty());
+
+    let stale = GcOptions {
+        min_age: std::time::Duration::ZERO,
+        ..GcOptions::default()
+    };
+    let report = app.gc(stale, &JsonOutput).unwrap();
+    assert!(report.dry_run);
+    let paths = |entries: &[kira_biodata_manager::app::GcEntry]| {
+        entries
+            .iter()
+            .map(|entry| entry.path.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        paths(&report.temp_dirs),
+        [staging.to_string(), copy.to_string()]
+    );
+    assert_eq!(paths(&report.dangling_metadata), [dangling.to_string()]);
+    assert_eq!(
+        report.dangling_metadata[0].specifier.as_deref(),
+        Some("uniprot:P69905")
+    );
+    assert_eq!(paths(&report.orphans), [orphan.to_string()]);
+    assert!(staging.exists() && dangling.exists() && orphan.exists());
+
+    let applied = app
+        .gc(
+            GcOptions {
+                apply: true,
+                ..stale
+            },
+            &JsonOutput,
+        )
+        .unwrap();
+    assert!(applied.temp_dirs.iter().all(|entry| entry.removed));
+    assert!(!staging.exists() && !copy.exists() && !dangling.exists());
+    assert!(!applied.orphans[0].removed);
+    assert!(orphan.exists() && kept.exists());
+
+    let pruned = app
+        .gc(
+            GcOptions {
+                apply: true,
+                prune_orphans: true,
+                ..stale
+            },
+            &JsonOutput,
+        )
+        .unwrap();
+    assert!(pruned.orphans[0].removed);
+    assert!(!orphan.exists() && kept.exists());
+    let audit = kira_biodata_manager::audit::read(&project_root, None).unwrap();
+    let specifiers = audit
+        .iter()
+        .flat_map(|record| &record.targets)
+        .map(|target| target.specifier.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(specifiers, ["uniprot:P69905", "genomes/GCF_000005845.2"]);
+}
+
+#[test]
+fn pins_survive_the_copy_from_cache_and_protect_the_project_copy() {
+    let temp = tempfile::tempdir().unwrap();
+    let project_root = Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap();
+    let cache_root = Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let store = Store::new_with_paths(project_root, cache_root);
+    let id: ProteinId = "1LYZ".parse().unwrap();
+    let cache_path = store.cache_protein_path(&id, ProteinFormat::Cif);
+    std::fs::create_dir_all(cache_path.parent().unwrap()).unwrap();
+    std::fs::write(&cache_path, b"data").unwrap();
+    Store::write_metadata(
+        &store.cache_metadata_path("protein", "1LYZ"),
+        &Metadata {
+            source: "rcsb".to_string(),
+            dataset_type: "protein".to_string(),
+            id: "1LYZ".to_string(),
+            format: Some("cif".to_string()),
+            downloaded_at: "2024-01-01T00:00:00+00:00".to_string(),
+            tool: "kira-bm".to_string(),
+            resolved_path: cache_path.to_string(),
+            status: None,
+            expiry: None,
+            provenance: None,
+            materialization: Materialization::Full,
+            assembly_name: None,
+            download: None,
+            converted_formats: Vec::new(),
+            formats: BTreeMap::new(),
+            alias: None,
+            pinned: false,
+            imported_from: None,
+            experiment: None,
+            cache: None,
+            acquisition: None,
+        },
+    )
+    .unwrap();
+    let project_record = store.project_metadata_path("protein", "1LYZ");
+    let project_path = store.project_protein_path(&id, ProteinFormat::Cif);
+    let app = App::new(
+        store.clone(),
+        MockNcbi,
+        MockRcsb::default(),
+        MockSrr,
+        MockUniprot,
+        MockGeo,
+        MockKnowledge,
+    );
+    let specifier = || DatasetSpecifier::Protein(id.clone());
+
+    let pinned = app.pin(specifier(), true, &JsonOutput).unwrap();
+    assert_eq!(pinned.specifier, "protein:1LYZ");
+    assert_eq!(pinned.records.len(), 1);
+    assert!(
+        app.pin(specifier(), true, &JsonOutput)
+            .unwrap()
+            .records
+            .is_empty()
+    );
+
+    app.fetch(
+        Some(specifier()),
+        None,
+        FetchOverrides::default(),
+        FetchOptions::default(),
+        &JsonOutput,
+    )
+    .unwrap();
+    let record: Metadata =
+        serde_json::from_slice(&std::fs::read(&project_record).unwrap()).unwrap();
+    assert!(record.pinned);
+    let list = app.list(&ListOptions::default(), &JsonOutput).unwrap();
+    assert!(list.datasets[0].pinned);
+
+    let cleared = app.clear(&ClearOptions::default(), &JsonOutput).unwrap();
+    assert_eq!(cleared.skipped_pinned, ["protein:1LYZ"]);
+    assert!(project_path.exists() && project_record.exists());
+    let err = store.remove_project_dataset(&record, false).unwrap_err();
+    assert!(matches!(err, KiraError::DatasetPinned(_)), "{err}");
+
+    let unpinned = app.pin(specifier(), false, &JsonOutput).unwrap();
+    assert_eq!(unpinned.records.len(), 2);
+    let cleared = app.clear(&ClearOptions::default(), &JsonOutput).unwrap();
+    assert!(cleared.skipped_pinned.is_empty());
+    assert!(!project_path.exists() && !project_record.exists());
+}
+
+fn import_app(
+    temp: &Path,
+) -> App<MockNcbi, MockRcsb, MockSrr, MockUniprot, MockGeo, MockKnowledge> {
+    let store = Store::new_with_paths(
+        Utf8PathBuf::from_path_buf(temp.join("project")).unwrap(),
+        Utf8PathBuf::from_path_buf(temp.join("cache")).unwrap(),
+    );
+    App::new(
+        store,
+        MockNcbi,
+        MockRcsb::default(),
+        MockSrr,
+        MockUniprot,
+        MockGeo,
+        MockKnowledge,
+    )
+}
+
+#[test]
+fn imported_protein_is_listed_with_its_origin_and_checksum() {
+    let temp = tempfile::tempdir().unwrap();
+    let app = import_app(temp.path());
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/rcsb/1LYZ.cif");
+    let specifier = || DatasetSpecifier::Protein("1LYZ".parse().unwrap());
+
+    let result = app
+        .import(specifier(), &fixture, ImportOptions::default(), &JsonOutput)
+        .unwrap();
+    assert_eq!(result.format.as_deref(), Some("cif"));
+    assert_eq!(result.files, ["1LYZ.cif"]);
+    assert!(result.verified && !result.linked);
+    assert_eq!(
+        std::fs::read(&result.project_path).unwrap(),
+        std::fs::read(&fixture).unwrap()
+    );
+
+    let info = app.info(specifier(), &JsonOutput).unwrap();
+    assert_eq!(info.source.as_deref(), Some("import"));
+    let origin = std::fs::canonicalize(&fixture).unwrap();
+    assert_eq!(info.imported_from, Some(origin.display().to_string()));
+    let listed = app
+        .list(&ListOptions::builder().build(), &JsonOutput)
+        .unwrap();
+    assert_eq!(listed.datasets.len(), 1);
+    assert_eq!(listed.datasets[0].source.as_deref(), Some("import"));
+
+    let record_path = temp.path().join("project/metadata/protein/1LYZ.json");
+    let record: Metadata = serde_json::from_slice(&std::fs::read(record_path).unwrap()).unwrap();
+    let download = record.download.unwrap();
+    assert!(download.source_url.is_empty());
+    assert_eq!(download.sha256.keys().collect::<Vec<_>>(), ["1LYZ.cif"]);
+    assert_eq!(download.bytes_written, result.bytes);
+
+    let again = app.import(specifier(), &fixture, ImportOptions::default(), &JsonOutput);
+    assert_matches!(again, Err(KiraError::InvalidImport { reason, .. }) if reason.contains("--force"));
+    let forced = ImportOptions {
+        force: true,
+        ..ImportOptions::default()
+    };
+    app.import(specifier(), &fixture, forced, &JsonOutput)
+        .unwrap();
+}
+
+#[test]
+fn import_rejects_a_structure_of_another_entry_unless_unverified() {
+    let temp = tempfile::tempdir().unwrap();
+    let app = import_app(temp.path());
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/rcsb/1LYZ.pdb");
+    let specifier = || DatasetSpecifier::Protein("2LYZ".parse().unwrap());
+
+    let rejected = app.import(specifier(), &fixture, ImportOptions::default(), &JsonOutput);
+    assert_matches!(
+        rejected,
+        Err(KiraError::InvalidImport { reason, .. }) if reason.contains("entry 1LYZ, not 2LYZ")
+    );
+    assert!(
+        !temp
+            .path()
+            .join("project/metadata/protein/2LYZ.json")
+            .exists()
+    );
+
+    let unverified = ImportOptions {
+        no_verify: true,
+        ..ImportOptions::default()
+    };
+    let result = app
+        .import(specifier(), &fixture, unverified, &JsonOutput)
+        .unwrap();
+    assert!(!result.verified);
+    assert!(result.project_path.ends_with("2LYZ.pdb"));
+}
+
+#[test]
+fn import_takes_a_genome_directory_and_checks_for_sequences() {
+    let temp = tempfile::tempdir().unwrap();
+    let app = import_app(temp.path());
+    let source = temp.path().join("assembly");
+    std::fs::create_dir_all(source.join("ncbi_dataset/data")).unwrap();
+    std::fs::write(source.join("README.md"), "notes").unwrap();
+    let specifier = || DatasetSpecifier::Genome("GCF_000005845.2".parse().unwrap());
+
+    let empty = app.import(specifier(), &source, ImportOptions::default(), &JsonOutput);
+    assert_matches!(
+        empty,
+        Err(KiraError::InvalidImport { reason, .. }) if reason.contains("no FASTA sequence file")
+    );
+
+    std::fs::write(source.join("ncbi_dataset/data/genomic.fna"), ">chr\nACGT\n").unwrap();
+    let linked = ImportOptions {
+        link: true,
+        ..ImportOptions::default()
+    };
+    let result = app
+        .import(specifier(), &source, linked, &JsonOutput)
+        .unwrap();
+    assert_eq!(result.files, ["README.md", "ncbi_dataset/data/genomic.fna"]);
+    let stored = Path::new(&result.project_path).join("ncbi_dataset/data/genomic.fna");
+    assert_eq!(std::fs::read_to_string(stored).unwrap(), ">chr\nACGT\n");
+    assert!(source.join("README.md").exists());
+}
+
+#[test]
+fn import_refuses_specifiers_that_name_no_local_dataset() {
+    let temp = tempfile::tempdir().unwrap();
+    let app = import_app(temp.path());
+    let result = app.import(
+        DatasetSpecifier::Go,
+        temp.path(),
+        ImportOptions::default(),
+        &JsonOutput,
+    );
+    assert_matches!(result, Err(KiraError::InvalidSpecifier(_)));
+}
+
+/// Takes a while over the structure download, then writes a real one.
+struct SlowRcsb;
+
+impl RcsbClient for SlowRcsb {
+    fn download_structure(
+        &self,
+        _id: &ProteinId,
+        _format: ProteinFormat,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<(), KiraError> {
+        std::thread::sleep(std::time::Duration::from_millis(60));
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/rcsb/1LYZ.cif");
+        std::fs::copy(fixture, destination).unwrap();
+        Ok(())
+    }
+
+    fn fetch_metadata(&self, id: &ProteinId) -> Result<RcsbMetadata, KiraError> {
+        Ok(RcsbMetadata {
+            registry: "rcsb".to_string(),
+            pdb_id: id.as_str().to_string(),
+            title: None,
+            experimental_method: None,
+            resolution: None,
+            deposition_date: None,
+            release_date: None,
+            source_structure_url: String::new(),
+            source_metadata_url: String::new(),
+            raw_json: serde_json::json!({}),
+        })
+    }
+}
+
+#[test]
+fn fetch_reports_where_each_item_spent_its_time() {
+    let temp = tempfile::tempdir().unwrap();
+    let store = Store::new_with_paths(
+        Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap(),
+        Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap(),
+    );
+    let app = App::new(
+        store,
+        MockNcbi,
+        SlowRcsb,
+        MockSrr,
+        MockUniprot,
+        MockGeo,
+        MockKnowledge,
+    );
+
+    let result = app
+        .fetch(
+            Some(DatasetSpecifier::Protein("1LYZ".parse().unwrap())),
+            None,
+            FetchOverrides::default(),
+            FetchOptions::default(),
+            &JsonOutput,
+        )
+        .unwrap();
+
+    let timings = result.items[0].timings;
+    assert!(timings.download_ms >= 60, "{timings:?}");
+    assert!(timings.download_ms > timings.resolve_ms + timings.verify_ms + timings.store_ms);
+    assert_eq!(result.timings, timings);
+    assert!(result.total_duration_ms >= timings.total_ms());
+}
+
+#[test]
+fn cache_policy_decides_the_copy_back_and_no_cache_overrides_it() {
+    let temp = tempfile::tempdir().unwrap();
+    let store = Store::new_with_paths(
+        Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap(),
+        Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap(),
+    );
+    let policy = |json: &str| {
+        let config: CacheConfig = serde_json::from_str(json).unwrap();
+        CachePolicy::from_config(&config).unwrap()
+    };
+    let app = |policy: CachePolicy| {
+        App::new(
+            store.clone(),
+            MockNcbi,
+            SlowRcsb,
+            MockSrr,
+            MockUniprot,
+            MockGeo,
+            MockKnowledge,
+        )
+        .with_cache_policy(policy)
+    };
+    let fetch = |app: &App<_, _, _, _, _, _>, id: &str, options: FetchOptions| {
+        let result = app
+            .fetch(
+                Some(DatasetSpecifier::Protein(id.parse().unwrap())),
+                None,
+                FetchOverrides::default(),
+                options,
+                &JsonOutput,
+            )
+            .unwrap();
+        result.items.into_iter().next().unwrap()
+    };
+    let recorded = |id: &str| {
+        let path = store.project_metadata_path("protein", id);
+        let meta: Metadata =
+            serde_json::from_slice(&std::fs::read(path.as_std_path()).unwrap()).unwrap();
+        meta.cache
+    };
+    let skipped_by = |source| CacheDecision {
+        enabled: false,
+        source,
+    };
+
+    let kept_out = app(policy(r#"{"protein": false, "default": true}"#));
+    let item = fetch(&kept_out, "1LYZ", FetchOptions::default());
+    assert_eq!(item.action, "download");
+    assert_eq!(item.cache_path, None);
+    assert_eq!(item.cache, Some(skipped_by(CacheSource::Config)));
+    assert_eq!(recorded("1LYZ"), Some(skipped_by(CacheSource::Config)));
+    let cached = store.cache_protein_path(&"1LYZ".parse().unwrap(), ProteinFormat::Cif);
+    assert!(!cached.as_std_path().exists());
+
+    // An entry already in the cache is still served from it.
+    let id: ProteinId = "4HHB".parse().unwrap();
+    let cached = store.cache_protein_path(&id, ProteinFormat::Cif);
+    std::fs::create_dir_all(cached.parent().unwrap().as_std_path()).unwrap();
+    std::fs::write(cached.as_std_path(), b"data_4HHB\n").unwrap();
+    let item = fetch(&kept_out, "4HHB", FetchOptions::default());
+    assert_eq!(item.action, "cache");
+    assert_eq!(item.cache_path.as_deref(), Some(cached.as_str()));
+    assert_eq!(item.cache, Some(skipped_by(CacheSource::Config)));
+
+    // `--no-cache` wins over a policy that caches the type.
+    let cached_in = app(policy(r#"{"default": false, "protein": true}"#));
+    let options = FetchOptions::builder().force(true).no_cache(true).build();
+    let item = fetch(&cached_in, "1LYZ", options);
+    assert_eq!(item.cache_path, None);
+    assert_eq!(item.cache, Some(skipped_by(CacheSource::Flag)));
+
+    let item = fetch(
+        &cached_in,
+        "1LYZ",
+        FetchOptions::builder().force(true).build(),
+    );
+    assert!(item.cache_path.is_some());
+    let decision = CacheDecision {
+        enabled: true,
+        source: CacheSource::Config,
+    };
+    assert_eq!(item.cache, Some(decision));
+    assert_eq!(recorded("1LYZ"), Some(decision));
+}
+
+#[test]
+fn a_pattern_pins_and_describes_every_matching_project_dataset() {
+    let temp = tempfile::tempdir().unwrap();
+    let store = Store::new_with_paths(
+        Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap(),
+        Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap(),
+    );
+    for (dataset_type, id) in [
+        ("protein", "1LYZ"),
+        ("protein", "1LZ1"),
+        ("protein", "2PTC"),
+        ("srr", "SRR014966"),
+    ] {
+        Store::write_metadata(
+            &store.project_metadata_path(dataset_type, id),
+            &Metadata {
+                source: "rcsb".to_string(),
+                dataset_type: dataset_type.to_string(),
+                id: id.to_string(),
+                format: None,
+                downloaded_at: "2024-01-01T00:00:00+00:00".to_string(),
+                tool: "kira-bm".to_string(),
+                resolved_path: temp.path().join(id).display().to_string(),
+                status: None,
+                expiry: None,
+                provenance: None,
+                materialization: Materialization::Full,
+                assembly_name: None,
+                download: None,
+                converted_formats: Vec::new(),
+                formats: BTreeMap::new(),
+                alias: None,
+                pinned: false,
+                imported_from: None,
+                experiment: None,
+                cache: None,
+                acquisition: None,
+            },
+        )
+        .unwrap();
+    }
+    let app = App::new(
+        store,
+        MockNcbi,
+        MockRcsb::default(),
+        MockSrr,
+        MockUniprot,
+        MockGeo,
+        MockKnowledge,
+    );
+
+    let pinned = app
+        .pin_matching(&"protein:1l*".parse().unwrap(), true, &JsonOutput)
+        .unwrap();
+    assert_eq!(pinned.matched, 2);
+    let specifiers = pinned
+        .datasets
+        .iter()
+        .map(|dataset| dataset.specifier.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(specifiers, ["protein:1LYZ", "protein:1LZ1"]);
+
+    let info = app
+        .info_matching(&"protein:????".parse().unwrap(), &JsonOutput)
+        .unwrap();
+    assert_eq!(info.matched, 3);
+    assert_eq!(info.datasets[2].id, "2PTC");
+
+    let err = app
+        .info_matching(&"srr:SRR0150*".parse().unwrap(), &JsonOutput)
+        .unwrap_err();
+    assert_matches!(err, KiraError::NoMatchingDatasets(pattern) if pattern == "srr:SRR0150*");
+}
+
+#[test]
+fn clear_removes_only_the_selected_types_and_can_keep_their_records() {
+    let temp = tempfile::tempdir().unwrap();
+    let store = Store::new_with_paths(
+        Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap(),
+        Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap(),
+    );
+    let data = |dir: &str, id: &str, bytes: &[u8]| {
+        let path = store.project_root().join(dir).join(id).join("data");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, bytes).unwrap();
+        path.parent().unwrap().to_string()
+    };
+    for (dataset_type, id, resolved, pinned) in [
+        ("protein", "1LYZ", data("proteins", "1LYZ", b"cif!"), false),
+        ("protein", "2PTC", data("proteins", "2PTC", b"cif!"), true),
+        (
+            "srr",
+            "SRR014966",
+            data("srr", "SRR014966", b"ACGTACGTAC"),
+            false,
+        ),
+        (
+            "doi",
+            "10.1000/xyz",
+            data("doi", "10.1000_xyz", b"{}"),
+            false,
+        ),
+    ] {
+        Store::write_metadata(
+            &store.project_metadata_path(dataset_type, id),
+            &Metadata {
+                source: "test".to_string(),
+                dataset_type: dataset_type.to_string(),
+                id: id.to_string(),
+                format: None,
+                downloaded_at: "2024-01-01T00:00:00+00:00".to_string(),
+                tool: "kira-bm".to_string(),
+                resolved_path: resolved,
+                status: None,
+                expiry: None,
+                provenance: None,
+                materialization: Materialization::Full,
+                assembly_name: None,
+                download: None,
+                converted_formats: Vec::new(),
+                formats: BTreeMap::new(),
+                alias: None,
+                pinned,
+                imported_from: None,
+                experiment: None,
+                cache: None,
+                acquisition: None,
+            },
+        )
+        .unwrap();
+    }
+    let app = App::new(
+        store.clone(),
+        MockNcbi,
+        MockRcsb::default(),
+        MockSrr,
+        MockUniprot,
+        MockGeo,
+        MockKnowledge,
+    );
+    let root = store.project_root().as_std_path().to_path_buf();
+    let options = |types: &[&str]| ClearOptions {
+        types: types.iter().map(|kind| kind.to_string()).collect(),
+        ..ClearOptions::default()
+    };
+
+    let preview = app
+        .clear(
+            &ClearOptions {
+                dry_run: true,
+                ..options(&["protein", "srr"])
+            },
+            &JsonOutput,
+        )
+        .unwrap();
+    assert!(preview.dry_run && !preview.cleared);
+    assert_eq!((preview.datasets, preview.bytes), (2, 14));
+    let types = preview
+        .types
+        .iter()
+        .map(|kind| (kind.dataset_type.as_str(), kind.datasets, kind.bytes))
+        .collect::<Vec<_>>();
+    assert_eq!(types, [("protein", 1, 4), ("srr", 1, 10)]);
+    assert_eq!(preview.skipped_pinned, ["protein:2PTC"]);
+    assert!(root.join("proteins/1LYZ/data").exists());
+
+    app.clear(
+        &ClearOptions {
+            keep_metadata: true,
+            ..options(&["protein"])
+        },
+        &JsonOutput,
+    )
+    .unwrap();
+    assert!(!root.join("proteins/1LYZ").exists());
+    assert!(store.project_metadata_path("protein", "1LYZ").exists());
+    assert!(root.join("proteins/2PTC/data").exists());
+    assert!(root.join("srr/SRR014966/data").exists());
+
+    let cleared = app.clear(&options(&["SRR"]), &JsonOutput).unwrap();
+    assert_eq!(cleared.datasets, 1);
+    assert!(!root.join("srr/SRR014966").exists());
+    assert!(!store.project_metadata_path("srr", "SRR014966").exists());
+    assert!(root.join("doi/10.1000_xyz/data").exists());
+    assert_eq!(Store::list_metadata(store.project_root()).unwrap().len(), 3);
+
+    let err = app.clear(&options(&["fastq"]), &JsonOutput).unwrap_err();
+    assert_matches!(err, KiraError::InvalidFormat(message) if message.contains("fastq"));
+}
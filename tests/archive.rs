@@ -0,0 +1,181 @@
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::fs_util::{ZipLimits, extract_zip, sha256_file};
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+/// A zip of `(name, contents)` files, written to `dir/name.zip`.
+fn write_zip(dir: &Path, name: &str, files: &[(&str, &[u8])]) -> std::path::PathBuf {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+    for (entry, contents) in files {
+        zip.start_file(*entry, options).unwrap();
+        zip.write_all(contents).unwrap();
+    }
+    let path = dir.join(format!("{name}.zip"));
+    std::fs::write(&path, zip.finish().unwrap().into_inner()).unwrap();
+    path
+}
+
+fn unsafe_entry(err: KiraError) -> (String, String) {
+    match err {
+        KiraError::UnsafeArchiveEntry { entry, reason, .. } => (entry, reason),
+        other => panic!("expected an unsafe entry, got {other:?}"),
+    }
+}
+
+#[test]
+fn a_zip_unpacks_its_files_and_directories_under_the_target() {
+    let temp = tempfile::tempdir().unwrap();
+    let zip = write_zip(
+        temp.path(),
+        "package",
+        &[
+            ("ncbi_dataset/data/genomic.fna", b">chr1\nACGT\n"),
+            ("ncbi_dataset/./data/../README.md", b"read me\n"),
+        ],
+    );
+    let target = temp.path().join("out");
+    let digests = extract_zip(&zip, &target, &ZipLimits::default()).unwrap();
+    assert_eq!(
+        std::fs::read(target.join("ncbi_dataset/data/genomic.fna")).unwrap(),
+        b">chr1\nACGT\n"
+    );
+    assert!(target.join("ncbi_dataset/README.md").exists());
+    // Keyed the way a download record names the files.
+    assert_eq!(
+        digests.keys().collect::<Vec<_>>(),
+        ["ncbi_dataset/README.md", "ncbi_dataset/data/genomic.fna"]
+    );
+    assert_eq!(
+        digests["ncbi_dataset/data/genomic.fna"],
+        sha256_file(&target.join("ncbi_dataset/data/genomic.fna")).unwrap()
+    );
+}
+
+#[test]
+fn an_entry_climbing_out_of_the_target_is_refused_before_anything_is_written() {
+    let temp = tempfile::tempdir().unwrap();
+    let zip = write_zip(
+        temp.path(),
+        "traversal",
+        &[
+            ("a/harmless.txt", b"fine"),
+            ("a/../../escaped.txt", b"gotcha"),
+        ],
+    );
+    let target = temp.path().join("out");
+    let err = extract_zip(&zip, &target, &ZipLimits::default()).unwrap_err();
+    let (entry, reason) = unsafe_entry(err);
+    assert_eq!(entry, "a/../../escaped.txt");
+    assert!(reason.contains("escapes"), "{reason}");
+    assert!(!temp.path().join("escaped.txt").exists());
+    assert!(!target.join("a/harmless.txt").exists());
+}
+
+#[test]
+fn absolute_entry_names_are_refused() {
+    for name in ["/tmp/kira-bm-absolute.txt", "C:\\kira-bm\\absolute.txt"] {
+        let temp = tempfile::tempdir().unwrap();
+        let zip = write_zip(temp.path(), "absolute", &[(name, b"gotcha")]);
+        let err = extract_zip(&zip, &temp.path().join("out"), &ZipLimits::default()).unwrap_err();
+        let (entry, reason) = unsafe_entry(err);
+        assert_eq!(entry, name);
+        assert_eq!(reason, "absolute path");
+    }
+}
+
+#[test]
+fn symlink_entries_are_refused() {
+    let temp = tempfile::tempdir().unwrap();
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    zip.add_symlink("data/passwd", "/etc/passwd", SimpleFileOptions::default())
+        .unwrap();
+    let path = temp.path().join("symlink.zip");
+    std::fs::write(&path, zip.finish().unwrap().into_inner()).unwrap();
+
+    let target = temp.path().join("out");
+    let err = extract_zip(&path, &target, &ZipLimits::default()).unwrap_err();
+    let (entry, reason) = unsafe_entry(err);
+    assert_eq!(entry, "data/passwd");
+    assert_eq!(reason, "symbolic link");
+    assert!(!target.join("data/passwd").exists());
+}
+
+#[test]
+fn archives_with_more_entries_than_the_limit_are_refused() {
+    let temp = tempfile::tempdir().unwrap();
+    let zip = write_zip(
+        temp.path(),
+        "many",
+        &[("a.txt", b"a"), ("b.txt", b"b"), ("c.txt", b"c")],
+    );
+    let limits = ZipLimits {
+        max_entries: 2,
+        ..ZipLimits::default()
+    };
+    let err = extract_zip(&zip, &temp.path().join("out"), &limits).unwrap_err();
+    assert!(
+        matches!(&err, KiraError::ArchiveTooLarge { message, .. } if message.contains("3 entries")),
+        "{err:?}"
+    );
+    assert!(err.to_string().contains("`archives`"), "{err}");
+
+    let limits = ZipLimits {
+        max_entries: 3,
+        ..ZipLimits::default()
+    };
+    extract_zip(&zip, &temp.path().join("out"), &limits).unwrap();
+}
+
+#[test]
+fn archives_unpacking_past_the_size_limit_are_refused() {
+    let temp = tempfile::tempdir().unwrap();
+    // Compresses to a few hundred bytes.
+    let bomb = vec![0u8; 1 << 20];
+    let zip = write_zip(temp.path(), "bomb", &[("zeros.bin", &bomb)]);
+    assert!(std::fs::metadata(&zip).unwrap().len() < 10_000);
+
+    let limits = ZipLimits {
+        max_unpacked_bytes: 1 << 19,
+        ..ZipLimits::default()
+    };
+    let target = temp.path().join("out");
+    let err = extract_zip(&zip, &target, &limits).unwrap_err();
+    assert!(
+        matches!(&err, KiraError::ArchiveTooLarge { message, .. } if message.contains("declares")),
+        "{err:?}"
+    );
+    assert!(!target.join("zeros.bin").exists());
+}
+
+#[test]
+fn an_entry_understating_its_size_is_stopped_at_the_limit() {
+    let temp = tempfile::tempdir().unwrap();
+    let bomb = vec![0u8; 1 << 20];
+    let zip = write_zip(temp.path(), "liar", &[("zeros.bin", &bomb)]);
+    // Claim 16 bytes unpacked in both the local and the central header.
+    let mut bytes = std::fs::read(&zip).unwrap();
+    for (signature, offset) in [(b"PK\x03\x04", 22), (b"PK\x01\x02", 24)] {
+        let at = bytes
+            .windows(4)
+            .position(|window| window == signature)
+            .unwrap();
+        bytes[at + offset..at + offset + 4].copy_from_slice(&16u32.to_le_bytes());
+    }
+    std::fs::write(&zip, bytes).unwrap();
+
+    let limits = ZipLimits {
+        max_unpacked_bytes: 1 << 10,
+        ..ZipLimits::default()
+    };
+    let target = temp.path().join("out");
+    let err = extract_zip(&zip, &target, &limits).unwrap_err();
+    assert!(
+        matches!(&err, KiraError::ArchiveTooLarge { message, .. } if message.contains("unpacks to more")),
+        "{err:?}"
+    );
+    assert!(!target.join("zeros.bin").exists());
+}
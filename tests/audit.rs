@@ -0,0 +1,153 @@
+mod support;
+
+use std::path::Path;
+
+use camino::Utf8PathBuf;
+
+use kira_biodata_manager::app::{App, ClearOptions, FetchOptions, FetchOverrides, ProgressSink};
+use kira_biodata_manager::audit::{self, AUDIT_FILE};
+use kira_biodata_manager::domain::{DatasetSpecifier, ProteinFormat, ProteinId};
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::output::JsonOutput;
+use kira_biodata_manager::rcsb::{RcsbClient, RcsbMetadata};
+use kira_biodata_manager::store::{Metadata, Store};
+
+use support::{UnusedGeo, UnusedKnowledge, UnusedNcbi, UnusedSrr, UnusedUniprot};
+
+struct FileRcsb;
+
+impl RcsbClient for FileRcsb {
+    fn download_structure(
+        &self,
+        _id: &ProteinId,
+        _format: ProteinFormat,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<(), KiraError> {
+        std::fs::write(destination, b"data_mock\nloop_\n_atom_site.id\n1\n")
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+
+    fn fetch_metadata(&self, id: &ProteinId) -> Result<RcsbMetadata, KiraError> {
+        Ok(RcsbMetadata {
+            registry: "rcsb".to_string(),
+            pdb_id: id.as_str().to_string(),
+            title: None,
+            experimental_method: None,
+            resolution: None,
+            deposition_date: None,
+            release_date: None,
+            source_structure_url: String::new(),
+            source_metadata_url: String::new(),
+            raw_json: serde_json::json!({}),
+        })
+    }
+}
+
+fn store(temp: &tempfile::TempDir) -> Store {
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    Store::new_with_paths(root.join("project"), root.join("cache"))
+}
+
+fn fetch_protein(store: &Store, force: bool) {
+    let app = App::new(
+        store.clone(),
+        UnusedNcbi,
+        FileRcsb,
+        UnusedSrr,
+        UnusedUniprot,
+        UnusedGeo,
+        UnusedKnowledge,
+    );
+    app.fetch(
+        Some(DatasetSpecifier::Protein("1LYZ".parse().unwrap())),
+        None,
+        FetchOverrides::default(),
+        FetchOptions::builder().force(force).build(),
+        &JsonOutput,
+    )
+    .unwrap();
+}
+
+#[test]
+fn clear_records_datasets_and_keeps_log() {
+    let temp = tempfile::tempdir().unwrap();
+    let store = store(&temp);
+    fetch_protein(&store, false);
+
+    store.clear_project(&ClearOptions::default()).unwrap();
+    store.clear_project(&ClearOptions::default()).unwrap();
+
+    assert!(store.project_root().join(AUDIT_FILE).exists());
+    let records = audit::read(store.project_root(), None).unwrap();
+    assert_eq!(records.len(), 2);
+    let clear = &records[0];
+    assert_eq!(clear.operation, "clear");
+    assert!(!clear.user.is_empty());
+    assert!(!clear.timestamp.is_empty());
+    assert_eq!(clear.targets.len(), 1);
+    let target = &clear.targets[0];
+    assert_eq!(target.specifier, "protein:1LYZ");
+    assert_eq!(target.bytes, 32);
+    assert!(!target.downloaded_at.is_empty());
+    assert_eq!(
+        target.undo.as_deref(),
+        Some("re-fetchable via `kira-bm fetch protein:1LYZ`")
+    );
+    assert!(records[1].targets.is_empty());
+    assert_eq!(audit::read(store.project_root(), Some(1)).unwrap().len(), 1);
+}
+
+#[test]
+fn remove_records_every_dropped_entry() {
+    let temp = tempfile::tempdir().unwrap();
+    let store = store(&temp);
+    fetch_protein(&store, false);
+    let entry: Metadata = Store::list_metadata(store.project_root())
+        .unwrap()
+        .remove(0);
+
+    store.remove_project_dataset(&entry, false).unwrap();
+
+    let records = audit::read(store.project_root(), None).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].operation, "remove");
+    assert_eq!(records[0].targets[0].specifier, "protein:1LYZ");
+    assert_eq!(records[0].targets[0].path, entry.resolved_path);
+}
+
+#[test]
+fn forced_fetch_records_replaced_copies() {
+    let temp = tempfile::tempdir().unwrap();
+    let store = store(&temp);
+    fetch_protein(&store, false);
+    fetch_protein(&store, false);
+    assert!(audit::read(store.project_root(), None).unwrap().is_empty());
+
+    fetch_protein(&store, true);
+
+    for root in [store.project_root(), store.cache_root()] {
+        let records = audit::read(root, None).unwrap();
+        assert_eq!(records.len(), 1, "{root}");
+        assert_eq!(records[0].operation, "force-replace");
+        assert_eq!(records[0].flags, vec!["--force"]);
+        assert_eq!(records[0].targets[0].specifier, "protein:1LYZ");
+        assert!(records[0].targets[0].undo.is_none());
+    }
+}
+
+#[test]
+fn torn_lines_are_skipped() {
+    let temp = tempfile::tempdir().unwrap();
+    let store = store(&temp);
+    fetch_protein(&store, false);
+    store.clear_project(&ClearOptions::default()).unwrap();
+    let path = store.project_root().join(AUDIT_FILE);
+    let mut text = std::fs::read_to_string(&path).unwrap();
+    text.push_str("{\"timestamp\": \"2024-");
+    std::fs::write(&path, text).unwrap();
+    store.clear_project(&ClearOptions::default()).unwrap();
+
+    let records = audit::read(store.project_root(), None).unwrap();
+    assert_eq!(records.len(), 2);
+}
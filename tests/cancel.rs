@@ -0,0 +1,232 @@
+#![cfg(unix)]
+
+mod support;
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use assert_matches::assert_matches;
+use camino::Utf8PathBuf;
+
+use kira_biodata_manager::app::{App, FetchOptions, FetchOverrides};
+use kira_biodata_manager::cancel::CancellationToken;
+use kira_biodata_manager::domain::DatasetSpecifier;
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::geo::{GeoClient, GeoHttpClient};
+use kira_biodata_manager::http::HttpSettings;
+use kira_biodata_manager::output::JsonOutput;
+use kira_biodata_manager::srr::{SrrClient, SrrDownloadOptions, SystemSrrClient};
+use kira_biodata_manager::store::Store;
+
+use support::{UnusedGeo, UnusedKnowledge, UnusedNcbi, UnusedRcsb, UnusedUniprot};
+
+/// Cancellation has to land well within this, not after the tool finishes.
+const BOUND: Duration = Duration::from_secs(5);
+
+/// A `prefetch` that leaves a partial download behind, starts a grandchild
+/// and waits on it; both pids are written next to the script.
+fn hanging_prefetch(dir: &Path) -> PathBuf {
+    let script = dir.join("prefetch");
+    std::fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\n\
+             echo partial > \"$3/$1.sra.tmp\"\n\
+             echo $$ > {dir}/tool.pid\n\
+             sleep 30 &\n\
+             echo $! > {dir}/grandchild.pid\n\
+             wait\n",
+            dir = dir.display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
+fn read_pid(path: &Path) -> u32 {
+    let deadline = Instant::now() + BOUND;
+    loop {
+        if let Some(pid) = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| text.trim().parse().ok())
+        {
+            return pid;
+        }
+        assert!(
+            Instant::now() < deadline,
+            "{} never appeared",
+            path.display()
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Running and not a zombie; killed processes may wait a moment for reaping.
+fn is_alive(pid: u32) -> bool {
+    std::fs::read_to_string(format!("/proc/{pid}/stat"))
+        .ok()
+        .and_then(|stat| {
+            let state = stat.rsplit_once(')')?.1.trim_start().chars().next()?;
+            Some(state != 'Z')
+        })
+        .unwrap_or(false)
+}
+
+fn assert_gone(pid: u32) {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while is_alive(pid) {
+        assert!(
+            Instant::now() < deadline,
+            "process {pid} outlived the cancellation"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn cancelling_kills_the_tool_and_its_children() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = temp.path().join("tools");
+    std::fs::create_dir_all(&tools).unwrap();
+    let client = SystemSrrClient::with_tools(None, Some(hanging_prefetch(&tools)), None);
+    let destination = temp.path().join("staging");
+    let token = CancellationToken::new();
+
+    let canceller = {
+        let token = token.clone();
+        let pid_file = tools.join("grandchild.pid");
+        std::thread::spawn(move || {
+            read_pid(&pid_file);
+            token.cancel();
+        })
+    };
+    let started = Instant::now();
+    let result = {
+        let _scope = token.enter();
+        client.download_fastq(
+            &"SRR000001".parse().unwrap(),
+            false,
+            &destination,
+            &SrrDownloadOptions::default(),
+        )
+    };
+    canceller.join().unwrap();
+
+    assert_matches!(result, Err(KiraError::Cancelled));
+    assert!(started.elapsed() < BOUND);
+    assert_gone(read_pid(&tools.join("tool.pid")));
+    assert_gone(read_pid(&tools.join("grandchild.pid")));
+    assert!(!destination.exists());
+}
+
+#[test]
+fn fetch_deadline_stops_the_tool_without_store_writes() {
+    let temp = tempfile::tempdir().unwrap();
+    let tools = temp.path().join("tools");
+    std::fs::create_dir_all(&tools).unwrap();
+    let fasterq = tools.join("fasterq-dump");
+    std::fs::write(&fasterq, "#!/bin/sh\nexit 1\n").unwrap();
+    let srr = SystemSrrClient::with_tools(None, Some(hanging_prefetch(&tools)), Some(fasterq));
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    let store = Store::new_with_paths(root.join("project"), root.join("cache"));
+    let app = App::new(
+        store.clone(),
+        UnusedNcbi,
+        UnusedRcsb,
+        srr,
+        UnusedUniprot,
+        UnusedGeo,
+        UnusedKnowledge,
+    );
+
+    let started = Instant::now();
+    let err = app
+        .fetch(
+            Some("srr:SRR000001".parse::<DatasetSpecifier>().unwrap()),
+            None,
+            FetchOverrides::default(),
+            FetchOptions::builder().deadline_secs(Some(1)).build(),
+            &JsonOutput,
+        )
+        .unwrap_err();
+
+    assert_matches!(err.root(), KiraError::Cancelled);
+    assert!(started.elapsed() < BOUND);
+    assert_gone(read_pid(&tools.join("grandchild.pid")));
+    let srr_id = "SRR000001".parse().unwrap();
+    assert!(!store.project_srr_dir(&srr_id).exists());
+    assert!(!store.cache_srr_dir(&srr_id).exists());
+    let leftovers = std::fs::read_dir(store.project_root())
+        .unwrap()
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("kira-bm-srr"))
+        .collect::<Vec<_>>();
+    assert!(leftovers.is_empty(), "staging left behind: {leftovers:?}");
+}
+
+/// Serves a large body a kilobyte at a time until the client hangs up.
+fn slow_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0u8; 1024];
+        let _ = stream.read(&mut request);
+        let _ = stream.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 104857600\r\n\r\n",
+        );
+        let chunk = [b'A'; 1024];
+        while stream.write_all(&chunk).is_ok() {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    });
+    format!("http://{address}/GSE1_RAW.tar")
+}
+
+#[test]
+fn cancelling_interrupts_a_streaming_download() {
+    let temp = tempfile::tempdir().unwrap();
+    let destination = temp.path().join("GSE1_RAW.tar");
+    let client = GeoHttpClient::with_settings(HttpSettings::new(None)).unwrap();
+    let url = slow_server();
+    let token = CancellationToken::new();
+
+    let canceller = {
+        let token = token.clone();
+        let destination = destination.clone();
+        std::thread::spawn(move || {
+            while std::fs::metadata(&destination).map_or(true, |meta| meta.len() == 0) {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            token.cancel();
+        })
+    };
+    let started = Instant::now();
+    let result = {
+        let _scope = token.enter();
+        client.download_url(&url, &destination, &JsonOutput)
+    };
+    canceller.join().unwrap();
+
+    assert_matches!(result, Err(KiraError::Cancelled));
+    assert!(started.elapsed() < BOUND);
+    assert!(!destination.exists());
+}
+
+#[test]
+fn deadline_fires_the_cancelled_channel() {
+    let token = CancellationToken::new();
+    let cancelled = token.cancelled_channel();
+    token.set_deadline(Instant::now() + Duration::from_millis(50));
+    assert!(!token.is_cancelled());
+    cancelled.recv_timeout(BOUND).unwrap();
+    assert!(token.is_cancelled());
+    assert_matches!(token.clone().check(), Err(KiraError::Cancelled));
+    // A fired token answers new waiters straight away.
+    token.cancelled_channel().try_recv().unwrap();
+}
@@ -0,0 +1,170 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::Value;
+
+/// `kira-bm` run in `dir`, with the user config and the cache kept there too.
+fn kira_bm(dir: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_kira-bm"));
+    command
+        .current_dir(dir)
+        .env("HOME", dir)
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .env("XDG_CACHE_HOME", dir.join("cache"))
+        .env("KIRA_BM_CACHE_DIR", dir.join("cache"));
+    command
+}
+
+/// The JSON error `--non-interactive` prints last on stderr.
+fn error_report(output: &Output) -> Value {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let start = stderr.rfind("{\n").expect("no JSON error on stderr");
+    serde_json::from_str(&stderr[start..]).unwrap()
+}
+
+/// A local address nothing listens on.
+fn unreachable_base() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+    format!("http://{address}")
+}
+
+#[test]
+fn failed_fetch_reports_its_context_and_exits_3() {
+    let temp = tempfile::tempdir().unwrap();
+    let output = kira_bm(temp.path())
+        .env("KIRA_BM_RCSB_BASE", unreachable_base())
+        .args(["--non-interactive", "fetch", "protein:1LYZ"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    let report = error_report(&output);
+    assert_eq!(report["specifier"], "protein:1LYZ");
+    assert_eq!(report["phase"], "Fetch");
+    assert!(
+        report["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("RCSB request failed")
+    );
+
+    // Progress before the error is NDJSON throughout.
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let progress = &stderr[..stderr.rfind("{\n").unwrap()];
+    let events = progress
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert!(events.iter().any(|event| event["event"] == "item.failed"));
+}
+
+/// Answers every request with 403 and an empty body.
+fn forbidding_base() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { break };
+            let mut request = [0u8; 4096];
+            let _ = stream.read(&mut request);
+            let head = "HTTP/1.1 403 Forbidden\r\ncontent-length: 0\r\n\r\n";
+            let _ = stream.write_all(head.as_bytes());
+        }
+    });
+    format!("http://{address}")
+}
+
+#[test]
+fn failed_geo_requests_exit_3() {
+    for (base, error) in [
+        (unreachable_base(), "GEO request failed"),
+        (forbidding_base(), "GEO returned status 403"),
+    ] {
+        let temp = tempfile::tempdir().unwrap();
+        let output = kira_bm(temp.path())
+            .env("KIRA_BM_GEO_BASE", base)
+            .args(["--non-interactive", "fetch", "expression:GSE102902"])
+            .output()
+            .unwrap();
+
+        assert_eq!(output.status.code(), Some(3));
+        let report = error_report(&output);
+        assert!(
+            report["error"].as_str().unwrap().starts_with(error),
+            "{report}"
+        );
+    }
+}
+
+#[test]
+fn info_pattern_without_matches_exits_2() {
+    let temp = tempfile::tempdir().unwrap();
+    let output = kira_bm(temp.path())
+        .args(["--non-interactive", "info", "protein:*"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        error_report(&output)["error"],
+        "no dataset in the project store matches protein:*"
+    );
+}
+
+#[test]
+fn path_of_a_missing_dataset_exits_2() {
+    let temp = tempfile::tempdir().unwrap();
+    let output = kira_bm(temp.path())
+        .args(["path", "protein:9ZZZ"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+}
+
+/// Answers one request with a body that trickles in ten bytes at a time,
+/// so a download is still running when the test interrupts it.
+fn trickling_base() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0u8; 4096];
+        let _ = stream.read(&mut request);
+        let head = "HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: 1000000\r\n\r\n";
+        if stream.write_all(head.as_bytes()).is_err() {
+            return;
+        }
+        while stream.write_all(&[b'x'; 10]).is_ok() {
+            thread::sleep(Duration::from_millis(50));
+        }
+    });
+    format!("http://{address}")
+}
+
+#[cfg(unix)]
+#[test]
+fn interrupted_headless_fetch_exits_130() {
+    let temp = tempfile::tempdir().unwrap();
+    let child = kira_bm(temp.path())
+        .env("KIRA_BM_RCSB_BASE", trickling_base())
+        .args(["--non-interactive", "fetch", "protein:1LYZ"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    thread::sleep(Duration::from_secs(1));
+    // SAFETY: the pid is our own child, which has not been waited on yet.
+    unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGINT) };
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!(output.status.code(), Some(130));
+    assert_eq!(error_report(&output)["specifier"], "protein:1LYZ");
+}
@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+
+use camino::Utf8PathBuf;
+use clap::{Arg, Command};
+use clap_complete::Shell;
+
+use kira_biodata_manager::completion::{script, specifiers};
+use kira_biodata_manager::store::{Materialization, Metadata, Store};
+
+fn seed(store: &Store, dataset_type: &str, id: &str) {
+    Store::write_metadata(
+        &store.project_metadata_path(dataset_type, id),
+        &Metadata {
+            source: "test".to_string(),
+            dataset_type: dataset_type.to_string(),
+            id: id.to_string(),
+            format: None,
+            downloaded_at: "2024-01-01T00:00:00Z".to_string(),
+            tool: "kira-bm".to_string(),
+            resolved_path: store.project_root().join(dataset_type).join(id).to_string(),
+            status: None,
+            expiry: None,
+            provenance: None,
+            materialization: Materialization::Full,
+            assembly_name: None,
+            download: None,
+            converted_formats: Vec::new(),
+            formats: BTreeMap::new(),
+            alias: None,
+            pinned: false,
+            imported_from: None,
+            experiment: None,
+            cache: None,
+            acquisition: None,
+        },
+    )
+    .unwrap();
+}
+
+fn populated_store(temp: &tempfile::TempDir) -> Store {
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    let store = Store::new_with_paths(root.join("project"), root.join("cache"));
+    seed(&store, "protein", "1LYZ");
+    seed(&store, "protein", "4HHB");
+    seed(&store, "srr", "SRR014966");
+    seed(&store, "kegg", "kegg");
+    store
+}
+
+#[test]
+fn partial_type_completes_to_stored_ids() {
+    let temp = tempfile::tempdir().unwrap();
+    let store = populated_store(&temp);
+
+    assert_eq!(
+        specifiers(&store, "prot"),
+        vec!["protein:1LYZ", "protein:4HHB"]
+    );
+    assert_eq!(specifiers(&store, "protein:4"), vec!["protein:4HHB"]);
+    assert_eq!(specifiers(&store, "SR"), vec!["srx:", "srr:SRR014966"]);
+    assert_eq!(specifiers(&store, "gen"), vec!["genome:"]);
+    assert_eq!(specifiers(&store, "k"), vec!["kegg"]);
+    assert!(specifiers(&store, "protein:9").is_empty());
+}
+
+#[test]
+fn empty_partial_lists_prefixes_then_stored_datasets() {
+    let temp = tempfile::tempdir().unwrap();
+    let store = populated_store(&temp);
+
+    assert_eq!(
+        specifiers(&store, ""),
+        vec![
+            "genome:",
+            "srx:",
+            "uniprot:",
+            "doi:",
+            "bioproject:",
+            "expression:",
+            "expression10x:",
+            "expressionsample:",
+            "structure-for:",
+            "go",
+            "reactome",
+            "alias:",
+            "kegg",
+            "protein:1LYZ",
+            "protein:4HHB",
+            "srr:SRR014966",
+        ]
+    );
+
+    let root = Utf8PathBuf::from_path_buf(temp.path().join("empty")).unwrap();
+    let empty = Store::new_with_paths(root.join("project"), root.join("cache"));
+    assert_eq!(specifiers(&empty, "p"), vec!["protein:"]);
+}
+
+#[test]
+fn scripts_route_the_specifier_to_the_helper() {
+    let command = || {
+        Command::new("kira-bm")
+            .subcommand(Command::new("fetch").arg(Arg::new("specifier")))
+            .subcommand(Command::new("info").arg(Arg::new("specifier").required(true)))
+    };
+
+    let bash = script(Shell::Bash, &mut command());
+    assert!(bash.contains("kira-bm __complete-specifiers \"$cur\""));
+    assert!(bash.contains("complete -F _kira_bm_specifiers "));
+
+    let zsh = script(Shell::Zsh, &mut command());
+    assert!(zsh.contains("'::specifier:_kira_bm_specifiers'"));
+    assert!(zsh.contains("':specifier:_kira_bm_specifiers'"));
+    assert!(zsh.find("_kira_bm_specifiers() {") < zsh.find("compdef _kira-bm kira-bm"));
+
+    let fish = script(Shell::Fish, &mut command());
+    assert!(fish.contains("-a \"(kira-bm __complete-specifiers (commandline -ct))\""));
+}
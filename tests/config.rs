@@ -1,8 +1,14 @@
 use kira_biodata_manager::config::{
-    Config, ConfigLoader, DoiEntry, GenomeEntry, ProteinEntry, SrrEntry, UniprotEntry,
-    default_genome_include,
+    BioProjectEntry, CacheConfig, CacheDecision, CachePolicy, CacheSource, Config, ConfigLoader,
+    ConfigSection, DefaultsConfig, DoiEntry, FetchDefaults, GenomeEntry, GlobalConfig,
+    ProteinEntry, ProteomeEntry, SettingOrigin, SrrEntry, UniprotEntry, default_genome_include,
+    read_id_list, write_config_atomic,
 };
-use kira_biodata_manager::domain::{Doi, ProteinFormat, SrrFormat, UniprotId};
+use kira_biodata_manager::domain::{DatasetSpecifier, Doi, ProteinFormat, SrrFormat, UniprotId};
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::geo::FileCategory;
+use kira_biodata_manager::io_util;
+use kira_biodata_manager::knowledge::GoFlavor;
 use std::str::FromStr;
 
 #[test]
@@ -14,6 +20,21 @@ fn parse_config_shorthand() {
         srr: vec![SrrEntry::Shorthand("SRR014966".to_string())],
         uniprot: vec![UniprotEntry::Shorthand("P69905".to_string())],
         doi: vec![DoiEntry::Shorthand("10.1038/s41586-020-2649-2".to_string())],
+        bioprojects: vec![BioProjectEntry::Shorthand("PRJNA680067".to_string())],
+        proteomes: vec![ProteomeEntry::Shorthand("up000005640".to_string())],
+        include_categories: Vec::new(),
+        ncbi_api_key: None,
+        proxy: None,
+        ca_bundle: None,
+        exclude: Vec::new(),
+        retention: None,
+        other: serde_json::Map::new(),
+        go: None,
+        runs: None,
+        timeouts: None,
+        cache: None,
+        defaults: None,
+        write_readme: None,
     };
 
     let resolved = ConfigLoader::resolve_config(config).unwrap();
@@ -23,6 +44,8 @@ fn parse_config_shorthand() {
     assert_eq!(resolved.srr.len(), 1);
     assert_eq!(resolved.uniprot.len(), 1);
     assert_eq!(resolved.doi.len(), 1);
+    assert_eq!(resolved.bioprojects.len(), 1);
+    assert_eq!(resolved.proteomes[0].id.as_str(), "UP000005640");
     assert_eq!(resolved.proteins[0].format, ProteinFormat::Cif);
     assert_eq!(resolved.genomes[0].include, default_genome_include());
     assert_eq!(resolved.srr[0].format, SrrFormat::Fastq);
@@ -34,4 +57,602 @@ fn parse_config_shorthand() {
         resolved.doi[0].id,
         Doi::from_str("10.1038/s41586-020-2649-2").unwrap()
     );
+    assert_eq!(resolved.bioprojects[0].accession.as_str(), "PRJNA680067");
+}
+
+#[test]
+fn parse_config_bioprojects_from_json() {
+    let config: Config =
+        serde_json::from_str(r#"{"bioprojects": ["PRJEB4337", {"accession": "prjna680067"}]}"#)
+            .unwrap();
+    let resolved = ConfigLoader::resolve_config(config).unwrap();
+    let accessions = resolved
+        .bioprojects
+        .iter()
+        .map(|entry| entry.accession.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(accessions, vec!["PRJEB4337", "PRJNA680067"]);
+}
+
+#[test]
+fn parse_srr_tool_settings() {
+    let json = r#"{"srr": [{"id": "SRR014966", "threads": 8, "temp_dir": "/scratch/kira"}]}"#;
+    let config: Config = serde_json::from_str(json).unwrap();
+    let resolved = ConfigLoader::resolve_config(config).unwrap();
+    assert_eq!(resolved.srr[0].threads, Some(8));
+    assert_eq!(
+        resolved.srr[0].temp_dir.as_deref(),
+        Some(std::path::Path::new("/scratch/kira"))
+    );
+
+    let config: Config =
+        serde_json::from_str(r#"{"srr": [{"id": "SRR014966", "threads": 0}]}"#).unwrap();
+    let err = ConfigLoader::resolve_config(config).unwrap_err();
+    assert!(matches!(err, KiraError::ConfigParse(_)), "{err:?}");
+}
+
+#[test]
+fn parse_retention_section_and_keep_it_on_rewrite() {
+    let json = r#"{"retention": {"srr": "180d", "kb": "90d"}}"#;
+    let config: Config = serde_json::from_str(json).unwrap();
+    let section = config.retention.as_ref().unwrap();
+    assert_eq!(section.max_age.len(), 2);
+    let rewritten = serde_json::to_value(&config).unwrap();
+    assert_eq!(rewritten["retention"]["srr"], "180d");
+
+    let resolved = ConfigLoader::resolve_config(config).unwrap();
+    assert_eq!(
+        resolved.retention.limit_for("go").map(|(label, _)| label),
+        Some("kb=90d".to_string())
+    );
+
+    let invalid: Config = serde_json::from_str(r#"{"retention": {"srr": "soon"}}"#).unwrap();
+    assert!(matches!(
+        ConfigLoader::resolve_config(invalid),
+        Err(KiraError::ConfigParse(_))
+    ));
+}
+
+#[test]
+fn retention_pins_point_to_the_pin_command() {
+    let temp = tempfile::tempdir().unwrap();
+    let path = temp.path().join("kira-bm.json");
+    std::fs::write(
+        &path,
+        r#"{"retention": {"srr": "180d", "pin": ["srr:SRR014966"]}}"#,
+    )
+    .unwrap();
+
+    let Err(KiraError::InvalidConfig { errors, .. }) = ConfigLoader::load(&path) else {
+        panic!("retention.pin was accepted");
+    };
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("kira-bm pin"), "{errors:?}");
+}
+
+#[test]
+fn parse_go_section() {
+    let json = r#"{"go": {"flavor": "full", "annotations": ["human", "mouse"]}}"#;
+    let config: Config = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        serde_json::to_value(&config).unwrap()["go"]["flavor"],
+        "full"
+    );
+    let resolved = ConfigLoader::resolve_config(config).unwrap();
+    assert_eq!(resolved.go_flavor, Some(GoFlavor::Full));
+    let species = resolved
+        .go_annotations
+        .iter()
+        .map(|species| species.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(species, vec!["human", "mouse"]);
+
+    let invalid: Config = serde_json::from_str(r#"{"go": {"annotations": ["h/s"]}}"#).unwrap();
+    assert!(matches!(
+        ConfigLoader::resolve_config(invalid),
+        Err(KiraError::InvalidGoaSpecies(_))
+    ));
+}
+
+#[test]
+fn parse_timeouts_section() {
+    let json =
+        r#"{"timeouts": {"connect_secs": 5, "request_secs": 90, "NCBI": {"stall_secs": 300}}}"#;
+    let config: Config = serde_json::from_str(json).unwrap();
+    let resolved = ConfigLoader::resolve_config(config).unwrap();
+    assert_eq!(resolved.timeouts.connect_secs, Some(5));
+    assert_eq!(resolved.timeouts.request_secs, Some(90));
+    assert_eq!(resolved.registry_timeouts["ncbi"].stall_secs, Some(300));
+    assert_eq!(resolved.registry_timeouts["ncbi"].request_secs, None);
+
+    for invalid in [
+        r#"{"timeouts": {"figshare": {"request_secs": 10}}}"#,
+        r#"{"timeouts": {"stall_secs": 0}}"#,
+        r#"{"timeouts": {"rcsb": {"connect_secs": 0}}}"#,
+    ] {
+        let config: Config = serde_json::from_str(invalid).unwrap();
+        let err = ConfigLoader::resolve_config(config).unwrap_err();
+        assert!(matches!(err, KiraError::ConfigParse(_)), "{err:?}");
+    }
+}
+
+#[test]
+fn cache_section_resolves_by_type_then_kb_then_default() {
+    let json = r#"{"cache": {"SRR": false, "kb": false, "default": true}}"#;
+    let config: Config = serde_json::from_str(json).unwrap();
+    let project = ConfigLoader::resolve_config(config).unwrap().cache;
+    assert!(!project.caches("srr"));
+    assert!(!project.caches("kegg"));
+    assert!(project.caches("genome"));
+
+    let global = CachePolicy::from_config(&CacheConfig {
+        types: [("default".to_string(), false), ("go".to_string(), true)].into(),
+    })
+    .unwrap();
+    let policy = global.overlay(&project);
+    assert!(policy.caches("go"), "a type's own key outranks kb");
+    assert!(policy.caches("protein"), "the project's default wins");
+    assert_eq!(
+        policy.decision("srr", false),
+        CacheDecision {
+            enabled: false,
+            source: CacheSource::Config,
+        }
+    );
+    assert_eq!(
+        CachePolicy::default().decision("genome", true),
+        CacheDecision {
+            enabled: false,
+            source: CacheSource::Flag,
+        }
+    );
+
+    let invalid: Config = serde_json::from_str(r#"{"cache": {"figshare": false}}"#).unwrap();
+    let err = ConfigLoader::resolve_config(invalid).unwrap_err();
+    assert!(matches!(err, KiraError::ConfigParse(_)), "{err:?}");
+}
+
+#[test]
+fn defaults_layer_builtin_then_user_then_project_then_flag() {
+    let user: GlobalConfig = serde_json::from_str(
+        r#"{"defaults": {"protein_format": "pdb", "srr_format": "fasta", "srr_paired": true},
+            "cache": {"srr": false}}"#,
+    )
+    .unwrap();
+    let project: Config = serde_json::from_str(
+        r#"{"defaults": {"srr_paired": false, "genome_include": ["genome"]},
+            "include_categories": ["document"],
+            "cache": {"srr": true},
+            "proteins": ["1LYZ", {"id": "2LYZ", "format": "bcif"}],
+            "srr": ["SRR014966", {"id": "SRR014967", "paired": true}],
+            "genomes": ["GCF_000005845.2"]}"#,
+    )
+    .unwrap();
+
+    let builtin = ConfigLoader::resolve_config(Config::default()).unwrap();
+    assert_eq!(builtin.defaults, FetchDefaults::default());
+    assert_eq!(
+        builtin.defaults.genome_include.value,
+        default_genome_include()
+    );
+
+    let resolved = ConfigLoader::resolve_layered(project.clone(), user.defaults.as_ref()).unwrap();
+    let defaults = &resolved.defaults;
+    assert_eq!(defaults.protein_format.value, ProteinFormat::Pdb);
+    assert_eq!(defaults.protein_format.origin, SettingOrigin::User);
+    assert_eq!(defaults.srr_format.origin, SettingOrigin::User);
+    // An explicit false in the project outranks the user's true.
+    assert!(!defaults.srr_paired.value);
+    assert_eq!(defaults.srr_paired.origin, SettingOrigin::Project);
+    assert_eq!(
+        defaults.include_categories.value,
+        vec![FileCategory::Document]
+    );
+    assert_eq!(defaults.include_categories.origin, SettingOrigin::Project);
+    assert_eq!(resolved.include_categories, vec![FileCategory::Document]);
+
+    // Entries keep what they set themselves.
+    assert_eq!(resolved.proteins[0].format, ProteinFormat::Pdb);
+    assert_eq!(resolved.proteins[1].format, ProteinFormat::Bcif);
+    assert_eq!(resolved.srr[0].format, SrrFormat::Fasta);
+    assert!(!resolved.srr[0].paired);
+    assert!(resolved.srr[1].paired);
+    assert_eq!(resolved.genomes[0].include, vec!["genome".to_string()]);
+
+    // A flag left unset keeps the config's value; a given one wins.
+    let paired = defaults.srr_paired.clone();
+    assert_eq!(paired.clone().over(None, SettingOrigin::Flag), paired);
+    let flagged = paired.over(Some(true), SettingOrigin::Flag);
+    assert!(flagged.value);
+    assert_eq!(flagged.origin, SettingOrigin::Flag);
+    assert_eq!(flagged.configured(), Some(&true));
+    assert_eq!(FetchDefaults::default().srr_paired.configured(), None);
+
+    let shown = ConfigLoader::effective_settings(&user, Some(&project)).settings;
+    let origin = |key: &str| {
+        shown
+            .iter()
+            .find(|setting| setting.key == key)
+            .map(|setting| (setting.value.clone(), setting.origin))
+    };
+    assert_eq!(
+        origin("defaults.protein_format"),
+        Some(("pdb".into(), SettingOrigin::User))
+    );
+    assert_eq!(
+        origin("defaults.srr_paired"),
+        Some((false.into(), SettingOrigin::Project))
+    );
+    assert_eq!(
+        origin("defaults.genome_include"),
+        Some((serde_json::json!(["genome"]), SettingOrigin::Project))
+    );
+    assert_eq!(
+        origin("cache.srr"),
+        Some((true.into(), SettingOrigin::Project))
+    );
+    let user_only = ConfigLoader::effective_settings(&GlobalConfig::default(), None).settings;
+    assert!(
+        user_only
+            .iter()
+            .all(|setting| setting.origin == SettingOrigin::Default)
+    );
+}
+
+#[test]
+fn defaults_reject_options_they_do_not_know() {
+    let err = serde_json::from_str::<Config>(r#"{"defaults": {"jobs": 4}}"#).unwrap_err();
+    assert!(err.to_string().contains("unknown field `jobs`"), "{err}");
+    let defaults: DefaultsConfig = serde_json::from_str(r#"{"srr_paired": false}"#).unwrap();
+    assert_eq!(defaults.srr_paired, Some(false));
+}
+
+#[test]
+fn a_minimal_config_round_trips_unchanged() {
+    let temp = tempfile::tempdir().unwrap();
+    let path = temp.path().join("kira-bm.json");
+    let minimal = "{\n  \"proteins\": [\n    \"1LYZ\"\n  ],\n  \"srr\": []\n}\n";
+    std::fs::write(&path, minimal).unwrap();
+
+    let config = ConfigLoader::load(&path).unwrap();
+    write_config_atomic(&path, &config).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), minimal);
+
+    // Only the added entry shows up; no nulls or empty sections appear.
+    let mut config = ConfigLoader::load(&path).unwrap();
+    config
+        .add_entry(&"uniprot:P69905".parse::<DatasetSpecifier>().unwrap())
+        .unwrap();
+    write_config_atomic(&path, &config).unwrap();
+    let written: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(
+        written,
+        serde_json::json!({"proteins": ["1LYZ"], "srr": [], "uniprot": ["P69905"]})
+    );
+}
+
+#[test]
+fn merging_keeps_the_file_key_order() {
+    let temp = tempfile::tempdir().unwrap();
+    let path = temp.path().join("kira-bm.json");
+    std::fs::write(
+        &path,
+        r#"{"srr": [], "proteins": ["1LYZ"], "expression": ["GSE102902"], "defaults": {"srr_paired": true, "protein_format": "pdb"}}"#,
+    )
+    .unwrap();
+
+    let mut config = ConfigLoader::load(&path).unwrap();
+    config
+        .add_entry(&"uniprot:P69905".parse::<DatasetSpecifier>().unwrap())
+        .unwrap();
+    write_config_atomic(&path, &config).unwrap();
+
+    let written: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let keys = written.as_object().unwrap().keys().collect::<Vec<_>>();
+    assert_eq!(
+        keys,
+        ["srr", "proteins", "expression", "defaults", "uniprot"]
+    );
+    let defaults = written["defaults"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .collect::<Vec<_>>();
+    assert_eq!(defaults, ["srr_paired", "protein_format"]);
+}
+
+#[test]
+fn a_gzipped_config_is_written_back_gzipped() {
+    let temp = tempfile::tempdir().unwrap();
+    let path = temp.path().join("kira-bm.json.gz");
+    std::fs::write(&path, gzip(br#"{"srr": [], "proteins": ["1LYZ"]}"#)).unwrap();
+
+    let mut config = ConfigLoader::load(&path).unwrap();
+    config
+        .add_entry(&"uniprot:P69905".parse::<DatasetSpecifier>().unwrap())
+        .unwrap();
+    write_config_atomic(&path, &config).unwrap();
+
+    assert!(io_util::open_maybe_gzip(&path).unwrap().is_gzip());
+    let reloaded = ConfigLoader::load(&path).unwrap();
+    assert_eq!(reloaded.proteins.len(), 1);
+    assert_eq!(reloaded.uniprot.len(), 1);
+}
+
+#[test]
+fn edits_round_trip_and_keep_unknown_keys() {
+    let temp = tempfile::tempdir().unwrap();
+    let path = temp.path().join("kira-bm.json");
+    std::fs::write(
+        &path,
+        r#"{"proteins": [{"id": "4V6X", "format": "pdb"}], "expression": ["GSE102902"]}"#,
+    )
+    .unwrap();
+
+    let mut config = ConfigLoader::load(&path).unwrap();
+    let added = config
+        .add_entry(&"uniprot:P69905".parse::<DatasetSpecifier>().unwrap())
+        .unwrap();
+    assert_eq!(added, ConfigSection::Uniprot);
+    assert!(config.toggle_excluded(ConfigSection::Proteins, "4V6X"));
+    write_config_atomic(&path, &config).unwrap();
+
+    let reloaded = ConfigLoader::load(&path).unwrap();
+    assert_eq!(reloaded.entry_ids(ConfigSection::Uniprot), vec!["P69905"]);
+    assert_eq!(reloaded.exclude, vec!["protein:4V6X"]);
+    assert_eq!(
+        reloaded.other["expression"],
+        serde_json::json!(["GSE102902"])
+    );
+    assert!(matches!(
+        &reloaded.proteins[0],
+        ProteinEntry::Detailed(obj) if obj.format == Some(ProteinFormat::Pdb)
+    ));
+
+    let resolved = ConfigLoader::resolve_config(reloaded).unwrap();
+    assert!(resolved.proteins.is_empty());
+    assert_eq!(resolved.uniprot.len(), 1);
+}
+
+#[test]
+fn add_entry_rejects_duplicates_and_unlisted_types() {
+    let mut config = Config::default();
+    let genome = "genome:GCF_000005845.2"
+        .parse::<DatasetSpecifier>()
+        .unwrap();
+    config.add_entry(&genome).unwrap();
+    assert!(matches!(
+        config.add_entry(&genome),
+        Err(KiraError::ConfigEntry(_))
+    ));
+    assert!(matches!(
+        config.add_entry(&DatasetSpecifier::Go),
+        Err(KiraError::ConfigEntry(_))
+    ));
+
+    config.toggle_excluded(ConfigSection::Genomes, "GCF_000005845.2");
+    assert_eq!(
+        config.remove_entry(ConfigSection::Genomes, 0).as_deref(),
+        Some("genome:GCF_000005845.2")
+    );
+    assert!(config.genomes.is_empty());
+    assert!(config.exclude.is_empty());
+}
+
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(bytes).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn gzipped_config_is_detected_by_content() {
+    let temp = tempfile::tempdir().unwrap();
+    let json = br#"{"proteins": ["1LYZ"], "srr": ["SRR014966"]}"#;
+    // Compression is sniffed, so the name does not matter either way.
+    for name in ["kira-bm.json.gz", "kira-bm.json"] {
+        let path = temp.path().join(name);
+        std::fs::write(&path, gzip(json)).unwrap();
+        let resolved = ConfigLoader::resolve(path.to_str()).unwrap();
+        assert_eq!(resolved.proteins.len(), 1);
+        assert_eq!(resolved.srr[0].id.as_str(), "SRR014966");
+    }
+
+    let misleading = temp.path().join("plain.json.gz");
+    std::fs::write(&misleading, json).unwrap();
+    let config = ConfigLoader::load(&misleading).unwrap();
+    assert_eq!(config.proteins.len(), 1);
+}
+
+#[test]
+fn corrupt_gzip_and_malformed_content_are_told_apart() {
+    let temp = tempfile::tempdir().unwrap();
+    let json = br#"{"proteins": ["1LYZ"], "uniprot": ["P69905"], "srr": ["SRR014966"]}"#;
+
+    let truncated = temp.path().join("truncated.json.gz");
+    let compressed = gzip(json);
+    std::fs::write(&truncated, &compressed[..compressed.len() / 2]).unwrap();
+    let err = ConfigLoader::load(&truncated).unwrap_err();
+    assert!(matches!(err, KiraError::CorruptGzip { .. }), "{err:?}");
+    assert!(
+        err.to_string()
+            .contains("gzip-compressed but its stream is corrupt")
+    );
+
+    let malformed = temp.path().join("malformed.json.gz");
+    std::fs::write(&malformed, gzip(b"{\"proteins\": [")).unwrap();
+    let err = ConfigLoader::load(&malformed).unwrap_err();
+    assert!(matches!(err, KiraError::ConfigParse(_)), "{err:?}");
+    assert!(
+        err.to_string().contains("after gzip decompression"),
+        "{err}"
+    );
+
+    let plain = temp.path().join("malformed.json");
+    std::fs::write(&plain, b"{\"proteins\": [").unwrap();
+    let err = ConfigLoader::load(&plain).unwrap_err();
+    assert!(matches!(err, KiraError::ConfigParse(_)), "{err:?}");
+    assert!(!err.to_string().contains("gzip"), "{err}");
+}
+
+/// Hands out one byte per read, like a slow pipe.
+struct Trickle(std::io::Cursor<Vec<u8>>);
+
+impl std::io::Read for Trickle {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(1);
+        self.0.read(&mut buf[..len])
+    }
+}
+
+#[test]
+fn gzipped_manifest_streams_from_stdin() {
+    use std::io::BufRead;
+
+    let manifest = b"protein:1LYZ\nsrr:SRR014966\nuniprot:P69905\n";
+    for (input, gzip_expected) in [(gzip(manifest), true), (manifest.to_vec(), false)] {
+        let stdin = std::io::BufReader::with_capacity(1, Trickle(std::io::Cursor::new(input)));
+        let reader = io_util::maybe_gzip(stdin).unwrap();
+        assert_eq!(reader.is_gzip(), gzip_expected);
+        let specifiers = reader
+            .lines()
+            .map(|line| line.unwrap().parse::<DatasetSpecifier>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(specifiers.len(), 3);
+        assert_eq!(specifiers[1].to_string(), "srr:SRR014966");
+    }
+}
+
+#[test]
+fn id_list_reports_every_bad_line() {
+    let temp = tempfile::tempdir().unwrap();
+    let path = temp.path().join("ids.txt");
+    std::fs::write(
+        &path,
+        "SRR014966\n# comment\nnot-an-id\nGSE102902\nSRR12X\n",
+    )
+    .unwrap();
+
+    let err = read_id_list(&path).unwrap_err();
+    let KiraError::InvalidIdList { errors, .. } = &err else {
+        panic!("unexpected error: {err}");
+    };
+    assert_eq!(
+        errors,
+        &vec![
+            "line 3: invalid dataset specifier: not-an-id".to_string(),
+            "line 5: invalid SRR accession: SRR12X".to_string(),
+        ]
+    );
+    assert!(
+        err.to_string()
+            .contains("ids.txt has unparseable entries:\nline 3:")
+    );
+
+    assert_matches::assert_matches!(
+        read_id_list(&temp.path().join("missing.txt")).unwrap_err(),
+        KiraError::IdListRead(_)
+    );
+}
+
+#[test]
+fn aliases_are_validated_and_unique() {
+    let config: Config = serde_json::from_str(
+        r#"{
+            "genomes": [{"accession": "GCF_000005845.2", "alias": "ecoli_ref"}],
+            "proteins": ["1LYZ", {"id": "4HHB", "alias": "hemoglobin"}],
+            "srr": [{"id": "SRR014966", "alias": "run-1"}]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.aliases(),
+        vec![
+            ("protein:4HHB".to_string(), "hemoglobin".to_string()),
+            (
+                "genome:GCF_000005845.2".to_string(),
+                "ecoli_ref".to_string()
+            ),
+            ("srr:SRR014966".to_string(), "run-1".to_string()),
+        ]
+    );
+    let resolved = ConfigLoader::resolve_config(config).unwrap();
+    assert_eq!(
+        resolved.genomes[0].alias.as_ref().unwrap().as_str(),
+        "ecoli_ref"
+    );
+    assert!(resolved.proteins[0].alias.is_none());
+
+    let duplicated: Config = serde_json::from_str(
+        r#"{
+            "genomes": [{"accession": "GCF_000005845.2", "alias": "ref"}],
+            "uniprot": [{"id": "P69905", "alias": "ref"}],
+            "doi": [{"id": "10.1038/s41586-020-2649-2", "alias": "paper"}]
+        }"#,
+    )
+    .unwrap();
+    let err = ConfigLoader::resolve_config(duplicated).unwrap_err();
+    assert!(matches!(err, KiraError::ConfigEntry(_)));
+    let message = err.to_string();
+    assert!(
+        message.contains("ref (genome:GCF_000005845.2, uniprot:P69905)"),
+        "{message}"
+    );
+    assert!(!message.contains("paper"), "{message}");
+
+    let invalid: Config =
+        serde_json::from_str(r#"{"proteins": [{"id": "1LYZ", "alias": "../up"}]}"#).unwrap();
+    assert!(matches!(
+        ConfigLoader::resolve_config(invalid),
+        Err(KiraError::InvalidAlias(_))
+    ));
+}
+
+#[test]
+fn validation_locates_malformed_invalid_and_duplicate_entries() {
+    let temp = tempfile::tempdir().unwrap();
+    let path = temp.path().join("kira-bm.json");
+    std::fs::write(
+        &path,
+        r#"{
+            "proteins": ["1LYZ", {"id": "4V6X", "fromat": "pdb"}],
+            "srr": [{"id": "SRR014966", "paired": "yes"}, 42]
+        }"#,
+    )
+    .unwrap();
+    let Err(KiraError::InvalidConfig { errors, .. }) = ConfigLoader::load(&path) else {
+        panic!("shape errors not reported");
+    };
+    assert_eq!(errors.len(), 3, "{errors:?}");
+    assert!(errors[0].starts_with("proteins[1]: unknown field `fromat`"));
+    assert!(errors[1].starts_with("srr[0]: invalid type"));
+    assert_eq!(errors[2], "srr[1]: expected an id string or an object");
+
+    std::fs::write(
+        &path,
+        r#"{
+            "proteins": ["1LYZ", "2PTC", "3HHB", "XY", {"id": "1lyz", "format": "pdb"}],
+            "uniprot": ["P69905"]
+        }"#,
+    )
+    .unwrap();
+    assert!(ConfigLoader::load(&path).is_ok());
+    let err = ConfigLoader::validate(&path).unwrap_err();
+    let KiraError::InvalidConfig { errors, .. } = &err else {
+        panic!("{err:?}");
+    };
+    assert_eq!(
+        errors,
+        &vec![
+            "proteins[3]: 'XY' is not a valid PDB id".to_string(),
+            "proteins[4]: '1lyz' duplicates proteins[0]".to_string(),
+        ]
+    );
+    assert!(
+        ConfigLoader::resolve(path.to_str()).is_err(),
+        "fetches resolve through the same checks"
+    );
 }
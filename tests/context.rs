@@ -0,0 +1,225 @@
+mod support;
+
+use std::path::Path;
+
+use camino::Utf8PathBuf;
+
+use kira_biodata_manager::app::{App, FetchOptions, FetchOverrides, FetchResult, ProgressSink};
+use kira_biodata_manager::domain::{DatasetSpecifier, ProteinFormat, ProteinId, UniprotId};
+use kira_biodata_manager::error::{ErrorReport, KiraError};
+use kira_biodata_manager::history;
+use kira_biodata_manager::rcsb::{RcsbClient, RcsbMetadata};
+use kira_biodata_manager::store::Store;
+use kira_biodata_manager::uniprot::{UniprotClient, UniprotRecord, extract_metadata};
+
+use support::{RecordingSink, UnusedGeo, UnusedKnowledge, UnusedNcbi, UnusedSrr};
+
+/// Writes structures unless `failing` is set, in which case RCSB answers 503.
+struct MockRcsb {
+    failing: bool,
+}
+
+impl RcsbClient for MockRcsb {
+    fn download_structure(
+        &self,
+        id: &ProteinId,
+        _format: ProteinFormat,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<(), KiraError> {
+        if self.failing {
+            return Err(KiraError::RcsbStatus {
+                status: 503,
+                url: format!("https://files.rcsb.org/download/{id}.cif"),
+                dataset: format!("protein:{id}"),
+                message: format!("{id} unavailable"),
+            });
+        }
+        std::fs::write(destination, b"data_mock\nloop_\n_atom_site.id\n1\n")
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+
+    fn fetch_metadata(&self, id: &ProteinId) -> Result<RcsbMetadata, KiraError> {
+        Ok(RcsbMetadata {
+            registry: "rcsb".to_string(),
+            pdb_id: id.as_str().to_string(),
+            title: None,
+            experimental_method: None,
+            resolution: None,
+            deposition_date: None,
+            release_date: None,
+            source_structure_url: String::new(),
+            source_metadata_url: String::new(),
+            raw_json: serde_json::json!({}),
+        })
+    }
+}
+
+/// Serves the lysozyme fixture without the predicted model, so the signal
+/// peptide has no covering structure.
+struct FixtureUniprot;
+
+impl UniprotClient for FixtureUniprot {
+    fn fetch(&self, _id: &UniprotId) -> Result<UniprotRecord, KiraError> {
+        let raw = std::fs::read_to_string("tests/fixtures/uniprot_P00698_structures.json")
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let mut raw_json: serde_json::Value = serde_json::from_str(&raw).unwrap();
+        raw_json["uniProtKBCrossReferences"]
+            .as_array_mut()
+            .unwrap()
+            .retain(|xref| xref["id"] != "9ZZZ");
+        Ok(UniprotRecord {
+            metadata: extract_metadata(&raw_json)?,
+            raw_json,
+            fasta: ">sp|P00698|LYSC_CHICK\nKVFGRCELAAAMKRHGLDNYRGYSLGNWVCAAKFESNFNTQATNRNTDGSTDYGILQINSRWWCNDGRTP\n"
+                .to_string(),
+        })
+    }
+}
+
+type TestApp = App<UnusedNcbi, MockRcsb, UnusedSrr, FixtureUniprot, UnusedGeo, UnusedKnowledge>;
+
+fn app(temp: &tempfile::TempDir, failing: bool) -> (TestApp, Store) {
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    let store = Store::new_with_paths(root.join("project"), root.join("cache"));
+    let app = App::new(
+        store.clone(),
+        UnusedNcbi,
+        MockRcsb { failing },
+        UnusedSrr,
+        FixtureUniprot,
+        UnusedGeo,
+        UnusedKnowledge,
+    );
+    (app, store)
+}
+
+fn fetch(
+    app: &TestApp,
+    specifier: &str,
+    options: FetchOptions,
+    sink: &RecordingSink,
+) -> Result<FetchResult, KiraError> {
+    app.fetch(
+        Some(specifier.parse::<DatasetSpecifier>().unwrap()),
+        None,
+        FetchOverrides::default(),
+        options,
+        sink,
+    )
+}
+
+/// Asserts the rendered message and the JSON report both name `specifier`
+/// and `phase` exactly once.
+fn assert_context(err: &KiraError, specifier: &str, phase: &str) {
+    assert_eq!(err.context(), Some((specifier, phase)));
+    let message = err.to_string();
+    assert!(
+        message.starts_with(&format!("{specifier} [{phase}]: ")),
+        "{message}"
+    );
+    assert_eq!(message.matches(specifier).count(), 1, "{message}");
+    assert_eq!(
+        message.matches(&format!("[{phase}]")).count(),
+        1,
+        "{message}"
+    );
+
+    let json = serde_json::to_string(&ErrorReport::from(err)).unwrap();
+    assert_eq!(json.matches(specifier).count(), 1, "{json}");
+    assert_eq!(json.matches(phase).count(), 1, "{json}");
+    let report: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(report["specifier"], specifier);
+    assert_eq!(report["phase"], phase);
+    assert_eq!(report["error"], err.root().to_string());
+    assert_eq!(report["help"].as_str().map(str::to_string), err.help());
+}
+
+#[test]
+fn client_failure_names_dataset_and_fetch_phase() {
+    let temp = tempfile::tempdir().unwrap();
+    let (app, store) = app(&temp, true);
+    let sink = RecordingSink::default();
+
+    let err = fetch(&app, "protein:1LYZ", FetchOptions::default(), &sink).unwrap_err();
+
+    assert!(matches!(
+        err.root(),
+        KiraError::RcsbStatus { status: 503, .. }
+    ));
+    assert_context(&err, "protein:1LYZ", "Fetch");
+    assert_eq!(
+        sink.failures(),
+        vec![format!("item.failed protein:1LYZ [Fetch] {}", err.root())]
+    );
+    let records = history::read(store.project_root(), None).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].phase.as_deref(), Some("Fetch"));
+    assert_eq!(records[0].error, Some(err.root().to_string()));
+}
+
+#[test]
+fn store_write_failure_names_store_phase() {
+    let temp = tempfile::tempdir().unwrap();
+    let (app, store) = app(&temp, false);
+    let id = "1LYZ".parse::<ProteinId>().unwrap();
+    // A directory where the structure file belongs makes the final write fail.
+    std::fs::create_dir_all(store.project_protein_path(&id, ProteinFormat::Cif)).unwrap();
+    let sink = RecordingSink::default();
+
+    let options = FetchOptions::builder().force(true).build();
+    let err = fetch(&app, "protein:1LYZ", options, &sink).unwrap_err();
+
+    assert!(matches!(err.root(), KiraError::Filesystem(_)), "{err}");
+    assert_context(&err, "protein:1LYZ", "Store");
+}
+
+#[test]
+fn validation_failure_in_a_composite_fetch_is_wrapped_once() {
+    let temp = tempfile::tempdir().unwrap();
+    let (app, _store) = app(&temp, false);
+    let sink = RecordingSink::default();
+
+    // The UniProt entry is fetched as a nested item; ranking then fails.
+    let err = fetch(
+        &app,
+        "structure-for:P00698[1-30]",
+        FetchOptions::default(),
+        &sink,
+    )
+    .unwrap_err();
+    assert!(matches!(err.root(), KiraError::NoCoveringStructure(_)));
+    assert_context(&err, "structure-for:P00698[1-30]", "Verify");
+    assert!(sink.failures().is_empty());
+}
+
+#[test]
+fn nested_item_failure_keeps_the_innermost_context() {
+    let temp = tempfile::tempdir().unwrap();
+    let (app, store) = app(&temp, true);
+    let sink = RecordingSink::default();
+
+    let err = fetch(
+        &app,
+        "structure-for:P00698[20-140]",
+        FetchOptions::default(),
+        &sink,
+    )
+    .unwrap_err();
+
+    assert_context(&err, "protein:4LZT", "Fetch");
+    assert!(!err.to_string().contains("structure-for"), "{err}");
+    let actions = history::read(store.project_root(), None)
+        .unwrap()
+        .into_iter()
+        .map(|record| format!("{} {}", record.action, record.specifier))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        actions,
+        vec!["download uniprot:P00698", "failed protein:4LZT"]
+    );
+    assert_eq!(
+        sink.failures(),
+        vec![format!("item.failed protein:4LZT [Fetch] {}", err.root())]
+    );
+}
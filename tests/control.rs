@@ -0,0 +1,461 @@
+mod support;
+
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use kira_biodata_manager::app::{App, FetchOptions, FetchOverrides, ProgressEvent, ProgressSink};
+use kira_biodata_manager::cancel::CancellationToken;
+use kira_biodata_manager::config::{Config, ConfigLoader};
+use kira_biodata_manager::control::{
+    self, BATCH_FILE, BatchControl, BatchState, BatchStatus, CONTROL_FILE, ControlRequest,
+    ControlSettings,
+};
+use kira_biodata_manager::domain::{ProteinFormat, ProteinId};
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::rcsb::{RcsbClient, RcsbMetadata};
+use kira_biodata_manager::store::Store;
+
+use support::{RecordingSink, UnusedGeo, UnusedKnowledge, UnusedNcbi, UnusedSrr, UnusedUniprot};
+
+/// Records every structure download; the first one waits for the test to
+/// let it finish so a pause can be requested while it is in flight.
+struct GatedRcsb {
+    downloads: Arc<Mutex<Vec<String>>>,
+    started: Mutex<Sender<()>>,
+    release: Mutex<Receiver<()>>,
+}
+
+impl RcsbClient for GatedRcsb {
+    fn download_structure(
+        &self,
+        id: &ProteinId,
+        _format: ProteinFormat,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<(), KiraError> {
+        let first = {
+            let mut downloads = self.downloads.lock().unwrap();
+            downloads.push(id.as_str().to_string());
+            downloads.len() == 1
+        };
+        if first {
+            self.started.lock().unwrap().send(()).unwrap();
+            self.release.lock().unwrap().recv().unwrap();
+        }
+        std::fs::write(destination, b"data_mock\nloop_\n_atom_site.id\n1\n")
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+
+    fn fetch_metadata(&self, id: &ProteinId) -> Result<RcsbMetadata, KiraError> {
+        Ok(metadata_for(id))
+    }
+}
+
+/// Takes `delay` for every download and records the order they finish in.
+struct SlowRcsb {
+    delay: fn(&str) -> Duration,
+    finished: Arc<Mutex<Vec<String>>>,
+}
+
+impl RcsbClient for SlowRcsb {
+    fn download_structure(
+        &self,
+        id: &ProteinId,
+        _format: ProteinFormat,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<(), KiraError> {
+        std::thread::sleep((self.delay)(id.as_str()));
+        std::fs::write(destination, b"data_mock\nloop_\n_atom_site.id\n1\n")
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        self.finished
+            .lock()
+            .unwrap()
+            .push(format!("protein:{}", id.as_str()));
+        Ok(())
+    }
+
+    fn fetch_metadata(&self, id: &ProteinId) -> Result<RcsbMetadata, KiraError> {
+        Ok(metadata_for(id))
+    }
+}
+
+type SlowApp = App<UnusedNcbi, SlowRcsb, UnusedSrr, UnusedUniprot, UnusedGeo, UnusedKnowledge>;
+
+fn metadata_for(id: &ProteinId) -> RcsbMetadata {
+    RcsbMetadata {
+        registry: "rcsb".to_string(),
+        pdb_id: id.as_str().to_string(),
+        title: None,
+        experimental_method: None,
+        resolution: None,
+        deposition_date: None,
+        release_date: None,
+        source_structure_url: String::new(),
+        source_metadata_url: String::new(),
+        raw_json: serde_json::json!({}),
+    }
+}
+
+fn fast_settings() -> ControlSettings {
+    ControlSettings {
+        poll_interval: Duration::from_millis(10),
+        stale_after: Duration::from_secs(30),
+    }
+}
+
+fn project_root(temp: &tempfile::TempDir) -> Utf8PathBuf {
+    Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap()
+}
+
+fn wait_for_state(root: &Utf8Path, state: BatchState) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let report = control::status(root, fast_settings()).unwrap();
+        if report.batch.is_some_and(|batch| batch.state == state) {
+            return;
+        }
+        assert!(Instant::now() < deadline, "batch never reached {state}");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn paused_batch_starts_no_downloads_until_resumed() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = project_root(&temp);
+    let cache = Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let (started_tx, started_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel();
+    let downloads = Arc::new(Mutex::new(Vec::new()));
+    let app = App::new(
+        Store::new_with_paths(root.clone(), cache),
+        UnusedNcbi,
+        GatedRcsb {
+            downloads: downloads.clone(),
+            started: Mutex::new(started_tx),
+            release: Mutex::new(release_rx),
+        },
+        UnusedSrr,
+        UnusedUniprot,
+        UnusedGeo,
+        UnusedKnowledge,
+    )
+    .with_control_settings(fast_settings());
+    let config: Config = serde_json::from_str(r#"{"proteins": ["1LYZ", "2LYZ", "3LYZ"]}"#).unwrap();
+    let config = ConfigLoader::resolve_config(config).unwrap();
+    let sink = RecordingSink::default();
+
+    std::thread::scope(|scope| {
+        let batch = scope.spawn(|| {
+            app.fetch(
+                None,
+                Some(&config),
+                FetchOverrides::default(),
+                FetchOptions::default(),
+                &sink,
+            )
+        });
+
+        started_rx.recv().unwrap();
+        control::request(&root, BatchState::Paused, fast_settings()).unwrap();
+        release_tx.send(()).unwrap();
+        wait_for_state(&root, BatchState::Paused);
+
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(downloads.lock().unwrap().clone(), vec!["1LYZ"]);
+        let report = control::status(&root, fast_settings()).unwrap();
+        assert_eq!(report.batch.unwrap().completed, 1);
+
+        control::request(&root, BatchState::Running, fast_settings()).unwrap();
+        let result = batch.join().unwrap().unwrap();
+        assert_eq!(result.items.len(), 3);
+    });
+
+    assert_eq!(
+        downloads.lock().unwrap().clone(),
+        vec!["1LYZ", "2LYZ", "3LYZ"]
+    );
+    let messages = sink.messages();
+    assert_eq!(
+        messages
+            .iter()
+            .filter(|message| message.starts_with("batch."))
+            .collect::<Vec<_>>(),
+        vec!["batch.paused", "batch.resumed"]
+    );
+    assert!(!root.join(BATCH_FILE).exists());
+    assert!(!root.join(CONTROL_FILE).exists());
+}
+
+#[test]
+fn pause_times_out_with_error() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = project_root(&temp);
+    let batch =
+        BatchControl::start(&root, fast_settings(), Some(Duration::from_millis(50))).unwrap();
+    control::request(&root, BatchState::Paused, fast_settings()).unwrap();
+    let sink = RecordingSink::default();
+    let err = batch.checkpoint(&sink).unwrap_err();
+    assert!(matches!(err, KiraError::BatchPauseTimeout { .. }));
+    assert!(sink.messages().contains(&"batch.pause_timeout".to_string()));
+}
+
+#[test]
+fn control_left_by_dead_batch_is_stale() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = project_root(&temp);
+    std::fs::create_dir_all(&root).unwrap();
+    let dead = BatchStatus {
+        id: "1-1-1".to_string(),
+        pid: 1,
+        started_at: 1,
+        heartbeat_at: 1,
+        state: BatchState::Paused,
+        completed: 2,
+    };
+    std::fs::write(root.join(BATCH_FILE), serde_json::to_vec(&dead).unwrap()).unwrap();
+    let request = ControlRequest {
+        state: BatchState::Paused,
+        batch_id: dead.id.clone(),
+        requested_at: 1,
+    };
+    std::fs::write(
+        root.join(CONTROL_FILE),
+        serde_json::to_vec(&request).unwrap(),
+    )
+    .unwrap();
+
+    let report = control::status(&root, fast_settings()).unwrap();
+    assert!(report.stale);
+    assert!(matches!(
+        control::request(&root, BatchState::Running, fast_settings()),
+        Err(KiraError::NoActiveBatch)
+    ));
+
+    // A new batch ignores the dead batch's pause.
+    let batch = BatchControl::start(&root, fast_settings(), Some(Duration::ZERO)).unwrap();
+    batch.checkpoint(&RecordingSink::default()).unwrap();
+    let report = control::status(&root, fast_settings()).unwrap();
+    assert!(!report.stale);
+    assert_eq!(report.batch.unwrap().id, batch.id());
+    assert!(report.request.is_none());
+}
+
+#[test]
+fn batch_items_report_begin_end_and_failure() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = project_root(&temp);
+    let cache = Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let (started_tx, _started_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel();
+    release_tx.send(()).unwrap();
+    let app = App::new(
+        Store::new_with_paths(root, cache),
+        UnusedNcbi,
+        GatedRcsb {
+            downloads: Arc::new(Mutex::new(Vec::new())),
+            started: Mutex::new(started_tx),
+            release: Mutex::new(release_rx),
+        },
+        UnusedSrr,
+        UnusedUniprot,
+        UnusedGeo,
+        UnusedKnowledge,
+    )
+    .with_control_settings(fast_settings());
+    let config: Config =
+        serde_json::from_str(r#"{"proteins": ["1LYZ"], "uniprot": ["P69905"]}"#).unwrap();
+    let config = ConfigLoader::resolve_config(config).unwrap();
+    let sink = RecordingSink::default();
+
+    let result = app
+        .fetch(
+            None,
+            Some(&config),
+            FetchOverrides::default(),
+            FetchOptions::default(),
+            &sink,
+        )
+        .unwrap();
+
+    assert_eq!(result.items.len(), 1);
+    assert_eq!(result.errors.len(), 1);
+    assert_eq!(result.errors[0].specifier, "uniprot:P69905");
+    assert!(result.errors[0].retryable);
+    let messages = sink.messages();
+    let items = messages
+        .iter()
+        .filter(|message| message.starts_with("item."))
+        .collect::<Vec<_>>();
+    assert_eq!(items.len(), 4);
+    assert_eq!(items[0], "item.begin protein:1LYZ");
+    assert_eq!(items[1], "item.end protein:1LYZ");
+    assert_eq!(items[2], "item.begin uniprot:P69905");
+    assert!(items[3].starts_with("item.failed uniprot:P69905 "));
+    assert_eq!(sink.items(), ["protein:1LYZ"]);
+
+    // --fail-fast gives up at the failed entry instead.
+    let err = app
+        .fetch(
+            None,
+            Some(&config),
+            FetchOverrides::default(),
+            FetchOptions::builder().fail_fast(true).build(),
+            &sink,
+        )
+        .unwrap_err();
+    assert_eq!(err.context(), Some(("uniprot:P69905", "Fetch")));
+}
+
+/// Cancels as soon as `cancel_after` is reported.
+struct CancellingSink {
+    cancel_after: &'static str,
+    token: CancellationToken,
+    messages: Mutex<Vec<String>>,
+}
+
+impl ProgressSink for CancellingSink {
+    fn event(&self, event: ProgressEvent) {
+        if event.message == self.cancel_after {
+            self.token.cancel();
+        }
+        self.messages.lock().unwrap().push(event.message);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+}
+
+#[test]
+fn concurrent_batches_each_report_items_in_completion_order() {
+    let temp = tempfile::tempdir().unwrap();
+    let app = |name: &str, delay: fn(&str) -> Duration| {
+        let root = Utf8PathBuf::from_path_buf(temp.path().join(name)).unwrap();
+        let finished = Arc::new(Mutex::new(Vec::new()));
+        let app = App::new(
+            Store::new_with_paths(root.join("project"), root.join("cache")),
+            UnusedNcbi,
+            SlowRcsb {
+                delay,
+                finished: finished.clone(),
+            },
+            UnusedSrr,
+            UnusedUniprot,
+            UnusedGeo,
+            UnusedKnowledge,
+        );
+        (app, finished)
+    };
+    // Opposite speeds, so the two batches finish their items interleaved.
+    let fast_first = app("fast-first", |id| match id {
+        "1LYZ" => Duration::ZERO,
+        "2LYZ" => Duration::from_millis(40),
+        _ => Duration::from_millis(80),
+    });
+    let slow_first = app("slow-first", |id| match id {
+        "1LYZ" => Duration::from_millis(80),
+        "2LYZ" => Duration::from_millis(40),
+        _ => Duration::ZERO,
+    });
+    let config: Config = serde_json::from_str(r#"{"proteins": ["1LYZ", "2LYZ", "3LYZ"]}"#).unwrap();
+    let config = ConfigLoader::resolve_config(config).unwrap();
+
+    let run = |(app, _): &(SlowApp, _)| {
+        let sink = RecordingSink::default();
+        let result = app
+            .fetch(
+                None,
+                Some(&config),
+                FetchOverrides::default(),
+                FetchOptions::default(),
+                &sink,
+            )
+            .unwrap();
+        let fetched = result
+            .items
+            .iter()
+            .map(|item| format!("{}:{}", item.dataset_type, item.id))
+            .collect::<Vec<_>>();
+        (sink.items(), fetched)
+    };
+    let (first, second) = std::thread::scope(|scope| {
+        let first = scope.spawn(|| run(&fast_first));
+        let second = scope.spawn(|| run(&slow_first));
+        (first.join().unwrap(), second.join().unwrap())
+    });
+
+    for ((streamed, fetched), (_, finished)) in [(first, &fast_first), (second, &slow_first)] {
+        assert_eq!(streamed, ["protein:1LYZ", "protein:2LYZ", "protein:3LYZ"]);
+        assert_eq!(streamed, fetched);
+        assert_eq!(streamed, *finished.lock().unwrap());
+    }
+}
+
+#[test]
+fn cancelled_batch_stops_before_next_item() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = project_root(&temp);
+    let cache = Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let (started_tx, _started_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel();
+    release_tx.send(()).unwrap();
+    let downloads = Arc::new(Mutex::new(Vec::new()));
+    let app = App::new(
+        Store::new_with_paths(root.clone(), cache),
+        UnusedNcbi,
+        GatedRcsb {
+            downloads: downloads.clone(),
+            started: Mutex::new(started_tx),
+            release: Mutex::new(release_rx),
+        },
+        UnusedSrr,
+        UnusedUniprot,
+        UnusedGeo,
+        UnusedKnowledge,
+    )
+    .with_control_settings(fast_settings());
+    let config: Config = serde_json::from_str(r#"{"proteins": ["1LYZ", "2LYZ", "3LYZ"]}"#).unwrap();
+    let config = ConfigLoader::resolve_config(config).unwrap();
+    let sink = CancellingSink {
+        cancel_after: "item.end protein:1LYZ",
+        token: CancellationToken::default(),
+        messages: Mutex::new(Vec::new()),
+    };
+
+    let err = app
+        .fetch(
+            None,
+            Some(&config),
+            FetchOverrides::default(),
+            FetchOptions::default(),
+            &sink,
+        )
+        .unwrap_err();
+
+    assert!(matches!(err, KiraError::Cancelled));
+    assert_eq!(downloads.lock().unwrap().clone(), vec!["1LYZ"]);
+    assert!(root.join("proteins").join("1LYZ").exists());
+    assert!(!root.join("proteins").join("2LYZ").exists());
+    assert!(!root.join(BATCH_FILE).exists());
+}
+
+#[test]
+fn cancel_releases_paused_batch() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = project_root(&temp);
+    let batch = BatchControl::start(&root, fast_settings(), None).unwrap();
+    control::request(&root, BatchState::Paused, fast_settings()).unwrap();
+    let sink = CancellingSink {
+        cancel_after: "batch.paused",
+        token: CancellationToken::default(),
+        messages: Mutex::new(Vec::new()),
+    };
+    let err = batch.checkpoint(&sink).unwrap_err();
+    assert!(matches!(err, KiraError::Cancelled));
+}
@@ -0,0 +1,133 @@
+use std::path::Path;
+
+use assert_matches::assert_matches;
+
+use kira_biodata_manager::convert::{bcif_to_cif, cif_to_pdb, convert_structure};
+use kira_biodata_manager::domain::ProteinFormat;
+use kira_biodata_manager::error::KiraError;
+
+fn fixture(name: &str) -> Vec<u8> {
+    std::fs::read(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/rcsb")
+            .join(name),
+    )
+    .unwrap()
+}
+
+#[test]
+fn bcif_decodes_to_text_cif() {
+    // The fixture uses every BinaryCIF encoding: string arrays, run-length,
+    // delta, integer packing across the 16-bit limit, fixed point, interval
+    // quantization, float32 and masks for `.` and `?`.
+    let cif = bcif_to_cif(&fixture("1LYZ_atoms.bcif")).unwrap();
+    assert_eq!(
+        cif,
+        "data_1LYZ\n\
+         #\n\
+         _entry.id 1LYZ\n\
+         #\n\
+         _cell.length_a    79.1\n\
+         _cell.length_b    79.1\n\
+         _cell.length_c    37.9\n\
+         _cell.angle_alpha 90\n\
+         _cell.angle_beta  90\n\
+         _cell.angle_gamma 90\n\
+         _cell.Z_PDB       8\n\
+         #\n\
+         _symmetry.space_group_name_H-M 'P 43 21 2'\n\
+         #\n\
+         loop_\n\
+         _atom_site.group_PDB\n\
+         _atom_site.id\n\
+         _atom_site.type_symbol\n\
+         _atom_site.label_atom_id\n\
+         _atom_site.label_alt_id\n\
+         _atom_site.label_comp_id\n\
+         _atom_site.auth_asym_id\n\
+         _atom_site.auth_seq_id\n\
+         _atom_site.pdbx_PDB_ins_code\n\
+         _atom_site.Cartn_x\n\
+         _atom_site.Cartn_y\n\
+         _atom_site.Cartn_z\n\
+         _atom_site.occupancy\n\
+         _atom_site.B_iso_or_equiv\n\
+         _atom_site.pdbx_formal_charge\n\
+         _atom_site.pdbx_PDB_model_num\n\
+         ATOM 1 N N . LYS A 1 ? 3.294 10.164 10.266 1 12 0 1\n\
+         ATOM 2 C CA . LYS A 1 ? 2.388 10.533 9.168 1 13 0 1\n\
+         HETATM 3 O O . HOH A 201 ? 40 0 1.5 0.5 40 0 1\n\
+         #\n"
+    );
+
+    let err = bcif_to_cif(&fixture("1LYZ.cif")).unwrap_err();
+    assert!(err.contains("dataBlocks"), "{err}");
+}
+
+#[test]
+fn cif_converts_to_fixed_width_pdb_records() {
+    let cif = bcif_to_cif(&fixture("1LYZ_atoms.bcif")).unwrap();
+    let pdb = cif_to_pdb(&cif).unwrap();
+    assert_eq!(
+        pdb,
+        "CRYST1   79.100   79.100   37.900  90.00  90.00  90.00 P 43 21 2     8\n\
+         ATOM      1  N   LYS A   1       3.294  10.164  10.266  1.00 12.00           N  \n\
+         ATOM      2  CA  LYS A   1       2.388  10.533   9.168  1.00 13.00           C  \n\
+         HETATM    3  O   HOH A 201      40.000   0.000   1.500  0.50 40.00           O  \n\
+         END\n"
+    );
+
+    // Label columns stand in for missing author columns; no CRYST1 without a cell.
+    let pdb = String::from_utf8(
+        convert_structure(&fixture("1LYZ.cif"), ProteinFormat::Cif, ProteinFormat::Pdb).unwrap(),
+    )
+    .unwrap();
+    let lines = pdb.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines[1],
+        "ATOM      2  CA  LYS     0       2.388  10.533   9.168  1.00  0.00           C  "
+    );
+    assert!(lines.iter().all(|line| line.len() == 80 || *line == "END"));
+}
+
+#[test]
+fn structures_beyond_pdb_limits_are_rejected() {
+    let atoms = |rows: &str| {
+        format!(
+            "data_X\nloop_\n_atom_site.group_PDB\n_atom_site.type_symbol\n\
+             _atom_site.label_atom_id\n_atom_site.label_comp_id\n_atom_site.auth_asym_id\n\
+             _atom_site.auth_seq_id\n_atom_site.Cartn_x\n_atom_site.Cartn_y\n\
+             _atom_site.Cartn_z\n_atom_site.pdbx_PDB_model_num\n{rows}"
+        )
+    };
+    let cases = [
+        (
+            "ATOM C CA GLY A 1 0 0 0 1\nATOM C CA GLY A 1 0 0 0 2\n".to_string(),
+            "2 models",
+        ),
+        ("ATOM C CA GLY AB 1 0 0 0 1\n".to_string(), "chain id AB"),
+        (
+            "ATOM C CA GLYX A 1 0 0 0 1\n".to_string(),
+            "residue name GLYX",
+        ),
+        (
+            "ATOM C CA GLY A 1 12345.5 0 0 1\n".to_string(),
+            "does not fit 8 columns",
+        ),
+        (
+            "ATOM C CA GLY A 1 0 0 0 1\n".repeat(100_000),
+            "100000 atoms exceed the PDB limit of 99999",
+        ),
+    ];
+    for (rows, reason) in cases {
+        let err = cif_to_pdb(&atoms(&rows)).unwrap_err();
+        assert!(err.contains(reason), "{reason}: {err}");
+    }
+
+    let err = convert_structure(b"ATOM", ProteinFormat::Pdb, ProteinFormat::Cif).unwrap_err();
+    assert_matches!(err, KiraError::Conversion { .. });
+    assert_eq!(
+        err.to_string(),
+        "cannot convert pdb to cif: this conversion is not supported"
+    );
+}
@@ -0,0 +1,7 @@
+use kira_biodata_manager::providers::doi::accession_term;
+
+#[test]
+fn accession_term_joins_ids() {
+    let ids = vec!["SRR1".to_string(), "SRR2".to_string()];
+    assert_eq!(accession_term(&ids), "SRR1[Accession] OR SRR2[Accession]");
+}
@@ -30,3 +30,22 @@ fn extract_ids_from_text() {
     assert!(extracted.uniprot.contains(&"P69905".to_string()));
     assert!(extracted.ena_runs.contains(&"ERR123456".to_string()));
 }
+
+#[test]
+fn extract_extended_pdb_ids() {
+    let texts = vec![
+        "Deposited as PDB_10001ABC and pdb_00001lyz (formerly 1LYZ).".to_string(),
+        "Not ids: pdb_1abc, xpdb_00002def, pdb_00003ghij.".to_string(),
+    ];
+
+    let extracted = extract_ids(&texts);
+    assert_eq!(extracted.pdb, vec!["1LYZ", "pdb_10001abc"]);
+}
+
+#[test]
+fn extract_uniprot_accessions_of_both_shapes_and_isoforms() {
+    let texts = vec!["Hemoglobin P69905, isoform Q9Y263-2 and A0A023GPI8; not ABCDEF.".to_string()];
+
+    let extracted = extract_ids(&texts);
+    assert_eq!(extracted.uniprot, vec!["A0A023GPI8", "P69905", "Q9Y263-2"]);
+}
@@ -1,10 +1,12 @@
 use assert_matches::assert_matches;
 
 use kira_biodata_manager::domain::{
-    DatasetSpecifier, Doi, GenomeAccession, GeoSeriesAccession, ProteinFormat, ProteinId, Registry,
-    SrrId, UniprotId,
+    BioProjectAccession, DatasetSpecifier, Doi, GenomeAccession, GeoSampleAccession,
+    GeoSeriesAccession, KeggOrganism, ProteinFormat, ProteinId, ProteomeId, Registry,
+    SpecifierPattern, SraExperimentAccession, SrrId, StructureQuery, UniprotId,
 };
 use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::uniprot::UniprotHttpClient;
 
 #[test]
 fn parse_protein_id_valid() {
@@ -18,6 +20,46 @@ fn parse_protein_id_invalid() {
     assert_matches!(err, KiraError::InvalidProteinId(_));
 }
 
+#[test]
+fn parse_extended_protein_id() {
+    let id: ProteinId = "PDB_10001ABC".parse().unwrap();
+    assert_eq!(id.as_str(), "pdb_10001abc");
+    let id: ProteinId = " pdb_10001abc_v1-2 ".parse().unwrap();
+    assert_eq!(id.as_str(), "pdb_10001abc");
+    // Four leading zeros name the classic entry.
+    let id: ProteinId = "pdb_00001lyz".parse().unwrap();
+    assert_eq!(id.as_str(), "1LYZ");
+    let id: ProteinId = "1LYZ_v3".parse().unwrap();
+    assert_eq!(id.as_str(), "1LYZ");
+    assert_matches!(
+        DatasetSpecifier::infer("pdb_10001abc"),
+        Ok(DatasetSpecifier::Protein(id)) if id.as_str() == "pdb_10001abc"
+    );
+}
+
+#[test]
+fn parse_extended_protein_id_rejects_look_alikes() {
+    for value in [
+        "pdb_1abc",
+        "pdb_10001abcd",
+        "pdb-10001abc",
+        "pdb10001abc",
+        "xdb_10001abc",
+        "pdb_1000-1ab",
+        "pdb_10001abc_v",
+        "pdb_10001abc_vx",
+        "pdb_10001abc_v1-",
+        "pdb_",
+        "1LYZ_v",
+    ] {
+        assert_matches!(
+            value.parse::<ProteinId>(),
+            Err(KiraError::InvalidProteinId(_)),
+            "{value}"
+        );
+    }
+}
+
 #[test]
 fn parse_genome_accession_valid() {
     let acc: GenomeAccession = "GCF_000005845.2".parse().unwrap();
@@ -30,6 +72,39 @@ fn parse_genome_accession_invalid() {
     assert_matches!(err, KiraError::InvalidGenomeAccession(_));
 }
 
+#[test]
+fn genome_specifier_accepts_assembly_names() {
+    let spec: DatasetSpecifier = "genome:GRCh38".parse().unwrap();
+    assert_matches!(spec, DatasetSpecifier::GenomeName(_));
+    assert_eq!(spec.to_string(), "genome:GRCh38");
+    let err = "genome:GCF_X1".parse::<DatasetSpecifier>().unwrap_err();
+    assert_matches!(err, KiraError::InvalidGenomeAccession(_));
+}
+
+#[test]
+fn ensembl_genome_specifiers_take_a_species_and_an_optional_release() {
+    let pinned: DatasetSpecifier = "genome:ensembl:Homo_Sapiens@110".parse().unwrap();
+    assert_matches!(&pinned, DatasetSpecifier::GenomeEnsembl(genome) if genome.release() == Some(110));
+    assert_eq!(pinned.to_string(), "genome:ensembl:homo_sapiens@110");
+    assert_eq!(pinned.dataset_type(), "genome");
+    assert_eq!(pinned.resolve_registry(None), Registry::Ensembl);
+
+    let current: DatasetSpecifier = "genome:ensembl:mus_musculus".parse().unwrap();
+    assert_matches!(&current, DatasetSpecifier::GenomeEnsembl(genome) if genome.release().is_none());
+    assert_eq!(current.to_string(), "genome:ensembl:mus_musculus");
+
+    for invalid in [
+        "genome:ensembl:homo sapiens",
+        "genome:ensembl:homo_sapiens@",
+        "genome:ensembl:homo_sapiens@0",
+        "genome:ensembl:homo_sapiens@latest",
+        "genome:ensembl:",
+    ] {
+        let err = invalid.parse::<DatasetSpecifier>().unwrap_err();
+        assert_matches!(err, KiraError::InvalidSpecifier(_), "{invalid}");
+    }
+}
+
 #[test]
 fn parse_dataset_specifier() {
     let spec: DatasetSpecifier = "protein:1LYZ".parse().unwrap();
@@ -63,12 +138,27 @@ fn registry_routing() {
     let expression10x: DatasetSpecifier = "expression10x:GSE115978".parse().unwrap();
     assert_eq!(expression10x.resolve_registry(None), Registry::Geo);
 
+    let sample: DatasetSpecifier = "expressionsample:GSM3577221".parse().unwrap();
+    assert_eq!(sample.resolve_registry(None), Registry::Geo);
+    assert_eq!(sample.dataset_type(), "expressionsample");
+
+    let bioproject: DatasetSpecifier = "bioproject:PRJNA680067".parse().unwrap();
+    assert_eq!(bioproject.resolve_registry(None), Registry::Ncbi);
+
+    let ena_project: DatasetSpecifier = "bioproject:PRJEB12345".parse().unwrap();
+    assert_eq!(ena_project.resolve_registry(None), Registry::Ena);
+
     let go: DatasetSpecifier = "go".parse().unwrap();
     assert_eq!(go.resolve_registry(None), Registry::Go);
 
     let kegg: DatasetSpecifier = "kegg".parse().unwrap();
     assert_eq!(kegg.resolve_registry(None), Registry::Kegg);
 
+    let organism: DatasetSpecifier = "kegg:hsa".parse().unwrap();
+    assert_eq!(organism.resolve_registry(None), Registry::Kegg);
+    assert_eq!(organism.dataset_type(), "kegg");
+    assert_eq!(organism.to_string(), "kegg:hsa");
+
     let reactome: DatasetSpecifier = "reactome".parse().unwrap();
     assert_eq!(reactome.resolve_registry(None), Registry::Reactome);
 }
@@ -89,6 +179,79 @@ fn parse_err_id_valid() {
 fn parse_uniprot_id_valid() {
     let id: UniprotId = "p69905".parse().unwrap();
     assert_eq!(id.as_str(), "P69905");
+    assert_eq!(id.isoform(), None);
+    let id: UniprotId = "A0A023GPI8".parse().unwrap();
+    assert_eq!(id.accession(), "A0A023GPI8");
+}
+
+#[test]
+fn parse_proteome_id() {
+    let id: ProteomeId = " up000005640 ".parse().unwrap();
+    assert_eq!(id.as_str(), "UP000005640");
+    for invalid in ["UP00000564", "UP0000056401", "UPX00005640", "P69905"] {
+        assert_matches!(
+            invalid.parse::<ProteomeId>(),
+            Err(KiraError::InvalidProteomeId(_))
+        );
+    }
+
+    let spec: DatasetSpecifier = "uniprot-proteome:UP000005640".parse().unwrap();
+    assert_matches!(spec, DatasetSpecifier::Proteome(_));
+    assert_eq!(spec.resolve_registry(None), Registry::Uniprot);
+    assert_eq!(spec.to_string(), "uniprot-proteome:UP000005640");
+    assert!(!spec.supports_metadata_only());
+    assert_eq!(DatasetSpecifier::infer("UP000005640").unwrap(), spec);
+    assert_eq!(
+        UniprotHttpClient::proteome_fasta_url(&id),
+        "https://rest.uniprot.org/uniprotkb/stream?query=proteome:UP000005640&format=fasta&compressed=true"
+    );
+}
+
+#[test]
+fn parse_uniprot_isoform() {
+    let id: UniprotId = " p69905-2 ".parse().unwrap();
+    assert_eq!(id.as_str(), "P69905-2");
+    assert_eq!(id.accession(), "P69905");
+    assert_eq!(id.isoform(), Some(2));
+    assert_eq!(serde_json::to_value(&id).unwrap(), "P69905-2");
+    assert_eq!(
+        serde_json::from_value::<UniprotId>(serde_json::json!("P69905-2")).unwrap(),
+        id
+    );
+    assert_eq!(
+        UniprotHttpClient::metadata_url(&id),
+        "https://rest.uniprot.org/uniprotkb/P69905.json"
+    );
+    assert_eq!(
+        UniprotHttpClient::fasta_url(&id),
+        "https://rest.uniprot.org/uniprotkb/P69905-2.fasta"
+    );
+    assert_matches!(
+        DatasetSpecifier::infer("Q9Y263-12"),
+        Ok(DatasetSpecifier::Uniprot(id)) if id.isoform() == Some(12)
+    );
+}
+
+#[test]
+fn parse_uniprot_id_follows_the_accession_pattern() {
+    for value in [
+        "ABCDEF",
+        "P6990",
+        "P699051",
+        "O1234A",
+        "A0A023GPI",
+        "P69905-",
+        "P69905-0",
+        "P69905-02",
+        "P69905-x",
+        "P69905-2-1",
+        "P69905_2",
+    ] {
+        let err = value.parse::<UniprotId>().unwrap_err();
+        assert_matches!(err, KiraError::InvalidUniprotId(_), "{value}");
+    }
+    let err = "ABCDEF".parse::<UniprotId>().unwrap_err();
+    assert!(err.to_string().contains("P69905-2"), "{err}");
 }
 
 #[test]
@@ -102,3 +265,266 @@ fn parse_expression_valid() {
     let acc: GeoSeriesAccession = "GSE102902".parse().unwrap();
     assert_eq!(acc.as_str(), "GSE102902");
 }
+
+#[test]
+fn parse_expression_sample_valid() {
+    let acc: GeoSampleAccession = "gsm3577221".parse().unwrap();
+    assert_eq!(acc.as_str(), "GSM3577221");
+    let spec: DatasetSpecifier = "expression:GSM3577221".parse().unwrap();
+    assert_eq!(spec, DatasetSpecifier::ExpressionSample(acc));
+}
+
+#[test]
+fn parse_expression_sample_invalid() {
+    for value in ["GSM", "GSE102902", "GSM12a"] {
+        let err = value.parse::<GeoSampleAccession>().unwrap_err();
+        assert_matches!(err, KiraError::InvalidExpressionSampleAccession(_));
+    }
+    let err = "expressionsample:GSE102902"
+        .parse::<DatasetSpecifier>()
+        .unwrap_err();
+    assert_matches!(err, KiraError::InvalidExpressionSampleAccession(_));
+}
+
+#[test]
+fn parse_bioproject_valid() {
+    let acc: BioProjectAccession = "prjna680067".parse().unwrap();
+    assert_eq!(acc.as_str(), "PRJNA680067");
+    assert!(!acc.is_ena());
+    let acc: BioProjectAccession = "PRJDB1234".parse().unwrap();
+    assert!(!acc.is_ena());
+    let acc: BioProjectAccession = "PRJEB4337".parse().unwrap();
+    assert!(acc.is_ena());
+}
+
+#[test]
+fn parse_bioproject_invalid() {
+    for value in ["PRJNA", "PRJXX123", "ERP001", "PRJNA12a"] {
+        let err = value.parse::<BioProjectAccession>().unwrap_err();
+        assert_matches!(err, KiraError::InvalidBioProjectAccession(_));
+    }
+}
+
+#[test]
+fn parse_sra_experiment_accessions() {
+    for (value, expected) in [
+        ("srx1234567", "SRX1234567"),
+        ("ERX4404056", "ERX4404056"),
+        (" DRX000001 ", "DRX000001"),
+    ] {
+        let acc: SraExperimentAccession = value.parse().unwrap();
+        assert_eq!(acc.as_str(), expected);
+    }
+    for value in ["SRX", "SRR1234", "SRX12a", "XRX123", "PRJNA1"] {
+        let err = value.parse::<SraExperimentAccession>().unwrap_err();
+        assert_matches!(err, KiraError::InvalidSraExperimentAccession(_));
+    }
+    let id: SrrId = "drr000001".parse().unwrap();
+    assert_eq!(id.as_str(), "DRR000001");
+
+    // `srx:` names an experiment; `srr:` takes one in place of a run.
+    for value in ["srx:SRX1234567", "srr:srx1234567", "SRX1234567"] {
+        let spec = DatasetSpecifier::infer(value).unwrap();
+        assert_matches!(spec, DatasetSpecifier::SrrExperiment(_));
+        assert_eq!(spec.to_string(), "srx:SRX1234567", "{value}");
+        assert_eq!(spec.resolve_registry(None), Registry::Ncbi);
+    }
+    assert_matches!(
+        "srx:SRR1234".parse::<DatasetSpecifier>(),
+        Err(KiraError::InvalidSraExperimentAccession(_))
+    );
+}
+
+#[test]
+fn parse_structure_for() {
+    let spec: DatasetSpecifier = "structure-for:p69905[50-120]".parse().unwrap();
+    assert_eq!(spec.to_string(), "structure-for:P69905[50-120]");
+    assert_eq!(spec.resolve_registry(None), Registry::Uniprot);
+    let query: StructureQuery = "P69905".parse().unwrap();
+    assert_eq!(query.range, None);
+    for value in ["P69905[0-10]", "P69905[20-10]", "P69905[50]", "P69905[a-b]"] {
+        let err = value.parse::<StructureQuery>().unwrap_err();
+        assert_matches!(err, KiraError::InvalidSpecifier(_));
+    }
+    let err = "structure-for:XX[1-10]"
+        .parse::<DatasetSpecifier>()
+        .unwrap_err();
+    assert_matches!(err, KiraError::InvalidUniprotId(_));
+}
+
+#[test]
+fn kegg_organism_codes_are_three_or_four_lowercase_letters() {
+    for code in ["hsa", "mmu", "ecoc"] {
+        assert_eq!(code.parse::<KeggOrganism>().unwrap().as_str(), code);
+    }
+    for code in ["hs", "hsapi", "HSA", "hs1", ""] {
+        assert!(
+            matches!(
+                code.parse::<KeggOrganism>(),
+                Err(KiraError::InvalidKeggOrganism(_))
+            ),
+            "{code}"
+        );
+    }
+    assert!("kegg:Hsa".parse::<DatasetSpecifier>().is_err());
+}
+
+#[test]
+fn infer_specifier_from_bare_ids() {
+    let cases = [
+        ("SRR014966", "srr:SRR014966"),
+        ("err1234567", "srr:ERR1234567"),
+        ("DRR000001", "srr:DRR000001"),
+        ("erx4404056", "srx:ERX4404056"),
+        ("GSE102902", "expression:GSE102902"),
+        ("gsm3577221", "expressionsample:GSM3577221"),
+        ("GCF_000005845.2", "genome:GCF_000005845.2"),
+        ("GCA_000001405.29", "genome:GCA_000001405.29"),
+        ("PRJNA680067", "bioproject:PRJNA680067"),
+        ("10.1038/s41586-020-2649-2", "doi:10.1038/s41586-020-2649-2"),
+        ("1lyz", "protein:1LYZ"),
+        ("4HHB", "protein:4HHB"),
+        ("P69905", "uniprot:P69905"),
+        ("q9y263", "uniprot:Q9Y263"),
+        ("A0A023GPI8", "uniprot:A0A023GPI8"),
+        ("  P00698  ", "uniprot:P00698"),
+    ];
+    for (value, expected) in cases {
+        let spec = DatasetSpecifier::infer(value).unwrap();
+        assert_eq!(spec.to_string(), expected, "{value}");
+    }
+}
+
+#[test]
+fn infer_specifier_keeps_explicit_types_and_keywords() {
+    let spec = DatasetSpecifier::infer("uniprot:P69905").unwrap();
+    assert_matches!(spec, DatasetSpecifier::Uniprot(_));
+    // `kegg` is four alphanumerics but names the knowledge base.
+    assert_matches!(
+        DatasetSpecifier::infer("kegg").unwrap(),
+        DatasetSpecifier::Kegg(None)
+    );
+    assert_matches!(DatasetSpecifier::infer("go").unwrap(), DatasetSpecifier::Go);
+    assert_matches!(
+        DatasetSpecifier::infer("structure-for:P69905[1-50]").unwrap(),
+        DatasetSpecifier::StructureFor(_)
+    );
+    // A DOI may contain colons of its own.
+    assert_eq!(
+        DatasetSpecifier::infer("10.1002/(SICI)1097-0258:2<3>")
+            .unwrap()
+            .to_string(),
+        "doi:10.1002/(sici)1097-0258:2<3>"
+    );
+}
+
+#[test]
+fn infer_specifier_rejects_unknown_shapes() {
+    assert_matches!(
+        DatasetSpecifier::infer("hello").unwrap_err(),
+        KiraError::InvalidSpecifier(_)
+    );
+    assert_matches!(
+        DatasetSpecifier::infer("PX9905").unwrap_err(),
+        KiraError::InvalidSpecifier(_)
+    );
+    assert_matches!(
+        DatasetSpecifier::infer("P123456789").unwrap_err(),
+        KiraError::InvalidSpecifier(_)
+    );
+    assert_matches!(
+        DatasetSpecifier::infer("ABC").unwrap_err(),
+        KiraError::InvalidSpecifier(_)
+    );
+    // Recognised prefixes with a malformed rest report the typed error.
+    assert_matches!(
+        DatasetSpecifier::infer("SRR12X").unwrap_err(),
+        KiraError::InvalidSrrId(_)
+    );
+    assert_matches!(
+        DatasetSpecifier::infer("GCF_ABC").unwrap_err(),
+        KiraError::InvalidGenomeAccession(_)
+    );
+    assert_matches!(
+        DatasetSpecifier::infer("protein:TOOLONG").unwrap_err(),
+        KiraError::InvalidProteinId(_)
+    );
+    assert_matches!(
+        DatasetSpecifier::infer("weird:thing").unwrap_err(),
+        KiraError::InvalidSpecifier(_)
+    );
+}
+
+#[test]
+fn bare_ids_list_every_type_they_could_be() {
+    let candidates = |value: &str| {
+        DatasetSpecifier::candidates(value)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(candidates("GSE102902"), ["expression:GSE102902"]);
+    assert_eq!(candidates("1lyz"), ["protein:1LYZ"]);
+    // Four characters fit a PDB id whatever their prefix.
+    assert_eq!(candidates("GSE1"), ["expression:GSE1", "protein:GSE1"]);
+    assert_eq!(candidates("SRR12X"), Vec::<String>::new());
+    assert!(candidates("hello").is_empty());
+    assert!(candidates("go").is_empty());
+    assert!(candidates("uniprot:P69905").is_empty());
+    // `infer` takes the likeliest.
+    assert_eq!(
+        DatasetSpecifier::infer("GSE1").unwrap().to_string(),
+        "expression:GSE1"
+    );
+}
+
+#[test]
+fn specifier_patterns_match_ids_by_glob() {
+    let pattern: SpecifierPattern = "srr:SRR0149*".parse().unwrap();
+    assert!(pattern.matches("srr", "SRR014966"));
+    assert!(pattern.matches("srr", "SRR0149"));
+    assert!(!pattern.matches("srr", "SRR015000"));
+    assert!(!pattern.matches("genome", "SRR014966"));
+
+    let pattern: SpecifierPattern = "protein:1l?z".parse().unwrap();
+    assert!(pattern.matches("protein", "1LYZ"));
+    assert!(!pattern.matches("protein", "1LZ"));
+    assert!(
+        "genome:*"
+            .parse::<SpecifierPattern>()
+            .unwrap()
+            .matches("genome", "GCF_000005845.2")
+    );
+}
+
+#[test]
+fn specifier_patterns_survive_shell_quoting() {
+    // POSIX shells strip the quotes; cmd.exe passes single quotes on and
+    // PowerShell may hand over double quotes from a nested invocation.
+    for quoted in ["protein:*", "'protein:*'", "\"protein:*\"", " 'protein:*' "] {
+        assert!(SpecifierPattern::is_pattern(quoted), "{quoted}");
+        let pattern: SpecifierPattern = quoted.parse().unwrap();
+        assert_eq!(pattern.to_string(), "protein:*");
+    }
+    for unbalanced in ["'protein:*", "protein:*\"", "'protein:*\""] {
+        assert_matches!(
+            unbalanced.parse::<SpecifierPattern>(),
+            Err(KiraError::InvalidSpecifier(_))
+        );
+    }
+}
+
+#[test]
+fn only_stored_types_with_wildcards_are_patterns() {
+    assert!(!SpecifierPattern::is_pattern("protein:1LYZ"));
+    assert!(!SpecifierPattern::is_pattern("doi:10.1000/x?y"));
+    assert!(!SpecifierPattern::is_pattern("go"));
+    assert_matches!(
+        "planet:*".parse::<SpecifierPattern>(),
+        Err(KiraError::InvalidSpecifier(_))
+    );
+    assert_matches!(
+        "protein:".parse::<SpecifierPattern>(),
+        Err(KiraError::InvalidSpecifier(_))
+    );
+}
@@ -0,0 +1,1131 @@
+//! Whole fetches through the real registry clients, served by a
+//! [`MockRegistry`] instead of the registries.
+
+mod support;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use camino::Utf8PathBuf;
+
+use kira_biodata_manager::app::{
+    ACTION_REFETCHED_CORRUPT, App, DoiSelection, FetchOptions, FetchOverrides, FetchResult,
+    VerifyCache,
+};
+use kira_biodata_manager::domain::{DatasetSpecifier, SrrId};
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::fs_util::sha256_file;
+use kira_biodata_manager::geo::GeoHttpClient;
+use kira_biodata_manager::http::HttpSettings;
+use kira_biodata_manager::knowledge::KnowledgeHttpClient;
+use kira_biodata_manager::manifest::{MANIFEST_FILE, Manifest};
+use kira_biodata_manager::ncbi::NcbiHttpClient;
+use kira_biodata_manager::providers::ensembl::EnsemblFile;
+use kira_biodata_manager::rcsb::RcsbHttpClient;
+use kira_biodata_manager::srr::{SrrClient, SrrDownloadOptions, ToolInfo};
+use kira_biodata_manager::store::Store;
+use kira_biodata_manager::uniprot::UniprotHttpClient;
+
+use support::{MockRegistry, NoProgress, RecordingSink, fixture, genome_package, gzip};
+
+const DOI: &str = "10.5555/kira.e2e.1";
+
+/// Stands in for the SRA toolkit: writes two single-end reads.
+struct FastqSrr;
+
+impl SrrClient for FastqSrr {
+    fn download_fastq(
+        &self,
+        id: &SrrId,
+        _paired: bool,
+        destination_dir: &Path,
+        _options: &SrrDownloadOptions,
+    ) -> Result<Vec<PathBuf>, KiraError> {
+        let path = destination_dir.join(format!("{}.fastq", id.as_str()));
+        std::fs::write(
+            &path,
+            "@r1\nACGTACGT\n+\nIIIIIIII\n@r2\nTTGGCCAA\n+\nIIIIIIII\n",
+        )
+        .unwrap();
+        Ok(vec![path])
+    }
+
+    fn tool_info(&self) -> ToolInfo {
+        ToolInfo::from_output(None, None)
+    }
+}
+
+type SandboxApp = App<
+    NcbiHttpClient,
+    RcsbHttpClient,
+    FastqSrr,
+    UniprotHttpClient,
+    GeoHttpClient,
+    KnowledgeHttpClient,
+>;
+
+/// An app whose every registry client talks to `registry`, storing into
+/// `project` and the shared `cache` under `root`.
+fn sandbox(root: &Path, project: &str, registry: &MockRegistry) -> SandboxApp {
+    sandbox_with(
+        Store::new_with_paths(
+            Utf8PathBuf::from_path_buf(root.join(project)).unwrap(),
+            Utf8PathBuf::from_path_buf(root.join("cache")).unwrap(),
+        ),
+        registry,
+    )
+}
+
+fn sandbox_with(store: Store, registry: &MockRegistry) -> SandboxApp {
+    let http = HttpSettings::new(None);
+    let base = registry.url();
+    App::new(
+        store,
+        NcbiHttpClient::with_base_url(http.clone(), base).unwrap(),
+        RcsbHttpClient::with_base_url(http.clone(), base).unwrap(),
+        FastqSrr,
+        UniprotHttpClient::with_base_url(http.clone(), base).unwrap(),
+        GeoHttpClient::with_base_url(http.clone(), base).unwrap(),
+        KnowledgeHttpClient::with_base_url(http.clone(), base).unwrap(),
+    )
+    .with_http_settings(http)
+    .with_doi_base_url(base)
+    .with_ensembl_base_url(base)
+}
+
+fn fetch(app: &SandboxApp, specifier: &str) -> FetchResult {
+    fetch_with(app, specifier, FetchOverrides::default())
+}
+
+fn fetch_with(app: &SandboxApp, specifier: &str, overrides: FetchOverrides) -> FetchResult {
+    app.fetch(
+        Some(specifier.parse::<DatasetSpecifier>().unwrap()),
+        None,
+        overrides,
+        FetchOptions::default(),
+        &NoProgress,
+    )
+    .unwrap()
+}
+
+fn with_protein_routes(registry: support::MockRegistryBuilder) -> support::MockRegistryBuilder {
+    registry
+        .get("/download/1LYZ.cif", fixture("rcsb/1LYZ.cif"))
+        .get("/rest/v1/core/entry/1LYZ", fixture("e2e/1LYZ_entry.json"))
+}
+
+#[test]
+fn a_protein_downloaded_once_is_served_from_the_cache_to_the_next_project() {
+    let registry = with_protein_routes(MockRegistry::builder()).start();
+    let temp = tempfile::tempdir().unwrap();
+
+    let first = fetch(&sandbox(temp.path(), "first", &registry), "protein:1LYZ");
+    let second = fetch(&sandbox(temp.path(), "second", &registry), "protein:1LYZ");
+
+    assert_eq!(first.items[0].action, "download");
+    assert_eq!(second.items[0].action, "cache");
+    assert_eq!(registry.count("GET /download/1LYZ.cif"), 1);
+    let stored = second.items[0].project_path.as_deref().unwrap();
+    assert_eq!(std::fs::read(stored).unwrap(), fixture("rcsb/1LYZ.cif"));
+}
+
+#[test]
+fn a_corrupt_cache_entry_is_evicted_and_downloaded_again() {
+    let registry = with_protein_routes(MockRegistry::builder()).start();
+    let temp = tempfile::tempdir().unwrap();
+    fetch(&sandbox(temp.path(), "first", &registry), "protein:1LYZ");
+    let cached = temp.path().join("cache/proteins/1LYZ/1LYZ.cif");
+    let original = std::fs::read(&cached).unwrap();
+    // A write cut short by a crash.
+    std::fs::write(&cached, &original[..original.len() / 2]).unwrap();
+
+    let fetch_verifying = |project: &str, mode: VerifyCache| {
+        let events = RecordingSink::default();
+        let result = sandbox(temp.path(), project, &registry)
+            .fetch(
+                Some("protein:1LYZ".parse().unwrap()),
+                None,
+                FetchOverrides::default(),
+                FetchOptions::builder().verify_cache(mode).build(),
+                &events,
+            )
+            .unwrap();
+        (result, events.messages())
+    };
+
+    let (trusted, _) = fetch_verifying("trusting", VerifyCache::Off);
+    assert_eq!(trusted.items[0].action, "cache");
+    assert_eq!(registry.count("GET /download/1LYZ.cif"), 1);
+
+    let (result, events) = fetch_verifying("second", VerifyCache::Fast);
+    assert_eq!(result.items[0].action, ACTION_REFETCHED_CORRUPT);
+    assert!(
+        events
+            .iter()
+            .any(|event| event == "cache.corrupt protein:1LYZ 1LYZ.cif"),
+        "{events:?}"
+    );
+    assert_eq!(registry.count("GET /download/1LYZ.cif"), 2);
+    assert_eq!(std::fs::read(&cached).unwrap(), original);
+    assert_eq!(
+        std::fs::read(temp.path().join("second/proteins/1LYZ/1LYZ.cif")).unwrap(),
+        original
+    );
+
+    let (third, _) = fetch_verifying("third", VerifyCache::Full);
+    assert_eq!(third.items[0].action, "cache");
+}
+
+#[test]
+fn an_unusable_cache_falls_back_to_the_project_or_is_left_out() {
+    let registry = with_protein_routes(MockRegistry::builder()).start();
+    let temp = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    // A file where the cache's parent directory should be.
+    std::fs::write(root.join("home"), "").unwrap();
+
+    let project = root.join("first");
+    let app = sandbox_with(
+        Store::new_with_paths(project.clone(), root.join("home/.cache")),
+        &registry,
+    );
+    let events = RecordingSink::default();
+    let result = app
+        .fetch(
+            Some("protein:1LYZ".parse().unwrap()),
+            None,
+            FetchOverrides::default(),
+            FetchOptions::default(),
+            &events,
+        )
+        .unwrap();
+    let events = events.messages();
+    assert!(
+        events
+            .iter()
+            .any(|event| event.starts_with(&format!("cache.fallback {project}/.cache; "))),
+        "{events:?}"
+    );
+    assert_eq!(
+        result.items[0].cache_path.as_deref(),
+        Some(project.join(".cache/proteins/1LYZ/1LYZ.cif").as_str())
+    );
+
+    let app = sandbox_with(Store::without_cache(root.join("second")), &registry);
+    let result = fetch(&app, "protein:1LYZ");
+    assert_eq!(result.items[0].action, "download");
+    assert_eq!(result.items[0].cache_path, None);
+    assert!(!root.join("second/.cache").exists());
+    assert_eq!(registry.count("GET /download/1LYZ.cif"), 2);
+}
+
+#[test]
+fn a_protein_keeps_its_fasta_and_entities_beside_the_structure() {
+    let registry = with_protein_routes(MockRegistry::builder())
+        .get("/fasta/entry/1LYZ", fixture("rcsb/1LYZ.fasta"))
+        .start();
+    let temp = tempfile::tempdir().unwrap();
+    let first = sandbox(temp.path(), "first", &registry);
+
+    fetch(&first, "protein:1LYZ");
+    fetch(&sandbox(temp.path(), "second", &registry), "protein:1LYZ");
+
+    for dir in [
+        "first/proteins/1LYZ",
+        "second/proteins/1LYZ",
+        "cache/proteins/1LYZ",
+    ] {
+        let fasta = std::fs::read(temp.path().join(dir).join("1LYZ.fasta")).unwrap();
+        assert_eq!(fasta, fixture("rcsb/1LYZ.fasta"), "{dir}");
+    }
+    assert_eq!(registry.count("GET /fasta/entry/1LYZ"), 1);
+    let metadata: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(temp.path().join("first/proteins/1LYZ/metadata.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(metadata["polymer_entity_count"], 1);
+    assert_eq!(
+        metadata["entities"],
+        serde_json::json!([
+            {"entity_id": "1", "chain_ids": ["A"], "organism": "Gallus gallus (9031)"}
+        ])
+    );
+    let info = first
+        .info("protein:1LYZ".parse().unwrap(), &NoProgress)
+        .unwrap();
+    assert_eq!(
+        info.details.unwrap()["sequences"],
+        serde_json::json!([{"entity_id": "1", "chain_ids": ["A"], "length": 129}])
+    );
+
+    let elsewhere = tempfile::tempdir().unwrap();
+    let skipped = fetch_with(
+        &sandbox(elsewhere.path(), "project", &registry),
+        "protein:1LYZ",
+        FetchOverrides {
+            no_fasta: true,
+            ..FetchOverrides::default()
+        },
+    );
+    assert_eq!(skipped.items[0].action, "download");
+    assert!(
+        !elsewhere
+            .path()
+            .join("project/proteins/1LYZ/1LYZ.fasta")
+            .exists()
+    );
+    assert_eq!(registry.count("GET /fasta/entry/1LYZ"), 1);
+}
+
+#[test]
+fn a_protein_is_fetched_from_the_configured_rcsb_mirror() {
+    let registry = MockRegistry::builder()
+        .get("/pdb/download/1LYZ.cif", fixture("rcsb/1LYZ.cif"))
+        .get(
+            "/pdb/rest/v1/core/entry/1LYZ",
+            fixture("e2e/1LYZ_entry.json"),
+        )
+        .start();
+    let temp = tempfile::tempdir().unwrap();
+    let http = HttpSettings::new(None).with_registry_bases(BTreeMap::from([(
+        "rcsb".to_string(),
+        format!("{}/pdb", registry.url()),
+    )]));
+    let app = App::new(
+        Store::new_with_paths(
+            Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap(),
+            Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap(),
+        ),
+        NcbiHttpClient::with_settings(http.clone()).unwrap(),
+        RcsbHttpClient::with_settings(http.clone()).unwrap(),
+        FastqSrr,
+        UniprotHttpClient::with_settings(http.clone()).unwrap(),
+        GeoHttpClient::with_settings(http.clone()).unwrap(),
+        KnowledgeHttpClient::with_settings(http.clone()).unwrap(),
+    )
+    .with_http_settings(http);
+
+    let result = fetch(&app, "protein:1LYZ");
+
+    assert_eq!(result.items[0].action, "download");
+    assert_eq!(registry.count("GET /pdb/download/1LYZ.cif"), 1);
+    let stored = result.items[0].project_path.as_deref().unwrap();
+    assert_eq!(std::fs::read(stored).unwrap(), fixture("rcsb/1LYZ.cif"));
+}
+
+#[test]
+fn a_genome_package_is_downloaded_and_extracted() {
+    let catalog = fixture("ncbi/GCF_000005845.2_catalog.json");
+    let registry = MockRegistry::builder()
+        .get(
+            "/datasets/v2/genome/accession/GCF_000005845.2/download",
+            genome_package(&catalog),
+        )
+        .content_type("application/zip")
+        .start();
+    let temp = tempfile::tempdir().unwrap();
+
+    let result = fetch(
+        &sandbox(temp.path(), "project", &registry),
+        "genome:GCF_000005845.2",
+    );
+
+    let item = &result.items[0];
+    assert_eq!(item.action, "download");
+    let project = Path::new(item.project_path.as_deref().unwrap());
+    let fasta = std::fs::read_to_string(
+        project.join("GCF_000005845.2/GCF_000005845.2_ASM584v2_genomic.fna"),
+    )
+    .or_else(|_| {
+        std::fs::read_to_string(
+            project.join("ncbi_dataset/data/GCF_000005845.2/GCF_000005845.2_ASM584v2_genomic.fna"),
+        )
+    })
+    .expect("genomic FASTA not extracted");
+    assert_eq!(fasta, ">chr1\nACGT\n");
+    assert_eq!(
+        registry.count("GET /datasets/v2/genome/accession/GCF_000005845.2/download"),
+        1
+    );
+}
+
+#[test]
+fn a_genome_carries_its_manifest_and_the_cache_check_reads_it() {
+    let catalog = fixture("ncbi/GCF_000005845.2_catalog.json");
+    let registry = MockRegistry::builder()
+        .get(GENOME_DOWNLOAD, genome_package(&catalog))
+        .content_type("application/zip")
+        .start();
+    let temp = tempfile::tempdir().unwrap();
+    let first = fetch(
+        &sandbox(temp.path(), "first", &registry),
+        "genome:GCF_000005845.2",
+    );
+
+    let item = &first.items[0];
+    let cache = PathBuf::from(item.cache_path.as_deref().unwrap());
+    let project = PathBuf::from(item.project_path.as_deref().unwrap());
+    let read_manifest = |dir: &Path| -> Manifest {
+        serde_json::from_slice(&std::fs::read(dir.join(MANIFEST_FILE)).unwrap()).unwrap()
+    };
+    let manifest = read_manifest(&project);
+    assert_eq!(read_manifest(&cache), manifest);
+    assert_eq!(
+        (manifest.dataset_type.as_str(), manifest.id.as_str()),
+        ("genome", "GCF_000005845.2")
+    );
+    let recorded = &item.download.as_ref().unwrap().sha256;
+    assert_eq!(manifest.files.len(), recorded.len());
+    for entry in &manifest.files {
+        let file = project.join(&entry.path);
+        assert_eq!(entry.bytes, std::fs::metadata(&file).unwrap().len());
+        assert_eq!(entry.sha256.as_ref(), recorded.get(&entry.path));
+    }
+
+    // Same size, different content: only the digest tells.
+    let fasta = manifest
+        .files
+        .iter()
+        .find(|entry| entry.path.ends_with("_genomic.fna"))
+        .unwrap();
+    std::fs::write(cache.join(&fasta.path), ">chr1\nTTTT\n").unwrap();
+    let second = fetch(
+        &sandbox(temp.path(), "second", &registry),
+        "genome:GCF_000005845.2",
+    );
+    assert_eq!(second.items[0].action, ACTION_REFETCHED_CORRUPT);
+    assert_eq!(registry.count(&format!("GET {GENOME_DOWNLOAD}")), 2);
+
+    // A manifest without digests still catches a truncated file by size.
+    let mut stripped = read_manifest(&cache);
+    for entry in &mut stripped.files {
+        entry.sha256 = None;
+    }
+    std::fs::write(
+        cache.join(MANIFEST_FILE),
+        serde_json::to_vec(&stripped).unwrap(),
+    )
+    .unwrap();
+    std::fs::write(cache.join(&fasta.path), ">chr1\n").unwrap();
+    let third = fetch(
+        &sandbox(temp.path(), "third", &registry),
+        "genome:GCF_000005845.2",
+    );
+    assert_eq!(third.items[0].action, ACTION_REFETCHED_CORRUPT);
+}
+
+const GENOME_DOWNLOAD: &str = "/datasets/v2/genome/accession/GCF_000005845.2/download";
+
+fn genome_package_dir(root: &Path) -> PathBuf {
+    root.join("cache/genomes/kira-bm-package-GCF_000005845.2_genome-gff3-protein-seq-report.0")
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(file.path(), bytes).unwrap();
+    sha256_file(file.path()).unwrap()
+}
+
+fn recorded_package_sha256(result: &FetchResult) -> String {
+    let project = Path::new(result.items[0].project_path.as_deref().unwrap());
+    let metadata: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(project.join("metadata.json")).unwrap())
+            .unwrap();
+    metadata["package_sha256"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn a_genome_package_cut_short_is_resumed() {
+    let package = genome_package(&fixture("ncbi/GCF_000005845.2_catalog.json"));
+    let registry = MockRegistry::builder()
+        .get(GENOME_DOWNLOAD, package.clone())
+        .content_type("application/zip")
+        .cut_after(package.len() / 2)
+        .start();
+    let temp = tempfile::tempdir().unwrap();
+
+    let result = fetch(
+        &sandbox(temp.path(), "project", &registry),
+        "genome:GCF_000005845.2",
+    );
+
+    assert_eq!(result.items[0].action, "download");
+    assert_eq!(registry.count(&format!("GET {GENOME_DOWNLOAD}")), 2);
+    assert_eq!(recorded_package_sha256(&result), sha256_hex(&package));
+    assert!(!genome_package_dir(temp.path()).exists());
+}
+
+#[test]
+fn a_genome_ncbi_refuses_is_not_retried() {
+    let registry = MockRegistry::builder()
+        .get(GENOME_DOWNLOAD, b"{\"message\":\"bad include\"}".to_vec())
+        .status(400)
+        .start();
+    let temp = tempfile::tempdir().unwrap();
+
+    let err = sandbox(temp.path(), "project", &registry)
+        .fetch(
+            Some("genome:GCF_000005845.2".parse().unwrap()),
+            None,
+            FetchOverrides::default(),
+            FetchOptions::default(),
+            &NoProgress,
+        )
+        .unwrap_err();
+
+    let KiraError::NcbiGenomeRejected {
+        accession,
+        include,
+        status,
+        ..
+    } = err.root()
+    else {
+        panic!("unexpected error: {err:?}");
+    };
+    assert_eq!(accession, "GCF_000005845.2");
+    assert_eq!(include, &["genome", "gff3", "protein", "seq-report"]);
+    assert_eq!(*status, 400);
+    assert!(err.help().unwrap().contains("include"));
+    assert_eq!(registry.count(&format!("GET {GENOME_DOWNLOAD}")), 1);
+}
+
+#[test]
+fn a_kept_genome_package_is_resumed_or_replaced() {
+    let package = genome_package(&fixture("ncbi/GCF_000005845.2_catalog.json"));
+    // A partial package left by an interrupted fetch has no checksum yet and
+    // is resumed; a complete one whose checksum disagrees is dropped.
+    for sidecar in [None, Some("0".repeat(64))] {
+        let registry = MockRegistry::builder()
+            .get(GENOME_DOWNLOAD, package.clone())
+            .content_type("application/zip")
+            .start();
+        let temp = tempfile::tempdir().unwrap();
+        let kept = genome_package_dir(temp.path());
+        std::fs::create_dir_all(&kept).unwrap();
+        match &sidecar {
+            None => {
+                std::fs::write(kept.join("dataset.zip"), &package[..package.len() / 3]).unwrap()
+            }
+            Some(digest) => {
+                std::fs::write(kept.join("dataset.zip"), &package).unwrap();
+                std::fs::write(kept.join("dataset.zip.sha256"), format!("{digest}\n")).unwrap();
+            }
+        }
+
+        let result = fetch(
+            &sandbox(temp.path(), "project", &registry),
+            "genome:GCF_000005845.2",
+        );
+
+        assert_eq!(result.items[0].action, "download");
+        assert_eq!(recorded_package_sha256(&result), sha256_hex(&package));
+        assert_eq!(registry.count(&format!("GET {GENOME_DOWNLOAD}")), 1);
+        assert!(!kept.exists());
+    }
+}
+
+#[test]
+fn an_expression_series_downloads_its_data_files_and_skips_documents() {
+    let registry = MockRegistry::builder()
+        .get(
+            "/geo/series/GSE300nnn/GSE300000/soft/GSE300000_family.soft.gz",
+            gzip(&fixture("e2e/GSE300000_family.soft")),
+        )
+        .get(
+            "/geo/series/GSE300nnn/GSE300000/suppl/GSE300000_counts.tsv.gz",
+            gzip(b"gene\tGSM3000001\tGSM3000002\nACTB\t10\t12\n"),
+        )
+        .get(
+            "/geo/series/GSE300nnn/GSE300000/suppl/GSE300000_protocol.pdf",
+            b"%PDF-1.4".to_vec(),
+        )
+        .start();
+    let temp = tempfile::tempdir().unwrap();
+
+    let result = fetch(
+        &sandbox(temp.path(), "project", &registry),
+        "expression:GSE300000",
+    );
+
+    assert_eq!(result.items[0].action, "download");
+    let project = Path::new(result.items[0].project_path.as_deref().unwrap());
+    let counts = walk(project)
+        .into_iter()
+        .find(|path| path.ends_with("GSE300000_counts.tsv.gz"))
+        .expect("counts file not stored");
+    assert_eq!(
+        std::fs::read(counts).unwrap(),
+        gzip(b"gene\tGSM3000001\tGSM3000002\nACTB\t10\t12\n")
+    );
+    assert!(
+        !walk(project)
+            .iter()
+            .any(|path| path.ends_with("GSE300000_protocol.pdf"))
+    );
+    assert_eq!(
+        registry.count("GET /geo/series/GSE300nnn/GSE300000/suppl/GSE300000_protocol.pdf"),
+        0
+    );
+}
+
+fn with_doi_routes(registry: support::MockRegistryBuilder) -> support::MockRegistryBuilder {
+    with_protein_routes(registry)
+        .get(
+            "/works/10.5555%2Fkira.e2e.1",
+            fixture("e2e/crossref_work.json"),
+        )
+        .post("/graphql", fixture("e2e/rcsb_graphql.json"))
+        .get_matching(
+            "/entrez/eutils/esearch.fcgi",
+            "db=sra",
+            fixture("e2e/esearch_sra.json"),
+        )
+        .get_matching(
+            "/entrez/eutils/esummary.fcgi",
+            "db=sra",
+            fixture("e2e/esummary_sra.json"),
+        )
+}
+
+fn download_all() -> FetchOverrides {
+    FetchOverrides::builder()
+        .doi_selection(DoiSelection {
+            download_all: true,
+            ..DoiSelection::default()
+        })
+        .build()
+}
+
+#[test]
+fn a_doi_fans_out_into_the_protein_and_run_it_cites() {
+    let registry = with_doi_routes(MockRegistry::builder()).start();
+    let temp = tempfile::tempdir().unwrap();
+
+    let result = fetch_with(
+        &sandbox(temp.path(), "project", &registry),
+        &format!("doi:{DOI}"),
+        download_all(),
+    );
+
+    let mut fetched = result
+        .items
+        .iter()
+        .map(|item| format!("{}:{} {}", item.dataset_type, item.id, item.action))
+        .collect::<Vec<_>>();
+    fetched.sort();
+    assert_eq!(
+        fetched,
+        ["protein:1LYZ download", "srr:SRR5000001 download"]
+    );
+    let summary = result.summary.unwrap();
+    assert_eq!(summary.resolved_targets, 2);
+    assert_eq!(summary.unresolved, 0);
+    assert_eq!(registry.count("GET /works/"), 1);
+}
+
+#[test]
+fn an_experiment_is_fetched_as_its_runs() {
+    let registry = MockRegistry::builder()
+        .get_matching(
+            "/entrez/eutils/esearch.fcgi",
+            "SRX9000002",
+            fixture("e2e/esearch_srx.json"),
+        )
+        .get_matching(
+            "/entrez/eutils/esummary.fcgi",
+            "id=9000002",
+            fixture("e2e/esummary_srx.json"),
+        )
+        .start();
+    let temp = tempfile::tempdir().unwrap();
+    let app = sandbox(temp.path(), "project", &registry);
+    let progress = RecordingSink::default();
+
+    let result = app
+        .fetch(
+            Some("srr:srx9000002".parse().unwrap()),
+            None,
+            FetchOverrides::default(),
+            FetchOptions::default(),
+            &progress,
+        )
+        .unwrap();
+
+    let fetched = result
+        .items
+        .iter()
+        .map(|item| format!("{}:{} {}", item.dataset_type, item.id, item.action))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        fetched,
+        ["srr:SRR5000002 download", "srr:SRR5000003 download"]
+    );
+    let summary = result.summary.unwrap();
+    assert_eq!(summary.kind, "srx");
+    assert_eq!(summary.experiment.as_deref(), Some("SRX9000002"));
+    assert_eq!((summary.resolved_targets, summary.unresolved), (2, 0));
+    assert!(
+        progress
+            .messages()
+            .contains(&"srx.runs SRX9000002 SRR5000002,SRR5000003".to_string())
+    );
+    for item in &result.items {
+        let record: serde_json::Value = serde_json::from_slice(
+            &std::fs::read(
+                temp.path()
+                    .join(format!("project/metadata/srr/{}.json", item.id)),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(record["experiment"], "SRX9000002", "{}", item.id);
+    }
+}
+
+#[test]
+fn fetched_items_are_streamed_as_they_finish() {
+    let registry = with_doi_routes(MockRegistry::builder()).start();
+    let temp = tempfile::tempdir().unwrap();
+    let app = sandbox(temp.path(), "project", &registry);
+    let progress = RecordingSink::default();
+    let mut streamed = Vec::new();
+
+    let result = app
+        .fetch_streaming(
+            Some(format!("doi:{DOI}").parse().unwrap()),
+            None,
+            download_all(),
+            FetchOptions::default(),
+            &progress,
+            |item| {
+                // Each item is handed over once it has ended, before the
+                // next begins and before the sink gets it.
+                let label = format!("{}:{}", item.dataset_type, item.id);
+                let messages = progress.messages();
+                let last = messages
+                    .iter()
+                    .rfind(|message| message.starts_with("item."));
+                assert_eq!(last, Some(&format!("item.end {label}")));
+                assert_eq!(progress.items().len(), streamed.len());
+                streamed.push(label);
+            },
+        )
+        .unwrap();
+
+    let fetched = result
+        .items
+        .iter()
+        .map(|item| format!("{}:{}", item.dataset_type, item.id))
+        .collect::<Vec<_>>();
+    assert_eq!(fetched.len(), 2);
+    assert_eq!(streamed, fetched);
+    assert_eq!(progress.items(), fetched);
+}
+
+#[test]
+fn a_doi_is_only_reported_until_targets_are_selected() {
+    let registry = with_doi_routes(MockRegistry::builder()).start();
+    let temp = tempfile::tempdir().unwrap();
+    let app = sandbox(temp.path(), "project", &registry);
+    let specifier = format!("doi:{DOI}");
+
+    let report = fetch(&app, &specifier);
+    assert!(report.items.is_empty());
+    let targets = report.summary.unwrap().doi_selection.unwrap();
+    assert!(targets.selected.is_empty());
+    let skipped = targets
+        .skipped
+        .iter()
+        .map(|target| format!("{} {}", target.specifier, target.reason.as_deref().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        skipped,
+        ["protein:1LYZ report-only", "srr:SRR5000001 report-only"]
+    );
+    assert_eq!(registry.count("GET /download/"), 0);
+
+    let only_runs = FetchOverrides::builder()
+        .doi_selection(DoiSelection {
+            only: vec!["srr".to_string()],
+            ..DoiSelection::default()
+        })
+        .build();
+    let result = fetch_with(&app, &specifier, only_runs);
+    let fetched = result
+        .items
+        .iter()
+        .map(|item| format!("{}:{}", item.dataset_type, item.id))
+        .collect::<Vec<_>>();
+    assert_eq!(fetched, ["srr:SRR5000001"]);
+    assert_eq!(registry.count("GET /download/"), 0);
+    // The resolution is stored, so later selections do not resolve again.
+    assert_eq!(registry.count("GET /works/"), 1);
+
+    let again = fetch(&app, &specifier)
+        .summary
+        .unwrap()
+        .doi_selection
+        .unwrap();
+    let run = again
+        .skipped
+        .iter()
+        .find(|target| target.specifier == "srr:SRR5000001")
+        .unwrap();
+    assert!(run.bytes.is_some_and(|bytes| bytes > 0));
+}
+
+#[test]
+fn a_doi_crossref_does_not_know_is_read_from_datacite() {
+    let registry = with_doi_routes(MockRegistry::builder())
+        .get(
+            "/dois/10.5281%2Fzenodo.5000",
+            fixture("e2e/datacite_doi.json"),
+        )
+        .start();
+    let temp = tempfile::tempdir().unwrap();
+    let app = sandbox(temp.path(), "project", &registry);
+    let specifier = "doi:10.5281/zenodo.5000";
+
+    let result = fetch_with(&app, specifier, download_all());
+    let fetched = result
+        .items
+        .iter()
+        .map(|item| format!("{}:{}", item.dataset_type, item.id))
+        .collect::<Vec<_>>();
+    assert_eq!(fetched, ["srr:SRR5000001"]);
+    assert_eq!(registry.count("GET /works/10.5281%2Fzenodo.5000"), 1);
+    // The paper it supplements is recorded, not read.
+    assert_eq!(registry.count("GET /works/10.5555%2Fkira.e2e.1"), 0);
+
+    let stored = Store::new_with_paths(
+        Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap(),
+        Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap(),
+    )
+    .project_doi_resolution_path(&"10.5281/zenodo.5000".parse().unwrap());
+    let resolution =
+        || serde_json::from_slice::<serde_json::Value>(&std::fs::read(&stored).unwrap()).unwrap();
+    assert_eq!(resolution()["agency"], "datacite");
+    assert_eq!(
+        resolution()["related"],
+        serde_json::json!([
+            {"doi": "10.5555/kira.e2e.1", "relation": "IsSupplementTo", "followed": false}
+        ])
+    );
+
+    let followed = FetchOverrides::builder()
+        .follow_related(true)
+        .doi_selection(DoiSelection {
+            download_all: true,
+            ..DoiSelection::default()
+        })
+        .build();
+    let mut fetched = fetch_with(&app, specifier, followed)
+        .items
+        .iter()
+        .map(|item| format!("{}:{}", item.dataset_type, item.id))
+        .collect::<Vec<_>>();
+    fetched.sort();
+    assert_eq!(fetched, ["protein:1LYZ", "srr:SRR5000001"]);
+    assert_eq!(registry.count("GET /works/10.5555%2Fkira.e2e.1"), 1);
+    assert_eq!(resolution()["related"][0]["followed"], true);
+}
+
+fn refetch(app: &SandboxApp, specifier: &str) -> FetchResult {
+    app.fetch(
+        Some(specifier.parse::<DatasetSpecifier>().unwrap()),
+        None,
+        FetchOverrides::default(),
+        FetchOptions {
+            force: true,
+            ..FetchOptions::default()
+        },
+        &NoProgress,
+    )
+    .unwrap()
+}
+
+fn tagged_protein_routes(tag: &str) -> MockRegistry {
+    MockRegistry::builder()
+        .get("/download/1LYZ.cif", fixture("rcsb/1LYZ.cif"))
+        .etag(&format!("\"cif-{tag}\""))
+        .get("/rest/v1/core/entry/1LYZ", fixture("e2e/1LYZ_entry.json"))
+        .etag(&format!("\"entry-{tag}\""))
+        .start()
+}
+
+#[test]
+fn a_forced_refetch_asks_rcsb_whether_the_protein_changed() {
+    let registry = tagged_protein_routes("v1");
+    let temp = tempfile::tempdir().unwrap();
+    let app = sandbox(temp.path(), "project", &registry);
+
+    let first = fetch(&app, "protein:1LYZ");
+    let validators = &first.items[0].download.as_ref().unwrap().validators;
+    assert_eq!(
+        validators["https://files.rcsb.org/download/1LYZ.cif"]
+            .etag
+            .as_deref(),
+        Some("\"cif-v1\"")
+    );
+
+    let unchanged = refetch(&app, "protein:1LYZ");
+    assert_eq!(unchanged.items[0].action, "not-modified");
+    let stored = unchanged.items[0].project_path.as_deref().unwrap();
+    assert_eq!(std::fs::read(stored).unwrap(), fixture("rcsb/1LYZ.cif"));
+    assert_eq!(
+        unchanged.items[0].download.as_ref().unwrap().validators,
+        *validators
+    );
+
+    let updated = tagged_protein_routes("v2");
+    let changed = refetch(&sandbox(temp.path(), "project", &updated), "protein:1LYZ");
+    assert_eq!(changed.items[0].action, "download");
+    assert_eq!(updated.count("GET /download/1LYZ.cif"), 1);
+}
+
+#[test]
+fn a_forced_refetch_of_an_unchanged_uniprot_entry_keeps_the_stored_files() {
+    let registry = MockRegistry::builder()
+        .get("/uniprotkb/P69905.json", fixture("uniprot_P69905.json"))
+        .etag("\"json-v1\"")
+        .get(
+            "/uniprotkb/P69905.fasta",
+            ">sp|P69905|HBA_HUMAN\nMVLSPADKTNVKAAWGKVGA\n",
+        )
+        .etag("\"fasta-v1\"")
+        .start();
+    let temp = tempfile::tempdir().unwrap();
+    let app = sandbox(temp.path(), "project", &registry);
+
+    assert_eq!(fetch(&app, "uniprot:P69905").items[0].action, "download");
+    let project = temp.path().join("project");
+    let before = walk(&project)
+        .into_iter()
+        .filter(|path| path.extension().is_some_and(|ext| ext == "fasta"))
+        .collect::<Vec<_>>();
+    assert_eq!(before.len(), 1);
+
+    let unchanged = refetch(&app, "uniprot:P69905");
+    assert_eq!(unchanged.items[0].action, "not-modified");
+    assert_eq!(
+        std::fs::read_to_string(&before[0]).unwrap(),
+        ">sp|P69905|HBA_HUMAN\nMVLSPADKTNVKAAWGKVGA\n"
+    );
+    assert_eq!(registry.count("GET /uniprotkb/P69905.json"), 2);
+    assert_eq!(registry.count("GET /uniprotkb/P69905.fasta"), 2);
+}
+
+const PROTEOME_FASTA: &str =
+    ">sp|P69905|HBA_HUMAN\nMVLSPADKTNVKAAWGKVGA\n>sp|P68871|HBB_HUMAN\nMVHLTPEEKSAVTALWGKVN\n";
+
+fn proteome_registry(fasta: Vec<u8>) -> MockRegistry {
+    MockRegistry::builder()
+        .get(
+            "/proteomes/UP000005640",
+            fixture("uniprot_proteome_UP000005640.json"),
+        )
+        .get_matching("/uniprotkb/stream", "proteome:UP000005640", fasta)
+        .start()
+}
+
+fn gunzip(path: &Path) -> String {
+    let mut text = String::new();
+    std::io::Read::read_to_string(
+        &mut flate2::read::GzDecoder::new(std::fs::File::open(path).unwrap()),
+        &mut text,
+    )
+    .unwrap();
+    text
+}
+
+#[test]
+fn a_proteome_is_streamed_to_gzip_with_its_counts_and_taxonomy() {
+    let registry = proteome_registry(gzip(PROTEOME_FASTA.as_bytes()));
+    let temp = tempfile::tempdir().unwrap();
+
+    let first = fetch(
+        &sandbox(temp.path(), "first", &registry),
+        "uniprot-proteome:UP000005640",
+    );
+    let item = &first.items[0];
+    assert_eq!(item.action, "download");
+    assert_eq!(item.dataset_type, "uniprot-proteome");
+    let dir = PathBuf::from(item.project_path.as_deref().unwrap());
+    assert!(dir.ends_with("proteomes/UP000005640"));
+    assert_eq!(
+        std::fs::read(dir.join("UP000005640.fasta.gz")).unwrap(),
+        gzip(PROTEOME_FASTA.as_bytes())
+    );
+    let metadata: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.join("metadata.json")).unwrap()).unwrap();
+    assert_eq!(metadata["protein_count"], 82485);
+    assert_eq!(metadata["gene_count"], 20597);
+    assert_eq!(metadata["organism"], "Homo sapiens");
+    assert_eq!(metadata["taxon_id"], 9606);
+    assert_eq!(metadata["components"][1]["protein_count"], 3853);
+    assert!(
+        temp.path()
+            .join("first/metadata/uniprot-proteome/UP000005640.json")
+            .is_file()
+    );
+
+    let second = fetch(
+        &sandbox(temp.path(), "second", &registry),
+        "uniprot-proteome:UP000005640",
+    );
+    assert_eq!(second.items[0].action, "cache");
+    assert_eq!(registry.count("GET /uniprotkb/stream"), 1);
+}
+
+#[test]
+fn an_uncompressed_proteome_stream_is_stored_compressed() {
+    let registry = proteome_registry(PROTEOME_FASTA.as_bytes().to_vec());
+    let temp = tempfile::tempdir().unwrap();
+
+    let result = fetch(
+        &sandbox(temp.path(), "project", &registry),
+        "uniprot-proteome:UP000005640",
+    );
+
+    let dir = PathBuf::from(result.items[0].project_path.as_deref().unwrap());
+    assert_eq!(gunzip(&dir.join("UP000005640.fasta.gz")), PROTEOME_FASTA);
+}
+
+const ENSEMBL_GTF: &str = "1\tensembl\tgene\t1\t4\t.\t+\t.\tgene_id \"G1\";\n";
+
+/// Release 110 of `homo_sapiens` with its directory listings, the current
+/// release being 110.
+fn ensembl_registry() -> MockRegistry {
+    let listing = |names: &[&str]| {
+        let links = names
+            .iter()
+            .map(|name| format!("<a href=\"{name}\">{name}</a>"))
+            .collect::<String>();
+        format!("<html><body><a href=\"../\">../</a>{links}</body></html>")
+    };
+    let release = "/pub/release-110";
+    MockRegistry::builder()
+        .get("/pub/VERSION", "110\n")
+        .get(
+            &format!("{release}/fasta/homo_sapiens/dna/"),
+            listing(&[
+                "CHECKSUMS",
+                "Homo_sapiens.GRCh38.dna.toplevel.fa.gz",
+                "Homo_sapiens.GRCh38.dna.primary_assembly.fa.gz",
+            ]),
+        )
+        .get(
+            &format!(
+                "{release}/fasta/homo_sapiens/dna/Homo_sapiens.GRCh38.dna.primary_assembly.fa.gz"
+            ),
+            gzip(b">1\nACGT\n"),
+        )
+        .get(
+            &format!("{release}/gtf/homo_sapiens/"),
+            listing(&[
+                "Homo_sapiens.GRCh38.110.abinitio.gtf.gz",
+                "Homo_sapiens.GRCh38.110.chr.gtf.gz",
+                "Homo_sapiens.GRCh38.110.gtf.gz",
+            ]),
+        )
+        .get(
+            &format!("{release}/gtf/homo_sapiens/Homo_sapiens.GRCh38.110.gtf.gz"),
+            gzip(ENSEMBL_GTF.as_bytes()),
+        )
+        .get(
+            &format!("{release}/gff3/homo_sapiens/"),
+            listing(&["README"]),
+        )
+        .start()
+}
+
+#[test]
+fn an_ensembl_genome_is_pinned_to_the_current_release_and_served_from_the_cache() {
+    let registry = ensembl_registry();
+    let temp = tempfile::tempdir().unwrap();
+
+    let first = fetch(
+        &sandbox(temp.path(), "first", &registry),
+        "genome:ensembl:homo_sapiens",
+    );
+
+    let item = &first.items[0];
+    assert_eq!(
+        (item.id.as_str(), item.source.as_str(), item.action.as_str()),
+        ("ensembl:homo_sapiens@110", "ensembl", "download")
+    );
+    let dir = PathBuf::from(item.project_path.as_deref().unwrap());
+    assert!(dir.ends_with("genomes/ensembl/homo_sapiens/110"));
+    assert_eq!(
+        gunzip(&dir.join("Homo_sapiens.GRCh38.110.gtf.gz")),
+        ENSEMBL_GTF
+    );
+    assert!(
+        dir.join("Homo_sapiens.GRCh38.dna.primary_assembly.fa.gz")
+            .is_file()
+    );
+    let meta: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.join("metadata.json")).unwrap()).unwrap();
+    assert_eq!(meta["release"], 110);
+    assert_eq!(meta["assembly"], "GRCh38");
+    assert_eq!(meta["include"], serde_json::json!(["dna", "gtf"]));
+    let record: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(
+            temp.path()
+                .join("first/metadata/genome/ensembl%3Ahomo_sapiens@110.json"),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(record["id"], "ensembl:homo_sapiens@110");
+    assert_eq!(record["assembly_name"], "GRCh38");
+
+    let second = fetch(
+        &sandbox(temp.path(), "second", &registry),
+        "genome:ensembl:homo_sapiens@110",
+    );
+    assert_eq!(second.items[0].action, "cache");
+    assert_eq!(registry.count("GET /pub/VERSION"), 1);
+    assert_eq!(
+        registry.count("GET /pub/release-110/gtf/homo_sapiens/Homo_sapiens"),
+        1
+    );
+}
+
+#[test]
+fn an_ensembl_file_the_release_does_not_list_fails_the_fetch() {
+    let registry = ensembl_registry();
+    let temp = tempfile::tempdir().unwrap();
+    let app = sandbox(temp.path(), "project", &registry);
+
+    let err = app
+        .fetch(
+            Some("genome:ensembl:homo_sapiens@110".parse().unwrap()),
+            None,
+            FetchOverrides::builder()
+                .ensembl_include([EnsemblFile::Gff3])
+                .build(),
+            FetchOptions::default(),
+            &NoProgress,
+        )
+        .unwrap_err();
+
+    assert_matches::assert_matches!(
+        err.root(),
+        KiraError::EnsemblFileMissing { file, release: 110, .. } if file == "gff3"
+    );
+    assert!(!temp.path().join("project/genomes/ensembl").exists());
+}
+
+fn walk(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).unwrap().filter_map(Result::ok) {
+        let path = entry.path();
+        if path.is_dir() {
+            files.extend(walk(&path));
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
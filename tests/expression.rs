@@ -0,0 +1,660 @@
+mod support;
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use camino::Utf8PathBuf;
+
+use kira_biodata_manager::app::{App, FetchOptions, FetchOverrides, ProgressSink};
+use kira_biodata_manager::domain::{DatasetSpecifier, GeoSampleAccession, GeoSeriesAccession};
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::fs_util::{extract_tar, write_hashed};
+use kira_biodata_manager::geo::{
+    BundlePreference, FileCategory, GeoClient, classify_supplementary, detect_10x_files,
+    detect_10x_h5, extract_sample_series, extract_series_relations, extract_series_samples,
+    filter_urls_by_samples, is_10x_archive, measure_10x_bundle, read_mtx_header, tenx_h5_component,
+};
+use kira_biodata_manager::store::Store;
+
+use support::{NoProgress, UnusedKnowledge, UnusedNcbi, UnusedRcsb, UnusedSrr, UnusedUniprot};
+
+#[derive(Default)]
+struct FixtureGeo {
+    downloads: Mutex<Vec<String>>,
+}
+
+impl GeoClient for FixtureGeo {
+    fn fetch_soft_text(&self, accession: &GeoSeriesAccession) -> Result<String, KiraError> {
+        Ok(fixture(accession.as_str()))
+    }
+
+    fn fetch_sample_soft_text(&self, accession: &GeoSampleAccession) -> Result<String, KiraError> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/geo")
+            .join(format!("{}.soft", accession.as_str()));
+        std::fs::read_to_string(path).map_err(|err| KiraError::GeoHttp(err.to_string()))
+    }
+
+    fn download_url(
+        &self,
+        url: &str,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        self.downloads.lock().unwrap().push(url.to_string());
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/geo")
+            .join(url.rsplit('/').next().unwrap());
+        if url.contains(".tar") && fixture.exists() {
+            let bytes =
+                std::fs::read(fixture).map_err(|err| KiraError::Filesystem(err.to_string()))?;
+            return write_hashed(destination, &bytes)
+                .map_err(|err| KiraError::Filesystem(err.to_string()));
+        }
+        write_hashed(destination, url.as_bytes())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+}
+
+fn fixture(accession: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/geo")
+        .join(format!("{accession}_family.soft"));
+    std::fs::read_to_string(path).unwrap()
+}
+
+fn temp_store(temp: &tempfile::TempDir) -> Store {
+    let project = Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap();
+    let cache = Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    Store::new_with_paths(project, cache)
+}
+
+fn options() -> FetchOptions {
+    FetchOptions::default()
+}
+
+#[test]
+fn parses_series_relations_and_samples() {
+    let sub = fixture("GSE200001");
+    let relations = extract_series_relations(&sub);
+    assert_eq!(relations.len(), 1);
+    assert_eq!(relations[0].relation, "SubSeries of");
+    assert_eq!(relations[0].accession, "GSE200000");
+    assert_eq!(
+        extract_series_samples(&sub),
+        vec!["GSM1000001".to_string(), "GSM1000002".to_string()]
+    );
+
+    let urls = vec![
+        "https://example.org/GSM1000001_counts.tsv.gz".to_string(),
+        "https://example.org/GSM10000010_counts.tsv.gz".to_string(),
+        "https://example.org/GSE200000_RAW.tar".to_string(),
+    ];
+    let samples = vec!["GSM1000001".to_string()];
+    assert_eq!(
+        filter_urls_by_samples(&urls, &samples),
+        vec!["https://example.org/GSM1000001_counts.tsv.gz".to_string()]
+    );
+}
+
+#[test]
+fn empty_subseries_reports_related_series() {
+    let temp = tempfile::tempdir().unwrap();
+    let app = App::new(
+        temp_store(&temp),
+        UnusedNcbi,
+        UnusedRcsb,
+        UnusedSrr,
+        UnusedUniprot,
+        FixtureGeo::default(),
+        UnusedKnowledge,
+    );
+    let err = app
+        .fetch(
+            Some(DatasetSpecifier::Expression("GSE200001".parse().unwrap())),
+            None,
+            FetchOverrides::default(),
+            options(),
+            &NoProgress,
+        )
+        .unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("GSE200000"), "{message}");
+    assert!(message.contains("--follow-superseries"), "{message}");
+}
+
+#[test]
+fn follow_superseries_fetches_subseries_samples() {
+    let temp = tempfile::tempdir().unwrap();
+    let store = temp_store(&temp);
+    let project_dir = store.project_expression_dir(&"GSE200001".parse().unwrap());
+    let app = App::new(
+        store,
+        UnusedNcbi,
+        UnusedRcsb,
+        UnusedSrr,
+        UnusedUniprot,
+        FixtureGeo::default(),
+        UnusedKnowledge,
+    );
+    let overrides = FetchOverrides {
+        follow_superseries: true,
+        ..FetchOverrides::default()
+    };
+    let result = app
+        .fetch(
+            Some(DatasetSpecifier::Expression("GSE200001".parse().unwrap())),
+            None,
+            overrides,
+            options(),
+            &NoProgress,
+        )
+        .unwrap();
+    assert_eq!(result.items[0].action, "download");
+    assert_eq!(result.items[0].id, "GSE200001");
+
+    let meta: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(project_dir.join("metadata/metadata.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(meta["accession"], "GSE200001");
+    assert_eq!(meta["source_series"], "GSE200000");
+    assert_eq!(meta["relations"][0]["relation"], "SubSeries of");
+    assert_eq!(
+        meta["files"],
+        serde_json::json!(["GSM1000001_counts.tsv.gz", "GSM1000002_counts.tsv.gz"])
+    );
+    assert!(
+        project_dir
+            .join("metadata/geo_soft_GSE200000.txt")
+            .as_std_path()
+            .exists()
+    );
+}
+
+#[test]
+fn series_metadata_from_soft_is_recorded_and_shown_by_info() {
+    let temp = tempfile::tempdir().unwrap();
+    let store = temp_store(&temp);
+    let app = App::new(
+        store,
+        UnusedNcbi,
+        UnusedRcsb,
+        UnusedSrr,
+        UnusedUniprot,
+        FixtureGeo::default(),
+        UnusedKnowledge,
+    );
+    let specifier = || DatasetSpecifier::Expression("GSE200030".parse().unwrap());
+    app.fetch(
+        Some(specifier()),
+        None,
+        FetchOverrides::default(),
+        options(),
+        &NoProgress,
+    )
+    .unwrap();
+
+    let details = app.info(specifier(), &NoProgress).unwrap().details.unwrap();
+    assert_eq!(
+        details["title"],
+        "Transcriptomes of human and mouse liver across two sequencing platforms"
+    );
+    assert!(
+        details["summary"]
+            .as_str()
+            .unwrap()
+            .ends_with("to compare platform effects.")
+    );
+    assert_eq!(
+        details["platforms"],
+        serde_json::json!(["GPL24676", "GPL21103"])
+    );
+    assert_eq!(details["n_samples"], 4);
+    assert_eq!(details["samples"][0]["accession"], "GSM6000001");
+    assert_eq!(details["samples"][0]["title"], "liver_human_rep1");
+    assert_eq!(details["samples"][2]["organism"], "Mus musculus");
+}
+
+#[test]
+fn classifies_geo_supplementary_names() {
+    let cases = [
+        ("GSE102902_RAW.tar", FileCategory::Data),
+        ("GSE115978_counts.csv.gz", FileCategory::Data),
+        ("GSM3087619_MUT_1_barcodes.tsv.gz", FileCategory::Data),
+        ("GSM3087619_MUT_1_matrix.mtx.gz", FileCategory::Data),
+        (
+            "GSE131907_Lung_Cancer_raw_UMI_matrix.txt.gz",
+            FileCategory::Data,
+        ),
+        (
+            "GSE120575_patient_ID_single_cells.txt.gz",
+            FileCategory::Data,
+        ),
+        (
+            "GSM4058963_filtered_feature_bc_matrix.h5",
+            FileCategory::Data,
+        ),
+        ("GSE96583_batch2.total.tsne.df.tsv.gz", FileCategory::Data),
+        ("GSM1234567_H3K27ac_peaks.bed.gz", FileCategory::Data),
+        ("GSM1234567_H3K27ac.bw", FileCategory::Data),
+        ("GSE139555_all_metadata.xlsx", FileCategory::Data),
+        ("GSE150728_cell_annotations.rds.gz", FileCategory::Data),
+        ("GSE999999_unknown.xyz", FileCategory::Data),
+        ("GSE999999_noextension", FileCategory::Data),
+        (
+            "GSE138852_Supplementary_Methods.pdf",
+            FileCategory::Document,
+        ),
+        ("GSE138852_protocol.docx", FileCategory::Document),
+        ("GSE138852_slides.pptx", FileCategory::Document),
+        ("GSE138852_README.txt", FileCategory::Document),
+        ("README.txt", FileCategory::Document),
+        ("GSE138852_Figure1.tif", FileCategory::Image),
+        ("GSE138852_histology.tif.gz", FileCategory::Image),
+        ("GSM4284316_P1_spatial_image.png", FileCategory::Image),
+        ("GSM4284316_tissue_lowres_image.jpg", FileCategory::Image),
+        ("GSE138852_report.html", FileCategory::Other),
+    ];
+    for (name, expected) in cases {
+        let url = format!("ftp://ftp.ncbi.nlm.nih.gov/geo/series/GSE138nnn/GSE138852/suppl/{name}");
+        assert_eq!(classify_supplementary(&url), expected, "{name}");
+    }
+}
+
+fn fetch_gse200002(include: Vec<FileCategory>) -> (serde_json::Value, Vec<String>) {
+    let temp = tempfile::tempdir().unwrap();
+    let store = temp_store(&temp);
+    let project_dir = store.project_expression_dir(&"GSE200002".parse().unwrap());
+    let geo = FixtureGeo::default();
+    let app = App::new(
+        store,
+        UnusedNcbi,
+        UnusedRcsb,
+        UnusedSrr,
+        UnusedUniprot,
+        geo,
+        UnusedKnowledge,
+    );
+    app.fetch(
+        Some(DatasetSpecifier::Expression("GSE200002".parse().unwrap())),
+        None,
+        FetchOverrides::builder()
+            .include_categories(include)
+            .build(),
+        options(),
+        &NoProgress,
+    )
+    .unwrap();
+    let meta: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(project_dir.join("metadata/metadata.json")).unwrap(),
+    )
+    .unwrap();
+    let files = meta["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|value| value.as_str().unwrap().to_string())
+        .collect();
+    (meta, files)
+}
+
+#[test]
+fn expression_fetch_skips_documents_and_images_by_default() {
+    let (meta, files) = fetch_gse200002(Vec::new());
+    assert_eq!(
+        files,
+        vec!["GSE200002_RAW.tar", "GSE200002_gene_counts.tsv.gz"]
+    );
+    let skipped = meta["skipped"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| {
+            format!(
+                "{}={}",
+                item["name"].as_str().unwrap(),
+                item["category"].as_str().unwrap()
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        skipped,
+        vec![
+            "GSE200002_README.txt=document",
+            "GSE200002_Supplementary_Figures.pdf=document",
+            "GSE200002_histology.tif.gz=image",
+        ]
+    );
+    assert_eq!(meta["file_categories"]["GSE200002_RAW.tar"], "data");
+}
+
+#[test]
+fn expression_fetch_includes_enabled_categories() {
+    let (meta, files) = fetch_gse200002(vec![FileCategory::Document]);
+    assert!(files.contains(&"GSE200002_Supplementary_Figures.pdf".to_string()));
+    assert!(files.contains(&"GSE200002_README.txt".to_string()));
+    assert!(!files.contains(&"GSE200002_histology.tif.gz".to_string()));
+    assert_eq!(meta["skipped"].as_array().unwrap().len(), 1);
+}
+
+fn fetch_sample(accession: &str) -> (tempfile::TempDir, String) {
+    let temp = tempfile::tempdir().unwrap();
+    let app = App::new(
+        temp_store(&temp),
+        UnusedNcbi,
+        UnusedRcsb,
+        UnusedSrr,
+        UnusedUniprot,
+        FixtureGeo::default(),
+        UnusedKnowledge,
+    );
+    let result = app
+        .fetch(
+            Some(format!("expressionsample:{accession}").parse().unwrap()),
+            None,
+            FetchOverrides::default(),
+            options(),
+            &NoProgress,
+        )
+        .unwrap();
+    let info = app
+        .info(
+            format!("expression:{accession}").parse().unwrap(),
+            &NoProgress,
+        )
+        .unwrap();
+    assert_eq!(info.dataset_type, "expressionsample");
+    assert_eq!(info.details.unwrap()["accession"], accession);
+    let project_path = result.items[0].project_path.clone().unwrap();
+    (temp, project_path)
+}
+
+#[test]
+fn sample_fetch_nests_under_parent_series() {
+    let soft = std::fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/geo/GSM5000001.soft"),
+    )
+    .unwrap();
+    let series = extract_sample_series(&soft);
+    assert_eq!(series, vec!["GSE200010".parse().unwrap()]);
+
+    let (_temp, project_path) = fetch_sample("GSM5000001");
+    let dir = Path::new(&project_path);
+    assert!(project_path.ends_with("expression/GSE200010/GSM5000001"));
+    assert!(dir.join("GSM5000001_matrix.mtx.gz").exists());
+    assert!(!dir.join("GSM5000001_web_summary.html.gz").exists());
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("metadata/metadata.json")).unwrap())
+            .unwrap();
+    assert_eq!(meta["type"], "expressionsample");
+    assert_eq!(meta["series"], "GSE200010");
+    assert_eq!(meta["organism"], "Homo sapiens");
+    assert_eq!(meta["bundle_format"], "10x");
+    assert_eq!(meta["n_bundles"], 1);
+    assert_eq!(meta["skipped"][0]["category"], "other");
+}
+
+#[test]
+fn sample_fetch_without_series_uses_sample_dir() {
+    let (_temp, project_path) = fetch_sample("GSM5000002");
+    assert!(project_path.ends_with("expression_samples/GSM5000002"));
+    let meta: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(Path::new(&project_path).join("metadata/metadata.json")).unwrap(),
+    )
+    .unwrap();
+    assert!(meta.get("series").is_none());
+    assert!(meta.get("bundle_format").is_none());
+    assert_eq!(meta["files"][0], "GSM5000002_counts.txt.gz");
+}
+
+#[test]
+fn detects_10x_bundles_inside_tarballs() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/geo");
+    assert!(is_10x_archive(
+        "GSM5000003_filtered_feature_bc_matrix.tar.gz"
+    ));
+    assert!(is_10x_archive("GSE200020_10x_matrices.tar"));
+    assert!(!is_10x_archive("GSE200020_RAW.tar"));
+    assert!(!is_10x_archive("GSE200020_matrix.mtx.gz"));
+
+    let temp = tempfile::tempdir().unwrap();
+    let gzipped = extract_tar(
+        &fixtures.join("GSM5000003_filtered_feature_bc_matrix.tar.gz"),
+        &temp.path().join("a"),
+    )
+    .unwrap();
+    assert_eq!(gzipped.len(), 3);
+    let bundles = detect_10x_files(&gzipped);
+    assert_eq!(bundles.len(), 1);
+    assert_eq!(bundles[0].sample, "filtered_feature_bc_matrix");
+    assert_eq!(
+        bundles[0].features,
+        "filtered_feature_bc_matrix/features.tsv.gz"
+    );
+
+    let plain = extract_tar(
+        &fixtures.join("GSE200020_10x_matrices.tar"),
+        &temp.path().join("b"),
+    )
+    .unwrap();
+    assert!(plain.contains(&"README.txt".to_string()));
+    assert!(
+        temp.path()
+            .join("b/GSM5000004/GSM5000004_genes.tsv.gz")
+            .exists()
+    );
+    let bundles = detect_10x_files(&plain);
+    let samples = bundles
+        .iter()
+        .map(|bundle| bundle.sample.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(samples, vec!["GSM5000004", "GSM5000004_2"]);
+    assert!(bundles[0].features.ends_with("GSM5000004_genes.tsv.gz"));
+    assert!(bundles[1].matrix.len() > 100);
+}
+
+#[test]
+fn expression10x_fetch_unpacks_and_normalizes_bundles() {
+    let temp = tempfile::tempdir().unwrap();
+    let app = App::new(
+        temp_store(&temp),
+        UnusedNcbi,
+        UnusedRcsb,
+        UnusedSrr,
+        UnusedUniprot,
+        FixtureGeo::default(),
+        UnusedKnowledge,
+    );
+    let result = app
+        .fetch(
+            Some("expression10x:GSE200020".parse().unwrap()),
+            None,
+            FetchOverrides::default(),
+            options(),
+            &NoProgress,
+        )
+        .unwrap();
+    let dir = Path::new(result.items[0].project_path.as_ref().unwrap()).to_path_buf();
+    for sample in ["GSM5000003", "GSM5000004", "GSM5000004_2"] {
+        for name in ["matrix.mtx.gz", "barcodes.tsv.gz", "features.tsv.gz"] {
+            assert!(dir.join("bundles").join(sample).join(name).exists());
+        }
+    }
+    assert!(!dir.join("unpacked").exists());
+    assert!(!dir.join("GSE200020_10x_matrices.tar").exists());
+    assert!(!dir.join("GSE200020_RAW.tar").exists());
+
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("metadata/metadata.json")).unwrap())
+            .unwrap();
+    assert_eq!(meta["n_bundles"], 3);
+    assert_eq!(meta["files"].as_array().unwrap().len(), 9);
+    assert_eq!(meta["files"][0], "bundles/GSM5000003/barcodes.tsv.gz");
+    assert_eq!(meta["unpacked_archives"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        meta["dimensions"][0],
+        serde_json::json!({"sample": "GSM5000003", "cells": 2, "genes": 2, "nnz": 2})
+    );
+    assert_eq!(meta["dimensions"].as_array().unwrap().len(), 3);
+    assert!(meta.get("warnings").is_none());
+    let notes = meta["notes"].as_array().unwrap();
+    assert_eq!(notes.len(), 1);
+    assert!(
+        notes[0]
+            .as_str()
+            .unwrap()
+            .starts_with("GSM5000004: unpacked/GSE200020_10x_matrices.tar/GSM5000004/")
+    );
+}
+
+#[test]
+fn mtx_header_gives_the_matrix_size_without_reading_entries() {
+    let header = "%%MatrixMarket matrix coordinate integer general\n\
+                  %metadata_json: {}\n\
+                  33538 4 3\n\
+                  not an entry\n";
+    assert_eq!(read_mtx_header(header.as_bytes()), Ok((33538, 4, 3)));
+    assert!(read_mtx_header("33538 4 3\n".as_bytes()).is_err());
+    assert!(
+        read_mtx_header("%%MatrixMarket matrix coordinate real general\n4 3\n".as_bytes()).is_err()
+    );
+}
+
+#[test]
+fn bundle_whose_barcodes_disagree_with_the_matrix_is_flagged() {
+    use std::io::Write;
+
+    let temp = tempfile::tempdir().unwrap();
+    let bundle = temp.path().join("bundles/GSM1");
+    std::fs::create_dir_all(&bundle).unwrap();
+    let gzip = |name: &str, text: &str| {
+        let file = std::fs::File::create(bundle.join(name)).unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+    };
+    gzip(
+        "matrix.mtx.gz",
+        "%%MatrixMarket matrix coordinate integer general\n3 2 4\n1 1 1\n",
+    );
+    gzip("features.tsv.gz", "G1\nG2\nG3");
+    gzip("barcodes.tsv.gz", "AAAC-1\nAAAG-1\nAAAT-1\n");
+
+    let (dims, warnings) = measure_10x_bundle(temp.path(), "GSM1");
+    let dims = dims.unwrap();
+    assert_eq!((dims.cells, dims.genes, dims.nnz), (2, 3, 4));
+    assert_eq!(
+        warnings,
+        vec!["GSM1: barcodes.tsv.gz has 3 lines but the matrix has 2 cells"]
+    );
+}
+
+#[test]
+fn detects_cellranger_h5_matrices() {
+    assert_eq!(
+        tenx_h5_component("GSM1_s1_filtered_feature_bc_matrix.h5"),
+        Some(("GSM1_s1", true))
+    );
+    assert_eq!(
+        tenx_h5_component("GSM1_raw_gene_bc_matrices_h5.h5"),
+        Some(("GSM1", false))
+    );
+    assert_eq!(tenx_h5_component("matrix.hdf5"), Some(("", true)));
+    assert_eq!(tenx_h5_component("GSM1_molecule_info.h5"), None);
+    assert_eq!(tenx_h5_component("GSM1_matrix.mtx.gz"), None);
+
+    let paths = [
+        "GSM2_raw_feature_bc_matrix.h5",
+        "GSM2_filtered_feature_bc_matrix.h5",
+        "run/GSM3/filtered_feature_bc_matrix.h5",
+    ]
+    .map(str::to_string);
+    let found = detect_10x_h5(&paths);
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].sample, "GSM2");
+    assert_eq!(found[0].matrix, "GSM2_filtered_feature_bc_matrix.h5");
+    assert_eq!(found[1].sample, "GSM3");
+}
+
+fn fetch_gse200021(prefer: Option<BundlePreference>) -> (serde_json::Value, Vec<String>) {
+    let temp = tempfile::tempdir().unwrap();
+    let app = App::new(
+        temp_store(&temp),
+        UnusedNcbi,
+        UnusedRcsb,
+        UnusedSrr,
+        UnusedUniprot,
+        FixtureGeo::default(),
+        UnusedKnowledge,
+    );
+    let overrides = match prefer {
+        Some(prefer) => FetchOverrides::builder().prefer_bundle(prefer).build(),
+        None => FetchOverrides::default(),
+    };
+    let result = app
+        .fetch(
+            Some("expression10x:GSE200021".parse().unwrap()),
+            None,
+            overrides,
+            options(),
+            &NoProgress,
+        )
+        .unwrap();
+    let dir = Path::new(result.items[0].project_path.as_ref().unwrap()).to_path_buf();
+    let meta: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("metadata/metadata.json")).unwrap())
+            .unwrap();
+    assert!(dir.join("bundles/GSM5000006/matrix.h5").exists());
+    let files = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name != "metadata" && name != "MANIFEST.json")
+        .collect::<Vec<_>>();
+    assert_eq!(files, vec!["bundles"]);
+    let files = meta["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|value| value.as_str().unwrap().to_string())
+        .collect();
+    (meta, files)
+}
+
+#[test]
+fn expression10x_prefers_one_layout_per_sample() {
+    let (meta, files) = fetch_gse200021(None);
+    assert_eq!(meta["bundle_format"], "10x-mixed");
+    assert_eq!(meta["n_bundles"], 2);
+    assert_eq!(meta["bundles"][0]["sample"], "GSM5000005_organoid");
+    assert_eq!(meta["bundles"][0]["format"], "mtx");
+    assert_eq!(meta["bundles"][1]["format"], "h5");
+    assert_eq!(
+        meta["bundles"][1]["files"][0],
+        "bundles/GSM5000006/matrix.h5"
+    );
+    assert_eq!(meta["skipped_bundles"][0]["format"], "h5");
+    assert_eq!(
+        meta["skipped_bundles"][0]["files"][0],
+        "GSM5000005_organoid_filtered_feature_bc_matrix.h5"
+    );
+    assert_eq!(files.len(), 4);
+
+    let (meta, files) = fetch_gse200021(Some(BundlePreference::H5));
+    assert_eq!(meta["bundle_format"], "10x-h5");
+    assert_eq!(meta["skipped_bundles"][0]["format"], "mtx");
+    assert_eq!(
+        meta["skipped_bundles"][0]["files"]
+            .as_array()
+            .unwrap()
+            .len(),
+        3
+    );
+    assert_eq!(
+        files,
+        vec![
+            "bundles/GSM5000005_organoid/matrix.h5",
+            "bundles/GSM5000006/matrix.h5"
+        ]
+    );
+}
@@ -0,0 +1,507 @@
+mod support;
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use camino::Utf8PathBuf;
+
+use kira_biodata_manager::app::{
+    App, FetchOptions, FetchOverrides, FetchResult, ProgressEvent, ProgressSink,
+};
+use kira_biodata_manager::domain::{
+    AssemblyName, DatasetSpecifier, GenomeAccession, ProteinFormat, ProteinId, TaxonQuery,
+};
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::ncbi::{
+    AssemblyCandidate, AssemblyLevel, DATASET_CATALOG_PATH, DownloadInfo, NcbiClient,
+    TaxonAssemblies, TaxonFilter, parse_assembly_reports, parse_taxon_assemblies,
+};
+use kira_biodata_manager::output::PlainOutput;
+use kira_biodata_manager::store::Store;
+
+use support::{RecordingSink, UnusedGeo, UnusedKnowledge, UnusedRcsb, UnusedSrr, UnusedUniprot};
+
+/// Serves a package built from `tests/fixtures/ncbi/<accession>_catalog.json`,
+/// with a placeholder file for every catalog entry, and assembly reports
+/// from `<lowercase name>_assemblies.json` and `taxon_<taxon>_assemblies.json`.
+struct CatalogNcbi;
+
+impl NcbiClient for CatalogNcbi {
+    fn download_protein(
+        &self,
+        _id: &ProteinId,
+        _format: ProteinFormat,
+        _destination: &Path,
+    ) -> Result<DownloadInfo, KiraError> {
+        Err(KiraError::NcbiHttp("not used".to_string()))
+    }
+
+    fn download_genome(
+        &self,
+        accession: &GenomeAccession,
+        _include: &[String],
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<DownloadInfo, KiraError> {
+        let catalog = std::fs::read_to_string(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/ncbi")
+                .join(format!("{}_catalog.json", accession.as_str())),
+        )
+        .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&catalog).unwrap();
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(destination).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file(DATASET_CATALOG_PATH, options).unwrap();
+        zip.write_all(catalog.as_bytes()).unwrap();
+        for assembly in parsed["assemblies"].as_array().unwrap() {
+            for file in assembly["files"].as_array().unwrap() {
+                let path = file["filePath"]
+                    .as_str()
+                    .or_else(|| file["file_path"].as_str())
+                    .unwrap();
+                zip.start_file(format!("ncbi_dataset/data/{path}"), options)
+                    .unwrap();
+                zip.write_all(b"placeholder\n").unwrap();
+            }
+        }
+        zip.finish().unwrap();
+        Ok(DownloadInfo { is_zip: true })
+    }
+
+    fn find_assemblies(
+        &self,
+        name: &AssemblyName,
+        _exact: bool,
+    ) -> Result<Vec<AssemblyCandidate>, KiraError> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/ncbi")
+            .join(format!("{}_assemblies.json", name.as_str().to_lowercase()));
+        match std::fs::read_to_string(path) {
+            Ok(text) => parse_assembly_reports(&text),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    fn find_taxon_assemblies(
+        &self,
+        taxon: &TaxonQuery,
+        filter: &TaxonFilter,
+    ) -> Result<TaxonAssemblies, KiraError> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/ncbi")
+            .join(format!("taxon_{}_assemblies.json", taxon.as_str()));
+        let Ok(text) = std::fs::read_to_string(path) else {
+            return Ok(TaxonAssemblies::default());
+        };
+        let mut page = parse_taxon_assemblies(&text)?;
+        page.assemblies.truncate(filter.limit);
+        Ok(page)
+    }
+}
+
+fn fetch_genome(
+    accession: &str,
+    overrides: FetchOverrides,
+) -> (tempfile::TempDir, RecordingSink, Result<String, KiraError>) {
+    fetch_genome_with(accession, overrides, FetchOptions::default())
+}
+
+fn fetch_genome_with(
+    accession: &str,
+    overrides: FetchOverrides,
+    options: FetchOptions,
+) -> (tempfile::TempDir, RecordingSink, Result<String, KiraError>) {
+    let temp = tempfile::tempdir().unwrap();
+    let project = Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap();
+    let cache = Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let app = App::new(
+        Store::new_with_paths(project, cache),
+        CatalogNcbi,
+        UnusedRcsb,
+        UnusedSrr,
+        UnusedUniprot,
+        UnusedGeo,
+        UnusedKnowledge,
+    );
+    let sink = RecordingSink::default();
+    let result = app
+        .fetch(
+            Some(format!("genome:{accession}").parse().unwrap()),
+            None,
+            overrides,
+            options,
+            &sink,
+        )
+        .map(|result| result.items[0].project_path.clone().unwrap());
+    (temp, sink, result)
+}
+
+fn fetch_taxon(
+    specifier: &str,
+    overrides: FetchOverrides,
+    options: FetchOptions,
+) -> (tempfile::TempDir, Result<FetchResult, KiraError>) {
+    let temp = tempfile::tempdir().unwrap();
+    let project = Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap();
+    let cache = Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let app = App::new(
+        Store::new_with_paths(project, cache),
+        CatalogNcbi,
+        UnusedRcsb,
+        UnusedSrr,
+        UnusedUniprot,
+        UnusedGeo,
+        UnusedKnowledge,
+    );
+    let result = app.fetch(
+        Some(specifier.parse().unwrap()),
+        None,
+        overrides,
+        options,
+        &RecordingSink::default(),
+    );
+    (temp, result)
+}
+
+fn genome_metadata(project_path: &str) -> serde_json::Value {
+    let text = std::fs::read_to_string(Path::new(project_path).join("metadata.json")).unwrap();
+    serde_json::from_str(&text).unwrap()
+}
+
+#[test]
+fn annotated_genome_records_catalog_inventory() {
+    let (_temp, sink, result) = fetch_genome("GCF_000005845.2", FetchOverrides::default());
+    let meta = genome_metadata(&result.unwrap());
+    assert_eq!(meta["catalog_version"], "V2");
+    assert_eq!(meta["files"]["protein"][0], "GCF_000005845.2/protein.faa");
+    assert!(meta.get("missing_artifacts").is_none());
+    assert!(
+        !sink
+            .messages()
+            .iter()
+            .any(|message| message.starts_with("genome.missing_artifact"))
+    );
+}
+
+#[test]
+fn missing_artifact_fails_by_default() {
+    let (temp, _sink, result) = fetch_genome("GCA_000002945.2", FetchOverrides::default());
+    match result.as_ref().map_err(KiraError::root) {
+        Err(KiraError::MissingArtifact {
+            accession,
+            artifact,
+        }) => {
+            assert_eq!(accession, "GCA_000002945.2");
+            assert_eq!(artifact, "gff3");
+        }
+        other => panic!("expected missing artifact error, got {other:?}"),
+    }
+    assert!(!temp.path().join("project/genomes/GCA_000002945.2").exists());
+}
+
+#[test]
+fn missing_artifact_is_recorded_when_allowed() {
+    let overrides = FetchOverrides::builder()
+        .allow_missing_artifacts(true)
+        .build();
+    let (_temp, sink, result) = fetch_genome("GCA_000002945.2", overrides);
+    let meta = genome_metadata(&result.unwrap());
+    assert_eq!(meta["catalog_version"], "V1");
+    assert_eq!(
+        meta["missing_artifacts"],
+        serde_json::json!(["gff3", "protein"])
+    );
+    assert_eq!(
+        meta["files"]["seq-report"][0],
+        "GCA_000002945.2/sequence_report.jsonl"
+    );
+    let messages = sink.messages();
+    assert!(messages.contains(&"genome.missing_artifact GCA_000002945.2 gff3".to_string()));
+    assert!(messages.contains(&"genome.missing_artifact GCA_000002945.2 protein".to_string()));
+}
+
+#[test]
+fn genome_name_resolves_to_the_refseq_reference() {
+    let (temp, sink, result) = fetch_genome("asm584v2", FetchOverrides::default());
+    let project_path = result.unwrap();
+    assert!(project_path.ends_with("GCF_000005845.2"), "{project_path}");
+    let messages = sink.messages();
+    assert!(
+        messages.iter().any(|message| message
+            == "phase=Resolve; asm584v2 is GCF_000005845.2 (ASM584v2, Escherichia coli str. K-12 substr. MG1655)"),
+        "{messages:?}"
+    );
+    let record: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(
+            temp.path()
+                .join("project/metadata/genome/GCF_000005845.2.json"),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(record["id"], "GCF_000005845.2");
+    assert_eq!(record["assembly_name"], "asm584v2");
+}
+
+#[test]
+fn exact_genome_name_must_match_case() {
+    let (_temp, _sink, result) =
+        fetch_genome("asm584v2", FetchOverrides::builder().exact(true).build());
+    let err = result.unwrap_err();
+    assert!(
+        matches!(err.root(), KiraError::UnknownGenomeName(name) if name == "asm584v2"),
+        "{err}"
+    );
+}
+
+#[test]
+fn ambiguous_genome_name_lists_candidates() {
+    let (_temp, _sink, result) = fetch_genome("Escherichia", FetchOverrides::default());
+    let err = result.unwrap_err();
+    let KiraError::AmbiguousGenomeName { candidates, .. } = err.root() else {
+        panic!("{err}");
+    };
+    assert_eq!(
+        candidates,
+        &vec![
+            "GCF_000005845.2 (ASM584v2, Escherichia coli str. K-12 substr. MG1655)".to_string(),
+            "GCF_000026225.1 (ASM2622v1, Escherichia fergusonii ATCC 35469)".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn built_in_aliases_resolve_offline() {
+    let (_temp, sink, result) = fetch_genome_with(
+        "grch38",
+        FetchOverrides::default(),
+        FetchOptions::builder().dry_run(true).build(),
+    );
+    assert!(result.unwrap().ends_with("GCF_000001405.40"));
+    let messages = sink.messages();
+    assert!(
+        messages
+            .iter()
+            .any(|message| message.ends_with("grch38 is GCF_000001405.40 (built-in alias)")),
+        "{messages:?}"
+    );
+
+    // Exact resolution skips the case-insensitive alias and finds nothing.
+    let (_temp, _sink, result) =
+        fetch_genome("grch38", FetchOverrides::builder().exact(true).build());
+    assert!(matches!(
+        result.unwrap_err().root(),
+        KiraError::UnknownGenomeName(_)
+    ));
+}
+
+#[test]
+fn taxon_dry_run_lists_matched_accessions() {
+    let spec: DatasetSpecifier = "genome:taxon:562".parse().unwrap();
+    assert_eq!(spec.to_string(), "genome:taxon:562");
+
+    let (temp, result) = fetch_taxon(
+        "genome:taxon:562",
+        FetchOverrides::builder()
+            .assembly_level(AssemblyLevel::Complete)
+            .limit(2)
+            .build(),
+        FetchOptions::builder().dry_run(true).build(),
+    );
+    let result = result.unwrap();
+    let ids = result
+        .items
+        .iter()
+        .map(|item| (item.id.as_str(), item.action.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        ids,
+        vec![
+            ("GCF_000005845.2", "download"),
+            ("GCF_000008865.2", "download")
+        ]
+    );
+    let summary = result.summary.as_ref().unwrap();
+    assert_eq!(summary.taxon.as_deref(), Some("562"));
+    assert_eq!(summary.id_counts[0].count, 3412);
+    assert_eq!(summary.resolved_targets, 2);
+    assert!(
+        PlainOutput::render_fetch(&result).contains("genome-taxon 562: 3412 matched, 2 fetched\n")
+    );
+    assert!(!temp.path().join("project/genomes").exists());
+}
+
+#[test]
+fn taxon_fetch_downloads_each_assembly() {
+    let (temp, result) = fetch_taxon(
+        "genome:taxon:562",
+        FetchOverrides::builder().limit(1).build(),
+        FetchOptions::default(),
+    );
+    let result = result.unwrap();
+    assert_eq!(result.items.len(), 1);
+    assert_eq!(result.items[0].action, "download");
+    assert!(
+        temp.path()
+            .join("project/genomes/GCF_000005845.2/metadata.json")
+            .exists()
+    );
+
+    let (_temp, result) = fetch_taxon(
+        "genome:taxon:Nonexistentia",
+        FetchOverrides::default(),
+        FetchOptions::default(),
+    );
+    let err = result.unwrap_err();
+    assert!(
+        matches!(err.root(), KiraError::NoMatchingAssemblies(_)),
+        "{err}"
+    );
+}
+
+/// Fails every download, as when the network is down.
+struct OfflineNcbi;
+
+impl NcbiClient for OfflineNcbi {
+    fn download_protein(
+        &self,
+        _id: &ProteinId,
+        _format: ProteinFormat,
+        _destination: &Path,
+    ) -> Result<DownloadInfo, KiraError> {
+        Err(KiraError::NcbiHttp("network is unreachable".to_string()))
+    }
+
+    fn download_genome(
+        &self,
+        _accession: &GenomeAccession,
+        _include: &[String],
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<DownloadInfo, KiraError> {
+        Err(KiraError::NcbiHttp("network is unreachable".to_string()))
+    }
+}
+
+/// Asks to stop once the package has been downloaded.
+#[derive(Default)]
+struct CancelAtVerify(AtomicBool);
+
+impl ProgressSink for CancelAtVerify {
+    fn event(&self, event: ProgressEvent) {
+        if event.message.starts_with("phase=Verify") {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Every file under `root` but the audit and history logs, which record
+/// the attempt.
+fn snapshot(root: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_none_or(|ext| ext != "jsonl") {
+                let bytes = std::fs::read(&path).unwrap();
+                files.insert(path.strip_prefix(root).unwrap().to_path_buf(), bytes);
+            }
+        }
+    }
+    files
+}
+
+#[test]
+fn a_forced_refetch_that_fails_keeps_the_stored_genome() {
+    let temp = tempfile::tempdir().unwrap();
+    let project = Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap();
+    let cache = Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let store = Store::new_with_paths(project, cache);
+    let specifier: DatasetSpecifier = "genome:GCF_000005845.2".parse().unwrap();
+    let forced = || FetchOptions::builder().force(true).build();
+    App::new(
+        store.clone(),
+        CatalogNcbi,
+        UnusedRcsb,
+        UnusedSrr,
+        UnusedUniprot,
+        UnusedGeo,
+        UnusedKnowledge,
+    )
+    .fetch(
+        Some(specifier.clone()),
+        None,
+        FetchOverrides::default(),
+        FetchOptions::default(),
+        &PlainOutput,
+    )
+    .unwrap();
+    let before = snapshot(temp.path());
+    assert!(
+        before
+            .keys()
+            .any(|path| path.starts_with("project/genomes"))
+    );
+    assert!(before.keys().any(|path| path.starts_with("cache")));
+
+    let err = App::new(
+        store.clone(),
+        OfflineNcbi,
+        UnusedRcsb,
+        UnusedSrr,
+        UnusedUniprot,
+        UnusedGeo,
+        UnusedKnowledge,
+    )
+    .fetch(
+        Some(specifier.clone()),
+        None,
+        FetchOverrides::default(),
+        forced(),
+        &PlainOutput,
+    )
+    .unwrap_err();
+    assert!(matches!(err.root(), KiraError::NcbiHttp(_)), "{err}");
+    let after = snapshot(temp.path());
+    assert_eq!(
+        after.keys().collect::<Vec<_>>(),
+        before.keys().collect::<Vec<_>>()
+    );
+    for (path, bytes) in &before {
+        assert_eq!(after[path], *bytes, "{}", path.display());
+    }
+
+    // Stopped after the new package is verified but before it is stored.
+    let err = App::new(
+        store,
+        CatalogNcbi,
+        UnusedRcsb,
+        UnusedSrr,
+        UnusedUniprot,
+        UnusedGeo,
+        UnusedKnowledge,
+    )
+    .fetch(
+        Some(specifier),
+        None,
+        FetchOverrides::default(),
+        forced(),
+        &CancelAtVerify::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err.root(), KiraError::Cancelled), "{err}");
+    let after = snapshot(temp.path());
+    for (path, bytes) in &before {
+        assert_eq!(after.get(path), Some(bytes), "{}", path.display());
+    }
+}
@@ -0,0 +1,146 @@
+//! Supplementary downloads through [`GeoHttpClient`], served by a
+//! [`MockRegistry`].
+
+mod support;
+
+use std::path::Path;
+
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::fs_util::sha256_file;
+use kira_biodata_manager::geo::{GeoClient, GeoHttpClient};
+use kira_biodata_manager::http::HttpSettings;
+
+use support::{MockRegistry, NoProgress};
+
+const SUPPL: &str = "/geo/series/GSE1nnn/GSE1000/suppl";
+
+fn download(registry: &MockRegistry, url: &str, destination: &Path) -> Result<String, KiraError> {
+    let settings = HttpSettings::new(None).with_rate("ncbi", 1000.0);
+    GeoHttpClient::with_base_url(settings, registry.url())
+        .unwrap()
+        .download_url(url, destination, &NoProgress)
+}
+
+#[test]
+fn ftp_urls_are_requested_over_https() {
+    let registry = MockRegistry::builder()
+        .get(&format!("{SUPPL}/GSE1000_counts.txt.gz"), "counts")
+        .start();
+    let temp = tempfile::tempdir().unwrap();
+    let destination = temp.path().join("GSE1000_counts.txt.gz");
+
+    let digest = download(
+        &registry,
+        &format!("ftp://ftp.ncbi.nlm.nih.gov{SUPPL}/GSE1000_counts.txt.gz"),
+        &destination,
+    )
+    .unwrap();
+
+    assert_eq!(std::fs::read(&destination).unwrap(), b"counts");
+    assert_eq!(digest, sha256_file(&destination).unwrap());
+    assert_eq!(
+        GeoHttpClient::normalize_url("ftp://ftp.ncbi.nih.gov/geo/x.txt"),
+        "https://ftp.ncbi.nlm.nih.gov/geo/x.txt"
+    );
+}
+
+#[test]
+fn redirects_and_meta_refreshes_are_followed_to_the_file() {
+    let registry = MockRegistry::builder()
+        .redirect(&format!("{SUPPL}/GSE1000_counts.txt"), "/moved/counts.txt")
+        .redirect(
+            "/moved/counts.txt",
+            "ftp://ftp.ncbi.nlm.nih.gov/geo/final/page.html",
+        )
+        .get(
+            "/geo/final/page.html",
+            r#"<html><head><meta http-equiv="Refresh" content="0; URL='/files/counts.txt'"></head></html>"#,
+        )
+        .content_type("text/html; charset=utf-8")
+        .get("/files/counts.txt", "gene\tcount\n")
+        .start();
+    let temp = tempfile::tempdir().unwrap();
+    let destination = temp.path().join("GSE1000_counts.txt");
+
+    download(
+        &registry,
+        &format!("https://ftp.ncbi.nlm.nih.gov{SUPPL}/GSE1000_counts.txt"),
+        &destination,
+    )
+    .unwrap();
+
+    assert_eq!(std::fs::read(&destination).unwrap(), b"gene\tcount\n");
+    assert_eq!(
+        registry.requests(),
+        [
+            format!("GET {SUPPL}/GSE1000_counts.txt"),
+            "GET /moved/counts.txt".to_string(),
+            "GET /geo/final/page.html".to_string(),
+            "GET /files/counts.txt".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn a_redirect_loop_is_cut_off() {
+    let registry = MockRegistry::builder()
+        .redirect("/geo/a.txt", "/geo/b.txt")
+        .redirect("/geo/b.txt", "/geo/a.txt")
+        .start();
+    let temp = tempfile::tempdir().unwrap();
+    let destination = temp.path().join("a.txt");
+
+    let err = download(
+        &registry,
+        "https://ftp.ncbi.nlm.nih.gov/geo/a.txt",
+        &destination,
+    )
+    .unwrap_err();
+
+    assert!(err.to_string().contains("redirects"), "{err}");
+    assert_eq!(registry.requests().len(), 11);
+    assert!(!destination.exists());
+}
+
+#[test]
+fn empty_and_truncated_responses_are_not_kept() {
+    let registry = MockRegistry::builder()
+        .get("/geo/empty.txt", Vec::new())
+        .get("/geo/short.txt", "only part")
+        .content_length(4096)
+        .start();
+    let temp = tempfile::tempdir().unwrap();
+
+    for name in ["empty.txt", "short.txt"] {
+        let destination = temp.path().join(name);
+        let result = download(
+            &registry,
+            &format!("https://ftp.ncbi.nlm.nih.gov/geo/{name}"),
+            &destination,
+        );
+        assert!(result.is_err(), "{name} was accepted");
+        assert!(!destination.exists(), "{name} was left behind");
+    }
+}
+
+#[test]
+fn a_missing_file_is_an_error() {
+    let registry = MockRegistry::builder()
+        .redirect("/geo/gone.txt", "/geo/nowhere.txt")
+        .start();
+    let temp = tempfile::tempdir().unwrap();
+    let destination = temp.path().join("gone.txt");
+
+    let err = download(
+        &registry,
+        "https://ftp.ncbi.nlm.nih.gov/geo/gone.txt",
+        &destination,
+    )
+    .unwrap_err();
+
+    assert!(
+        matches!(err, KiraError::GeoStatus { status: 404, .. }),
+        "{err}"
+    );
+    assert!(!destination.exists());
+}
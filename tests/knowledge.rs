@@ -1,21 +1,21 @@
+mod support;
+
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 
 use kira_biodata_manager::app::{App, FetchOptions, FetchOverrides, ProgressSink};
-use kira_biodata_manager::domain::DatasetSpecifier;
+use kira_biodata_manager::domain::{DatasetSpecifier, GoaSpecies, KeggOrganism};
 use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::fs_util::write_hashed;
 use kira_biodata_manager::geo::GeoClient;
-use kira_biodata_manager::knowledge::KnowledgeClient;
+use kira_biodata_manager::knowledge::{GoFlavor, KnowledgeClient};
 use kira_biodata_manager::ncbi::NcbiClient;
 use kira_biodata_manager::rcsb::RcsbClient;
-use kira_biodata_manager::srr::SrrClient;
+use kira_biodata_manager::srr::{SrrClient, SrrDownloadOptions};
 use kira_biodata_manager::store::Store;
 use kira_biodata_manager::uniprot::UniprotClient;
 
-struct NoopSink;
-
-impl ProgressSink for NoopSink {
-    fn event(&self, _event: kira_biodata_manager::app::ProgressEvent) {}
-}
+use support::NoProgress;
 
 #[derive(Clone, Copy)]
 struct DummyNcbi;
@@ -45,6 +45,7 @@ impl NcbiClient for DummyNcbi {
         _accession: &kira_biodata_manager::domain::GenomeAccession,
         _include: &[String],
         _destination: &Path,
+        _sink: &dyn ProgressSink,
     ) -> Result<kira_biodata_manager::ncbi::DownloadInfo, KiraError> {
         Err(KiraError::NcbiHttp("not used".to_string()))
     }
@@ -56,6 +57,7 @@ impl RcsbClient for DummyRcsb {
         _id: &kira_biodata_manager::domain::ProteinId,
         _format: kira_biodata_manager::domain::ProteinFormat,
         _destination: &Path,
+        _sink: &dyn ProgressSink,
     ) -> Result<(), KiraError> {
         Err(KiraError::RcsbHttp("not used".to_string()))
     }
@@ -74,15 +76,13 @@ impl SrrClient for DummySrr {
         _id: &kira_biodata_manager::domain::SrrId,
         _paired: bool,
         _destination_dir: &Path,
+        _options: &SrrDownloadOptions,
     ) -> Result<Vec<std::path::PathBuf>, KiraError> {
         Err(KiraError::SrrConversion("not used".to_string()))
     }
 
     fn tool_info(&self) -> kira_biodata_manager::srr::ToolInfo {
-        kira_biodata_manager::srr::ToolInfo {
-            datasets: None,
-            sra_toolkit: None,
-        }
+        kira_biodata_manager::srr::ToolInfo::from_output(None, None)
     }
 }
 
@@ -103,13 +103,30 @@ impl GeoClient for DummyGeo {
         Err(KiraError::GeoHttp("not used".to_string()))
     }
 
-    fn download_url(&self, _url: &str, _destination: &Path) -> Result<(), KiraError> {
+    fn fetch_sample_soft_text(
+        &self,
+        _accession: &kira_biodata_manager::domain::GeoSampleAccession,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::GeoHttp("not used".to_string()))
+    }
+
+    fn download_url(
+        &self,
+        _url: &str,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
         Err(KiraError::GeoHttp("not used".to_string()))
     }
 }
 
 impl KnowledgeClient for DummyKnowledge {
-    fn download_go(&self, destination: &Path) -> Result<Vec<u8>, KiraError> {
+    fn download_go_ontology(
+        &self,
+        _flavor: GoFlavor,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<Vec<u8>, KiraError> {
         let payload = b"format-version: 1.2\ndata-version: 2025-01-01\n";
         if let Some(parent) = destination.parent() {
             std::fs::create_dir_all(parent)
@@ -120,19 +137,43 @@ impl KnowledgeClient for DummyKnowledge {
         Ok(payload.to_vec())
     }
 
-    fn download_kegg_pathways(&self, _destination: &Path) -> Result<(), KiraError> {
+    fn download_kegg_pathways(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn download_kegg_pathway_links(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn download_reactome_pathways(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
         Err(KiraError::KnowledgeHttp("not used".to_string()))
     }
 
-    fn download_kegg_pathway_links(&self, _destination: &Path) -> Result<(), KiraError> {
+    fn download_reactome_mappings(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
         Err(KiraError::KnowledgeHttp("not used".to_string()))
     }
 
-    fn download_reactome_pathways(&self, _destination: &Path) -> Result<(), KiraError> {
+    fn fetch_kegg_info(&self, _database: &str) -> Result<String, KiraError> {
         Err(KiraError::KnowledgeHttp("not used".to_string()))
     }
 
-    fn download_reactome_mappings(&self, _destination: &Path) -> Result<(), KiraError> {
+    fn fetch_kegg_links(&self, _target: &str, _ids: &[String]) -> Result<String, KiraError> {
         Err(KiraError::KnowledgeHttp("not used".to_string()))
     }
 }
@@ -153,18 +194,14 @@ fn go_fetch_reuses_cache() {
         DummyGeo,
         DummyKnowledge,
     );
-    let options = FetchOptions {
-        force: false,
-        no_cache: false,
-        dry_run: false,
-    };
+    let options = FetchOptions::default();
     let result = app
         .fetch(
             Some(DatasetSpecifier::Go),
             None,
             FetchOverrides::default(),
             options.clone(),
-            &NoopSink,
+            &NoProgress,
         )
         .unwrap();
     assert_eq!(result.items[0].action, "download");
@@ -175,8 +212,569 @@ fn go_fetch_reuses_cache() {
             None,
             FetchOverrides::default(),
             options,
-            &NoopSink,
+            &NoProgress,
         )
         .unwrap();
     assert_eq!(result.items[0].action, "cache");
 }
+
+/// GO stub recording which files were downloaded; annotation files are
+/// gzipped GAF with a GAF 2.2 header.
+#[derive(Clone, Default)]
+struct RecordingGo {
+    downloads: Arc<Mutex<Vec<String>>>,
+}
+
+impl RecordingGo {
+    fn downloads(&self) -> Vec<String> {
+        std::mem::take(&mut *self.downloads.lock().unwrap())
+    }
+}
+
+impl KnowledgeClient for RecordingGo {
+    fn download_go_ontology(
+        &self,
+        flavor: GoFlavor,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<Vec<u8>, KiraError> {
+        self.downloads
+            .lock()
+            .unwrap()
+            .push(flavor.file_name().to_string());
+        let payload =
+            format!("format-version: 1.2\ndata-version: releases/2025-01-01\n! {flavor}\n");
+        std::fs::write(destination, &payload)
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        Ok(payload.into_bytes())
+    }
+
+    fn download_go_annotations(
+        &self,
+        species: &GoaSpecies,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<Vec<u8>, KiraError> {
+        use std::io::Write;
+
+        self.downloads.lock().unwrap().push(species.to_string());
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(
+                b"!gaf-version: 2.2\n!date-generated: 2025-02-03T10:06\nUniProtKB\tP69905\tHBA1\n",
+            )
+            .unwrap();
+        let bytes = encoder.finish().unwrap();
+        std::fs::write(destination, &bytes)
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        Ok(bytes)
+    }
+
+    fn download_kegg_pathways(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn download_kegg_pathway_links(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn download_reactome_pathways(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn download_reactome_mappings(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn fetch_kegg_info(&self, _database: &str) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn fetch_kegg_links(&self, _target: &str, _ids: &[String]) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+}
+
+#[test]
+fn go_flavor_and_annotations_are_fetched_and_recorded() {
+    let temp = tempfile::tempdir().unwrap();
+    let project = camino::Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap();
+    let cache = camino::Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let store = Store::new_with_paths(project.clone(), cache.clone());
+    let go = RecordingGo::default();
+    let app = App::new(
+        Store::new_with_paths(project, cache),
+        DummyNcbi,
+        DummyRcsb,
+        DummySrr,
+        DummyUniprot,
+        DummyGeo,
+        go.clone(),
+    );
+    let fetch = |overrides: FetchOverrides| {
+        app.fetch(
+            Some(DatasetSpecifier::Go),
+            None,
+            overrides,
+            FetchOptions::default(),
+            &NoProgress,
+        )
+        .unwrap()
+        .items
+        .remove(0)
+        .action
+    };
+    let dir = store.cache_kb_dir("go");
+    let metadata = || -> serde_json::Value {
+        serde_json::from_slice(&std::fs::read(dir.join("metadata.json")).unwrap()).unwrap()
+    };
+    let human = || "human".parse::<GoaSpecies>().unwrap();
+
+    // A dataset stored before flavors existed records none and is basic.
+    assert_eq!(fetch(FetchOverrides::default()), "download");
+    let mut legacy = metadata();
+    legacy.as_object_mut().unwrap().remove("flavor");
+    std::fs::write(dir.join("metadata.json"), legacy.to_string()).unwrap();
+    assert_eq!(fetch(FetchOverrides::default()), "cache");
+    assert_eq!(go.downloads(), ["go-basic.obo"]);
+
+    // Adding annotations keeps the ontology already stored.
+    let overrides = FetchOverrides::builder().go_annotations([human()]).build();
+    assert_eq!(fetch(overrides.clone()), "download");
+    assert_eq!(go.downloads(), ["human"]);
+    assert!(dir.join("goa_human.gaf.gz").exists());
+    let meta = metadata();
+    assert_eq!(meta["flavor"], "basic");
+    assert_eq!(meta["version"], "releases/2025-01-01");
+    assert_eq!(meta["annotations"][0]["species"], "human");
+    assert_eq!(meta["annotations"][0]["release_date"], "2025-02-03T10:06");
+    assert_eq!(
+        meta["source_urls"][1],
+        "https://ftp.ebi.ac.uk/pub/databases/GO/goa/HUMAN/goa_human.gaf.gz"
+    );
+    assert_eq!(fetch(overrides), "cache");
+    assert_eq!(fetch(FetchOverrides::default()), "cache");
+
+    // Switching flavor replaces the ontology and keeps the annotations.
+    let full = FetchOverrides::builder().go_flavor(GoFlavor::Full).build();
+    assert_eq!(fetch(full.clone()), "download");
+    assert_eq!(go.downloads(), ["go.obo"]);
+    assert!(dir.join("go.obo").exists() && !dir.join("go-basic.obo").exists());
+    assert!(dir.join("goa_human.gaf.gz").exists());
+    assert_eq!(metadata()["flavor"], "full");
+    assert_eq!(fetch(full), "cache");
+    assert_eq!(fetch(FetchOverrides::default()), "download");
+}
+
+#[test]
+fn goa_species_names_are_checked() {
+    assert_eq!("Human".parse::<GoaSpecies>().unwrap().as_str(), "human");
+    assert!("uniprot_all".parse::<GoaSpecies>().is_ok());
+    for name in ["", "1human", "hu-man", "../human"] {
+        assert!(
+            matches!(
+                name.parse::<GoaSpecies>(),
+                Err(KiraError::InvalidGoaSpecies(_))
+            ),
+            "{name}"
+        );
+    }
+}
+
+/// Reactome stub serving two human pathways and one mouse pathway.
+struct TwoSpeciesReactome;
+
+impl KnowledgeClient for TwoSpeciesReactome {
+    fn download_go_ontology(
+        &self,
+        _flavor: GoFlavor,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<Vec<u8>, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn download_kegg_pathways(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn download_kegg_pathway_links(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn download_reactome_pathways(
+        &self,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        write_hashed(destination, "R-HSA-1\tApoptosis\tHomo sapiens\nR-HSA-2\tAutophagy\tHomo sapiens\nR-MMU-1\tApoptosis\tMus musculus\n".as_bytes())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+
+    fn download_reactome_mappings(
+        &self,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        write_hashed(destination, "P69905\tR-HSA-1\thttps://reactome.org/R-HSA-1\tApoptosis\tTAS\tHomo sapiens\nP01942\tR-MMU-1\thttps://reactome.org/R-MMU-1\tApoptosis\tIEA\tMus musculus\n".as_bytes())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+
+    fn download_reactome_relations(
+        &self,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        write_hashed(destination, "R-HSA-1\tR-HSA-2\n".as_bytes())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+
+    fn fetch_kegg_info(&self, _database: &str) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn fetch_kegg_links(&self, _target: &str, _ids: &[String]) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+}
+
+#[test]
+fn reactome_files_are_filtered_to_one_species() {
+    let temp = tempfile::tempdir().unwrap();
+    let project = camino::Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap();
+    let cache = camino::Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let store = Store::new_with_paths(project.clone(), cache.clone());
+    let app = App::new(
+        Store::new_with_paths(project, cache),
+        DummyNcbi,
+        DummyRcsb,
+        DummySrr,
+        DummyUniprot,
+        DummyGeo,
+        TwoSpeciesReactome,
+    );
+    let fetch = |overrides: FetchOverrides| {
+        app.fetch(
+            Some(DatasetSpecifier::Reactome),
+            None,
+            overrides,
+            FetchOptions::default(),
+            &NoProgress,
+        )
+        .map(|mut result| result.items.remove(0).action)
+    };
+    let dir = store.cache_kb_dir("reactome");
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+
+    let human = FetchOverrides::builder()
+        .reactome_species("homo sapiens")
+        .build();
+    assert_eq!(fetch(human.clone()).unwrap(), "download");
+    assert_eq!(read("ReactomePathways.txt").lines().count(), 3);
+    assert_eq!(
+        read("ReactomePathways.homo_sapiens.txt"),
+        "R-HSA-1\tApoptosis\tHomo sapiens\nR-HSA-2\tAutophagy\tHomo sapiens\n"
+    );
+    assert_eq!(read("UniProt2Reactome.homo_sapiens.txt").lines().count(), 1);
+    assert_eq!(read("ReactomePathwaysRelation.txt"), "R-HSA-1\tR-HSA-2\n");
+
+    let info = app.info(DatasetSpecifier::Reactome, &NoProgress).unwrap();
+    let filter = &info.details.unwrap()["species_filter"];
+    assert_eq!(filter["species"], "homo sapiens");
+    assert_eq!(filter["files"][0]["lines_before"], 3);
+    assert_eq!(filter["files"][0]["lines_after"], 2);
+    assert_eq!(
+        filter["files"][1]["file"],
+        "UniProt2Reactome.homo_sapiens.txt"
+    );
+
+    // The full files are still there, so a plain fetch is a cache hit.
+    assert_eq!(fetch(human).unwrap(), "cache");
+    assert_eq!(fetch(FetchOverrides::default()).unwrap(), "cache");
+
+    let mouse_only = FetchOverrides::builder()
+        .reactome_species("Mus musculus")
+        .replace_unfiltered(true)
+        .build();
+    assert_eq!(fetch(mouse_only.clone()).unwrap(), "download");
+    assert_eq!(read("ReactomePathways.txt").lines().count(), 1);
+    assert!(!dir.join("ReactomePathways.homo_sapiens.txt").exists());
+    assert_eq!(fetch(mouse_only).unwrap(), "cache");
+    assert_eq!(fetch(FetchOverrides::default()).unwrap(), "download");
+
+    let unknown = FetchOverrides::builder()
+        .reactome_species("Homo sapien")
+        .build();
+    let err = fetch(unknown).unwrap_err();
+    assert!(matches!(
+        err.root(),
+        KiraError::UnknownReactomeSpecies(species) if species == "Homo sapien"
+    ));
+    // The failed fetch left the stored files alone.
+    assert_eq!(read("ReactomePathways.txt").lines().count(), 3);
+}
+
+/// KEGG stub with `pathways` maps of which only `listed` are returned by
+/// `list/pathway`; the bulk link file is cut off mid-line.
+#[derive(Clone)]
+struct TruncatingKegg {
+    pathways: usize,
+    listed: usize,
+    list_calls: Arc<Mutex<usize>>,
+    link_batches: Arc<Mutex<Vec<Vec<String>>>>,
+}
+
+impl TruncatingKegg {
+    fn new(pathways: usize, listed: usize) -> Self {
+        Self {
+            pathways,
+            listed,
+            list_calls: Arc::new(Mutex::new(0)),
+            link_batches: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+impl KnowledgeClient for TruncatingKegg {
+    fn download_go_ontology(
+        &self,
+        _flavor: GoFlavor,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<Vec<u8>, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn download_kegg_pathways(
+        &self,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        *self.list_calls.lock().unwrap() += 1;
+        let body = (0..self.listed)
+            .map(|index| format!("map{:05}\tPathway {index}\n", index * 10))
+            .collect::<String>();
+        write_hashed(destination, body.as_bytes())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+
+    fn download_kegg_pathway_links(
+        &self,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        write_hashed(destination, "ko:K00001\tpath:map00000\nko:K000".as_bytes())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+
+    fn download_reactome_pathways(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn download_reactome_mappings(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn fetch_kegg_info(&self, database: &str) -> Result<String, KiraError> {
+        assert_eq!(database, "pathway");
+        Ok(format!(
+            "pathway          KEGG Pathway Database\npath             Release 110.0+/05-01, May 24\n                 Kanehisa Laboratories\n                 {} entries\n",
+            self.pathways
+        ))
+    }
+
+    fn download_kegg_organism_pathways(
+        &self,
+        organism: &KeggOrganism,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        let body = format!("{organism}00010\tGlycolysis\n{organism}00020\tCitrate cycle\n");
+        write_hashed(destination, body.as_bytes())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+
+    fn download_kegg_gene_links(
+        &self,
+        organism: &KeggOrganism,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        let body = format!("path:{organism}00010\t{organism}:10327\n");
+        write_hashed(destination, body.as_bytes())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+
+    fn fetch_kegg_links(&self, target: &str, ids: &[String]) -> Result<String, KiraError> {
+        assert_eq!(target, "ko");
+        self.link_batches.lock().unwrap().push(ids.to_vec());
+        Ok(ids
+            .iter()
+            .enumerate()
+            .map(|(index, id)| format!("path:{id}\tko:K{index:05}\n"))
+            .collect())
+    }
+}
+
+fn kegg_app(
+    temp: &tempfile::TempDir,
+    kegg: TruncatingKegg,
+) -> (
+    App<DummyNcbi, DummyRcsb, DummySrr, DummyUniprot, DummyGeo, TruncatingKegg>,
+    Store,
+) {
+    let project = camino::Utf8PathBuf::from_path_buf(temp.path().join("project")).unwrap();
+    let cache = camino::Utf8PathBuf::from_path_buf(temp.path().join("cache")).unwrap();
+    let store = Store::new_with_paths(project.clone(), cache.clone());
+    let app = App::new(
+        Store::new_with_paths(project, cache),
+        DummyNcbi,
+        DummyRcsb,
+        DummySrr,
+        DummyUniprot,
+        DummyGeo,
+        kegg,
+    );
+    (app, store)
+}
+
+#[test]
+fn truncated_kegg_links_fall_back_to_batches() {
+    let temp = tempfile::tempdir().unwrap();
+    let kegg = TruncatingKegg::new(25, 25);
+    let (app, store) = kegg_app(&temp, kegg.clone());
+    app.fetch(
+        Some(DatasetSpecifier::Kegg(None)),
+        None,
+        FetchOverrides::default(),
+        FetchOptions::default(),
+        &NoProgress,
+    )
+    .unwrap();
+
+    let batches = kegg.link_batches.lock().unwrap().clone();
+    assert_eq!(
+        batches.iter().map(Vec::len).collect::<Vec<_>>(),
+        vec![10, 10, 5]
+    );
+
+    let dir = store.cache_kb_dir("kegg");
+    let links = std::fs::read_to_string(dir.join("pathway_ko.txt")).unwrap();
+    assert_eq!(links.lines().count(), 25);
+    assert!(links.ends_with('\n'));
+    assert!(links.lines().all(|line| line.starts_with("ko:K")));
+    assert!(links.contains("ko:K00004\tpath:map00240"));
+
+    let meta: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.join("metadata.json")).unwrap()).unwrap();
+    let validation = &meta["validation"];
+    assert_eq!(validation["pathway_rows"], 25);
+    assert_eq!(validation["declared_pathways"], 25);
+    assert_eq!(validation["link_rows"], 25);
+    assert_eq!(validation["link_retrieval"], "chunked");
+    assert_eq!(validation["complete"], true);
+}
+
+#[test]
+fn short_kegg_pathway_list_is_rejected() {
+    let temp = tempfile::tempdir().unwrap();
+    let kegg = TruncatingKegg::new(25, 20);
+    let (app, store) = kegg_app(&temp, kegg.clone());
+    let err = app
+        .fetch(
+            Some(DatasetSpecifier::Kegg(None)),
+            None,
+            FetchOverrides::default(),
+            FetchOptions::default(),
+            &NoProgress,
+        )
+        .unwrap_err();
+
+    assert!(
+        matches!(err.root(), KiraError::KnowledgeIncomplete(_)),
+        "{err}"
+    );
+    assert_eq!(*kegg.list_calls.lock().unwrap(), 2);
+    assert!(!store.cache_kb_dir("kegg").exists());
+}
+
+#[test]
+fn organism_pathways_sit_beside_the_generic_kegg_files() {
+    let temp = tempfile::tempdir().unwrap();
+    let (app, store) = kegg_app(&temp, TruncatingKegg::new(25, 25));
+    let fetch = |specifier: &str| {
+        app.fetch(
+            Some(specifier.parse().unwrap()),
+            None,
+            FetchOverrides::default(),
+            FetchOptions::default(),
+            &NoProgress,
+        )
+        .unwrap()
+        .items
+        .remove(0)
+    };
+
+    let item = fetch("kegg:hsa");
+    assert_eq!(
+        (item.id.as_str(), item.action.as_str()),
+        ("hsa", "download")
+    );
+    let dir = store.cache_kb_dir("kegg").join("hsa");
+    assert_eq!(item.cache_path.as_deref(), Some(dir.as_str()));
+    assert!(
+        std::fs::read_to_string(dir.join("pathway_gene.txt"))
+            .unwrap()
+            .contains("hsa:10327")
+    );
+    let meta: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.join("metadata.json")).unwrap()).unwrap();
+    assert_eq!(meta["organism"], "hsa");
+    assert_eq!(
+        meta["source_urls"][0],
+        "https://rest.kegg.jp/list/pathway/hsa"
+    );
+
+    // The organism's files do not count as the generic dataset, and
+    // fetching that keeps them.
+    assert_eq!(fetch("kegg").action, "download");
+    assert!(dir.join("pathway_list.txt").exists());
+    assert_eq!(fetch("kegg:hsa").action, "cache");
+    assert_eq!(fetch("kegg").action, "cache");
+
+    let info = app.info("kegg:hsa".parse().unwrap(), &NoProgress).unwrap();
+    assert_eq!(info.id, "hsa");
+    assert_eq!(info.details.unwrap()["organism"], "hsa");
+}
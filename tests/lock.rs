@@ -0,0 +1,201 @@
+mod support;
+
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use assert_matches::assert_matches;
+use camino::Utf8PathBuf;
+
+use kira_biodata_manager::app::{App, FetchOptions, FetchOverrides, FetchResult, ProgressSink};
+use kira_biodata_manager::domain::{DatasetSpecifier, ProteinFormat, ProteinId};
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::lock::LockHolder;
+use kira_biodata_manager::rcsb::{RcsbClient, RcsbMetadata};
+use kira_biodata_manager::store::Store;
+
+use support::{RecordingSink, UnusedGeo, UnusedKnowledge, UnusedNcbi, UnusedSrr, UnusedUniprot};
+
+/// Counts structure downloads; the first one waits for the test to let it
+/// finish so a second fetch can run into the lock.
+struct GatedRcsb {
+    downloads: Arc<Mutex<Vec<String>>>,
+    started: Mutex<Sender<()>>,
+    release: Mutex<Receiver<()>>,
+}
+
+impl RcsbClient for GatedRcsb {
+    fn download_structure(
+        &self,
+        id: &ProteinId,
+        _format: ProteinFormat,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<(), KiraError> {
+        let first = {
+            let mut downloads = self.downloads.lock().unwrap();
+            downloads.push(id.as_str().to_string());
+            downloads.len() == 1
+        };
+        if first {
+            self.started.lock().unwrap().send(()).unwrap();
+            self.release.lock().unwrap().recv().unwrap();
+        }
+        std::fs::write(destination, b"data_mock\nloop_\n_atom_site.id\n1\n")
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+
+    fn fetch_metadata(&self, id: &ProteinId) -> Result<RcsbMetadata, KiraError> {
+        Ok(RcsbMetadata {
+            registry: "rcsb".to_string(),
+            pdb_id: id.as_str().to_string(),
+            title: None,
+            experimental_method: None,
+            resolution: None,
+            deposition_date: None,
+            release_date: None,
+            source_structure_url: String::new(),
+            source_metadata_url: String::new(),
+            raw_json: serde_json::json!({}),
+        })
+    }
+}
+
+type TestApp = App<UnusedNcbi, GatedRcsb, UnusedSrr, UnusedUniprot, UnusedGeo, UnusedKnowledge>;
+
+struct Harness {
+    app: TestApp,
+    store: Store,
+    downloads: Arc<Mutex<Vec<String>>>,
+    started: Receiver<()>,
+    release: Sender<()>,
+}
+
+fn harness(temp: &tempfile::TempDir) -> Harness {
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    let store = Store::new_with_paths(root.join("project"), root.join("cache"));
+    let downloads = Arc::new(Mutex::new(Vec::new()));
+    let (started_tx, started) = mpsc::channel();
+    let (release, release_rx) = mpsc::channel();
+    let rcsb = GatedRcsb {
+        downloads: downloads.clone(),
+        started: Mutex::new(started_tx),
+        release: Mutex::new(release_rx),
+    };
+    let app = App::new(
+        store.clone(),
+        UnusedNcbi,
+        rcsb,
+        UnusedSrr,
+        UnusedUniprot,
+        UnusedGeo,
+        UnusedKnowledge,
+    );
+    Harness {
+        app,
+        store,
+        downloads,
+        started,
+        release,
+    }
+}
+
+fn fetch(
+    app: &TestApp,
+    options: FetchOptions,
+    sink: &dyn ProgressSink,
+) -> Result<FetchResult, KiraError> {
+    app.fetch(
+        Some("protein:1LYZ".parse::<DatasetSpecifier>().unwrap()),
+        None,
+        FetchOverrides::default(),
+        options,
+        sink,
+    )
+}
+
+#[test]
+fn concurrent_fetches_of_one_dataset_download_once() {
+    let temp = tempfile::tempdir().unwrap();
+    let harness = harness(&temp);
+    let first_sink = RecordingSink::default();
+    let second_sink = RecordingSink::default();
+
+    let (first, second) = std::thread::scope(|scope| {
+        let first = scope.spawn(|| fetch(&harness.app, FetchOptions::default(), &first_sink));
+        harness.started.recv().unwrap();
+        let second = scope.spawn(|| fetch(&harness.app, FetchOptions::default(), &second_sink));
+        while !second_sink.saw("lock.wait protein:1LYZ") {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        harness.release.send(()).unwrap();
+        (first.join().unwrap(), second.join().unwrap())
+    });
+
+    assert_eq!(first.unwrap().items[0].action, "download");
+    assert_eq!(second.unwrap().items[0].action, "project");
+    assert_eq!(*harness.downloads.lock().unwrap(), vec!["1LYZ"]);
+    let lock = harness.store.lock_path("protein", "1LYZ");
+    assert!(lock.exists());
+    assert!(std::fs::read(lock).unwrap().is_empty());
+}
+
+#[test]
+fn waiting_gives_up_and_names_the_holder() {
+    let temp = tempfile::tempdir().unwrap();
+    let harness = harness(&temp);
+    let held = harness
+        .store
+        .lock_dataset("protein", "1LYZ", Duration::ZERO, &RecordingSink::default())
+        .unwrap();
+
+    let options = FetchOptions::builder().lock_timeout_secs(Some(0)).build();
+    let err = fetch(&harness.app, options, &RecordingSink::default()).unwrap_err();
+    let holder = format!("pid {}", std::process::id());
+    assert_matches!(
+        err.root(),
+        KiraError::StoreLocked { dataset, holder: named, .. }
+            if dataset == "protein:1LYZ" && named.starts_with(&holder)
+    );
+    assert!(err.to_string().contains("--lock-timeout"));
+    assert!(harness.downloads.lock().unwrap().is_empty());
+
+    // Dry runs never wait.
+    let options = FetchOptions::builder()
+        .dry_run(true)
+        .lock_timeout_secs(Some(0))
+        .build();
+    assert!(fetch(&harness.app, options, &RecordingSink::default()).is_ok());
+
+    drop(held);
+    harness.release.send(()).unwrap();
+    let result = fetch(
+        &harness.app,
+        FetchOptions::default(),
+        &RecordingSink::default(),
+    );
+    assert_eq!(result.unwrap().items[0].action, "download");
+}
+
+#[test]
+fn stale_lock_from_a_dead_process_is_reclaimed() {
+    let temp = tempfile::tempdir().unwrap();
+    let harness = harness(&temp);
+    let lock = harness.store.lock_path("protein", "1LYZ");
+    std::fs::create_dir_all(lock.parent().unwrap()).unwrap();
+    let crashed = LockHolder {
+        pid: 4_000_000,
+        acquired_at: "2024-01-01T00:00:00+00:00".to_string(),
+    };
+    std::fs::write(&lock, serde_json::to_vec(&crashed).unwrap()).unwrap();
+
+    harness.release.send(()).unwrap();
+    let sink = RecordingSink::default();
+    let options = FetchOptions::builder().lock_timeout_secs(Some(0)).build();
+    let result = fetch(&harness.app, options, &sink).unwrap();
+
+    assert_eq!(result.items[0].action, "download");
+    assert!(sink.saw("lock.reclaimed protein:1LYZ; stale lock from pid 4000000"));
+    assert!(std::fs::read(lock).unwrap().is_empty());
+}
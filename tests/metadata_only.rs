@@ -0,0 +1,399 @@
+mod support;
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use camino::Utf8PathBuf;
+
+use kira_biodata_manager::app::{App, FetchOptions, FetchOverrides, ListOptions, ProgressSink};
+use kira_biodata_manager::domain::{
+    GenomeAccession, GeoSampleAccession, GeoSeriesAccession, ProteinFormat, ProteinId, SrrId,
+};
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::fs_util::write_hashed;
+use kira_biodata_manager::geo::GeoClient;
+use kira_biodata_manager::ncbi::{DATASET_CATALOG_PATH, DownloadInfo, NcbiClient};
+use kira_biodata_manager::output::JsonOutput;
+use kira_biodata_manager::rcsb::{RcsbClient, RcsbMetadata};
+use kira_biodata_manager::srr::{SrrClient, SrrDownloadOptions, ToolInfo};
+use kira_biodata_manager::store::{Materialization, Store};
+
+use support::{RecordingSink, UnusedKnowledge, UnusedUniprot};
+
+/// Registry requests made by the mocks, by kind.
+#[derive(Default)]
+struct Calls {
+    structures: AtomicUsize,
+    entries: AtomicUsize,
+    packages: AtomicUsize,
+    reports: AtomicUsize,
+    soft: AtomicUsize,
+    files: AtomicUsize,
+}
+
+impl Calls {
+    fn count(counter: &AtomicUsize) -> usize {
+        counter.load(Ordering::SeqCst)
+    }
+}
+
+struct CountingRcsb(Arc<Calls>);
+
+impl RcsbClient for CountingRcsb {
+    fn download_structure(
+        &self,
+        id: &ProteinId,
+        _format: ProteinFormat,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<(), KiraError> {
+        self.0.structures.fetch_add(1, Ordering::SeqCst);
+        std::fs::write(
+            destination,
+            format!("data_{}\nloop_\n_atom_site.id\n1\n", id.as_str()),
+        )
+        .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+
+    fn fetch_metadata(&self, id: &ProteinId) -> Result<RcsbMetadata, KiraError> {
+        self.0.entries.fetch_add(1, Ordering::SeqCst);
+        Ok(RcsbMetadata {
+            registry: "rcsb".to_string(),
+            pdb_id: id.as_str().to_string(),
+            title: Some("LYSOZYME".to_string()),
+            experimental_method: Some("X-RAY DIFFRACTION".to_string()),
+            resolution: Some(1.5),
+            deposition_date: None,
+            release_date: None,
+            source_structure_url: String::new(),
+            source_metadata_url: String::new(),
+            raw_json: serde_json::json!({"rcsb_id": id.as_str()}),
+        })
+    }
+}
+
+/// Serves packages built from `tests/fixtures/ncbi/<accession>_catalog.json`
+/// and a one-line dataset report.
+struct CatalogNcbi(Arc<Calls>);
+
+impl NcbiClient for CatalogNcbi {
+    fn download_protein(
+        &self,
+        _id: &ProteinId,
+        _format: ProteinFormat,
+        _destination: &Path,
+    ) -> Result<DownloadInfo, KiraError> {
+        Err(KiraError::NcbiHttp("not used".to_string()))
+    }
+
+    fn download_genome(
+        &self,
+        accession: &GenomeAccession,
+        _include: &[String],
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<DownloadInfo, KiraError> {
+        self.0.packages.fetch_add(1, Ordering::SeqCst);
+        let catalog = std::fs::read_to_string(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/ncbi")
+                .join(format!("{}_catalog.json", accession.as_str())),
+        )
+        .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&catalog).unwrap();
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(destination).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file(DATASET_CATALOG_PATH, options).unwrap();
+        zip.write_all(catalog.as_bytes()).unwrap();
+        for assembly in parsed["assemblies"].as_array().unwrap() {
+            for file in assembly["files"].as_array().unwrap() {
+                let path = file["filePath"]
+                    .as_str()
+                    .or_else(|| file["file_path"].as_str())
+                    .unwrap();
+                zip.start_file(format!("ncbi_dataset/data/{path}"), options)
+                    .unwrap();
+                zip.write_all(b"placeholder\n").unwrap();
+            }
+        }
+        zip.finish().unwrap();
+        Ok(DownloadInfo { is_zip: true })
+    }
+
+    fn download_genome_report(
+        &self,
+        accession: &GenomeAccession,
+        destination: &Path,
+    ) -> Result<(), KiraError> {
+        self.0.reports.fetch_add(1, Ordering::SeqCst);
+        let report = serde_json::json!({
+            "reports": [{"accession": accession.as_str()}],
+            "total_count": 1,
+        });
+        std::fs::write(destination, report.to_string())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+}
+
+/// Serves SOFT text from `tests/fixtures/geo`; downloads write the URL.
+struct FixtureGeo(Arc<Calls>);
+
+impl GeoClient for FixtureGeo {
+    fn fetch_soft_text(&self, accession: &GeoSeriesAccession) -> Result<String, KiraError> {
+        self.0.soft.fetch_add(1, Ordering::SeqCst);
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/geo")
+            .join(format!("{}_family.soft", accession.as_str()));
+        std::fs::read_to_string(path).map_err(|err| KiraError::GeoHttp(err.to_string()))
+    }
+
+    fn fetch_sample_soft_text(&self, _accession: &GeoSampleAccession) -> Result<String, KiraError> {
+        Err(KiraError::GeoHttp("not used".to_string()))
+    }
+
+    fn download_url(
+        &self,
+        url: &str,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        self.0.files.fetch_add(1, Ordering::SeqCst);
+        write_hashed(destination, url.as_bytes())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+}
+
+struct FixtureSrr;
+
+/// Four reads on disk, five spots according to ENA.
+impl SrrClient for FixtureSrr {
+    fn download_fastq(
+        &self,
+        id: &SrrId,
+        _paired: bool,
+        destination_dir: &Path,
+        _options: &SrrDownloadOptions,
+    ) -> Result<Vec<PathBuf>, KiraError> {
+        std::fs::create_dir_all(destination_dir).unwrap();
+        let path = destination_dir.join(format!("{}.fastq", id.as_str()));
+        std::fs::write(&path, "@r\nACGT\n+\nIIII\n".repeat(4)).unwrap();
+        Ok(vec![path])
+    }
+
+    fn tool_info(&self) -> ToolInfo {
+        ToolInfo::from_output(None, None)
+    }
+
+    fn fetch_run_info(&self, id: &SrrId) -> Result<serde_json::Value, KiraError> {
+        Ok(serde_json::json!({
+            "run_accession": id.as_str(),
+            "read_count": "5",
+            "base_count": "20",
+            "instrument_platform": "ILLUMINA",
+            "library_layout": "SINGLE",
+            "library_strategy": "RNA-Seq",
+            "fastq_bytes": "",
+        }))
+    }
+}
+
+type TestApp =
+    App<CatalogNcbi, CountingRcsb, FixtureSrr, UnusedUniprot, FixtureGeo, UnusedKnowledge>;
+
+fn app(temp: &tempfile::TempDir) -> (TestApp, Arc<Calls>) {
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    let calls = Arc::new(Calls::default());
+    let app = App::new(
+        Store::new_with_paths(root.join("project"), root.join("cache")),
+        CatalogNcbi(calls.clone()),
+        CountingRcsb(calls.clone()),
+        FixtureSrr,
+        UnusedUniprot,
+        FixtureGeo(calls.clone()),
+        UnusedKnowledge,
+    );
+    (app, calls)
+}
+
+/// Fetches one specifier and returns its action.
+fn fetch(app: &TestApp, specifier: &str, metadata_only: bool) -> Result<String, KiraError> {
+    app.fetch(
+        Some(specifier.parse().unwrap()),
+        None,
+        FetchOverrides::default(),
+        FetchOptions::builder().metadata_only(metadata_only).build(),
+        &JsonOutput,
+    )
+    .map(|result| result.items[0].action.clone())
+}
+
+fn materialization(app: &TestApp, dataset_type: &str) -> Materialization {
+    let list = app.list(&ListOptions::default(), &JsonOutput).unwrap();
+    list.datasets
+        .iter()
+        .find(|entry| entry.dataset_type == dataset_type)
+        .unwrap()
+        .materialization
+}
+
+#[test]
+fn protein_metadata_is_upgraded_without_refetching_the_entry() {
+    let temp = tempfile::tempdir().unwrap();
+    let (app, calls) = app(&temp);
+    let dir = temp.path().join("project/proteins/1LYZ");
+
+    assert_eq!(fetch(&app, "protein:1LYZ", true).unwrap(), "metadata");
+    assert!(dir.join("metadata.json").exists());
+    assert!(dir.join("metadata.raw.json").exists());
+    assert!(!dir.join("1LYZ.cif").exists());
+    assert!(!temp.path().join("cache/proteins").exists());
+    assert_eq!(
+        materialization(&app, "protein"),
+        Materialization::MetadataOnly
+    );
+    let record: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(temp.path().join("project/metadata/protein/1LYZ.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(record["materialization"], "metadata-only");
+    // A second metadata-only fetch finds the copy already there.
+    assert_eq!(fetch(&app, "protein:1LYZ", true).unwrap(), "project");
+
+    assert_eq!(fetch(&app, "protein:1LYZ", false).unwrap(), "download");
+    assert!(dir.join("1LYZ.cif").exists());
+    assert_eq!(Calls::count(&calls.entries), 1);
+    assert_eq!(Calls::count(&calls.structures), 1);
+    assert_eq!(materialization(&app, "protein"), Materialization::Full);
+    let info = app
+        .info("protein:1LYZ".parse().unwrap(), &JsonOutput)
+        .unwrap();
+    assert!(
+        serde_json::to_value(&info)
+            .unwrap()
+            .get("materialization")
+            .is_none()
+    );
+}
+
+#[test]
+fn genome_report_is_kept_when_the_package_is_fetched() {
+    let temp = tempfile::tempdir().unwrap();
+    let (app, calls) = app(&temp);
+    let dir = temp.path().join("project/genomes/GCF_000005845.2");
+
+    assert_eq!(
+        fetch(&app, "genome:GCF_000005845.2", true).unwrap(),
+        "metadata"
+    );
+    assert!(dir.join("dataset_report.json").exists());
+    assert!(!dir.join("metadata.json").exists());
+    assert_eq!(Calls::count(&calls.packages), 0);
+    assert_eq!(
+        materialization(&app, "genome"),
+        Materialization::MetadataOnly
+    );
+
+    assert_eq!(
+        fetch(&app, "genome:GCF_000005845.2", false).unwrap(),
+        "download"
+    );
+    assert!(dir.join("dataset_report.json").exists());
+    assert!(dir.join("metadata.json").exists());
+    assert_eq!(Calls::count(&calls.packages), 1);
+    assert_eq!(Calls::count(&calls.reports), 1);
+    assert_eq!(materialization(&app, "genome"), Materialization::Full);
+    // Nothing more to do for a full copy either way.
+    assert_eq!(
+        fetch(&app, "genome:GCF_000005845.2", false).unwrap(),
+        "project"
+    );
+    assert_eq!(
+        fetch(&app, "genome:GCF_000005845.2", true).unwrap(),
+        "project"
+    );
+}
+
+#[test]
+fn expression_upgrade_reuses_the_stored_soft_text() {
+    let temp = tempfile::tempdir().unwrap();
+    let (app, calls) = app(&temp);
+    let dir = temp.path().join("project/expression/GSE200002");
+
+    assert_eq!(
+        fetch(&app, "expression:GSE200002", true).unwrap(),
+        "metadata"
+    );
+    assert!(dir.join("metadata/geo_soft.txt").exists());
+    let samples: Vec<String> =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("metadata/samples.json")).unwrap())
+            .unwrap();
+    assert!(!samples.is_empty());
+    assert_eq!(Calls::count(&calls.files), 0);
+    assert_eq!(
+        materialization(&app, "expression"),
+        Materialization::MetadataOnly
+    );
+
+    assert_eq!(
+        fetch(&app, "expression:GSE200002", false).unwrap(),
+        "download"
+    );
+    assert_eq!(Calls::count(&calls.soft), 1);
+    assert!(Calls::count(&calls.files) > 0);
+    assert!(dir.join("GSE200002_RAW.tar").exists());
+    assert!(dir.join("metadata/metadata.json").exists());
+    assert!(dir.join("metadata/samples.json").exists());
+    assert_eq!(materialization(&app, "expression"), Materialization::Full);
+}
+
+#[test]
+fn srr_run_info_is_stored_and_checked_against_the_reads() {
+    let temp = tempfile::tempdir().unwrap();
+    let (app, _) = app(&temp);
+    let run_info = |app: &TestApp| {
+        let info = app
+            .info("srr:SRR000001".parse().unwrap(), &JsonOutput)
+            .unwrap();
+        info.details.unwrap()["run_info"].clone()
+    };
+
+    assert_eq!(fetch(&app, "srr:SRR000001", true).unwrap(), "metadata");
+    assert_eq!(run_info(&app)["read_count"], 5);
+    assert_eq!(run_info(&app)["instrument_platform"], "ILLUMINA");
+
+    let sink = RecordingSink::default();
+    app.fetch(
+        Some("srr:SRR000001".parse().unwrap()),
+        None,
+        FetchOverrides::default(),
+        FetchOptions::default(),
+        &sink,
+    )
+    .unwrap();
+    let events = sink.messages();
+    assert!(
+        events.contains(&"srr.read_count_mismatch SRR000001 4 5".to_string()),
+        "{events:?}"
+    );
+    let details = app
+        .info("srr:SRR000001".parse().unwrap(), &JsonOutput)
+        .unwrap()
+        .details
+        .unwrap();
+    assert_eq!(details["fastq_read_count"], 4);
+    assert_eq!(details["run_info"]["base_count"], 20);
+    assert_eq!(details["run_info"]["library_layout"], "SINGLE");
+}
+
+#[test]
+fn knowledge_bases_have_no_metadata_only_mode() {
+    let temp = tempfile::tempdir().unwrap();
+    let (app, _) = app(&temp);
+    let err = fetch(&app, "go", true).unwrap_err();
+    assert!(
+        matches!(err.root(), KiraError::MetadataOnlyUnsupported(kind) if kind == "go"),
+        "{err}"
+    );
+}
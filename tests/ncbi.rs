@@ -1,4 +1,5 @@
-use kira_biodata_manager::ncbi::map_genome_include;
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::ncbi::{map_genome_include, parse_dataset_catalog};
 
 #[test]
 fn map_include_values() {
@@ -10,3 +11,61 @@ fn map_include_values() {
     let mapped = map_genome_include(&include).unwrap();
     assert_eq!(mapped, vec!["GENOME_FASTA", "GENOME_GFF", "PROT_FASTA"]);
 }
+
+fn catalog_fixture(accession: &str) -> String {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/ncbi")
+        .join(format!("{accession}_catalog.json"));
+    std::fs::read_to_string(path).unwrap()
+}
+
+fn default_include() -> Vec<String> {
+    ["genome", "gff3", "protein", "seq-report"]
+        .iter()
+        .map(|item| item.to_string())
+        .collect()
+}
+
+#[test]
+fn parses_current_catalog() {
+    let catalog = parse_dataset_catalog(&catalog_fixture("GCF_000005845.2")).unwrap();
+    assert_eq!(catalog.api_version.as_deref(), Some("V2"));
+    assert_eq!(catalog.files.len(), 5);
+    assert_eq!(
+        catalog.files[1].accession.as_deref(),
+        Some("GCF_000005845.2")
+    );
+
+    let inventory = catalog.inventory();
+    assert_eq!(
+        inventory["gff3"],
+        vec!["GCF_000005845.2/genomic.gff".to_string()]
+    );
+    assert_eq!(
+        inventory["data-report"],
+        vec!["assembly_data_report.jsonl".to_string()]
+    );
+    assert!(catalog.missing_artifacts(&default_include()).is_empty());
+}
+
+#[test]
+fn parses_legacy_catalog_and_reports_gaps() {
+    let catalog = parse_dataset_catalog(&catalog_fixture("GCA_000002945.2")).unwrap();
+    assert_eq!(catalog.api_version.as_deref(), Some("V1"));
+    assert!(catalog.inventory().contains_key("genome"));
+    assert_eq!(
+        catalog.missing_artifacts(&default_include()),
+        vec!["gff3".to_string(), "protein".to_string()]
+    );
+    assert!(
+        catalog
+            .missing_artifacts(&["default".to_string()])
+            .is_empty()
+    );
+}
+
+#[test]
+fn rejects_malformed_catalog() {
+    let err = parse_dataset_catalog("{\"assemblies\": [{\"files\": [{}]}]}").unwrap_err();
+    assert!(matches!(err, KiraError::InvalidCatalog(_)));
+}
@@ -0,0 +1,59 @@
+use clap::Parser;
+
+use kira_biodata_manager::app::{FetchFlags, FetchOptions, FetchOverrides};
+use kira_biodata_manager::domain::SrrFormat;
+
+#[derive(Parser)]
+struct FlagsCli {
+    #[command(flatten)]
+    flags: FetchFlags,
+}
+
+#[test]
+fn default_options_match_cli_defaults() {
+    let cli = FlagsCli::try_parse_from(["kira-bm"]).unwrap();
+    assert_eq!(FetchOptions::from(&cli.flags), FetchOptions::default());
+}
+
+#[test]
+fn cli_flags_map_onto_builder() {
+    let cli = FlagsCli::try_parse_from(["kira-bm", "--force", "--no-cache"]).unwrap();
+    let expected = FetchOptions::builder().force(true).no_cache(true).build();
+    assert_eq!(FetchOptions::from(&cli.flags), expected);
+}
+
+#[test]
+fn options_summary_lists_enabled_flags() {
+    assert_eq!(FetchOptions::default().summary(), "options: defaults");
+    let options = FetchOptions::builder()
+        .force(true)
+        .no_cache(true)
+        .dry_run(true)
+        .build();
+    assert_eq!(options.summary(), "options: force, no-cache, dry-run");
+
+    let overrides = FetchOverrides::builder()
+        .srr_format(SrrFormat::Fasta)
+        .srr_paired(true)
+        .follow_superseries(true)
+        .build();
+    assert_eq!(
+        overrides.to_string(),
+        "srr-format=fasta, paired=true, follow-superseries"
+    );
+    assert_eq!(FetchOverrides::default().to_string(), "none");
+}
+
+#[test]
+fn options_serialize_for_provenance() {
+    let options = FetchOptions::builder().dry_run(true).build();
+    let value = serde_json::to_value(&options).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({"force": false, "no_cache": false, "dry_run": true})
+    );
+    let options = FetchOptions::builder().metadata_only(true).build();
+    let value = serde_json::to_value(&options).unwrap();
+    assert_eq!(value["metadata_only"], true);
+    assert_eq!(options.summary(), "options: metadata-only");
+}
@@ -0,0 +1,316 @@
+use std::collections::BTreeMap;
+
+use kira_biodata_manager::app::{
+    FetchItemError, FetchItemResult, FetchResult, FetchSummary, FindResult, IdCount, ListEntry,
+    ListResult, PhaseTimings,
+};
+use kira_biodata_manager::output::{OutputMode, PlainOutput, StyledOutput};
+use kira_biodata_manager::search::{SearchField, SearchMatch};
+use kira_biodata_manager::stats::{CacheSavings, CacheStats};
+use kira_biodata_manager::store::{DownloadRecord, Materialization};
+
+fn fetch_result() -> FetchResult {
+    FetchResult {
+        items: vec![
+            FetchItemResult {
+                dataset_type: "protein".to_string(),
+                id: "1LYZ".to_string(),
+                format: Some("cif".to_string()),
+                source: "rcsb".to_string(),
+                action: "download".to_string(),
+                project_path: Some("./.kira-bm/data/proteins/1LYZ".to_string()),
+                cache_path: None,
+                download: None,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
+            },
+            FetchItemResult {
+                dataset_type: "uniprot".to_string(),
+                id: "P69905".to_string(),
+                format: None,
+                source: "uniprot".to_string(),
+                action: "cache".to_string(),
+                project_path: None,
+                cache_path: Some("/cache/uniprot/P69905".to_string()),
+                download: None,
+                cache_savings: None,
+                cache: None,
+                timings: PhaseTimings::default(),
+            },
+        ],
+        errors: Vec::new(),
+        summary: None,
+        total_duration_ms: 0,
+        timings: PhaseTimings::default(),
+    }
+}
+
+#[test]
+fn plain_fetch_summary_is_an_ascii_table() {
+    let text = PlainOutput::render_fetch(&fetch_result());
+    assert_eq!(
+        text,
+        "Fetched 2 datasets\n\
+         TYPE     ID      ACTION    PROJECT                        CACHE\n\
+         protein  1LYZ    download  ./.kira-bm/data/proteins/1LYZ  -\n\
+         uniprot  P69905  cache     -                              /cache/uniprot/P69905\n"
+    );
+    assert!(text.is_ascii());
+}
+
+#[test]
+fn fetch_summaries_list_the_datasets_that_failed() {
+    let mut result = fetch_result();
+    result.items.truncate(1);
+    result.errors.push(FetchItemError {
+        specifier: "genome:GCF_000001405.40".to_string(),
+        message: "NCBI request failed: 503".to_string(),
+        retryable: true,
+    });
+    assert_eq!(
+        PlainOutput::render_fetch(&result),
+        "Fetched 1 datasets\n\
+         TYPE     ID    ACTION    PROJECT                        CACHE\n\
+         protein  1LYZ  download  ./.kira-bm/data/proteins/1LYZ  -\n\
+         Failed 1 datasets\n\
+         SPECIFIER                RETRYABLE  ERROR\n\
+         genome:GCF_000001405.40  yes        NCBI request failed: 503\n"
+    );
+    let styled = StyledOutput::render_fetch(&result);
+    assert!(styled.contains("Errors: 1"));
+    assert!(styled.contains("genome:GCF_000001405.40: NCBI request failed: 503"));
+}
+
+#[test]
+fn plain_fetch_summary_reports_doi_resolution() {
+    let mut result = fetch_result();
+    result.items.truncate(1);
+    result.summary = Some(FetchSummary {
+        kind: "doi".to_string(),
+        doi: Some("10.1038/s41586-020-2649-2".to_string()),
+        bioproject: None,
+        experiment: None,
+        taxon: None,
+        id_counts: vec![IdCount {
+            id_type: "pdb".to_string(),
+            count: 1,
+        }],
+        resolved_targets: 1,
+        unresolved: 2,
+        selection: None,
+        uniprot: None,
+        structures: None,
+        doi_selection: None,
+    });
+    assert_eq!(
+        PlainOutput::render_fetch(&result),
+        "Fetched 1 datasets\n\
+         doi 10.1038/s41586-020-2649-2: 1 resolved, 2 unresolved\n\
+         TYPE     ID    ACTION    PROJECT                        CACHE\n\
+         protein  1LYZ  download  ./.kira-bm/data/proteins/1LYZ  -\n"
+    );
+}
+
+#[test]
+fn plain_output_reports_cache_savings() {
+    let mut result = fetch_result();
+    result.items[1].cache_savings = Some(CacheSavings {
+        bytes_avoided: 3 * 1024 * 1024,
+        time_saved_ms: Some(4_200),
+    });
+    assert!(
+        PlainOutput::render_fetch(&result)
+            .ends_with("Cache saved 3.0 MB and 4.2 s of downloading\n")
+    );
+    assert!(StyledOutput::render_fetch(&result).contains("saved: 3.0 MB and 4.2 s"));
+
+    let stats = CacheStats {
+        cache_hits: 7,
+        bytes_avoided: 2048,
+        time_saved_ms: 125_000,
+    };
+    assert_eq!(
+        PlainOutput::render_cache_stats(&stats),
+        "Cache hits: 7\nBytes avoided: 2.0 KB\nTime saved: 2m 05s\n"
+    );
+}
+
+#[test]
+fn plain_list_and_find_carry_no_escapes() {
+    let list = ListResult {
+        datasets: vec![ListEntry {
+            dataset_type: "go".to_string(),
+            id: "go".to_string(),
+            format: None,
+            source: Some("go".to_string()),
+            project_path: None,
+            cache_path: Some("/cache/metadata/go".to_string()),
+            status: None,
+            downloaded_at: Some("2024-05-02T10:00:00+00:00".to_string()),
+            bytes: Some(2048),
+            provenance: None,
+            materialization: Materialization::Full,
+            formats: vec![],
+            alias: None,
+            pinned: false,
+        }],
+        warnings: vec![],
+    };
+    assert_eq!(
+        PlainOutput::render_list(&list),
+        "TYPE  ID  FORMAT  STATUS  SIZE    DOWNLOADED  PROJECT  CACHE\n\
+         go    go  -       -       2.0 KB  2024-05-02  -        /cache/metadata/go\n"
+    );
+    assert_eq!(
+        PlainOutput::render_list(&ListResult {
+            datasets: vec![],
+            warnings: vec![],
+        }),
+        "No datasets found\n"
+    );
+
+    let find = FindResult {
+        pattern: "lyso".to_string(),
+        matches: vec![SearchMatch {
+            dataset_type: "protein".to_string(),
+            id: "1LYZ".to_string(),
+            matched_field: SearchField::Title,
+            matched_text: "Hen egg-white lysozyme".to_string(),
+            score: 10,
+            project_path: None,
+            cache_path: None,
+        }],
+    };
+    assert_eq!(
+        PlainOutput::render_find(&find),
+        "TYPE     ID    FIELD      MATCH\n\
+         protein  1LYZ  title      Hen egg-white lysozyme\n"
+    );
+    assert!(StyledOutput::render_find(&find).contains("\x1b[1;33mlyso\x1b[0m"));
+}
+
+#[test]
+fn styled_fetch_summary_keeps_color_and_emoji() {
+    let text = StyledOutput::render_fetch(&fetch_result());
+    assert!(text.starts_with("\x1b[36m📦 KIRA-BM summary\x1b[0m\n"));
+    assert!(text.contains("\x1b[32m✅ Downloaded datasets: 2\x1b[0m\n"));
+}
+
+#[test]
+fn plain_fetch_summary_ends_with_where_the_time_went() {
+    let mut result = fetch_result();
+    result.items.push(FetchItemResult {
+        id: "P68871".to_string(),
+        ..result.items[1].clone()
+    });
+    for (item, download_ms) in result.items.iter_mut().zip([2_400, 0, 90]) {
+        item.timings = PhaseTimings {
+            resolve_ms: 10,
+            download_ms,
+            verify_ms: 0,
+            store_ms: 5,
+        };
+    }
+    result.total_duration_ms = 2_600;
+    result.timings = PhaseTimings {
+        resolve_ms: 30,
+        download_ms: 2_490,
+        verify_ms: 0,
+        store_ms: 15,
+    };
+
+    let text = PlainOutput::render_fetch(&result);
+    assert!(text.ends_with(
+        "Took 2.6 s: resolve 30 ms, download 2.5 s, verify 0 ms, store 15 ms\n\
+         Slowest: protein:1LYZ 2.4 s, uniprot:P68871 105 ms, uniprot:P69905 15 ms\n"
+    ));
+    assert!(!PlainOutput::render_fetch(&fetch_result()).contains("Took"));
+}
+
+#[test]
+fn fetch_json_stays_byte_stable() {
+    let mut result = fetch_result();
+    result.items.truncate(1);
+    assert_eq!(
+        serde_json::to_string_pretty(&result).unwrap(),
+        r#"{
+  "items": [
+    {
+      "dataset_type": "protein",
+      "id": "1LYZ",
+      "format": "cif",
+      "source": "rcsb",
+      "action": "download",
+      "project_path": "./.kira-bm/data/proteins/1LYZ",
+      "cache_path": null,
+      "timings": {
+        "resolve_ms": 0,
+        "download_ms": 0,
+        "verify_ms": 0,
+        "store_ms": 0
+      }
+    }
+  ],
+  "total_duration_ms": 0,
+  "timings": {
+    "resolve_ms": 0,
+    "download_ms": 0,
+    "verify_ms": 0,
+    "store_ms": 0
+  }
+}"#
+    );
+}
+
+#[test]
+fn fetch_json_reports_what_was_downloaded() {
+    let mut item = fetch_result().items.remove(1);
+    item.download = Some(DownloadRecord {
+        source_url: vec!["https://rest.uniprot.org/uniprotkb/P69905.fasta".to_string()],
+        bytes_written: 160,
+        sha256: BTreeMap::from([("P69905.fasta".to_string(), "ab12".to_string())]),
+        duration_ms: None,
+        validators: BTreeMap::new(),
+    });
+    assert_eq!(
+        serde_json::to_value(&item).unwrap(),
+        serde_json::json!({
+            "dataset_type": "uniprot",
+            "id": "P69905",
+            "format": null,
+            "source": "uniprot",
+            "action": "cache",
+            "project_path": null,
+            "cache_path": "/cache/uniprot/P69905",
+            "source_url": ["https://rest.uniprot.org/uniprotkb/P69905.fasta"],
+            "bytes_written": 160,
+            "sha256": {"P69905.fasta": "ab12"},
+            "timings": {"resolve_ms": 0, "download_ms": 0, "verify_ms": 0, "store_ms": 0},
+        })
+    );
+}
+
+#[test]
+fn output_mode_falls_back_to_plain_off_terminal() {
+    assert_eq!(
+        OutputMode::select(false, false, true, false),
+        OutputMode::Interactive
+    );
+    assert_eq!(
+        OutputMode::select(false, false, false, false),
+        OutputMode::Plain
+    );
+    assert_eq!(
+        OutputMode::select(false, false, true, true),
+        OutputMode::Plain
+    );
+    assert_eq!(
+        OutputMode::select(false, true, true, false),
+        OutputMode::Plain
+    );
+    assert_eq!(
+        OutputMode::select(true, true, false, true),
+        OutputMode::NonInteractive
+    );
+}
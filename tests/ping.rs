@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use kira_biodata_manager::http::{HEALTH_ENDPOINTS, HealthEndpoint, health_endpoint};
+use kira_biodata_manager::output::PlainOutput;
+use kira_biodata_manager::ping::{self, EndpointProbe, PingStatus, ProbeError, ProbeResponse};
+
+/// Gives every endpoint the same answer.
+struct FixedProbe(Result<ProbeResponse, ProbeError>);
+
+impl EndpointProbe for FixedProbe {
+    fn probe(&self, _endpoint: &HealthEndpoint) -> Result<ProbeResponse, ProbeError> {
+        self.0.clone()
+    }
+}
+
+fn answered(status: u16) -> FixedProbe {
+    FixedProbe(Ok(ProbeResponse {
+        status,
+        latency: Duration::from_millis(85),
+        date: None,
+    }))
+}
+
+fn all() -> Vec<&'static HealthEndpoint> {
+    HEALTH_ENDPOINTS.iter().collect()
+}
+
+#[test]
+fn outcomes_are_classified_for_every_registry() {
+    let cases = [
+        (answered(200), PingStatus::Ok),
+        (answered(404), PingStatus::ClientError),
+        (answered(503), PingStatus::ServerError),
+        (
+            FixedProbe(Err(ProbeError::Timeout("operation timed out".to_string()))),
+            PingStatus::Timeout,
+        ),
+        (
+            FixedProbe(Err(ProbeError::Tls(
+                "invalid peer certificate: UnknownIssuer".to_string(),
+            ))),
+            PingStatus::Tls,
+        ),
+        (
+            FixedProbe(Err(ProbeError::Unreachable(
+                "dns error: failed to lookup address".to_string(),
+            ))),
+            PingStatus::Unreachable,
+        ),
+    ];
+    for (probe, expected) in cases {
+        let report = ping::ping(&probe, &all());
+        assert_eq!(report.results.len(), HEALTH_ENDPOINTS.len());
+        for (result, endpoint) in report.results.iter().zip(HEALTH_ENDPOINTS.iter()) {
+            assert_eq!(result.registry, endpoint.registry);
+            assert_eq!(result.url, endpoint.url);
+            assert_eq!(result.status, expected, "{}", endpoint.registry);
+            assert_eq!(result.http_status.is_some(), expected.is_reachable());
+            assert_eq!(result.error.is_some(), !expected.is_reachable());
+        }
+        if expected.is_reachable() {
+            assert!(report.unreachable().is_empty());
+        } else {
+            assert_eq!(report.unreachable().len(), HEALTH_ENDPOINTS.len());
+        }
+    }
+}
+
+#[test]
+fn report_serialises_for_scripts() {
+    let endpoints = vec![
+        health_endpoint("uniprot").unwrap(),
+        health_endpoint("GO").unwrap(),
+    ];
+    let report = ping::ping(&answered(200), &endpoints);
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(
+        json["results"][0],
+        serde_json::json!({
+            "registry": "uniprot",
+            "method": "GET",
+            "url": "https://rest.uniprot.org/uniprotkb/P69905.json?fields=accession",
+            "status": "ok",
+            "http_status": 200,
+            "latency_ms": 85,
+            "tls": true,
+        })
+    );
+    assert_eq!(json["results"][1]["tls"], false);
+
+    let failed = ping::ping(
+        &FixedProbe(Err(ProbeError::Timeout("timed out".to_string()))),
+        &endpoints,
+    );
+    let json = serde_json::to_value(&failed).unwrap();
+    assert_eq!(json["results"][0]["status"], "timeout");
+    assert_eq!(json["results"][0]["error"], "timed out");
+    assert!(json["results"][0].get("http_status").is_none());
+    assert_eq!(failed.unreachable(), vec!["uniprot", "go"]);
+
+    let text = PlainOutput::render_ping(&failed);
+    assert!(
+        text.starts_with("REGISTRY  STATUS   HTTP  LATENCY  TLS  URL\n"),
+        "{text}"
+    );
+    assert!(text.contains("\nuniprot: timed out\n"), "{text}");
+}
+
+#[test]
+fn every_registry_has_one_endpoint() {
+    for endpoint in &HEALTH_ENDPOINTS {
+        assert_eq!(
+            health_endpoint(endpoint.registry).map(|found| found.url),
+            Some(endpoint.url)
+        );
+    }
+    assert_eq!(health_endpoint("ena"), None);
+}
@@ -0,0 +1,60 @@
+use std::fs;
+use std::path::Path;
+
+use kira_biodata_manager::project::{Anchor, Project};
+
+fn touch(path: &Path) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, "{}").unwrap();
+}
+
+#[test]
+fn discovery_walks_up_to_the_nearest_config() {
+    let temp = tempfile::tempdir().unwrap();
+    let repo = temp.path().join("repo");
+    let notebooks = repo.join("analysis").join("notebooks");
+    fs::create_dir_all(repo.join(".git")).unwrap();
+    fs::create_dir_all(&notebooks).unwrap();
+    touch(&repo.join("kira-bm.json"));
+
+    let project = Project::discover(&notebooks);
+    assert_eq!(project.root, repo);
+    assert_eq!(project.anchor, Anchor::Config);
+    assert_eq!(project.config_path(), repo.join("kira-bm.json"));
+    assert_eq!(project.store_root(), repo.join(".kira-bm"));
+
+    // A nested project wins over the one above it.
+    touch(&repo.join("analysis").join("kira-bm.json"));
+    assert_eq!(Project::discover(&notebooks).root, repo.join("analysis"));
+}
+
+#[test]
+fn discovery_stops_at_the_repository_root() {
+    let temp = tempfile::tempdir().unwrap();
+    touch(&temp.path().join("kira-bm.json"));
+    let repo = temp.path().join("repo");
+    let scripts = repo.join("scripts");
+    fs::create_dir_all(repo.join(".git")).unwrap();
+    fs::create_dir_all(&scripts).unwrap();
+
+    // The config outside the repository belongs to another project.
+    let project = Project::discover(&scripts);
+    assert_eq!(project.root, repo);
+    assert_eq!(project.anchor, Anchor::Repository);
+    assert_eq!(project.store_root(), repo.join(".kira-bm"));
+}
+
+#[test]
+fn without_config_or_repository_the_start_directory_is_the_project() {
+    let temp = tempfile::tempdir().unwrap();
+    let nested = temp.path().join("a").join("b");
+    fs::create_dir_all(&nested).unwrap();
+
+    let project = Project::discover(&nested);
+    // Only meaningful when the temp dir is not itself inside a project.
+    if project.anchor == Anchor::WorkingDirectory {
+        assert_eq!(project.root, nested);
+    }
+    assert_eq!(Project::at(&nested).root, nested);
+    assert_eq!(Project::at(&nested).anchor, Anchor::WorkingDirectory);
+}
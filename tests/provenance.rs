@@ -0,0 +1,258 @@
+mod support;
+
+use std::collections::BTreeMap;
+
+use camino::Utf8PathBuf;
+
+use kira_biodata_manager::app::{App, DoiSelection};
+use kira_biodata_manager::domain::Doi;
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::output::{JsonOutput, PlainOutput};
+use kira_biodata_manager::providers::doi::{
+    DoiResolution, DoiSourceMetadata, ExtractedIds, HydratedGeo, HydratedSummary, ResolvedTarget,
+    ValidationSummary,
+};
+use kira_biodata_manager::store::{ExtractionSource, Materialization, Metadata, Provenance, Store};
+
+use support::{UnusedGeo, UnusedKnowledge, UnusedNcbi, UnusedRcsb, UnusedSrr, UnusedUniprot};
+
+const DOI: &str = "10.1000/example.1";
+
+fn resolution() -> DoiResolution {
+    DoiResolution {
+        doi: DOI.to_string(),
+        source: DoiSourceMetadata {
+            title: Some("Single-cell atlas of the lung".to_string()),
+            abstract_text: Some("Profiles are deposited as GSE200002.".to_string()),
+            references: vec!["See also 4HHB and gse200002.".to_string()],
+            links: vec![],
+            data_availability: vec!["Structures: PDB 1LYZ.".to_string()],
+        },
+        extracted: ExtractedIds::default(),
+        validation: ValidationSummary::default(),
+        hydrated: HydratedSummary {
+            geo: vec![HydratedGeo {
+                gse: "GSE200002".to_string(),
+                gsm: vec!["GSM6000001".to_string()],
+                srr: vec!["SRR1000001".to_string()],
+            }],
+            ..HydratedSummary::default()
+        },
+        resolved_targets: vec![],
+        unresolved: vec![],
+        resolved_at: Some("2026-10-15T12:00:00+00:00".to_string()),
+        agency: None,
+        related: vec![],
+    }
+}
+
+fn target(dataset_type: &str, id: &str) -> ResolvedTarget {
+    ResolvedTarget {
+        dataset_type: dataset_type.to_string(),
+        id: id.to_string(),
+    }
+}
+
+#[test]
+fn provenance_names_the_first_section_mentioning_the_id() {
+    let resolution = resolution();
+    let source = &resolution.source;
+    assert_eq!(
+        source.mentions("GSE200002"),
+        Some(ExtractionSource::Abstract)
+    );
+    assert_eq!(
+        source.mentions("1LYZ"),
+        Some(ExtractionSource::DataAvailability)
+    );
+    assert_eq!(source.mentions("4hhb"), Some(ExtractionSource::Reference));
+    // Whole accessions only: GSE2000 is a prefix of GSE200002.
+    assert_eq!(source.mentions("GSE2000"), None);
+
+    let direct = resolution.provenance(&target("expression", "GSE200002"));
+    assert_eq!(
+        direct,
+        Provenance {
+            doi: DOI.to_string(),
+            resolved_at: "2026-10-15T12:00:00+00:00".to_string(),
+            extracted_from: ExtractionSource::Abstract,
+            via: None,
+        }
+    );
+
+    let expanded = resolution.provenance(&target("srr", "SRR1000001"));
+    assert_eq!(expanded.extracted_from, ExtractionSource::Abstract);
+    assert_eq!(expanded.via.as_deref(), Some("GSE200002"));
+    assert_eq!(
+        expanded.to_string(),
+        format!("doi:{DOI} (abstract, via GSE200002)")
+    );
+
+    let unknown = resolution.provenance(&target("srr", "SRR9999999"));
+    assert_eq!(unknown.extracted_from, ExtractionSource::Unknown);
+    assert_eq!(unknown.via, None);
+}
+
+#[test]
+fn metadata_records_provenance_only_when_present() {
+    let provenance = resolution().provenance(&target("srr", "SRR1000001"));
+    let meta = record("srr", "SRR1000001", Some(provenance.clone()));
+    let json = serde_json::to_value(&meta).unwrap();
+    assert_eq!(
+        json["provenance"],
+        serde_json::json!({
+            "doi": DOI,
+            "resolved_at": "2026-10-15T12:00:00+00:00",
+            "extracted_from": "abstract",
+            "via": "GSE200002",
+        })
+    );
+    let parsed: Metadata = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed.provenance, Some(provenance));
+
+    // Records written before provenance was tracked still load.
+    let old = serde_json::to_value(record("protein", "1LYZ", None)).unwrap();
+    assert!(old.get("provenance").is_none());
+    let parsed: Metadata = serde_json::from_value(old).unwrap();
+    assert_eq!(parsed.provenance, None);
+}
+
+fn record(dataset_type: &str, id: &str, provenance: Option<Provenance>) -> Metadata {
+    Metadata {
+        source: "test".to_string(),
+        dataset_type: dataset_type.to_string(),
+        id: id.to_string(),
+        format: None,
+        downloaded_at: "2026-10-15T12:00:00+00:00".to_string(),
+        tool: "kira-bm".to_string(),
+        resolved_path: format!("/project/{dataset_type}/{id}"),
+        status: None,
+        expiry: None,
+        provenance,
+        materialization: Materialization::Full,
+        assembly_name: None,
+        download: None,
+        converted_formats: Vec::new(),
+        formats: BTreeMap::new(),
+        alias: None,
+        pinned: false,
+        imported_from: None,
+        experiment: None,
+        cache: None,
+        acquisition: None,
+    }
+}
+
+#[test]
+fn datasets_are_listed_per_doi() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    let store = Store::new_with_paths(root.join("project"), root.join("cache"));
+    let resolution = resolution();
+    let other = Provenance {
+        doi: "10.1000/other".to_string(),
+        ..resolution.provenance(&target("protein", "4HHB"))
+    };
+    for meta in [
+        record(
+            "srr",
+            "SRR1000001",
+            Some(resolution.provenance(&target("srr", "SRR1000001"))),
+        ),
+        record(
+            "expression",
+            "GSE200002",
+            Some(resolution.provenance(&target("expression", "GSE200002"))),
+        ),
+        record("protein", "4HHB", Some(other)),
+        record("protein", "1LYZ", None),
+    ] {
+        Store::write_metadata(
+            &store.project_metadata_path(&meta.dataset_type, &meta.id),
+            &meta,
+        )
+        .unwrap();
+    }
+    let app = App::new(
+        store,
+        UnusedNcbi,
+        UnusedRcsb,
+        UnusedSrr,
+        UnusedUniprot,
+        UnusedGeo,
+        UnusedKnowledge,
+    );
+
+    let doi: Doi = DOI.parse().unwrap();
+    let result = app.provenance(&doi, &JsonOutput).unwrap();
+    let ids = result
+        .datasets
+        .iter()
+        .map(|meta| meta.id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec!["GSE200002", "SRR1000001"]);
+
+    let text = PlainOutput::render_doi_datasets(&result);
+    let lines = text.lines().collect::<Vec<_>>();
+    assert!(lines[0].starts_with("TYPE"), "{text}");
+    assert!(lines[1].contains("abstract"), "{text}");
+    assert!(lines[2].contains("GSE200002"), "{text}");
+
+    let unknown: Doi = "10.1000/never-fetched".parse().unwrap();
+    assert!(matches!(
+        app.provenance(&unknown, &JsonOutput),
+        Err(KiraError::DatasetNotFound(_))
+    ));
+}
+
+#[test]
+fn doi_selection_filters_by_type_exclusion_and_per_type_limit() {
+    let targets = [
+        target("genome", "GCF_000005845.2"),
+        target("protein", "1LYZ"),
+        target("srr", "SRR1000001"),
+        target("srr", "SRR1000002"),
+        target("srr", "SRR1000003"),
+    ];
+    assert_eq!(
+        DoiSelection::default().skip_reasons(&targets),
+        [Some("report-only"); 5]
+    );
+    let download_all = DoiSelection {
+        download_all: true,
+        ..DoiSelection::default()
+    };
+    assert_eq!(download_all.skip_reasons(&targets), [None; 5]);
+
+    let filtered = DoiSelection {
+        only: vec!["SRR".to_string(), "protein".to_string()],
+        limit_per_type: Some(1),
+        exclude: vec!["srr1000001".to_string()],
+        ..DoiSelection::default()
+    };
+    assert_eq!(
+        filtered.skip_reasons(&targets),
+        [
+            Some("type"),
+            None,
+            Some("excluded"),
+            None,
+            Some("limit-per-type")
+        ]
+    );
+
+    let picked = DoiSelection {
+        picked: Some(vec!["srr:SRR1000003".to_string()]),
+        ..DoiSelection::default()
+    };
+    assert_eq!(
+        picked.skip_reasons(&targets),
+        [
+            Some("not-picked"),
+            Some("not-picked"),
+            Some("not-picked"),
+            Some("not-picked"),
+            None
+        ]
+    );
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use camino::Utf8PathBuf;
+use kira_biodata_manager::readme::{self, Citation, README_FILE, ReadmeDetails};
+use kira_biodata_manager::store::{
+    DownloadRecord, ExtractionSource, Materialization, Metadata, Provenance, Store,
+};
+
+fn metadata(dataset_type: &str, id: &str, source: &str) -> Metadata {
+    Metadata {
+        source: source.to_string(),
+        dataset_type: dataset_type.to_string(),
+        id: id.to_string(),
+        format: None,
+        downloaded_at: "2024-05-01T12:00:00+00:00".to_string(),
+        tool: "kira-bm/0.1.2".to_string(),
+        resolved_path: "/project/.kira-bm/x".to_string(),
+        status: None,
+        expiry: None,
+        provenance: None,
+        materialization: Materialization::Full,
+        assembly_name: None,
+        download: None,
+        converted_formats: Vec::new(),
+        formats: BTreeMap::new(),
+        alias: None,
+        pinned: false,
+        imported_from: None,
+        experiment: None,
+        cache: None,
+        acquisition: None,
+    }
+}
+
+fn snapshot_cases() -> Vec<(&'static str, Metadata, ReadmeDetails)> {
+    let mut protein = metadata("protein", "1LYZ", "rcsb");
+    protein.format = Some("cif".to_string());
+    protein.download = Some(DownloadRecord {
+        source_url: vec!["https://files.rcsb.org/download/1LYZ.cif".to_string()],
+        bytes_written: 1_200_000,
+        ..DownloadRecord::default()
+    });
+
+    let mut sample = metadata("expressionsample", "GSM3577221", "geo");
+    sample.provenance = Some(Provenance {
+        doi: "10.1038/s41586-020-2649-2".to_string(),
+        resolved_at: "2024-05-01T11:59:00+00:00".to_string(),
+        extracted_from: ExtractionSource::DataAvailability,
+        via: Some("GSE102902".to_string()),
+    });
+
+    let go = metadata("go", "go", "go");
+    let mut metadata_only = metadata("srr", "SRR014966", "ncbi");
+    metadata_only.materialization = Materialization::MetadataOnly;
+
+    vec![
+        (
+            "protein",
+            protein,
+            ReadmeDetails {
+                title: Some(
+                    "REAL-SPACE REFINEMENT OF THE STRUCTURE OF HEN EGG-WHITE LYSOZYME".to_string(),
+                ),
+                files: vec![
+                    ("1LYZ.cif".to_string(), 1_200_000),
+                    ("1LYZ.fasta".to_string(), 180),
+                    ("metadata.json".to_string(), 2_048),
+                ],
+                citation: None,
+            },
+        ),
+        (
+            "doi-sample",
+            sample,
+            ReadmeDetails {
+                title: Some("Lung tissue, patient 3".to_string()),
+                files: vec![("counts/matrix.mtx.gz".to_string(), 52_428_800)],
+                citation: Some(Citation {
+                    doi: "10.1038/s41586-020-2649-2".to_string(),
+                    title: Some("Array programming with NumPy".to_string()),
+                }),
+            },
+        ),
+        (
+            "metadata-only",
+            metadata_only,
+            ReadmeDetails {
+                title: None,
+                files: vec![("metadata.json".to_string(), 4_096)],
+                citation: None,
+            },
+        ),
+        (
+            "many-files",
+            go,
+            ReadmeDetails {
+                title: Some("Gene Ontology releases/2024-01-17".to_string()),
+                files: (0..103)
+                    .map(|n| (format!("goa/part-{n:03}.gaf.gz"), 1024))
+                    .collect(),
+                citation: None,
+            },
+        ),
+    ]
+}
+
+/// Regenerate with `KIRA_BM_UPDATE_READMES=1 cargo test --test readme`.
+#[test]
+fn readmes_match_their_snapshots() {
+    let update = std::env::var_os("KIRA_BM_UPDATE_READMES").is_some();
+    for (name, metadata, details) in snapshot_cases() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/readme")
+            .join(format!("{name}.md"));
+        let text = readme::render(&metadata, &details);
+        if update {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &text).unwrap();
+            continue;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+        assert_eq!(text, expected, "{name}");
+    }
+}
+
+#[test]
+fn rewriting_a_readme_gives_the_same_file_and_keeps_foreign_ones() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    let store = Store::new_with_paths(root.join("project"), root.join("cache"));
+    let dir = root.join("project/srr/SRR014966");
+    std::fs::create_dir_all(dir.join("reads").as_std_path()).unwrap();
+    std::fs::write(
+        dir.join("reads/SRR014966_1.fastq").as_std_path(),
+        b"@r\nACGT\n+\n!!!!\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("metadata.json").as_std_path(),
+        br#"{"run_info": {}}"#,
+    )
+    .unwrap();
+    let record = metadata("srr", "SRR014966", "ncbi");
+
+    assert!(readme::write(dir.as_std_path(), &record, &store).unwrap());
+    let first = std::fs::read_to_string(dir.join(README_FILE).as_std_path()).unwrap();
+    assert!(first.contains("| `reads/SRR014966_1.fastq` |"), "{first}");
+    assert!(!first.contains("| `README.md` |"), "{first}");
+    assert!(readme::write(dir.as_std_path(), &record, &store).unwrap());
+    let second = std::fs::read_to_string(dir.join(README_FILE).as_std_path()).unwrap();
+    assert_eq!(first, second);
+
+    // A README that came with the data stays as it is.
+    let genome = root.join("project/genomes/GCF_000005845.2");
+    std::fs::create_dir_all(genome.as_std_path()).unwrap();
+    std::fs::write(genome.join(README_FILE).as_std_path(), "# NCBI Datasets\n").unwrap();
+    let record = metadata("genome", "GCF_000005845.2", "ncbi");
+    assert!(!readme::write(genome.as_std_path(), &record, &store).unwrap());
+    assert_eq!(
+        std::fs::read_to_string(genome.join(README_FILE).as_std_path()).unwrap(),
+        "# NCBI Datasets\n"
+    );
+}
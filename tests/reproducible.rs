@@ -0,0 +1,232 @@
+mod support;
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+use camino::Utf8PathBuf;
+use chrono::DateTime;
+
+use kira_biodata_manager::app::{App, FetchOptions, FetchOverrides, ListOptions, ProgressSink};
+use kira_biodata_manager::domain::{DatasetSpecifier, GeoSampleAccession, GeoSeriesAccession};
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::fs_util::{sha256_file, to_canonical_json, write_hashed};
+use kira_biodata_manager::geo::GeoClient;
+use kira_biodata_manager::output::JsonOutput;
+use kira_biodata_manager::store::{StagingDir, Store};
+
+use support::{UnusedKnowledge, UnusedNcbi, UnusedRcsb, UnusedSrr, UnusedUniprot};
+
+struct FixtureGeo;
+
+impl GeoClient for FixtureGeo {
+    fn fetch_soft_text(&self, accession: &GeoSeriesAccession) -> Result<String, KiraError> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/geo")
+            .join(format!("{}_family.soft", accession.as_str()));
+        std::fs::read_to_string(path).map_err(|err| KiraError::GeoHttp(err.to_string()))
+    }
+
+    fn fetch_sample_soft_text(&self, _accession: &GeoSampleAccession) -> Result<String, KiraError> {
+        Err(KiraError::GeoHttp("not used".to_string()))
+    }
+
+    fn download_url(
+        &self,
+        url: &str,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        write_hashed(destination, url.as_bytes())
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+}
+
+const TIMESTAMP: &str = "<timestamp>";
+
+/// Fetches GSE200002 into a fresh store: a download into the cache, then a
+/// materialization into the project.
+fn fetch_into_fresh_store(
+    temp: &tempfile::TempDir,
+) -> App<UnusedNcbi, UnusedRcsb, UnusedSrr, UnusedUniprot, FixtureGeo, UnusedKnowledge> {
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    let app = App::new(
+        Store::new_with_paths(root.join("project"), root.join("cache")),
+        UnusedNcbi,
+        UnusedRcsb,
+        UnusedSrr,
+        UnusedUniprot,
+        FixtureGeo,
+        UnusedKnowledge,
+    );
+    app.fetch(
+        Some(DatasetSpecifier::Expression("GSE200002".parse().unwrap())),
+        None,
+        FetchOverrides::default(),
+        FetchOptions::default(),
+        &JsonOutput,
+    )
+    .unwrap();
+    app
+}
+
+/// Every file under `root` except the fetch history, keyed by relative
+/// path. The store root, `downloaded_at` timestamps and download durations,
+/// which differ between any two fetches, are masked in JSON files.
+fn snapshot(root: &Path) -> BTreeMap<String, Vec<u8>> {
+    let mut files = BTreeMap::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let relative = path
+                .strip_prefix(root)
+                .unwrap()
+                .to_string_lossy()
+                .into_owned();
+            if path.is_dir() {
+                stack.push(path);
+            } else if !relative.ends_with(".jsonl") {
+                let bytes = std::fs::read(&path).unwrap();
+                let bytes = if relative.ends_with(".json") {
+                    assert_canonical(&relative, &bytes);
+                    mask(&String::from_utf8(bytes).unwrap(), root).into_bytes()
+                } else {
+                    bytes
+                };
+                files.insert(relative, bytes);
+            }
+        }
+    }
+    files
+}
+
+fn assert_canonical(name: &str, bytes: &[u8]) {
+    let value: serde_json::Value = serde_json::from_slice(bytes).unwrap();
+    assert_eq!(
+        to_canonical_json(&value).unwrap(),
+        bytes,
+        "{name} is not canonical JSON"
+    );
+}
+
+fn mask(text: &str, root: &Path) -> String {
+    let pattern = regex::Regex::new(r#""downloaded_at": "[^"]*""#).unwrap();
+    let duration = regex::Regex::new(r#""duration_ms": \d+"#).unwrap();
+    let text = text.replace(&root.to_string_lossy().into_owned(), "<root>");
+    let text = pattern.replace_all(&text, format!(r#""downloaded_at": "{TIMESTAMP}""#));
+    duration
+        .replace_all(&text, r#""duration_ms": 0"#)
+        .into_owned()
+}
+
+fn recorded_mtime(metadata_path: &Path) -> SystemTime {
+    let record: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(metadata_path).unwrap()).unwrap();
+    DateTime::parse_from_rfc3339(record["downloaded_at"].as_str().unwrap())
+        .unwrap()
+        .into()
+}
+
+#[test]
+fn same_dataset_fetched_into_two_stores_is_identical() {
+    let first = tempfile::tempdir().unwrap();
+    let second = tempfile::tempdir().unwrap();
+    let first_app = fetch_into_fresh_store(&first);
+    let second_app = fetch_into_fresh_store(&second);
+
+    let first_files = snapshot(first.path());
+    let second_files = snapshot(second.path());
+    assert_eq!(
+        first_files.keys().collect::<Vec<_>>(),
+        second_files.keys().collect::<Vec<_>>()
+    );
+    assert_eq!(first_files, second_files);
+    assert!(first_files.contains_key("project/expression/GSE200002/GSE200002_RAW.tar"));
+    // Nothing staged is left beside the dataset.
+    assert!(
+        !first_files.keys().any(|name| name.contains("kira-bm-")),
+        "{:?}",
+        first_files.keys()
+    );
+
+    // The project copy carries the record's time, not the copy's.
+    for temp in [&first, &second] {
+        let mtime = recorded_mtime(
+            &temp
+                .path()
+                .join("project/metadata/expression/GSE200002.json"),
+        );
+        let data = temp.path().join("project/expression/GSE200002");
+        for name in [
+            "GSE200002_RAW.tar",
+            "GSE200002_gene_counts.tsv.gz",
+            "metadata/metadata.json",
+        ] {
+            let modified = std::fs::metadata(data.join(name))
+                .unwrap()
+                .modified()
+                .unwrap();
+            assert_eq!(modified, mtime, "{name}");
+        }
+    }
+
+    // Both records carry the digests taken at download time.
+    let record: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(
+            first
+                .path()
+                .join("cache/metadata/expression/GSE200002.json"),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let tar = first
+        .path()
+        .join("cache/expression/GSE200002/GSE200002_RAW.tar");
+    assert_eq!(
+        record["download"]["sha256"]["GSE200002_RAW.tar"],
+        sha256_file(&tar).unwrap().as_str()
+    );
+    assert!(
+        record["download"]["source_url"][0]
+            .as_str()
+            .unwrap()
+            .starts_with("https://ftp.ncbi.nlm.nih.gov/geo/series/")
+    );
+
+    let listed = |app: &App<_, _, _, _, _, _>, temp: &tempfile::TempDir| {
+        let result = app.list(&ListOptions::default(), &JsonOutput).unwrap();
+        mask(
+            &String::from_utf8(to_canonical_json(&result).unwrap()).unwrap(),
+            temp.path(),
+        )
+    };
+    assert_eq!(listed(&first_app, &first), listed(&second_app, &second));
+}
+
+#[test]
+fn staging_directories_are_named_after_the_dataset() {
+    let temp = tempfile::tempdir().unwrap();
+    let first = StagingDir::create_in(temp.path(), "kira-bm-geo", "GSE200002").unwrap();
+    let second = StagingDir::create_in(temp.path(), "kira-bm-geo", "GSE200002").unwrap();
+    let odd = StagingDir::create_in(temp.path(), "kira-bm-doi", "10.1000/xyz 1").unwrap();
+    assert_eq!(first.path(), temp.path().join("kira-bm-geo-GSE200002.0"));
+    assert_eq!(second.path(), temp.path().join("kira-bm-geo-GSE200002.1"));
+    assert_eq!(odd.path(), temp.path().join("kira-bm-doi-10.1000_xyz_1.0"));
+
+    // A dropped staging directory is removed and its name reused.
+    drop(first);
+    assert!(!temp.path().join("kira-bm-geo-GSE200002.0").exists());
+    let reused = StagingDir::create_in(temp.path(), "kira-bm-geo", "GSE200002").unwrap();
+    assert_eq!(reused.path(), temp.path().join("kira-bm-geo-GSE200002.0"));
+
+    let target = temp.path().join("GSE200002");
+    std::fs::write(second.path().join("data.txt"), "staged").unwrap();
+    second.persist(&target).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(target.join("data.txt")).unwrap(),
+        "staged"
+    );
+    assert!(!temp.path().join("kira-bm-geo-GSE200002.1").exists());
+}
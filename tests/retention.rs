@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+
+use camino::Utf8PathBuf;
+use chrono::{Duration, Utc};
+
+use kira_biodata_manager::audit;
+use kira_biodata_manager::config::RetentionConfig;
+use kira_biodata_manager::retention::{self, RetentionPolicy, parse_max_age};
+use kira_biodata_manager::store::{Materialization, Metadata, Store};
+
+fn store(temp: &tempfile::TempDir) -> Store {
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    Store::new_with_paths(root.join("project"), root.join("cache"))
+}
+
+/// Writes a project dataset directory with `files` and a record `days_old`.
+fn seed(
+    store: &Store,
+    dataset_type: &str,
+    id: &str,
+    dir: Utf8PathBuf,
+    days_old: i64,
+    files: &[(&str, &[u8])],
+) -> Utf8PathBuf {
+    for (name, content) in files {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    let record = store.project_metadata_path(dataset_type, id);
+    Store::write_metadata(
+        &record,
+        &Metadata {
+            source: "test".to_string(),
+            dataset_type: dataset_type.to_string(),
+            id: id.to_string(),
+            format: None,
+            downloaded_at: (Utc::now() - Duration::days(days_old)).to_rfc3339(),
+            tool: "kira-bm".to_string(),
+            resolved_path: dir.to_string(),
+            status: None,
+            expiry: None,
+            provenance: None,
+            materialization: Materialization::Full,
+            assembly_name: None,
+            download: None,
+            converted_formats: Vec::new(),
+            formats: BTreeMap::new(),
+            alias: None,
+            pinned: false,
+            imported_from: None,
+            experiment: None,
+            cache: None,
+            acquisition: None,
+        },
+    )
+    .unwrap();
+    record
+}
+
+fn read_record(path: &Utf8PathBuf) -> Metadata {
+    serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap()
+}
+
+fn policy(entries: &[(&str, &str)]) -> RetentionPolicy {
+    RetentionPolicy::from_config(&RetentionConfig {
+        max_age: entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<BTreeMap<_, _>>(),
+    })
+    .unwrap()
+}
+
+#[test]
+fn expires_old_data_and_keeps_records() {
+    let temp = tempfile::tempdir().unwrap();
+    let store = store(&temp);
+    let old_dir = store.project_srr_dir(&"SRR000001".parse().unwrap());
+    let old = seed(
+        &store,
+        "srr",
+        "SRR000001",
+        old_dir.clone(),
+        200,
+        &[
+            ("SRR000001_1.fastq", b"ACGT"),
+            ("SRR000001_2.fastq", b"TTGCA"),
+        ],
+    );
+    let fresh_dir = store.project_srr_dir(&"SRR000002".parse().unwrap());
+    let fresh = seed(
+        &store,
+        "srr",
+        "SRR000002",
+        fresh_dir.clone(),
+        10,
+        &[("SRR000002.fastq", b"ACGT")],
+    );
+    let pinned_dir = store.project_srr_dir(&"SRR000003".parse().unwrap());
+    let pinned = seed(
+        &store,
+        "srr",
+        "SRR000003",
+        pinned_dir.clone(),
+        400,
+        &[("SRR000003.fastq", b"ACGT")],
+    );
+    let mut record = read_record(&pinned);
+    record.pinned = true;
+    Store::write_metadata(&pinned, &record).unwrap();
+    let protein_dir = store.project_protein_dir(&"1LYZ".parse().unwrap());
+    seed(
+        &store,
+        "protein",
+        "1LYZ",
+        protein_dir.clone(),
+        900,
+        &[("1LYZ.cif", b"data")],
+    );
+    let kegg_dir = store.project_kb_dir("kegg");
+    let kegg = seed(
+        &store,
+        "kegg",
+        "kegg",
+        kegg_dir.clone(),
+        100,
+        &[
+            ("pathway_list.txt", b"map00010\n"),
+            ("metadata.json", b"{}"),
+        ],
+    );
+
+    let policy = policy(&[("srr", "180d"), ("kb", "90d")]);
+    let report = retention::apply(&store, &policy, Utc::now(), false, false).unwrap();
+
+    let mut expired = report
+        .expired
+        .iter()
+        .map(|entry| entry.specifier.as_str())
+        .collect::<Vec<_>>();
+    expired.sort();
+    assert_eq!(expired, vec!["kegg", "srr:SRR000001"]);
+    assert_eq!(report.pinned, vec!["srr:SRR000003"]);
+
+    assert!(!old_dir.exists());
+    assert!(fresh_dir.join("SRR000002.fastq").exists());
+    assert!(pinned_dir.join("SRR000003.fastq").exists());
+    assert!(protein_dir.join("1LYZ.cif").exists());
+    assert!(!kegg_dir.join("pathway_list.txt").exists());
+    assert!(kegg.exists());
+
+    let record = read_record(&old);
+    assert!(record.is_expired());
+    let expiry = record.expiry.unwrap();
+    assert_eq!(expiry.policy, "srr=180d");
+    assert_eq!(expiry.bytes, 9);
+    assert_eq!(expiry.files.len(), 2);
+    assert_eq!(expiry.files[0].path, "SRR000001_1.fastq");
+    assert_eq!(expiry.files[0].bytes, 4);
+    assert_eq!(
+        expiry.files[0].sha256,
+        "1dff3e84fe7877e0673b69bbddcf40124e396e3f9943dd890c91b6a09adb9af0"
+    );
+    assert!(!read_record(&fresh).is_expired());
+    let kegg_record = read_record(&kegg);
+    assert!(kegg_record.is_expired());
+    assert_eq!(kegg_record.expiry.unwrap().policy, "kb=90d");
+
+    let log = audit::read(store.project_root(), None).unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].operation, "expire");
+    assert_eq!(log[0].targets.len(), 2);
+
+    let again = retention::apply(&store, &policy, Utc::now(), false, false).unwrap();
+    assert!(again.expired.is_empty());
+}
+
+#[test]
+fn dry_run_reports_without_touching_anything() {
+    let temp = tempfile::tempdir().unwrap();
+    let store = store(&temp);
+    let dir = store.project_srr_dir(&"SRR000001".parse().unwrap());
+    let record = seed(
+        &store,
+        "srr",
+        "SRR000001",
+        dir.clone(),
+        200,
+        &[("SRR000001.fastq", b"ACGT")],
+    );
+
+    let report =
+        retention::apply(&store, &policy(&[("srr", "180d")]), Utc::now(), true, false).unwrap();
+
+    assert!(report.dry_run);
+    assert_eq!(report.expired.len(), 1);
+    assert_eq!(report.expired[0].specifier, "srr:SRR000001");
+    assert_eq!(report.expired[0].age_days, 200);
+    assert_eq!(report.expired[0].bytes, 4);
+    assert!(dir.join("SRR000001.fastq").exists());
+    assert!(!read_record(&record).is_expired());
+    assert!(audit::read(store.project_root(), None).unwrap().is_empty());
+}
+
+#[test]
+fn policy_values_are_validated() {
+    assert_eq!(parse_max_age("180d"), Some(Duration::days(180)));
+    assert_eq!(parse_max_age("2w"), Some(Duration::weeks(2)));
+    assert_eq!(parse_max_age("36h"), Some(Duration::hours(36)));
+    assert_eq!(parse_max_age("6 months"), None);
+    assert_eq!(parse_max_age("d"), None);
+
+    let config = |key: &str, value: &str| RetentionConfig {
+        max_age: BTreeMap::from([(key.to_string(), value.to_string())]),
+    };
+    assert!(RetentionPolicy::from_config(&config("srr", "forever")).is_err());
+    assert!(RetentionPolicy::from_config(&config("fastq", "10d")).is_err());
+    let kb = RetentionPolicy::from_config(&config("kb", "90d")).unwrap();
+    assert_eq!(
+        kb.limit_for("reactome"),
+        Some(("kb=90d".to_string(), Duration::days(90)))
+    );
+    assert_eq!(kb.limit_for("srr"), None);
+}
@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use kira_biodata_manager::app::ClearResult;
+use kira_biodata_manager::schema::{SchemaCommand, Versioned};
+
+fn snapshot(command: SchemaCommand) -> PathBuf {
+    let name = command.to_possible_value().unwrap().get_name().to_string();
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/schema")
+        .join(format!("{name}.json"))
+}
+
+/// Regenerate with `KIRA_BM_UPDATE_SCHEMAS=1 cargo test --test schema`.
+#[test]
+fn output_schemas_match_their_snapshots() {
+    let update = std::env::var_os("KIRA_BM_UPDATE_SCHEMAS").is_some();
+    for command in SchemaCommand::value_variants() {
+        let path = snapshot(*command);
+        let schema = serde_json::to_string_pretty(&command.schema()).unwrap() + "\n";
+        if update {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &schema).unwrap();
+            continue;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+        assert_eq!(
+            schema,
+            expected,
+            "the {} output changed: bump its OUTPUT_VERSION if a field was removed, renamed or retyped, then regenerate the snapshots",
+            command.command()
+        );
+    }
+}
+
+#[test]
+fn every_schema_requires_its_output_version() {
+    for command in SchemaCommand::value_variants() {
+        let schema = command.schema();
+        assert_eq!(schema["properties"]["output_version"]["const"], 1);
+        assert!(
+            schema["required"]
+                .as_array()
+                .unwrap()
+                .contains(&"output_version".into())
+        );
+    }
+}
+
+#[test]
+fn versioned_results_print_their_fields_beside_the_version() {
+    let result = ClearResult {
+        cleared: true,
+        dry_run: false,
+        datasets: 0,
+        bytes: 0,
+        types: Vec::new(),
+        skipped_pinned: Vec::new(),
+    };
+    let value = serde_json::to_value(Versioned::new(&result)).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({"output_version": 1, "cleared": true, "datasets": 0, "bytes": 0})
+    );
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use kira_biodata_manager::search::{SearchField, collect_documents, search};
+use kira_biodata_manager::store::{Materialization, Metadata, Store};
+
+fn seed(store_root: &Utf8Path, dataset_type: &str, id: &str, dir: &Utf8Path) {
+    let meta = Metadata {
+        source: "test".to_string(),
+        dataset_type: dataset_type.to_string(),
+        id: id.to_string(),
+        format: None,
+        downloaded_at: "2024-01-01T00:00:00Z".to_string(),
+        tool: "kira-bm".to_string(),
+        resolved_path: dir.to_string(),
+        status: None,
+        expiry: None,
+        provenance: None,
+        materialization: Materialization::Full,
+        assembly_name: None,
+        download: None,
+        converted_formats: Vec::new(),
+        formats: BTreeMap::new(),
+        alias: None,
+        pinned: false,
+        imported_from: None,
+        experiment: None,
+        cache: None,
+        acquisition: None,
+    };
+    Store::write_metadata(
+        &store_root
+            .join("metadata")
+            .join(dataset_type)
+            .join(format!("{id}.json")),
+        &meta,
+    )
+    .unwrap();
+}
+
+fn write(path: &Utf8Path, content: &str) {
+    std::fs::create_dir_all(path.parent().unwrap().as_std_path()).unwrap();
+    std::fs::write(path.as_std_path(), content).unwrap();
+}
+
+fn seeded_store(root: &Utf8Path) {
+    let protein = root.join("proteins/2HHB");
+    write(
+        &protein.join("metadata.json"),
+        r#"{"title": "THE CRYSTAL STRUCTURE OF HUMAN DEOXYHAEMOGLOBIN"}"#,
+    );
+    write(&protein.join("2HHB.cif"), "data");
+    seed(root, "protein", "2HHB", &protein.join("2HHB.cif"));
+
+    let uniprot = root.join("uniprot/P69905");
+    write(
+        &uniprot.join("metadata.json"),
+        r#"{"protein_name": "Hemoglobin subunit alpha", "gene_names": ["HBA1"], "organism": "Homo sapiens"}"#,
+    );
+    seed(root, "uniprot", "P69905", &uniprot);
+
+    let expression = root.join("expression/GSE100001");
+    write(
+        &expression.join("metadata/geo_soft.txt"),
+        "!Series_title = Cerebellum granule cell atlas\n!Series_organism = Mus musculus\n",
+    );
+    write(&expression.join("GSE100001_hemoglobin_counts.tsv.gz"), "x");
+    seed(root, "expression", "GSE100001", &expression);
+}
+
+#[test]
+fn find_ranks_id_above_title_above_file_name() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    seeded_store(&root);
+
+    let docs = collect_documents(Store::list_metadata(&root).unwrap(), Vec::new());
+    let matches = search(&docs, "hemoglobin");
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0].id, "P69905");
+    assert_eq!(matches[0].matched_field, SearchField::Title);
+    assert_eq!(matches[0].matched_text, "Hemoglobin subunit alpha");
+    assert_eq!(matches[1].id, "GSE100001");
+    assert_eq!(matches[1].matched_field, SearchField::FileName);
+
+    let matches = search(&docs, "2hhb");
+    assert_eq!(matches[0].id, "2HHB");
+    assert_eq!(matches[0].matched_field, SearchField::Id);
+    assert_eq!(matches[0].score, 100);
+
+    let matches = search(&docs, "CEREBELLUM");
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].matched_field, SearchField::Title);
+
+    let matches = search(&docs, "musculus");
+    assert_eq!(matches[0].matched_field, SearchField::Organism);
+    assert_eq!(matches[0].matched_text, "Mus musculus");
+}
+
+#[test]
+fn find_scans_a_thousand_datasets_quickly() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    for idx in 0..1000 {
+        let id = format!("P{idx:05}");
+        let dir = root.join("uniprot").join(&id);
+        write(
+            &dir.join("metadata.json"),
+            &format!(r#"{{"protein_name": "Protein {idx}", "organism": "Homo sapiens"}}"#),
+        );
+        seed(&root, "uniprot", &id, &dir);
+    }
+
+    let start = Instant::now();
+    let docs = collect_documents(Store::list_metadata(&root).unwrap(), Vec::new());
+    let matches = search(&docs, "P00999");
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert_eq!(matches[0].id, "P00999");
+}
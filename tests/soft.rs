@@ -0,0 +1,118 @@
+use std::path::Path;
+
+use kira_biodata_manager::geo::{
+    extract_organism, extract_sample_series, extract_sample_title, extract_series_title,
+    extract_supplementary_urls,
+};
+use kira_biodata_manager::soft::{SoftRecord, SoftSample};
+
+fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/geo")
+        .join(name);
+    std::fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn parses_summary_paragraphs_platforms_and_samples() {
+    let record = SoftRecord::parse(&fixture("GSE200030_family.soft"));
+    assert_eq!(record.series_accession.as_deref(), Some("GSE200030"));
+    assert_eq!(
+        record.series_title.as_deref(),
+        Some("Transcriptomes of human and mouse liver across two sequencing platforms")
+    );
+    assert_eq!(
+        record.summary.as_deref(),
+        Some(
+            "Hepatocytes were profiled by bulk RNA-seq in both species.\n\
+             Libraries were split across two instruments to compare platform effects."
+        )
+    );
+    assert_eq!(record.platforms, ["GPL24676", "GPL21103"]);
+    assert_eq!(record.organism.as_deref(), Some("Homo sapiens"));
+    // The family file describes three of the four samples.
+    assert_eq!(record.sample_count(), 4);
+    assert_eq!(record.samples.len(), 3);
+    assert_eq!(
+        record.samples[2],
+        SoftSample {
+            accession: "GSM6000003".to_string(),
+            title: Some("liver_mouse_rep1".to_string()),
+            organism: Some("Mus musculus".to_string()),
+            platform: Some("GPL21103".to_string()),
+            series: vec!["GSE200030".parse().unwrap(), "GSE200031".parse().unwrap()],
+        }
+    );
+    // A BioProject relation is not a series, and NONE is not a file.
+    assert!(record.relations.is_empty());
+    assert_eq!(
+        record.supplementary_urls,
+        ["ftp://ftp.ncbi.nlm.nih.gov/geo/series/GSE200nnn/GSE200030/suppl/GSE200030_counts.tsv.gz"]
+    );
+}
+
+#[test]
+fn parses_superseries_with_sample_titles() {
+    let record = SoftRecord::parse(&fixture("GSE200000_family.soft"));
+    assert_eq!(record.relations.len(), 2);
+    assert_eq!(record.relations[1].accession, "GSE200002");
+    assert_eq!(record.summary, None);
+    assert!(record.platforms.is_empty());
+    assert_eq!(
+        record
+            .samples
+            .iter()
+            .map(|sample| (sample.accession.as_str(), sample.title.as_deref()))
+            .collect::<Vec<_>>(),
+        [
+            ("GSM1000001", Some("cerebellum_rep1")),
+            ("GSM1000002", Some("cerebellum_rep2")),
+            ("GSM1000010", Some("cortex_rep1")),
+        ]
+    );
+    assert_eq!(record.supplementary_urls.len(), 4);
+}
+
+#[test]
+fn organism_falls_back_to_the_first_sample() {
+    let record = SoftRecord::parse(&fixture("GSE200020_family.soft"));
+    assert_eq!(record.organism.as_deref(), Some("Homo sapiens"));
+    assert_eq!(record.samples[1].organism.as_deref(), Some("Mus musculus"));
+    assert_eq!(
+        record.series_title,
+        extract_series_title(&fixture("GSE200020_family.soft"))
+    );
+}
+
+#[test]
+fn a_sample_soft_without_its_header_is_one_sample() {
+    let text = fixture("GSM5000001.soft");
+    let headerless = text.lines().skip(1).collect::<Vec<_>>().join("\n");
+    for text in [&text, &headerless] {
+        let record = SoftRecord::parse(text);
+        assert_eq!(record.series_title, None);
+        assert_eq!(record.samples.len(), 1);
+        assert_eq!(record.samples[0].accession, "GSM5000001");
+        assert_eq!(record.samples[0].title.as_deref(), Some("PBMC_donor1_10x"));
+        assert_eq!(record.supplementary_urls.len(), 4);
+    }
+}
+
+#[test]
+fn extract_helpers_read_the_parsed_record() {
+    let sample = fixture("GSM5000001.soft");
+    assert_eq!(
+        extract_sample_title(&sample).as_deref(),
+        Some("PBMC_donor1_10x")
+    );
+    assert_eq!(extract_organism(&sample).as_deref(), Some("Homo sapiens"));
+    assert_eq!(
+        extract_sample_series(&sample),
+        ["GSE200010".parse().unwrap()]
+    );
+    let series = fixture("GSE200030_family.soft");
+    assert_eq!(
+        extract_supplementary_urls(&series),
+        SoftRecord::parse(&series).supplementary_urls
+    );
+}
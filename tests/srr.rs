@@ -0,0 +1,130 @@
+#![cfg(unix)]
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use assert_matches::assert_matches;
+
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::srr::{SrrClient, SrrDownloadOptions, SystemSrrClient, ToolCheckStatus};
+
+fn script(dir: &Path, name: &str, body: &str) -> PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, format!("#!/bin/sh\n{body}")).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+/// prefetch leaves a small archive; fasterq-dump records its arguments and
+/// writes one read file into `--outdir`.
+fn fake_tools(dir: &Path) -> (PathBuf, PathBuf) {
+    let prefetch = script(
+        dir,
+        "prefetch",
+        "mkdir -p \"$3/$1\"\necho archive > \"$3/$1/$1.sra\"\n",
+    );
+    let fasterq = script(
+        dir,
+        "fasterq-dump",
+        &format!(
+            "echo \"$@\" > {args}\n\
+             printf '@r1\\nACGT\\n+\\nIIII\\n' > \"$3/SRR000001.fastq\"\n",
+            args = dir.join("fasterq.args").display()
+        ),
+    );
+    (prefetch, fasterq)
+}
+
+#[test]
+fn fasterq_dump_runs_with_the_requested_threads_and_temp_dir() {
+    let temp = tempfile::tempdir().unwrap();
+    let (prefetch, fasterq) = fake_tools(temp.path());
+    let client = SystemSrrClient::with_tools(None, Some(prefetch), Some(fasterq));
+    let scratch = temp.path().join("scratch");
+    let options = SrrDownloadOptions {
+        threads: Some(4),
+        temp_dir: Some(scratch.clone()),
+    };
+
+    let files = client
+        .download_fastq(
+            &"SRR000001".parse().unwrap(),
+            false,
+            &temp.path().join("staging"),
+            &options,
+        )
+        .unwrap();
+
+    assert_eq!(files.len(), 1);
+    let args = std::fs::read_to_string(temp.path().join("fasterq.args")).unwrap();
+    assert!(args.contains("--threads 4"), "{args}");
+    assert!(
+        args.contains(&format!("--temp {}", scratch.display())),
+        "{args}"
+    );
+    assert!(scratch.is_dir());
+}
+
+#[test]
+fn conversion_stops_early_when_scratch_space_is_short() {
+    let temp = tempfile::tempdir().unwrap();
+    let (prefetch, fasterq) = fake_tools(temp.path());
+    let vdb_dump = script(
+        temp.path(),
+        "vdb-dump",
+        "echo 'acc  : SRR000001'\necho 'size : 1,000,000,000,000,000'\n",
+    );
+    let client = SystemSrrClient::with_tools(None, Some(prefetch), Some(fasterq))
+        .with_vdb_dump(Some(vdb_dump));
+
+    let err = client
+        .download_fastq(
+            &"SRR000001".parse().unwrap(),
+            false,
+            &temp.path().join("staging"),
+            &SrrDownloadOptions::default(),
+        )
+        .unwrap_err();
+
+    assert_matches!(
+        err,
+        KiraError::InsufficientScratchSpace { required, .. } if required == 10_000_000_000_000_000
+    );
+    assert!(err.to_string().contains("--srr-temp-dir"));
+    assert!(!temp.path().join("fasterq.args").exists());
+}
+
+#[test]
+fn tools_check_runs_each_tool_for_its_version() {
+    let temp = tempfile::tempdir().unwrap();
+    let prefetch = script(
+        temp.path(),
+        "prefetch",
+        "printf '\\nprefetch : 3.0.10\\n\\n'\n",
+    );
+    let fasterq = script(
+        temp.path(),
+        "fasterq-dump",
+        "printf '\\nfasterq-dump : 2.9.6\\n\\n'\n",
+    );
+    let client = SystemSrrClient::with_tools(None, Some(prefetch), Some(fasterq));
+
+    let report = client.check_tools();
+    let found = report
+        .tools
+        .iter()
+        .map(|check| (check.tool.as_str(), check.version.as_deref(), check.status))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        [
+            ("prefetch", Some("3.0.10"), ToolCheckStatus::Ok),
+            ("fasterq-dump", Some("2.9.6"), ToolCheckStatus::Outdated),
+            ("datasets", None, ToolCheckStatus::Missing),
+        ]
+    );
+    assert_eq!(
+        client.tool_info().sra_toolkit_version.as_deref(),
+        Some("2.9.6")
+    );
+}
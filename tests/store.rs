@@ -1,7 +1,17 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use camino::Utf8PathBuf;
+use kira_biodata_manager::cancel;
 use kira_biodata_manager::domain::{
-    Doi, GenomeAccession, GeoSeriesAccession, ProteinFormat, ProteinId, SrrId, UniprotId,
+    DatasetSpecifier, Doi, GenomeAccession, GeoSampleAccession, GeoSeriesAccession, ProteinFormat,
+    ProteinId, SrrId, UniprotId,
+};
+use kira_biodata_manager::fs_util::{Digests, sha256_file};
+use kira_biodata_manager::output::JsonOutput;
+use kira_biodata_manager::store::{
+    DownloadRecord, Materialization, Metadata, Store, atomic_rename_dir,
 };
-use kira_biodata_manager::store::Store;
 
 #[test]
 fn layout_paths() {
@@ -36,6 +46,431 @@ fn layout_paths() {
     let expr10x_path = store.project_expression10x_dir(&gse);
     assert!(expr10x_path.ends_with("expression10x/GSE102902"));
 
+    let gsm: GeoSampleAccession = "GSM3577221".parse().unwrap();
+    let sample_path = store.project_expression_sample_dir(&gsm, Some(&gse));
+    assert!(sample_path.ends_with("expression/GSE102902/GSM3577221"));
+    let orphan_path = store.cache_expression_sample_dir(&gsm, None);
+    assert!(orphan_path.ends_with("expression_samples/GSM3577221"));
+
     let go_cache = store.cache_kb_dir("go");
     assert!(go_cache.to_string().contains("metadata/go"));
 }
+
+fn metadata(dataset_type: &str, id: &str, path: &Utf8PathBuf) -> Metadata {
+    Metadata {
+        source: "geo".to_string(),
+        dataset_type: dataset_type.to_string(),
+        id: id.to_string(),
+        format: None,
+        downloaded_at: "2024-01-01T00:00:00Z".to_string(),
+        tool: "kira-bm/test".to_string(),
+        resolved_path: path.to_string(),
+        status: None,
+        expiry: None,
+        provenance: None,
+        materialization: Materialization::Full,
+        assembly_name: None,
+        download: None,
+        converted_formats: Vec::new(),
+        formats: BTreeMap::new(),
+        alias: None,
+        pinned: false,
+        imported_from: None,
+        experiment: None,
+        cache: None,
+        acquisition: None,
+    }
+}
+
+#[test]
+fn remove_project_dataset_drops_nested_entries() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    let store = Store::new_with_paths(root.join("project"), root.join("cache"));
+    let gse: GeoSeriesAccession = "GSE102902".parse().unwrap();
+    let gsm: GeoSampleAccession = "GSM3577221".parse().unwrap();
+    let series_dir = store.project_expression_dir(&gse);
+    let sample_dir = store.project_expression_sample_dir(&gsm, Some(&gse));
+    std::fs::create_dir_all(sample_dir.as_std_path()).unwrap();
+    std::fs::write(series_dir.join("counts.tsv").as_std_path(), b"x").unwrap();
+
+    let series = metadata("expression", "GSE102902", &series_dir);
+    Store::write_metadata(
+        &store.project_metadata_path("expression", "GSE102902"),
+        &series,
+    )
+    .unwrap();
+    Store::write_metadata(
+        &store.project_metadata_path("expressionsample", "GSM3577221"),
+        &metadata("expressionsample", "GSM3577221", &sample_dir),
+    )
+    .unwrap();
+
+    let removed = store.remove_project_dataset(&series, false).unwrap();
+    assert_eq!(removed.len(), 2);
+    assert!(!series_dir.as_std_path().exists());
+    assert!(
+        Store::list_metadata(store.project_root())
+            .unwrap()
+            .is_empty()
+    );
+
+    let outside = metadata("expression", "GSE1", &root.join("elsewhere"));
+    assert!(store.remove_project_dataset(&outside, false).is_err());
+}
+
+#[test]
+fn dedup_links_identical_cache_files_to_one_blob() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    let store = Store::new_with_paths(root.join("project"), root.join("cache"));
+    let content = b"ID   P69905\nSQ   SEQUENCE\n".repeat(64);
+    let cached = |id: &str| {
+        let id: UniprotId = id.parse().unwrap();
+        let dir = store.cache_uniprot_dir(&id);
+        std::fs::create_dir_all(dir.as_std_path()).unwrap();
+        std::fs::write(dir.join("entry.txt").as_std_path(), &content).unwrap();
+        let mut meta = metadata("uniprot", id.as_str(), &dir);
+        meta.download =
+            Some(DownloadRecord::of_dir(dir.as_std_path(), Vec::new(), &Digests::new()).unwrap());
+        store
+            .write_cache_metadata(&store.cache_metadata_path("uniprot", id.as_str()), &meta)
+            .unwrap();
+        dir
+    };
+    let first = cached("P69905");
+    let second = cached("P68871");
+    assert!(!store.uses_blobs());
+
+    let report = store
+        .dedup_cache(Duration::from_secs(5), &JsonOutput)
+        .unwrap();
+    assert!(store.uses_blobs());
+    assert_eq!((report.datasets, report.files, report.blobs), (2, 2, 1));
+    assert_eq!(report.linked + report.copied, 2);
+    if report.copied == 0 {
+        assert_eq!(report.bytes_reclaimed, content.len() as u64);
+    }
+    assert_eq!(
+        std::fs::read(first.join("entry.txt").as_std_path()).unwrap(),
+        content
+    );
+    assert!(store.unreferenced_blobs().is_empty());
+
+    // Once the store exists, new cache records go straight into it.
+    let third = cached("P01308");
+    let again = store
+        .dedup_cache(Duration::from_secs(5), &JsonOutput)
+        .unwrap();
+    assert_eq!((again.files, again.bytes_reclaimed, again.blobs), (3, 0, 1));
+
+    std::fs::remove_dir_all(first.as_std_path()).unwrap();
+    std::fs::remove_dir_all(second.as_std_path()).unwrap();
+    assert!(store.unreferenced_blobs().is_empty());
+    std::fs::remove_dir_all(third.as_std_path()).unwrap();
+    if report.copied == 0 {
+        assert_eq!(store.unreferenced_blobs().len(), 1);
+    }
+}
+
+#[test]
+fn renaming_over_a_directory_keeps_it_until_the_new_one_is_in_place() {
+    let temp = tempfile::tempdir().unwrap();
+    let stored = temp.path().join("genomes/GCF_000005845.2");
+    std::fs::create_dir_all(&stored).unwrap();
+    std::fs::write(stored.join("genomic.fna"), ">old\n").unwrap();
+
+    let missing = temp.path().join("never-staged");
+    assert!(atomic_rename_dir(&missing, &stored).is_err());
+    assert_eq!(
+        std::fs::read(stored.join("genomic.fna")).unwrap(),
+        b">old\n"
+    );
+
+    let staged = temp.path().join("staged");
+    std::fs::create_dir(&staged).unwrap();
+    std::fs::write(staged.join("genomic.fna"), ">new\n").unwrap();
+    atomic_rename_dir(&staged, &stored).unwrap();
+    assert_eq!(
+        std::fs::read(stored.join("genomic.fna")).unwrap(),
+        b">new\n"
+    );
+    let left = std::fs::read_dir(temp.path().join("genomes"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect::<Vec<_>>();
+    assert_eq!(left, ["GCF_000005845.2"]);
+}
+
+fn touch(path: &Utf8PathBuf, file: bool) {
+    if file {
+        std::fs::create_dir_all(path.parent().unwrap().as_std_path()).unwrap();
+        std::fs::write(path.as_std_path(), b"x").unwrap();
+    } else {
+        std::fs::create_dir_all(path.as_std_path()).unwrap();
+    }
+}
+
+#[test]
+fn every_dataset_type_resolves_to_its_place_in_either_store() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    let store = Store::new_with_paths(root.join("project"), root.join("cache"));
+    let doi: Doi = "10.1038/s41586-020-2649-2".parse().unwrap();
+    let doi_dir = store
+        .project_doi_dir(&doi)
+        .strip_prefix(store.project_root())
+        .unwrap()
+        .to_string();
+    let cases: Vec<(&str, Option<ProteinFormat>, String)> = vec![
+        (
+            "protein:1LYZ",
+            Some(ProteinFormat::Cif),
+            "proteins/1LYZ/1LYZ.cif".into(),
+        ),
+        (
+            "protein:1LYZ",
+            Some(ProteinFormat::Pdb),
+            "proteins/1LYZ/1LYZ.pdb".into(),
+        ),
+        (
+            "protein:1LYZ",
+            Some(ProteinFormat::Bcif),
+            "proteins/1LYZ/1LYZ.bcif".into(),
+        ),
+        (
+            "genome:GCF_000005845.2",
+            None,
+            "genomes/GCF_000005845.2".into(),
+        ),
+        (
+            "genome:ensembl:homo_sapiens@110",
+            None,
+            "genomes/ensembl/homo_sapiens/110".into(),
+        ),
+        ("srr:SRR014966", None, "srr/SRR014966".into()),
+        ("uniprot:P69905", None, "uniprot/P69905".into()),
+        (
+            "uniprot-proteome:UP000005640",
+            None,
+            "proteomes/UP000005640".into(),
+        ),
+        ("doi:10.1038/s41586-020-2649-2", None, doi_dir),
+        ("expression:GSE102902", None, "expression/GSE102902".into()),
+        (
+            "expression10x:GSE102902",
+            None,
+            "expression10x/GSE102902".into(),
+        ),
+        (
+            "expressionsample:GSM3577221",
+            None,
+            "expression_samples/GSM3577221".into(),
+        ),
+        (
+            "bioproject:PRJNA257197",
+            None,
+            "bioprojects/PRJNA257197".into(),
+        ),
+        ("go", None, "metadata/go".into()),
+        ("kegg", None, "metadata/kegg".into()),
+        ("kegg:hsa", None, "metadata/kegg/hsa".into()),
+        ("reactome", None, "metadata/reactome".into()),
+    ];
+    for (specifier, format, relative) in cases {
+        let parsed: DatasetSpecifier = specifier.parse().unwrap();
+        for cache in [false, true] {
+            let resolve = || {
+                if cache {
+                    store.resolve_cache_dataset_path(&parsed, format)
+                } else {
+                    store.resolve_dataset_path(&parsed, format)
+                }
+            };
+            let expected = if cache {
+                store.cache_root().join(&relative)
+            } else {
+                store.project_root().join(&relative)
+            };
+            assert_eq!(resolve(), None, "{specifier} before it is stored");
+            touch(&expected, format.is_some());
+            assert_eq!(resolve(), Some(expected), "{specifier} {format:?}");
+        }
+    }
+}
+
+#[test]
+fn dataset_paths_follow_what_the_records_say() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    let store = Store::new_with_paths(root.join("project"), root.join("cache"));
+    let project = store.project_root();
+    let resolve = |specifier: &str, format: Option<ProteinFormat>| {
+        store.resolve_dataset_path(&specifier.parse().unwrap(), format)
+    };
+
+    // A protein without a format is the file fetched last, or any there is.
+    let id: ProteinId = "1LYZ".parse().unwrap();
+    let pdb = store.project_protein_path(&id, ProteinFormat::Pdb);
+    touch(&pdb, true);
+    assert_eq!(resolve("protein:1LYZ", None), Some(pdb.clone()));
+    assert_eq!(resolve("protein:1LYZ", Some(ProteinFormat::Cif)), None);
+    let bcif = store.project_protein_path(&id, ProteinFormat::Bcif);
+    touch(&bcif, true);
+    let mut record = metadata("protein", "1LYZ", &bcif);
+    record.format = Some("bcif".to_string());
+    record.alias = Some("lysozyme".to_string());
+    Store::write_metadata(&store.project_metadata_path("protein", "1LYZ"), &record).unwrap();
+    assert_eq!(resolve("protein:1LYZ", None), Some(bcif.clone()));
+    assert_eq!(resolve("alias:lysozyme", None), Some(bcif));
+    assert_eq!(
+        resolve("alias:lysozyme", Some(ProteinFormat::Pdb)),
+        Some(pdb)
+    );
+    assert_eq!(resolve("alias:unknown", None), None);
+
+    // Ensembl without a release is the newest one stored.
+    for release in [109, 111] {
+        let id = format!("ensembl:homo_sapiens@{release}");
+        let dir = store.project_ensembl_dir("homo_sapiens", release);
+        touch(&dir, false);
+        Store::write_metadata(
+            &store.project_metadata_path("genome", &id),
+            &metadata("genome", &id, &dir),
+        )
+        .unwrap();
+    }
+    assert_eq!(
+        resolve("genome:ensembl:homo_sapiens", None),
+        Some(project.join("genomes/ensembl/homo_sapiens/111"))
+    );
+    assert_eq!(resolve("genome:ensembl:mus_musculus", None), None);
+
+    // An assembly name is looked up among the stored genomes.
+    let acc: GenomeAccession = "GCA_000001405.29".parse().unwrap();
+    let dir = store.project_genome_dir(&acc);
+    touch(&dir, false);
+    let mut record = metadata("genome", acc.as_str(), &dir);
+    record.assembly_name = Some("MyAssembly1".to_string());
+    Store::write_metadata(
+        &store.project_metadata_path("genome", acc.as_str()),
+        &record,
+    )
+    .unwrap();
+    assert_eq!(resolve("genome:myassembly1", None), Some(dir));
+    assert_eq!(resolve("genome:taxon:9606", None), None);
+
+    // A sample stored with its series, and a covering structure's record.
+    let gse: GeoSeriesAccession = "GSE102902".parse().unwrap();
+    let gsm: GeoSampleAccession = "GSM3577221".parse().unwrap();
+    let sample = store.project_expression_sample_dir(&gsm, Some(&gse));
+    touch(&sample, false);
+    assert_eq!(resolve("expression:GSM3577221", None), Some(sample));
+    let uniprot = store.project_uniprot_dir(&"P00698".parse().unwrap());
+    touch(&uniprot, false);
+    assert_eq!(resolve("structure-for:P00698[1-30]", None), Some(uniprot));
+}
+
+fn staged_leftovers(dir: &Utf8PathBuf) -> Vec<String> {
+    std::fs::read_dir(dir.as_std_path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("kira-bm-") || name.contains(".kira-bm-copy."))
+        .collect()
+}
+
+#[test]
+fn a_copy_that_cannot_be_moved_into_place_names_both_paths_and_leaves_nothing() {
+    let temp = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    let source = root.join("source.cif");
+    std::fs::write(source.as_std_path(), b"new").unwrap();
+    // A file cannot replace a directory that has something in it.
+    let dest = root.join("store").join("1LYZ.cif");
+    std::fs::create_dir_all(dest.join("in-the-way").as_std_path()).unwrap();
+
+    // Nothing is going to move out of the way, so it fails without the
+    // retries' 750 ms of backoff.
+    let started = std::time::Instant::now();
+    let err = Store::copy_file_atomic(&source, &dest)
+        .unwrap_err()
+        .to_string();
+    assert!(started.elapsed() < Duration::from_millis(500));
+    assert!(err.contains("kira-bm-file-1LYZ.cif"), "{err}");
+    assert!(err.contains(dest.as_str()), "{err}");
+    assert!(staged_leftovers(&root.join("store")).is_empty());
+    assert!(dest.join("in-the-way").as_std_path().is_dir());
+}
+
+#[cfg(windows)]
+#[test]
+fn a_destination_held_open_for_a_moment_is_replaced_once_released() {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const FILE_SHARE_READ: u32 = 1;
+    let temp = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    let source = root.join("source.cif");
+    std::fs::write(source.as_std_path(), b"new").unwrap();
+    let dest = root.join("1LYZ.cif");
+    std::fs::write(dest.as_std_path(), b"old").unwrap();
+
+    // Opened without delete sharing, as an indexer would, the file cannot
+    // be replaced until the handle is closed.
+    let open = || {
+        std::fs::OpenOptions::new()
+            .read(true)
+            .share_mode(FILE_SHARE_READ)
+            .open(dest.as_std_path())
+            .unwrap()
+    };
+    let held = open();
+    let releaser = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(120));
+        drop(held);
+    });
+    Store::copy_file_atomic(&source, &dest).unwrap();
+    releaser.join().unwrap();
+    assert_eq!(std::fs::read(dest.as_std_path()).unwrap(), b"new");
+    assert!(staged_leftovers(&root).is_empty());
+
+    // Held for good, the copy fails with both paths and the old file kept.
+    std::fs::write(source.as_std_path(), b"newer").unwrap();
+    let held = open();
+    let err = Store::copy_file_atomic(&source, &dest)
+        .unwrap_err()
+        .to_string();
+    drop(held);
+    assert!(err.contains("kira-bm-file-1LYZ.cif"), "{err}");
+    assert!(err.contains(dest.as_str()), "{err}");
+    assert_eq!(std::fs::read(dest.as_std_path()).unwrap(), b"new");
+    assert!(staged_leftovers(&root).is_empty());
+}
+
+#[test]
+fn download_records_use_the_digests_taken_while_writing() {
+    let temp = tempfile::tempdir().unwrap();
+    let staging = temp.path().join("staging");
+    std::fs::create_dir_all(staging.join("reads")).unwrap();
+    let streamed = staging.join("reads/SRR014966_1.fastq");
+    let (_, digest) = cancel::copy_to_file(&mut &b"@r1\nACGT\n+\nIIII\n"[..], &streamed).unwrap();
+    assert_eq!(digest, sha256_file(&streamed).unwrap());
+    std::fs::write(staging.join("notes.txt"), b"written by a tool").unwrap();
+
+    let target = temp.path().join("dataset");
+    atomic_rename_dir(&staging, &target).unwrap();
+    // A record that read the file back would see these bytes instead.
+    std::fs::write(
+        target.join("reads/SRR014966_1.fastq"),
+        b"@r1\nTTTT\n+\nIIII\n",
+    )
+    .unwrap();
+
+    let digests = Digests::from([("reads/SRR014966_1.fastq".to_string(), digest.clone())]);
+    let record = DownloadRecord::of_dir(&target, Vec::new(), &digests).unwrap();
+    assert_eq!(record.sha256["reads/SRR014966_1.fastq"], digest);
+    assert_eq!(
+        record.sha256["notes.txt"],
+        sha256_file(&target.join("notes.txt")).unwrap()
+    );
+}
@@ -0,0 +1,303 @@
+mod support;
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use assert_matches::assert_matches;
+use camino::Utf8PathBuf;
+
+use kira_biodata_manager::app::{App, FetchOptions, FetchOverrides, FetchResult, ProgressSink};
+use kira_biodata_manager::domain::{DatasetSpecifier, ProteinFormat, ProteinId, UniprotId};
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::output::JsonOutput;
+use kira_biodata_manager::rcsb::{RcsbClient, RcsbMetadata};
+use kira_biodata_manager::store::Store;
+use kira_biodata_manager::uniprot::{UniprotClient, UniprotRecord, extract_metadata};
+
+use support::{UnusedGeo, UnusedKnowledge, UnusedNcbi, UnusedSrr};
+
+#[derive(Clone, Default)]
+struct FileRcsb {
+    downloads: Arc<Mutex<Vec<String>>>,
+}
+
+impl RcsbClient for FileRcsb {
+    fn download_structure(
+        &self,
+        id: &ProteinId,
+        _format: ProteinFormat,
+        destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<(), KiraError> {
+        self.downloads.lock().unwrap().push(id.to_string());
+        std::fs::write(destination, b"data_mock\nloop_\n_atom_site.id\n1\n")
+            .map_err(|err| KiraError::Filesystem(err.to_string()))
+    }
+
+    fn fetch_metadata(&self, id: &ProteinId) -> Result<RcsbMetadata, KiraError> {
+        Ok(RcsbMetadata {
+            registry: "rcsb".to_string(),
+            pdb_id: id.as_str().to_string(),
+            title: None,
+            experimental_method: None,
+            resolution: None,
+            deposition_date: None,
+            release_date: None,
+            source_structure_url: String::new(),
+            source_metadata_url: String::new(),
+            raw_json: serde_json::json!({}),
+        })
+    }
+}
+
+/// Serves the lysozyme fixture, minus the PDB entries in `without`.
+#[derive(Clone, Default)]
+struct FixtureUniprot {
+    without: Vec<&'static str>,
+    calls: Arc<Mutex<usize>>,
+}
+
+impl UniprotClient for FixtureUniprot {
+    fn fetch(&self, _id: &UniprotId) -> Result<UniprotRecord, KiraError> {
+        *self.calls.lock().unwrap() += 1;
+        let raw = std::fs::read_to_string("tests/fixtures/uniprot_P00698_structures.json")
+            .map_err(|err| KiraError::Filesystem(err.to_string()))?;
+        let mut raw_json: serde_json::Value = serde_json::from_str(&raw).unwrap();
+        raw_json["uniProtKBCrossReferences"]
+            .as_array_mut()
+            .unwrap()
+            .retain(|xref| !self.without.contains(&xref["id"].as_str().unwrap_or("")));
+        Ok(UniprotRecord {
+            metadata: extract_metadata(&raw_json)?,
+            raw_json,
+            fasta: ">sp|P00698|LYSC_CHICK\nKVFGRCELAAAMKRHGLDNYRGYSLGNWVCAAKFESNFNTQATNRNTDGSTDYGILQINSRWWCNDGRTP\n"
+                .to_string(),
+        })
+    }
+}
+
+type TestApp = App<UnusedNcbi, FileRcsb, UnusedSrr, FixtureUniprot, UnusedGeo, UnusedKnowledge>;
+
+fn app(temp: &tempfile::TempDir, rcsb: FileRcsb, uniprot: FixtureUniprot) -> (TestApp, Store) {
+    let root = Utf8PathBuf::from_path_buf(temp.path().to_path_buf()).unwrap();
+    let store = Store::new_with_paths(root.join("project"), root.join("cache"));
+    let app = App::new(
+        store.clone(),
+        UnusedNcbi,
+        rcsb,
+        UnusedSrr,
+        uniprot,
+        UnusedGeo,
+        UnusedKnowledge,
+    );
+    (app, store)
+}
+
+fn fetch(app: &TestApp, specifier: &str, top: Option<usize>) -> Result<FetchResult, KiraError> {
+    let mut overrides = FetchOverrides::builder();
+    if let Some(top) = top {
+        overrides = overrides.structure_top(top);
+    }
+    app.fetch(
+        Some(specifier.parse::<DatasetSpecifier>().unwrap()),
+        None,
+        overrides.build(),
+        FetchOptions::default(),
+        &JsonOutput,
+    )
+}
+
+fn selected(result: &FetchResult) -> Vec<String> {
+    result
+        .summary
+        .as_ref()
+        .unwrap()
+        .selection
+        .as_ref()
+        .unwrap()
+        .candidates
+        .iter()
+        .filter(|candidate| candidate.selected)
+        .map(|candidate| candidate.id.clone())
+        .collect()
+}
+
+#[test]
+fn full_coverage_picks_best_method_and_resolution() {
+    let temp = tempfile::tempdir().unwrap();
+    let rcsb = FileRcsb::default();
+    let uniprot = FixtureUniprot::default();
+    let (app, store) = app(&temp, rcsb.clone(), uniprot.clone());
+
+    let result = fetch(&app, "structure-for:P00698[20-140]", None).unwrap();
+
+    let items = result
+        .items
+        .iter()
+        .map(|item| format!("{}:{}", item.dataset_type, item.id))
+        .collect::<Vec<_>>();
+    assert_eq!(items, vec!["uniprot:P00698", "protein:4LZT"]);
+    assert_eq!(*rcsb.downloads.lock().unwrap(), vec!["4LZT"]);
+    let summary = result.summary.as_ref().unwrap();
+    assert_eq!(summary.kind, "structure-for");
+    let selection = summary.selection.as_ref().unwrap();
+    assert_eq!((selection.start, selection.end), (20, 140));
+    let ranked = selection
+        .candidates
+        .iter()
+        .map(|candidate| (candidate.id.as_str(), candidate.coverage))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        ranked,
+        vec![
+            ("4LZT", 1.0),
+            ("2LYZ", 1.0),
+            ("1E8L", 1.0),
+            ("9ZZZ", 1.0),
+            ("1LYZ", 0.669),
+            ("5ABC", 0.0),
+        ]
+    );
+
+    let metadata: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(
+            store
+                .project_protein_dir(&"4LZT".parse().unwrap())
+                .join("metadata.json"),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(metadata["pdb_id"], "4LZT");
+    assert_eq!(metadata["selection"]["accession"], "P00698");
+    assert_eq!(metadata["selection"]["candidates"][0]["selected"], true);
+    let info = app
+        .info("protein:4LZT".parse().unwrap(), &JsonOutput)
+        .unwrap();
+    assert_eq!(info.details.unwrap()["selection"]["end"], 140);
+
+    // The stored UniProt entry is reused for the next query.
+    let result = fetch(&app, "structure-for:P00698[20-140]", Some(2)).unwrap();
+    assert_eq!(selected(&result), vec!["4LZT", "2LYZ"]);
+    assert_eq!(*uniprot.calls.lock().unwrap(), 1);
+    assert_eq!(*rcsb.downloads.lock().unwrap(), vec!["4LZT", "2LYZ"]);
+}
+
+#[test]
+fn coverage_outranks_method_for_partial_structures() {
+    let temp = tempfile::tempdir().unwrap();
+    let (app, _store) = app(&temp, FileRcsb::default(), FixtureUniprot::default());
+
+    // Only the predicted model spans the signal peptide; the X-ray
+    // structures cover 129 of 147 residues and still pass the threshold.
+    let result = fetch(&app, "structure-for:P00698", Some(3)).unwrap();
+    assert_eq!(selected(&result), vec!["9ZZZ", "4LZT", "2LYZ"]);
+    let selection = result.summary.unwrap().selection.unwrap();
+    assert_eq!((selection.start, selection.end), (1, 147));
+    assert_eq!(selection.candidates[1].covered_residues, 129);
+
+    // 1LYZ covers too little of the range to be fetched even when asked for more.
+    let result = fetch(&app, "structure-for:P00698[19-147]", Some(10)).unwrap();
+    assert_eq!(selected(&result), vec!["4LZT", "2LYZ", "1E8L", "9ZZZ"]);
+}
+
+#[test]
+fn uncovered_range_reports_partial_options() {
+    let temp = tempfile::tempdir().unwrap();
+    let rcsb = FileRcsb::default();
+    let uniprot = FixtureUniprot {
+        without: vec!["9ZZZ"],
+        ..FixtureUniprot::default()
+    };
+    let (app, _store) = app(&temp, rcsb.clone(), uniprot);
+
+    let err = fetch(&app, "structure-for:P00698[1-30]", None).unwrap_err();
+    assert_matches!(err.root(), KiraError::NoCoveringStructure(_));
+    assert_eq!(
+        err.to_string(),
+        "structure-for:P00698[1-30] [Verify]: no PDB structure covers at least 80% of P00698[1-30]; best partial matches: \
+         4LZT 40% (X-ray, 0.95 Å), 1LYZ 40% (X-ray, 2.00 Å), 2LYZ 40% (X-ray, 2.00 Å)"
+    );
+    assert!(rcsb.downloads.lock().unwrap().is_empty());
+
+    let err = fetch(&app, "structure-for:P00698[100-200]", None).unwrap_err();
+    assert_matches!(err.root(), KiraError::InvalidSpecifier(_));
+}
+
+#[test]
+fn with_structures_fetches_the_best_linked_entries() {
+    let temp = tempfile::tempdir().unwrap();
+    let rcsb = FileRcsb::default();
+    let uniprot = FixtureUniprot::default();
+    let (app, store) = app(&temp, rcsb.clone(), uniprot.clone());
+    let run = || {
+        app.fetch(
+            Some("uniprot:P00698".parse::<DatasetSpecifier>().unwrap()),
+            None,
+            FetchOverrides::builder()
+                .with_structures(true)
+                .max_structures(2)
+                .build(),
+            FetchOptions::default(),
+            &JsonOutput,
+        )
+        .unwrap()
+    };
+
+    let result = run();
+    let summary = result.summary.as_ref().unwrap();
+    assert_eq!(summary.kind, "uniprot-structures");
+    assert_eq!(summary.uniprot.as_deref(), Some("P00698"));
+    let counts = summary.structures.as_ref().unwrap();
+    assert_eq!(
+        (
+            counts.listed,
+            counts.downloaded,
+            counts.cached,
+            counts.skipped
+        ),
+        (6, 2, 0, 4)
+    );
+    let downloaded = rcsb.downloads.lock().unwrap().clone();
+    assert_eq!(downloaded.len(), 2);
+    let items = result
+        .items
+        .iter()
+        .map(|item| format!("{}:{}", item.dataset_type, item.id))
+        .collect::<Vec<_>>();
+    assert_eq!(items[0], "uniprot:P00698");
+    assert_eq!(
+        items[1..],
+        downloaded
+            .iter()
+            .map(|id| format!("protein:{id}"))
+            .collect::<Vec<_>>()
+    );
+
+    let metadata: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(
+            store
+                .project_uniprot_dir(&"P00698".parse().unwrap())
+                .join("metadata.json"),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        metadata["linked_structures"],
+        serde_json::json!(downloaded.clone())
+    );
+
+    // A second run finds everything in the store.
+    let result = run();
+    let counts = result
+        .summary
+        .as_ref()
+        .unwrap()
+        .structures
+        .as_ref()
+        .unwrap();
+    assert_eq!((counts.downloaded, counts.cached), (0, 2));
+    assert_eq!(*rcsb.downloads.lock().unwrap(), downloaded);
+    assert_eq!(*uniprot.calls.lock().unwrap(), 1);
+}
@@ -0,0 +1,538 @@
+//! A sandboxed stand-in for the registries.
+//!
+//! [`MockRegistry`] is a local HTTP server answering canned responses by
+//! method and path. Registry clients built with `with_base_url` send it
+//! every request they would send to RCSB, NCBI, UniProt, GEO, Crossref or
+//! ENA; registry paths do not overlap, so one server answers for all of
+//! them. Requests no route matches get a 404, and every request is logged
+//! for the tests to inspect. A `Range: bytes=<n>-` request gets the rest of
+//! the body as a 206.
+//!
+//! The `Unused*` clients stand in for the registries an `App` needs but a
+//! test never reaches; each of their calls fails. [`NoProgress`] drops
+//! progress events and [`RecordingSink`] keeps them.
+
+// Each test binary that includes this module uses only part of it.
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+use kira_biodata_manager::app::{FetchItemResult, ProgressEvent, ProgressSink};
+use kira_biodata_manager::domain::{
+    GenomeAccession, GeoSampleAccession, GeoSeriesAccession, ProteinFormat, ProteinId, SrrId,
+    UniprotId,
+};
+use kira_biodata_manager::error::KiraError;
+use kira_biodata_manager::geo::GeoClient;
+use kira_biodata_manager::knowledge::{GoFlavor, KnowledgeClient};
+use kira_biodata_manager::ncbi::{DownloadInfo, NcbiClient};
+use kira_biodata_manager::rcsb::{RcsbClient, RcsbMetadata};
+use kira_biodata_manager::srr::{SrrClient, SrrDownloadOptions, ToolInfo};
+use kira_biodata_manager::uniprot::{UniprotClient, UniprotRecord};
+
+struct Route {
+    method: &'static str,
+    path: String,
+    /// A fragment the query has to contain, for endpoints told apart by
+    /// their parameters (E-utilities' `db`).
+    query: Option<String>,
+    status: u16,
+    content_type: &'static str,
+    /// Sent instead of the real body length, to cut a response short.
+    content_length: Option<usize>,
+    location: Option<String>,
+    /// Sent as `ETag`; a request naming it in `If-None-Match` gets a 304.
+    etag: Option<String>,
+    /// Bytes of the body the first response sends before the connection
+    /// drops.
+    cut_after: Option<usize>,
+    served: AtomicUsize,
+    body: Vec<u8>,
+}
+
+#[derive(Default)]
+pub struct MockRegistryBuilder {
+    routes: Vec<Route>,
+}
+
+impl MockRegistryBuilder {
+    pub fn get(self, path: &str, body: impl Into<Vec<u8>>) -> Self {
+        self.route("GET", path, None, 200, body.into())
+    }
+
+    /// A GET answered only when its query contains `query`.
+    pub fn get_matching(self, path: &str, query: &str, body: impl Into<Vec<u8>>) -> Self {
+        self.route("GET", path, Some(query), 200, body.into())
+    }
+
+    pub fn post(self, path: &str, body: impl Into<Vec<u8>>) -> Self {
+        self.route("POST", path, None, 200, body.into())
+    }
+
+    /// A GET answered with a `302` to `location`.
+    pub fn redirect(mut self, path: &str, location: &str) -> Self {
+        self = self.route("GET", path, None, 302, Vec::new());
+        if let Some(route) = self.routes.last_mut() {
+            route.location = Some(location.to_string());
+        }
+        self
+    }
+
+    /// Makes the route added last announce `length` bytes, whatever its
+    /// body holds.
+    pub fn content_length(mut self, length: usize) -> Self {
+        if let Some(route) = self.routes.last_mut() {
+            route.content_length = Some(length);
+        }
+        self
+    }
+
+    /// Tags the route added last with `etag`.
+    pub fn etag(mut self, etag: &str) -> Self {
+        if let Some(route) = self.routes.last_mut() {
+            route.etag = Some(etag.to_string());
+        }
+        self
+    }
+
+    /// Answers the route added last with `status`.
+    pub fn status(mut self, status: u16) -> Self {
+        if let Some(route) = self.routes.last_mut() {
+            route.status = status;
+        }
+        self
+    }
+
+    /// Drops the connection of the first response to the route added last
+    /// after `bytes` bytes of its body; later requests get all of it.
+    pub fn cut_after(mut self, bytes: usize) -> Self {
+        if let Some(route) = self.routes.last_mut() {
+            route.cut_after = Some(bytes);
+        }
+        self
+    }
+
+    /// Sets the `Content-Type` of the route added last.
+    pub fn content_type(mut self, content_type: &'static str) -> Self {
+        if let Some(route) = self.routes.last_mut() {
+            route.content_type = content_type;
+        }
+        self
+    }
+
+    fn route(
+        mut self,
+        method: &'static str,
+        path: &str,
+        query: Option<&str>,
+        status: u16,
+        body: Vec<u8>,
+    ) -> Self {
+        self.routes.push(Route {
+            method,
+            path: path.to_string(),
+            query: query.map(str::to_string),
+            status,
+            content_type: "application/octet-stream",
+            content_length: None,
+            location: None,
+            etag: None,
+            cut_after: None,
+            served: AtomicUsize::new(0),
+            body,
+        });
+        self
+    }
+
+    pub fn start(self) -> MockRegistry {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let routes = Arc::new(self.routes);
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let routes = routes.clone();
+                let log = log.clone();
+                std::thread::spawn(move || serve(stream, &routes, &log));
+            }
+        });
+        MockRegistry { url, requests }
+    }
+}
+
+pub struct MockRegistry {
+    url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockRegistry {
+    pub fn builder() -> MockRegistryBuilder {
+        MockRegistryBuilder::default()
+    }
+
+    /// `http://127.0.0.1:<port>`, the base URL to build clients with.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Requests served so far, as `METHOD /path?query`.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// How many requests started with `prefix`, e.g. `GET /download/`.
+    pub fn count(&self, prefix: &str) -> usize {
+        self.requests()
+            .iter()
+            .filter(|request| request.starts_with(prefix))
+            .count()
+    }
+}
+
+fn serve(stream: TcpStream, routes: &[Route], log: &Mutex<Vec<String>>) {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut content_length = 0usize;
+    let mut if_none_match = None;
+    let mut range_start = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).is_err() || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            } else if name.eq_ignore_ascii_case("if-none-match") {
+                if_none_match = Some(value.trim().to_string());
+            } else if name.eq_ignore_ascii_case("range") {
+                range_start = value
+                    .trim()
+                    .strip_prefix("bytes=")
+                    .and_then(|range| range.strip_suffix('-'))
+                    .and_then(|start| start.parse::<usize>().ok());
+            }
+        }
+    }
+    let mut body = vec![0u8; content_length];
+    let _ = reader.read_exact(&mut body);
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+    log.lock().unwrap().push(format!("{method} {target}"));
+    let (path, query) = target.split_once('?').unwrap_or((target.as_str(), ""));
+    // A HEAD is answered like a GET, without the body.
+    let lookup = if method == "HEAD" { "GET" } else { &method };
+    let route = routes.iter().find(|route| {
+        route.method == lookup
+            && route.path == path
+            && route
+                .query
+                .as_deref()
+                .is_none_or(|fragment| query.contains(fragment))
+    });
+    let etag = route.and_then(|route| route.etag.as_deref());
+    let mut content_range = String::new();
+    let (status, content_type, body) = match route {
+        Some(route) if etag.is_some() && if_none_match.as_deref() == etag => {
+            (304, route.content_type, [].as_slice())
+        }
+        Some(route) if route.status == 200 && range_start.is_some() => {
+            let start = range_start.unwrap_or_default();
+            let total = route.body.len();
+            if start < total {
+                content_range = format!("Content-Range: bytes {start}-{}/{total}\r\n", total - 1);
+                (206, route.content_type, &route.body[start..])
+            } else {
+                content_range = format!("Content-Range: bytes */{total}\r\n");
+                (416, route.content_type, [].as_slice())
+            }
+        }
+        Some(route) => (route.status, route.content_type, route.body.as_slice()),
+        None => (404, "text/plain", b"not found".as_slice()),
+    };
+    let length = route
+        .and_then(|route| route.content_length)
+        .unwrap_or(body.len());
+    let first = route.is_some_and(|route| route.served.fetch_add(1, Ordering::SeqCst) == 0);
+    let body = match route.and_then(|route| route.cut_after) {
+        Some(cut) if first => &body[..cut.min(body.len())],
+        _ => body,
+    };
+    let location = route
+        .and_then(|route| route.location.as_deref())
+        .map(|location| format!("Location: {location}\r\n"))
+        .unwrap_or_default();
+    let etag = etag
+        .map(|etag| format!("ETag: {etag}\r\n"))
+        .unwrap_or_default();
+    let reason = match status {
+        200 => "OK",
+        206 => "Partial Content",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        416 => "Range Not Satisfiable",
+        503 => "Service Unavailable",
+        _ => "Not Found",
+    };
+    let mut stream = reader.into_inner();
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: {content_type}\r\nContent-Length: {length}\r\n{content_range}{location}{etag}Connection: close\r\n\r\n"
+    );
+    if method != "HEAD" {
+        let _ = stream.write_all(body);
+    }
+}
+
+/// A file under `tests/fixtures`.
+pub fn fixture(path: &str) -> Vec<u8> {
+    std::fs::read(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(path),
+    )
+    .unwrap_or_else(|err| panic!("fixture {path}: {err}"))
+}
+
+pub fn gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// An NCBI Datasets genome package: the catalog and a small FASTA for
+/// every file it lists.
+pub fn genome_package(catalog: &[u8]) -> Vec<u8> {
+    let parsed: serde_json::Value = serde_json::from_slice(catalog).unwrap();
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default();
+    zip.start_file("ncbi_dataset/data/dataset_catalog.json", options)
+        .unwrap();
+    zip.write_all(catalog).unwrap();
+    for assembly in parsed["assemblies"].as_array().unwrap() {
+        for file in assembly["files"].as_array().unwrap() {
+            let path = file["filePath"].as_str().unwrap();
+            zip.start_file(format!("ncbi_dataset/data/{path}"), options)
+                .unwrap();
+            zip.write_all(b">chr1\nACGT\n").unwrap();
+        }
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+/// Drops every progress event.
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn event(&self, _event: ProgressEvent) {}
+}
+
+/// Keeps every progress event and completed item, in the order they came.
+#[derive(Default)]
+pub struct RecordingSink {
+    events: Mutex<Vec<ProgressEvent>>,
+    items: Mutex<Vec<FetchItemResult>>,
+}
+
+impl ProgressSink for RecordingSink {
+    fn event(&self, event: ProgressEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+
+    fn item_completed(&self, item: &FetchItemResult) {
+        self.items.lock().unwrap().push(item.clone());
+    }
+}
+
+impl RecordingSink {
+    pub fn events(&self) -> Vec<ProgressEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// The completed items as `type:id`.
+    pub fn items(&self) -> Vec<String> {
+        self.items
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|item| format!("{}:{}", item.dataset_type, item.id))
+            .collect()
+    }
+
+    pub fn messages(&self) -> Vec<String> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|event| event.message.clone())
+            .collect()
+    }
+
+    /// The `item.failed` messages.
+    pub fn failures(&self) -> Vec<String> {
+        self.messages()
+            .into_iter()
+            .filter(|message| message.starts_with("item.failed "))
+            .collect()
+    }
+
+    /// Whether a message starting with `prefix` came.
+    pub fn saw(&self, prefix: &str) -> bool {
+        self.messages()
+            .iter()
+            .any(|message| message.starts_with(prefix))
+    }
+}
+
+pub struct UnusedNcbi;
+pub struct UnusedRcsb;
+pub struct UnusedSrr;
+pub struct UnusedUniprot;
+pub struct UnusedGeo;
+pub struct UnusedKnowledge;
+
+impl NcbiClient for UnusedNcbi {
+    fn download_protein(
+        &self,
+        _id: &ProteinId,
+        _format: ProteinFormat,
+        _destination: &Path,
+    ) -> Result<DownloadInfo, KiraError> {
+        Err(KiraError::NcbiHttp("not used".to_string()))
+    }
+
+    fn download_genome(
+        &self,
+        _accession: &GenomeAccession,
+        _include: &[String],
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<DownloadInfo, KiraError> {
+        Err(KiraError::NcbiHttp("not used".to_string()))
+    }
+}
+
+impl RcsbClient for UnusedRcsb {
+    fn download_structure(
+        &self,
+        _id: &ProteinId,
+        _format: ProteinFormat,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<(), KiraError> {
+        Err(KiraError::RcsbHttp("not used".to_string()))
+    }
+
+    fn fetch_metadata(&self, _id: &ProteinId) -> Result<RcsbMetadata, KiraError> {
+        Err(KiraError::RcsbHttp("not used".to_string()))
+    }
+}
+
+impl SrrClient for UnusedSrr {
+    fn download_fastq(
+        &self,
+        _id: &SrrId,
+        _paired: bool,
+        _destination_dir: &Path,
+        _options: &SrrDownloadOptions,
+    ) -> Result<Vec<PathBuf>, KiraError> {
+        Err(KiraError::SrrConversion("not used".to_string()))
+    }
+
+    fn tool_info(&self) -> ToolInfo {
+        ToolInfo::from_output(None, None)
+    }
+}
+
+impl UniprotClient for UnusedUniprot {
+    fn fetch(&self, _id: &UniprotId) -> Result<UniprotRecord, KiraError> {
+        Err(KiraError::UniprotHttp("not used".to_string()))
+    }
+}
+
+impl GeoClient for UnusedGeo {
+    fn fetch_soft_text(&self, _accession: &GeoSeriesAccession) -> Result<String, KiraError> {
+        Err(KiraError::GeoHttp("not used".to_string()))
+    }
+
+    fn fetch_sample_soft_text(&self, _accession: &GeoSampleAccession) -> Result<String, KiraError> {
+        Err(KiraError::GeoHttp("not used".to_string()))
+    }
+
+    fn download_url(
+        &self,
+        _url: &str,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::GeoHttp("not used".to_string()))
+    }
+}
+
+impl KnowledgeClient for UnusedKnowledge {
+    fn download_go_ontology(
+        &self,
+        _flavor: GoFlavor,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<Vec<u8>, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn download_kegg_pathways(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn download_kegg_pathway_links(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn download_reactome_pathways(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn download_reactome_mappings(
+        &self,
+        _destination: &Path,
+        _sink: &dyn ProgressSink,
+    ) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn fetch_kegg_info(&self, _database: &str) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+
+    fn fetch_kegg_links(&self, _target: &str, _ids: &[String]) -> Result<String, KiraError> {
+        Err(KiraError::KnowledgeHttp("not used".to_string()))
+    }
+}
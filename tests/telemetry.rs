@@ -0,0 +1,227 @@
+mod support;
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{Layer, Registry};
+
+use kira_biodata_manager::geo::{GeoClient, GeoHttpClient};
+use kira_biodata_manager::http::HttpSettings;
+
+use support::NoProgress;
+
+type Fields = BTreeMap<String, String>;
+
+#[derive(Debug, Clone)]
+struct ClosedSpan {
+    name: String,
+    fields: Fields,
+}
+
+/// Keeps every closed span with its final field values.
+#[derive(Clone, Default)]
+struct Capture {
+    closed: Arc<Mutex<Vec<ClosedSpan>>>,
+}
+
+struct Recorder<'a>(&'a mut Fields);
+
+impl Visit for Recorder<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+impl<S> Layer<S> for Capture
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = Fields::new();
+        attrs.record(&mut Recorder(&mut fields));
+        ctx.span(id).unwrap().extensions_mut().insert(fields);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let span = ctx.span(id).unwrap();
+        let mut extensions = span.extensions_mut();
+        values.record(&mut Recorder(extensions.get_mut::<Fields>().unwrap()));
+    }
+
+    fn on_event(&self, _event: &Event<'_>, _ctx: Context<'_, S>) {}
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let span = ctx.span(&id).unwrap();
+        let fields = span.extensions_mut().remove::<Fields>().unwrap();
+        self.closed.lock().unwrap().push(ClosedSpan {
+            name: span.name().to_string(),
+            fields,
+        });
+    }
+}
+
+/// Answers one request with `status` and `body`.
+fn serve_once(status: &'static str, body: &'static [u8]) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0u8; 1024];
+        let _ = stream.read(&mut request);
+        let _ = stream.write_all(
+            format!(
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .as_bytes(),
+        );
+        let _ = stream.write_all(body);
+    });
+    format!("http://{address}/suppl/GSE1_counts.txt?token=secret")
+}
+
+fn download_traced(url: &str) -> (Vec<ClosedSpan>, bool) {
+    let capture = Capture::default();
+    let subscriber = Registry::default().with(capture.clone());
+    let temp = tempfile::tempdir().unwrap();
+    let destination = temp.path().join("GSE1_counts.txt");
+    let client = GeoHttpClient::with_settings(HttpSettings::new(None)).unwrap();
+    let ok = tracing::subscriber::with_default(subscriber, || {
+        client.download_url(url, &destination, &NoProgress).is_ok()
+    });
+    let closed = capture.closed.lock().unwrap().clone();
+    (closed, ok)
+}
+
+#[test]
+fn registry_calls_run_in_spans_carrying_endpoint_status_and_bytes() {
+    let url = serve_once("200 OK", b"g1\t5\n");
+
+    let (closed, ok) = download_traced(&url);
+
+    assert!(ok);
+    let span = closed
+        .iter()
+        .find(|span| span.name == "geo.download_url")
+        .expect("no span for the download");
+    assert_eq!(span.fields["registry"], "geo");
+    assert_eq!(span.fields["dataset"], "GSE1_counts.txt");
+    assert_eq!(span.fields["status"], "200");
+    assert_eq!(span.fields["bytes"], "5");
+    // The query, which may hold credentials, stays out of the span.
+    let endpoint = &span.fields["endpoint"];
+    assert!(endpoint.ends_with("/suppl/GSE1_counts.txt"), "{endpoint}");
+    assert!(!endpoint.contains("secret"));
+}
+
+#[test]
+fn a_failed_registry_call_records_its_status() {
+    let url = serve_once("404 Not Found", b"missing");
+
+    let (closed, ok) = download_traced(&url);
+
+    assert!(!ok);
+    let span = closed
+        .iter()
+        .find(|span| span.name == "geo.download_url")
+        .expect("no span for the download");
+    assert_eq!(span.fields["status"], "404");
+}
+
+/// Accepts one request, answers 200 and hands back its body.
+#[cfg(feature = "telemetry")]
+fn collector() -> (String, std::sync::mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 4096];
+        loop {
+            let read = stream.read(&mut buffer).unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().ok())?
+                    })
+                    .unwrap_or(0);
+                if body.len() >= length || read == 0 {
+                    let _ = stream.write_all(
+                        b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    );
+                    sender
+                        .send(format!("{}\n{body}", head.lines().next().unwrap()))
+                        .unwrap();
+                    return;
+                }
+            }
+        }
+    });
+    (format!("http://{address}"), receiver)
+}
+
+#[cfg(feature = "telemetry")]
+#[test]
+fn spans_are_exported_to_the_otlp_collector_when_the_guard_drops() {
+    use kira_biodata_manager::telemetry;
+
+    let (endpoint, received) = collector();
+    let (layer, guard) = telemetry::otlp(&endpoint.parse().unwrap());
+    let subscriber = Registry::default().with(layer);
+    let url = serve_once("200 OK", b"g1\t5\n");
+    let temp = tempfile::tempdir().unwrap();
+    let client = GeoHttpClient::with_settings(HttpSettings::new(None)).unwrap();
+    tracing::subscriber::with_default(subscriber, || {
+        let _item = tracing::info_span!("fetch.item", dataset = "expression:GSE1").entered();
+        client
+            .download_url(&url, &temp.path().join("GSE1_counts.txt"), &NoProgress)
+            .unwrap();
+    });
+    drop(guard);
+
+    let request = received.recv().unwrap();
+    let (request_line, body) = request.split_once('\n').unwrap();
+    assert!(
+        request_line.starts_with("POST /v1/traces "),
+        "{request_line}"
+    );
+    let body: serde_json::Value = serde_json::from_str(body).unwrap();
+    let spans = body["resourceSpans"][0]["scopeSpans"][0]["spans"]
+        .as_array()
+        .unwrap();
+    let item = spans
+        .iter()
+        .find(|span| span["name"] == "fetch.item")
+        .unwrap();
+    let download = spans
+        .iter()
+        .find(|span| span["name"] == "geo.download_url")
+        .unwrap();
+    assert_eq!(download["traceId"], item["traceId"]);
+    assert_eq!(download["parentSpanId"], item["spanId"]);
+    assert_eq!(download["kind"], 3);
+    assert!(
+        download["attributes"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!({"key": "status", "value": {"intValue": "200"}}))
+    );
+}
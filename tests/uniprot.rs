@@ -1,6 +1,6 @@
 use std::fs;
 
-use kira_biodata_manager::uniprot::extract_metadata;
+use kira_biodata_manager::uniprot::{ChainRange, extract_metadata, rank_pdb_xrefs};
 
 #[test]
 fn extract_uniprot_metadata() {
@@ -16,7 +16,9 @@ fn extract_uniprot_metadata() {
     assert!(meta.gene_names.contains(&"HBA1".to_string()));
     assert_eq!(meta.organism.as_deref(), Some("Homo sapiens"));
     assert_eq!(meta.sequence_length, Some(141));
-    assert!(meta.cross_references.pdb.contains(&"1A3N".to_string()));
+    assert!(meta.cross_references.pdb_ids.contains(&"1A3N".to_string()));
+    assert_eq!(meta.cross_references.pdb[0].id, "1A3N");
+    assert!(meta.cross_references.pdb[0].method.is_none());
     assert!(meta.functions.iter().any(|f| f.contains("Oxygen")));
     assert!(!meta.features.domains.is_empty());
     assert!(
@@ -128,3 +130,42 @@ fn extract_uniprot_metadata() {
             .any(|f| f.name == "Peptide")
     );
 }
+
+#[test]
+fn pdb_xrefs_parse_properties_and_rank() {
+    let raw = fs::read_to_string("tests/fixtures/uniprot_P00698_structures.json").unwrap();
+    let value: serde_json::Value = serde_json::from_str(&raw).unwrap();
+    let meta = extract_metadata(&value).unwrap();
+    let refs = &meta.cross_references;
+
+    assert_eq!(refs.pdb_ids.len(), 6);
+    let xray = refs.pdb.iter().find(|xref| xref.id == "2LYZ").unwrap();
+    assert_eq!(xray.method.as_deref(), Some("X-ray"));
+    assert_eq!(xray.resolution, Some(2.0));
+    assert_eq!(
+        xray.chains,
+        vec![
+            ChainRange {
+                chain: "A".to_string(),
+                start: Some(19),
+                end: Some(147),
+            },
+            ChainRange {
+                chain: "B".to_string(),
+                start: Some(19),
+                end: Some(147),
+            },
+        ]
+    );
+    assert_eq!(xray.coverage(meta.sequence_length), 129);
+    let nmr = refs.pdb.iter().find(|xref| xref.id == "1E8L").unwrap();
+    assert_eq!(nmr.resolution, None);
+    let bare = refs.pdb.iter().find(|xref| xref.id == "5ABC").unwrap();
+    assert!(bare.method.is_none() && bare.chains.is_empty());
+
+    let ranked = rank_pdb_xrefs(&refs.pdb, meta.sequence_length)
+        .into_iter()
+        .map(|xref| xref.id)
+        .collect::<Vec<_>>();
+    assert_eq!(ranked, vec!["4LZT", "2LYZ", "1LYZ", "1E8L", "5ABC", "9ZZZ"]);
+}